@@ -0,0 +1,58 @@
+use std::hint::black_box;
+
+use divan::Bencher;
+use valence::layer::chunk::{Chunk, UnloadedChunk};
+use valence::network::NetworkPlugin;
+use valence::prelude::*;
+use valence::protocol::encode::PacketEncoder;
+
+/// Serializes a chunk with varied terrain into the packets sent to clients
+/// that start viewing it.
+#[divan::bench]
+fn write_chunk_init_packets(bencher: Bencher) {
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins.build().disable::<NetworkPlugin>());
+
+    app.update(); // Initialize plugins.
+
+    let mut layer = LayerBundle::new(
+        ident!("overworld"),
+        app.world().resource::<DimensionTypeRegistry>(),
+        app.world().resource::<BiomeRegistry>(),
+        app.world().resource::<Server>(),
+    )
+    .chunk;
+
+    let mut chunk = UnloadedChunk::with_height(layer.height());
+
+    let blocks = [
+        BlockState::STONE,
+        BlockState::DEEPSLATE,
+        BlockState::DIRT,
+        BlockState::COAL_ORE,
+        BlockState::IRON_ORE,
+        BlockState::GRAVEL,
+    ];
+
+    for y in 0..chunk.height() / 2 {
+        for z in 0..16 {
+            for x in 0..16 {
+                let i = (x * 7 + y * 13 + z * 31) as usize;
+                chunk.set_block_state(x, y, z, blocks[i % blocks.len()]);
+            }
+        }
+    }
+
+    layer.insert_chunk([0, 0], chunk);
+
+    let mut encoder = PacketEncoder::new();
+
+    bencher.bench_local(|| {
+        encoder.clear();
+
+        layer.write_uncached_chunk_init_packets([0, 0], &mut encoder);
+
+        black_box(&encoder);
+    });
+}
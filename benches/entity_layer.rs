@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use divan::Bencher;
+use rand::Rng;
+use valence::entity::zombie::ZombieEntityBundle;
+use valence::entity::Position;
+use valence::keepalive::KeepaliveSettings;
+use valence::layer::chunk::UnloadedChunk;
+use valence::network::NetworkPlugin;
+use valence::prelude::*;
+use valence::testing::create_mock_client;
+
+/// Moves many entities around an entity layer every tick, so the layer's
+/// messages are sent to every client in view of them.
+#[divan::bench(args = [100, 500])]
+fn entity_layer_fan_out(bencher: Bencher, client_count: usize) {
+    const ENTITY_COUNT: usize = 1000;
+    const WORLD_SIZE: i32 = 8;
+
+    let mut app = App::new();
+
+    app.insert_resource(KeepaliveSettings {
+        period: Duration::MAX,
+    });
+
+    app.add_plugins(DefaultPlugins.build().disable::<NetworkPlugin>());
+
+    app.update(); // Initialize plugins.
+
+    let mut layer = LayerBundle::new(
+        ident!("overworld"),
+        app.world().resource::<DimensionTypeRegistry>(),
+        app.world().resource::<BiomeRegistry>(),
+        app.world().resource::<Server>(),
+    );
+
+    for z in -WORLD_SIZE..WORLD_SIZE {
+        for x in -WORLD_SIZE..WORLD_SIZE {
+            layer.chunk.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    let layer = app.world_mut().spawn(layer).id();
+
+    let mut rng = rand::thread_rng();
+    let extent = f64::from(WORLD_SIZE) * 16.0;
+
+    let mut random_pos = move || {
+        DVec3::new(
+            rng.gen_range(-extent..extent),
+            64.0,
+            rng.gen_range(-extent..extent),
+        )
+    };
+
+    let entities: Vec<_> = (0..ENTITY_COUNT)
+        .map(|_| {
+            app.world_mut()
+                .spawn(ZombieEntityBundle {
+                    layer: EntityLayerId(layer),
+                    position: Position::new(random_pos()),
+                    ..Default::default()
+                })
+                .id()
+        })
+        .collect();
+
+    let mut helpers = vec![];
+
+    for i in 0..client_count {
+        let (mut bundle, helper) = create_mock_client(format!("client_{i}"));
+
+        bundle.visible_chunk_layer.0 = layer;
+        bundle.visible_entity_layers.0.insert(layer);
+        bundle.player.layer.0 = layer;
+        bundle.player.position.set(random_pos());
+        bundle.view_distance.set(4);
+
+        app.world_mut().spawn(bundle);
+
+        helpers.push(helper);
+    }
+
+    app.update();
+
+    for helper in &mut helpers {
+        helper.confirm_initial_pending_teleports();
+    }
+
+    app.update();
+
+    bencher.bench_local(|| {
+        let mut rng = rand::thread_rng();
+
+        for &entity in &entities {
+            let mut pos = app.world_mut().get_mut::<Position>(entity).unwrap();
+
+            let offset = DVec3::new(rng.gen_range(-0.5..=0.5), 0.0, rng.gen_range(-0.5..=0.5));
+            let new_pos = pos.get() + offset;
+
+            pos.set(new_pos);
+        }
+
+        app.update(); // The important part.
+
+        for helper in &mut helpers {
+            helper.clear_received();
+        }
+    });
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use divan::Bencher;
+use rand::Rng;
+use valence::keepalive::KeepaliveSettings;
+use valence::layer::chunk::UnloadedChunk;
+use valence::network::NetworkPlugin;
+use valence::prelude::*;
+use valence::testing::create_mock_client;
+
+/// Changes the slots of a chest viewed by every client, so the changes are
+/// sent to all of them.
+#[divan::bench(args = [100, 500])]
+fn broadcast_inventory_changes(bencher: Bencher, client_count: usize) {
+    let mut app = App::new();
+
+    app.insert_resource(KeepaliveSettings {
+        period: Duration::MAX,
+    });
+
+    app.add_plugins(DefaultPlugins.build().disable::<NetworkPlugin>());
+
+    app.update(); // Initialize plugins.
+
+    let mut layer = LayerBundle::new(
+        ident!("overworld"),
+        app.world().resource::<DimensionTypeRegistry>(),
+        app.world().resource::<BiomeRegistry>(),
+        app.world().resource::<Server>(),
+    );
+
+    layer.chunk.insert_chunk([0, 0], UnloadedChunk::new());
+
+    let layer = app.world_mut().spawn(layer).id();
+
+    let chest = app
+        .world_mut()
+        .spawn(Inventory::new(InventoryKind::Generic9x3))
+        .id();
+
+    let mut helpers = vec![];
+
+    for i in 0..client_count {
+        let (mut bundle, helper) = create_mock_client(format!("client_{i}"));
+
+        bundle.visible_chunk_layer.0 = layer;
+        bundle.visible_entity_layers.0.insert(layer);
+        bundle.player.layer.0 = layer;
+
+        app.world_mut().spawn((bundle, OpenInventory::new(chest)));
+
+        helpers.push(helper);
+    }
+
+    app.update();
+
+    for helper in &mut helpers {
+        helper.confirm_initial_pending_teleports();
+    }
+
+    app.update();
+
+    bencher.bench_local(|| {
+        let mut rng = rand::thread_rng();
+
+        let mut inv = app.world_mut().get_mut::<Inventory>(chest).unwrap();
+
+        for slot in (0..27).step_by(3) {
+            inv.set_slot(
+                slot,
+                ItemStack::new(ItemKind::Diamond, rng.gen_range(1..=64), None),
+            );
+        }
+
+        app.update(); // The important part.
+
+        for helper in &mut helpers {
+            helper.clear_received();
+        }
+    });
+}
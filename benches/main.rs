@@ -1,7 +1,10 @@
 mod anvil;
 mod block;
+mod chunk;
 mod decode_array;
+mod entity_layer;
 mod idle;
+mod inventory;
 mod many_players;
 mod packet;
 mod var_int;
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::hint::black_box;
 
 use divan::Bencher;
+use rayon::prelude::*;
 use valence::nbt::{compound, List};
 use valence::prelude::*;
 use valence::protocol::decode::PacketDecoder;
@@ -297,3 +298,68 @@ fn decode_spawn_data_compressed(bencher: Bencher) {
         black_box(decoder);
     });
 }
+
+/// Encodes a tick's worth of packets for every client and encrypts them when
+/// they're taken, like flushing clients does on the main thread.
+#[divan::bench(args = [500, 1000])]
+fn take_encrypted(bencher: Bencher, client_count: usize) {
+    let (_, _, _, spawn_entity_packet) = setup();
+
+    let mut encoders: Vec<_> = (0..client_count)
+        .map(|_| {
+            let mut encoder = PacketEncoder::new();
+            encoder.enable_encryption(&[0x42; 16]);
+            encoder
+        })
+        .collect();
+
+    bencher.bench_local(|| {
+        for encoder in &mut encoders {
+            for _ in 0..20 {
+                encoder.append_packet(&spawn_entity_packet).unwrap();
+            }
+
+            black_box(encoder.take());
+        }
+    });
+}
+
+/// Like [`take_encrypted`], but with the encryption moved off the encoders as
+/// the network does with `NetworkSettings::offload_encryption`. The taken
+/// bytes are encrypted on a thread pool standing in for the tokio workers, so
+/// this measures the total time until every client's data is ready to be sent.
+#[divan::bench(args = [500, 1000])]
+fn take_offloaded(bencher: Bencher, client_count: usize) {
+    let (_, _, _, spawn_entity_packet) = setup();
+
+    let mut clients: Vec<_> = (0..client_count)
+        .map(|_| {
+            let mut encoder = PacketEncoder::new();
+            encoder.enable_encryption(&[0x42; 16]);
+
+            let encryptor = encoder.take_encryptor().unwrap();
+
+            (encoder, encryptor)
+        })
+        .collect();
+
+    bencher.bench_local(|| {
+        let mut taken: Vec<_> = clients
+            .iter_mut()
+            .map(|(encoder, _)| {
+                for _ in 0..20 {
+                    encoder.append_packet(&spawn_entity_packet).unwrap();
+                }
+
+                encoder.take()
+            })
+            .collect();
+
+        taken
+            .par_iter_mut()
+            .zip(clients.par_iter_mut())
+            .for_each(|(bytes, (_, encryptor))| encryptor.encrypt(bytes));
+
+        black_box(taken);
+    });
+}
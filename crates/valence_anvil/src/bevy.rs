@@ -8,7 +8,7 @@ use bevy_ecs::prelude::*;
 use flume::{Receiver, Sender};
 use valence_server::client::{Client, OldView, View};
 use valence_server::entity::{EntityLayerId, OldEntityLayerId};
-use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::layer::{ChunkUnloadPolicy, UpdateLayersPreClientSet};
 use valence_server::protocol::anyhow;
 use valence_server::registry::BiomeRegistry;
 use valence_server::{ChunkLayer, ChunkPos};
@@ -33,6 +33,9 @@ pub struct AnvilLevel {
     /// Chunks that need to be loaded. Chunks with `None` priority have already
     /// been sent to the anvil thread.
     pending: HashMap<ChunkPos, Option<Priority>>,
+    /// Chunks covered by a ticket in the chunk layer, which have been queued
+    /// for loading.
+    ticketed: HashSet<ChunkPos>,
     /// Sender for the chunk worker thread.
     sender: Sender<ChunkPos>,
     /// Receiver for the chunk worker thread.
@@ -52,6 +55,7 @@ impl AnvilLevel {
             }),
             ignored_chunks: HashSet::new(),
             pending: HashMap::new(),
+            ticketed: HashSet::new(),
             sender: pending_sender,
             receiver: finished_receiver,
         }
@@ -60,7 +64,8 @@ impl AnvilLevel {
     /// Forces a chunk to be loaded at a specific position in this world. This
     /// will bypass [`AnvilLevel::ignored_chunks`].
     /// Note that the chunk will be unloaded next tick unless it has been added
-    /// to [`AnvilLevel::ignored_chunks`] or it is in view of a client.
+    /// to [`AnvilLevel::ignored_chunks`], it is in view of a client or it is
+    /// covered by a [ticket](valence_server::layer::ticket).
     ///
     /// This has no effect if a chunk at the position is already present.
     pub fn force_chunk_load(&mut self, pos: ChunkPos) {
@@ -99,7 +104,12 @@ impl Plugin for AnvilPlugin {
             .add_systems(PreUpdate, remove_unviewed_chunks)
             .add_systems(
                 PostUpdate,
-                (init_anvil, update_client_views, send_recv_chunks)
+                (
+                    init_anvil,
+                    update_client_views,
+                    update_ticketed_chunks,
+                    send_recv_chunks,
+                )
                     .chain()
                     .before(UpdateLayersPreClientSet),
             );
@@ -114,17 +124,21 @@ fn init_anvil(mut query: Query<&mut AnvilLevel, (Added<AnvilLevel>, With<ChunkLa
     }
 }
 
-/// Removes all chunks no longer viewed by clients.
+/// Removes all chunks no longer viewed by clients or covered by a ticket.
+/// Layers with a [`ChunkUnloadPolicy`] are left to the policy instead.
 ///
 /// This needs to run in `PreUpdate` where the chunk viewer counts have been
 /// updated from the previous tick.
 fn remove_unviewed_chunks(
-    mut chunk_layers: Query<(Entity, &mut ChunkLayer, &AnvilLevel)>,
+    mut chunk_layers: Query<(Entity, &mut ChunkLayer, &AnvilLevel), Without<ChunkUnloadPolicy>>,
     mut unload_events: EventWriter<ChunkUnloadEvent>,
 ) {
     for (entity, mut layer, anvil) in &mut chunk_layers {
         layer.retain_chunks(|pos, chunk| {
-            if chunk.viewer_count_mut() > 0 || anvil.ignored_chunks.contains(&pos) {
+            if chunk.viewer_count_mut() > 0
+                || anvil.ignored_chunks.contains(&pos)
+                || anvil.ticketed.contains(&pos)
+            {
                 true
             } else {
                 unload_events.send(ChunkUnloadEvent {
@@ -178,6 +192,24 @@ fn update_client_views(
     }
 }
 
+/// Loads the chunks covered by tickets in the chunk layer.
+fn update_ticketed_chunks(mut chunk_layers: Query<(&ChunkLayer, &mut AnvilLevel)>) {
+    for (layer, mut anvil) in &mut chunk_layers {
+        // Forget the chunks of removed tickets, so they're loaded again if a new
+        // ticket covers them.
+        anvil.ticketed.retain(|pos| layer.has_ticket(*pos));
+
+        for pos in layer.ticketed_chunks() {
+            if anvil.ticketed.insert(pos)
+                && !anvil.ignored_chunks.contains(&pos)
+                && layer.chunk(pos).is_none()
+            {
+                anvil.force_chunk_load(pos);
+            }
+        }
+    }
+}
+
 fn send_recv_chunks(
     mut layers: Query<(Entity, &mut ChunkLayer, &mut AnvilLevel)>,
     mut to_send: Local<Vec<(Priority, ChunkPos)>>,
@@ -194,6 +226,12 @@ fn send_recv_chunks(
             let status = match res {
                 Ok(Some(ParsedChunk { chunk, timestamp })) => {
                     layer.insert_chunk(pos, chunk);
+
+                    // The chunk matches what's on disk, so it doesn't need saving yet.
+                    if let Some(chunk) = layer.chunk_mut(pos) {
+                        chunk.set_modified(false);
+                    }
+
                     ChunkLoadStatus::Success { timestamp }
                 }
                 Ok(None) => ChunkLoadStatus::Empty,
@@ -261,6 +299,9 @@ pub enum ChunkLoadStatus {
 }
 
 /// An event sent by `valence_anvil` when a chunk is unloaded from an layer.
+/// Layers with a [`ChunkUnloadPolicy`] send
+/// [`UnviewedChunkUnloadEvent`](valence_server::layer::unload::UnviewedChunkUnloadEvent)
+/// instead.
 #[derive(Event, Debug)]
 pub struct ChunkUnloadEvent {
     /// The [`ChunkLayer`] where the chunk was unloaded.
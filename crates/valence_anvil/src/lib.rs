@@ -14,6 +14,7 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use flate2::bufread::{GzDecoder, ZlibDecoder};
 use flate2::write::{GzEncoder, ZlibEncoder};
 use lru::LruCache;
+pub use repair::RegionIssue;
 use thiserror::Error;
 use valence_nbt::binary::{FromModifiedUtf8, ToModifiedUtf8};
 use valence_nbt::Compound;
@@ -22,6 +23,7 @@ use valence_nbt::Compound;
 mod bevy;
 #[cfg(feature = "parsing")]
 pub mod parsing;
+mod repair;
 
 const LRU_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(256) {
     Some(n) => n,
@@ -247,38 +249,6 @@ impl RegionFolder {
     pub fn all_chunk_positions(
         &mut self,
     ) -> Result<impl Iterator<Item = Result<(i32, i32), RegionError>> + '_, RegionError> {
-        fn extract_region_coordinates(
-            file: std::io::Result<DirEntry>,
-        ) -> Result<Option<(i32, i32)>, RegionError> {
-            let file = file?;
-
-            if !file.file_type()?.is_file() {
-                return Ok(None);
-            }
-
-            let file_name = file
-                .file_name()
-                .into_string()
-                .map_err(|_| RegionError::OsStringConv)?;
-
-            // read the file name as r.x.z.mca
-            let mut split = file_name.splitn(4, '.');
-            if split.next() != Some("r") {
-                return Ok(None);
-            }
-            let Some(Ok(x)) = split.next().map(str::parse) else {
-                return Ok(None);
-            };
-            let Some(Ok(z)) = split.next().map(str::parse) else {
-                return Ok(None);
-            };
-            if split.next() != Some("mca") {
-                return Ok(None);
-            }
-
-            Ok(Some((x, z)))
-        }
-
         fn region_chunks(
             this: &mut RegionFolder,
             pos: Result<(i32, i32), RegionError>,
@@ -302,11 +272,86 @@ impl RegionFolder {
         }
 
         Ok(std::fs::read_dir(&self.region_root)?
-            .filter_map(|file| extract_region_coordinates(file).transpose())
+            .filter_map(|file| region_file_coordinates(file, "r", "mca").transpose())
             .flat_map(|pos| region_chunks(self, pos)))
     }
 }
 
+/// Reads the coordinates from the name of a file in a region folder, such as
+/// `r.x.z.mca` for region files. Returns `Ok(None)` for other files.
+fn region_file_coordinates(
+    file: std::io::Result<DirEntry>,
+    prefix: &str,
+    extension: &str,
+) -> Result<Option<(i32, i32)>, RegionError> {
+    let file = file?;
+
+    if !file.file_type()?.is_file() {
+        return Ok(None);
+    }
+
+    let file_name = file
+        .file_name()
+        .into_string()
+        .map_err(|_| RegionError::OsStringConv)?;
+
+    // read the file name as prefix.x.z.extension
+    let mut split = file_name.splitn(4, '.');
+    if split.next() != Some(prefix) {
+        return Ok(None);
+    }
+    let Some(Ok(x)) = split.next().map(str::parse) else {
+        return Ok(None);
+    };
+    let Some(Ok(z)) = split.next().map(str::parse) else {
+        return Ok(None);
+    };
+    if split.next() != Some(extension) {
+        return Ok(None);
+    }
+
+    Ok(Some((x, z)))
+}
+
+/// Decompresses chunk data stored with the given compression scheme and
+/// parses the NBT in it.
+fn decompress_chunk<S>(
+    compression: u8,
+    data: &[u8],
+    decompress_buf: &mut Vec<u8>,
+) -> Result<Compound<S>, RegionError>
+where
+    S: for<'a> FromModifiedUtf8<'a> + Hash + Ord,
+{
+    decompress_buf.clear();
+
+    // What compression does the chunk use?
+    let mut nbt_slice = match Compression::from_u8(compression) {
+        Some(Compression::Gzip) => {
+            let mut z = GzDecoder::new(data);
+            z.read_to_end(decompress_buf)?;
+            decompress_buf.as_slice()
+        }
+        Some(Compression::Zlib) => {
+            let mut z = ZlibDecoder::new(data);
+            z.read_to_end(decompress_buf)?;
+            decompress_buf.as_slice()
+        }
+        // Uncompressed
+        Some(Compression::None) => data,
+        // Unknown
+        None => return Err(RegionError::InvalidCompressionScheme(compression)),
+    };
+
+    let (data, _) = valence_nbt::from_binary(&mut nbt_slice)?;
+
+    if !nbt_slice.is_empty() {
+        return Err(RegionError::TrailingNbtData);
+    }
+
+    Ok(data)
+}
+
 /// A chunk represented by the raw compound data.
 pub struct RawChunk<S = String> {
     pub data: Compound<S>,
@@ -469,33 +514,7 @@ impl Region {
             data_buf
         };
 
-        let r = data_buf.as_ref();
-
-        decompress_buf.clear();
-
-        // What compression does the chunk use?
-        let mut nbt_slice = match Compression::from_u8(compression) {
-            Some(Compression::Gzip) => {
-                let mut z = GzDecoder::new(r);
-                z.read_to_end(decompress_buf)?;
-                decompress_buf.as_slice()
-            }
-            Some(Compression::Zlib) => {
-                let mut z = ZlibDecoder::new(r);
-                z.read_to_end(decompress_buf)?;
-                decompress_buf.as_slice()
-            }
-            // Uncompressed
-            Some(Compression::None) => r,
-            // Unknown
-            None => return Err(RegionError::InvalidCompressionScheme(compression)),
-        };
-
-        let (data, _) = valence_nbt::from_binary(&mut nbt_slice)?;
-
-        if !nbt_slice.is_empty() {
-            return Err(RegionError::TrailingNbtData);
-        }
+        let data = decompress_chunk(compression, &data_buf, decompress_buf)?;
 
         Ok(Some(RawChunk { data, timestamp }))
     }
@@ -515,14 +534,25 @@ impl Region {
             return Ok(false);
         }
 
+        let (sector_offset, sector_count) = location.offset_and_count();
+
         if delete_on_disk {
             self.file.seek(SeekFrom::Start(chunk_idx as u64 * 4))?;
             self.file.write_u32::<BigEndian>(0)?;
 
+            // Clear the size of the chunk's data too, so repairing the region doesn't
+            // bring the chunk back.
+            if sector_offset >= 2
+                && sector_offset < self.file.metadata()?.len() / SECTOR_SIZE as u64
+            {
+                self.file
+                    .seek(SeekFrom::Start(sector_offset * SECTOR_SIZE as u64))?;
+                self.file.write_u32::<BigEndian>(0)?;
+            }
+
             Self::delete_external_chunk_file(pos_x, pos_z, region_root)?;
         }
 
-        let (sector_offset, sector_count) = location.offset_and_count();
         if sector_offset >= 2 {
             let start_index = sector_offset as usize;
             let end_index = start_index + sector_count;
@@ -1,23 +1,27 @@
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use thiserror::Error;
 use valence_server::block::{PropName, PropValue};
-use valence_server::layer::chunk::{Chunk, UnloadedChunk};
+use valence_server::layer::chunk::{Chunk, ChunkLayerSnapshot, UnloadedChunk};
 use valence_server::nbt::{Compound, List, Value};
-use valence_server::protocol::BlockKind;
+use valence_server::protocol::{BlockKind, BlockState};
 use valence_server::registry::biome::BiomeId;
 use valence_server::registry::BiomeRegistry;
 use valence_server::{ChunkPos, Ident};
 
 use crate::{RegionError, RegionFolder};
 
+mod upgrade;
+
 #[derive(Debug)]
 pub struct DimensionFolder {
     region: RegionFolder,
     /// Mapping of biome names to their biome ID.
     biome_to_id: BTreeMap<Ident<String>, BiomeId>,
+    /// Mapping of biome IDs to their biome name.
+    id_to_biome: BTreeMap<BiomeId, Ident<String>>,
 }
 
 impl DimensionFolder {
@@ -25,12 +29,16 @@ impl DimensionFolder {
         let mut region_root = dimension_root.into();
         region_root.push("region");
 
+        let biome_to_id = biome_map(biomes);
+        let id_to_biome = biome_to_id
+            .iter()
+            .map(|(name, id)| (*id, name.clone()))
+            .collect();
+
         Self {
             region: RegionFolder::new(region_root),
-            biome_to_id: biomes
-                .iter()
-                .map(|(id, name, _)| (name.to_string_ident(), id))
-                .collect(),
+            biome_to_id,
+            id_to_biome,
         }
     }
 
@@ -50,6 +58,74 @@ impl DimensionFolder {
             timestamp: raw_chunk.timestamp,
         }))
     }
+
+    /// Writes `chunk` to the region file at the given chunk position,
+    /// overwriting the chunk already there. `min_y` is the minimum Y
+    /// coordinate of the chunk's dimension.
+    pub fn set_chunk(
+        &mut self,
+        pos: ChunkPos,
+        chunk: &impl Chunk,
+        min_y: i32,
+    ) -> Result<(), RegionError> {
+        let nbt = write_chunk(pos, chunk, min_y, &self.id_to_biome);
+        self.region.set_chunk(pos.x, pos.z, &nbt)
+    }
+
+    /// Writes every chunk in `snapshot` to the region files. Combined with
+    /// [`ChunkLayer::take_modified_chunks`], this saves only the chunks
+    /// changed since the last save.
+    ///
+    /// [`ChunkLayer::take_modified_chunks`]: valence_server::ChunkLayer::take_modified_chunks
+    pub fn set_chunks(
+        &mut self,
+        snapshot: &ChunkLayerSnapshot,
+        min_y: i32,
+    ) -> Result<(), RegionError> {
+        for (pos, chunk) in snapshot.chunks() {
+            self.set_chunk(pos, chunk, min_y)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the NBT of a chunk in the anvil format, as stored in region files.
+/// Biomes missing from `biomes` are replaced with the default biome.
+///
+/// Chunks saved by versions from 1.13 up to 1.18 are upgraded to the current
+/// layout first. They start at Y 0 and are 256 blocks tall, like the worlds
+/// they come from. Older chunks fail with
+/// [`ParseChunkError::UnsupportedDataVersion`].
+pub fn parse_chunk_nbt(
+    nbt: Compound,
+    biomes: &BiomeRegistry,
+) -> Result<UnloadedChunk, ParseChunkError> {
+    parse_chunk(nbt, &biome_map(biomes))
+}
+
+/// Converts `chunk` to NBT in the anvil format, as stored in region files.
+/// `min_y` is the minimum Y coordinate of the chunk's dimension. Biomes
+/// missing from `biomes` are written as `minecraft:plains`.
+pub fn chunk_to_nbt(
+    pos: ChunkPos,
+    chunk: &impl Chunk,
+    min_y: i32,
+    biomes: &BiomeRegistry,
+) -> Compound {
+    let id_to_biome = biomes
+        .iter()
+        .map(|(id, name, _)| (id, name.to_string_ident()))
+        .collect();
+
+    write_chunk(pos, chunk, min_y, &id_to_biome)
+}
+
+fn biome_map(biomes: &BiomeRegistry) -> BTreeMap<Ident<String>, BiomeId> {
+    biomes
+        .iter()
+        .map(|(id, name, _)| (name.to_string_ident(), id))
+        .collect()
 }
 
 /// A chunk parsed to show block information, biome information etc.
@@ -113,12 +189,16 @@ pub enum ParseChunkError {
     InvalidBlockEntityName(String),
     #[error("invalid block entity position")]
     InvalidBlockEntityPosition,
+    #[error("chunks from data version {0} are too old to be upgraded")]
+    UnsupportedDataVersion(i32),
 }
 
 fn parse_chunk(
-    mut nbt: Compound,
+    nbt: Compound,
     biome_map: &BTreeMap<Ident<String>, BiomeId>, // TODO: replace with biome registry arg.
 ) -> Result<UnloadedChunk, ParseChunkError> {
+    let mut nbt = upgrade::upgrade_chunk(nbt)?;
+
     let Some(Value::List(List::Compound(sections))) = nbt.remove("sections") else {
         return Err(ParseChunkError::MissingSections);
     };
@@ -363,6 +443,165 @@ fn parse_chunk(
     Ok(chunk)
 }
 
+fn write_chunk(
+    pos: ChunkPos,
+    chunk: &impl Chunk,
+    min_y: i32,
+    id_to_biome: &BTreeMap<BiomeId, Ident<String>>,
+) -> Compound {
+    let min_sect_y = min_y.div_euclid(16);
+
+    let mut sections = vec![];
+    let mut block_entities = vec![];
+
+    let mut block_palette: Vec<BlockState> = vec![];
+    let mut block_idxs: HashMap<BlockState, u64> = HashMap::new();
+    let mut block_data = [0_u64; BLOCKS_PER_SECTION];
+    let mut biome_palette: Vec<BiomeId> = vec![];
+    let mut biome_data = [0_u64; BIOMES_PER_SECTION];
+
+    for sect_y in 0..chunk.height() / 16 {
+        block_palette.clear();
+        block_idxs.clear();
+
+        for (i, idx) in block_data.iter_mut().enumerate() {
+            let i = i as u32;
+            let x = i % 16;
+            let z = i / 16 % 16;
+            let y = sect_y * 16 + i / (16 * 16);
+
+            let state = chunk.block_state(x, y, z);
+
+            *idx = *block_idxs.entry(state).or_insert_with(|| {
+                block_palette.push(state);
+                block_palette.len() as u64 - 1
+            });
+
+            if let Some(kind) = state.block_entity_kind() {
+                let mut nbt = chunk.block_entity(x, y, z).cloned().unwrap_or_default();
+
+                nbt.insert("id", kind.ident().as_str());
+                nbt.insert("x", pos.x * 16 + x as i32);
+                nbt.insert("y", min_y + y as i32);
+                nbt.insert("z", pos.z * 16 + z as i32);
+
+                block_entities.push(nbt);
+            }
+        }
+
+        biome_palette.clear();
+
+        for (i, idx) in biome_data.iter_mut().enumerate() {
+            let i = i as u32;
+            let biome = chunk.biome(i % 4, sect_y * 4 + i / (4 * 4), i / 4 % 4);
+
+            *idx = match biome_palette.iter().position(|&b| b == biome) {
+                Some(idx) => idx as u64,
+                None => {
+                    biome_palette.push(biome);
+                    biome_palette.len() as u64 - 1
+                }
+            };
+        }
+
+        let mut block_states = Compound::new();
+
+        block_states.insert(
+            "palette",
+            List::Compound(block_palette.iter().map(|&b| block_nbt(b)).collect()),
+        );
+
+        if block_palette.len() > 1 {
+            let bits_per_idx = bit_width(block_palette.len() - 1).max(4);
+            block_states.insert("data", pack_indices(&block_data, bits_per_idx));
+        }
+
+        let mut biomes = Compound::new();
+
+        biomes.insert(
+            "palette",
+            List::String(
+                biome_palette
+                    .iter()
+                    .map(|id| {
+                        id_to_biome
+                            .get(id)
+                            .map_or("minecraft:plains", |name| name.as_str())
+                            .to_owned()
+                    })
+                    .collect(),
+            ),
+        );
+
+        if biome_palette.len() > 1 {
+            let bits_per_idx = bit_width(biome_palette.len() - 1);
+            biomes.insert("data", pack_indices(&biome_data, bits_per_idx));
+        }
+
+        let mut section = Compound::new();
+
+        section.insert("Y", (min_sect_y + sect_y as i32) as i8);
+        section.insert("block_states", block_states);
+        section.insert("biomes", biomes);
+
+        sections.push(section);
+    }
+
+    let mut nbt = Compound::new();
+
+    nbt.insert("DataVersion", DATA_VERSION);
+    nbt.insert("xPos", pos.x);
+    nbt.insert("zPos", pos.z);
+    nbt.insert("yPos", min_sect_y);
+    nbt.insert("Status", "minecraft:full");
+    nbt.insert("LastUpdate", 0_i64);
+    nbt.insert("sections", List::Compound(sections));
+    nbt.insert("block_entities", List::Compound(block_entities));
+
+    nbt
+}
+
+fn block_nbt(state: BlockState) -> Compound {
+    let kind = state.to_kind();
+
+    let mut nbt = Compound::new();
+    nbt.insert("Name", format!("minecraft:{}", kind.to_str()));
+
+    if !kind.props().is_empty() {
+        let properties = kind
+            .props()
+            .iter()
+            .filter_map(|&name| {
+                let value = state.get(name)?;
+                Some((name.to_str().to_owned(), Value::from(value.to_str())))
+            })
+            .collect::<Compound>();
+
+        nbt.insert("Properties", properties);
+    }
+
+    nbt
+}
+
+/// Packs palette indices into longs the way the anvil format expects, without
+/// indices spanning two longs.
+fn pack_indices(idxs: &[u64], bits_per_idx: usize) -> Vec<i64> {
+    let idxs_per_long = 64 / bits_per_idx;
+
+    idxs.chunks(idxs_per_long)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0_u64, |long, (j, &idx)| long | (idx << (bits_per_idx * j)))
+                as i64
+        })
+        .collect()
+}
+
+/// The data version of Minecraft 1.20.1, which written chunks are tagged
+/// with.
+const DATA_VERSION: i32 = 3465;
 const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
 const BIOMES_PER_SECTION: usize = 4 * 4 * 4;
 
@@ -0,0 +1,261 @@
+//! Converting chunks saved by older versions of Minecraft to the current
+//! layout before they're parsed.
+//!
+//! Before 1.18, everything in a chunk was stored in a `Level` compound with
+//! different names, biomes were stored as numeric IDs for the whole chunk, and
+//! before 1.16 palette indices could span two longs. Chunks from before 1.13
+//! use numeric block IDs and aren't supported.
+//!
+//! Chunks from before 1.18 are 256 blocks tall and start at Y 0, so they only
+//! line up with the original world in dimensions with a `min_y` of 0.
+
+use std::collections::BTreeMap;
+
+use valence_server::nbt::{Compound, List, Value};
+
+use super::{bit_width, pack_indices, ParseChunkError, BIOMES_PER_SECTION, BLOCKS_PER_SECTION};
+
+/// The first data version with flattened block states (1.13).
+const FLATTENING: i32 = 1451;
+/// The first data version where palette indices don't span two longs (1.16).
+const NON_SPANNING_INDICES: i32 = 2527;
+
+/// Converts `nbt` to the layout used since 1.18, if it's older.
+pub(super) fn upgrade_chunk(mut nbt: Compound) -> Result<Compound, ParseChunkError> {
+    let Some(Value::Compound(mut level)) = nbt.remove("Level") else {
+        // Already in the current layout.
+        return Ok(nbt);
+    };
+
+    // Chunks from before 1.9 don't have a data version at all.
+    let data_version = match nbt.get("DataVersion") {
+        Some(Value::Int(version)) => *version,
+        _ => 0,
+    };
+
+    if data_version < FLATTENING {
+        return Err(ParseChunkError::UnsupportedDataVersion(data_version));
+    }
+
+    let Some(Value::List(List::Compound(old_sections))) = level.remove("Sections") else {
+        return Err(ParseChunkError::MissingSections);
+    };
+
+    let mut block_states = BTreeMap::new();
+
+    for mut section in old_sections {
+        let Some(Value::Byte(sect_y)) = section.remove("Y") else {
+            return Err(ParseChunkError::MissingSectionY);
+        };
+
+        // Sections without a palette only hold light.
+        let Some(Value::List(List::Compound(mut palette))) = section.remove("Palette") else {
+            continue;
+        };
+
+        for block in &mut palette {
+            if let Some(Value::String(name)) = block.get_mut("Name") {
+                // Renamed in 1.17.
+                if *name == "minecraft:grass_path" {
+                    *name = "minecraft:dirt_path".into();
+                }
+            }
+        }
+
+        let mut states = Compound::new();
+
+        if palette.len() > 1 {
+            let Some(Value::LongArray(data)) = section.remove("BlockStates") else {
+                return Err(ParseChunkError::MissingBlockStateData);
+            };
+
+            let data = if data_version < NON_SPANNING_INDICES {
+                unspan_indices(&data, palette.len())?
+            } else {
+                data
+            };
+
+            states.insert("data", data);
+        }
+
+        states.insert("palette", List::Compound(palette));
+        block_states.insert(sect_y, states);
+    }
+
+    let biomes = match level.remove("Biomes") {
+        Some(Value::IntArray(biomes)) => biomes,
+        _ => vec![],
+    };
+
+    // Sections which are all air are left out, so they need to be added back.
+    let min_sect_y = block_states.keys().next().map_or(0, |&y| y.min(0));
+    let max_sect_y = block_states.keys().next_back().map_or(15, |&y| y.max(15));
+
+    let sections = (min_sect_y..=max_sect_y)
+        .map(|sect_y| {
+            let states = block_states.remove(&sect_y).unwrap_or_else(|| {
+                let mut air = Compound::new();
+                air.insert("Name", "minecraft:air");
+
+                let mut states = Compound::new();
+                states.insert("palette", List::Compound(vec![air]));
+                states
+            });
+
+            let mut section = Compound::new();
+            section.insert("Y", sect_y);
+            section.insert("block_states", states);
+            section.insert("biomes", section_biomes(&biomes, i32::from(sect_y)));
+            section
+        })
+        .collect();
+
+    nbt.insert("sections", List::Compound(sections));
+
+    let block_entities = match level.remove("TileEntities") {
+        Some(Value::List(block_entities)) => block_entities,
+        _ => List::End,
+    };
+
+    nbt.insert("block_entities", block_entities);
+
+    Ok(nbt)
+}
+
+/// Repacks block state data from before 1.16, where indices could span two
+/// longs, so that they don't.
+fn unspan_indices(data: &[i64], palette_len: usize) -> Result<Vec<i64>, ParseChunkError> {
+    let bits_per_idx = bit_width(palette_len - 1).max(4);
+
+    if data.len() != (BLOCKS_PER_SECTION * bits_per_idx).div_ceil(64) {
+        return Err(ParseChunkError::BadBlockLongCount);
+    }
+
+    let mask = (1_u64 << bits_per_idx) - 1;
+
+    let idxs: Vec<u64> = (0..BLOCKS_PER_SECTION)
+        .map(|i| {
+            let bit = i * bits_per_idx;
+            let (long, offset) = (bit / 64, bit % 64);
+
+            let mut idx = data[long] as u64 >> offset;
+            if offset + bits_per_idx > 64 {
+                idx |= (data[long + 1] as u64) << (64 - offset);
+            }
+
+            idx & mask
+        })
+        .collect();
+
+    Ok(pack_indices(&idxs, bits_per_idx))
+}
+
+/// Builds the biomes of a section from the numeric biome IDs of a chunk from
+/// before 1.18. Before 1.15 there's one biome per column, and since then one
+/// per 4x4x4 cell, starting at Y 0.
+fn section_biomes(biomes: &[i32], sect_y: i32) -> Compound {
+    let mut palette: Vec<&str> = vec![];
+    let mut idxs = [0_u64; BIOMES_PER_SECTION];
+
+    let cell_height = biomes.len() / 16;
+
+    for (i, idx) in idxs.iter_mut().enumerate() {
+        let x = i % 4;
+        let z = i / 4 % 4;
+        let y = i / 16;
+
+        let id = if biomes.len() == 256 {
+            biomes[z * 4 * 16 + x * 4]
+        } else if cell_height > 0 {
+            let cell_y = (sect_y * 4 + y as i32).clamp(0, cell_height as i32 - 1) as usize;
+            biomes[cell_y << 4 | z << 2 | x]
+        } else {
+            1
+        };
+
+        let name = legacy_biome_name(id);
+
+        *idx = match palette.iter().position(|&n| n == name) {
+            Some(idx) => idx as u64,
+            None => {
+                palette.push(name);
+                palette.len() as u64 - 1
+            }
+        };
+    }
+
+    let mut nbt = Compound::new();
+
+    if palette.len() > 1 {
+        nbt.insert("data", pack_indices(&idxs, bit_width(palette.len() - 1)));
+    }
+
+    nbt.insert(
+        "palette",
+        List::String(palette.into_iter().map(String::from).collect()),
+    );
+
+    nbt
+}
+
+/// Maps the numeric biome IDs used before 1.18 to the names of the biomes
+/// they became in 1.18.
+fn legacy_biome_name(id: i32) -> &'static str {
+    match id {
+        0 => "minecraft:ocean",
+        2 | 17 | 130 => "minecraft:desert",
+        3 | 20 => "minecraft:windswept_hills",
+        4 | 18 => "minecraft:forest",
+        5 | 19 | 133 => "minecraft:taiga",
+        6 | 134 => "minecraft:swamp",
+        7 => "minecraft:river",
+        8 => "minecraft:nether_wastes",
+        9 => "minecraft:the_end",
+        10 => "minecraft:frozen_ocean",
+        11 => "minecraft:frozen_river",
+        12 | 13 => "minecraft:snowy_plains",
+        14 | 15 => "minecraft:mushroom_fields",
+        16 => "minecraft:beach",
+        21 | 22 | 149 => "minecraft:jungle",
+        23 | 151 => "minecraft:sparse_jungle",
+        24 => "minecraft:deep_ocean",
+        25 => "minecraft:stony_shore",
+        26 => "minecraft:snowy_beach",
+        27 | 28 => "minecraft:birch_forest",
+        29 | 157 => "minecraft:dark_forest",
+        30 | 31 | 158 => "minecraft:snowy_taiga",
+        32 | 33 => "minecraft:old_growth_pine_taiga",
+        34 => "minecraft:windswept_forest",
+        35 => "minecraft:savanna",
+        36 => "minecraft:savanna_plateau",
+        37 | 39 | 167 => "minecraft:badlands",
+        38 | 166 => "minecraft:wooded_badlands",
+        40 => "minecraft:small_end_islands",
+        41 => "minecraft:end_midlands",
+        42 => "minecraft:end_highlands",
+        43 => "minecraft:end_barrens",
+        44 | 47 => "minecraft:warm_ocean",
+        45 => "minecraft:lukewarm_ocean",
+        46 => "minecraft:cold_ocean",
+        48 => "minecraft:deep_lukewarm_ocean",
+        49 => "minecraft:deep_cold_ocean",
+        50 => "minecraft:deep_frozen_ocean",
+        127 => "minecraft:the_void",
+        129 => "minecraft:sunflower_plains",
+        131 | 162 => "minecraft:windswept_gravelly_hills",
+        132 => "minecraft:flower_forest",
+        140 => "minecraft:ice_spikes",
+        155 | 156 => "minecraft:old_growth_birch_forest",
+        160 | 161 => "minecraft:old_growth_spruce_taiga",
+        163 | 164 => "minecraft:windswept_savanna",
+        165 => "minecraft:eroded_badlands",
+        168 | 169 => "minecraft:bamboo_jungle",
+        170 => "minecraft:soul_sand_valley",
+        171 => "minecraft:crimson_forest",
+        172 => "minecraft:warped_forest",
+        173 => "minecraft:basalt_deltas",
+        174 => "minecraft:dripstone_caves",
+        175 => "minecraft:lush_caves",
+        _ => "minecraft:plains",
+    }
+}
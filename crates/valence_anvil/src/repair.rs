@@ -0,0 +1,385 @@
+//! Finding and fixing corrupted region files.
+
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+use valence_nbt::{Compound, Value};
+
+use crate::{
+    decompress_chunk, region_file_coordinates, Compression, Location, Region, RegionError,
+    RegionFolder, RegionPos, SECTOR_SIZE,
+};
+
+/// A problem found in a region file by [`RegionFolder::verify_and_repair`].
+/// Chunk positions are absolute chunk coordinates.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RegionIssue {
+    #[error("the header of region file r.{region_x}.{region_z}.mca is truncated")]
+    TruncatedHeader { region_x: i32, region_z: i32 },
+    #[error("chunk ({chunk_x}, {chunk_z}) points inside the region header")]
+    InvalidSectorOffset { chunk_x: i32, chunk_z: i32 },
+    #[error("chunk ({chunk_x}, {chunk_z}) points past the end of the region file")]
+    PastEndOfFile { chunk_x: i32, chunk_z: i32 },
+    #[error("chunk ({chunk_x}, {chunk_z}) overlaps chunk ({other_x}, {other_z})")]
+    OverlappingChunks {
+        chunk_x: i32,
+        chunk_z: i32,
+        other_x: i32,
+        other_z: i32,
+    },
+    #[error("the external file of oversized chunk ({chunk_x}, {chunk_z}) is missing")]
+    MissingExternalChunk { chunk_x: i32, chunk_z: i32 },
+    #[error("chunk ({chunk_x}, {chunk_z}) is corrupted: {error}")]
+    CorruptedChunk {
+        chunk_x: i32,
+        chunk_z: i32,
+        #[source]
+        error: RegionError,
+    },
+    #[error("chunk ({chunk_x}, {chunk_z}) contains the data of chunk ({found_x}, {found_z})")]
+    MisplacedChunk {
+        chunk_x: i32,
+        chunk_z: i32,
+        found_x: i32,
+        found_z: i32,
+    },
+    #[error("recovered chunk ({chunk_x}, {chunk_z}) which was missing from the header")]
+    RecoveredChunk { chunk_x: i32, chunk_z: i32 },
+}
+
+impl RegionFolder {
+    /// Checks every region file in the folder for corruption, such as from a
+    /// crash while writing, and repairs what it can. Returns the problems
+    /// found, which have all been dealt with when this returns.
+    ///
+    /// Chunks which can't be read are removed from the header, so the rest of
+    /// the region can be read and written again. Their data is left in the
+    /// file. Chunks whose data is still in the file but missing from the
+    /// header are added back, including oversized chunks stored in external
+    /// files.
+    ///
+    /// Returns `Err(_)` only if the folder or a region file couldn't be
+    /// accessed.
+    pub fn verify_and_repair(&mut self) -> Result<Vec<RegionIssue>, RegionError> {
+        let regions = std::fs::read_dir(&self.region_root)?
+            .filter_map(|file| region_file_coordinates(file, "r", "mca").transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut issues = vec![];
+
+        for (region_x, region_z) in regions {
+            // The cached header would be outdated after repairing.
+            self.regions.pop(&(region_x, region_z));
+
+            repair_region(
+                &self.region_root,
+                (region_x, region_z),
+                &mut self.compression_buf,
+                &mut issues,
+            )?;
+        }
+
+        Ok(issues)
+    }
+}
+
+fn repair_region(
+    region_root: &Path,
+    (region_x, region_z): RegionPos,
+    decompress_buf: &mut Vec<u8>,
+    issues: &mut Vec<RegionIssue>,
+) -> Result<(), RegionError> {
+    let path = region_root.join(format!("r.{region_x}.{region_z}.mca"));
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+
+    let mut changed = false;
+
+    if data.len() < SECTOR_SIZE * 2 {
+        issues.push(RegionIssue::TruncatedHeader { region_x, region_z });
+        data.resize(SECTOR_SIZE * 2, 0);
+        changed = true;
+    }
+
+    let mut locations: [Location; 1024] = std::array::from_fn(|i| {
+        Location(u32::from_be_bytes(
+            data[i * 4..i * 4 + 4].try_into().unwrap(),
+        ))
+    });
+    let mut timestamps: [u32; 1024] = std::array::from_fn(|i| {
+        let i = i * 4 + SECTOR_SIZE;
+        u32::from_be_bytes(data[i..i + 4].try_into().unwrap())
+    });
+
+    let chunk_pos = |chunk_idx: usize| {
+        (
+            region_x * 32 + (chunk_idx % 32) as i32,
+            region_z * 32 + (chunk_idx / 32) as i32,
+        )
+    };
+
+    let sector_count = data.len().div_ceil(SECTOR_SIZE);
+    // The chunk each sector belongs to. Only chunks which could be read claim
+    // their sectors.
+    let mut owners: Vec<Option<usize>> = vec![None; sector_count];
+
+    for chunk_idx in 0..locations.len() {
+        let location = locations[chunk_idx];
+        if location.is_none() {
+            continue;
+        }
+
+        let (chunk_x, chunk_z) = chunk_pos(chunk_idx);
+        let (sector_offset, count) = location.offset_and_count();
+        let sector_offset = sector_offset as usize;
+
+        let issue = if sector_offset < 2 {
+            Some(RegionIssue::InvalidSectorOffset { chunk_x, chunk_z })
+        } else if sector_offset + count > sector_count {
+            Some(RegionIssue::PastEndOfFile { chunk_x, chunk_z })
+        } else if let Some(other) = owners[sector_offset..sector_offset + count]
+            .iter()
+            .find_map(|owner| *owner)
+        {
+            let (other_x, other_z) = chunk_pos(other);
+
+            Some(RegionIssue::OverlappingChunks {
+                chunk_x,
+                chunk_z,
+                other_x,
+                other_z,
+            })
+        } else {
+            match read_chunk(
+                &data,
+                sector_offset,
+                Some((chunk_x, chunk_z)),
+                region_root,
+                decompress_buf,
+            ) {
+                Ok((_, used)) if used > count => Some(RegionIssue::CorruptedChunk {
+                    chunk_x,
+                    chunk_z,
+                    error: RegionError::InvalidChunkSize,
+                }),
+                Ok((nbt, _)) => match nbt_chunk_pos(&nbt) {
+                    Some((found_x, found_z)) if (found_x, found_z) != (chunk_x, chunk_z) => {
+                        Some(RegionIssue::MisplacedChunk {
+                            chunk_x,
+                            chunk_z,
+                            found_x,
+                            found_z,
+                        })
+                    }
+                    _ => None,
+                },
+                Err(RegionError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                    Some(RegionIssue::MissingExternalChunk { chunk_x, chunk_z })
+                }
+                Err(error) => Some(RegionIssue::CorruptedChunk {
+                    chunk_x,
+                    chunk_z,
+                    error,
+                }),
+            }
+        };
+
+        match issue {
+            Some(issue) => {
+                issues.push(issue);
+                locations[chunk_idx] = Location::new();
+                timestamps[chunk_idx] = 0;
+                changed = true;
+            }
+            None => owners[sector_offset..sector_offset + count].fill(Some(chunk_idx)),
+        }
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or(0);
+
+    // Look for chunks in the sectors no chunk in the header claims. These are
+    // usually left behind when a crash happens between writing a chunk and
+    // writing the header.
+    let mut sector = 2;
+    while sector < sector_count {
+        if owners[sector].is_none() {
+            if let Ok((nbt, used)) = read_chunk(&data, sector, None, region_root, decompress_buf) {
+                if let Some((chunk_x, chunk_z)) = nbt_chunk_pos(&nbt) {
+                    let chunk_idx = Region::chunk_idx(chunk_x, chunk_z);
+
+                    if (chunk_x.div_euclid(32), chunk_z.div_euclid(32)) == (region_x, region_z)
+                        && locations[chunk_idx].is_none()
+                        && used < 256
+                        && owners[sector..sector + used].iter().all(Option::is_none)
+                    {
+                        locations[chunk_idx] = Location::new()
+                            .with_offset(sector as u32)
+                            .with_count(used as u8);
+                        timestamps[chunk_idx] = now;
+                        owners[sector..sector + used].fill(Some(chunk_idx));
+
+                        issues.push(RegionIssue::RecoveredChunk { chunk_x, chunk_z });
+                        changed = true;
+
+                        sector += used;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        sector += 1;
+    }
+
+    // Oversized chunks are stored in external files, with only a stub in the
+    // region file pointing to them. Add new stubs for external files which
+    // lost theirs.
+    let external_chunks = std::fs::read_dir(region_root)?
+        .filter_map(|file| region_file_coordinates(file, "c", "mcc").transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (chunk_x, chunk_z) in external_chunks {
+        let chunk_idx = Region::chunk_idx(chunk_x, chunk_z);
+
+        if (chunk_x.div_euclid(32), chunk_z.div_euclid(32)) != (region_x, region_z)
+            || !locations[chunk_idx].is_none()
+        {
+            continue;
+        }
+
+        let Ok(compression) = external_chunk_compression(
+            &Region::external_chunk_file(chunk_x, chunk_z, region_root),
+            decompress_buf,
+        ) else {
+            continue;
+        };
+
+        // Pad the file to a multiple of the sector size before appending.
+        data.resize(data.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+
+        let stub_sector = data.len() / SECTOR_SIZE;
+        data.extend_from_slice(&1_u32.to_be_bytes());
+        data.push((compression as u8) | 0x80);
+        data.resize((stub_sector + 1) * SECTOR_SIZE, 0);
+
+        locations[chunk_idx] = Location::new()
+            .with_offset(stub_sector as u32)
+            .with_count(1);
+        timestamps[chunk_idx] = now;
+
+        issues.push(RegionIssue::RecoveredChunk { chunk_x, chunk_z });
+        changed = true;
+    }
+
+    if changed {
+        for (i, location) in locations.iter().enumerate() {
+            data[i * 4..i * 4 + 4].copy_from_slice(&location.0.to_be_bytes());
+        }
+
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            let i = i * 4 + SECTOR_SIZE;
+            data[i..i + 4].copy_from_slice(&timestamp.to_be_bytes());
+        }
+
+        data.resize(data.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&data)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the chunk whose data starts at `sector`. Returns the chunk's NBT and
+/// the number of sectors its data takes up. Oversized chunks can only be read
+/// if their position is known, since it's part of the external file's name.
+fn read_chunk(
+    data: &[u8],
+    sector: usize,
+    pos: Option<(i32, i32)>,
+    region_root: &Path,
+    decompress_buf: &mut Vec<u8>,
+) -> Result<(Compound, usize), RegionError> {
+    let start = sector * SECTOR_SIZE;
+
+    let Some(header) = data.get(start..start + 5) else {
+        return Err(RegionError::InvalidChunkSize);
+    };
+
+    let exact_chunk_size = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    if exact_chunk_size == 0 {
+        return Err(RegionError::MissingChunkStream);
+    }
+
+    // The size includes the compression byte.
+    let Some(chunk_data) = data.get(start + 5..start + 4 + exact_chunk_size) else {
+        return Err(RegionError::InvalidChunkSize);
+    };
+
+    let used = (exact_chunk_size + 4).div_ceil(SECTOR_SIZE);
+    let compression = header[4];
+
+    let nbt = if Region::is_external_stream_chunk(compression) {
+        let Some((pos_x, pos_z)) = pos else {
+            return Err(RegionError::MissingChunkStream);
+        };
+
+        let mut buf = vec![];
+        File::open(Region::external_chunk_file(pos_x, pos_z, region_root))?
+            .read_to_end(&mut buf)?;
+
+        decompress_chunk(
+            Region::external_chunk_version(compression),
+            &buf,
+            decompress_buf,
+        )?
+    } else {
+        decompress_chunk(compression, chunk_data, decompress_buf)?
+    };
+
+    Ok((nbt, used))
+}
+
+/// Finds the compression of an external chunk file, which is normally stored
+/// in the stub pointing to it.
+fn external_chunk_compression(
+    path: &Path,
+    decompress_buf: &mut Vec<u8>,
+) -> Result<Compression, RegionError> {
+    let mut buf = vec![];
+    File::open(path)?.read_to_end(&mut buf)?;
+
+    let mut res = Err(RegionError::MissingChunkStream);
+
+    for compression in [Compression::Zlib, Compression::Gzip, Compression::None] {
+        match decompress_chunk::<String>(compression as u8, &buf, decompress_buf) {
+            Ok(_) => return Ok(compression),
+            Err(e) => res = Err(e),
+        }
+    }
+
+    res
+}
+
+/// Reads the position a chunk's NBT says it's at, if any. Chunks from before
+/// 1.18 keep it in the `Level` compound.
+fn nbt_chunk_pos(nbt: &Compound) -> Option<(i32, i32)> {
+    let level = match nbt.get("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => nbt,
+    };
+
+    match (level.get("xPos"), level.get("zPos")) {
+        (Some(Value::Int(x)), Some(Value::Int(z))) => Some((*x, *z)),
+        _ => None,
+    }
+}
@@ -0,0 +1,327 @@
+//! Command blocks and command block minecarts.
+//!
+//! Clients in creative mode with a high enough [`OpLevel`] can edit command
+//! blocks through the command block screen. The command of a command block is
+//! stored in the `Command` tag of its block entity like in vanilla, and the
+//! command of a minecart is stored in its [`Command`] component.
+//!
+//! Valence doesn't simulate redstone, so command blocks only run when a
+//! [`RunCommandBlockEvent`] is sent. The command is then dispatched as a
+//! [`CommandExecutionEvent`] like any other command. Running command blocks is
+//! disabled unless [`CommandBlockSettings::enabled`] is set, which mirrors
+//! `enable-command-block` in vanilla's `server.properties`.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::entity::command_block_minecart::{Command, LastOutput};
+use valence_server::entity::EntityManager;
+use valence_server::event_loop::{EventLoopPreUpdate, PacketEvent};
+use valence_server::layer::chunk::Block;
+use valence_server::message::SendMessage;
+use valence_server::nbt::Value;
+use valence_server::op_level::OpLevel;
+use valence_server::protocol::packets::play::update_command_block_c2s::UpdateCommandBlockMode;
+use valence_server::protocol::packets::play::{
+    UpdateCommandBlockC2s, UpdateCommandBlockMinecartC2s,
+};
+use valence_server::{BlockPos, ChunkLayer, GameMode, Text};
+
+use crate::scopes::CommandScopes;
+use crate::{CommandExecutionEvent, CommandSystemSet};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<CommandBlockSettings>()
+        .add_event::<CommandBlockEditEvent>()
+        .add_event::<RunCommandBlockEvent>()
+        .add_systems(PreUpdate, despawn_command_block_executors)
+        .add_systems(
+            EventLoopPreUpdate,
+            (
+                handle_command_block_updates,
+                handle_command_block_minecart_updates,
+                run_command_blocks.before(CommandSystemSet),
+            ),
+        );
+}
+
+/// Global configuration for command blocks.
+#[derive(Resource, Clone, Debug)]
+pub struct CommandBlockSettings {
+    /// Whether [`RunCommandBlockEvent`]s run commands. Off by default.
+    pub enabled: bool,
+    /// The minimum [`OpLevel`] clients need to edit command blocks.
+    pub edit_op_level: u8,
+    /// The scopes command blocks run their commands with.
+    pub scopes: CommandScopes,
+}
+
+impl Default for CommandBlockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            edit_op_level: 2,
+            scopes: CommandScopes::default(),
+        }
+    }
+}
+
+/// A command block or command block minecart.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CommandBlockTarget {
+    /// A command block at `position` in the [`ChunkLayer`] `layer`.
+    Block { layer: Entity, position: BlockPos },
+    /// A command block minecart entity.
+    Minecart(Entity),
+}
+
+/// Sent after a client changes the command of a command block.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct CommandBlockEditEvent {
+    pub client: Entity,
+    pub target: CommandBlockTarget,
+    /// The new command.
+    pub command: String,
+}
+
+/// Send this event to run the command of a command block.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RunCommandBlockEvent {
+    pub target: CommandBlockTarget,
+}
+
+/// The executor of commands run by command blocks, which are not entities
+/// themselves. A new executor is spawned every time a command block runs and
+/// is despawned at the start of the next tick. Command block minecarts run
+/// commands as themselves.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CommandBlockExecutor {
+    /// The [`ChunkLayer`] the command block is in.
+    pub layer: Entity,
+    /// The position of the command block.
+    pub position: BlockPos,
+}
+
+/// Returns whether `kind` is one of the command block kinds.
+pub const fn is_command_block(kind: BlockKind) -> bool {
+    matches!(
+        kind,
+        BlockKind::CommandBlock | BlockKind::ChainCommandBlock | BlockKind::RepeatingCommandBlock
+    )
+}
+
+fn can_edit(op_level: &OpLevel, game_mode: GameMode, settings: &CommandBlockSettings) -> bool {
+    op_level.get() >= settings.edit_op_level && game_mode == GameMode::Creative
+}
+
+fn handle_command_block_updates(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(&mut Client, &OpLevel, &GameMode, &VisibleChunkLayer)>,
+    mut layers: Query<&mut ChunkLayer>,
+    settings: Res<CommandBlockSettings>,
+    mut events: EventWriter<CommandBlockEditEvent>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<UpdateCommandBlockC2s>() else {
+            continue;
+        };
+
+        let Ok((mut client, op_level, game_mode, layer)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        if !can_edit(op_level, *game_mode, &settings) {
+            client.send_chat_message(Text::translate("advMode.notAllowed", []));
+            continue;
+        }
+
+        let Ok(mut chunk_layer) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        let Some(block) = chunk_layer.block(pkt.position) else {
+            continue;
+        };
+
+        if !is_command_block(block.state.to_kind()) {
+            continue;
+        }
+
+        let kind = match pkt.mode {
+            UpdateCommandBlockMode::Sequence => BlockKind::ChainCommandBlock,
+            UpdateCommandBlockMode::Auto => BlockKind::RepeatingCommandBlock,
+            UpdateCommandBlockMode::Redstone => BlockKind::CommandBlock,
+        };
+
+        let mut state = kind.to_state().set(
+            PropName::Conditional,
+            PropValue::from_bool(pkt.flags.conditional()),
+        );
+
+        if let Some(facing) = block.state.get(PropName::Facing) {
+            state = state.set(PropName::Facing, facing);
+        }
+
+        let mut nbt = block.nbt.cloned().unwrap_or_default();
+
+        nbt.insert("Command", pkt.command.to_owned());
+        nbt.insert("TrackOutput", pkt.flags.track_output());
+        nbt.insert("auto", pkt.flags.automatic());
+
+        if !pkt.flags.track_output() {
+            nbt.remove("LastOutput");
+        }
+
+        chunk_layer.set_block(pkt.position, Block::new(state, Some(nbt)));
+
+        if !pkt.command.is_empty() {
+            client.send_chat_message(Text::translate(
+                "advMode.setCommand.success",
+                [Text::text(pkt.command.to_owned())],
+            ));
+        }
+
+        events.send(CommandBlockEditEvent {
+            client: packet.client,
+            target: CommandBlockTarget::Block {
+                layer: layer.0,
+                position: pkt.position,
+            },
+            command: pkt.command.to_owned(),
+        });
+    }
+}
+
+fn handle_command_block_minecart_updates(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(&mut Client, &OpLevel, &GameMode)>,
+    mut minecarts: Query<(&mut Command, &mut LastOutput)>,
+    entities: Res<EntityManager>,
+    settings: Res<CommandBlockSettings>,
+    mut events: EventWriter<CommandBlockEditEvent>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<UpdateCommandBlockMinecartC2s>() else {
+            continue;
+        };
+
+        let Ok((mut client, op_level, game_mode)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        if !can_edit(op_level, *game_mode, &settings) {
+            client.send_chat_message(Text::translate("advMode.notAllowed", []));
+            continue;
+        }
+
+        let Some(minecart) = entities.get_by_id(pkt.entity_id.0) else {
+            continue;
+        };
+
+        let Ok((mut command, mut last_output)) = minecarts.get_mut(minecart) else {
+            continue;
+        };
+
+        command.0 = pkt.command.to_owned();
+
+        if !pkt.track_output {
+            last_output.0 = Text::default();
+        }
+
+        if !pkt.command.is_empty() {
+            client.send_chat_message(Text::translate(
+                "advMode.setCommand.success",
+                [Text::text(pkt.command.to_owned())],
+            ));
+        }
+
+        events.send(CommandBlockEditEvent {
+            client: packet.client,
+            target: CommandBlockTarget::Minecart(minecart),
+            command: pkt.command.to_owned(),
+        });
+    }
+}
+
+fn run_command_blocks(
+    mut events: EventReader<RunCommandBlockEvent>,
+    layers: Query<&ChunkLayer>,
+    minecarts: Query<(&Command, Has<CommandScopes>)>,
+    settings: Res<CommandBlockSettings>,
+    mut execution_events: EventWriter<CommandExecutionEvent>,
+    mut commands: Commands,
+) {
+    if !settings.enabled {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        let command = match event.target {
+            CommandBlockTarget::Block { layer, position } => {
+                let Some(block) = layers.get(layer).ok().and_then(|l| l.block(position)) else {
+                    continue;
+                };
+
+                if !is_command_block(block.state.to_kind()) {
+                    continue;
+                }
+
+                let Some(Value::String(command)) = block.nbt.and_then(|nbt| nbt.get("Command"))
+                else {
+                    continue;
+                };
+
+                command.clone()
+            }
+            CommandBlockTarget::Minecart(minecart) => {
+                let Ok((command, _)) = minecarts.get(minecart) else {
+                    continue;
+                };
+
+                command.0.clone()
+            }
+        };
+
+        // Unlike chat, command blocks accept commands with a leading slash.
+        let command = command.strip_prefix('/').unwrap_or(&command);
+
+        if command.is_empty() {
+            continue;
+        }
+
+        let executor = match event.target {
+            CommandBlockTarget::Block { layer, position } => commands
+                .spawn((
+                    CommandBlockExecutor { layer, position },
+                    settings.scopes.clone(),
+                ))
+                .id(),
+            CommandBlockTarget::Minecart(minecart) => {
+                if minecarts
+                    .get(minecart)
+                    .is_ok_and(|(_, has_scopes)| !has_scopes)
+                {
+                    commands.entity(minecart).insert(settings.scopes.clone());
+                }
+
+                minecart
+            }
+        };
+
+        execution_events.send(CommandExecutionEvent {
+            command: command.to_owned(),
+            executor,
+        });
+    }
+}
+
+fn despawn_command_block_executors(
+    executors: Query<Entity, With<CommandBlockExecutor>>,
+    mut commands: Commands,
+) {
+    for entity in &executors {
+        commands.entity(entity).despawn();
+    }
+}
@@ -70,6 +70,7 @@
 //! trait on the [`CommandGraph`] struct. Then you can use a tool like
 //! [Graphviz Online](https://dreampuf.github.io/GraphvizOnline) to look at the graph.
 
+use std::any::TypeId;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
@@ -240,9 +241,10 @@ impl From<CommandGraph> for CommandTreeS2c {
 ///     .literal("command")
 ///     .redirect_to(simple_command);
 ///
-/// assert_eq!(command_graph.graph.graph.node_count(), 5); // root, test, command, <test>, test
-/// // 5 edges, 2 for the simple command, 2 for the complex command and 1 for the redirect
-/// assert_eq!(command_graph.graph.graph.edge_count(), 5);
+/// // root, test, <test>, command (the second `test` literal is shared)
+/// assert_eq!(command_graph.graph.graph.node_count(), 4);
+/// // 4 edges, 2 for the simple command, 1 for the complex command and 1 for the redirect
+/// assert_eq!(command_graph.graph.graph.edge_count(), 4);
 /// ```
 ///
 /// in this example we can execute either of the following commands for the same
@@ -256,6 +258,7 @@ impl From<CommandGraph> for CommandTreeS2c {
 pub struct CommandGraphBuilder<'a, T> {
     // We do not own the graph, we just have a mutable reference to it
     graph: &'a mut CommandGraph,
+    argument_types: &'a HashMap<TypeId, Parser>,
     current_node: NodeIndex,
     executables: &'a mut HashMap<NodeIndex, fn(&mut ParseInput) -> T>,
     parsers: &'a mut HashMap<NodeIndex, fn(&mut ParseInput) -> bool>,
@@ -283,6 +286,7 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
         CommandGraphBuilder {
             current_node: registry.graph.root,
             graph: &mut registry.graph,
+            argument_types: &registry.argument_types,
             executables,
             parsers,
             modifiers,
@@ -297,7 +301,9 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
         self
     }
 
-    /// Creates a new literal node and transitions to it.
+    /// Creates a new literal node and transitions to it. If the current node
+    /// already has a literal child with the same name, it transitions to that
+    /// node instead, so paths with a common start share their nodes.
     ///
     /// # Default Values
     /// * executable - `false`
@@ -305,12 +311,25 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
     pub fn literal<S: Into<String>>(&mut self, literal: S) -> &mut Self {
         let graph = &mut self.graph.graph;
         let current_node = &mut self.current_node;
+        let name = literal.into();
+
+        let existing = graph
+            .edges_directed(*current_node, Direction::Outgoing)
+            .filter(|edge| *edge.weight() == CommandEdgeType::Child)
+            .map(|edge| edge.target())
+            .find(|&node| match &graph[node].data {
+                NodeData::Literal { name: n } => *n == name,
+                _ => false,
+            });
+
+        if let Some(literal_node) = existing {
+            *current_node = literal_node;
+            return self;
+        }
 
         let literal_node = graph.add_node(CommandNode {
             executable: false,
-            data: NodeData::Literal {
-                name: literal.into(),
-            },
+            data: NodeData::Literal { name },
             scopes: Vec::new(),
         });
 
@@ -491,9 +510,13 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
     /// it is passed to the executable. The node should be an argument node
     /// or nothing will happen.
     ///
+    /// The parser sent to clients is the one added with
+    /// [`AddCommand::add_argument_type`](crate::AddCommand::add_argument_type)
+    /// for `P`, or [`CommandArg::display`] otherwise.
+    ///
     /// # Type Parameters
     /// * `P` - the parser to use for the current node (must be [`CommandArg`])
-    pub fn with_parser<P: CommandArg>(&mut self) -> &mut Self {
+    pub fn with_parser<P: CommandArg + 'static>(&mut self) -> &mut Self {
         let graph = &mut self.graph.graph;
         let current_node = self.current_node;
 
@@ -501,7 +524,11 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
         self.parsers
             .insert(current_node, |input| P::parse_arg(input).is_ok());
 
-        let parser = P::display();
+        let parser = self
+            .argument_types
+            .get(&TypeId::of::<P>())
+            .cloned()
+            .unwrap_or_else(P::display);
 
         node.data = match node.data.clone() {
             NodeData::Argument {
@@ -530,3 +557,64 @@ impl<'a, T> CommandGraphBuilder<'a, T> {
         self.current_node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_merges_siblings() {
+        let mut registry = CommandRegistry::default();
+        let mut executables = HashMap::new();
+        let mut parsers = HashMap::new();
+        let mut modifiers = HashMap::new();
+        let mut builder = CommandGraphBuilder::<()>::new(
+            &mut registry,
+            &mut executables,
+            &mut parsers,
+            &mut modifiers,
+        );
+
+        let survival = builder.root().literal("gamemode").literal("survival").id();
+        let creative = builder.root().literal("gamemode").literal("creative").id();
+        let gamemode = builder.root().literal("gamemode").id();
+        let survival_again = builder.root().literal("gamemode").literal("survival").id();
+
+        assert_ne!(survival, creative);
+        assert_eq!(survival, survival_again);
+
+        let graph = &registry.graph.graph;
+        // root, gamemode, survival, creative
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.neighbors(registry.graph.root).count(), 1);
+        assert_eq!(graph.neighbors(gamemode).count(), 2);
+    }
+
+    #[test]
+    fn literal_does_not_merge_arguments_or_redirects() {
+        let mut registry = CommandRegistry::default();
+        let mut executables = HashMap::new();
+        let mut parsers = HashMap::new();
+        let mut modifiers = HashMap::new();
+        let mut builder = CommandGraphBuilder::<()>::new(
+            &mut registry,
+            &mut executables,
+            &mut parsers,
+            &mut modifiers,
+        );
+
+        // an argument with the same name as a literal is a different node
+        let argument = builder.root().literal("give").argument("item").id();
+        let literal = builder.root().literal("give").literal("item").id();
+        assert_ne!(argument, literal);
+
+        // a redirect target isn't reused as a child literal
+        let teleport = builder.root().literal("teleport").id();
+        builder.root().literal("tp").redirect_to(teleport);
+        let tp_teleport = builder.root().literal("tp").literal("teleport").id();
+        assert_ne!(tp_teleport, teleport);
+
+        // root, give, <item>, item, teleport, tp, tp teleport
+        assert_eq!(registry.graph.graph.node_count(), 7);
+    }
+}
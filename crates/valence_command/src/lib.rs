@@ -1,3 +1,4 @@
+pub mod command_block;
 pub mod graph;
 pub mod handler;
 pub mod manager;
@@ -5,6 +6,7 @@ mod modifier_value;
 pub mod parsers;
 pub mod scopes;
 
+use std::any::TypeId;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
@@ -17,7 +19,7 @@ pub use scopes::CommandScopeRegistry;
 
 use crate::graph::{CommandGraph, CommandGraphBuilder};
 use crate::handler::CommandHandlerPlugin;
-use crate::parsers::ParseInput;
+use crate::parsers::{CommandArg, ParseInput, Parser};
 
 #[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct CommandSystemSet;
@@ -29,6 +31,9 @@ pub struct CommandRegistry {
     pub parsers: HashMap<NodeIndex, fn(&mut ParseInput) -> bool>,
     pub modifiers: HashMap<NodeIndex, fn(String, &mut HashMap<ModifierValue, ModifierValue>)>,
     pub executables: HashSet<NodeIndex>,
+    /// Parsers sent to clients for argument types in place of
+    /// [`CommandArg::display`].
+    pub argument_types: HashMap<TypeId, Parser>,
 }
 
 pub trait Command {
@@ -39,10 +44,27 @@ pub trait Command {
 
 pub trait AddCommand {
     fn add_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self;
+
+    /// Sends `parser` to clients for arguments of type `T`, so they can check
+    /// and highlight them like vanilla arguments. Use [`Parser::Custom`] for
+    /// parsers with properties which aren't covered by the other variants.
+    ///
+    /// Argument types must be added before commands using them are built on
+    /// startup.
+    fn add_argument_type<T: CommandArg + 'static>(&mut self, parser: Parser) -> &mut Self;
 }
 
 impl AddCommand for App {
     fn add_command<T: Command + Send + Sync + 'static>(&mut self) -> &mut Self {
         self.add_plugins(CommandHandlerPlugin::<T>::new())
     }
+
+    fn add_argument_type<T: CommandArg + 'static>(&mut self, parser: Parser) -> &mut Self {
+        self.init_resource::<CommandRegistry>();
+        self.world_mut()
+            .resource_mut::<CommandRegistry>()
+            .argument_types
+            .insert(TypeId::of::<T>(), parser);
+        self
+    }
 }
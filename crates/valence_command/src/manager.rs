@@ -40,17 +40,10 @@ impl Plugin for CommandPlugin {
                 ),
             );
 
-        let graph: CommandGraph = CommandGraph::new();
-        let modifiers = HashMap::new();
-        let parsers = HashMap::new();
-        let executables = HashSet::new();
+        // Argument types may have been added to the registry already.
+        app.init_resource::<CommandRegistry>();
 
-        app.insert_resource(CommandRegistry {
-            graph,
-            parsers,
-            modifiers,
-            executables,
-        });
+        crate::command_block::build(app);
     }
 }
 
@@ -381,8 +374,25 @@ fn parse_command_args(
         return true;
     }
 
+    // a matching flag (a literal starting with `-`) takes precedence over any
+    // arguments next to it, so a written out flag isn't parsed as an argument.
+    // other literals are tried alongside the arguments.
+    let next_word = input.peek_word();
+    let flag_matches = graph
+        .neighbors(current_node)
+        .any(|neighbor| match &graph[neighbor].data {
+            NodeData::Literal { name } => {
+                name.starts_with('-') && name.eq_ignore_ascii_case(next_word)
+            }
+            _ => false,
+        });
+
     let mut all_invalid = true;
     for neighbor in graph.neighbors(current_node) {
+        if flag_matches && matches!(graph[neighbor].data, NodeData::Argument { .. }) {
+            continue;
+        }
+
         let pre_input = input.clone();
         let mut args = command_args.clone();
         let mut modifiers = modifiers_to_be_executed.clone();
@@ -59,7 +59,7 @@ impl CommandArg for Time {
     }
 
     fn display() -> Parser {
-        Parser::Time
+        Parser::Time { min: 0 }
     }
 }
 
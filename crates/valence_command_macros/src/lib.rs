@@ -12,6 +12,100 @@ pub fn derive_command(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Implements `CommandArg` for an enum of unit variants, so that each variant
+/// is written as a word in commands. The word is the variant name in
+/// snake_case, unless it's set with `#[literal = "..."]`.
+#[proc_macro_derive(CommandArg, attributes(literal))]
+pub fn derive_command_arg(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match command_arg(input) {
+        Ok(expansion) => expansion,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn command_arg(input: DeriveInput) -> Result<TokenStream> {
+    let input_name = input.ident;
+
+    let Data::Enum(data_enum) = input.data else {
+        return Err(Error::new_spanned(
+            input_name,
+            "CommandArg can only be derived for enums",
+        ));
+    };
+
+    let mut literals = Vec::new();
+    let mut variants = Vec::new();
+
+    for variant in &data_enum.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "CommandArg variants can't have fields",
+            ));
+        }
+
+        let literal = variant
+            .attrs
+            .iter()
+            .find_map(|attr| get_lit_list_attr(attr, "literal"))
+            .and_then(|literals| literals.into_iter().next())
+            .unwrap_or_else(|| to_snake_case(&variant.ident.to_string()));
+
+        literals.push(literal);
+        variants.push(&variant.ident);
+    }
+
+    let expected = literals.join("|");
+
+    Ok(TokenStream::from(quote! {
+        impl valence::command::parsers::CommandArg for #input_name {
+            fn parse_arg(
+                input: &mut valence::command::parsers::ParseInput,
+            ) -> Result<Self, valence::command::parsers::CommandArgParseError> {
+                input.skip_whitespace();
+                let word = input.peek_word();
+
+                let value = match word {
+                    #(#literals => Self::#variants,)*
+                    _ => {
+                        return Err(valence::command::parsers::CommandArgParseError::InvalidArgument {
+                            expected: #expected.to_owned(),
+                            got: word.to_owned(),
+                        })
+                    }
+                };
+
+                input.pop_word();
+                Ok(value)
+            }
+
+            fn display() -> valence::protocol::packets::play::command_tree_s2c::Parser {
+                valence::protocol::packets::play::command_tree_s2c::Parser::String(
+                    valence::protocol::packets::play::command_tree_s2c::StringArg::SingleWord,
+                )
+            }
+        }
+    }))
+}
+
+fn to_snake_case(ident: &str) -> String {
+    let mut snake = String::new();
+
+    for (i, c) in ident.char_indices() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
 fn command(input: DeriveInput) -> Result<TokenStream> {
     let input_name = input.ident;
 
@@ -262,6 +356,51 @@ fn process_paths_enum(
                         };
                     }
                 }
+                CommandArg::Greedy(ident) => {
+                    if i != path.len() - 1 {
+                        return Error::new_spanned(
+                            variant_ident,
+                            "A greedy arg must be the last arg",
+                        )
+                        .into_compile_error();
+                    }
+                    let ident_string = ident.to_string();
+
+                    inner_expansion = quote! {
+                        #inner_expansion
+                            .argument(#ident_string)
+                            .with_parser::<valence::command::parsers::GreedyString>()
+                            .with_executable(|s| {
+                                #enum_name::#variant_ident {
+                                    #(#final_executable,)*
+                                    #ident: valence::command::parsers::GreedyString::parse_arg(s)
+                                        .unwrap()
+                                        .0,
+                                }
+                            })
+                    };
+                }
+                CommandArg::Flag {
+                    literal,
+                    ident,
+                    present,
+                } => {
+                    final_executable.push(quote! {
+                        #ident: #present
+                    });
+
+                    if *present {
+                        inner_expansion = quote! {
+                            #inner_expansion.literal(#literal)
+                        };
+                        if executables && i == path.len() - 1 {
+                            inner_expansion = quote! {
+                                #inner_expansion
+                                    .with_executable(|s| #enum_name::#variant_ident{#(#final_executable,)*})
+                            };
+                        }
+                    }
+                }
                 CommandArg::Optional(ident) => {
                     let field_type = &fields
                         .iter()
@@ -377,6 +516,19 @@ fn process_paths_enum(
                 }
             }
         }
+
+        // a path made up of only left out flags executes the variant at the
+        // command itself
+        if executables
+            && path
+                .iter()
+                .all(|arg| matches!(arg, CommandArg::Flag { present: false, .. }))
+        {
+            inner_expansion = quote! {
+                #inner_expansion
+                    .with_executable(|s| #enum_name::#variant_ident{#(#final_executable,)*})
+            };
+        }
     }
     quote!(#inner_expansion)
 }
@@ -468,6 +620,67 @@ fn process_paths_struct(
                         path_first = false;
                     }
                 }
+                CommandArg::Greedy(ident) => {
+                    if i != path.len() - 1 {
+                        return Error::new_spanned(
+                            struct_name,
+                            "A greedy arg must be the last arg",
+                        )
+                        .into_compile_error();
+                    }
+                    let ident_string = ident.to_string();
+
+                    inner_expansion = quote! {
+                        #inner_expansion
+                            .argument(#ident_string)
+                            .with_parser::<valence::command::parsers::GreedyString>()
+                            .with_executable(|s| {
+                                #struct_name {
+                                    #(#final_executable,)*
+                                    #ident: valence::command::parsers::GreedyString::parse_arg(s)
+                                        .unwrap()
+                                        .0,
+                                }
+                            })
+                    };
+
+                    if path_first {
+                        inner_expansion = quote! {
+                            #inner_expansion
+                                .with_scopes(vec![#(#outer_scopes),*])
+                        };
+                        path_first = false;
+                    }
+                }
+                CommandArg::Flag {
+                    literal,
+                    ident,
+                    present,
+                } => {
+                    final_executable.push(quote! {
+                        #ident: #present
+                    });
+
+                    if *present {
+                        inner_expansion = quote! {
+                            #inner_expansion.literal(#literal)
+                        };
+                        if i == path.len() - 1 {
+                            inner_expansion = quote! {
+                                #inner_expansion
+                                    .with_executable(|s| #struct_name{#(#final_executable,)*})
+                            };
+                        }
+
+                        if path_first {
+                            inner_expansion = quote! {
+                                #inner_expansion
+                                    .with_scopes(vec![#(#outer_scopes),*])
+                            };
+                            path_first = false;
+                        }
+                    }
+                }
                 CommandArg::Optional(ident) => {
                     let field_type = &fields
                         .iter()
@@ -595,11 +808,17 @@ fn process_paths_struct(
     quote!(#inner_expansion)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum CommandArg {
     Required(Ident),
     Optional(Ident),
+    Greedy(Ident),
     Literal(String),
+    Flag {
+        literal: String,
+        ident: Ident,
+        present: bool,
+    },
 }
 
 // example input: #[paths = "strawberry {0?}"]
@@ -617,27 +836,80 @@ fn parse_path(path: &Attribute) -> Option<Vec<(Vec<CommandArg>, bool)>> {
 
         for word in path_str.split_whitespace().skip(usize::from(at_root)) {
             if word.starts_with('{') && word.ends_with('}') {
-                if word.ends_with("?}") {
-                    args.push(CommandArg::Optional(format_ident!(
-                        "{}",
-                        word[1..word.len() - 2].to_owned()
-                    )));
+                let inner = &word[1..word.len() - 1];
+
+                if let Some((literal, ident)) =
+                    inner.split_once(':').filter(|_| inner.starts_with('-'))
+                {
+                    // a flag eg "{-s:silent}"
+                    args.push(CommandArg::Flag {
+                        literal: literal.to_owned(),
+                        ident: format_ident!("{}", ident),
+                        present: true,
+                    });
+                } else if let Some(ident) = inner.strip_suffix("...") {
+                    args.push(CommandArg::Greedy(format_ident!("{}", ident)));
+                } else if let Some(ident) = inner.strip_suffix('?') {
+                    args.push(CommandArg::Optional(format_ident!("{}", ident)));
                 } else {
-                    args.push(CommandArg::Required(format_ident!(
-                        "{}",
-                        word[1..word.len() - 1].to_owned()
-                    )));
+                    args.push(CommandArg::Required(format_ident!("{}", inner)));
                 }
             } else {
                 args.push(CommandArg::Literal(word.to_owned()));
             }
         }
-        paths.push((args, at_root));
+
+        for args in expand_flags(args) {
+            paths.push((args, at_root));
+        }
     }
 
     Some(paths)
 }
 
+// Flags can be left out, so a path with flags is turned into one path for every
+// combination of flags. Missing flags go at the start of the path since they
+// don't add any nodes to the graph.
+fn expand_flags(args: Vec<CommandArg>) -> Vec<Vec<CommandArg>> {
+    let flag_count = args
+        .iter()
+        .filter(|arg| matches!(arg, CommandArg::Flag { .. }))
+        .count();
+
+    (0..1_u32 << flag_count)
+        .map(|mask| {
+            let mut missing = Vec::new();
+            let mut path = Vec::new();
+            let mut flag_idx = 0;
+
+            for arg in &args {
+                match arg {
+                    CommandArg::Flag { literal, ident, .. } => {
+                        let present = mask & (1 << flag_idx) != 0;
+                        flag_idx += 1;
+
+                        let flag = CommandArg::Flag {
+                            literal: literal.clone(),
+                            ident: ident.clone(),
+                            present,
+                        };
+
+                        if present {
+                            path.push(flag);
+                        } else {
+                            missing.push(flag);
+                        }
+                    }
+                    arg => path.push(arg.clone()),
+                }
+            }
+
+            missing.extend(path);
+            missing
+        })
+        .collect()
+}
+
 fn get_lit_list_attr(attr: &Attribute, ident: &str) -> Option<Vec<String>> {
     match &attr.meta {
         Meta::NameValue(key_value) => {
@@ -692,3 +964,128 @@ fn get_lit_list_attr(attr: &Attribute, ident: &str) -> Option<Vec<String>> {
         Meta::Path(_) => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    /// Writes a path like it's written in the `paths` attribute. Flags which
+    /// are left out are written in brackets.
+    fn describe(args: &[CommandArg]) -> String {
+        args.iter()
+            .map(|arg| match arg {
+                CommandArg::Required(ident) => format!("{{{ident}}}"),
+                CommandArg::Optional(ident) => format!("{{{ident}?}}"),
+                CommandArg::Greedy(ident) => format!("{{{ident}...}}"),
+                CommandArg::Literal(literal) => literal.clone(),
+                CommandArg::Flag {
+                    literal,
+                    ident,
+                    present: true,
+                } => format!("{{{literal}:{ident}}}"),
+                CommandArg::Flag {
+                    literal,
+                    ident,
+                    present: false,
+                } => format!("[{literal}:{ident}]"),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn paths(attr: Attribute) -> Vec<(String, bool)> {
+        parse_path(&attr)
+            .unwrap()
+            .into_iter()
+            .map(|(args, at_root)| (describe(&args), at_root))
+            .collect()
+    }
+
+    #[test]
+    fn parse_path_args() {
+        assert_eq!(
+            paths(parse_quote!(#[paths("give {targets} {item} {count?}")])),
+            [("give {targets} {item} {count?}".to_owned(), false)]
+        );
+        assert_eq!(
+            paths(parse_quote!(#[paths = "{/} gms {target?}"])),
+            [("gms {target?}".to_owned(), true)]
+        );
+        assert_eq!(
+            paths(
+                parse_quote!(#[paths("msg {target} {message...}", "tell {target} {message...}")])
+            ),
+            [
+                ("msg {target} {message...}".to_owned(), false),
+                ("tell {target} {message...}".to_owned(), false),
+            ]
+        );
+        assert!(parse_path(&parse_quote!(#[scopes("valence.command")])).is_none());
+    }
+
+    #[test]
+    fn parse_path_flags() {
+        assert_eq!(
+            paths(parse_quote!(#[paths("message {-s:silent} {tone} {text...}")])),
+            [
+                ("[-s:silent] message {tone} {text...}".to_owned(), false),
+                ("message {-s:silent} {tone} {text...}".to_owned(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_two_flags() {
+        let args = vec![
+            CommandArg::Literal("kill".to_owned()),
+            CommandArg::Flag {
+                literal: "-a".to_owned(),
+                ident: format_ident!("all"),
+                present: true,
+            },
+            CommandArg::Flag {
+                literal: "-q".to_owned(),
+                ident: format_ident!("quiet"),
+                present: true,
+            },
+            CommandArg::Required(format_ident!("target")),
+        ];
+
+        let paths: Vec<_> = expand_flags(args)
+            .iter()
+            .map(|args| describe(args))
+            .collect();
+
+        // every combination of flags, with the missing flags moved to the start
+        // where they don't add any nodes
+        assert_eq!(
+            paths,
+            [
+                "[-a:all] [-q:quiet] kill {target}",
+                "[-q:quiet] kill {-a:all} {target}",
+                "[-a:all] kill {-q:quiet} {target}",
+                "kill {-a:all} {-q:quiet} {target}",
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_without_flags() {
+        let paths = expand_flags(vec![
+            CommandArg::Literal("time".to_owned()),
+            CommandArg::Optional(format_ident!("ticks")),
+        ]);
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(describe(&paths[0]), "time {ticks?}");
+    }
+
+    #[test]
+    fn snake_case_literals() {
+        assert_eq!(to_snake_case("Normal"), "normal");
+        assert_eq!(to_snake_case("TrueNorth"), "true_north");
+        assert_eq!(to_snake_case("already_snake"), "already_snake");
+    }
+}
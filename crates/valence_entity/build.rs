@@ -299,6 +299,8 @@ fn build_entities() -> anyhow::Result<TokenStream> {
     let mut entity_kind_consts = TokenStream::new();
     let mut entity_kind_fmt_args = TokenStream::new();
     let mut translation_key_arms = TokenStream::new();
+    let mut entity_kind_from_str_arms = TokenStream::new();
+    let mut entity_kind_to_str_arms = TokenStream::new();
     let mut modules = TokenStream::new();
     let mut systems = TokenStream::new();
     let mut system_names = vec![];
@@ -347,6 +349,14 @@ fn build_entities() -> anyhow::Result<TokenStream> {
                 EntityKind::#stripped_shouty_entity_name_ident => #translation_key_expr,
             }]);
 
+            entity_kind_from_str_arms.extend([quote! {
+                #entity_type => Some(EntityKind::#stripped_shouty_entity_name_ident),
+            }]);
+
+            entity_kind_to_str_arms.extend([quote! {
+                EntityKind::#stripped_shouty_entity_name_ident => Some(#entity_type),
+            }]);
+
             // Create bundle type.
             let mut bundle_fields = TokenStream::new();
             let mut bundle_init_fields = TokenStream::new();
@@ -461,6 +471,7 @@ fn build_entities() -> anyhow::Result<TokenStream> {
                 pub old_layer: super::OldEntityLayerId,
                 pub position: super::Position,
                 pub old_position: super::OldPosition,
+                pub sent_position: super::movement::SentPosition,
                 pub look: super::Look,
                 pub head_yaw: super::HeadYaw,
                 pub on_ground: super::OnGround,
@@ -479,6 +490,7 @@ fn build_entities() -> anyhow::Result<TokenStream> {
                 old_layer: Default::default(),
                 position: Default::default(),
                 old_position: Default::default(),
+                sent_position: Default::default(),
                 look: Default::default(),
                 head_yaw: Default::default(),
                 on_ground: Default::default(),
@@ -698,6 +710,27 @@ fn build_entities() -> anyhow::Result<TokenStream> {
                     _ => None,
                 }
             }
+
+            /// Construct an entity kind from its snake_case name, such as
+            /// `"pig"`.
+            ///
+            /// Returns `None` if the name is invalid.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    #entity_kind_from_str_arms
+                    _ => None,
+                }
+            }
+
+            /// Gets the snake_case name of this entity kind, or `None` if the
+            /// kind is unknown.
+            pub const fn to_str(self) -> Option<&'static str> {
+                match self {
+                    #entity_kind_to_str_arms
+                    _ => None,
+                }
+            }
         }
 
         impl std::fmt::Debug for EntityKind {
@@ -0,0 +1,154 @@
+//! Poses for armor stands.
+//!
+//! Insert [`ArmorStandPose`] on an armor stand to set the rotations of all of
+//! its body parts at once. Poses can be interpolated with
+//! [`ArmorStandPose::lerp`] for simple animations. The remaining options of
+//! armor stands are flags, which have typed setters on
+//! [`ArmorStandFlags`](crate::armor_stand::ArmorStandFlags) (small, arms, base
+//! plate and marker mode) and [`Flags`](crate::entity::Flags) (invisibility).
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+
+use crate::armor_stand::{
+    TrackerBodyRotation, TrackerHeadRotation, TrackerLeftArmRotation, TrackerLeftLegRotation,
+    TrackerRightArmRotation, TrackerRightLegRotation,
+};
+use crate::{EulerAngle, UpdateTrackedDataSet};
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        update_armor_stand_poses.before(UpdateTrackedDataSet),
+    );
+}
+
+/// A body part of an armor stand.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ArmorStandPart {
+    Head,
+    Body,
+    LeftArm,
+    RightArm,
+    LeftLeg,
+    RightLeg,
+}
+
+impl ArmorStandPart {
+    pub const ALL: [Self; 6] = [
+        Self::Head,
+        Self::Body,
+        Self::LeftArm,
+        Self::RightArm,
+        Self::LeftLeg,
+        Self::RightLeg,
+    ];
+}
+
+/// The rotations of the body parts of an armor stand, in degrees. The default
+/// is the pose armor stands are placed with in vanilla.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct ArmorStandPose {
+    pub head: EulerAngle,
+    pub body: EulerAngle,
+    pub left_arm: EulerAngle,
+    pub right_arm: EulerAngle,
+    pub left_leg: EulerAngle,
+    pub right_leg: EulerAngle,
+}
+
+impl ArmorStandPose {
+    pub const DEFAULT: Self = Self {
+        head: EulerAngle::ZERO,
+        body: EulerAngle::ZERO,
+        left_arm: EulerAngle::new(-10.0, 0.0, -10.0),
+        right_arm: EulerAngle::new(-15.0, 0.0, 10.0),
+        left_leg: EulerAngle::new(-1.0, 0.0, -1.0),
+        right_leg: EulerAngle::new(1.0, 0.0, 1.0),
+    };
+
+    /// Every body part pointing straight ahead or down.
+    pub const ZERO: Self = Self {
+        head: EulerAngle::ZERO,
+        body: EulerAngle::ZERO,
+        left_arm: EulerAngle::ZERO,
+        right_arm: EulerAngle::ZERO,
+        left_leg: EulerAngle::ZERO,
+        right_leg: EulerAngle::ZERO,
+    };
+
+    pub fn get(&self, part: ArmorStandPart) -> EulerAngle {
+        match part {
+            ArmorStandPart::Head => self.head,
+            ArmorStandPart::Body => self.body,
+            ArmorStandPart::LeftArm => self.left_arm,
+            ArmorStandPart::RightArm => self.right_arm,
+            ArmorStandPart::LeftLeg => self.left_leg,
+            ArmorStandPart::RightLeg => self.right_leg,
+        }
+    }
+
+    pub fn set(&mut self, part: ArmorStandPart, angle: EulerAngle) {
+        let slot = match part {
+            ArmorStandPart::Head => &mut self.head,
+            ArmorStandPart::Body => &mut self.body,
+            ArmorStandPart::LeftArm => &mut self.left_arm,
+            ArmorStandPart::RightArm => &mut self.right_arm,
+            ArmorStandPart::LeftLeg => &mut self.left_leg,
+            ArmorStandPart::RightLeg => &mut self.right_leg,
+        };
+
+        *slot = angle;
+    }
+
+    #[must_use]
+    pub fn with(mut self, part: ArmorStandPart, angle: EulerAngle) -> Self {
+        self.set(part, angle);
+        self
+    }
+
+    /// Linearly interpolates every body part between `self` and `other`.
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut pose = *self;
+
+        for part in ArmorStandPart::ALL {
+            pose.set(part, self.get(part).lerp(other.get(part), t));
+        }
+
+        pose
+    }
+}
+
+impl Default for ArmorStandPose {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_armor_stand_poses(
+    mut armor_stands: Query<
+        (
+            &ArmorStandPose,
+            &mut TrackerHeadRotation,
+            &mut TrackerBodyRotation,
+            &mut TrackerLeftArmRotation,
+            &mut TrackerRightArmRotation,
+            &mut TrackerLeftLegRotation,
+            &mut TrackerRightLegRotation,
+        ),
+        Changed<ArmorStandPose>,
+    >,
+) {
+    for (pose, mut head, mut body, mut left_arm, mut right_arm, mut left_leg, mut right_leg) in
+        &mut armor_stands
+    {
+        head.set_if_neq(TrackerHeadRotation(pose.head));
+        body.set_if_neq(TrackerBodyRotation(pose.body));
+        left_arm.set_if_neq(TrackerLeftArmRotation(pose.left_arm));
+        right_arm.set_if_neq(TrackerRightArmRotation(pose.right_arm));
+        left_leg.set_if_neq(TrackerLeftLegRotation(pose.left_leg));
+        right_leg.set_if_neq(TrackerRightLegRotation(pose.right_leg));
+    }
+}
@@ -0,0 +1,175 @@
+//! High-level transforms for display entities.
+//!
+//! Insert [`DisplayTransform`] on a block, item or text display to set its
+//! translation, rotations and scale with [`Vec3`], [`Quat`] or a [`Mat4`]. The
+//! transform is packed into the display's tracked data automatically. Add
+//! [`DisplayInterpolation`] to have clients smoothly animate to every new
+//! transform.
+//!
+//! To attach a display to another entity, insert a
+//! [`Vehicle`](crate::passengers::Vehicle) on the display. The display then
+//! moves with its parent, and [`DisplayTransform::translation`] becomes an
+//! offset from the point where the parent carries its passengers.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_math::{Mat4, Quat, Vec3};
+
+use crate::display::{
+    InterpolationDuration, LeftRotation, RightRotation, Scale, StartInterpolation, Translation,
+};
+use crate::UpdateTrackedDataSet;
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        update_display_transforms.before(UpdateTrackedDataSet),
+    );
+}
+
+/// The transformation of a display entity. The rendered model is scaled and
+/// rotated by [`Self::right_rotation`] first, then rotated by
+/// [`Self::left_rotation`] and finally translated.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct DisplayTransform {
+    pub translation: Vec3,
+    pub left_rotation: Quat,
+    pub scale: Vec3,
+    pub right_rotation: Quat,
+}
+
+impl DisplayTransform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        left_rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+        right_rotation: Quat::IDENTITY,
+    };
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self::IDENTITY.with_translation(translation)
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self::IDENTITY.with_left_rotation(rotation)
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::IDENTITY.with_scale(scale)
+    }
+
+    /// Decomposes an affine transformation matrix. Shearing can't be
+    /// represented exactly and is lost.
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+
+        Self {
+            translation,
+            left_rotation: rotation,
+            scale,
+            right_rotation: Quat::IDENTITY,
+        }
+    }
+
+    pub fn to_matrix(&self) -> Mat4 {
+        Mat4::from_translation(self.translation)
+            * Mat4::from_quat(self.left_rotation)
+            * Mat4::from_scale(self.scale)
+            * Mat4::from_quat(self.right_rotation)
+    }
+
+    #[must_use]
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    #[must_use]
+    pub fn with_left_rotation(mut self, rotation: Quat) -> Self {
+        self.left_rotation = rotation.normalize();
+        self
+    }
+
+    #[must_use]
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub fn with_right_rotation(mut self, rotation: Quat) -> Self {
+        self.right_rotation = rotation.normalize();
+        self
+    }
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// How clients interpolate between the previous and the new
+/// [`DisplayTransform`] of a display entity whenever it changes.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct DisplayInterpolation {
+    /// The length of the interpolation in ticks. Zero means the new transform
+    /// is applied immediately.
+    pub duration: i32,
+    /// The number of ticks after receiving the new transform before the
+    /// interpolation starts.
+    pub delay: i32,
+}
+
+impl DisplayInterpolation {
+    pub fn new(duration: i32) -> Self {
+        Self { duration, delay: 0 }
+    }
+
+    #[must_use]
+    pub fn with_delay(mut self, delay: i32) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_display_transforms(
+    mut displays: Query<
+        (
+            &DisplayTransform,
+            Option<&DisplayInterpolation>,
+            &mut Translation,
+            &mut LeftRotation,
+            &mut Scale,
+            &mut RightRotation,
+            &mut InterpolationDuration,
+            &mut StartInterpolation,
+        ),
+        Changed<DisplayTransform>,
+    >,
+) {
+    for (
+        transform,
+        interpolation,
+        mut translation,
+        mut left_rotation,
+        mut scale,
+        mut right_rotation,
+        mut duration,
+        mut start,
+    ) in &mut displays
+    {
+        translation.set_if_neq(Translation(transform.translation));
+        left_rotation.set_if_neq(LeftRotation(transform.left_rotation));
+        scale.set_if_neq(Scale(transform.scale));
+        right_rotation.set_if_neq(RightRotation(transform.right_rotation));
+
+        if let Some(interpolation) = interpolation {
+            duration.set_if_neq(InterpolationDuration(interpolation.duration));
+            // Always resend the start of the interpolation, even if it didn't change, so
+            // that clients restart it from the current transform.
+            start.0 = interpolation.delay;
+        }
+    }
+}
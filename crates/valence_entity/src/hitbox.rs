@@ -3,6 +3,7 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use derive_more::Deref;
+use rustc_hash::FxHashMap;
 use valence_math::{Aabb, UVec3, Vec3Swizzles};
 use valence_protocol::Direction;
 
@@ -39,7 +40,12 @@ impl Default for EntityHitboxSettings {
 impl Plugin for HitboxPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<EntityHitboxSettings>()
+            .init_resource::<HitboxKindOverrides>()
             .configure_sets(PreUpdate, HitboxShapeUpdateSet)
+            .add_systems(
+                PreUpdate,
+                reset_overridden_hitboxes.before(HitboxShapeUpdateSet),
+            )
             .add_systems(
                 PreUpdate,
                 (
@@ -64,12 +70,18 @@ impl Plugin for HitboxPlugin {
                 add_hitbox_component.in_set(HitboxComponentsAddSet),
             )
             .configure_sets(PreUpdate, HitboxUpdateSet.after(HitboxShapeUpdateSet))
-            .add_systems(PreUpdate, update_hitbox.in_set(HitboxUpdateSet));
+            .add_systems(
+                PreUpdate,
+                (apply_hitbox_overrides, update_hitbox)
+                    .chain()
+                    .in_set(HitboxUpdateSet),
+            );
     }
 }
 
-/// Size of hitbox. The only way to manipulate it without losing it on the next
-/// tick is using a marker entity. Marker entity's hitbox is never updated.
+/// Size of hitbox. Changes made to it directly are lost the next time it is
+/// recomputed, except on marker entities whose hitbox is never updated. Use
+/// [`HitboxOverride`] or [`HitboxKindOverrides`] to change it permanently.
 #[derive(Component, Debug, PartialEq, Deref)]
 pub struct HitboxShape(pub Aabb);
 
@@ -101,6 +113,50 @@ impl Hitbox {
     }
 }
 
+/// The size of a hitbox centered on the entity's position, which can depend on
+/// the entity's [`Pose`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct HitboxSize {
+    /// The size used for poses without an entry in `poses`.
+    pub size: DVec3,
+    pub poses: Vec<(Pose, DVec3)>,
+}
+
+impl HitboxSize {
+    pub fn new<S: Into<DVec3>>(size: S) -> Self {
+        Self {
+            size: size.into(),
+            poses: vec![],
+        }
+    }
+
+    /// Uses `size` while the entity is in `pose`.
+    pub fn with_pose<S: Into<DVec3>>(mut self, pose: Pose, size: S) -> Self {
+        self.poses.push((pose, size.into()));
+        self
+    }
+
+    /// Returns the size for an entity in `pose`.
+    pub fn get(&self, pose: Pose) -> DVec3 {
+        self.poses
+            .iter()
+            .find(|(p, _)| *p == pose)
+            .map_or(self.size, |&(_, size)| size)
+    }
+}
+
+/// Overrides the [`HitboxShape`] that would otherwise be computed from the
+/// kind and metadata of this entity. Removing this component restores the
+/// computed shape.
+#[derive(Component, Clone, PartialEq, Debug, Deref)]
+pub struct HitboxOverride(pub HitboxSize);
+
+/// Overrides the [`HitboxShape`] of every entity of a kind. A
+/// [`HitboxOverride`] on an entity takes priority over this. The hitboxes of
+/// existing entities are recomputed when this resource changes.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct HitboxKindOverrides(pub FxHashMap<EntityKind, HitboxSize>);
+
 fn add_hitbox_component(
     settings: Res<EntityHitboxSettings>,
     mut commands: Commands,
@@ -123,6 +179,66 @@ fn add_hitbox_component(
     }
 }
 
+/// Re-adding the shape makes the systems in [`HitboxShapeUpdateSet`] compute it
+/// again from the entity's kind and metadata.
+fn reset_hitbox_shape(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<HitboxShape>()
+        .insert(HitboxShape::ZERO);
+}
+
+fn reset_overridden_hitboxes(
+    mut removed: RemovedComponents<HitboxOverride>,
+    kind_overrides: Res<HitboxKindOverrides>,
+    shapes: Query<Entity, (With<HitboxShape>, Without<HitboxOverride>)>,
+    mut commands: Commands,
+) {
+    if kind_overrides.is_changed() && !kind_overrides.is_added() {
+        removed.clear();
+
+        for entity in &shapes {
+            reset_hitbox_shape(&mut commands, entity);
+        }
+    } else {
+        for entity in removed.read() {
+            if shapes.contains(entity) {
+                reset_hitbox_shape(&mut commands, entity);
+            }
+        }
+    }
+}
+
+fn apply_hitbox_overrides(
+    mut query: Query<
+        (
+            &mut HitboxShape,
+            &EntityKind,
+            &entity::Pose,
+            Option<&HitboxOverride>,
+        ),
+        Or<(
+            Changed<HitboxShape>,
+            Changed<entity::Pose>,
+            Changed<HitboxOverride>,
+        )>,
+    >,
+    kind_overrides: Res<HitboxKindOverrides>,
+) {
+    // Changes made here are not seen by this system on the next run, so shapes
+    // are only overridden again after something else changes them.
+    for (mut hitbox, kind, pose, entity_override) in &mut query {
+        let Some(size) = entity_override
+            .map(|o| &o.0)
+            .or_else(|| kind_overrides.0.get(kind))
+        else {
+            continue;
+        };
+
+        hitbox.centered(size.get(pose.0));
+    }
+}
+
 fn update_hitbox(
     mut hitbox_query: Query<
         (&mut Hitbox, &HitboxShape, &Position),
@@ -2,10 +2,16 @@
 #![allow(clippy::unseparated_literal_suffix, clippy::manual_string_new)]
 
 pub mod active_status_effects;
+pub mod armor_stand_pose;
 pub mod attributes;
+pub mod display_transform;
 mod flags;
 pub mod hitbox;
 pub mod manager;
+pub mod move_to;
+pub mod movement;
+pub mod name_tag;
+pub mod passengers;
 pub mod query;
 pub mod tracked_data;
 
@@ -21,6 +27,7 @@ use valence_protocol::{Decode, Encode, VarInt};
 use valence_server_common::{Despawned, UniqueId};
 
 use crate::attributes::TrackedEntityAttributes;
+use crate::movement::SentPosition;
 
 include!(concat!(env!("OUT_DIR"), "/entity.rs"));
 
@@ -78,13 +85,18 @@ impl Plugin for EntityPlugin {
                     clear_animation_changes,
                     clear_tracked_data_changes,
                     clear_tracked_attributes_changes,
-                    update_old_position,
+                    update_old_position.after(movement::update_sent_positions),
                     update_old_layer_id,
                 )
                     .in_set(ClearEntityChangesSet),
             );
 
         add_tracked_data_systems(app);
+        armor_stand_pose::build(app);
+        display_transform::build(app);
+        move_to::build(app);
+        movement::build(app);
+        passengers::build(app);
     }
 }
 
@@ -111,14 +123,24 @@ fn remove_despawned_from_manager(
 
 fn init_entities(
     mut entities: Query<
-        (Entity, &mut EntityId, &Position, &mut OldPosition),
+        (
+            Entity,
+            &mut EntityId,
+            &Position,
+            &mut OldPosition,
+            Option<&mut SentPosition>,
+        ),
         (Added<EntityKind>, Without<Despawned>),
     >,
     mut manager: ResMut<EntityManager>,
 ) {
-    for (entity, mut id, pos, mut old_pos) in &mut entities {
+    for (entity, mut id, pos, mut old_pos, sent_pos) in &mut entities {
         *old_pos = OldPosition::new(pos.0);
 
+        if let Some(mut sent_pos) = sent_pos {
+            *sent_pos = SentPosition(pos.0);
+        }
+
         if *id == EntityId::default() {
             *id = manager.next_id();
         }
@@ -564,6 +586,24 @@ pub struct EulerAngle {
     pub roll: f32,
 }
 
+impl EulerAngle {
+    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
+
+    pub const fn new(pitch: f32, yaw: f32, roll: f32) -> Self {
+        Self { pitch, yaw, roll }
+    }
+
+    /// Linearly interpolates each angle between `self` and `other`.
+    #[must_use]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            pitch: self.pitch + (other.pitch - self.pitch) * t,
+            yaw: self.yaw + (other.yaw - self.yaw) * t,
+            roll: self.roll + (other.roll - self.roll) * t,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 struct OptionalInt(Option<i32>);
 
@@ -0,0 +1,172 @@
+//! Moving entities smoothly over several ticks.
+//!
+//! Writing a far away [`Position`] makes clients see the entity snap to it.
+//! Inserting [`MoveTo`] instead moves the entity a little every tick until it
+//! reaches the target, which clients see as smooth movement. This works well
+//! for moving platforms and animated NPCs. The component is removed once the
+//! target is reached.
+//!
+//! The movement is sent as relative moves as long as the entity moves less
+//! than [`EntityMovementSettings::teleport_distance`] per tick.
+//!
+//! [`EntityMovementSettings::teleport_distance`]: crate::movement::EntityMovementSettings::teleport_distance
+
+use std::f64::consts::PI;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_math::DVec3;
+
+use crate::{InitEntitiesSet, Position};
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(PostUpdate, move_entities.before(InitEntitiesSet));
+}
+
+/// Moves an entity from where it is when the component is inserted to
+/// `target` over `ticks` ticks. The [`Position`] of the entity is overwritten
+/// while it's moving.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct MoveTo {
+    pub target: DVec3,
+    /// The number of ticks until the target is reached.
+    pub ticks: u32,
+    pub easing: Easing,
+    /// The position the movement started at. `None` until the first tick.
+    start: Option<DVec3>,
+    elapsed: u32,
+}
+
+impl MoveTo {
+    /// Moves to `target` at a constant speed.
+    pub fn new<P: Into<DVec3>>(target: P, ticks: u32) -> Self {
+        Self {
+            target: target.into(),
+            ticks,
+            easing: Easing::Linear,
+            start: None,
+            elapsed: 0,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Returns the number of ticks the entity has been moving for.
+    pub fn elapsed(&self) -> u32 {
+        self.elapsed
+    }
+
+    /// Returns the position the movement started at, or `None` if it hasn't
+    /// started yet.
+    pub fn start(&self) -> Option<DVec3> {
+        self.start
+    }
+}
+
+/// How the speed of a [`MoveTo`] changes over time.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Easing {
+    /// A constant speed.
+    #[default]
+    Linear,
+    /// Start slowly and speed up.
+    QuadIn,
+    /// Start quickly and slow down.
+    QuadOut,
+    /// Start slowly, speed up and slow down again.
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Like [`Easing::QuadInOut`], but gentler.
+    SineInOut,
+}
+
+impl Easing {
+    /// Maps the fraction of time passed to the fraction of the distance
+    /// covered. Both are between zero and one.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::SineInOut => (1.0 - (t * PI).cos()) / 2.0,
+        }
+    }
+}
+
+fn move_entities(
+    mut entities: Query<(Entity, &mut MoveTo, &mut Position)>,
+    mut commands: Commands,
+) {
+    for (entity, mut move_to, mut pos) in &mut entities {
+        let start = *move_to.start.get_or_insert(pos.0);
+
+        move_to.elapsed += 1;
+
+        if move_to.elapsed >= move_to.ticks {
+            pos.set_if_neq(Position(move_to.target));
+            commands.entity(entity).remove::<MoveTo>();
+        } else {
+            let t = move_to
+                .easing
+                .apply(f64::from(move_to.elapsed) / f64::from(move_to.ticks));
+
+            pos.set_if_neq(Position(start.lerp(move_to.target, t)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_endpoints() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::SineInOut,
+        ];
+
+        for easing in easings {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{easing:?}");
+
+            // Easings never move backwards.
+            for i in 0..100 {
+                let t = f64::from(i) / 100.0;
+                assert!(easing.apply(t + 0.01) >= easing.apply(t), "{easing:?}");
+            }
+        }
+
+        assert!((Easing::QuadInOut.apply(0.5) - 0.5).abs() < 1e-9);
+        assert!((Easing::SineInOut.apply(0.5) - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,109 @@
+//! Choosing how entity movement is sent to clients.
+//!
+//! Every tick, the movement of an entity since its [`SentPosition`] is sent as
+//! a relative move, as a teleport, or not at all. Suppressing small movements
+//! with [`EntityMovementSettings::min_distance`] saves a lot of bandwidth when
+//! many entities shuffle around at once. Suppressed movement is not lost: it
+//! accumulates until it crosses the threshold, and entities that stop moving
+//! are teleported to their exact position.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_math::DVec3;
+
+use crate::{ClearEntityChangesSet, OldPosition, Position};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<EntityMovementSettings>().add_systems(
+        PostUpdate,
+        update_sent_positions.in_set(ClearEntityChangesSet),
+    );
+}
+
+/// Global configuration for sending entity movement.
+#[derive(Resource, Clone, Debug)]
+pub struct EntityMovementSettings {
+    /// Movements shorter than this many blocks along every axis are not sent
+    /// while the entity keeps moving. Zero sends all movement.
+    pub min_distance: f64,
+    /// Movements at least this many blocks along any axis are sent as
+    /// teleports instead of relative moves. Relative moves can't go farther
+    /// than 8 blocks, so larger values act like 8.
+    pub teleport_distance: f64,
+}
+
+impl Default for EntityMovementSettings {
+    fn default() -> Self {
+        Self {
+            min_distance: 0.0,
+            teleport_distance: 8.0,
+        }
+    }
+}
+
+/// The position of an entity as last sent to clients. This lags behind
+/// [`Position`] while movement is suppressed.
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct SentPosition(pub(crate) DVec3);
+
+impl SentPosition {
+    pub fn get(self) -> DVec3 {
+        self.0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum MovementUpdate {
+    None,
+    /// A relative move in 1/4096ths of a block.
+    Relative([i16; 3]),
+    Teleport,
+}
+
+pub(crate) fn movement_update(
+    pos: DVec3,
+    old_pos: DVec3,
+    sent_pos: DVec3,
+    settings: &EntityMovementSettings,
+) -> MovementUpdate {
+    let delta = pos - sent_pos;
+    let distance = delta.abs().max_element();
+
+    if distance >= settings.teleport_distance.min(8.0) {
+        return MovementUpdate::Teleport;
+    }
+
+    let units = (delta * 4096.0).to_array().map(|v| v as i16);
+
+    if units == [0; 3] {
+        return MovementUpdate::None;
+    }
+
+    if distance < settings.min_distance {
+        // Catch up once the entity stops. A teleport also fixes the position for
+        // clients that started viewing the entity while its movement was
+        // suppressed.
+        return if pos == old_pos {
+            MovementUpdate::Teleport
+        } else {
+            MovementUpdate::None
+        };
+    }
+
+    MovementUpdate::Relative(units)
+}
+
+pub(crate) fn update_sent_positions(
+    mut entities: Query<(&Position, &OldPosition, &mut SentPosition)>,
+    settings: Res<EntityMovementSettings>,
+) {
+    for (pos, old_pos, mut sent_pos) in &mut entities {
+        match movement_update(pos.0, old_pos.get(), sent_pos.0, &settings) {
+            MovementUpdate::None => {}
+            MovementUpdate::Relative(units) => {
+                sent_pos.0 += DVec3::from_array(units.map(f64::from)) / 4096.0;
+            }
+            MovementUpdate::Teleport => sent_pos.0 = pos.0,
+        }
+    }
+}
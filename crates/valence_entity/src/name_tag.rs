@@ -0,0 +1,71 @@
+//! Helpers for naming entities.
+//!
+//! Entities show their [`CustomName`] above their head when the player looks
+//! at them, or at all times when [`NameVisible`] is `true`. Insert a
+//! [`NameTagBundle`] to name an entity without touching its tracked data:
+//!
+//! ```
+//! # use bevy_ecs::prelude::*;
+//! use valence_entity::name_tag::NameTagBundle;
+//! use valence_protocol::text::{Color, IntoText};
+//!
+//! fn name_mob(mut commands: Commands, mob: Entity) {
+//!     commands
+//!         .entity(mob)
+//!         .insert(NameTagBundle::new("Grumpy".color(Color::RED)).always_visible());
+//! }
+//! ```
+//!
+//! The color of a name tag is also determined by the team the entity is on.
+//! Mobs are added to teams by the string form of their
+//! [`UniqueId`](valence_server_common::UniqueId).
+
+use bevy_ecs::prelude::*;
+use valence_protocol::text::IntoText;
+use valence_protocol::Text;
+
+pub use crate::entity::{CustomName, NameVisible};
+
+impl CustomName {
+    pub fn new<'a>(name: impl IntoText<'a>) -> Self {
+        Self(Some(name.into_text()))
+    }
+
+    /// Returns the name, or `None` if the entity isn't named.
+    pub fn get(&self) -> Option<&Text> {
+        self.0.as_ref()
+    }
+
+    pub fn set<'a>(&mut self, name: impl IntoText<'a>) {
+        self.0 = Some(name.into_text());
+    }
+
+    /// Removes the name, so that the entity type is shown instead.
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// The components that control an entity's name tag. Insert this on an
+/// existing entity to rename it.
+#[derive(Bundle, Clone, Default, Debug)]
+pub struct NameTagBundle {
+    pub name: CustomName,
+    pub visible: NameVisible,
+}
+
+impl NameTagBundle {
+    /// A name which is only shown when the entity is looked at.
+    pub fn new<'a>(name: impl IntoText<'a>) -> Self {
+        Self {
+            name: CustomName::new(name),
+            visible: NameVisible(false),
+        }
+    }
+
+    /// Shows the name at all times, even when the entity isn't looked at.
+    pub fn always_visible(mut self) -> Self {
+        self.visible.0 = true;
+        self
+    }
+}
@@ -0,0 +1,127 @@
+//! Entities riding other entities.
+//!
+//! Insert [`Vehicle`] on an entity to make it ride another entity, such as a
+//! display entity attached to a player. The [`Passengers`] of the vehicle are
+//! kept in sync and sent to clients automatically, and the [`Position`] of a
+//! passenger follows its vehicle so that both are always visible together.
+//! Remove [`Vehicle`] to dismount.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+use valence_server_common::Despawned;
+
+use crate::{EntityId, InitEntitiesSet, Position, UpdateTrackedDataSet};
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        update_passengers
+            .after(InitEntitiesSet)
+            .before(UpdateTrackedDataSet),
+    );
+}
+
+/// The entity that this entity is riding.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct Vehicle {
+    entity: Entity,
+    /// The entity ID of the vehicle and the entity IDs of all its passengers,
+    /// for when this entity is spawned for a client after its vehicle.
+    pub(crate) passenger_ids: Option<(i32, Vec<i32>)>,
+}
+
+impl Vehicle {
+    pub fn new(entity: Entity) -> Self {
+        Self {
+            entity,
+            passenger_ids: None,
+        }
+    }
+
+    pub fn get(&self) -> Entity {
+        self.entity
+    }
+}
+
+/// The entities riding this entity, ordered by entity ID. This is inserted and
+/// updated automatically from the [`Vehicle`] components of the passengers.
+#[derive(Component, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Passengers {
+    entities: Vec<Entity>,
+    pub(crate) ids: Vec<i32>,
+}
+
+impl Passengers {
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+fn update_passengers(
+    mut riders: Query<(Entity, &EntityId, &mut Vehicle), Without<Despawned>>,
+    mut passengers: Query<(Entity, &mut Passengers)>,
+    mut positions: Query<(&EntityId, &mut Position)>,
+    mut commands: Commands,
+) {
+    let mut lists = FxHashMap::<Entity, Vec<(i32, Entity)>>::default();
+
+    for (entity, id, vehicle) in &riders {
+        if vehicle.entity != entity {
+            lists
+                .entry(vehicle.entity)
+                .or_default()
+                .push((id.get(), entity));
+        }
+    }
+
+    for (entity, mut current) in &mut passengers {
+        if !lists.contains_key(&entity) {
+            current.set_if_neq(Passengers::default());
+        }
+    }
+
+    let mut vehicles = FxHashMap::default();
+
+    for (vehicle, mut list) in lists {
+        let Ok((vehicle_id, vehicle_pos)) = positions.get(vehicle) else {
+            continue;
+        };
+
+        list.sort_unstable();
+
+        let new = Passengers {
+            entities: list.iter().map(|&(_, entity)| entity).collect(),
+            ids: list.iter().map(|&(id, _)| id).collect(),
+        };
+
+        vehicles.insert(vehicle, (vehicle_id.get(), vehicle_pos.0, new.ids.clone()));
+
+        if let Ok((_, mut current)) = passengers.get_mut(vehicle) {
+            current.set_if_neq(new);
+        } else {
+            commands.entity(vehicle).insert(new);
+        }
+    }
+
+    for (entity, _, mut vehicle) in &mut riders {
+        let passenger_ids = match vehicles.get(&vehicle.entity) {
+            Some((vehicle_id, vehicle_pos, ids)) => {
+                if let Ok((_, mut pos)) = positions.get_mut(entity) {
+                    pos.set_if_neq(Position(*vehicle_pos));
+                }
+
+                Some((*vehicle_id, ids.clone()))
+            }
+            None => None,
+        };
+
+        if vehicle.passenger_ids != passenger_ids {
+            vehicle.passenger_ids = passenger_ids;
+        }
+    }
+}
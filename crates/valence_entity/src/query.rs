@@ -6,15 +6,18 @@ use bevy_ecs::world::Ref;
 use valence_math::DVec3;
 use valence_protocol::encode::WritePacket;
 use valence_protocol::packets::play::{
-    EntityAnimationS2c, EntityAttributesS2c, EntityPositionS2c, EntitySetHeadYawS2c,
-    EntitySpawnS2c, EntityStatusS2c, EntityTrackerUpdateS2c, EntityVelocityUpdateS2c,
-    ExperienceOrbSpawnS2c, MoveRelativeS2c, PlayerSpawnS2c, RotateAndMoveRelativeS2c, RotateS2c,
+    EntityAnimationS2c, EntityAttributesS2c, EntityPassengersSetS2c, EntityPositionS2c,
+    EntitySetHeadYawS2c, EntitySpawnS2c, EntityStatusS2c, EntityTrackerUpdateS2c,
+    EntityVelocityUpdateS2c, ExperienceOrbSpawnS2c, MoveRelativeS2c, PlayerSpawnS2c,
+    RotateAndMoveRelativeS2c, RotateS2c,
 };
 use valence_protocol::var_int::VarInt;
 use valence_protocol::ByteAngle;
 use valence_server_common::UniqueId;
 
 use crate::attributes::TrackedEntityAttributes;
+use crate::movement::{movement_update, EntityMovementSettings, MovementUpdate, SentPosition};
+use crate::passengers::{Passengers, Vehicle};
 use crate::tracked_data::TrackedData;
 use crate::{
     EntityAnimations, EntityId, EntityKind, EntityLayerId, EntityStatuses, HeadYaw, Look,
@@ -32,13 +35,22 @@ pub struct EntityInitQuery {
     pub object_data: &'static ObjectData,
     pub velocity: &'static Velocity,
     pub tracked_data: &'static TrackedData,
+    pub passengers: Option<&'static Passengers>,
+    pub vehicle: Option<&'static Vehicle>,
 }
 
 impl EntityInitQueryItem<'_> {
     /// Writes the appropriate packets to initialize an entity. This will spawn
     /// the entity and initialize tracked data. `pos` is the initial position of
     /// the entity.
+    ///
+    /// The packets are [bundled](WritePacket::write_bundle), so the entity
+    /// never appears without its tracked data.
     pub fn write_init_packets<W: WritePacket>(&self, pos: DVec3, mut writer: W) {
+        writer.write_bundle(|writer| self.write_bundled_init_packets(pos, writer));
+    }
+
+    fn write_bundled_init_packets<W: WritePacket>(&self, pos: DVec3, mut writer: W) {
         match *self.kind {
             EntityKind::MARKER => {}
             EntityKind::EXPERIENCE_ORB => {
@@ -82,14 +94,35 @@ impl EntityInitQueryItem<'_> {
                 tracked_values: init_data.into(),
             });
         }
+
+        // The vehicle and its passengers can be spawned in any order, so both send the
+        // passengers of the vehicle. The client ignores passengers it doesn't know
+        // about.
+        if let Some(passengers) = self.passengers.filter(|p| !p.is_empty()) {
+            write_passengers(&mut writer, self.entity_id.get(), &passengers.ids);
+        }
+
+        if let Some((vehicle_id, passenger_ids)) =
+            self.vehicle.and_then(|v| v.passenger_ids.as_ref())
+        {
+            write_passengers(&mut writer, *vehicle_id, passenger_ids);
+        }
     }
 }
 
+fn write_passengers<W: WritePacket>(mut writer: W, vehicle_id: i32, passenger_ids: &[i32]) {
+    writer.write_packet(&EntityPassengersSetS2c {
+        entity_id: vehicle_id.into(),
+        passengers: passenger_ids.iter().map(|&id| VarInt(id)).collect(),
+    });
+}
+
 #[derive(QueryData)]
 pub struct UpdateEntityQuery {
     pub id: &'static EntityId,
     pub pos: &'static Position,
     pub old_pos: &'static OldPosition,
+    pub sent_pos: Option<&'static SentPosition>,
     pub loc: &'static EntityLayerId,
     pub old_loc: &'static OldEntityLayerId,
     pub look: Ref<'static, Look>,
@@ -101,49 +134,57 @@ pub struct UpdateEntityQuery {
     pub animations: &'static EntityAnimations,
     // Option because not all entities have attributes, only LivingEntity.
     pub tracked_attributes: Option<&'static TrackedEntityAttributes>,
+    pub passengers: Option<Ref<'static, Passengers>>,
 }
 
 impl UpdateEntityQueryItem<'_> {
-    pub fn write_update_packets<W: WritePacket>(&self, mut writer: W) {
+    /// Writes the packets to update an entity for clients that are already
+    /// viewing it. `settings` decides how its movement is sent.
+    pub fn write_update_packets<W: WritePacket>(
+        &self,
+        mut writer: W,
+        settings: &EntityMovementSettings,
+    ) {
         // TODO: @RJ I saw you're using UpdateEntityPosition and UpdateEntityRotation sometimes. These two packets are actually broken on the client and will erase previous position/rotation https://bugs.mojang.com/browse/MC-255263 -Moulberry
 
         let entity_id = VarInt(self.id.get());
 
-        let position_delta = self.pos.0 - self.old_pos.get();
-        let needs_teleport = position_delta.abs().max_element() >= 8.0;
-        let changed_position = self.pos.0 != self.old_pos.get();
+        // Entities without a `SentPosition` send all movement since the last tick.
+        let sent_pos = self.sent_pos.map_or(self.old_pos.get(), |p| p.get());
+        let movement = movement_update(self.pos.0, self.old_pos.get(), sent_pos, settings);
 
-        if changed_position && !needs_teleport && self.look.is_changed() {
-            writer.write_packet(&RotateAndMoveRelativeS2c {
-                entity_id,
-                delta: (position_delta * 4096.0).to_array().map(|v| v as i16),
-                yaw: ByteAngle::from_degrees(self.look.yaw),
-                pitch: ByteAngle::from_degrees(self.look.pitch),
-                on_ground: self.on_ground.0,
-            });
-        } else {
-            if changed_position && !needs_teleport {
+        match movement {
+            MovementUpdate::Relative(delta) if self.look.is_changed() => {
+                writer.write_packet(&RotateAndMoveRelativeS2c {
+                    entity_id,
+                    delta,
+                    yaw: ByteAngle::from_degrees(self.look.yaw),
+                    pitch: ByteAngle::from_degrees(self.look.pitch),
+                    on_ground: self.on_ground.0,
+                });
+            }
+            MovementUpdate::Relative(delta) => {
                 writer.write_packet(&MoveRelativeS2c {
                     entity_id,
-                    delta: (position_delta * 4096.0).to_array().map(|v| v as i16),
+                    delta,
                     on_ground: self.on_ground.0,
                 });
             }
-
-            if self.look.is_changed() {
-                writer.write_packet(&RotateS2c {
+            MovementUpdate::Teleport => {
+                writer.write_packet(&EntityPositionS2c {
                     entity_id,
+                    position: self.pos.0,
                     yaw: ByteAngle::from_degrees(self.look.yaw),
                     pitch: ByteAngle::from_degrees(self.look.pitch),
                     on_ground: self.on_ground.0,
                 });
             }
+            MovementUpdate::None => {}
         }
 
-        if needs_teleport {
-            writer.write_packet(&EntityPositionS2c {
+        if self.look.is_changed() && !matches!(movement, MovementUpdate::Relative(_)) {
+            writer.write_packet(&RotateS2c {
                 entity_id,
-                position: self.pos.0,
                 yaw: ByteAngle::from_degrees(self.look.yaw),
                 pitch: ByteAngle::from_degrees(self.look.pitch),
                 on_ground: self.on_ground.0,
@@ -203,5 +244,11 @@ impl UpdateEntityQueryItem<'_> {
                 });
             }
         }
+
+        if let Some(passengers) = &self.passengers {
+            if passengers.is_changed() {
+                write_passengers(&mut writer, entity_id.0, &passengers.ids);
+            }
+        }
     }
 }
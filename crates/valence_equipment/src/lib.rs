@@ -10,7 +10,7 @@ use valence_server::entity::{EntityId, EntityLayerId, Position};
 use valence_server::protocol::packets::play::entity_equipment_update_s2c::EquipmentEntry;
 use valence_server::protocol::packets::play::EntityEquipmentUpdateS2c;
 use valence_server::protocol::WritePacket;
-use valence_server::{EntityLayer, ItemStack, Layer};
+use valence_server::{EntityLayer, Hand, ItemStack, Layer};
 
 pub struct EquipmentPlugin;
 
@@ -138,6 +138,34 @@ impl Equipment {
         self.set_slot(Self::HEAD_IDX, item);
     }
 
+    /// Returns the slot index of the item held in `hand`.
+    pub const fn hand_idx(hand: Hand) -> u8 {
+        match hand {
+            Hand::Main => Self::MAIN_HAND_IDX,
+            Hand::Off => Self::OFF_HAND_IDX,
+        }
+    }
+
+    pub fn hand(&self, hand: Hand) -> &ItemStack {
+        self.slot(Self::hand_idx(hand))
+    }
+
+    pub fn set_hand(&mut self, hand: Hand, item: ItemStack) {
+        self.set_slot(Self::hand_idx(hand), item);
+    }
+
+    /// Swaps the items in the main hand and the off hand. An entity whose
+    /// main arm is the opposite of a player's then holds the player's items in
+    /// the same arms, which is useful for entities mirroring left-handed
+    /// players.
+    pub fn swap_hands(&mut self) {
+        let main_hand = self.main_hand().clone();
+        let off_hand = self.off_hand().clone();
+
+        self.set_main_hand(off_hand);
+        self.set_off_hand(main_hand);
+    }
+
     pub fn clear(&mut self) {
         for slot in 0..Self::SLOT_COUNT as u8 {
             self.set_slot(slot, ItemStack::EMPTY);
@@ -5,6 +5,7 @@ mod block;
 mod chunk_view;
 mod item;
 mod packet_id;
+mod potions;
 mod sound;
 mod status_effects;
 
@@ -16,6 +17,7 @@ pub fn main() -> anyhow::Result<()> {
     write_generated_file(packet_id::build()?, "packet_id.rs")?;
     write_generated_file(chunk_view::build(), "chunk_view.rs")?;
     write_generated_file(status_effects::build()?, "status_effects.rs")?;
+    write_generated_file(potions::build()?, "potions.rs")?;
 
     Ok(())
 }
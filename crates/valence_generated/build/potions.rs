@@ -0,0 +1,176 @@
+use heck::ToPascalCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use valence_build_utils::{ident, rerun_if_changed};
+
+#[derive(Deserialize, Debug)]
+struct Potion {
+    id: u16,
+    name: String,
+    effects: Vec<PotionEffect>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PotionEffect {
+    effect: String,
+    amplifier: u8,
+    duration: i32,
+}
+
+pub(crate) fn build() -> anyhow::Result<TokenStream> {
+    rerun_if_changed(["extracted/potions.json"]);
+
+    let potions = serde_json::from_str::<Vec<Potion>>(include_str!("../extracted/potions.json"))?;
+
+    let potion_count = potions.len();
+
+    let potion_from_raw_id_arms = potions
+        .iter()
+        .map(|potion| {
+            let id = &potion.id;
+            let name = ident(potion.name.to_pascal_case());
+
+            quote! {
+                #id => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let potion_to_raw_id_arms = potions
+        .iter()
+        .map(|potion| {
+            let id = &potion.id;
+            let name = ident(potion.name.to_pascal_case());
+
+            quote! {
+                Self::#name => #id,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let potion_from_ident_arms = potions
+        .iter()
+        .map(|potion| {
+            let ident_name = format!("minecraft:{}", &potion.name);
+            let name = ident(potion.name.to_pascal_case());
+
+            quote! {
+                #ident_name => Some(Self::#name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let potion_to_ident_arms = potions
+        .iter()
+        .map(|potion| {
+            let str_name = &potion.name;
+            let name = ident(str_name.to_pascal_case());
+
+            quote! {
+                Self::#name => ident!(#str_name),
+            }
+        })
+        .collect::<TokenStream>();
+
+    let potion_to_effects_arms = potions
+        .iter()
+        .map(|potion| {
+            let name = ident(potion.name.to_pascal_case());
+            let effects = potion.effects.iter().map(|effect| {
+                let effect_name = ident(effect.effect.to_pascal_case());
+                let amplifier = &effect.amplifier;
+                let duration = &effect.duration;
+
+                quote! {
+                    PotionEffect {
+                        effect: StatusEffect::#effect_name,
+                        amplifier: #amplifier,
+                        duration: #duration,
+                    }
+                }
+            });
+
+            quote! {
+                Self::#name => &[#(#effects,)*],
+            }
+        })
+        .collect::<TokenStream>();
+
+    let potion_variants = potions
+        .iter()
+        .map(|potion| ident(potion.name.to_pascal_case()))
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        use valence_ident::{Ident, ident};
+        use super::status_effects::StatusEffect;
+
+        /// A status effect given by a potion type.
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        pub struct PotionEffect {
+            /// The status effect given.
+            pub effect: StatusEffect,
+            /// The amplifier of the effect, starting at 0 for level I.
+            pub amplifier: u8,
+            /// The duration of the effect in ticks.
+            pub duration: i32,
+        }
+
+        /// Represents a potion type from the game.
+        ///
+        /// This is the base potion of potions, splash potions, lingering
+        /// potions and tipped arrows.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+        pub enum Potion {
+            #[default]
+            #(#potion_variants,)*
+        }
+
+        impl Potion {
+            /// Constructs a potion from a raw potion ID.
+            ///
+            /// If the given ID is invalid, `None` is returned.
+            pub const fn from_raw(id: u16) -> Option<Self> {
+                match id {
+                    #potion_from_raw_id_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the raw potion ID from the potion.
+            pub const fn to_raw(self) -> u16 {
+                match self {
+                    #potion_to_raw_id_arms
+                }
+            }
+
+            /// Construct a potion from its identifier.
+            ///
+            /// Returns `None` if the identifier is invalid.
+            pub fn from_ident(id: Ident<&str>) -> Option<Self> {
+                match id.as_str() {
+                    #potion_from_ident_arms
+                    _ => None
+                }
+            }
+
+            /// Gets the identifier of this potion.
+            pub const fn to_ident(self) -> Ident<&'static str> {
+                match self {
+                    #potion_to_ident_arms
+                }
+            }
+
+            /// Gets the status effects given by this potion.
+            pub const fn effects(self) -> &'static [PotionEffect] {
+                match self {
+                    #potion_to_effects_arms
+                }
+            }
+
+            /// An array of all potions.
+            pub const ALL: [Self; #potion_count] = [#(Self::#potion_variants,)*];
+        }
+    })
+}
@@ -26,3 +26,7 @@ pub mod chunk_view {
 pub mod status_effects {
     include!(concat!(env!("OUT_DIR"), "/status_effects.rs"));
 }
+
+pub mod potions {
+    include!(concat!(env!("OUT_DIR"), "/potions.rs"));
+}
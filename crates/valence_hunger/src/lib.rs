@@ -0,0 +1,345 @@
+#![doc = include_str!("../README.md")]
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use derive_more::{Deref, DerefMut};
+use valence_inventory::item_use::{ItemUseFinishedEvent, ItemUseKind};
+use valence_inventory::{HeldItem, Inventory};
+use valence_server::action::{DiggingEvent, DiggingState};
+use valence_server::client::{Client, SpawnClientsSet, UpdateClientsSet};
+use valence_server::entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_server::entity::entity::Flags;
+use valence_server::entity::living::Health;
+use valence_server::entity::player::{Food, Saturation};
+use valence_server::entity::{EntityLayerId, Position};
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::game_rules::GameRules;
+use valence_server::interact_entity::{EntityInteraction, InteractEntityEvent};
+use valence_server::movement::MovementEvent;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::{Difficulty, GameMode, ItemKind, ItemStack, Server};
+
+/// The maximum food level of a player.
+pub const MAX_FOOD: i32 = 20;
+/// The amount of exhaustion that removes one point of saturation or food.
+pub const EXHAUSTION_PER_FOOD_POINT: f32 = 4.0;
+/// The maximum amount of exhaustion a player can accumulate.
+pub const MAX_EXHAUSTION: f32 = 40.0;
+
+pub struct HungerPlugin;
+
+impl Plugin for HungerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HungerSettings>()
+            .add_systems(PreUpdate, init_hunger.after(SpawnClientsSet))
+            .add_systems(
+                EventLoopUpdate,
+                (
+                    exhaust_on_movement,
+                    exhaust_on_attack,
+                    exhaust_on_block_break,
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (eat_food, tick_hunger).chain().before(UpdateClientsSet),
+            );
+    }
+}
+
+/// Global configuration for the hunger simulation.
+#[derive(Resource, Clone, Debug)]
+pub struct HungerSettings {
+    /// Controls starvation damage and whether food is drained at all. Players
+    /// on [`Difficulty::Peaceful`] slowly regain food and health instead.
+    pub difficulty: Difficulty,
+}
+
+impl Default for HungerSettings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::Normal,
+        }
+    }
+}
+
+/// The exhaustion level of a player. Once this reaches
+/// [`EXHAUSTION_PER_FOOD_POINT`], one point of saturation is removed, or one
+/// point of food if the player has no saturation left.
+#[derive(Component, Copy, Clone, PartialEq, PartialOrd, Default, Debug, Deref, DerefMut)]
+pub struct Exhaustion(pub f32);
+
+impl Exhaustion {
+    /// Adds `amount` exhaustion, clamping the result to [`MAX_EXHAUSTION`].
+    pub fn add(&mut self, amount: f32) {
+        self.0 = (self.0 + amount).min(MAX_EXHAUSTION);
+    }
+}
+
+/// Counts ticks between health regeneration and starvation damage.
+#[derive(Component, Copy, Clone, Default, Debug)]
+struct FoodTickTimer(u32);
+
+fn init_hunger(
+    clients: Query<Entity, (Added<Client>, Without<Exhaustion>)>,
+    mut commands: Commands,
+) {
+    for entity in &clients {
+        commands
+            .entity(entity)
+            .insert((Exhaustion::default(), FoodTickTimer::default()));
+    }
+}
+
+/// Players in creative and spectator mode don't get hungry.
+fn is_exhaustible(game_mode: GameMode) -> bool {
+    matches!(game_mode, GameMode::Survival | GameMode::Adventure)
+}
+
+fn exhaust_on_movement(
+    mut events: EventReader<MovementEvent>,
+    mut clients: Query<(&mut Exhaustion, &Flags, &GameMode)>,
+) {
+    for event in events.read() {
+        let Ok((mut exhaustion, flags, game_mode)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if !is_exhaustible(*game_mode) {
+            continue;
+        }
+
+        let delta = event.position - event.old_position;
+
+        if flags.swimming() {
+            exhaustion.add(0.01 * delta.length() as f32);
+        } else if flags.sprinting() && event.on_ground {
+            exhaustion.add(0.1 * delta.x.hypot(delta.z) as f32);
+        }
+
+        if event.old_on_ground && !event.on_ground && delta.y > 0.0 {
+            // Jumping.
+            exhaustion.add(if flags.sprinting() { 0.2 } else { 0.05 });
+        }
+    }
+}
+
+fn exhaust_on_attack(
+    mut events: EventReader<InteractEntityEvent>,
+    mut clients: Query<(&mut Exhaustion, &GameMode)>,
+) {
+    for event in events.read() {
+        if event.interact != EntityInteraction::Attack {
+            continue;
+        }
+
+        if let Ok((mut exhaustion, game_mode)) = clients.get_mut(event.client) {
+            if is_exhaustible(*game_mode) {
+                exhaustion.add(0.1);
+            }
+        }
+    }
+}
+
+fn exhaust_on_block_break(
+    mut events: EventReader<DiggingEvent>,
+    mut clients: Query<(&mut Exhaustion, &GameMode)>,
+) {
+    for event in events.read() {
+        if event.state != DiggingState::Stop {
+            continue;
+        }
+
+        if let Ok((mut exhaustion, game_mode)) = clients.get_mut(event.client) {
+            if is_exhaustible(*game_mode) {
+                exhaustion.add(0.005);
+            }
+        }
+    }
+}
+
+/// Returns the item left behind after eating or drinking `item`.
+fn food_remainder(item: ItemKind) -> Option<ItemKind> {
+    match item {
+        ItemKind::MushroomStew
+        | ItemKind::RabbitStew
+        | ItemKind::BeetrootSoup
+        | ItemKind::SuspiciousStew => Some(ItemKind::Bowl),
+        ItemKind::HoneyBottle => Some(ItemKind::GlassBottle),
+        _ => None,
+    }
+}
+
+fn eat_food(
+    mut events: EventReader<ItemUseFinishedEvent>,
+    mut clients: Query<(
+        &mut Food,
+        &mut Saturation,
+        &mut Inventory,
+        &HeldItem,
+        &GameMode,
+        &Position,
+        &mut Client,
+    )>,
+) {
+    for event in events.read() {
+        if !matches!(event.kind, ItemUseKind::Eat | ItemUseKind::Drink) {
+            continue;
+        }
+
+        let Some(food_component) = event.item.food_component() else {
+            continue;
+        };
+
+        let Ok((mut food, mut saturation, mut inventory, held_item, game_mode, pos, mut client)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        let slot = held_item.hand_slot(event.hand);
+
+        let stack = inventory.slot(slot);
+
+        if stack.item != event.item || stack.is_empty() {
+            // The item is no longer in the player's hand.
+            continue;
+        }
+
+        let hunger = i32::from(food_component.hunger);
+
+        food.0 = (food.0 + hunger).min(MAX_FOOD);
+        saturation.0 =
+            (saturation.0 + hunger as f32 * food_component.saturation * 2.0).min(food.0 as f32);
+
+        if *game_mode != GameMode::Creative {
+            let count = stack.count;
+
+            if count > 1 {
+                inventory.set_slot_amount(slot, count - 1);
+            } else {
+                inventory.set_slot(
+                    slot,
+                    food_remainder(event.item)
+                        .map_or(ItemStack::EMPTY, |item| ItemStack::new(item, 1, None)),
+                );
+            }
+        }
+
+        if event.kind == ItemUseKind::Eat {
+            client.play_sound(
+                Sound::EntityPlayerBurp,
+                SoundCategory::Player,
+                pos.0,
+                0.5,
+                1.0,
+            );
+        }
+    }
+}
+
+fn tick_hunger(
+    mut clients: Query<(
+        &mut Food,
+        &mut Saturation,
+        &mut Exhaustion,
+        &mut FoodTickTimer,
+        &mut Health,
+        &GameMode,
+        &EntityLayerId,
+        Option<&EntityAttributes>,
+    )>,
+    layers: Query<&GameRules>,
+    settings: Res<HungerSettings>,
+    server: Res<Server>,
+) {
+    let difficulty = settings.difficulty;
+    let tick = server.current_tick();
+
+    for (
+        mut food,
+        mut saturation,
+        mut exhaustion,
+        mut timer,
+        mut health,
+        game_mode,
+        layer,
+        attributes,
+    ) in &mut clients
+    {
+        if health.0 <= 0.0 {
+            // Dead players don't get hungry.
+            continue;
+        }
+
+        let max_health = attributes
+            .and_then(|attrs| attrs.get_compute_value(EntityAttribute::GenericMaxHealth))
+            .unwrap_or(20.0) as f32;
+
+        let can_heal = health.0 < max_health;
+
+        let natural_regeneration = layers
+            .get(layer.0)
+            .copied()
+            .unwrap_or_default()
+            .natural_regeneration;
+
+        if exhaustion.0 > EXHAUSTION_PER_FOOD_POINT {
+            exhaustion.0 -= EXHAUSTION_PER_FOOD_POINT;
+
+            if saturation.0 > 0.0 {
+                saturation.0 = (saturation.0 - 1.0).max(0.0);
+            } else if difficulty != Difficulty::Peaceful {
+                food.0 = (food.0 - 1).max(0);
+            }
+        }
+
+        if difficulty == Difficulty::Peaceful && natural_regeneration {
+            if can_heal && tick % 20 == 0 {
+                health.0 = (health.0 + 1.0).min(max_health);
+            }
+
+            if food.0 < MAX_FOOD && tick % 10 == 0 {
+                food.0 += 1;
+            }
+        }
+
+        if natural_regeneration && saturation.0 > 0.0 && food.0 >= MAX_FOOD && can_heal {
+            timer.0 += 1;
+
+            if timer.0 >= 10 {
+                let amount = saturation.0.min(6.0);
+                health.0 = (health.0 + amount / 6.0).min(max_health);
+                exhaustion.add(amount);
+                timer.0 = 0;
+            }
+        } else if natural_regeneration && food.0 >= 18 && can_heal {
+            timer.0 += 1;
+
+            if timer.0 >= 80 {
+                health.0 = (health.0 + 1.0).min(max_health);
+                exhaustion.add(6.0);
+                timer.0 = 0;
+            }
+        } else if food.0 <= 0 {
+            timer.0 += 1;
+
+            if timer.0 >= 80 {
+                let starves = match difficulty {
+                    Difficulty::Peaceful => false,
+                    Difficulty::Easy => health.0 > 10.0,
+                    Difficulty::Normal => health.0 > 1.0,
+                    Difficulty::Hard => true,
+                };
+
+                if starves && is_exhaustible(*game_mode) {
+                    health.0 -= 1.0;
+                }
+
+                timer.0 = 0;
+            }
+        } else {
+            timer.0 = 0;
+        }
+    }
+}
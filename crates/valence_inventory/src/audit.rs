@@ -0,0 +1,104 @@
+//! Auditing the items moving in and out of inventories.
+//!
+//! Every change to a slot of an [`Inventory`] is reported with a
+//! [`SlotChangeEvent`], whether it was made by a client clicking around or by
+//! the server. Economy and anti-dupe systems can follow the flow of items
+//! with these events instead of diffing whole inventories every tick.
+//!
+//! Changes made by the server are reported once per tick, so a slot changed
+//! several times in a tick is reported as a single change from its first to
+//! its last stack. Slots changed back to their original stack aren't
+//! reported.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::{Client, FlushPacketsSet};
+use valence_server::ItemStack;
+
+use crate::Inventory;
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<SlotChangeEvent>().add_systems(
+        PostUpdate,
+        audit_server_slot_changes.before(FlushPacketsSet),
+    );
+}
+
+/// Sent when the stack in a slot of an [`Inventory`] changes.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct SlotChangeEvent {
+    /// The client which made the change, or the client owning the inventory
+    /// for changes made by the server to a player inventory.
+    pub client: Option<Entity>,
+    /// The entity of the inventory. For player inventories, this is the
+    /// client.
+    pub window: Entity,
+    /// The index of the slot in the inventory.
+    pub slot: u16,
+    pub old: ItemStack,
+    pub new: ItemStack,
+    pub cause: SlotChangeCause,
+}
+
+/// What changed the stack in a slot.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SlotChangeCause {
+    /// A client clicked a slot, or dropped an item from its inventory with the
+    /// drop key while the inventory was open.
+    Click,
+    /// A client in creative mode set a slot of its inventory.
+    Creative,
+    /// A client dropped or swapped its held item outside of the inventory
+    /// screen.
+    PlayerAction,
+    /// The server changed the slot.
+    Server,
+}
+
+/// Sends the changes recorded in `inventory` since they were last sent.
+pub(crate) fn send_slot_changes(
+    inventory: &mut Mut<Inventory>,
+    window: Entity,
+    client: Option<Entity>,
+    cause: SlotChangeCause,
+    events: &mut EventWriter<SlotChangeEvent>,
+) {
+    if inventory.audit_log.is_empty() {
+        return;
+    }
+
+    // The changes themselves have already been detected.
+    let inventory = inventory.bypass_change_detection();
+
+    for (slot, old) in inventory.audit_log.drain(..) {
+        let new = &inventory.slots[slot as usize];
+
+        if old != *new {
+            events.send(SlotChangeEvent {
+                client,
+                window,
+                slot,
+                old,
+                new: new.clone(),
+                cause,
+            });
+        }
+    }
+
+    inventory.audited = 0;
+}
+
+fn audit_server_slot_changes(
+    mut inventories: Query<(Entity, &mut Inventory, Has<Client>)>,
+    mut events: EventWriter<SlotChangeEvent>,
+) {
+    for (entity, mut inventory, is_client) in &mut inventories {
+        send_slot_changes(
+            &mut inventory,
+            entity,
+            is_client.then_some(entity),
+            SlotChangeCause::Server,
+            &mut events,
+        );
+    }
+}
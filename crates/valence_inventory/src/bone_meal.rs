@@ -0,0 +1,95 @@
+//! Growing crops with bone meal. This lives here rather than next to the rest
+//! of the farming mechanics because it depends on the item in the client's
+//! hand.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::VisibleChunkLayer;
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::farming::{apply_bone_meal, is_mature, CropMatureEvent};
+use valence_server::interact_block::InteractBlockEvent;
+use valence_server::math::{DVec3, Vec3};
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::protocol::Particle;
+use valence_server::{ChunkLayer, GameMode, ItemKind, ItemStack};
+
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<CropMatureEvent>()
+        .add_systems(EventLoopUpdate, handle_bone_meal);
+}
+
+fn handle_bone_meal(
+    mut events: EventReader<InteractBlockEvent>,
+    mut clients: Query<(&mut Inventory, &HeldItem, &GameMode, &VisibleChunkLayer)>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut mature_events: EventWriter<CropMatureEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut inventory, held_item, game_mode, layer)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if matches!(game_mode, GameMode::Spectator | GameMode::Adventure) {
+            continue;
+        }
+
+        let slot = held_item.hand_slot(event.hand);
+
+        if inventory.slot(slot).item != ItemKind::BoneMeal {
+            continue;
+        }
+
+        let layer_entity = layer.0;
+
+        let Ok(mut layer) = layers.get_mut(layer_entity) else {
+            continue;
+        };
+
+        let Some(state) = apply_bone_meal(&mut layer, event.position) else {
+            continue;
+        };
+
+        let center = DVec3::new(
+            f64::from(event.position.x) + 0.5,
+            f64::from(event.position.y) + 0.5,
+            f64::from(event.position.z) + 0.5,
+        );
+
+        layer.play_particle(
+            &Particle::HappyVillager,
+            false,
+            center,
+            Vec3::splat(0.25),
+            0.0,
+            15,
+        );
+
+        layer.play_sound(
+            Sound::ItemBoneMealUse,
+            SoundCategory::Block,
+            center,
+            1.0,
+            1.0,
+        );
+
+        if is_mature(state) {
+            mature_events.send(CropMatureEvent {
+                layer: layer_entity,
+                position: event.position,
+                state,
+            });
+        }
+
+        if *game_mode != GameMode::Creative {
+            let count = inventory.slot(slot).count;
+
+            if count > 1 {
+                inventory.set_slot_amount(slot, count - 1);
+            } else {
+                inventory.set_slot(slot, ItemStack::EMPTY);
+            }
+        }
+    }
+}
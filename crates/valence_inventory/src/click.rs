@@ -0,0 +1,607 @@
+//! Computes the outcome of clicks in inventory windows on the server, for
+//! [`ClickHandling::Authoritative`].
+//!
+//! [`ClickHandling::Authoritative`]: crate::ClickHandling::Authoritative
+
+use std::borrow::Cow;
+
+use valence_server::protocol::packets::play::click_slot_c2s::{ClickMode, SlotChange};
+use valence_server::protocol::packets::play::ClickSlotC2s;
+use valence_server::{ItemKind, ItemStack};
+
+use crate::player_inventory::PlayerInventory;
+use crate::{InventoryKind, InventoryWindow};
+
+/// Replaces the slot changes and carried item of a click with the ones
+/// computed by the server from the click mode and button.
+///
+/// Returns whether the client's prediction was wrong, in which case the client
+/// needs to be resynced. Clicks which can't be computed, such as clicks in the
+/// windows of furnaces or anvils, are left untouched.
+pub(super) fn apply_authoritative_click(
+    pkt: &mut ClickSlotC2s,
+    window: &InventoryWindow,
+    cursor: &ItemStack,
+    drag_slots: &mut Vec<u16>,
+    creative: bool,
+) -> bool {
+    // Dragging spreads over several packets. Only the last one changes slots.
+    if pkt.mode == ClickMode::Drag {
+        match pkt.button & 3 {
+            0 => drag_slots.clear(),
+            1 => {
+                if let Ok(idx) = u16::try_from(pkt.slot_idx) {
+                    if !drag_slots.contains(&idx) {
+                        drag_slots.push(idx);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(mut sim) = ClickSimulation::new(window, cursor) else {
+        return false;
+    };
+
+    let simulated = sim.click(pkt, drag_slots, creative);
+
+    if pkt.mode == ClickMode::Drag && pkt.button & 3 == 2 {
+        drag_slots.clear();
+    }
+
+    if simulated.is_none() {
+        return false;
+    }
+
+    let slot_changes: Vec<_> = sim
+        .slots
+        .iter()
+        .enumerate()
+        .filter(|&(idx, stack)| stack != window.slot(idx as u16))
+        .map(|(idx, stack)| SlotChange {
+            idx: idx as i16,
+            stack: stack.clone(),
+        })
+        .collect();
+
+    let mispredicted = sim.cursor != pkt.carried_item
+        || pkt.slot_changes.iter().any(|change| {
+            usize::try_from(change.idx)
+                .ok()
+                .and_then(|idx| sim.slots.get(idx))
+                != Some(&change.stack)
+        })
+        || slot_changes
+            .iter()
+            .any(|change| pkt.slot_changes.iter().all(|c| c.idx != change.idx));
+
+    pkt.slot_changes = Cow::Owned(slot_changes);
+    pkt.carried_item = sim.cursor;
+
+    mispredicted
+}
+
+/// A copy of the slots of a window and the cursor item, which clicks are
+/// applied to.
+struct ClickSimulation {
+    kind: InventoryKind,
+    /// The number of slots of the open inventory, or 0 if the player's own
+    /// inventory is shown.
+    open_slot_count: u16,
+    slots: Vec<ItemStack>,
+    cursor: ItemStack,
+}
+
+impl ClickSimulation {
+    /// Returns `None` if the outcome of clicks in the window can't be
+    /// computed.
+    fn new(window: &InventoryWindow, cursor: &ItemStack) -> Option<Self> {
+        let kind = window
+            .open_inventory
+            .map_or(InventoryKind::Player, |inv| inv.kind());
+
+        if !matches!(
+            kind,
+            InventoryKind::Player
+                | InventoryKind::Generic9x1
+                | InventoryKind::Generic9x2
+                | InventoryKind::Generic9x3
+                | InventoryKind::Generic9x4
+                | InventoryKind::Generic9x5
+                | InventoryKind::Generic9x6
+                | InventoryKind::Generic3x3
+                | InventoryKind::Hopper
+                | InventoryKind::ShulkerBox
+        ) {
+            return None;
+        }
+
+        Some(Self {
+            kind,
+            open_slot_count: window.open_inventory.map_or(0, |inv| inv.slot_count()),
+            slots: (0..window.slot_count())
+                .map(|idx| window.slot(idx).clone())
+                .collect(),
+            cursor: cursor.clone(),
+        })
+    }
+
+    /// Applies the click. Returns `None` if its outcome can't be computed.
+    fn click(&mut self, pkt: &ClickSlotC2s, drag_slots: &[u16], creative: bool) -> Option<()> {
+        let slot = usize::try_from(pkt.slot_idx)
+            .ok()
+            .filter(|&idx| idx < self.slots.len());
+
+        match pkt.mode {
+            ClickMode::Click => {
+                if let Some(idx) = slot {
+                    self.pickup(idx, pkt.button == 1);
+                }
+            }
+            ClickMode::ShiftClick => self.quick_move(slot?),
+            ClickMode::Hotbar => self.swap_with_hotbar(slot?, pkt.button)?,
+            ClickMode::CreativeMiddleClick => {
+                let stack = &self.slots[slot?];
+
+                if creative && self.cursor.is_empty() && !stack.is_empty() {
+                    self.cursor = stack.clone().with_count(stack.item.max_stack());
+                }
+            }
+            // Drops are handled separately from the server's state.
+            ClickMode::DropKey => return None,
+            ClickMode::Drag => {
+                if pkt.button & 3 == 2 {
+                    self.drag(drag_slots, pkt.button >> 2, creative);
+                }
+            }
+            ClickMode::DoubleClick => {
+                if slot.is_some() {
+                    self.pickup_all();
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    /// A left or right click on a slot.
+    fn pickup(&mut self, idx: usize, right: bool) {
+        let stack = &self.slots[idx];
+
+        if stack.is_empty() {
+            if !self.cursor.is_empty() {
+                let count = if right { 1 } else { self.cursor.count };
+                self.place(idx, count);
+            }
+        } else if self.cursor.is_empty() {
+            let count = if right {
+                (stack.count + 1) / 2
+            } else {
+                stack.count
+            };
+            self.cursor = self.take(idx, count);
+        } else if self.may_place(idx, self.cursor.item) {
+            if can_stack(stack, &self.cursor) {
+                let count = if right { 1 } else { self.cursor.count };
+                self.place(idx, count);
+            } else if self.cursor.count <= self.slot_limit(idx, &self.cursor) {
+                std::mem::swap(&mut self.slots[idx], &mut self.cursor);
+            }
+        }
+    }
+
+    /// Moves up to `count` items from the cursor to the slot, if they fit.
+    fn place(&mut self, idx: usize, count: i8) {
+        if !self.may_place(idx, self.cursor.item) {
+            return;
+        }
+
+        let limit = self.slot_limit(idx, &self.cursor);
+        let stack = &mut self.slots[idx];
+        let count = count.min(limit - stack.count.max(0)).max(0);
+
+        if count == 0 {
+            return;
+        }
+
+        if stack.is_empty() {
+            *stack = self.cursor.clone().with_count(count);
+        } else {
+            stack.count += count;
+        }
+
+        self.cursor.count -= count;
+
+        if self.cursor.count == 0 {
+            self.cursor = ItemStack::EMPTY;
+        }
+    }
+
+    /// Takes up to `count` items out of the slot.
+    fn take(&mut self, idx: usize, count: i8) -> ItemStack {
+        let stack = &mut self.slots[idx];
+        let count = count.min(stack.count);
+        let taken = stack.clone().with_count(count);
+
+        stack.count -= count;
+
+        if stack.count == 0 {
+            *stack = ItemStack::EMPTY;
+        }
+
+        taken
+    }
+
+    /// A shift click, moving the stack to the other part of the window.
+    fn quick_move(&mut self, idx: usize) {
+        let stack = &self.slots[idx];
+
+        if stack.is_empty() {
+            return;
+        }
+
+        let open = usize::from(self.open_slot_count);
+
+        let (range, reverse) = if self.kind != InventoryKind::Player {
+            if idx < open {
+                (open..self.slots.len(), true)
+            } else {
+                (0..open, false)
+            }
+        } else {
+            let equipment = equipment_slot(stack.item).map(usize::from);
+
+            match idx {
+                0 => (9..45, true),
+                1..=8 => (9..45, false),
+                _ if equipment.is_some_and(|slot| slot != idx && self.slots[slot].is_empty()) => {
+                    let slot = equipment.unwrap();
+                    (slot..slot + 1, false)
+                }
+                9..=35 => (36..45, false),
+                36..=44 => (9..36, false),
+                _ => (9..45, false),
+            }
+        };
+
+        let mut stack = std::mem::take(&mut self.slots[idx]);
+        let order: Vec<_> = if reverse {
+            range.rev().collect()
+        } else {
+            range.collect()
+        };
+
+        // Fill up stacks of the same item first, then empty slots.
+        for &i in &order {
+            if stack.is_empty() {
+                break;
+            }
+
+            if can_stack(&self.slots[i], &stack) {
+                let free = self.slot_limit(i, &stack) - self.slots[i].count;
+                let count = free.min(stack.count).max(0);
+                self.slots[i].count += count;
+                stack.count -= count;
+            }
+        }
+
+        for &i in &order {
+            if stack.is_empty() {
+                break;
+            }
+
+            if self.slots[i].is_empty() && self.may_place(i, stack.item) {
+                let count = stack.count.min(self.slot_limit(i, &stack));
+                self.slots[i] = stack.clone().with_count(count);
+                stack.count -= count;
+            }
+        }
+
+        self.slots[idx] = if stack.is_empty() {
+            ItemStack::EMPTY
+        } else {
+            stack
+        };
+    }
+
+    /// Swaps the slot with a hotbar slot, or the offhand slot if `button` is
+    /// 40.
+    fn swap_with_hotbar(&mut self, idx: usize, button: i8) -> Option<()> {
+        let other = match (button, self.kind) {
+            (0..=8, InventoryKind::Player) => PlayerInventory::hotbar_to_slot(button as u8),
+            (0..=8, _) => self.open_slot_count + 27 + button as u16,
+            // The offhand slot isn't part of the windows of other inventories.
+            (40, InventoryKind::Player) => PlayerInventory::SLOT_OFFHAND,
+            _ => return None,
+        };
+        let other = usize::from(other);
+
+        let fits = |sim: &Self, from: usize, to: usize| {
+            let stack = &sim.slots[from];
+            stack.is_empty()
+                || (sim.may_place(to, stack.item) && stack.count <= sim.slot_limit(to, stack))
+        };
+
+        if fits(self, idx, other) && fits(self, other, idx) {
+            self.slots.swap(idx, other);
+        }
+
+        Some(())
+    }
+
+    /// The end of a drag, spreading the cursor over the dragged slots.
+    fn drag(&mut self, drag_slots: &[u16], kind: i8, creative: bool) {
+        if self.cursor.is_empty() || (kind == 2 && !creative) {
+            return;
+        }
+
+        let mut slots = vec![];
+
+        for idx in drag_slots.iter().map(|&idx| usize::from(idx)) {
+            if kind != 2 && i32::from(self.cursor.count) <= slots.len() as i32 {
+                break;
+            }
+
+            if idx < self.slots.len()
+                && (self.slots[idx].is_empty() || can_stack(&self.slots[idx], &self.cursor))
+                && self.may_place(idx, self.cursor.item)
+            {
+                slots.push(idx);
+            }
+        }
+
+        if slots.is_empty() {
+            return;
+        }
+
+        // Dragging over a single slot is the same as clicking it.
+        if slots.len() == 1 && kind != 2 {
+            self.pickup(slots[0], kind == 1);
+            return;
+        }
+
+        let per_slot = match kind {
+            0 => i32::from(self.cursor.count) / slots.len() as i32,
+            1 => 1,
+            _ => i32::from(self.cursor.item.max_stack()),
+        };
+        let mut remaining = i32::from(self.cursor.count);
+
+        for idx in slots {
+            let existing = i32::from(self.slots[idx].count.max(0));
+            let limit = i32::from(self.slot_limit(idx, &self.cursor));
+            let count = (per_slot + existing).min(limit).max(existing);
+
+            if kind != 2 {
+                remaining -= count - existing;
+            }
+
+            self.slots[idx] = self.cursor.clone().with_count(count as i8);
+        }
+
+        self.cursor = if remaining > 0 {
+            self.cursor.clone().with_count(remaining as i8)
+        } else {
+            ItemStack::EMPTY
+        };
+    }
+
+    /// A double click, collecting items of the same kind onto the cursor.
+    fn pickup_all(&mut self) {
+        if self.cursor.is_empty() {
+            return;
+        }
+
+        let max = self.cursor.item.max_stack();
+
+        // Full stacks are only taken once there are no partial stacks left.
+        for take_full in [false, true] {
+            for idx in 0..self.slots.len() {
+                if self.cursor.count >= max {
+                    return;
+                }
+
+                // The crafting result can't be collected.
+                if self.kind == InventoryKind::Player && idx == 0 {
+                    continue;
+                }
+
+                let stack = &self.slots[idx];
+
+                if !stack.is_empty()
+                    && can_stack(stack, &self.cursor)
+                    && (take_full || stack.count != max)
+                {
+                    let taken = self.take(idx, max - self.cursor.count);
+                    self.cursor.count += taken.count;
+                }
+            }
+        }
+    }
+
+    /// Returns whether `item` can be put in the slot.
+    fn may_place(&self, idx: usize, item: ItemKind) -> bool {
+        match self.kind {
+            InventoryKind::Player => match idx {
+                0 => false,
+                5..=8 => equipment_slot(item) == Some(idx as u16),
+                _ => true,
+            },
+            InventoryKind::ShulkerBox => {
+                idx >= usize::from(self.open_slot_count) || !item.to_str().ends_with("shulker_box")
+            }
+            _ => true,
+        }
+    }
+
+    /// The maximum number of items of `stack` the slot can hold.
+    fn slot_limit(&self, idx: usize, stack: &ItemStack) -> i8 {
+        if self.kind == InventoryKind::Player && (5..=8).contains(&idx) {
+            1
+        } else {
+            stack.item.max_stack()
+        }
+    }
+}
+
+fn can_stack(a: &ItemStack, b: &ItemStack) -> bool {
+    a.item == b.item && a.nbt == b.nbt
+}
+
+/// The slot of the player inventory that `item` is equipped in when it's
+/// shift clicked.
+fn equipment_slot(item: ItemKind) -> Option<u16> {
+    let name = item.to_str();
+
+    if name.ends_with("_helmet")
+        || name.ends_with("_head")
+        || name.ends_with("_skull")
+        || item == ItemKind::CarvedPumpkin
+    {
+        Some(PlayerInventory::SLOT_HEAD)
+    } else if name.ends_with("_chestplate") || item == ItemKind::Elytra {
+        Some(PlayerInventory::SLOT_CHEST)
+    } else if name.ends_with("_leggings") {
+        Some(PlayerInventory::SLOT_LEGS)
+    } else if name.ends_with("_boots") {
+        Some(PlayerInventory::SLOT_FEET)
+    } else if item == ItemKind::Shield {
+        Some(PlayerInventory::SLOT_OFFHAND)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_server::protocol::VarInt;
+
+    use super::*;
+    use crate::Inventory;
+
+    fn click(mode: ClickMode, slot_idx: i16, button: i8) -> ClickSlotC2s<'static> {
+        ClickSlotC2s {
+            window_id: 0,
+            state_id: VarInt(0),
+            slot_idx,
+            button,
+            mode,
+            slot_changes: Cow::Owned(vec![]),
+            carried_item: ItemStack::EMPTY,
+        }
+    }
+
+    #[test]
+    fn forged_changes_are_replaced() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+        let window = InventoryWindow::new(&player_inventory, None);
+
+        // The client claims to pick up the diamonds while keeping them in the slot.
+        let mut pkt = click(ClickMode::Click, 20, 0);
+        pkt.carried_item = ItemStack::new(ItemKind::Diamond, 10, None);
+
+        assert!(apply_authoritative_click(
+            &mut pkt,
+            &window,
+            &ItemStack::EMPTY,
+            &mut vec![],
+            false
+        ));
+        assert_eq!(pkt.slot_changes.len(), 1);
+        assert_eq!(pkt.slot_changes[0].idx, 20);
+        assert_eq!(pkt.slot_changes[0].stack, ItemStack::EMPTY);
+        assert_eq!(
+            pkt.carried_item,
+            ItemStack::new(ItemKind::Diamond, 10, None)
+        );
+    }
+
+    #[test]
+    fn right_click_splits_stack() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 5, None));
+        let window = InventoryWindow::new(&player_inventory, None);
+
+        let mut pkt = click(ClickMode::Click, 20, 1);
+        pkt.slot_changes = Cow::Owned(vec![SlotChange {
+            idx: 20,
+            stack: ItemStack::new(ItemKind::Diamond, 2, None),
+        }]);
+        pkt.carried_item = ItemStack::new(ItemKind::Diamond, 3, None);
+
+        assert!(!apply_authoritative_click(
+            &mut pkt,
+            &window,
+            &ItemStack::EMPTY,
+            &mut vec![],
+            false
+        ));
+        assert_eq!(pkt.carried_item.count, 3);
+    }
+
+    #[test]
+    fn shift_click_moves_to_player_inventory() {
+        let mut player_inventory = Inventory::new(InventoryKind::Player);
+        player_inventory.set_slot(44, ItemStack::new(ItemKind::Stone, 60, None));
+        let mut chest = Inventory::new(InventoryKind::Generic9x1);
+        chest.set_slot(0, ItemStack::new(ItemKind::Stone, 10, None));
+        let window = InventoryWindow::new(&player_inventory, Some(&chest));
+
+        let mut pkt = click(ClickMode::ShiftClick, 0, 0);
+
+        apply_authoritative_click(&mut pkt, &window, &ItemStack::EMPTY, &mut vec![], false);
+
+        let mut changes = pkt.slot_changes.to_vec();
+        changes.sort_by_key(|c| c.idx);
+
+        // The last hotbar slot is filled up first, and the rest goes in the
+        // last empty slot.
+        assert_eq!(changes.len(), 3);
+        assert_eq!((changes[0].idx, changes[0].stack.count), (0, 0));
+        assert_eq!((changes[1].idx, changes[1].stack.count), (43, 6));
+        assert_eq!((changes[2].idx, changes[2].stack.count), (44, 64));
+    }
+
+    #[test]
+    fn drag_spreads_cursor() {
+        let player_inventory = Inventory::new(InventoryKind::Player);
+        let window = InventoryWindow::new(&player_inventory, None);
+        let cursor = ItemStack::new(ItemKind::Diamond, 7, None);
+        let mut drag_slots = vec![];
+
+        for (slot_idx, button) in [(-999, 0), (9, 1), (10, 1), (11, 1)] {
+            let mut pkt = click(ClickMode::Drag, slot_idx, button);
+            pkt.carried_item = cursor.clone();
+            apply_authoritative_click(&mut pkt, &window, &cursor, &mut drag_slots, false);
+        }
+
+        let mut pkt = click(ClickMode::Drag, -999, 2);
+        apply_authoritative_click(&mut pkt, &window, &cursor, &mut drag_slots, false);
+
+        assert_eq!(pkt.slot_changes.len(), 3);
+        assert!(pkt.slot_changes.iter().all(|c| c.stack.count == 2));
+        assert_eq!(pkt.carried_item, ItemStack::new(ItemKind::Diamond, 1, None));
+        assert!(drag_slots.is_empty());
+    }
+
+    #[test]
+    fn unsupported_windows_are_untouched() {
+        let player_inventory = Inventory::new(InventoryKind::Player);
+        let furnace = Inventory::new(InventoryKind::Furnace);
+        let window = InventoryWindow::new(&player_inventory, Some(&furnace));
+
+        let mut pkt = click(ClickMode::Click, 0, 0);
+        pkt.carried_item = ItemStack::new(ItemKind::Coal, 1, None);
+
+        assert!(!apply_authoritative_click(
+            &mut pkt,
+            &window,
+            &ItemStack::EMPTY,
+            &mut vec![],
+            false
+        ));
+        assert_eq!(pkt.carried_item, ItemStack::new(ItemKind::Coal, 1, None));
+    }
+}
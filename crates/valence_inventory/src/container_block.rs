@@ -0,0 +1,182 @@
+//! Opening and closing the lids of chests and other container blocks.
+//!
+//! An [`Inventory`] with a [`ContainerBlock`] belongs to the block at that
+//! position. When the first client opens the inventory, the block is shown
+//! opening to everyone in view and its opening sound is played, and when the
+//! last one closes it, the block closes again. [`ContainerAnimations`]
+//! configures this for each kind of container.
+//!
+//! ```
+//! # use bevy_ecs::prelude::*;
+//! # use valence_inventory::container_block::ContainerBlock;
+//! # use valence_inventory::{Inventory, InventoryKind};
+//! # use valence_server::BlockPos;
+//! fn spawn_chest(mut commands: Commands, layer: Entity) {
+//!     commands.spawn((
+//!         Inventory::new(InventoryKind::Generic9x3),
+//!         ContainerBlock::new(layer, BlockPos::new(0, 64, 0)),
+//!     ));
+//! }
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::FlushPacketsSet;
+use valence_server::layer::chunk::Block;
+use valence_server::protocol::packets::play::BlockEventS2c;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::protocol::WritePacket;
+use valence_server::{BlockPos, ChunkLayer, Layer};
+
+use crate::viewers::{update_inventory_viewers, InventoryClosedEvent, InventoryOpenedEvent};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<ContainerAnimations>().add_systems(
+        PostUpdate,
+        animate_container_blocks
+            .after(update_inventory_viewers)
+            .before(FlushPacketsSet),
+    );
+}
+
+/// The block action making chests, ender chests and shulker boxes open or
+/// close. They're open while the parameter, the number of viewers in vanilla,
+/// is above zero.
+const OPEN_BLOCK_ACTION: u8 = 1;
+
+/// Links an [`Inventory`] to the container block it belongs to.
+///
+/// [`Inventory`]: crate::Inventory
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ContainerBlock {
+    /// The [`ChunkLayer`] the block is in.
+    pub layer: Entity,
+    pub position: BlockPos,
+}
+
+impl ContainerBlock {
+    pub fn new(layer: Entity, position: BlockPos) -> Self {
+        Self { layer, position }
+    }
+}
+
+/// A [`Resource`] controlling how each kind of container block reacts to
+/// being opened and closed. Everything is enabled by default, like vanilla.
+#[derive(Resource, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ContainerAnimations {
+    /// Chests and trapped chests.
+    pub chest: ContainerAnimation,
+    pub ender_chest: ContainerAnimation,
+    /// Shulker boxes of all colors.
+    pub shulker_box: ContainerAnimation,
+    /// Barrels, which are opened by changing their block state.
+    pub barrel: ContainerAnimation,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ContainerAnimation {
+    /// Whether the block is shown opening and closing.
+    pub animate: bool,
+    /// Whether the opening and closing sounds are played.
+    pub sound: bool,
+}
+
+impl Default for ContainerAnimation {
+    fn default() -> Self {
+        Self {
+            animate: true,
+            sound: true,
+        }
+    }
+}
+
+impl ContainerAnimations {
+    fn get(&self, kind: BlockKind) -> Option<(ContainerAnimation, Sound, Sound)> {
+        Some(match kind {
+            BlockKind::Chest | BlockKind::TrappedChest => {
+                (self.chest, Sound::BlockChestOpen, Sound::BlockChestClose)
+            }
+            BlockKind::EnderChest => (
+                self.ender_chest,
+                Sound::BlockEnderChestOpen,
+                Sound::BlockEnderChestClose,
+            ),
+            BlockKind::Barrel => (self.barrel, Sound::BlockBarrelOpen, Sound::BlockBarrelClose),
+            _ if kind.to_str().ends_with("shulker_box") => (
+                self.shulker_box,
+                Sound::BlockShulkerBoxOpen,
+                Sound::BlockShulkerBoxClose,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+fn animate_container_blocks(
+    mut opened_events: EventReader<InventoryOpenedEvent>,
+    mut closed_events: EventReader<InventoryClosedEvent>,
+    containers: Query<&ContainerBlock>,
+    mut layers: Query<&mut ChunkLayer>,
+    animations: Res<ContainerAnimations>,
+) {
+    let opened = opened_events.read().map(|e| (e.inventory, true));
+    let closed = closed_events.read().map(|e| (e.inventory, false));
+
+    for (inventory, open) in opened.chain(closed) {
+        let Ok(container) = containers.get(inventory) else {
+            continue;
+        };
+
+        let Ok(mut layer) = layers.get_mut(container.layer) else {
+            continue;
+        };
+
+        let pos = container.position;
+
+        let Some(block) = layer.block(pos) else {
+            continue;
+        };
+
+        let state = block.state;
+        let nbt = block.nbt.cloned();
+        let kind = state.to_kind();
+
+        let Some((animation, open_sound, close_sound)) = animations.get(kind) else {
+            continue;
+        };
+
+        if animation.animate {
+            if kind == BlockKind::Barrel {
+                let value = if open {
+                    PropValue::True
+                } else {
+                    PropValue::False
+                };
+
+                layer.set_block(pos, Block::new(state.set(PropName::Open, value), nbt));
+            } else {
+                layer.view_writer(pos).write_packet(&BlockEventS2c {
+                    position: pos,
+                    action_id: OPEN_BLOCK_ACTION,
+                    action_parameter: open.into(),
+                    block_type: kind,
+                });
+            }
+        }
+
+        if animation.sound {
+            layer.play_sound(
+                if open { open_sound } else { close_sound },
+                SoundCategory::Block,
+                [
+                    f64::from(pos.x) + 0.5,
+                    f64::from(pos.y) + 0.5,
+                    f64::from(pos.z) + 0.5,
+                ],
+                0.5,
+                valence_server::rand::random::<f32>() * 0.1 + 0.9,
+            );
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Drops and resyncs player inventories as clients die and respawn.
+
+use std::f32::consts::TAU;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::FlushPacketsSet;
+use valence_server::death::{PlayerDeathEvent, PlayerRespawnEvent};
+use valence_server::entity::item::{ItemEntityBundle, Stack};
+use valence_server::entity::{EntityLayerId, Position, Velocity};
+use valence_server::game_rules::GameRules;
+use valence_server::math::{DVec3, Vec3};
+use valence_server::rand::Rng;
+use valence_server::{GameMode, ItemStack};
+
+use crate::{CursorItem, HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    // The events are only sent if `DeathPlugin` is added.
+    app.add_event::<PlayerDeathEvent>()
+        .add_event::<PlayerRespawnEvent>()
+        .add_systems(
+            PostUpdate,
+            (drop_inventory_on_death, resync_inventory_on_respawn).before(FlushPacketsSet),
+        );
+}
+
+fn drop_inventory_on_death(
+    mut events: EventReader<PlayerDeathEvent>,
+    mut clients: Query<(&mut Inventory, &mut CursorItem, &EntityLayerId, &GameMode)>,
+    rules: Query<&GameRules>,
+    mut commands: Commands,
+) {
+    let mut rng = valence_server::rand::thread_rng();
+
+    for event in events.read() {
+        let Ok((mut inventory, mut cursor_item, layer, game_mode)) = clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if *game_mode == GameMode::Spectator
+            || rules.get(layer.0).is_ok_and(|rules| rules.keep_inventory)
+        {
+            continue;
+        }
+
+        // Slot 0 is the crafting result, which isn't a real item.
+        let mut stacks: Vec<ItemStack> = (1..inventory.slot_count())
+            .map(|idx| inventory.replace_slot(idx, ItemStack::EMPTY))
+            .collect();
+
+        if !cursor_item.0.is_empty() {
+            stacks.push(std::mem::replace(&mut cursor_item.0, ItemStack::EMPTY));
+        }
+
+        // Dropped from roughly the height of the player's hands.
+        let position = event.position + DVec3::new(0.0, 1.32, 0.0);
+
+        for stack in stacks.into_iter().filter(|stack| !stack.is_empty()) {
+            // Scatter items in random directions like vanilla. Velocity is in m/s.
+            let speed = rng.gen::<f32>() * 0.5 * 20.0;
+            let angle = rng.gen::<f32>() * TAU;
+
+            commands.spawn(ItemEntityBundle {
+                item_stack: Stack(stack),
+                layer: *layer,
+                position: Position::new(position),
+                velocity: Velocity(Vec3::new(
+                    -angle.sin() * speed,
+                    0.2 * 20.0,
+                    angle.cos() * speed,
+                )),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Respawned clients start with an empty inventory, so the whole inventory
+/// needs to be sent again.
+fn resync_inventory_on_respawn(
+    mut events: EventReader<PlayerRespawnEvent>,
+    mut clients: Query<(&mut Inventory, &mut HeldItem)>,
+) {
+    for event in events.read() {
+        if let Ok((mut inventory, mut held_item)) = clients.get_mut(event.client) {
+            inventory.changed = u64::MAX;
+            held_item.set_changed();
+        }
+    }
+}
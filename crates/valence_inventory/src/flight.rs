@@ -0,0 +1,383 @@
+//! Elytra flight, firework rocket boosts and trident riptide launches.
+//!
+//! Clients move themselves in all of these modes, so the server only decides
+//! whether they're allowed and keeps track of them:
+//!
+//! - A client starts gliding once the server sets the fall flying flag after a
+//!   [`StartFlyingWithElytraEvent`]. [`Gliding`] is present while it glides.
+//!   Gliding stops when the client lands, mounts a vehicle or takes off its
+//!   elytra.
+//! - Using a firework rocket in the air while gliding spawns a rocket attached
+//!   to the client, which boosts it for the rocket's lifetime. Rockets are
+//!   simulated by `FireworkPlugin`, so boosts are denied without it.
+//! - Releasing a trident enchanted with riptide after charging it launches the
+//!   client. [`RiptideSpin`] is present while it spins.
+//!
+//! The [`FlightRules`] resource allows or denies each of these. Denied glides
+//! and boosts never start, and denied riptide launches teleport the client
+//! back to where it released the trident.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client_command::StartFlyingWithElytraEvent;
+use valence_server::entity::entity::Flags;
+use valence_server::entity::living::LivingFlags;
+use valence_server::entity::passengers::Vehicle;
+use valence_server::entity::{EntityLayerId, OnGround, Position};
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::firework::{FireworkBundle, FireworkExplodeEvent};
+use valence_server::nbt::{List, Value};
+use valence_server::pose::UpdatePosesSet;
+use valence_server::use_item::UseItemEvent;
+use valence_server::{GameMode, Hand, ItemKind, ItemStack};
+
+use crate::item_use::{ItemUseKind, ItemUseReleasedEvent};
+use crate::player_inventory::PlayerInventory;
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<FlightRules>()
+        .add_event::<GlidingEvent>()
+        .add_event::<FireworkBoostEvent>()
+        .add_event::<RiptideEvent>()
+        .add_systems(
+            EventLoopUpdate,
+            (handle_start_gliding, handle_firework_boost, handle_riptide),
+        )
+        .add_systems(
+            PostUpdate,
+            (tick_gliding, tick_riptide).before(UpdatePosesSet),
+        );
+}
+
+/// A [`Resource`] with the movement modes clients are allowed to use. All of
+/// them are allowed by default, like vanilla.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct FlightRules {
+    /// Whether clients can glide with an elytra. Clients already gliding stop
+    /// when this is disabled.
+    pub elytra: bool,
+    /// Whether firework rockets boost gliding clients.
+    pub firework_boost: bool,
+    /// Whether tridents enchanted with riptide launch clients.
+    pub riptide: bool,
+}
+
+impl Default for FlightRules {
+    fn default() -> Self {
+        Self {
+            elytra: true,
+            firework_boost: true,
+            riptide: true,
+        }
+    }
+}
+
+/// Present on clients gliding with an elytra.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Gliding {
+    /// The number of ticks the client has been gliding for.
+    pub ticks: u32,
+}
+
+/// Present on clients spinning after a riptide launch.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RiptideSpin {
+    /// The level of the riptide enchantment.
+    pub level: u32,
+    /// The number of ticks until the spin ends.
+    pub remaining_ticks: u32,
+}
+
+impl RiptideSpin {
+    /// The number of ticks a riptide spin lasts.
+    pub const DURATION: u32 = 20;
+}
+
+/// Sent when a client starts or stops gliding.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GlidingEvent {
+    pub client: Entity,
+    pub state: GlidingState,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GlidingState {
+    Start,
+    Stop,
+}
+
+/// Sent when a gliding client is boosted by a firework rocket.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FireworkBoostEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    /// The number of ticks the boost lasts.
+    pub duration: u32,
+}
+
+/// Sent when a client is launched by a trident enchanted with riptide.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RiptideEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    /// The level of the riptide enchantment.
+    pub level: u32,
+}
+
+/// The minimum number of ticks a trident must be charged for to be released.
+const TRIDENT_CHARGE_TICKS: u32 = 10;
+
+/// Returns whether `stack` is an elytra which isn't broken.
+fn is_usable_elytra(stack: &ItemStack) -> bool {
+    if stack.item != ItemKind::Elytra {
+        return false;
+    }
+
+    let damage = match stack.nbt.as_ref().and_then(|nbt| nbt.get("Damage")) {
+        Some(Value::Int(damage)) => *damage,
+        _ => 0,
+    };
+
+    // Elytra stop working at their last point of durability instead of
+    // breaking.
+    damage < i32::from(ItemKind::Elytra.max_durability()) - 1
+}
+
+/// Returns the level of the enchantment with the ID `id` on `stack`, or `0` if
+/// it isn't enchanted with it.
+fn enchantment_level(stack: &ItemStack, id: &str) -> u32 {
+    let Some(Value::List(List::Compound(enchantments))) =
+        stack.nbt.as_ref().and_then(|nbt| nbt.get("Enchantments"))
+    else {
+        return 0;
+    };
+
+    enchantments
+        .iter()
+        .find(|ench| matches!(ench.get("id"), Some(Value::String(s)) if s == id))
+        .and_then(|ench| match ench.get("lvl") {
+            Some(Value::Short(lvl)) => Some((*lvl).max(0) as u32),
+            Some(Value::Int(lvl)) => Some((*lvl).max(0) as u32),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+fn set_fall_flying(flags: &mut Mut<Flags>, fall_flying: bool) {
+    let mut new_flags = flags.clone();
+    new_flags.set_fall_flying(fall_flying);
+
+    if **flags != new_flags {
+        **flags = new_flags;
+    } else {
+        // The client starts gliding before asking, so it's corrected by
+        // sending the flags again.
+        flags.set_changed();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_start_gliding(
+    mut events: EventReader<StartFlyingWithElytraEvent>,
+    mut clients: Query<(
+        &Inventory,
+        &GameMode,
+        &OnGround,
+        &mut Flags,
+        Has<Vehicle>,
+        Has<Gliding>,
+    )>,
+    rules: Res<FlightRules>,
+    mut gliding_events: EventWriter<GlidingEvent>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        let Ok((inventory, game_mode, on_ground, mut flags, riding, gliding)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if gliding {
+            continue;
+        }
+
+        let allowed = rules.elytra
+            && *game_mode != GameMode::Spectator
+            && !on_ground.0
+            && !riding
+            && is_usable_elytra(inventory.slot(PlayerInventory::SLOT_CHEST));
+
+        set_fall_flying(&mut flags, allowed);
+
+        if allowed {
+            commands.entity(event.client).insert(Gliding::default());
+
+            gliding_events.send(GlidingEvent {
+                client: event.client,
+                state: GlidingState::Start,
+            });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_gliding(
+    mut clients: Query<(
+        Entity,
+        &mut Gliding,
+        &Inventory,
+        &OnGround,
+        &mut Flags,
+        Has<Vehicle>,
+    )>,
+    rules: Res<FlightRules>,
+    mut gliding_events: EventWriter<GlidingEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut gliding, inventory, on_ground, mut flags, riding) in &mut clients {
+        if rules.elytra
+            && !on_ground.0
+            && !riding
+            && is_usable_elytra(inventory.slot(PlayerInventory::SLOT_CHEST))
+        {
+            gliding.ticks += 1;
+            continue;
+        }
+
+        set_fall_flying(&mut flags, false);
+
+        commands.entity(entity).remove::<Gliding>();
+
+        gliding_events.send(GlidingEvent {
+            client: entity,
+            state: GlidingState::Stop,
+        });
+    }
+}
+
+fn handle_firework_boost(
+    mut events: EventReader<UseItemEvent>,
+    mut clients: Query<
+        (
+            &mut Inventory,
+            &HeldItem,
+            &GameMode,
+            &EntityLayerId,
+            &Position,
+        ),
+        With<Gliding>,
+    >,
+    rules: Res<FlightRules>,
+    // Only present if `FireworkPlugin` is added.
+    fireworks: Option<Res<Events<FireworkExplodeEvent>>>,
+    mut boost_events: EventWriter<FireworkBoostEvent>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        // Rockets used on blocks are launched from the block instead.
+        if event.target.is_some() {
+            continue;
+        }
+
+        let Ok((mut inventory, held_item, game_mode, layer, pos)) = clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        let slot = held_item.hand_slot(event.hand);
+        let stack = inventory.slot(slot);
+
+        if stack.item != ItemKind::FireworkRocket {
+            continue;
+        }
+
+        if !rules.firework_boost || fireworks.is_none() {
+            // Resend the rocket the client expects to have used up.
+            inventory.changed |= 1 << slot;
+            continue;
+        }
+
+        let rocket = FireworkBundle::from_rocket(layer.0, pos.0, stack.clone().with_count(1))
+            .attached_to(event.client);
+        let duration = rocket.firework.remaining_ticks;
+
+        commands.spawn(rocket);
+
+        if *game_mode != GameMode::Creative {
+            let count = stack.count;
+
+            if count > 1 {
+                inventory.set_slot_amount(slot, count - 1);
+            } else {
+                inventory.set_slot(slot, ItemStack::EMPTY);
+            }
+        }
+
+        boost_events.send(FireworkBoostEvent {
+            client: event.client,
+            hand: event.hand,
+            duration,
+        });
+    }
+}
+
+fn handle_riptide(
+    mut events: EventReader<ItemUseReleasedEvent>,
+    mut clients: Query<(&Inventory, &HeldItem, &mut Position, &mut LivingFlags)>,
+    rules: Res<FlightRules>,
+    mut riptide_events: EventWriter<RiptideEvent>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        if event.kind != ItemUseKind::Spear || event.ticks < TRIDENT_CHARGE_TICKS {
+            continue;
+        }
+
+        let Ok((inventory, held_item, mut pos, mut flags)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let stack = inventory.slot(held_item.hand_slot(event.hand));
+        let level = enchantment_level(stack, "minecraft:riptide");
+
+        if stack.item != ItemKind::Trident || level == 0 {
+            continue;
+        }
+
+        if !rules.riptide {
+            // The client launches itself right away, so it's teleported back.
+            pos.set_changed();
+            continue;
+        }
+
+        flags.set_using_riptide(true);
+
+        commands.entity(event.client).insert(RiptideSpin {
+            level,
+            remaining_ticks: RiptideSpin::DURATION,
+        });
+
+        riptide_events.send(RiptideEvent {
+            client: event.client,
+            hand: event.hand,
+            level,
+        });
+    }
+}
+
+fn tick_riptide(
+    mut clients: Query<(Entity, &mut RiptideSpin, &mut LivingFlags)>,
+    mut commands: Commands,
+) {
+    for (entity, mut spin, mut flags) in &mut clients {
+        spin.remaining_ticks = spin.remaining_ticks.saturating_sub(1);
+
+        if spin.remaining_ticks == 0 {
+            flags.set_using_riptide(false);
+
+            commands.entity(entity).remove::<RiptideSpin>();
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Lighting fires, campfires and candles with flint and steel. This lives here
+//! rather than next to the rest of the fire mechanics because it depends on
+//! the item in the client's hand. Fires are only lit in layers with [`Fires`],
+//! which are added by [`FirePlugin`](valence_server::fire::FirePlugin).
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::VisibleChunkLayer;
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::fire::Fires;
+use valence_server::interact_block::InteractBlockEvent;
+use valence_server::math::DVec3;
+use valence_server::nbt::Value;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::{BlockPos, BlockState, ChunkLayer, GameMode, ItemKind, ItemStack};
+
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(EventLoopUpdate, handle_flint_and_steel);
+}
+
+/// Returns whether `state` is an unlit block that flint and steel can light.
+fn can_be_lit(state: BlockState) -> bool {
+    let lightable = match state.to_kind() {
+        BlockKind::Campfire | BlockKind::SoulCampfire => {
+            state.get(PropName::Waterlogged) != Some(PropValue::True)
+        }
+        kind => kind.to_str().ends_with("candle") || kind.to_str().ends_with("candle_cake"),
+    };
+
+    lightable && state.get(PropName::Lit) == Some(PropValue::False)
+}
+
+/// Adds a point of damage to `stack`, breaking it once it runs out of
+/// durability.
+fn damage_item(stack: &mut ItemStack) {
+    let max = i32::from(stack.item.max_durability());
+
+    let nbt = stack.nbt.get_or_insert_with(Default::default);
+
+    let damage = match nbt.get("Damage") {
+        Some(Value::Int(damage)) => *damage + 1,
+        _ => 1,
+    };
+
+    if max > 0 && damage >= max {
+        *stack = ItemStack::EMPTY;
+    } else {
+        nbt.insert("Damage", damage);
+    }
+}
+
+fn block_center(pos: BlockPos) -> DVec3 {
+    DVec3::new(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y) + 0.5,
+        f64::from(pos.z) + 0.5,
+    )
+}
+
+fn handle_flint_and_steel(
+    mut events: EventReader<InteractBlockEvent>,
+    mut clients: Query<(&mut Inventory, &HeldItem, &GameMode, &VisibleChunkLayer)>,
+    mut layers: Query<(&mut ChunkLayer, &mut Fires)>,
+) {
+    for event in events.read() {
+        let Ok((mut inventory, held_item, game_mode, layer)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if matches!(game_mode, GameMode::Spectator | GameMode::Adventure) {
+            continue;
+        }
+
+        let slot = held_item.hand_slot(event.hand);
+
+        if inventory.slot(slot).item != ItemKind::FlintAndSteel {
+            continue;
+        }
+
+        let Ok((mut layer, mut fires)) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        let Some(state) = layer.block(event.position).map(|block| block.state) else {
+            continue;
+        };
+
+        let lit_pos = if can_be_lit(state) {
+            layer.set_block(event.position, state.set(PropName::Lit, PropValue::True));
+            event.position
+        } else {
+            let target = event.position.get_in_direction(event.face);
+
+            if !layer
+                .block(target)
+                .is_some_and(|block| block.state.is_air())
+            {
+                continue;
+            }
+
+            fires.light(target);
+            target
+        };
+
+        layer.play_sound(
+            Sound::ItemFlintandsteelUse,
+            SoundCategory::Block,
+            block_center(lit_pos),
+            1.0,
+            valence_server::rand::random::<f32>() * 0.4 + 0.8,
+        );
+
+        if *game_mode != GameMode::Creative {
+            let mut stack = inventory.slot(slot).clone();
+            damage_item(&mut stack);
+            inventory.set_slot(slot, stack);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::ops::Range;
+
+use valence_server::ItemKind;
+
+/// Slots of an inventory with the [`InventoryKind::Horse`] kind.
+///
+/// Horses, donkeys, mules and llamas all share the same window. The first two
+/// slots hold the saddle and the armor, followed by the slots of the chest if
+/// the animal carries one.
+///
+/// [`InventoryKind::Horse`]: crate::InventoryKind::Horse
+pub struct HorseInventory;
+
+impl HorseInventory {
+    pub const SLOT_SADDLE: u16 = 0;
+    pub const SLOT_ARMOR: u16 = 1;
+    /// The most columns a chest can have. Llamas of the highest strength have
+    /// this many.
+    pub const MAX_CHEST_COLUMNS: u8 = 5;
+
+    /// The slots of a chest with `chest_columns` columns.
+    pub const fn slots_chest(chest_columns: u8) -> Range<u16> {
+        2..2 + 3 * chest_columns as u16
+    }
+
+    /// Whether `item` can be put in the saddle slot.
+    pub fn is_saddle(item: ItemKind) -> bool {
+        item == ItemKind::Saddle
+    }
+
+    /// Whether `item` can be put in the armor slot. Horses wear horse armor and
+    /// llamas wear carpets.
+    pub fn is_armor(item: ItemKind) -> bool {
+        matches!(
+            item,
+            ItemKind::LeatherHorseArmor
+                | ItemKind::IronHorseArmor
+                | ItemKind::GoldenHorseArmor
+                | ItemKind::DiamondHorseArmor
+                | ItemKind::WhiteCarpet
+                | ItemKind::OrangeCarpet
+                | ItemKind::MagentaCarpet
+                | ItemKind::LightBlueCarpet
+                | ItemKind::YellowCarpet
+                | ItemKind::LimeCarpet
+                | ItemKind::PinkCarpet
+                | ItemKind::GrayCarpet
+                | ItemKind::LightGrayCarpet
+                | ItemKind::CyanCarpet
+                | ItemKind::PurpleCarpet
+                | ItemKind::BlueCarpet
+                | ItemKind::BrownCarpet
+                | ItemKind::GreenCarpet
+                | ItemKind::RedCarpet
+                | ItemKind::BlackCarpet
+        )
+    }
+}
@@ -0,0 +1,369 @@
+//! Tracks items that are used over a period of time, such as food, potions,
+//! bows and shields.
+//!
+//! When a client starts using an item, the [`UsingItem`] component is inserted
+//! on the client entity and the "using item" tracked data flags are set so that
+//! other players see the animation. The component is removed once the use is
+//! finished or released.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::Client;
+use valence_server::entity::living::LivingFlags;
+use valence_server::entity::player::Food;
+use valence_server::entity::{EntityStatus, UpdateTrackedDataSet};
+use valence_server::event_loop::{EventLoopPreUpdate, PacketEvent};
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{PlayerActionC2s, PlayerInteractItemC2s};
+use valence_server::{GameMode, Hand, ItemKind, ItemStack};
+
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<ItemUseStartedEvent>()
+        .add_event::<ItemUseReleasedEvent>()
+        .add_event::<ItemUseFinishedEvent>()
+        .add_systems(EventLoopPreUpdate, handle_item_use_packets)
+        .add_systems(PostUpdate, tick_item_use.before(UpdateTrackedDataSet));
+}
+
+/// The way an item behaves while it is being used.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ItemUseKind {
+    /// Eating food.
+    Eat,
+    /// Drinking potions, milk and honey.
+    Drink,
+    /// Drawing a bow.
+    Bow,
+    /// Charging a crossbow.
+    Crossbow,
+    /// Readying a trident to throw.
+    Spear,
+    /// Blocking with a shield.
+    Block,
+    /// Looking through a spyglass.
+    Spyglass,
+    /// Playing a goat horn.
+    TootHorn,
+    /// Brushing a suspicious block.
+    Brush,
+    /// Casting or reeling in a fishing rod. Fishing rods are used instantly,
+    /// so an [`ItemUseFinishedEvent`] is sent as soon as the rod is used.
+    Fish,
+}
+
+impl ItemUseKind {
+    /// Returns how `item` is used, or `None` if the item cannot be used.
+    pub const fn from_item(item: ItemKind) -> Option<Self> {
+        match item {
+            ItemKind::Bow => Some(Self::Bow),
+            ItemKind::Crossbow => Some(Self::Crossbow),
+            ItemKind::Trident => Some(Self::Spear),
+            ItemKind::Shield => Some(Self::Block),
+            ItemKind::Spyglass => Some(Self::Spyglass),
+            ItemKind::GoatHorn => Some(Self::TootHorn),
+            ItemKind::Brush => Some(Self::Brush),
+            ItemKind::FishingRod => Some(Self::Fish),
+            ItemKind::Potion | ItemKind::MilkBucket | ItemKind::HoneyBottle => Some(Self::Drink),
+            _ if item.food_component().is_some() => Some(Self::Eat),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the number of ticks `item` needs to be used for before the use
+/// finishes.
+///
+/// Items that are used until the client releases them (such as bows and
+/// shields) return `None`. Items that cannot be used also return `None`.
+pub const fn item_use_duration(item: ItemKind) -> Option<u32> {
+    match ItemUseKind::from_item(item) {
+        Some(ItemUseKind::Eat) => match item.food_component() {
+            Some(food) if food.snack => Some(16),
+            _ => Some(32),
+        },
+        Some(ItemUseKind::Drink) => match item {
+            ItemKind::HoneyBottle => Some(40),
+            _ => Some(32),
+        },
+        Some(ItemUseKind::Crossbow) => Some(25),
+        Some(ItemUseKind::Spyglass) => Some(1200),
+        Some(ItemUseKind::TootHorn) => Some(140),
+        Some(ItemUseKind::Brush) => Some(200),
+        Some(ItemUseKind::Fish) => Some(0),
+        Some(ItemUseKind::Bow | ItemUseKind::Spear | ItemUseKind::Block) | None => None,
+    }
+}
+
+/// Present on clients that are currently using an item. Removed when the use
+/// finishes or is released.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UsingItem {
+    /// The hand holding the item.
+    pub hand: Hand,
+    /// The item being used.
+    pub item: ItemKind,
+    /// How the item is being used.
+    pub kind: ItemUseKind,
+    /// The number of ticks the item has been used for.
+    pub ticks: u32,
+}
+
+impl UsingItem {
+    /// The number of ticks the item needs to be used for before the use
+    /// finishes. `None` if the item is used until released.
+    pub fn max_ticks(&self) -> Option<u32> {
+        item_use_duration(self.item)
+    }
+
+    /// The number of ticks remaining until the use finishes.
+    pub fn remaining_ticks(&self) -> Option<u32> {
+        self.max_ticks().map(|max| max.saturating_sub(self.ticks))
+    }
+
+    /// How far along the use is in `0.0..=1.0`. Always `0.0` for items that
+    /// are used until released.
+    pub fn progress(&self) -> f32 {
+        match self.max_ticks() {
+            Some(0) => 1.0,
+            Some(max) => (self.ticks as f32 / max as f32).min(1.0),
+            None => 0.0,
+        }
+    }
+
+    /// How far a bow has been drawn in `0.0..=1.0`, using the same curve as
+    /// vanilla. Arrows fired below `0.1` are not shot at all.
+    pub fn bow_pull_progress(&self) -> f32 {
+        let secs = self.ticks as f32 / 20.0;
+        ((secs * secs + secs * 2.0) / 3.0).min(1.0)
+    }
+}
+
+/// Sent when a client starts using an item.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemUseStartedEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    pub item: ItemKind,
+    pub kind: ItemUseKind,
+}
+
+/// Sent when a client stops using an item before the use finished. This is
+/// when bows are shot, shields are lowered and eating is cancelled.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemUseReleasedEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    pub item: ItemKind,
+    pub kind: ItemUseKind,
+    /// The number of ticks the item was used for.
+    pub ticks: u32,
+}
+
+/// Sent when a client has used an item for its full duration. This is when
+/// food is eaten and potions are drunk.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemUseFinishedEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    pub item: ItemKind,
+    pub kind: ItemUseKind,
+    /// The number of ticks the item was used for.
+    pub ticks: u32,
+}
+
+fn held_stack<'a>(inventory: &'a Inventory, held_item: &HeldItem, hand: Hand) -> &'a ItemStack {
+    inventory.slot(held_item.hand_slot(hand))
+}
+
+fn has_ammo(inventory: &Inventory) -> bool {
+    inventory.slots().any(|stack| {
+        matches!(
+            stack.item,
+            ItemKind::Arrow | ItemKind::SpectralArrow | ItemKind::TippedArrow
+        ) && !stack.is_empty()
+    })
+}
+
+fn can_start_using(
+    stack: &ItemStack,
+    kind: ItemUseKind,
+    inventory: &Inventory,
+    game_mode: GameMode,
+    food: Option<&Food>,
+) -> bool {
+    match kind {
+        ItemUseKind::Eat => {
+            let Some(food_component) = stack.item.food_component() else {
+                return false;
+            };
+
+            game_mode == GameMode::Creative
+                || food_component.always_edible
+                || !food.is_some_and(|food| food.0 >= 20)
+        }
+        ItemUseKind::Bow | ItemUseKind::Crossbow => {
+            game_mode == GameMode::Creative || has_ammo(inventory)
+        }
+        _ => true,
+    }
+}
+
+fn set_using_flags(flags: &mut LivingFlags, using: Option<Hand>) {
+    let mut new_flags = flags.clone();
+    new_flags.set_using_item(using.is_some());
+    new_flags.set_off_hand_active(using == Some(Hand::Off));
+
+    if *flags != new_flags {
+        *flags = new_flags;
+    }
+}
+
+fn handle_item_use_packets(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(
+        &Inventory,
+        &HeldItem,
+        &GameMode,
+        Option<&Food>,
+        Option<&UsingItem>,
+        &mut LivingFlags,
+    )>,
+    mut started_events: EventWriter<ItemUseStartedEvent>,
+    mut released_events: EventWriter<ItemUseReleasedEvent>,
+    mut finished_events: EventWriter<ItemUseFinishedEvent>,
+    mut commands: Commands,
+) {
+    for packet in packets.read() {
+        if let Some(pkt) = packet.decode::<PlayerInteractItemC2s>() {
+            let Ok((inventory, held_item, game_mode, food, using, mut flags)) =
+                clients.get_mut(packet.client)
+            else {
+                continue;
+            };
+
+            if using.is_some() {
+                // Already using an item.
+                continue;
+            }
+
+            let stack = held_stack(inventory, held_item, pkt.hand);
+
+            if stack.is_empty() {
+                continue;
+            }
+
+            let Some(kind) = ItemUseKind::from_item(stack.item) else {
+                continue;
+            };
+
+            if !can_start_using(stack, kind, inventory, *game_mode, food) {
+                continue;
+            }
+
+            if item_use_duration(stack.item) == Some(0) {
+                // Instant use.
+                finished_events.send(ItemUseFinishedEvent {
+                    client: packet.client,
+                    hand: pkt.hand,
+                    item: stack.item,
+                    kind,
+                    ticks: 0,
+                });
+
+                continue;
+            }
+
+            set_using_flags(&mut flags, Some(pkt.hand));
+
+            commands.entity(packet.client).insert(UsingItem {
+                hand: pkt.hand,
+                item: stack.item,
+                kind,
+                ticks: 0,
+            });
+
+            started_events.send(ItemUseStartedEvent {
+                client: packet.client,
+                hand: pkt.hand,
+                item: stack.item,
+                kind,
+            });
+        } else if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
+            if pkt.action != PlayerAction::ReleaseUseItem {
+                continue;
+            }
+
+            let Ok((_, _, _, _, Some(using), mut flags)) = clients.get_mut(packet.client) else {
+                continue;
+            };
+
+            set_using_flags(&mut flags, None);
+
+            commands.entity(packet.client).remove::<UsingItem>();
+
+            released_events.send(ItemUseReleasedEvent {
+                client: packet.client,
+                hand: using.hand,
+                item: using.item,
+                kind: using.kind,
+                ticks: using.ticks,
+            });
+        }
+    }
+}
+
+fn tick_item_use(
+    mut clients: Query<(
+        Entity,
+        &mut UsingItem,
+        &Inventory,
+        &HeldItem,
+        &mut LivingFlags,
+        Option<&mut Client>,
+    )>,
+    mut released_events: EventWriter<ItemUseReleasedEvent>,
+    mut finished_events: EventWriter<ItemUseFinishedEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut using, inventory, held_item, mut flags, client) in &mut clients {
+        if held_stack(inventory, held_item, using.hand).item != using.item {
+            // The item was moved out of the client's hand.
+            set_using_flags(&mut flags, None);
+            commands.entity(entity).remove::<UsingItem>();
+
+            released_events.send(ItemUseReleasedEvent {
+                client: entity,
+                hand: using.hand,
+                item: using.item,
+                kind: using.kind,
+                ticks: using.ticks,
+            });
+
+            continue;
+        }
+
+        using.ticks += 1;
+
+        if using.remaining_ticks() == Some(0) {
+            set_using_flags(&mut flags, None);
+            commands.entity(entity).remove::<UsingItem>();
+
+            if matches!(using.kind, ItemUseKind::Eat | ItemUseKind::Drink) {
+                if let Some(mut client) = client {
+                    // Stops the eating animation on the client.
+                    client.trigger_status(EntityStatus::ConsumeItem);
+                }
+            }
+
+            finished_events.send(ItemUseFinishedEvent {
+                client: entity,
+                hand: using.hand,
+                item: using.item,
+                kind: using.kind,
+                ticks: using.ticks,
+            });
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Putting music discs in jukeboxes and taking them out again.
+//!
+//! Clients play the song themselves once they're told a jukebox started
+//! playing, so [`insert_record`] and [`eject_record`] can also be used to
+//! control jukeboxes without a client, such as for the music of a hub.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::VisibleChunkLayer;
+use valence_server::client_command::PlayerInput;
+use valence_server::entity::item::{ItemEntityBundle, Stack};
+use valence_server::entity::{EntityLayerId, Position, Velocity};
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::interact_block::InteractBlockEvent;
+use valence_server::layer::chunk::Block;
+use valence_server::math::{DVec3, Vec3};
+use valence_server::nbt::{compound, Value};
+use valence_server::protocol::packets::play::WorldEventS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::rand::Rng;
+use valence_server::{BlockPos, ChunkLayer, GameMode, Hand, ItemKind, ItemStack, Layer};
+
+use crate::player_inventory::PlayerInventory;
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<RecordPlayingEvent>()
+        .add_systems(EventLoopUpdate, handle_jukebox_interactions);
+}
+
+/// The world event making clients play the record of a jukebox. The data is
+/// the raw ID of the record.
+const PLAY_RECORD_EVENT: i32 = 1010;
+/// The world event making clients stop the song of a jukebox.
+const STOP_RECORD_EVENT: i32 = 1011;
+
+/// Sent when a client puts a music disc in a jukebox, which starts playing it.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RecordPlayingEvent {
+    pub client: Entity,
+    /// The position of the jukebox.
+    pub position: BlockPos,
+    /// The music disc being played.
+    pub record: ItemKind,
+}
+
+/// Returns whether `item` is a music disc which can be played in a jukebox.
+pub fn is_record(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::MusicDisc13
+            | ItemKind::MusicDiscCat
+            | ItemKind::MusicDiscBlocks
+            | ItemKind::MusicDiscChirp
+            | ItemKind::MusicDiscFar
+            | ItemKind::MusicDiscMall
+            | ItemKind::MusicDiscMellohi
+            | ItemKind::MusicDiscStal
+            | ItemKind::MusicDiscStrad
+            | ItemKind::MusicDiscWard
+            | ItemKind::MusicDisc11
+            | ItemKind::MusicDiscWait
+            | ItemKind::MusicDiscOtherside
+            | ItemKind::MusicDiscRelic
+            | ItemKind::MusicDisc5
+            | ItemKind::MusicDiscPigstep
+    )
+}
+
+/// Puts a single item of `record` in the empty jukebox at `pos` and starts
+/// playing it for the clients in view. Returns `false` if there's no empty
+/// jukebox at `pos` or `record` isn't a music disc.
+pub fn insert_record(layer: &mut ChunkLayer, pos: BlockPos, record: &ItemStack) -> bool {
+    let Some(block) = layer.block(pos) else {
+        return false;
+    };
+
+    if block.state.to_kind() != BlockKind::Jukebox
+        || block.state.get(PropName::HasRecord) == Some(PropValue::True)
+        || !is_record(record.item)
+    {
+        return false;
+    }
+
+    let mut nbt = block.nbt.cloned().unwrap_or_default();
+    let mut record_nbt = compound! {
+        "id" => format!("minecraft:{}", record.item.to_str()),
+        "Count" => 1_i8,
+    };
+
+    if let Some(tag) = &record.nbt {
+        record_nbt.insert("tag", tag.clone());
+    }
+
+    nbt.insert("RecordItem", record_nbt);
+
+    let state = block.state.set(PropName::HasRecord, PropValue::True);
+    layer.set_block(pos, Block::new(state, Some(nbt)));
+
+    layer.view_writer(pos).write_packet(&WorldEventS2c {
+        event: PLAY_RECORD_EVENT,
+        location: pos,
+        data: record.item.to_raw().into(),
+        disable_relative_volume: false,
+    });
+
+    true
+}
+
+/// Stops the jukebox at `pos` and takes its record out. Returns `None` if
+/// there's no jukebox playing a record at `pos`.
+pub fn eject_record(layer: &mut ChunkLayer, pos: BlockPos) -> Option<ItemStack> {
+    let block = layer.block(pos)?;
+
+    if block.state.to_kind() != BlockKind::Jukebox
+        || block.state.get(PropName::HasRecord) != Some(PropValue::True)
+    {
+        return None;
+    }
+
+    let mut nbt = block.nbt.cloned().unwrap_or_default();
+
+    let record = match nbt.remove("RecordItem") {
+        Some(Value::Compound(mut record_nbt)) => {
+            let item = match record_nbt.get("id") {
+                Some(Value::String(id)) => {
+                    ItemKind::from_str(id.strip_prefix("minecraft:").unwrap_or(id.as_str()))
+                }
+                _ => None,
+            };
+
+            let tag = match record_nbt.remove("tag") {
+                Some(Value::Compound(tag)) => Some(tag),
+                _ => None,
+            };
+
+            item.map(|item| ItemStack::new(item, 1, tag))
+        }
+        _ => None,
+    };
+
+    let state = block.state.set(PropName::HasRecord, PropValue::False);
+    layer.set_block(pos, Block::new(state, Some(nbt)));
+
+    layer.view_writer(pos).write_packet(&WorldEventS2c {
+        event: STOP_RECORD_EVENT,
+        location: pos,
+        data: 0,
+        disable_relative_volume: false,
+    });
+
+    // A jukebox without a valid record is still emptied.
+    Some(record.unwrap_or(ItemStack::EMPTY))
+}
+
+#[allow(clippy::type_complexity)]
+fn handle_jukebox_interactions(
+    mut events: EventReader<InteractBlockEvent>,
+    mut clients: Query<(
+        &mut Inventory,
+        &HeldItem,
+        &GameMode,
+        &PlayerInput,
+        &VisibleChunkLayer,
+        &EntityLayerId,
+    )>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut record_events: EventWriter<RecordPlayingEvent>,
+    mut commands: Commands,
+) {
+    let mut rng = valence_server::rand::thread_rng();
+
+    for event in events.read() {
+        let Ok((mut inventory, held_item, game_mode, input, chunk_layer, entity_layer)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(chunk_layer.0) else {
+            continue;
+        };
+
+        let Some(state) = layer.block(event.position).map(|block| block.state) else {
+            continue;
+        };
+
+        if state.to_kind() != BlockKind::Jukebox {
+            continue;
+        }
+
+        let slot = held_item.hand_slot(event.hand);
+
+        if state.get(PropName::HasRecord) == Some(PropValue::True) {
+            // Sneaking clients holding something use their item instead of the
+            // jukebox.
+            let holding_item = !inventory.slot(held_item.slot()).is_empty()
+                || !inventory.slot(PlayerInventory::SLOT_OFFHAND).is_empty();
+
+            if event.hand == Hand::Off || (input.sneaking && holding_item) {
+                continue;
+            }
+
+            let Some(record) = eject_record(&mut layer, event.position) else {
+                continue;
+            };
+
+            if record.is_empty() {
+                continue;
+            }
+
+            let position = DVec3::new(
+                f64::from(event.position.x) + 0.5 + rng.gen_range(-0.35..0.35),
+                f64::from(event.position.y) + 1.01,
+                f64::from(event.position.z) + 0.5 + rng.gen_range(-0.35..0.35),
+            );
+
+            commands.spawn(ItemEntityBundle {
+                item_stack: Stack(record),
+                layer: *entity_layer,
+                position: Position::new(position),
+                velocity: Velocity(Vec3::new(0.0, 0.1 * 20.0, 0.0)),
+                ..Default::default()
+            });
+        } else {
+            let stack = inventory.slot(slot);
+
+            if !insert_record(&mut layer, event.position, stack) {
+                continue;
+            }
+
+            let record = stack.item;
+            let count = stack.count;
+
+            // Like vanilla, records are used up even in creative mode.
+            if count > 1 {
+                inventory.set_slot_amount(slot, count - 1);
+            } else {
+                inventory.set_slot(slot, ItemStack::EMPTY);
+            }
+
+            record_events.send(RecordPlayingEvent {
+                client: event.client,
+                position: event.position,
+                record,
+            });
+        }
+    }
+}
@@ -5,12 +5,14 @@ use std::iter::FusedIterator;
 use std::num::Wrapping;
 use std::ops::Range;
 
+use audit::{SlotChangeCause, SlotChangeEvent};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use derive_more::{Deref, DerefMut};
 use player_inventory::PlayerInventory;
 use tracing::{debug, warn};
 use valence_server::client::{Client, FlushPacketsSet, SpawnClientsSet};
+use valence_server::entity::EntityId;
 use valence_server::event_loop::{EventLoopPreUpdate, PacketEvent};
 use valence_server::interact_block::InteractBlockEvent;
 pub use valence_server::protocol::packets::play::click_slot_c2s::{ClickMode, SlotChange};
@@ -18,15 +20,27 @@ use valence_server::protocol::packets::play::open_screen_s2c::WindowType;
 pub use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
 use valence_server::protocol::packets::play::{
     ClickSlotC2s, CloseHandledScreenC2s, CloseScreenS2c, CreativeInventoryActionC2s, InventoryS2c,
-    OpenScreenS2c, PlayerActionC2s, ScreenHandlerSlotUpdateS2c, UpdateSelectedSlotC2s,
-    UpdateSelectedSlotS2c,
+    OpenHorseScreenS2c, OpenScreenS2c, PlayerActionC2s, ScreenHandlerSlotUpdateS2c,
+    UpdateSelectedSlotC2s, UpdateSelectedSlotS2c,
 };
 use valence_server::protocol::{VarInt, WritePacket};
 use valence_server::text::IntoText;
 use valence_server::{GameMode, Hand, ItemKind, ItemStack, Text};
 
+pub mod audit;
+mod bone_meal;
+mod click;
+pub mod container_block;
+mod death;
+pub mod flight;
+mod flint_and_steel;
+pub mod horse_inventory;
+pub mod item_use;
+pub mod jukebox;
 pub mod player_inventory;
+pub mod respawn_anchor;
 mod validate;
+pub mod viewers;
 
 pub struct InventoryPlugin;
 
@@ -63,6 +77,17 @@ impl Plugin for InventoryPlugin {
         .add_event::<DropItemStackEvent>()
         .add_event::<CreativeInventoryActionEvent>()
         .add_event::<UpdateSelectedSlotEvent>();
+
+        audit::build(app);
+        bone_meal::build(app);
+        container_block::build(app);
+        death::build(app);
+        flight::build(app);
+        flint_and_steel::build(app);
+        item_use::build(app);
+        jukebox::build(app);
+        respawn_anchor::build(app);
+        viewers::build(app);
     }
 }
 
@@ -74,6 +99,19 @@ pub struct Inventory {
     /// Contains a set bit for each modified slot in `slots`.
     #[doc(hidden)]
     pub changed: u64,
+    /// The stacks of the slots modified since they were last audited, from
+    /// before they were first modified.
+    audit_log: Vec<(u16, ItemStack)>,
+    /// Contains a set bit for each slot in `audit_log`.
+    audited: u64,
+    /// The clients viewing this inventory, in the order they opened it.
+    viewers: Vec<Entity>,
+    /// Titles shown to specific viewers instead of `title`.
+    title_overrides: Vec<(Entity, Text)>,
+    /// Whether `title` changed since it was last sent to the viewers.
+    title_changed: bool,
+    /// The viewers whose title override changed since it was last sent.
+    viewer_titles_changed: Vec<Entity>,
     /// Makes an inventory read-only for clients. This will prevent adding
     /// or removing items. If this is a player inventory
     /// This will also make it impossible to drop items while not
@@ -93,6 +131,12 @@ impl Inventory {
             kind,
             slots: vec![ItemStack::EMPTY; kind.slot_count()].into(),
             changed: 0,
+            audit_log: vec![],
+            audited: 0,
+            viewers: vec![],
+            title_overrides: vec![],
+            title_changed: false,
+            viewer_titles_changed: vec![],
             readonly: false,
         }
     }
@@ -140,13 +184,13 @@ impl Inventory {
         assert!(idx < self.slot_count(), "slot index of {idx} out of bounds");
 
         let new = item.into();
-        let old = &mut self.slots[idx as usize];
 
-        if new != *old {
+        if new != self.slots[idx as usize] {
+            self.record_audit(idx);
             self.changed |= 1 << idx;
         }
 
-        std::mem::replace(old, new)
+        std::mem::replace(&mut self.slots[idx as usize], new)
     }
 
     /// Swap the contents of two slots. If the slots are the same, nothing
@@ -177,6 +221,9 @@ impl Inventory {
             return;
         }
 
+        self.record_audit(idx_a);
+        self.record_audit(idx_b);
+
         self.changed |= 1 << idx_a;
         self.changed |= 1 << idx_b;
 
@@ -199,17 +246,27 @@ impl Inventory {
     pub fn set_slot_amount(&mut self, idx: u16, amount: i8) {
         assert!(idx < self.slot_count(), "slot index out of range");
 
-        let item = &mut self.slots[idx as usize];
+        let item = &self.slots[idx as usize];
 
         if !item.is_empty() {
             if item.count == amount {
                 return;
             }
-            item.count = amount;
+            self.record_audit(idx);
+            self.slots[idx as usize].count = amount;
             self.changed |= 1 << idx;
         }
     }
 
+    /// Remembers the stack in the slot before its first change since the
+    /// last [`SlotChangeEvent`]s were sent.
+    fn record_audit(&mut self, idx: u16) {
+        if self.audited & (1 << idx) == 0 {
+            self.audited |= 1 << idx;
+            self.audit_log.push((idx, self.slots[idx as usize].clone()));
+        }
+    }
+
     pub fn slot_count(&self) -> u16 {
         self.slots.len() as u16
     }
@@ -254,12 +311,75 @@ impl Inventory {
 
     /// Replace the text displayed on the inventory's title bar, and returns the
     /// old text.
+    ///
+    /// Clients already viewing the inventory have it reopened with the new
+    /// title, unless they have their own title set with
+    /// [`Inventory::set_viewer_title`].
     #[must_use]
     pub fn replace_title<'a, T: IntoText<'a>>(&mut self, title: T) -> Text {
-        // TODO: set title modified flag
+        self.title_changed = true;
         std::mem::replace(&mut self.title, title.into_cow_text().into_owned())
     }
 
+    /// The clients viewing this inventory, in the order they opened it.
+    ///
+    /// This is updated in [`PostUpdate`], so clients which opened or closed the
+    /// inventory in this tick may not be reflected yet.
+    pub fn viewers(&self) -> &[Entity] {
+        &self.viewers
+    }
+
+    /// The title shown to `viewer`: its own title if it has one, or the title
+    /// of the inventory otherwise.
+    pub fn viewer_title(&self, viewer: Entity) -> &Text {
+        self.title_overrides
+            .iter()
+            .find(|(e, _)| *e == viewer)
+            .map_or(&self.title, |(_, title)| title)
+    }
+
+    /// Shows `title` to `viewer` instead of the title of the inventory, until
+    /// the viewer closes the inventory or the title is removed with
+    /// [`Inventory::remove_viewer_title`]. The title can be set before the
+    /// client opens the inventory.
+    ///
+    /// ```
+    /// # use bevy_ecs::entity::Entity;
+    /// # use valence_inventory::*;
+    /// # use valence_server::text::Text;
+    /// let mut inv = Inventory::with_title(InventoryKind::Generic9x3, "Shop");
+    /// let viewer = Entity::from_raw(1);
+    ///
+    /// inv.set_viewer_title(viewer, "Shop (5 coins)");
+    ///
+    /// assert_eq!(inv.viewer_title(viewer), &Text::from("Shop (5 coins)"));
+    /// assert_eq!(inv.viewer_title(Entity::from_raw(2)), &Text::from("Shop"));
+    /// ```
+    pub fn set_viewer_title<'a, T: IntoText<'a>>(&mut self, viewer: Entity, title: T) {
+        let title = title.into_cow_text().into_owned();
+
+        match self.title_overrides.iter_mut().find(|(e, _)| *e == viewer) {
+            Some((_, old)) if *old == title => return,
+            Some((_, old)) => *old = title,
+            None => self.title_overrides.push((viewer, title)),
+        }
+
+        self.viewer_titles_changed.push(viewer);
+    }
+
+    /// Removes the title shown to `viewer` with
+    /// [`Inventory::set_viewer_title`], and returns it.
+    pub fn remove_viewer_title(&mut self, viewer: Entity) -> Option<Text> {
+        let idx = self
+            .title_overrides
+            .iter()
+            .position(|(e, _)| *e == viewer)?;
+
+        self.viewer_titles_changed.push(viewer);
+
+        Some(self.title_overrides.swap_remove(idx).1)
+    }
+
     pub(crate) fn slot_slice(&self) -> &[ItemStack] {
         &self.slots
     }
@@ -372,6 +492,8 @@ pub struct ClientInventoryState {
     /// This is so we can inform the user of the update through change detection
     /// when they differ in a given tick
     client_updated_cursor_item: Option<ItemStack>,
+    /// The slots the client has dragged items over so far.
+    drag_slots: Vec<u16>,
 }
 
 impl ClientInventoryState {
@@ -403,8 +525,22 @@ impl HeldItem {
         PlayerInventory::slot_to_hotbar(self.held_item_slot)
     }
 
+    /// Returns the slot of the item in `hand`: the held slot for the main
+    /// hand, or [`PlayerInventory::SLOT_OFFHAND`].
+    pub fn hand_slot(&self, hand: Hand) -> u16 {
+        match hand {
+            Hand::Main => self.held_item_slot,
+            Hand::Off => PlayerInventory::SLOT_OFFHAND,
+        }
+    }
+
+    /// Sets the held slot. The client is told about the change at the end of
+    /// the tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot` isn't a hotbar slot.
     pub fn set_slot(&mut self, slot: u16) {
-        // temp
         assert!(
             PlayerInventory::SLOTS_HOTBAR.contains(&slot),
             "slot index of {slot} out of bounds"
@@ -418,6 +554,52 @@ impl HeldItem {
     }
 }
 
+/// Controls how changes to the [`HeldItem`] made by the client are handled.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct HeldItemSync {
+    pub mode: HeldItemSyncMode,
+    /// The hotbar index the client was told to select and hasn't reported
+    /// back yet.
+    awaiting_ack: Option<u8>,
+    /// The hotbar index the client has selected as far as the server knows.
+    /// The client only reports a slot it was told to select if it differs from
+    /// this.
+    client_slot: u8,
+}
+
+impl HeldItemSync {
+    pub fn new(mode: HeldItemSyncMode) -> Self {
+        Self {
+            mode,
+            awaiting_ack: None,
+            client_slot: 0,
+        }
+    }
+
+    /// Returns whether the server changed the held slot and the client hasn't
+    /// reported selecting it yet. Only tracked in
+    /// [`HeldItemSyncMode::Acknowledged`].
+    pub fn is_awaiting_ack(&self) -> bool {
+        self.awaiting_ack.is_some()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum HeldItemSyncMode {
+    /// Slot changes from the client are always accepted.
+    #[default]
+    Free,
+    /// After the server changes the held slot, slot changes from the client
+    /// are ignored until the client reports selecting the server's slot. This
+    /// keeps changes the client made before it was told about the server's
+    /// slot from overwriting it. Ignored changes are answered with the
+    /// server's slot again, so the client switches back.
+    Acknowledged,
+    /// Slot changes from the client are rejected, and the client is sent the
+    /// server's slot again. The server can still change the held slot.
+    Locked,
+}
+
 /// The item stack that the client thinks it's holding under the mouse
 /// cursor.
 #[derive(Component, Clone, PartialEq, Default, Debug, Deref, DerefMut)]
@@ -591,11 +773,13 @@ fn init_new_client_inventories(clients: Query<Entity, Added<Client>>, mut comman
                 state_id: Wrapping(0),
                 slots_changed: 0,
                 client_updated_cursor_item: None,
+                drag_slots: vec![],
             },
             HeldItem {
                 // First slot of the hotbar.
                 held_item_slot: 36,
             },
+            HeldItemSync::default(),
         ));
     }
 }
@@ -672,8 +856,9 @@ fn update_open_inventories(
         &CursorItem,
         &mut OpenInventory,
     )>,
-    mut inventories: Query<&mut Inventory>,
+    mut inventories: Query<(&mut Inventory, Option<&EntityId>)>,
     mut commands: Commands,
+    mut viewed: Local<Vec<Entity>>,
 ) {
     // These operations need to happen in this order.
 
@@ -681,7 +866,7 @@ fn update_open_inventories(
     for (client_entity, mut client, mut inv_state, cursor_item, mut open_inventory) in &mut clients
     {
         // Validate that the inventory exists.
-        let Ok([inventory, player_inventory]) =
+        let Ok([(inventory, entity_id), (player_inventory, _)]) =
             inventories.get_many_mut([open_inventory.entity, client_entity])
         else {
             // The inventory no longer exists, so close the inventory.
@@ -699,11 +884,31 @@ fn update_open_inventories(
             inv_state.window_id = inv_state.window_id % 100 + 1;
             open_inventory.client_changed = 0;
 
-            client.write_packet(&OpenScreenS2c {
-                window_id: VarInt(inv_state.window_id.into()),
-                window_type: WindowType::from(inventory.kind),
-                window_title: Cow::Borrowed(&inventory.title),
-            });
+            if let InventoryKind::Horse { .. } = inventory.kind {
+                // Horse screens are opened for the animal the inventory belongs to.
+                let Some(entity_id) = entity_id else {
+                    warn!(
+                        "horse inventory {:?} has no entity ID, closing it",
+                        open_inventory.entity
+                    );
+
+                    commands.entity(client_entity).remove::<OpenInventory>();
+
+                    continue;
+                };
+
+                client.write_packet(&OpenHorseScreenS2c {
+                    window_id: inv_state.window_id,
+                    slot_count: VarInt(inventory.slot_count() as i32),
+                    entity_id: entity_id.get(),
+                });
+            } else {
+                client.write_packet(&OpenScreenS2c {
+                    window_id: VarInt(inv_state.window_id.into()),
+                    window_type: WindowType::from(inventory.kind),
+                    window_title: Cow::Borrowed(inventory.viewer_title(client_entity)),
+                });
+            }
 
             client.write_packet(&InventoryS2c {
                 window_id: inv_state.window_id,
@@ -714,7 +919,26 @@ fn update_open_inventories(
         } else {
             // The client is already viewing the inventory.
 
-            if inventory.changed == u64::MAX {
+            let has_own_title = inventory
+                .title_overrides
+                .iter()
+                .any(|(viewer, _)| *viewer == client_entity);
+
+            // Horse screens don't have a title.
+            let retitled = !matches!(inventory.kind, InventoryKind::Horse { .. })
+                && (inventory.viewer_titles_changed.contains(&client_entity)
+                    || (inventory.title_changed && !has_own_title));
+
+            if retitled {
+                // Reopening the screen with the same window ID changes its title.
+                client.write_packet(&OpenScreenS2c {
+                    window_id: VarInt(inv_state.window_id.into()),
+                    window_type: WindowType::from(inventory.kind),
+                    window_title: Cow::Borrowed(inventory.viewer_title(client_entity)),
+                });
+            }
+
+            if inventory.changed == u64::MAX || retitled {
                 // Send the entire inventory.
 
                 inv_state.state_id += 1;
@@ -773,6 +997,10 @@ fn update_open_inventories(
                 }
             }
         }
+        // The inventory may have other viewers which haven't been sent the changes
+        // yet.
+        viewed.push(open_inventory.entity);
+
         // Since these happen every gametick we only want to trigger change detection
         // if we actually did update these. Otherwise systems that are
         // running looking for changes to the `Inventory`,`ClientInventoryState`
@@ -783,6 +1011,19 @@ fn update_open_inventories(
         inv_state
             .map_unchanged(|f| &mut f.slots_changed)
             .set_if_neq(0);
+    }
+
+    for entity in viewed.drain(..) {
+        let Ok((mut inventory, _)) = inventories.get_mut(entity) else {
+            continue;
+        };
+
+        if inventory.title_changed || !inventory.viewer_titles_changed.is_empty() {
+            let inventory = inventory.bypass_change_detection();
+            inventory.title_changed = false;
+            inventory.viewer_titles_changed.clear();
+        }
+
         inventory.map_unchanged(|f| &mut f.changed).set_if_neq(0);
     }
 }
@@ -864,24 +1105,54 @@ fn handle_click_slot(
         &mut ClientInventoryState,
         Option<&mut OpenInventory>,
         &mut CursorItem,
+        Option<&GameMode>,
     )>,
     mut inventories: Query<&mut Inventory, Without<Client>>,
+    settings: Res<InventorySettings>,
     mut drop_item_stack_events: EventWriter<DropItemStackEvent>,
     mut click_slot_events: EventWriter<ClickSlotEvent>,
+    mut slot_change_events: EventWriter<SlotChangeEvent>,
 ) {
     for packet in packets.read() {
-        let Some(pkt) = packet.decode::<ClickSlotC2s>() else {
+        let Some(mut pkt) = packet.decode::<ClickSlotC2s>() else {
             // Not the packet we're looking for.
             continue;
         };
 
-        let Ok((mut client, mut client_inv, mut inv_state, open_inventory, mut cursor_item)) =
-            clients.get_mut(packet.client)
+        let Ok((
+            mut client,
+            mut client_inv,
+            mut inv_state,
+            open_inventory,
+            mut cursor_item,
+            game_mode,
+        )) = clients.get_mut(packet.client)
         else {
             // The client does not exist, ignore.
             continue;
         };
 
+        let open_entity = open_inventory.as_ref().map(|open| open.entity);
+
+        // Changes made by the server before this click shouldn't be attributed to it.
+        audit::send_slot_changes(
+            &mut client_inv,
+            packet.client,
+            Some(packet.client),
+            SlotChangeCause::Server,
+            &mut slot_change_events,
+        );
+
+        if let Some(mut open_inv) = open_entity.and_then(|e| inventories.get_mut(e).ok()) {
+            audit::send_slot_changes(
+                &mut open_inv,
+                open_entity.unwrap(),
+                None,
+                SlotChangeCause::Server,
+                &mut slot_change_events,
+            );
+        }
+
         let open_inv = open_inventory
             .as_ref()
             .and_then(|open| inventories.get_mut(open.entity).ok());
@@ -1113,6 +1384,15 @@ fn handle_click_slot(
                     continue;
                 }
 
+                let mispredicted = settings.click_handling == ClickHandling::Authoritative
+                    && click::apply_authoritative_click(
+                        &mut pkt,
+                        &InventoryWindow::new(&client_inv, Some(&*target_inventory)),
+                        &cursor_item.0,
+                        &mut inv_state.drag_slots,
+                        game_mode == Some(&GameMode::Creative),
+                    );
+
                 let mut new_cursor = pkt.carried_item.clone();
 
                 for slot in pkt.slot_changes.iter() {
@@ -1150,7 +1430,7 @@ fn handle_click_slot(
                 cursor_item.set_if_neq(CursorItem(new_cursor.clone()));
                 inv_state.client_updated_cursor_item = Some(new_cursor);
 
-                if target_inventory.readonly || client_inv.readonly {
+                if target_inventory.readonly || client_inv.readonly || mispredicted {
                     // resync the target inventory
                     client.write_packet(&InventoryS2c {
                         window_id: inv_state.window_id,
@@ -1187,6 +1467,15 @@ fn handle_click_slot(
                     continue;
                 }
 
+                let mispredicted = settings.click_handling == ClickHandling::Authoritative
+                    && click::apply_authoritative_click(
+                        &mut pkt,
+                        &InventoryWindow::new(&client_inv, None),
+                        &cursor_item.0,
+                        &mut inv_state.drag_slots,
+                        game_mode == Some(&GameMode::Creative),
+                    );
+
                 let mut new_cursor = pkt.carried_item.clone();
 
                 for slot in pkt.slot_changes.iter() {
@@ -1210,7 +1499,7 @@ fn handle_click_slot(
                 cursor_item.set_if_neq(CursorItem(new_cursor.clone()));
                 inv_state.client_updated_cursor_item = Some(new_cursor);
 
-                if client_inv.readonly {
+                if client_inv.readonly || mispredicted {
                     // resync the client inventory
                     client.write_packet(&InventoryS2c {
                         window_id: 0,
@@ -1232,6 +1521,24 @@ fn handle_click_slot(
                 carried_item: pkt.carried_item,
             });
         }
+
+        audit::send_slot_changes(
+            &mut client_inv,
+            packet.client,
+            Some(packet.client),
+            SlotChangeCause::Click,
+            &mut slot_change_events,
+        );
+
+        if let Some(mut open_inv) = open_entity.and_then(|e| inventories.get_mut(e).ok()) {
+            audit::send_slot_changes(
+                &mut open_inv,
+                open_entity.unwrap(),
+                Some(packet.client),
+                SlotChangeCause::Click,
+                &mut slot_change_events,
+            );
+        }
     }
 }
 
@@ -1244,9 +1551,20 @@ fn handle_player_actions(
         &mut Client,
     )>,
     mut drop_item_stack_events: EventWriter<DropItemStackEvent>,
+    mut slot_change_events: EventWriter<SlotChangeEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
+            if let Ok((mut inv, ..)) = clients.get_mut(packet.client) {
+                audit::send_slot_changes(
+                    &mut inv,
+                    packet.client,
+                    Some(packet.client),
+                    SlotChangeCause::Server,
+                    &mut slot_change_events,
+                );
+            }
+
             match pkt.action {
                 PlayerAction::DropAllItems => {
                     if let Ok((mut inv, mut inv_state, &held, mut client)) =
@@ -1334,6 +1652,16 @@ fn handle_player_actions(
                 }
                 _ => {}
             }
+
+            if let Ok((mut inv, ..)) = clients.get_mut(packet.client) {
+                audit::send_slot_changes(
+                    &mut inv,
+                    packet.client,
+                    Some(packet.client),
+                    SlotChangeCause::PlayerAction,
+                    &mut slot_change_events,
+                );
+            }
         }
     }
 }
@@ -1353,11 +1681,7 @@ fn resync_readonly_inventory_after_block_interaction(
             continue;
         }
 
-        let slot = if event.hand == Hand::Main {
-            held_item.slot()
-        } else {
-            PlayerInventory::SLOT_OFFHAND
-        };
+        let slot = held_item.hand_slot(event.hand);
 
         if inventory.slot(slot).is_empty() {
             continue;
@@ -1385,6 +1709,7 @@ fn handle_creative_inventory_action(
     )>,
     mut inv_action_events: EventWriter<CreativeInventoryActionEvent>,
     mut drop_item_stack_events: EventWriter<DropItemStackEvent>,
+    mut slot_change_events: EventWriter<SlotChangeEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<CreativeInventoryActionC2s>() {
@@ -1417,9 +1742,26 @@ fn handle_creative_inventory_action(
                 continue;
             }
 
+            audit::send_slot_changes(
+                &mut inventory,
+                packet.client,
+                Some(packet.client),
+                SlotChangeCause::Server,
+                &mut slot_change_events,
+            );
+
             // Set the slot without marking it as changed.
+            inventory.record_audit(pkt.slot as u16);
             inventory.slots[pkt.slot as usize] = pkt.clicked_item.clone();
 
+            audit::send_slot_changes(
+                &mut inventory,
+                packet.client,
+                Some(packet.client),
+                SlotChangeCause::Creative,
+                &mut slot_change_events,
+            );
+
             inv_state.state_id += 1;
 
             // HACK: notchian clients rely on the server to send the slot update when in
@@ -1442,45 +1784,125 @@ fn handle_creative_inventory_action(
     }
 }
 
+/// Sent when the [`HeldItem`] of a client changes.
 #[derive(Event, Clone, Debug)]
 pub struct UpdateSelectedSlotEvent {
     pub client: Entity,
+    /// The new hotbar index, from 0 to 8.
     pub slot: u8,
+    /// Whether the client or the server changed the slot.
+    pub source: SelectedSlotSource,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SelectedSlotSource {
+    /// The client scrolled or pressed a hotbar key.
+    Client,
+    /// The server changed the [`HeldItem`] component.
+    Server,
 }
 
 /// Handles the `HeldItem` component being changed on a client entity, which
 /// indicates that the server has changed the selected hotbar slot.
-fn update_player_selected_slot(mut clients: Query<(&mut Client, &HeldItem), Changed<HeldItem>>) {
-    for (mut client, held_item) in &mut clients {
+fn update_player_selected_slot(
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            Ref<HeldItem>,
+            Option<&mut HeldItemSync>,
+        ),
+        Changed<HeldItem>,
+    >,
+    mut events: EventWriter<UpdateSelectedSlotEvent>,
+) {
+    for (entity, mut client, held_item, sync) in &mut clients {
         client.write_packet(&UpdateSelectedSlotS2c {
             slot: held_item.hotbar_idx(),
         });
+
+        if held_item.is_added() {
+            continue;
+        }
+
+        if let Some(mut sync) = sync {
+            let slot = held_item.hotbar_idx();
+
+            // The client doesn't answer if it has already selected the slot, so
+            // there is nothing to wait for.
+            if sync.mode == HeldItemSyncMode::Acknowledged && slot != sync.client_slot {
+                sync.awaiting_ack = Some(slot);
+            }
+
+            sync.client_slot = slot;
+        }
+
+        events.send(UpdateSelectedSlotEvent {
+            client: entity,
+            slot: held_item.hotbar_idx(),
+            source: SelectedSlotSource::Server,
+        });
     }
 }
 
 /// Client to Server `HeldItem` Slot
 fn handle_update_selected_slot(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<&mut HeldItem>,
+    mut clients: Query<(&mut Client, &mut HeldItem, Option<&mut HeldItemSync>)>,
     mut events: EventWriter<UpdateSelectedSlotEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<UpdateSelectedSlotC2s>() {
-            if let Ok(mut mut_held) = clients.get_mut(packet.client) {
-                // We bypass the change detection here because the server listens for changes
-                // of `HeldItem` in order to send the update to the client.
-                // This is not required here because the update is coming from the client.
-                let held = mut_held.bypass_change_detection();
+            if let Ok((mut client, mut mut_held, mut sync)) = clients.get_mut(packet.client) {
                 if pkt.slot > 8 {
                     // The client is trying to interact with a slot that does not exist, ignore.
                     continue;
                 }
 
-                held.set_hotbar_idx(pkt.slot as u8);
+                let slot = pkt.slot as u8;
+
+                if let Some(sync) = &mut sync {
+                    match sync.mode {
+                        HeldItemSyncMode::Free => {}
+                        HeldItemSyncMode::Acknowledged => {
+                            if let Some(expected) = sync.awaiting_ack {
+                                // Until the client reports the server's slot, anything else it
+                                // sends was chosen before it knew about the change.
+                                if slot == expected {
+                                    sync.awaiting_ack = None;
+                                    sync.client_slot = slot;
+                                } else {
+                                    client.write_packet(&UpdateSelectedSlotS2c { slot: expected });
+                                }
+
+                                continue;
+                            }
+                        }
+                        HeldItemSyncMode::Locked => {
+                            if slot != mut_held.hotbar_idx() {
+                                client.write_packet(&UpdateSelectedSlotS2c {
+                                    slot: mut_held.hotbar_idx(),
+                                });
+                            }
+
+                            continue;
+                        }
+                    }
+                }
+
+                if let Some(sync) = &mut sync {
+                    sync.client_slot = slot;
+                }
+
+                // We bypass the change detection here because the server listens for changes
+                // of `HeldItem` in order to send the update to the client.
+                // This is not required here because the update is coming from the client.
+                mut_held.bypass_change_detection().set_hotbar_idx(slot);
 
                 events.send(UpdateSelectedSlotEvent {
                     client: packet.client,
-                    slot: pkt.slot as u8,
+                    slot,
+                    source: SelectedSlotSource::Client,
                 });
             }
         }
@@ -1522,6 +1944,18 @@ pub enum InventoryKind {
     Smoker,
     Cartography,
     Stonecutter,
+    /// The inventory of a horse, donkey, mule or llama, opened with the
+    /// animal's screen. Animals carrying a chest have `chest_columns` columns
+    /// of 3 slots, up to [`HorseInventory::MAX_CHEST_COLUMNS`].
+    ///
+    /// The inventory must be on the animal's entity so the client knows which
+    /// animal it belongs to. See [`HorseInventory`] for the slots.
+    ///
+    /// [`HorseInventory`]: horse_inventory::HorseInventory
+    /// [`HorseInventory::MAX_CHEST_COLUMNS`]: horse_inventory::HorseInventory::MAX_CHEST_COLUMNS
+    Horse {
+        chest_columns: u8,
+    },
     Player,
 }
 
@@ -1554,6 +1988,7 @@ impl InventoryKind {
             InventoryKind::Smoker => 3,
             InventoryKind::Cartography => 3,
             InventoryKind::Stonecutter => 2,
+            InventoryKind::Horse { chest_columns } => 2 + 3 * chest_columns as usize,
             InventoryKind::Player => 46,
         }
     }
@@ -1586,9 +2021,9 @@ impl From<InventoryKind> for WindowType {
             InventoryKind::Smoker => WindowType::Smoker,
             InventoryKind::Cartography => WindowType::Cartography,
             InventoryKind::Stonecutter => WindowType::Stonecutter,
-            // arbitrarily chosen, because a player inventory technically does not have a window
-            // type
-            InventoryKind::Player => WindowType::Generic9x4,
+            // arbitrarily chosen, because neither a player inventory nor a horse inventory is
+            // opened with a window type
+            InventoryKind::Horse { .. } | InventoryKind::Player => WindowType::Generic9x4,
         }
     }
 }
@@ -1627,16 +2062,36 @@ impl From<WindowType> for InventoryKind {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Resource)]
 pub struct InventorySettings {
     pub validate_actions: bool,
+    pub click_handling: ClickHandling,
 }
 
 impl Default for InventorySettings {
     fn default() -> Self {
         Self {
             validate_actions: true,
+            click_handling: ClickHandling::default(),
         }
     }
 }
 
+/// Controls how the outcome of clicks in inventory windows is decided.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub enum ClickHandling {
+    /// The slot changes sent by the client are applied once they pass
+    /// validation.
+    #[default]
+    Permissive,
+    /// The server computes the slot changes from the click mode and button,
+    /// and ignores the ones sent by the client. Clients which predicted a
+    /// different outcome are resynced.
+    ///
+    /// This is currently done for the player inventory and chest-like
+    /// inventories (generic containers, dispensers, hoppers and shulker
+    /// boxes). Clicks in other windows are handled like
+    /// [`ClickHandling::Permissive`].
+    Authoritative,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,244 @@
+//! Charging respawn anchors with glowstone and setting the respawn position
+//! with them. This lives here rather than next to beds because it depends on
+//! the item in the client's hand.
+//!
+//! Respawn anchors only work if `DeathPlugin` is added, since clients never
+//! respawn at them otherwise.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::block::{BlockKind, PropName, PropValue};
+use valence_server::client::{Client, VisibleChunkLayer};
+use valence_server::death::{Dead, PlayerRespawnEvent, RespawnPoint, RespawnRules};
+use valence_server::entity::Look;
+use valence_server::event_loop::EventLoopUpdate;
+use valence_server::interact_block::InteractBlockEvent;
+use valence_server::layer::UpdateLayersPreClientSet;
+use valence_server::math::DVec3;
+use valence_server::message::SendMessage;
+use valence_server::protocol::sound::{Sound, SoundCategory};
+use valence_server::registry::DimensionTypeRegistry;
+use valence_server::sleep::SetRespawnPositionEvent;
+use valence_server::spawn::RespawnPosition;
+use valence_server::{BlockPos, BlockState, ChunkLayer, GameMode, Hand, ItemKind, ItemStack, Text};
+
+use crate::player_inventory::PlayerInventory;
+use crate::{HeldItem, Inventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<SetRespawnPositionEvent>()
+        .add_event::<RespawnAnchorExplodeEvent>()
+        .add_systems(
+            EventLoopUpdate,
+            handle_respawn_anchor_interactions.run_if(resource_exists::<RespawnRules>),
+        )
+        .add_systems(
+            PostUpdate,
+            deplete_respawn_anchors
+                .run_if(resource_exists::<RespawnRules>)
+                .before(UpdateLayersPreClientSet),
+        );
+}
+
+/// The maximum number of charges a respawn anchor can hold.
+pub const MAX_RESPAWN_ANCHOR_CHARGES: u16 = 4;
+
+/// Sent when a client uses a charged respawn anchor in a dimension where
+/// respawn anchors don't work. Vanilla removes the anchor and creates an
+/// explosion, which is left up to users.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RespawnAnchorExplodeEvent {
+    pub client: Entity,
+    /// The position of the respawn anchor.
+    pub position: BlockPos,
+}
+
+/// Returns the number of charges of `state`, or `None` if it isn't a respawn
+/// anchor.
+fn anchor_charges(state: BlockState) -> Option<u16> {
+    if state.to_kind() != BlockKind::RespawnAnchor {
+        return None;
+    }
+
+    state.get(PropName::Charges).and_then(PropValue::to_u16)
+}
+
+fn block_center(pos: BlockPos) -> DVec3 {
+    DVec3::new(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y) + 0.5,
+        f64::from(pos.z) + 0.5,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_respawn_anchor_interactions(
+    mut events: EventReader<InteractBlockEvent>,
+    mut clients: Query<
+        (
+            &mut Client,
+            &mut Inventory,
+            &HeldItem,
+            &GameMode,
+            &Look,
+            &mut RespawnPosition,
+            &VisibleChunkLayer,
+        ),
+        Without<Dead>,
+    >,
+    mut layers: Query<&mut ChunkLayer>,
+    dimensions: Res<DimensionTypeRegistry>,
+    mut respawn_events: EventWriter<SetRespawnPositionEvent>,
+    mut explode_events: EventWriter<RespawnAnchorExplodeEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut client, mut inventory, held_item, game_mode, look, mut respawn_pos, layer)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        let Some(state) = layer.block(event.position).map(|block| block.state) else {
+            continue;
+        };
+
+        let Some(charges) = anchor_charges(state) else {
+            continue;
+        };
+
+        let slot = held_item.hand_slot(event.hand);
+
+        let holding_glowstone = inventory.slot(slot).item == ItemKind::Glowstone;
+
+        if holding_glowstone && charges < MAX_RESPAWN_ANCHOR_CHARGES {
+            let Some(new_charges) = PropValue::from_u16(charges + 1) else {
+                continue;
+            };
+
+            layer.set_block(event.position, state.set(PropName::Charges, new_charges));
+
+            layer.play_sound(
+                Sound::BlockRespawnAnchorCharge,
+                SoundCategory::Block,
+                block_center(event.position),
+                1.0,
+                1.0,
+            );
+
+            if *game_mode != GameMode::Creative {
+                let count = inventory.slot(slot).count;
+
+                if count > 1 {
+                    inventory.set_slot_amount(slot, count - 1);
+                } else {
+                    inventory.set_slot(slot, ItemStack::EMPTY);
+                }
+            }
+
+            continue;
+        }
+
+        // Let the off hand charge the anchor if it is holding glowstone.
+        if event.hand == Hand::Main
+            && !holding_glowstone
+            && inventory.slot(PlayerInventory::SLOT_OFFHAND).item == ItemKind::Glowstone
+        {
+            continue;
+        }
+
+        // Clients send an interaction for each hand, so only the main hand sets the
+        // respawn position.
+        if charges == 0 || event.hand != Hand::Main {
+            continue;
+        }
+
+        if dimensions
+            .get(layer.dimension_type_name())
+            .is_some_and(|dim| !dim.respawn_anchor_works)
+        {
+            explode_events.send(RespawnAnchorExplodeEvent {
+                client: event.client,
+                position: event.position,
+            });
+
+            continue;
+        }
+
+        if respawn_pos.pos == event.position {
+            continue;
+        }
+
+        *respawn_pos = RespawnPosition {
+            pos: event.position,
+            yaw: look.yaw,
+        };
+
+        client.send_action_bar_message(Text::translate("block.minecraft.set_spawn", []));
+
+        layer.play_sound(
+            Sound::BlockRespawnAnchorSetSpawn,
+            SoundCategory::Block,
+            block_center(event.position),
+            1.0,
+            1.0,
+        );
+
+        respawn_events.send(SetRespawnPositionEvent {
+            client: event.client,
+            position: event.position,
+        });
+    }
+}
+
+/// Uses up a charge of the respawn anchor clients respawn at.
+fn deplete_respawn_anchors(
+    mut events: EventReader<PlayerRespawnEvent>,
+    clients: Query<(&RespawnPosition, &VisibleChunkLayer)>,
+    mut layers: Query<&mut ChunkLayer>,
+    rules: Res<RespawnRules>,
+) {
+    if rules.respawn_point != RespawnPoint::Personal {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        let Ok((respawn_pos, layer)) = clients.get(event.client) else {
+            continue;
+        };
+
+        let Ok(mut layer) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        let Some(state) = layer.block(respawn_pos.pos).map(|block| block.state) else {
+            continue;
+        };
+
+        let Some(charges) = anchor_charges(state).filter(|&charges| charges > 0) else {
+            continue;
+        };
+
+        let Some(new_charges) = PropValue::from_u16(charges - 1) else {
+            continue;
+        };
+
+        layer.set_block(respawn_pos.pos, state.set(PropName::Charges, new_charges));
+
+        layer.play_sound(
+            Sound::BlockRespawnAnchorDeplete,
+            SoundCategory::Block,
+            block_center(respawn_pos.pos),
+            1.0,
+            1.0,
+        );
+    }
+}
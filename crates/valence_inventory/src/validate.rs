@@ -2,7 +2,8 @@ use valence_server::protocol::anyhow::{self, bail, ensure};
 use valence_server::protocol::packets::play::click_slot_c2s::ClickMode;
 use valence_server::protocol::packets::play::ClickSlotC2s;
 
-use super::{CursorItem, Inventory, InventoryWindow};
+use super::{CursorItem, Inventory, InventoryKind, InventoryWindow};
+use crate::horse_inventory::HorseInventory;
 use crate::player_inventory::PlayerInventory;
 
 /// Validates a click slot packet enforcing that all fields are valid.
@@ -46,6 +47,22 @@ pub(super) fn validate_click_slot_packet(
         "invalid slot ids or item counts"
     );
 
+    // check that only saddles and armor are put on horses
+    if let Some(InventoryKind::Horse { .. }) = open_inventory.map(Inventory::kind) {
+        ensure!(
+            packet.slot_changes.iter().all(|s| {
+                let valid_item = match s.idx as u16 {
+                    HorseInventory::SLOT_SADDLE => HorseInventory::is_saddle(s.stack.item),
+                    HorseInventory::SLOT_ARMOR => HorseInventory::is_armor(s.stack.item),
+                    _ => return true,
+                };
+
+                s.stack.is_empty() || (valid_item && s.stack.count == 1)
+            }),
+            "invalid horse saddle or armor"
+        );
+    }
+
     // check carried item count is valid
     if !packet.carried_item.is_empty() {
         let carried_item = &packet.carried_item;
@@ -654,6 +671,58 @@ mod tests {
             .expect("packet should be valid");
     }
 
+    #[test]
+    fn horse_saddle_and_armor_slots() {
+        let player_inventory = Inventory::new(InventoryKind::Player);
+        let inventory = Inventory::new(InventoryKind::Horse { chest_columns: 0 });
+
+        let place = |idx: i16, item: ItemKind| ClickSlotC2s {
+            window_id: 1,
+            button: 0,
+            mode: ClickMode::Click,
+            state_id: VarInt(0),
+            slot_idx: idx,
+            slot_changes: vec![SlotChange {
+                idx,
+                stack: ItemStack::new(item, 1, None),
+            }]
+            .into(),
+            carried_item: ItemStack::EMPTY,
+        };
+
+        let cursor_item = CursorItem(ItemStack::new(ItemKind::Saddle, 1, None));
+        validate_click_slot_packet(
+            &place(0, ItemKind::Saddle),
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+        )
+        .expect("saddles should fit in the saddle slot");
+        validate_click_slot_packet(
+            &place(1, ItemKind::Saddle),
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+        )
+        .expect_err("saddles should not fit in the armor slot");
+
+        let cursor_item = CursorItem(ItemStack::new(ItemKind::IronHorseArmor, 1, None));
+        validate_click_slot_packet(
+            &place(1, ItemKind::IronHorseArmor),
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+        )
+        .expect("horse armor should fit in the armor slot");
+        validate_click_slot_packet(
+            &place(0, ItemKind::IronHorseArmor),
+            &player_inventory,
+            Some(&inventory),
+            &cursor_item,
+        )
+        .expect_err("horse armor should not fit in the saddle slot");
+    }
+
     #[test]
     fn click_slot_with_filled_cursor_failure() {
         let player_inventory = Inventory::new(InventoryKind::Player);
@@ -0,0 +1,113 @@
+//! Tracking the clients viewing an inventory.
+//!
+//! Any number of clients can open the same [`Inventory`] entity by inserting
+//! an [`OpenInventory`] pointing to it. The clients viewing an inventory are
+//! listed by [`Inventory::viewers`], and each of them can be shown its own
+//! title with [`Inventory::set_viewer_title`].
+//!
+//! [`InventoryOpenedEvent`] and [`InventoryClosedEvent`] are sent when the
+//! first viewer opens an inventory and when the last one closes it, which is
+//! when vanilla opens and closes the lid of a chest.
+
+use std::collections::HashMap;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_server::client::FlushPacketsSet;
+
+use crate::{Inventory, OpenInventory};
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<InventoryOpenedEvent>()
+        .add_event::<InventoryClosedEvent>()
+        .add_systems(PostUpdate, update_inventory_viewers.before(FlushPacketsSet));
+}
+
+/// Sent when a client opens an inventory nobody else is viewing.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InventoryOpenedEvent {
+    pub inventory: Entity,
+    /// The client which opened the inventory.
+    pub client: Entity,
+}
+
+/// Sent when the last client viewing an inventory closes it. Not sent if the
+/// inventory is despawned.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InventoryClosedEvent {
+    pub inventory: Entity,
+    /// The client which closed the inventory.
+    pub client: Entity,
+}
+
+pub(crate) fn update_inventory_viewers(
+    mut closed: RemovedComponents<OpenInventory>,
+    opened: Query<(Entity, &OpenInventory), Changed<OpenInventory>>,
+    mut inventories: Query<&mut Inventory>,
+    mut opened_events: EventWriter<InventoryOpenedEvent>,
+    mut closed_events: EventWriter<InventoryClosedEvent>,
+    // The inventory each client was last seen viewing.
+    mut viewing: Local<HashMap<Entity, Entity>>,
+) {
+    // Inventories which lost their last viewer this tick.
+    let mut emptied = vec![];
+
+    let mut remove_viewer = |inventory: Entity, client: Entity| {
+        let Ok(mut inv) = inventories.get_mut(inventory) else {
+            return;
+        };
+
+        let inv = inv.bypass_change_detection();
+
+        inv.viewers.retain(|&viewer| viewer != client);
+        inv.title_overrides.retain(|(viewer, _)| *viewer != client);
+
+        if inv.viewers.is_empty() {
+            emptied.push((inventory, client));
+        }
+    };
+
+    for client in closed.read() {
+        if let Some(inventory) = viewing.remove(&client) {
+            remove_viewer(inventory, client);
+        }
+    }
+
+    for (client, open) in &opened {
+        match viewing.insert(client, open.entity) {
+            Some(inventory) if inventory == open.entity => continue,
+            Some(inventory) => remove_viewer(inventory, client),
+            None => {}
+        }
+    }
+
+    // Viewers are added after all of them are removed, so an inventory passed
+    // from one client to another in the same tick is neither closed nor opened.
+    for (client, open) in &opened {
+        let Ok(mut inv) = inventories.get_mut(open.entity) else {
+            continue;
+        };
+
+        let inv = inv.bypass_change_detection();
+
+        if !inv.viewers.contains(&client) {
+            inv.viewers.push(client);
+
+            if inv.viewers.len() == 1 && emptied.iter().all(|&(e, _)| e != open.entity) {
+                opened_events.send(InventoryOpenedEvent {
+                    inventory: open.entity,
+                    client,
+                });
+            }
+        }
+    }
+
+    for (inventory, client) in emptied {
+        if inventories
+            .get(inventory)
+            .is_ok_and(|inv| inv.viewers.is_empty())
+        {
+            closed_events.send(InventoryClosedEvent { inventory, client });
+        }
+    }
+}
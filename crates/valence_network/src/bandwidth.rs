@@ -0,0 +1,81 @@
+//! Limiting the rate data is written to a connection.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// A token bucket holding up to one second worth of bytes.
+pub(crate) struct BandwidthLimiter {
+    /// The number of bytes per second.
+    rate: f64,
+    /// The number of bytes which can be written right away. This is negative
+    /// after writing more than the rate allows.
+    available: f64,
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    pub(crate) fn new(bytes_per_second: u32) -> Self {
+        let rate = f64::from(bytes_per_second.max(1));
+
+        Self {
+            rate,
+            available: rate,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+
+        self.available = (self.available + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+    }
+
+    /// Takes `len` bytes if they can be written right away. Returns whether
+    /// the bytes were taken.
+    pub(crate) fn try_take(&mut self, len: usize) -> bool {
+        self.refill();
+
+        if self.available >= len as f64 {
+            self.available -= len as f64;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes `len` bytes, even if they exceed the rate. Returns how long to
+    /// wait before writing them.
+    pub(crate) fn take(&mut self, len: usize) -> Duration {
+        self.refill();
+
+        self.available -= len as f64;
+
+        if self.available < 0.0 {
+            Duration::from_secs_f64(-self.available / self.rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bandwidth_limiter() {
+        let mut limiter = BandwidthLimiter::new(1000);
+
+        assert!(limiter.try_take(600));
+        assert!(!limiter.try_take(600));
+
+        assert_eq!(limiter.take(300), Duration::ZERO);
+
+        // Bytes which must be written are taken even if they exceed the rate.
+        assert!(limiter.take(200) > Duration::from_millis(50));
+        assert!(!limiter.try_take(1));
+    }
+}
@@ -1,17 +1,24 @@
 //! A channel specifically for sending/receiving batches of bytes.
+//!
+//! The bytes are queued as reference counted chunks, so data shared between
+//! several channels is never copied.
 
 #![allow(dead_code)]
 
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 
-use bytes::BytesMut;
+use bytes::Bytes;
 use thiserror::Error;
 use tokio::sync::Notify;
 
 pub(crate) fn byte_channel(limit: usize) -> (ByteSender, ByteReceiver) {
     let shared = Arc::new(Shared {
         mtx: Mutex::new(Inner {
-            bytes: BytesMut::new(),
+            chunks: VecDeque::new(),
+            len: 0,
             disconnected: false,
         }),
         notify: Notify::new(),
@@ -42,21 +49,72 @@ struct Shared {
 }
 
 struct Inner {
-    bytes: BytesMut,
+    chunks: VecDeque<Chunk>,
+    /// The total length of `chunks`.
+    len: usize,
     disconnected: bool,
 }
 
-impl ByteSender {
-    pub(crate) fn take_capacity(&mut self, additional: usize) -> BytesMut {
-        let mut lck = self.shared.mtx.lock().unwrap();
+/// A chunk of bytes queued in a channel.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Chunk {
+    pub(crate) bytes: Bytes,
+    pub(crate) priority: ChunkPriority,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum ChunkPriority {
+    /// The chunk may be written before the chunks sent earlier. See
+    /// [`ByteSender::try_send_critical`].
+    Critical,
+    Normal,
+    /// The chunk may be dropped when the connection is congested. See
+    /// [`ByteSender::try_send_low_priority`].
+    Low,
+}
+
+impl Deref for Chunk {
+    type Target = Bytes;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl PartialEq<Bytes> for Chunk {
+    fn eq(&self, other: &Bytes) -> bool {
+        self.bytes == *other
+    }
+}
 
-        lck.bytes.reserve(additional);
+impl Inner {
+    fn push(&mut self, bytes: Bytes, priority: ChunkPriority) {
+        self.len += bytes.len();
+        self.chunks.push_back(Chunk { bytes, priority });
+    }
 
-        let len = lck.bytes.len();
-        lck.bytes.split_off(len)
+    fn take(&mut self) -> VecDeque<Chunk> {
+        self.len = 0;
+        mem::take(&mut self.chunks)
     }
+}
 
-    pub(crate) fn try_send(&mut self, mut bytes: BytesMut) -> Result<(), TrySendError> {
+impl ByteSender {
+    pub(crate) fn try_send(&mut self, bytes: Bytes) -> Result<(), TrySendError> {
+        self.try_send_with_priority(bytes, ChunkPriority::Normal)
+    }
+
+    /// Like [`Self::try_send`], but the bytes may be received before the bytes
+    /// sent earlier. See [`ByteReceiver::take_critical`].
+    pub(crate) fn try_send_critical(&mut self, bytes: Bytes) -> Result<(), TrySendError> {
+        self.try_send_with_priority(bytes, ChunkPriority::Critical)
+    }
+
+    fn try_send_with_priority(
+        &mut self,
+        mut bytes: Bytes,
+        priority: ChunkPriority,
+    ) -> Result<(), TrySendError> {
         let mut lck = self.shared.mtx.lock().unwrap();
 
         if lck.disconnected {
@@ -67,24 +125,41 @@ impl ByteSender {
             return Ok(());
         }
 
-        let available = self.shared.limit - lck.bytes.len();
+        let available = self.shared.limit - lck.len;
 
         if bytes.len() > available {
             if available > 0 {
-                lck.bytes.unsplit(bytes.split_to(available));
+                lck.push(bytes.split_to(available), priority);
                 self.shared.notify.notify_waiters();
             }
 
             return Err(TrySendError::Full(bytes));
         }
 
-        lck.bytes.unsplit(bytes);
+        lck.push(bytes, priority);
         self.shared.notify.notify_waiters();
 
         Ok(())
     }
 
-    pub(crate) async fn send_async(&mut self, mut bytes: BytesMut) -> Result<(), SendError> {
+    /// Like [`Self::try_send`], but the bytes are dropped instead of split if
+    /// they don't fit in the channel, and the receiver may drop them too.
+    pub(crate) fn try_send_low_priority(&mut self, bytes: Bytes) -> Result<(), TrySendError> {
+        let mut lck = self.shared.mtx.lock().unwrap();
+
+        if lck.disconnected {
+            return Err(TrySendError::Disconnected(bytes));
+        }
+
+        if !bytes.is_empty() && bytes.len() <= self.shared.limit - lck.len {
+            lck.push(bytes, ChunkPriority::Low);
+            self.shared.notify.notify_waiters();
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn send_async(&mut self, mut bytes: Bytes) -> Result<(), SendError> {
         loop {
             {
                 let mut lck = self.shared.mtx.lock().unwrap();
@@ -97,16 +172,16 @@ impl ByteSender {
                     return Ok(());
                 }
 
-                let available = self.shared.limit - lck.bytes.len();
+                let available = self.shared.limit - lck.len;
 
                 if bytes.len() <= available {
-                    lck.bytes.unsplit(bytes);
+                    lck.push(bytes, ChunkPriority::Normal);
                     self.shared.notify.notify_waiters();
                     return Ok(());
                 }
 
                 if available > 0 {
-                    lck.bytes.unsplit(bytes.split_to(available));
+                    lck.push(bytes.split_to(available), ChunkPriority::Normal);
                     self.shared.notify.notify_waiters();
                 }
             }
@@ -122,34 +197,41 @@ impl ByteSender {
     pub(crate) fn limit(&self) -> usize {
         self.shared.limit
     }
+
+    /// Returns the number of bytes in the channel which haven't been received
+    /// yet.
+    pub(crate) fn len(&self) -> usize {
+        self.shared.mtx.lock().unwrap().len
+    }
 }
 
 /// Contains any excess bytes not sent.
 #[derive(Clone, PartialEq, Eq, Debug, Error)]
 pub(crate) enum TrySendError {
     #[error("sender disconnected")]
-    Disconnected(BytesMut),
+    Disconnected(Bytes),
     #[error("channel full (see `Config::outgoing_capacity`)")]
-    Full(BytesMut),
+    Full(Bytes),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Error)]
 #[error("sender disconnected")]
-pub(crate) struct SendError(pub(crate) BytesMut);
+pub(crate) struct SendError(pub(crate) Bytes);
 
 impl SendError {
-    pub(crate) fn into_inner(self) -> BytesMut {
+    pub(crate) fn into_inner(self) -> Bytes {
         self.0
     }
 }
 
 impl ByteReceiver {
-    pub(crate) fn try_recv(&mut self) -> Result<BytesMut, TryRecvError> {
+    /// Receives all queued chunks of bytes in the order they were sent.
+    pub(crate) fn try_recv(&mut self) -> Result<VecDeque<Chunk>, TryRecvError> {
         let mut lck = self.shared.mtx.lock().unwrap();
 
-        if !lck.bytes.is_empty() {
+        if !lck.chunks.is_empty() {
             self.shared.notify.notify_waiters();
-            return Ok(lck.bytes.split());
+            return Ok(lck.take());
         }
 
         if lck.disconnected {
@@ -159,14 +241,15 @@ impl ByteReceiver {
         Err(TryRecvError::Empty)
     }
 
-    pub(crate) async fn recv_async(&mut self) -> Result<BytesMut, RecvError> {
+    /// Like [`Self::try_recv`], but waits for bytes to be sent.
+    pub(crate) async fn recv_async(&mut self) -> Result<VecDeque<Chunk>, RecvError> {
         loop {
             {
                 let mut lck = self.shared.mtx.lock().unwrap();
 
-                if !lck.bytes.is_empty() {
+                if !lck.chunks.is_empty() {
                     self.shared.notify.notify_waiters();
-                    return Ok(lck.bytes.split());
+                    return Ok(lck.take());
                 }
 
                 if lck.disconnected {
@@ -178,6 +261,29 @@ impl ByteReceiver {
         }
     }
 
+    /// Receives only the critical chunks, leaving the others in the channel.
+    pub(crate) fn take_critical(&mut self) -> VecDeque<Chunk> {
+        let mut lck = self.shared.mtx.lock().unwrap();
+
+        if !lck
+            .chunks
+            .iter()
+            .any(|chunk| chunk.priority == ChunkPriority::Critical)
+        {
+            return VecDeque::new();
+        }
+
+        let (critical, rest): (VecDeque<_>, _) = mem::take(&mut lck.chunks)
+            .into_iter()
+            .partition(|chunk| chunk.priority == ChunkPriority::Critical);
+
+        lck.chunks = rest;
+        lck.len -= critical.iter().map(|chunk| chunk.len()).sum::<usize>();
+        self.shared.notify.notify_waiters();
+
+        critical
+    }
+
     pub(crate) fn is_disconnected(&self) -> bool {
         self.shared.mtx.lock().unwrap().disconnected
     }
@@ -222,13 +328,64 @@ mod tests {
         let (mut sender, mut receiver) = byte_channel(4);
 
         assert_eq!(
-            sender.try_send("hello".as_bytes().into()),
-            Err(TrySendError::Full("o".as_bytes().into()))
+            sender.try_send(Bytes::from_static(b"hello")),
+            Err(TrySendError::Full(Bytes::from_static(b"o")))
         );
 
+        assert_eq!(receiver.try_recv().unwrap(), [Bytes::from_static(b"hell")]);
+    }
+
+    #[test]
+    fn byte_channel_shares_chunks() {
+        let (mut sender, mut receiver) = byte_channel(16);
+
+        let shared = Bytes::from_static(b"shared");
+
+        sender.try_send(Bytes::from_static(b"owned")).unwrap();
+        sender.try_send(shared.clone()).unwrap();
+
+        let chunks = receiver.try_recv().unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].as_ptr(), shared.as_ptr());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn byte_channel_drops_low_priority() {
+        let (mut sender, mut receiver) = byte_channel(8);
+
+        sender.try_send(Bytes::from_static(b"hello")).unwrap();
+        sender
+            .try_send_low_priority(Bytes::from_static(b"world"))
+            .unwrap();
+        sender
+            .try_send_low_priority(Bytes::from_static(b"!"))
+            .unwrap();
+
+        let chunks = receiver.try_recv().unwrap();
+
+        assert_eq!(
+            chunks,
+            [Bytes::from_static(b"hello"), Bytes::from_static(b"!")]
+        );
+        assert_eq!(chunks[0].priority, ChunkPriority::Normal);
+        assert_eq!(chunks[1].priority, ChunkPriority::Low);
+    }
+
+    #[test]
+    fn byte_channel_take_critical() {
+        let (mut sender, mut receiver) = byte_channel(16);
+
+        sender.try_send(Bytes::from_static(b"normal")).unwrap();
+        sender
+            .try_send_critical(Bytes::from_static(b"critical"))
+            .unwrap();
+
+        assert_eq!(receiver.take_critical(), [Bytes::from_static(b"critical")]);
         assert_eq!(
             receiver.try_recv().unwrap(),
-            BytesMut::from("hell".as_bytes())
+            [Bytes::from_static(b"normal")]
         );
     }
 
@@ -237,15 +394,18 @@ mod tests {
         let (mut sender, mut receiver) = byte_channel(4);
 
         let t = tokio::spawn(async move {
-            let bytes = receiver.recv_async().await.unwrap();
-            assert_eq!(&bytes[..], b"hell");
-            let bytes = receiver.recv_async().await.unwrap();
-            assert_eq!(&bytes[..], b"o");
+            let chunks = receiver.recv_async().await.unwrap();
+            assert_eq!(chunks, [Bytes::from_static(b"hell")]);
+            let chunks = receiver.recv_async().await.unwrap();
+            assert_eq!(chunks, [Bytes::from_static(b"o")]);
 
             assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
         });
 
-        sender.send_async("hello".as_bytes().into()).await.unwrap();
+        sender
+            .send_async(Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
 
         t.await.unwrap();
 
@@ -1,11 +1,13 @@
 //! Handles new connections to the server and the log-in process.
 
 use std::io;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::time::Duration;
 
 use anyhow::{bail, ensure, Context};
-use base64::prelude::*;
+use bytes::BytesMut;
 use hmac::digest::Update;
 use hmac::{Hmac, Mac};
 use num_bigint::BigInt;
@@ -15,8 +17,10 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use sha1::Sha1;
 use sha2::{Digest, Sha256};
-use tokio::net::{TcpListener, TcpStream};
-use tracing::{error, info, trace, warn};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::TcpListener;
+use tokio::sync::OwnedSemaphorePermit;
+use tracing::{debug, error, field, info, info_span, trace, warn, Instrument, Span};
 use uuid::Uuid;
 use valence_lang::keys;
 use valence_protocol::profile::Property;
@@ -37,38 +41,57 @@ use valence_server::{ident, Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
 
 use crate::legacy_ping::try_handle_legacy_ping;
 use crate::packet_io::PacketIo;
-use crate::{CleanupOnDrop, ConnectionMode, NewClientInfo, ServerListPing, SharedNetworkState};
+use crate::proxy_protocol::read_proxy_header;
+use crate::server_list::png_data_uri;
+use crate::transport::{Listener, Stream};
+use crate::{
+    CleanupOnDrop, ConnectionMode, LoginFailureEvent, LoginFailureReason, NewClientInfo,
+    ProxyForwarding, ServerListPing, SharedNetworkState, VelocityPlayerKey,
+};
 
-/// Accepts new connections to the server as they occur.
-pub(super) async fn do_accept_loop(shared: SharedNetworkState) {
-    let listener = match TcpListener::bind(shared.0.address).await {
+/// Accepts new connections to `address` as they occur.
+pub(super) async fn do_accept_loop(shared: SharedNetworkState, address: SocketAddr) {
+    let listener = match bind_listener(address, only_v6(address, &shared.0.addresses)) {
         Ok(listener) => listener,
         Err(e) => {
-            error!("failed to start TCP listener: {e}");
+            error!("failed to start TCP listener on {address}: {e}");
             return;
         }
     };
 
-    let timeout = Duration::from_secs(5);
+    accept_connections(shared, Listener::Tcp(listener)).await;
+}
+
+/// Accepts new connections to the unix domain socket at `path` as they occur.
+#[cfg(unix)]
+pub(super) async fn do_unix_accept_loop(shared: SharedNetworkState, path: PathBuf) {
+    use std::os::unix::fs::FileTypeExt;
+
+    // The socket file of a previous run would make binding fail.
+    if std::fs::metadata(&path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        let _ = std::fs::remove_file(&path);
+    }
 
+    let listener = match tokio::net::UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "failed to start unix socket listener on `{}`: {e}",
+                path.display()
+            );
+            return;
+        }
+    };
+
+    accept_connections(shared, Listener::Unix(listener)).await;
+}
+
+async fn accept_connections(shared: SharedNetworkState, listener: Listener) {
     loop {
         match shared.0.connection_sema.clone().acquire_owned().await {
             Ok(permit) => match listener.accept().await {
                 Ok((stream, remote_addr)) => {
-                    let shared = shared.clone();
-
-                    tokio::spawn(async move {
-                        if let Err(e) = tokio::time::timeout(
-                            timeout,
-                            handle_connection(shared, stream, remote_addr),
-                        )
-                        .await
-                        {
-                            warn!("initial connection timed out: {e}");
-                        }
-
-                        drop(permit);
-                    });
+                    spawn_connection(shared.clone(), stream, remote_addr, permit);
                 }
                 Err(e) => {
                     error!("failed to accept incoming connection: {e}");
@@ -80,18 +103,165 @@ pub(super) async fn do_accept_loop(shared: SharedNetworkState) {
     }
 }
 
-async fn handle_connection(
+/// Handles a connection from a stream which wasn't accepted by a listener.
+/// See [`SharedNetworkState::accept_stream`].
+pub(super) async fn accept_stream(
     shared: SharedNetworkState,
-    mut stream: TcpStream,
+    stream: Box<dyn Stream>,
     remote_addr: SocketAddr,
+) {
+    // Closed semaphore indicates server shutdown.
+    if let Ok(permit) = shared.0.connection_sema.clone().acquire_owned().await {
+        spawn_connection(shared, stream, remote_addr, permit);
+    }
+}
+
+fn spawn_connection(
+    shared: SharedNetworkState,
+    stream: Box<dyn Stream>,
+    remote_addr: SocketAddr,
+    permit: OwnedSemaphorePermit,
+) {
+    let timeout = Duration::from_secs(5);
+
+    // The username and UUID are recorded once the client sends them.
+    let span = info_span!(
+        "connection",
+        ip = %remote_addr.ip(),
+        username = field::Empty,
+        uuid = field::Empty,
+    );
+
+    let task = async move {
+        let mut attempt = LoginAttempt::new(remote_addr.ip());
+
+        if let Err(e) = tokio::time::timeout(
+            timeout,
+            handle_connection(shared.clone(), stream, remote_addr, &mut attempt),
+        )
+        .await
+        {
+            warn!("initial connection timed out: {e}");
+            attempt.fail(&shared, LoginFailureReason::Timeout);
+        }
+
+        drop(permit);
+    };
+
+    tokio::spawn(task.instrument(span));
+}
+
+/// Returns whether the listener on `address` should only accept IPv6
+/// connections, given all the addresses the server listens on.
+fn only_v6(address: SocketAddr, addresses: &[SocketAddr]) -> bool {
+    // Binding both IPv4 and IPv6 wildcard addresses on the same port fails if the
+    // IPv6 socket also accepts IPv4 connections.
+    address.is_ipv6()
+        && addresses
+            .iter()
+            .any(|other| other.is_ipv4() && other.port() == address.port())
+}
+
+fn bind_listener(address: SocketAddr, only_v6: bool) -> io::Result<TcpListener> {
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+
+    if address.is_ipv6() {
+        socket.set_only_v6(only_v6)?;
+    }
+
+    // Same as `TcpListener::bind`, which allows restarting the server right away.
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// The progress of a client through the login process, for reporting
+/// [`LoginFailureEvent`]s.
+struct LoginAttempt {
+    ip: IpAddr,
+    /// Whether the client is logging in, rather than pinging the server.
+    logging_in: bool,
+    username: Option<String>,
+}
+
+impl LoginAttempt {
+    fn new(ip: IpAddr) -> Self {
+        Self {
+            ip,
+            logging_in: false,
+            username: None,
+        }
+    }
+
+    /// Reports the login as failed if the client was logging in.
+    fn fail(&mut self, shared: &SharedNetworkState, reason: LoginFailureReason) {
+        if !self.logging_in {
+            return;
+        }
+
+        self.logging_in = false;
+
+        // Drop the event if the server is falling behind.
+        let _ = shared.0.login_failures_send.try_send(LoginFailureEvent {
+            ip: self.ip,
+            username: self.username.take(),
+            reason,
+        });
+    }
+}
+
+/// Returns the [`LoginFailureReason`] describing a login error.
+fn login_failure_reason(e: &anyhow::Error) -> LoginFailureReason {
+    if let Some(reason) = e.downcast_ref::<LoginFailureReason>() {
+        return reason.clone();
+    }
+
+    if let Some(e) = e.downcast_ref::<io::Error>() {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            return LoginFailureReason::Disconnected;
+        }
+    }
+
+    LoginFailureReason::Other(format!("{e:#}"))
+}
+
+async fn handle_connection(
+    shared: SharedNetworkState,
+    mut stream: Box<dyn Stream>,
+    mut remote_addr: SocketAddr,
+    attempt: &mut LoginAttempt,
 ) {
     trace!("handling connection");
 
-    if let Err(e) = stream.set_nodelay(true) {
-        error!("failed to set TCP_NODELAY: {e}");
+    // The bytes read while checking for a PROXY protocol header or a legacy ping.
+    let mut buf = BytesMut::new();
+
+    if shared.0.proxy_protocol {
+        match read_proxy_header(&mut stream, &mut buf).await {
+            Ok(Some(addr)) => {
+                remote_addr = addr;
+                attempt.ip = addr.ip();
+                Span::current().record("ip", field::display(addr.ip()));
+            }
+            // The proxy connected on its own behalf.
+            Ok(None) => {}
+            Err(e) => {
+                warn!("failed to read PROXY protocol header: {e:#}");
+                return;
+            }
+        }
     }
 
-    match try_handle_legacy_ping(&shared, &mut stream, remote_addr).await {
+    match try_handle_legacy_ping(&shared, &mut stream, &mut buf, remote_addr).await {
         Ok(true) => return, // Legacy ping succeeded.
         Ok(false) => {}     // No legacy ping.
         Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
@@ -100,9 +270,17 @@ async fn handle_connection(
         }
     }
 
-    let io = PacketIo::new(stream, PacketEncoder::new(), PacketDecoder::new());
+    let mut enc = PacketEncoder::new();
+    enc.set_compression_level(shared.0.compression_level);
+
+    let mut dec = PacketDecoder::new();
+    dec.queue_bytes(buf);
+
+    let io = PacketIo::new(stream, enc, dec);
+
+    if let Err(e) = handle_handshake(shared.clone(), io, remote_addr, attempt).await {
+        attempt.fail(&shared, login_failure_reason(&e));
 
-    if let Err(e) = handle_handshake(shared, io, remote_addr).await {
         // EOF can happen if the client disconnects while joining, which isn't
         // very erroneous.
         if let Some(e) = e.downcast_ref::<io::Error>() {
@@ -130,11 +308,17 @@ async fn handle_handshake(
     shared: SharedNetworkState,
     mut io: PacketIo,
     remote_addr: SocketAddr,
+    attempt: &mut LoginAttempt,
 ) -> anyhow::Result<()> {
     let handshake = io.recv_packet::<HandshakeC2s>().await?;
 
     let next_state = handshake.next_state;
 
+    debug!(
+        protocol_version = handshake.protocol_version.0,
+        "handshake requested state {next_state:?}"
+    );
+
     let handshake = HandshakeData {
         protocol_version: handshake.protocol_version.0,
         server_address: handshake.server_address.0.to_owned(),
@@ -153,19 +337,37 @@ async fn handle_handshake(
             .await
             .context("handling status"),
         HandshakeNextState::Login => {
-            match handle_login(&shared, &mut io, remote_addr, handshake)
+            let protocol_version = handshake.protocol_version;
+
+            attempt.logging_in = true;
+
+            match handle_login(&shared, &mut io, remote_addr, handshake, attempt)
                 .await
                 .context("handling login")?
             {
                 Some((info, cleanup)) => {
+                    let forwarding = info.forwarding.clone();
+
                     let client = io.into_client_args(
                         info,
+                        protocol_version,
                         shared.0.incoming_byte_limit,
                         shared.0.outgoing_byte_limit,
+                        shared.0.outgoing_bandwidth_limit,
+                        shared.0.offload_encryption,
+                        shared.0.threshold,
                         cleanup,
                     );
 
-                    let _ = shared.0.new_clients_send.send_async(client).await;
+                    attempt.logging_in = false;
+
+                    info!("logged in");
+
+                    let _ = shared
+                        .0
+                        .new_clients_send
+                        .send_async((client, forwarding))
+                        .await;
 
                     Ok(())
                 }
@@ -226,12 +428,12 @@ async fn handle_status(
                     "sample": player_sample,
                 },
                 "description": description,
+                // Valence doesn't verify signed chat messages.
+                "enforcesSecureChat": false,
             });
 
             if !favicon_png.is_empty() {
-                let mut buf = "data:image/png;base64,".to_owned();
-                BASE64_STANDARD.encode_string(favicon_png, &mut buf);
-                json["favicon"] = Value::String(buf);
+                json["favicon"] = Value::String(png_data_uri(favicon_png));
             }
 
             io.send_packet(&QueryResponseS2c {
@@ -250,11 +452,14 @@ async fn handle_status(
 }
 
 /// Handle the login process and return the new client's data if successful.
+///
+/// Failed logins which don't end in an error are reported through `attempt`.
 async fn handle_login(
     shared: &SharedNetworkState,
     io: &mut PacketIo,
     remote_addr: SocketAddr,
     handshake: HandshakeData,
+    attempt: &mut LoginAttempt,
 ) -> anyhow::Result<Option<(NewClientInfo, CleanupOnDrop)>> {
     if handshake.protocol_version != PROTOCOL_VERSION {
         io.send_packet(&LoginDisconnectS2c {
@@ -265,6 +470,11 @@ async fn handle_login(
         })
         .await?;
 
+        attempt.fail(
+            shared,
+            LoginFailureReason::UnsupportedVersion(handshake.protocol_version),
+        );
+
         return Ok(None);
     }
 
@@ -275,36 +485,83 @@ async fn handle_login(
 
     let username = username.0.to_owned();
 
+    Span::current().record("username", username.as_str());
+
+    attempt.username = Some(username.clone());
+
     let info = match shared.connection_mode() {
         ConnectionMode::Online { .. } => login_online(shared, io, remote_addr, username).await?,
         ConnectionMode::Offline => login_offline(remote_addr, username)?,
         ConnectionMode::BungeeCord => {
-            login_bungeecord(remote_addr, &handshake.server_address, username)?
+            login_bungeecord(remote_addr, &handshake.server_address, username)
+                .map_err(forwarding_error)?
         }
-        ConnectionMode::Velocity { secret } => login_velocity(io, username, secret).await?,
+        ConnectionMode::Velocity { secret } => login_velocity(io, username, secret)
+            .await
+            .map_err(forwarding_error)?,
     };
 
-    if shared.0.threshold.0 > 0 {
-        io.send_packet(&LoginCompressionS2c {
-            threshold: shared.0.threshold.0.into(),
-        })
-        .await?;
+    attempt.ip = info.ip;
 
-        io.set_compression(shared.0.threshold);
-    }
+    // The IP and username may have been changed by a proxy or the session server.
+    Span::current()
+        .record("ip", field::display(info.ip))
+        .record("username", info.username.as_str())
+        .record("uuid", field::display(info.uuid));
+
+    // Removes the UUID from the online UUIDs when the client is dropped or the
+    // login fails.
+    let uuid_guard = if shared.0.reject_duplicate_uuids {
+        let Some(uuid_guard) = OnlineUuidGuard::new(shared, info.uuid) else {
+            info!("disconnect at login: duplicate UUID {}", info.uuid);
+            io.send_packet(&LoginDisconnectS2c {
+                reason: Text::translate(keys::MULTIPLAYER_DISCONNECT_NAME_TAKEN, []).into(),
+            })
+            .await?;
+
+            attempt.fail(shared, LoginFailureReason::DuplicateUuid(info.uuid));
+
+            return Ok(None);
+        };
+
+        Some(uuid_guard)
+    } else {
+        None
+    };
 
     let cleanup = match shared.0.callbacks.inner.login(shared, &info).await {
-        Ok(f) => CleanupOnDrop(Some(f)),
+        Ok(f) => CleanupOnDrop(Some(Box::new(move || {
+            f();
+            drop(uuid_guard);
+        }))),
         Err(reason) => {
             info!("disconnect at login: \"{reason}\"");
             io.send_packet(&LoginDisconnectS2c {
-                reason: reason.into(),
+                reason: reason.clone().into(),
             })
             .await?;
+
+            attempt.fail(shared, LoginFailureReason::Rejected(reason));
+
             return Ok(None);
         }
     };
 
+    let threshold = shared
+        .0
+        .callbacks
+        .inner
+        .compression_threshold(shared, &info);
+
+    if threshold.0 > 0 {
+        io.send_packet(&LoginCompressionS2c {
+            threshold: threshold.0.into(),
+        })
+        .await?;
+
+        io.set_compression(threshold);
+    }
+
     io.send_packet(&LoginSuccessS2c {
         uuid: info.uuid,
         username: info.username.as_str().into(),
@@ -315,6 +572,45 @@ async fn handle_login(
     Ok(Some((info, cleanup)))
 }
 
+/// Classifies errors from reading the player data forwarded by a proxy, keeping
+/// connection errors as they are.
+fn forwarding_error(e: anyhow::Error) -> anyhow::Error {
+    if e.is::<io::Error>() || e.is::<LoginFailureReason>() {
+        e
+    } else {
+        LoginFailureReason::Forwarding(format!("{e:#}")).into()
+    }
+}
+
+/// Marks a UUID as logged in for as long as it's alive.
+struct OnlineUuidGuard {
+    shared: SharedNetworkState,
+    uuid: Uuid,
+}
+
+impl OnlineUuidGuard {
+    /// Returns `None` if a client with `uuid` is already logged in.
+    fn new(shared: &SharedNetworkState, uuid: Uuid) -> Option<Self> {
+        let inserted = shared.0.online_uuids.lock().unwrap().insert(uuid);
+
+        inserted.then(|| Self {
+            shared: shared.clone(),
+            uuid,
+        })
+    }
+}
+
+impl Drop for OnlineUuidGuard {
+    fn drop(&mut self) {
+        self.shared
+            .0
+            .online_uuids
+            .lock()
+            .unwrap()
+            .remove(&self.uuid);
+    }
+}
+
 /// Login procedure for online mode.
 async fn login_online(
     shared: &SharedNetworkState,
@@ -340,22 +636,25 @@ async fn login_online(
         .0
         .rsa_key
         .decrypt(Pkcs1v15Encrypt, shared_secret)
+        .map_err(|_| LoginFailureReason::Encryption)
         .context("failed to decrypt shared secret")?;
 
     let verify_token = shared
         .0
         .rsa_key
         .decrypt(Pkcs1v15Encrypt, encrypted_verify_token)
+        .map_err(|_| LoginFailureReason::Encryption)
         .context("failed to decrypt verify token")?;
 
-    ensure!(
-        my_verify_token.as_slice() == verify_token,
-        "verify tokens do not match"
-    );
+    if my_verify_token.as_slice() != verify_token {
+        return Err(anyhow::Error::new(LoginFailureReason::Encryption))
+            .context("verify tokens do not match");
+    }
 
     let crypt_key: [u8; 16] = shared_secret
         .as_slice()
         .try_into()
+        .map_err(|_| LoginFailureReason::Encryption)
         .context("shared secret has the wrong length")?;
 
     io.enable_encryption(&crypt_key);
@@ -377,7 +676,17 @@ async fn login_online(
         )
         .await;
 
-    let resp = shared.0.http_client.get(url).send().await?;
+    let resp = match shared.0.http_client.get(url).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let reason = Text::translate(keys::MULTIPLAYER_DISCONNECT_AUTHSERVERS_DOWN, []);
+            io.send_packet(&LoginDisconnectS2c {
+                reason: reason.into(),
+            })
+            .await?;
+            return Err(LoginFailureReason::SessionServer(e.to_string()).into());
+        }
+    };
 
     match resp.status() {
         StatusCode::OK => {}
@@ -387,10 +696,17 @@ async fn login_online(
                 reason: reason.into(),
             })
             .await?;
-            bail!("session server could not verify username");
+            bail!(LoginFailureReason::Unverified);
         }
         status => {
-            bail!("session server GET request failed (status code {status})");
+            let reason = Text::translate(keys::MULTIPLAYER_DISCONNECT_AUTHSERVERS_DOWN, []);
+            io.send_packet(&LoginDisconnectS2c {
+                reason: reason.into(),
+            })
+            .await?;
+            bail!(LoginFailureReason::SessionServer(format!(
+                "GET request failed (status code {status})"
+            )));
         }
     }
 
@@ -401,15 +717,22 @@ async fn login_online(
         properties: Vec<Property>,
     }
 
-    let profile: GameProfile = resp.json().await.context("parsing game profile")?;
+    let profile: GameProfile = resp
+        .json()
+        .await
+        .map_err(|e| LoginFailureReason::SessionServer(format!("invalid game profile: {e}")))?;
 
-    ensure!(profile.name == username, "usernames do not match");
+    ensure!(
+        profile.name == username,
+        LoginFailureReason::UsernameMismatch
+    );
 
     Ok(NewClientInfo {
         uuid: profile.id,
         username,
         ip: remote_addr.ip(),
         properties: Properties(profile.properties),
+        forwarding: None,
     })
 }
 
@@ -429,6 +752,7 @@ fn login_offline(remote_addr: SocketAddr, username: String) -> anyhow::Result<Ne
         username,
         properties: Default::default(),
         ip: remote_addr.ip(),
+        forwarding: None,
     })
 }
 
@@ -439,7 +763,7 @@ fn login_bungeecord(
     username: String,
 ) -> anyhow::Result<NewClientInfo> {
     // Get data from server_address field of the handshake
-    let data = server_address.split('\0').take(4).collect::<Vec<_>>();
+    let data = server_address.split('\0').collect::<Vec<_>>();
 
     // Ip of player, only given if ip_forward on bungee is true
     let ip = match data.get(1) {
@@ -462,22 +786,42 @@ fn login_bungeecord(
         None => vec![],
     };
 
+    let mut properties = Properties(properties);
+    let bungeeguard_token = take_bungeeguard_token(&mut properties);
+
+    let extra = data.iter().skip(4).map(|&s| s.to_owned()).collect();
+
     Ok(NewClientInfo {
         uuid,
         username,
-        properties: Properties(properties),
+        properties,
         ip,
+        forwarding: Some(ProxyForwarding::BungeeCord {
+            bungeeguard_token,
+            extra,
+        }),
     })
 }
 
+/// Removes the token BungeeGuard adds to the properties, so it isn't sent to
+/// other clients.
+fn take_bungeeguard_token(properties: &mut Properties) -> Option<String> {
+    let idx = properties
+        .0
+        .iter()
+        .position(|p| p.name == "bungeeguard-token")?;
+
+    Some(properties.0.remove(idx).value)
+}
+
 /// Login procedure for Velocity.
 async fn login_velocity(
     io: &mut PacketIo,
     username: String,
     velocity_secret: &str,
 ) -> anyhow::Result<NewClientInfo> {
-    const VELOCITY_MIN_SUPPORTED_VERSION: u8 = 1;
-    const VELOCITY_MODERN_FORWARDING_WITH_KEY_V2: i32 = 3;
+    // The proxy uses the highest version up to this one the client supports.
+    const VELOCITY_REQUESTED_VERSION: u8 = VELOCITY_MODERN_FORWARDING_WITH_KEY_V2 as u8;
 
     let message_id: i32 = 0; // TODO: make this random?
 
@@ -485,7 +829,7 @@ async fn login_velocity(
     io.send_packet(&LoginQueryRequestS2c {
         message_id: VarInt(message_id),
         channel: ident!("velocity:player_info").into(),
-        data: RawBytes(&[VELOCITY_MIN_SUPPORTED_VERSION]).into(),
+        data: RawBytes(&[VELOCITY_REQUESTED_VERSION]).into(),
     })
     .await?;
 
@@ -532,23 +876,290 @@ async fn login_velocity(
     let properties = Vec::<Property>::decode(&mut data_without_signature)
         .context("decoding velocity game profile properties")?;
 
-    if version >= VELOCITY_MODERN_FORWARDING_WITH_KEY_V2 {
-        // TODO
-    }
+    let player_key = decode_velocity_player_key(version, &mut data_without_signature)
+        .context("decoding velocity player key")?;
 
     Ok(NewClientInfo {
         uuid,
         username,
         properties: Properties(properties),
         ip: remote_addr,
+        forwarding: Some(ProxyForwarding::Velocity {
+            version,
+            player_key,
+            extra: data_without_signature.to_vec(),
+        }),
     })
 }
 
+const VELOCITY_MODERN_FORWARDING_WITH_KEY: i32 = 2;
+const VELOCITY_MODERN_FORWARDING_WITH_KEY_V2: i32 = 3;
+const VELOCITY_MODERN_LAZY_SESSION: i32 = 4;
+
+/// Decodes the signed player key which follows the properties in versions 2
+/// and 3 of the Velocity forwarding protocol.
+fn decode_velocity_player_key(
+    version: i32,
+    r: &mut &[u8],
+) -> anyhow::Result<Option<VelocityPlayerKey>> {
+    if !(VELOCITY_MODERN_FORWARDING_WITH_KEY..VELOCITY_MODERN_LAZY_SESSION).contains(&version) {
+        return Ok(None);
+    }
+
+    let expires_at = i64::decode(r)?;
+    let public_key = <&[u8]>::decode(r)?.to_vec();
+    let signature = <&[u8]>::decode(r)?.to_vec();
+
+    // The key may have been signed for a different UUID than the one the
+    // player has on the proxy.
+    let holder = if version >= VELOCITY_MODERN_FORWARDING_WITH_KEY_V2 && bool::decode(r)? {
+        Some(Uuid::decode(r)?)
+    } else {
+        None
+    };
+
+    Ok(Some(VelocityPlayerKey {
+        expires_at,
+        public_key,
+        signature,
+        holder,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::net::Ipv6Addr;
+
+    use bevy_app::App;
     use sha1::Digest;
+    use valence_protocol::{CompressionThreshold, Encode};
+    use valence_server::ServerPlugin;
 
     use super::*;
+    use crate::transport::LOCAL_ADDRESS;
+    use crate::{async_trait, build_plugin, CleanupFn, NetworkCallbacks, NetworkSettings};
+
+    /// Builds the network state for the current tokio runtime without starting
+    /// any listeners.
+    fn network_state(settings: NetworkSettings) -> SharedNetworkState {
+        let mut app = App::new();
+
+        app.add_plugins(ServerPlugin)
+            .insert_resource(NetworkSettings {
+                tokio_handle: Some(tokio::runtime::Handle::current()),
+                ..settings
+            });
+
+        build_plugin(&mut app).unwrap();
+
+        app.world().resource::<SharedNetworkState>().clone()
+    }
+
+    /// Connects a client through an in-memory pipe and starts logging in.
+    async fn start_login(shared: &SharedNetworkState, username: &str) -> PacketIo {
+        let (client, server) = tokio::io::duplex(4096);
+
+        shared.accept_stream(server, LOCAL_ADDRESS);
+
+        send_login_hello(Box::new(client), username).await
+    }
+
+    /// Starts logging in over a stream already connected to the server.
+    async fn send_login_hello(stream: Box<dyn Stream>, username: &str) -> PacketIo {
+        let mut io = PacketIo::new(stream, PacketEncoder::new(), PacketDecoder::new());
+
+        io.send_packet(&HandshakeC2s {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_address: "localhost".into(),
+            server_port: 25565,
+            next_state: HandshakeNextState::Login,
+        })
+        .await
+        .unwrap();
+
+        io.send_packet(&LoginHelloC2s {
+            username: username.into(),
+            profile_id: None,
+        })
+        .await
+        .unwrap();
+
+        io
+    }
+
+    /// Receives the rest of an offline mode login, checking that it succeeds.
+    async fn finish_login(io: &mut PacketIo, username: &str) {
+        let compression = io.recv_packet::<LoginCompressionS2c>().await.unwrap();
+        io.set_compression(CompressionThreshold(compression.threshold.0));
+
+        let success = io.recv_packet::<LoginSuccessS2c>().await.unwrap();
+        assert_eq!(success.uuid, offline_uuid(username).unwrap());
+        assert_eq!(*success.username, username);
+    }
+
+    async fn next_login_failure(shared: &SharedNetworkState) -> LoginFailureEvent {
+        shared.0.login_failures_recv.recv_async().await.unwrap()
+    }
+
+    struct RejectLogins;
+
+    #[async_trait]
+    impl NetworkCallbacks for RejectLogins {
+        async fn login(
+            &self,
+            _shared: &SharedNetworkState,
+            _info: &NewClientInfo,
+        ) -> Result<CleanupFn, Text> {
+            Err("go away".into_text())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn login_failure_timeout() {
+        let shared = network_state(NetworkSettings::default());
+
+        // Never answer the encryption request.
+        let _io = start_login(&shared, "player").await;
+
+        let event = next_login_failure(&shared).await;
+
+        assert_eq!(event.ip, LOCAL_ADDRESS.ip());
+        assert_eq!(event.username.as_deref(), Some("player"));
+        assert_eq!(event.reason, LoginFailureReason::Timeout);
+    }
+
+    #[tokio::test]
+    async fn login_failure_encryption() {
+        let shared = network_state(NetworkSettings::default());
+
+        let mut io = start_login(&shared, "player").await;
+
+        io.recv_packet::<LoginHelloS2c>().await.unwrap();
+
+        // Not encrypted with the server's public key.
+        io.send_packet(&LoginKeyC2s {
+            shared_secret: &[0; 16],
+            verify_token: &[0; 16],
+        })
+        .await
+        .unwrap();
+
+        let event = next_login_failure(&shared).await;
+
+        assert_eq!(event.username.as_deref(), Some("player"));
+        assert_eq!(event.reason, LoginFailureReason::Encryption);
+    }
+
+    #[tokio::test]
+    async fn login_failure_rejected() {
+        let shared = network_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            callbacks: RejectLogins.into(),
+            ..Default::default()
+        });
+
+        let mut io = start_login(&shared, "player").await;
+
+        let packet = io.recv_packet::<LoginDisconnectS2c>().await.unwrap();
+        assert_eq!(*packet.reason, "go away".into_text());
+
+        let event = next_login_failure(&shared).await;
+
+        assert_eq!(event.username.as_deref(), Some("player"));
+        assert_eq!(
+            event.reason,
+            LoginFailureReason::Rejected("go away".into_text())
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_uuids() {
+        let shared = network_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            ..Default::default()
+        });
+
+        let _first = start_login(&shared, "player").await;
+        let _second = start_login(&shared, "player").await;
+
+        // Both clients are let in by default.
+        shared.0.new_clients_recv.recv_async().await.unwrap();
+        shared.0.new_clients_recv.recv_async().await.unwrap();
+
+        let shared = network_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            reject_duplicate_uuids: true,
+            ..Default::default()
+        });
+
+        let _first = start_login(&shared, "player").await;
+        let first_client = shared.0.new_clients_recv.recv_async().await.unwrap();
+
+        let _second = start_login(&shared, "player").await;
+
+        let event = next_login_failure(&shared).await;
+
+        assert_eq!(
+            event.reason,
+            LoginFailureReason::DuplicateUuid(offline_uuid("player").unwrap())
+        );
+
+        // The UUID can be used again once the first client is gone.
+        drop(first_client);
+
+        let _third = start_login(&shared, "player").await;
+        shared.0.new_clients_recv.recv_async().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn login_through_stream() {
+        let shared = network_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            ..Default::default()
+        });
+
+        let mut io = start_login(&shared, "player").await;
+        finish_login(&mut io, "player").await;
+
+        let (args, forwarding) = shared.0.new_clients_recv.recv_async().await.unwrap();
+
+        assert_eq!(args.username, "player");
+        assert_eq!(args.uuid, offline_uuid("player").unwrap());
+        assert_eq!(args.ip, LOCAL_ADDRESS.ip());
+        assert_eq!(args.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(forwarding, None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn login_through_unix_socket() {
+        let path = std::env::temp_dir().join(format!("valence-test-{}.sock", std::process::id()));
+
+        let shared = network_state(NetworkSettings {
+            connection_mode: ConnectionMode::Offline,
+            ..Default::default()
+        });
+
+        tokio::spawn(do_unix_accept_loop(shared.clone(), path.clone()));
+
+        // The listener is bound in the background.
+        let stream = loop {
+            match tokio::net::UnixStream::connect(&path).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        let mut io = send_login_hello(Box::new(stream), "player").await;
+        finish_login(&mut io, "player").await;
+
+        let (args, _) = shared.0.new_clients_recv.recv_async().await.unwrap();
+
+        assert_eq!(args.username, "player");
+        assert_eq!(args.ip, LOCAL_ADDRESS.ip());
+
+        let _ = std::fs::remove_file(&path);
+    }
 
     #[test]
     fn auth_digest_usernames() {
@@ -565,4 +1176,164 @@ mod tests {
             "88e16a1019277b15d58faf0541e11910eb756f6"
         );
     }
+
+    #[test]
+    fn classify_login_failures() {
+        let e = anyhow::Error::new(LoginFailureReason::Encryption).context("verify tokens differ");
+        assert_eq!(login_failure_reason(&e), LoginFailureReason::Encryption);
+
+        let e = anyhow::Error::new(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert_eq!(login_failure_reason(&e), LoginFailureReason::Disconnected);
+
+        let e = forwarding_error(anyhow::anyhow!("missing plugin response data"));
+        assert_eq!(
+            login_failure_reason(&e),
+            LoginFailureReason::Forwarding("missing plugin response data".into())
+        );
+
+        // Connection errors aren't blamed on the proxy.
+        let e = forwarding_error(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        assert_eq!(login_failure_reason(&e), LoginFailureReason::Disconnected);
+    }
+
+    #[tokio::test]
+    async fn dual_stack_listeners() {
+        if bind_listener(SocketAddr::from((Ipv6Addr::LOCALHOST, 0)), true).is_err() {
+            // IPv6 isn't available on this machine.
+            return;
+        }
+
+        let v4 = bind_listener("0.0.0.0:0".parse().unwrap(), false).unwrap();
+        let port = v4.local_addr().unwrap().port();
+
+        let v4_addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let v6_addr = SocketAddr::from(([0; 16], port));
+        let addresses = [v4_addr, v6_addr];
+
+        assert!(!only_v6(v4_addr, &addresses));
+        assert!(only_v6(v6_addr, &addresses));
+        assert!(!only_v6(v6_addr, &[v6_addr]));
+
+        // Only works because the IPv6 listener leaves IPv4 to the other one.
+        let v6 = bind_listener(v6_addr, only_v6(v6_addr, &addresses)).unwrap();
+        assert_eq!(v6.local_addr().unwrap(), v6_addr);
+    }
+
+    #[test]
+    fn bungeecord_forwarding_extra_fields() {
+        let remote_addr: SocketAddr = "127.0.0.1:25565".parse().unwrap();
+        let uuid = Uuid::from_u128(1);
+
+        let address = format!(
+            "localhost\010.0.0.1\0{}\0[{{\"name\":\"premium\",\"value\":\"true\"}}]\0extra",
+            uuid.simple()
+        );
+
+        let info = login_bungeecord(remote_addr, &address, "player".into()).unwrap();
+
+        assert_eq!(info.ip, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(info.uuid, uuid);
+        assert_eq!(info.properties.get("premium").unwrap().value, "true");
+        assert_eq!(
+            info.forwarding,
+            Some(ProxyForwarding::BungeeCord {
+                bungeeguard_token: None,
+                extra: vec!["extra".into()]
+            })
+        );
+    }
+
+    #[test]
+    fn bungeecord_forwarding_bungeeguard_token() {
+        let remote_addr: SocketAddr = "127.0.0.1:25565".parse().unwrap();
+        let uuid = Uuid::from_u128(1);
+
+        let address = format!(
+            "localhost\010.0.0.1\0{}\0[{{\"name\":\"textures\",\"value\":\"skin\"}},\
+             {{\"name\":\"bungeeguard-token\",\"value\":\"secret\"}}]",
+            uuid.simple()
+        );
+
+        let info = login_bungeecord(remote_addr, &address, "player".into()).unwrap();
+
+        assert_eq!(
+            info.forwarding,
+            Some(ProxyForwarding::BungeeCord {
+                bungeeguard_token: Some("secret".into()),
+                extra: vec![]
+            })
+        );
+
+        // The token isn't passed on with the other properties.
+        assert!(info.properties.get("bungeeguard-token").is_none());
+        assert_eq!(info.properties.textures().unwrap().value, "skin");
+    }
+
+    fn encode_velocity_player_key(key: &VelocityPlayerKey, with_holder: bool) -> Vec<u8> {
+        let mut buf = vec![];
+
+        key.expires_at.encode(&mut buf).unwrap();
+        key.public_key.encode(&mut buf).unwrap();
+        key.signature.encode(&mut buf).unwrap();
+
+        if with_holder {
+            key.holder.is_some().encode(&mut buf).unwrap();
+
+            if let Some(holder) = key.holder {
+                holder.encode(&mut buf).unwrap();
+            }
+        }
+
+        buf
+    }
+
+    #[test]
+    fn velocity_forwarding_player_key() {
+        let key = VelocityPlayerKey {
+            expires_at: 1_700_000_000_000,
+            public_key: vec![1, 2, 3],
+            signature: vec![4, 5],
+            holder: None,
+        };
+
+        let mut data = encode_velocity_player_key(&key, false);
+        data.push(42);
+
+        let mut r = data.as_slice();
+        assert_eq!(
+            decode_velocity_player_key(VELOCITY_MODERN_FORWARDING_WITH_KEY, &mut r).unwrap(),
+            Some(key.clone())
+        );
+        // Anything after the key is left over.
+        assert_eq!(r, [42]);
+
+        let key = VelocityPlayerKey {
+            holder: Some(Uuid::from_u128(7)),
+            ..key
+        };
+
+        let data = encode_velocity_player_key(&key, true);
+
+        let mut r = data.as_slice();
+        assert_eq!(
+            decode_velocity_player_key(VELOCITY_MODERN_FORWARDING_WITH_KEY_V2, &mut r).unwrap(),
+            Some(key)
+        );
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn velocity_forwarding_without_player_key() {
+        // Versions without a key leave the data alone.
+        for version in [1, VELOCITY_MODERN_LAZY_SESSION] {
+            let mut r: &[u8] = &[1, 2, 3];
+
+            assert_eq!(decode_velocity_player_key(version, &mut r).unwrap(), None);
+            assert_eq!(r, [1, 2, 3]);
+        }
+
+        // A truncated key is an error.
+        let mut r: &[u8] = &[0, 0, 1];
+        assert!(decode_velocity_player_key(VELOCITY_MODERN_FORWARDING_WITH_KEY, &mut r).is_err());
+    }
 }
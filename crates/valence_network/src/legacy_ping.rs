@@ -2,9 +2,9 @@ use std::io;
 use std::net::SocketAddr;
 use std::time::Duration;
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tokio::time::sleep;
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{timeout_at, Instant};
 
 use crate::{ServerListLegacyPing, SharedNetworkState};
 
@@ -62,16 +62,21 @@ enum PingFormat {
     Pre1_7, // 1.6
 }
 
-/// Returns true if legacy ping detected and handled
-pub(crate) async fn try_handle_legacy_ping(
+/// Returns true if legacy ping detected and handled. The bytes read from the
+/// stream are left in `buf` otherwise, after any bytes that were already in
+/// it.
+pub(crate) async fn try_handle_legacy_ping<S: AsyncRead + AsyncWrite + Unpin>(
     shared: &SharedNetworkState,
-    stream: &mut TcpStream,
+    stream: &mut S,
+    buf: &mut BytesMut,
     remote_addr: SocketAddr,
 ) -> io::Result<bool> {
-    let mut temp_buf = [0_u8; 3];
-    let mut n = stream.peek(&mut temp_buf).await?;
+    if buf.is_empty() {
+        buf.reserve(256);
+        stream.read_buf(buf).await?;
+    }
 
-    if let [0xfe] | [0xfe, 0x01] = &temp_buf[..n] {
+    if let [0xfe] | [0xfe, 0x01] = &buf[..] {
         // This could mean one of following things:
         // 1. The beginning of a normal handshake packet, not fully received yet though
         // 2. The beginning of the 1.6 legacy ping, not fully received yet either
@@ -97,11 +102,19 @@ pub(crate) async fn try_handle_legacy_ping(
         // In my opinion, 1 is insignificant, and 2/3 are so rare that they are
         // effectively insignificant too. Network IO is just not that reliable
         // at this level, the connection may be lost as well or something at this point.
-        sleep(Duration::from_millis(10)).await;
-        n = stream.peek(&mut temp_buf).await?;
+        let deadline = Instant::now() + Duration::from_millis(10);
+
+        while buf.len() < 3 {
+            match timeout_at(deadline, stream.read_buf(buf)).await {
+                Ok(Ok(0)) | Err(_) => break,
+                Ok(res) => {
+                    res?;
+                }
+            }
+        }
     }
 
-    let format = match &temp_buf[..n] {
+    let format = match &buf[..buf.len().min(3)] {
         [0xfe] => PingFormat::Pre1_4,
         [0xfe, 0x01] => PingFormat::Pre1_6,
         [0xfe, 0x01, 0xfa] => PingFormat::Pre1_7,
@@ -109,7 +122,8 @@ pub(crate) async fn try_handle_legacy_ping(
     };
 
     let payload = match format {
-        PingFormat::Pre1_7 => read_payload(stream).await?,
+        // The payload starts with the bytes that were already read.
+        PingFormat::Pre1_7 => read_payload(&mut (&buf[..]).chain(&mut *stream)).await?,
         PingFormat::Pre1_6 => ServerListLegacyPingPayload::Pre1_6,
         PingFormat::Pre1_4 => ServerListLegacyPingPayload::Pre1_4,
     };
@@ -182,7 +196,9 @@ pub(crate) async fn try_handle_legacy_ping(
 }
 
 // Reads the payload of a 1.6 legacy ping
-async fn read_payload(stream: &mut TcpStream) -> io::Result<ServerListLegacyPingPayload> {
+async fn read_payload<R: AsyncRead + Unpin>(
+    stream: &mut R,
+) -> io::Result<ServerListLegacyPingPayload> {
     // consume the first 29 useless bytes of this amazing protocol
     stream.read_exact(&mut [0_u8; 29]).await?;
 
@@ -1,14 +1,20 @@
 #![doc = include_str!("../README.md")]
 
+mod bandwidth;
 mod byte_channel;
 mod connect;
 mod legacy_ping;
 mod packet_io;
+mod proxy_protocol;
+pub mod server_list;
+pub mod transport;
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::Context;
@@ -23,14 +29,17 @@ use rand::rngs::OsRng;
 use rsa::traits::PublicKeyParts;
 use rsa::RsaPrivateKey;
 use serde::Serialize;
+use thiserror::Error;
 use tokio::net::UdpSocket;
 use tokio::runtime::{Handle, Runtime};
 use tokio::sync::Semaphore;
 use tokio::time;
 use tracing::error;
+use transport::Stream;
 use uuid::Uuid;
 use valence_protocol::text::IntoText;
-use valence_server::client::{ClientBundle, ClientBundleArgs, Properties, SpawnClientsSet};
+use valence_server::client::{Client, ClientBundle, ClientBundleArgs, Properties, SpawnClientsSet};
+use valence_server::vanish::Vanished;
 use valence_server::{CompressionThreshold, Server, Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
 
 pub struct NetworkPlugin;
@@ -44,17 +53,20 @@ impl Plugin for NetworkPlugin {
 }
 
 fn build_plugin(app: &mut App) -> anyhow::Result<()> {
-    let threshold = app
+    let server = app
         .world()
         .get_resource::<Server>()
-        .context("missing server resource")?
-        .compression_threshold();
+        .context("missing server resource")?;
+
+    let threshold = server.compression_threshold();
+    let compression_level = server.compression_level();
 
     let settings = app
         .world_mut()
         .get_resource_or_insert_with(NetworkSettings::default);
 
     let (new_clients_send, new_clients_recv) = flume::bounded(64);
+    let (login_failures_send, login_failures_recv) = flume::bounded(256);
 
     let rsa_key = RsaPrivateKey::new(&mut OsRng, 1024)?;
 
@@ -76,20 +88,32 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
 
     let shared = SharedNetworkState(Arc::new(SharedNetworkStateInner {
         callbacks: settings.callbacks.clone(),
-        address: settings.address,
+        addresses: std::iter::once(settings.address)
+            .chain(settings.additional_addresses.iter().copied())
+            .collect(),
+        unix_socket: settings.unix_socket.clone(),
+        proxy_protocol: settings.proxy_protocol,
         incoming_byte_limit: settings.incoming_byte_limit,
         outgoing_byte_limit: settings.outgoing_byte_limit,
+        outgoing_bandwidth_limit: settings.outgoing_bandwidth_limit,
+        offload_encryption: settings.offload_encryption,
+        reject_duplicate_uuids: settings.reject_duplicate_uuids,
         connection_sema: Arc::new(Semaphore::new(
             settings.max_connections.min(Semaphore::MAX_PERMITS),
         )),
         player_count: AtomicUsize::new(0),
+        hidden_player_count: AtomicUsize::new(0),
         max_players: settings.max_players,
         connection_mode: settings.connection_mode.clone(),
         threshold,
+        compression_level,
         tokio_handle,
         _tokio_runtime: runtime,
         new_clients_send,
         new_clients_recv,
+        login_failures_send,
+        login_failures_recv,
+        online_uuids: Mutex::new(HashSet::new()),
         rsa_key,
         public_key_der,
         http_client: reqwest::Client::new(),
@@ -101,8 +125,22 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
     let start_accept_loop = move |shared: Res<SharedNetworkState>| {
         let _guard = shared.0.tokio_handle.enter();
 
-        // Start accepting new connections.
-        tokio::spawn(do_accept_loop(shared.clone()));
+        // Start accepting new connections on every address.
+        for &address in &shared.0.addresses {
+            tokio::spawn(do_accept_loop(shared.clone(), address));
+        }
+
+        if let Some(path) = &shared.0.unix_socket {
+            #[cfg(unix)]
+            tokio::spawn(connect::do_unix_accept_loop(shared.clone(), path.clone()));
+
+            #[cfg(not(unix))]
+            error!(
+                "cannot listen on unix socket `{}`: unix sockets are not supported on this \
+                 platform",
+                path.display()
+            );
+        }
     };
 
     let start_broadcast_to_lan_loop = move |shared: Res<SharedNetworkState>| {
@@ -115,12 +153,33 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
     let spawn_new_clients = move |world: &mut World| {
         for _ in 0..shared.0.new_clients_recv.len() {
             match shared.0.new_clients_recv.try_recv() {
-                Ok(args) => world.spawn(ClientBundle::new(args)),
+                Ok((args, forwarding)) => {
+                    let mut client = world.spawn(ClientBundle::new(args));
+
+                    if let Some(forwarding) = forwarding {
+                        client.insert(forwarding);
+                    }
+                }
                 Err(_) => break,
             };
         }
     };
 
+    // System for sending the login failures from the accept loop as events.
+    let send_login_failure_events =
+        |shared: Res<SharedNetworkState>, mut events: EventWriter<LoginFailureEvent>| {
+            events.send_batch(shared.0.login_failures_recv.try_iter());
+        };
+
+    // System for counting the clients hidden from the server list.
+    let update_hidden_player_count =
+        |shared: Res<SharedNetworkState>, clients: Query<&Vanished, With<Client>>| {
+            let count = clients.iter().filter(|v| v.hide_from_server_list).count();
+            shared.0.hidden_player_count.store(count, Ordering::Relaxed);
+        };
+
+    app.add_event::<LoginFailureEvent>();
+
     // Start accepting connections in `PostStartup` to allow user startup code to
     // run first.
     app.add_systems(PostStartup, start_accept_loop);
@@ -131,6 +190,10 @@ fn build_plugin(app: &mut App) -> anyhow::Result<()> {
     // Spawn new clients before the event loop starts.
     app.add_systems(PreUpdate, spawn_new_clients.in_set(SpawnClientsSet));
 
+    app.add_systems(PreUpdate, send_login_failure_events);
+
+    app.add_systems(PostUpdate, update_hidden_player_count);
+
     Ok(())
 }
 
@@ -146,31 +209,88 @@ impl SharedNetworkState {
         &self.0.player_count
     }
 
+    /// The number of [`Vanished`] clients hidden from the server list.
+    pub fn hidden_player_count(&self) -> usize {
+        self.0.hidden_player_count.load(Ordering::Relaxed)
+    }
+
+    /// The number of players shown in the server list, which excludes the
+    /// [hidden players](Self::hidden_player_count).
+    pub fn visible_player_count(&self) -> usize {
+        self.player_count()
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.hidden_player_count())
+    }
+
     pub fn max_players(&self) -> usize {
         self.0.max_players
     }
+
+    /// The socket addresses the server listens on, starting with
+    /// [`NetworkSettings::address`].
+    pub fn addresses(&self) -> &[SocketAddr] {
+        &self.0.addresses
+    }
+
+    /// Handles a new connection from any [`Stream`], as if it was accepted by
+    /// one of the server's listeners. `remote_addr` is the address reported
+    /// for the client, such as [`LOCAL_ADDRESS`](transport::LOCAL_ADDRESS).
+    ///
+    /// Use one half of a [`tokio::io::duplex`] pipe to connect a proxy or a
+    /// test client running in the same process, without going through the
+    /// network.
+    pub fn accept_stream<S: Stream>(&self, stream: S, remote_addr: SocketAddr) {
+        let _guard = self.0.tokio_handle.enter();
+
+        tokio::spawn(connect::accept_stream(
+            self.clone(),
+            Box::new(stream),
+            remote_addr,
+        ));
+    }
+
+    /// The [compression threshold](Server::compression_threshold) of the
+    /// server.
+    pub fn compression_threshold(&self) -> CompressionThreshold {
+        self.0.threshold
+    }
 }
 struct SharedNetworkStateInner {
     callbacks: ErasedNetworkCallbacks,
-    address: SocketAddr,
+    addresses: Vec<SocketAddr>,
+    unix_socket: Option<PathBuf>,
+    proxy_protocol: bool,
     incoming_byte_limit: usize,
     outgoing_byte_limit: usize,
+    outgoing_bandwidth_limit: Option<u32>,
+    offload_encryption: bool,
+    reject_duplicate_uuids: bool,
     /// Limits the number of simultaneous connections to the server before the
     /// play state.
     connection_sema: Arc<Semaphore>,
     //// The number of clients in the play state, past the login state.
     player_count: AtomicUsize,
+    /// The number of clients left out of `player_count` in the server list.
+    hidden_player_count: AtomicUsize,
     max_players: usize,
     connection_mode: ConnectionMode,
     threshold: CompressionThreshold,
+    compression_level: u32,
     tokio_handle: Handle,
     // Holding a runtime handle is not enough to keep tokio working. We need
     // to store the runtime here so we don't drop it.
     _tokio_runtime: Option<Runtime>,
     /// Sender for new clients past the login stage.
-    new_clients_send: Sender<ClientBundleArgs>,
+    new_clients_send: Sender<(ClientBundleArgs, Option<ProxyForwarding>)>,
     /// Receiver for new clients past the login stage.
-    new_clients_recv: Receiver<ClientBundleArgs>,
+    new_clients_recv: Receiver<(ClientBundleArgs, Option<ProxyForwarding>)>,
+    /// Sender for failed logins, to be sent as [`LoginFailureEvent`]s.
+    login_failures_send: Sender<LoginFailureEvent>,
+    /// Receiver for failed logins.
+    login_failures_recv: Receiver<LoginFailureEvent>,
+    /// The UUIDs of the clients past the login stage, if
+    /// `reject_duplicate_uuids` is enabled.
+    online_uuids: Mutex<HashSet<Uuid>>,
     /// The RSA keypair used for encryption with clients.
     rsa_key: RsaPrivateKey,
     /// The public part of `rsa_key` encoded in DER, which is an ASN.1 format.
@@ -193,6 +313,104 @@ pub struct NewClientInfo {
     /// The client's properties from the game profile. Typically contains a
     /// `textures` property with the skin and cape of the player.
     pub properties: Properties,
+    /// The extra data forwarded by the proxy in the
+    /// [`BungeeCord`](ConnectionMode::BungeeCord) and
+    /// [`Velocity`](ConnectionMode::Velocity) connection modes.
+    pub forwarding: Option<ProxyForwarding>,
+}
+
+/// The data forwarded by a proxy about a client besides its username, UUID,
+/// address and [`Properties`], which are part of [`NewClientInfo`]. Inserted on
+/// clients which logged in through a proxy.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub enum ProxyForwarding {
+    BungeeCord {
+        /// The token added to the properties by BungeeGuard. It's removed from
+        /// the [`Properties`] of the client, so it isn't sent to other
+        /// clients.
+        bungeeguard_token: Option<String>,
+        /// The fields after the properties in the handshake server address,
+        /// which some BungeeCord forks append.
+        extra: Vec<String>,
+    },
+    Velocity {
+        /// The version of the Velocity forwarding protocol.
+        version: i32,
+        /// The signed chat key of the player. Only forwarded in versions 2
+        /// and 3 of the forwarding protocol, which Velocity uses for clients
+        /// older than 1.19.3.
+        player_key: Option<VelocityPlayerKey>,
+        /// Any data after the fields known to Valence.
+        extra: Vec<u8>,
+    },
+}
+
+/// The signed chat key of a player forwarded by Velocity.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VelocityPlayerKey {
+    /// When the key expires, in milliseconds since the Unix epoch.
+    pub expires_at: i64,
+    /// The public key, encoded in DER.
+    pub public_key: Vec<u8>,
+    /// The signature of the key by Mojang.
+    pub signature: Vec<u8>,
+    /// The UUID the key was signed for, if it was forwarded.
+    pub holder: Option<Uuid>,
+}
+
+/// An [`Event`] sent when a client fails to log in. Useful for tracking join
+/// problems and noticing outages of the session server.
+///
+/// Events that can't be delivered because too many logins failed within a
+/// single tick are dropped.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct LoginFailureEvent {
+    /// The address the client connected from.
+    pub ip: IpAddr,
+    /// The username of the client, if it got far enough to send one.
+    pub username: Option<String>,
+    pub reason: LoginFailureReason,
+}
+
+/// Why a client failed to log in.
+#[derive(Clone, PartialEq, Debug, Error)]
+#[non_exhaustive]
+pub enum LoginFailureReason {
+    /// The login took too long to complete.
+    #[error("login timed out")]
+    Timeout,
+    /// The client closed the connection.
+    #[error("client disconnected")]
+    Disconnected,
+    /// The client is on a different version of the game.
+    #[error("unsupported protocol version {0}")]
+    UnsupportedVersion(i32),
+    /// The client sent an invalid shared secret or verify token.
+    #[error("failed to enable encryption")]
+    Encryption,
+    /// The session server doesn't know about the client, usually because it
+    /// isn't using a legitimate account.
+    #[error("session server could not verify username")]
+    Unverified,
+    /// The session server couldn't be reached or returned an error.
+    #[error("session server request failed: {0}")]
+    SessionServer(String),
+    /// The username the client sent doesn't match its game profile.
+    #[error("username does not match game profile")]
+    UsernameMismatch,
+    /// The player data forwarded by the proxy is missing or invalid.
+    #[error("invalid proxy forwarding data: {0}")]
+    Forwarding(String),
+    /// A client with the same UUID is already logged in. Only used if
+    /// [`NetworkSettings::reject_duplicate_uuids`] is enabled.
+    #[error("a client with UUID {0} is already logged in")]
+    DuplicateUuid(Uuid),
+    /// [`NetworkCallbacks::login`] refused to let the client join.
+    #[error("rejected by login callback")]
+    Rejected(Text),
+    /// The client sent invalid data or the connection failed.
+    #[error("{0}")]
+    Other(String),
 }
 
 /// Settings for [`NetworkPlugin`]. Note that mutations to these fields have no
@@ -226,6 +444,46 @@ pub struct NetworkSettings {
     ///
     /// `0.0.0.0:25565`, which will listen on every available network interface.
     pub address: SocketAddr,
+    /// More socket addresses the server will be bound to besides
+    /// [`address`](Self::address), each with its own listener. Use these to
+    /// listen on several specific network interfaces, or on both IPv4 and
+    /// IPv6.
+    ///
+    /// IPv6 addresses also accept IPv4 connections if the operating system
+    /// allows it, unless an IPv4 address with the same port is listened on
+    /// too.
+    ///
+    /// # Default Value
+    ///
+    /// Empty.
+    pub additional_addresses: Vec<SocketAddr>,
+    /// The path of a unix domain socket the server will also be bound to, for
+    /// proxies running on the same machine. Clients connected through it are
+    /// reported with the address [`LOCAL_ADDRESS`](transport::LOCAL_ADDRESS),
+    /// so a [`ConnectionMode`] forwarding the real addresses of clients should
+    /// be used.
+    ///
+    /// This is only supported on unix platforms.
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub unix_socket: Option<PathBuf>,
+    /// Whether connections start with a [PROXY protocol] header of version 1
+    /// or 2, which TCP load balancers such as HAProxy use to pass on the
+    /// address of the client. The address from the header replaces the
+    /// address of the connection.
+    ///
+    /// Only enable this if every connection goes through such a load
+    /// balancer. Connections without the header are rejected, and clients
+    /// connecting directly could otherwise send any address.
+    ///
+    /// [PROXY protocol]: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub proxy_protocol: bool,
     /// The connection mode. This determines if client authentication and
     /// encryption should take place and if the server should get the player
     /// data from a proxy.
@@ -258,6 +516,52 @@ pub struct NetworkSettings {
     ///
     /// The default value is left unspecified and may change in future versions.
     pub outgoing_byte_limit: usize,
+    /// The maximum number of bytes per second written to each client in the
+    /// play state, or `None` for no limit.
+    ///
+    /// Data over the limit is held back, except for low priority packets such
+    /// as particles, which are dropped instead so that the rest of the data
+    /// isn't delayed. See [`Client::write_low_priority_packet`]. This keeps the
+    /// server playable when its uplink can't keep up with every client.
+    ///
+    /// Packets are only dropped if they're encrypted after being queued, which
+    /// requires [`offload_encryption`](Self::offload_encryption) for clients
+    /// in [online mode](ConnectionMode::Online).
+    ///
+    /// [`Client::write_low_priority_packet`]: valence_server::client::Client::write_low_priority_packet
+    ///
+    /// # Default Value
+    ///
+    /// `None`
+    pub outgoing_bandwidth_limit: Option<u32>,
+    /// Whether packets sent to encrypted connections are encrypted by the
+    /// tasks writing to the connections instead of when packets are flushed at
+    /// the end of the tick. This moves the work from the main thread to the
+    /// tokio runtime's worker threads, which helps servers with many clients
+    /// in [online mode](ConnectionMode::Online).
+    ///
+    /// Only encryption is moved. Packets are still compressed on the main
+    /// thread when they're written, since compression happens packet by packet
+    /// while encryption covers the whole flushed buffer. Packets received from
+    /// clients are always decrypted on the worker threads.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub offload_encryption: bool,
+    /// Whether logins are rejected while a client with the same UUID is
+    /// already logged in. In [offline mode](ConnectionMode::Offline), UUIDs
+    /// are derived from usernames, so this rejects logins with the username
+    /// of an online client.
+    ///
+    /// Rejected logins are reported with
+    /// [`LoginFailureReason::DuplicateUuid`]. Vanilla disconnects the old
+    /// client instead, which is left up to users when this is disabled.
+    ///
+    /// # Default Value
+    ///
+    /// `false`
+    pub reject_duplicate_uuids: bool,
 }
 
 impl Default for NetworkSettings {
@@ -268,11 +572,17 @@ impl Default for NetworkSettings {
             max_connections: 1024,
             max_players: 20,
             address: SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 25565).into(),
+            additional_addresses: vec![],
+            unix_socket: None,
+            proxy_protocol: false,
             connection_mode: ConnectionMode::Online {
                 prevent_proxy_connections: false,
             },
             incoming_byte_limit: 2097152, // 2 MiB
             outgoing_byte_limit: 8388608, // 8 MiB
+            outgoing_bandwidth_limit: None,
+            offload_encryption: false,
+            reject_duplicate_uuids: false,
         }
     }
 }
@@ -326,7 +636,7 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
         #![allow(unused_variables)]
 
         ServerListPing::Respond {
-            online_players: shared.player_count().load(Ordering::Relaxed) as i32,
+            online_players: shared.visible_player_count() as i32,
             max_players: shared.max_players() as i32,
             player_sample: vec![],
             description: "A Valence Server".into_text(),
@@ -451,6 +761,29 @@ pub trait NetworkCallbacks: Send + Sync + 'static {
         }
     }
 
+    /// Called for each client after [`login`](Self::login) succeeds to choose
+    /// the compression threshold of the packets sent to and from it. Returning
+    /// a negative threshold disables compression for the client, which is
+    /// useful for clients on the local network or behind a proxy.
+    ///
+    /// Packets shared between clients are compressed with the server's
+    /// threshold, so they are compressed again for clients with a different
+    /// one. This costs CPU time and should be reserved for a few connections.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns the [server's
+    /// threshold](SharedNetworkState::compression_threshold).
+    fn compression_threshold(
+        &self,
+        shared: &SharedNetworkState,
+        info: &NewClientInfo,
+    ) -> CompressionThreshold {
+        let _ = info;
+
+        shared.compression_threshold()
+    }
+
     /// Called upon every client login to obtain the full URL to use for session
     /// server requests. This is done to authenticate player accounts. This
     /// method is not called unless [online mode] is enabled.
@@ -646,7 +979,8 @@ pub struct PlayerSampleEntry {
 
 #[allow(clippy::infinite_loop)]
 async fn do_broadcast_to_lan_loop(shared: SharedNetworkState) {
-    let port = shared.0.address.port();
+    // Clients on the LAN connect to the port of the main address.
+    let port = shared.0.addresses[0].port();
 
     let Ok(socket) = UdpSocket::bind("0.0.0.0:0").await else {
         tracing::error!("Failed to bind to UDP socket for broadcast to LAN");
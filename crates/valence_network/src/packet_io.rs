@@ -4,22 +4,23 @@ use std::time::Instant;
 use std::{io, mem};
 
 use anyhow::bail;
-use bytes::BytesMut;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
-use tracing::{debug, warn};
+use tracing::{debug, trace, warn, Instrument};
 use valence_protocol::CompressionThreshold;
 use valence_server::client::{ClientBundleArgs, ClientConnection, ReceivedPacket};
 use valence_server::protocol::decode::PacketFrame;
 use valence_server::protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder};
 
-use crate::byte_channel::{byte_channel, ByteSender, TrySendError};
+use crate::bandwidth::BandwidthLimiter;
+use crate::byte_channel::{byte_channel, ByteSender, ChunkPriority, TrySendError};
+use crate::transport::Stream;
 use crate::{CleanupOnDrop, NewClientInfo};
 
 pub(crate) struct PacketIo {
-    stream: TcpStream,
+    stream: Box<dyn Stream>,
     enc: PacketEncoder,
     dec: PacketDecoder,
     frame: PacketFrame,
@@ -28,7 +29,7 @@ pub(crate) struct PacketIo {
 const READ_BUF_SIZE: usize = 4096;
 
 impl PacketIo {
-    pub(crate) fn new(stream: TcpStream, enc: PacketEncoder, dec: PacketDecoder) -> Self {
+    pub(crate) fn new(stream: Box<dyn Stream>, enc: PacketEncoder, dec: PacketDecoder) -> Self {
         Self {
             stream,
             enc,
@@ -74,7 +75,6 @@ impl PacketIo {
         }
     }
 
-    #[allow(dead_code)]
     pub(crate) fn set_compression(&mut self, threshold: CompressionThreshold) {
         self.enc.set_compression(threshold);
         self.dec.set_compression(threshold);
@@ -85,11 +85,16 @@ impl PacketIo {
         self.dec.enable_encryption(key);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn into_client_args(
         mut self,
         info: NewClientInfo,
+        protocol_version: i32,
         incoming_byte_limit: usize,
         outgoing_byte_limit: usize,
+        outgoing_bandwidth_limit: Option<u32>,
+        offload_encryption: bool,
+        broadcast_threshold: CompressionThreshold,
         cleanup: CleanupOnDrop,
     ) -> ClientBundleArgs {
         let (incoming_sender, incoming_receiver) = flume::unbounded();
@@ -99,9 +104,9 @@ impl PacketIo {
         let recv_sem = Arc::new(Semaphore::new(incoming_byte_limit));
         let recv_sem_clone = recv_sem.clone();
 
-        let (mut reader, mut writer) = self.stream.into_split();
+        let (mut reader, mut writer) = tokio::io::split(self.stream);
 
-        let reader_task = tokio::spawn(async move {
+        let reader = async move {
             let mut buf = BytesMut::new();
 
             loop {
@@ -166,31 +171,122 @@ impl PacketIo {
                     break;
                 }
             }
-        });
+        };
+
+        // The tasks log in the span of the connection.
+        let reader_task = tokio::spawn(reader.in_current_span());
 
         let (outgoing_sender, mut outgoing_receiver) = byte_channel(outgoing_byte_limit);
 
-        let writer_task = tokio::spawn(async move {
+        // The client's encoder leaves its packets unencrypted without the cipher.
+        let mut encryptor = if offload_encryption {
+            self.enc.take_encryptor()
+        } else {
+            None
+        };
+
+        let mut limiter = outgoing_bandwidth_limit.map(BandwidthLimiter::new);
+
+        let writer = async move {
+            // Buffers the small chunks of a batch into fewer writes. Large chunks are
+            // written directly.
+            let mut writer = BufWriter::new(writer);
+
+            // The number of low priority bytes dropped since the last log.
+            let mut dropped = 0;
+
             loop {
-                let bytes = match outgoing_receiver.recv_async().await {
-                    Ok(bytes) => bytes,
+                let mut chunks = match outgoing_receiver.recv_async().await {
+                    Ok(chunks) => chunks,
                     Err(e) => {
                         debug!("error receiving packet data: {e}");
                         break;
                     }
                 };
 
-                if let Err(e) = writer.write_all(&bytes).await {
+                // Critical chunks are written ahead of the rest of the batch.
+                chunks
+                    .make_contiguous()
+                    .sort_by_key(|chunk| chunk.priority != ChunkPriority::Critical);
+
+                loop {
+                    // Critical chunks sent while the batch is being written are written next,
+                    // instead of waiting behind large chunks.
+                    for chunk in outgoing_receiver.take_critical().into_iter().rev() {
+                        chunks.push_front(chunk);
+                    }
+
+                    let Some(chunk) = chunks.pop_front() else {
+                        break;
+                    };
+
+                    let critical = chunk.priority == ChunkPriority::Critical;
+
+                    if let Some(limiter) = &mut limiter {
+                        if chunk.priority == ChunkPriority::Low {
+                            // Low priority data is only written if it doesn't delay the rest.
+                            if !limiter.try_take(chunk.len()) {
+                                dropped += chunk.len();
+                                continue;
+                            }
+                        } else {
+                            let delay = limiter.take(chunk.len());
+
+                            if !delay.is_zero() {
+                                // Write what was buffered so far before waiting.
+                                if let Err(e) = writer.flush().await {
+                                    debug!("error writing data to stream: {e}");
+                                }
+
+                                tokio::time::sleep(delay).await;
+                            }
+                        }
+                    }
+
+                    let res = match &mut encryptor {
+                        Some(encryptor) => {
+                            // Chunks shared with other clients must be copied before encrypting.
+                            let mut chunk = chunk
+                                .bytes
+                                .try_into_mut()
+                                .unwrap_or_else(|chunk| BytesMut::from(&chunk[..]));
+
+                            encryptor.encrypt(&mut chunk);
+                            writer.write_all(&chunk).await
+                        }
+                        None => writer.write_all(&chunk.bytes).await,
+                    };
+
+                    if let Err(e) = res {
+                        debug!("error writing data to stream: {e}");
+                    }
+
+                    if critical {
+                        if let Err(e) = writer.flush().await {
+                            debug!("error writing data to stream: {e}");
+                        }
+                    }
+                }
+
+                if let Err(e) = writer.flush().await {
                     debug!("error writing data to stream: {e}");
                 }
+
+                if dropped > 0 {
+                    trace!(dropped, "dropped low priority packet data");
+                    dropped = 0;
+                }
             }
-        });
+        };
+
+        let writer_task = tokio::spawn(writer.in_current_span());
 
         ClientBundleArgs {
             username: info.username,
             uuid: info.uuid,
             ip: info.ip,
             properties: info.properties.0,
+            protocol_version,
             conn: Box::new(RealClientConnection {
                 send: outgoing_sender,
                 recv: incoming_receiver,
@@ -200,6 +296,7 @@ impl PacketIo {
                 _cleanup: cleanup,
             }),
             enc: self.enc,
+            broadcast_threshold,
         }
     }
 }
@@ -217,6 +314,10 @@ struct RealClientConnection {
 
 impl ClientConnection for RealClientConnection {
     fn try_send(&mut self, bytes: BytesMut) -> anyhow::Result<()> {
+        self.try_send_shared(bytes.freeze())
+    }
+
+    fn try_send_shared(&mut self, bytes: Bytes) -> anyhow::Result<()> {
         match self.send.try_send(bytes) {
             Ok(()) => Ok(()),
             Err(TrySendError::Full(_)) => bail!(
@@ -227,6 +328,24 @@ impl ClientConnection for RealClientConnection {
         }
     }
 
+    fn try_send_critical(&mut self, bytes: Bytes) -> anyhow::Result<()> {
+        match self.send.try_send_critical(bytes) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => bail!(
+                "reached configured outgoing limit of {} bytes",
+                self.send.limit()
+            ),
+            Err(TrySendError::Disconnected(_)) => bail!("client disconnected"),
+        }
+    }
+
+    fn try_send_low_priority(&mut self, bytes: Bytes) -> anyhow::Result<()> {
+        match self.send.try_send_low_priority(bytes) {
+            Ok(()) => Ok(()),
+            Err(_) => bail!("client disconnected"),
+        }
+    }
+
     fn try_recv(&mut self) -> anyhow::Result<Option<ReceivedPacket>> {
         match self.recv.try_recv() {
             Ok(packet) => {
@@ -245,6 +364,10 @@ impl ClientConnection for RealClientConnection {
     fn len(&self) -> usize {
         self.recv.len()
     }
+
+    fn queued_bytes(&self) -> usize {
+        self.send.len()
+    }
 }
 
 impl Drop for RealClientConnection {
@@ -0,0 +1,218 @@
+//! Parsing of the [PROXY protocol] headers sent by TCP load balancers such as
+//! HAProxy, which carry the address of the client the connection is proxied
+//! for.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{bail, ensure, Context};
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// The signature at the start of version 2 headers.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// The maximum length of a version 1 header, including the CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Reads a PROXY protocol header of either version from the stream. Returns
+/// the address of the proxied client, or `None` if the proxy didn't provide one
+/// (such as for its own health checks).
+///
+/// Bytes read past the header are left in `buf`.
+pub(crate) async fn read_proxy_header<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut BytesMut,
+) -> anyhow::Result<Option<SocketAddr>> {
+    loop {
+        if let Some((addr, len)) = parse_header(buf)? {
+            buf.advance(len);
+            return Ok(addr);
+        }
+
+        buf.reserve(256);
+
+        if stream.read_buf(buf).await? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+    }
+}
+
+/// Parses the header at the start of `buf`. Returns the address of the client
+/// and the length of the header, or `None` if the header is incomplete.
+fn parse_header(buf: &[u8]) -> anyhow::Result<Option<(Option<SocketAddr>, usize)>> {
+    if buf.starts_with(b"PROXY ") {
+        return parse_v1(buf);
+    }
+
+    let n = buf.len().min(V2_SIGNATURE.len());
+
+    if buf[..n] == V2_SIGNATURE[..n] {
+        return parse_v2(buf);
+    }
+
+    if buf.len() < 6 && b"PROXY ".starts_with(buf) {
+        return Ok(None);
+    }
+
+    bail!("missing PROXY protocol header")
+}
+
+fn parse_v1(buf: &[u8]) -> anyhow::Result<Option<(Option<SocketAddr>, usize)>> {
+    let Some(end) = buf.windows(2).position(|w| w == b"\r\n") else {
+        ensure!(buf.len() < V1_MAX_LEN, "PROXY protocol v1 header too long");
+        return Ok(None);
+    };
+
+    ensure!(end + 2 <= V1_MAX_LEN, "PROXY protocol v1 header too long");
+
+    let line = std::str::from_utf8(&buf[..end]).context("invalid PROXY protocol v1 header")?;
+    let mut parts = line.split(' ').skip(1);
+
+    let addr = match parts.next() {
+        Some("TCP4" | "TCP6") => {
+            let (Some(src_ip), Some(_dst_ip), Some(src_port), Some(_dst_port), None) = (
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+                parts.next(),
+            ) else {
+                bail!("invalid PROXY protocol v1 header `{line}`");
+            };
+
+            let ip: IpAddr = src_ip
+                .parse()
+                .context("invalid PROXY protocol v1 address")?;
+            let port: u16 = src_port.parse().context("invalid PROXY protocol v1 port")?;
+
+            Some(SocketAddr::new(ip, port))
+        }
+        // The rest of the line is ignored for unknown protocols.
+        Some("UNKNOWN") => None,
+        _ => bail!("invalid PROXY protocol v1 header `{line}`"),
+    };
+
+    Ok(Some((addr, end + 2)))
+}
+
+fn parse_v2(buf: &[u8]) -> anyhow::Result<Option<(Option<SocketAddr>, usize)>> {
+    // Signature, version and command, address family, and length.
+    const HEADER_LEN: usize = 16;
+
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+
+    let version = buf[12] >> 4;
+    let command = buf[12] & 0xf;
+    let family = buf[13];
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+
+    ensure!(version == 2, "unsupported PROXY protocol version {version}");
+
+    if buf.len() < HEADER_LEN + len {
+        return Ok(None);
+    }
+
+    let mut addrs = &buf[HEADER_LEN..HEADER_LEN + len];
+
+    let addr = match command {
+        // The connection was made by the proxy itself.
+        0x0 => None,
+        0x1 => match family {
+            // TCP over IPv4.
+            0x11 => {
+                ensure!(addrs.len() >= 12, "truncated PROXY protocol v2 addresses");
+
+                let ip = Ipv4Addr::from(addrs.get_u32());
+                let _dst_ip = addrs.get_u32();
+                let port = addrs.get_u16();
+
+                Some(SocketAddr::new(ip.into(), port))
+            }
+            // TCP over IPv6.
+            0x21 => {
+                ensure!(addrs.len() >= 36, "truncated PROXY protocol v2 addresses");
+
+                let ip = Ipv6Addr::from(addrs.get_u128());
+                let _dst_ip = addrs.get_u128();
+                let port = addrs.get_u16();
+
+                Some(SocketAddr::new(ip.into(), port))
+            }
+            // Other families, such as unix sockets, carry no IP address.
+            _ => None,
+        },
+        _ => bail!("unsupported PROXY protocol v2 command {command}"),
+    };
+
+    Ok(Some((addr, HEADER_LEN + len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_header() {
+        let buf = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10\x00";
+
+        assert_eq!(
+            parse_header(buf).unwrap(),
+            Some((Some("192.168.0.1:56324".parse().unwrap()), buf.len() - 2))
+        );
+
+        let buf = b"PROXY TCP6 ::1 ::1 4000 25565\r\n";
+
+        assert_eq!(
+            parse_header(buf).unwrap(),
+            Some((Some("[::1]:4000".parse().unwrap()), buf.len()))
+        );
+
+        assert_eq!(
+            parse_header(b"PROXY UNKNOWN\r\n").unwrap(),
+            Some((None, 15))
+        );
+
+        // Incomplete headers.
+        assert_eq!(parse_header(b"PRO").unwrap(), None);
+        assert_eq!(parse_header(b"PROXY TCP4 192.16").unwrap(), None);
+
+        assert!(parse_header(b"PROXY TCP4 nonsense\r\n").is_err());
+
+        let mut long = b"PROXY ".to_vec();
+        long.resize(V1_MAX_LEN, b'0');
+        assert!(parse_header(&long).is_err());
+    }
+
+    #[test]
+    fn v2_header() {
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.extend([0x21, 0x11, 0, 12]);
+        buf.extend([10, 0, 0, 1, 10, 0, 0, 2]);
+        buf.extend(1234_u16.to_be_bytes());
+        buf.extend(25565_u16.to_be_bytes());
+
+        assert_eq!(
+            parse_header(&buf).unwrap(),
+            Some((Some("10.0.0.1:1234".parse().unwrap()), buf.len()))
+        );
+
+        // Incomplete header.
+        assert_eq!(parse_header(&buf[..20]).unwrap(), None);
+
+        // Local command.
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.extend([0x20, 0x00, 0, 0]);
+
+        assert_eq!(parse_header(&buf).unwrap(), Some((None, 16)));
+    }
+
+    #[test]
+    fn missing_header() {
+        // The start of a handshake packet.
+        assert!(parse_header(&[0x10, 0x00, 0xfb, 0x05]).is_err());
+    }
+}
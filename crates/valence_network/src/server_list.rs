@@ -0,0 +1,308 @@
+//! Helpers for building [`ServerListPing`] responses.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use base64::prelude::*;
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageResult};
+use uuid::Uuid;
+use valence_server::text::IntoText;
+use valence_server::{Text, MINECRAFT_VERSION, PROTOCOL_VERSION};
+
+use crate::{PlayerSampleEntry, ServerListPing};
+
+/// The width and height of server icons in pixels.
+pub const FAVICON_SIZE: u32 = 64;
+
+/// A server icon, stored as a [`FAVICON_SIZE`]x[`FAVICON_SIZE`] PNG image.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Favicon {
+    png: Vec<u8>,
+}
+
+impl Favicon {
+    /// Decodes an image in any format supported by the `image` crate, resizing
+    /// it to the size of server icons if needed.
+    pub fn from_image_bytes(bytes: &[u8]) -> ImageResult<Self> {
+        let format = image::guess_format(bytes)?;
+        let image = image::load_from_memory_with_format(bytes, format)?;
+
+        // Images which are already valid are kept as they are.
+        if format == ImageFormat::Png
+            && image.width() == FAVICON_SIZE
+            && image.height() == FAVICON_SIZE
+        {
+            return Ok(Self {
+                png: bytes.to_vec(),
+            });
+        }
+
+        let image = image.resize_exact(FAVICON_SIZE, FAVICON_SIZE, FilterType::Lanczos3);
+
+        let mut png = vec![];
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+        Ok(Self { png })
+    }
+
+    /// Reads and decodes the image file at `path`. See
+    /// [`Favicon::from_image_bytes`].
+    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        Self::from_image_bytes(&std::fs::read(path)?)
+    }
+
+    /// Returns the bytes of the PNG image, for
+    /// [`ServerListPing::Respond::favicon_png`].
+    pub fn png(&self) -> &[u8] {
+        &self.png
+    }
+
+    /// Returns the image as the base64 data URI sent to clients.
+    pub fn to_data_uri(&self) -> String {
+        png_data_uri(&self.png)
+    }
+}
+
+/// Encodes a PNG image as a base64 data URI.
+pub(crate) fn png_data_uri(png: &[u8]) -> String {
+    let mut buf = "data:image/png;base64,".to_owned();
+    BASE64_STANDARD.encode_string(png, &mut buf);
+    buf
+}
+
+/// Builds a [`ServerListPing::Respond`].
+///
+/// ```
+/// # use valence_network::server_list::ServerListPingBuilder;
+/// # use valence_server::text::{Color, IntoText};
+/// let ping = ServerListPingBuilder::new(3, 20)
+///     .motd(
+///         "A Valence Server".color(Color::GOLD),
+///         "Now with more blocks!",
+///     )
+///     .sample_line("Mini-games:".bold())
+///     .sample_line(" - Parkour")
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServerListPingBuilder<'a> {
+    online_players: i32,
+    max_players: i32,
+    player_sample: Vec<PlayerSampleEntry>,
+    description: Text,
+    favicon_png: &'a [u8],
+    version_name: String,
+    protocol: i32,
+}
+
+impl<'a> ServerListPingBuilder<'a> {
+    pub fn new(online_players: i32, max_players: i32) -> Self {
+        Self {
+            online_players,
+            max_players,
+            player_sample: vec![],
+            description: Text::default(),
+            favicon_png: &[],
+            version_name: MINECRAFT_VERSION.to_owned(),
+            protocol: PROTOCOL_VERSION,
+        }
+    }
+
+    /// Sets the description of the server.
+    pub fn description(mut self, description: impl IntoText<'static>) -> Self {
+        self.description = description.into_text();
+        self
+    }
+
+    /// Sets the description of the server to two lines of text, which is how
+    /// much the server list displays.
+    pub fn motd(
+        mut self,
+        first_line: impl IntoText<'static>,
+        second_line: impl IntoText<'static>,
+    ) -> Self {
+        self.description = first_line.into_text() + "\n" + second_line.into_text();
+        self
+    }
+
+    /// Adds a player to the list shown when hovering over the player count.
+    pub fn sample_player(mut self, name: impl Into<String>, id: Uuid) -> Self {
+        self.player_sample.push(PlayerSampleEntry {
+            name: name.into(),
+            id,
+        });
+        self
+    }
+
+    /// Adds a line of text to the list shown when hovering over the player
+    /// count. The text is converted to legacy formatting codes, since the list
+    /// only holds player names.
+    pub fn sample_line(mut self, line: impl IntoText<'static>) -> Self {
+        self.player_sample.push(PlayerSampleEntry {
+            name: line.into_text().to_legacy_lossy(),
+            id: Uuid::nil(),
+        });
+        self
+    }
+
+    /// Sets the server icon.
+    pub fn favicon(mut self, favicon: &'a Favicon) -> Self {
+        self.favicon_png = favicon.png();
+        self
+    }
+
+    /// Sets the version name displayed to clients using a different protocol.
+    /// Defaults to [`MINECRAFT_VERSION`].
+    pub fn version_name(mut self, version_name: impl Into<String>) -> Self {
+        self.version_name = version_name.into();
+        self
+    }
+
+    /// Sets the protocol version of the server. Defaults to
+    /// [`PROTOCOL_VERSION`].
+    pub fn protocol(mut self, protocol: i32) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn build(self) -> ServerListPing<'a> {
+        ServerListPing::Respond {
+            online_players: self.online_players,
+            max_players: self.max_players,
+            player_sample: self.player_sample,
+            description: self.description,
+            favicon_png: self.favicon_png,
+            version_name: self.version_name,
+            protocol: self.protocol,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView, RgbaImage};
+    use valence_server::text::Color;
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+
+        let mut png = vec![];
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn favicon_dimensions(favicon: &Favicon) -> (u32, u32) {
+        image::load_from_memory_with_format(favicon.png(), ImageFormat::Png)
+            .unwrap()
+            .dimensions()
+    }
+
+    #[test]
+    fn favicon_is_resized() {
+        for (width, height) in [(128, 128), (16, 16), (200, 50)] {
+            let favicon = Favicon::from_image_bytes(&png(width, height)).unwrap();
+
+            assert_eq!(favicon_dimensions(&favicon), (FAVICON_SIZE, FAVICON_SIZE));
+        }
+    }
+
+    #[test]
+    fn favicon_of_right_size_is_kept() {
+        let bytes = png(FAVICON_SIZE, FAVICON_SIZE);
+
+        let favicon = Favicon::from_image_bytes(&bytes).unwrap();
+
+        assert_eq!(favicon.png(), bytes);
+    }
+
+    #[test]
+    fn favicon_from_invalid_bytes() {
+        assert!(Favicon::from_image_bytes(b"not an image").is_err());
+        assert!(Favicon::from_image_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn favicon_data_uri() {
+        let favicon = Favicon::from_image_bytes(&png(FAVICON_SIZE, FAVICON_SIZE)).unwrap();
+
+        let uri = favicon.to_data_uri();
+        let data = uri.strip_prefix("data:image/png;base64,").unwrap();
+
+        assert_eq!(BASE64_STANDARD.decode(data).unwrap(), favicon.png());
+        assert_eq!(png_data_uri(&[]), "data:image/png;base64,");
+    }
+
+    #[test]
+    fn ping_builder_output() {
+        let favicon = Favicon::from_image_bytes(&png(FAVICON_SIZE, FAVICON_SIZE)).unwrap();
+        let id = Uuid::from_u128(1);
+
+        let ping = ServerListPingBuilder::new(3, 20)
+            .motd(
+                "A Valence Server".color(Color::GOLD),
+                "Now with more blocks!",
+            )
+            .sample_player("player", id)
+            .sample_line("Mini-games:".bold())
+            .favicon(&favicon)
+            .build();
+
+        let ServerListPing::Respond {
+            online_players,
+            max_players,
+            player_sample,
+            description,
+            favicon_png,
+            version_name,
+            protocol,
+        } = ping
+        else {
+            panic!("expected a response");
+        };
+
+        assert_eq!(online_players, 3);
+        assert_eq!(max_players, 20);
+        assert_eq!(
+            description.to_legacy_lossy(),
+            "§6A Valence Server\nNow with more blocks!"
+        );
+        assert_eq!(player_sample.len(), 2);
+        assert_eq!(player_sample[0].name, "player");
+        assert_eq!(player_sample[0].id, id);
+        assert_eq!(player_sample[1].name, "§lMini-games:");
+        assert_eq!(player_sample[1].id, Uuid::nil());
+        assert_eq!(favicon_png, favicon.png());
+        assert_eq!(version_name, MINECRAFT_VERSION);
+        assert_eq!(protocol, PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn ping_builder_version() {
+        let ping = ServerListPingBuilder::new(0, 0)
+            .description("Hello")
+            .version_name("Valence 1.0")
+            .protocol(1)
+            .build();
+
+        let ServerListPing::Respond {
+            description,
+            favicon_png,
+            version_name,
+            protocol,
+            ..
+        } = ping
+        else {
+            panic!("expected a response");
+        };
+
+        assert_eq!(description, "Hello".into_text());
+        assert!(favicon_png.is_empty());
+        assert_eq!(version_name, "Valence 1.0");
+        assert_eq!(protocol, 1);
+    }
+}
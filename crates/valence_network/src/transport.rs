@@ -0,0 +1,58 @@
+//! The byte streams clients connect through.
+//!
+//! Besides TCP, the server can accept clients over a unix domain socket with
+//! [`NetworkSettings::unix_socket`], which avoids the TCP stack when a proxy
+//! runs on the same machine. Any other stream, such as one half of a
+//! [`tokio::io::duplex`] pipe, can be handed to the server with
+//! [`SharedNetworkState::accept_stream`] to embed a proxy or tests in the same
+//! process.
+//!
+//! [`NetworkSettings::unix_socket`]: crate::NetworkSettings::unix_socket
+//! [`SharedNetworkState::accept_stream`]: crate::SharedNetworkState::accept_stream
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tracing::error;
+
+/// A bidirectional byte stream to a client. This is implemented for every
+/// type which can be read from and written to asynchronously.
+pub trait Stream: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Stream for T {}
+
+/// The address reported for clients which didn't connect over TCP, since
+/// they have no IP address of their own.
+pub const LOCAL_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub(crate) async fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, remote_addr) = listener.accept().await?;
+
+                if let Err(e) = stream.set_nodelay(true) {
+                    error!("failed to set TCP_NODELAY: {e}");
+                }
+
+                Ok((Box::new(stream), remote_addr))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+
+                Ok((Box::new(stream), LOCAL_ADDRESS))
+            }
+        }
+    }
+}
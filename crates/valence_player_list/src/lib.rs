@@ -1,11 +1,12 @@
 #![doc = include_str!("../README.md")]
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use derive_more::{Deref, DerefMut};
-use valence_server::client::{Client, Properties, Username};
+use valence_server::client::{Client, Properties, Username, VisibleChunkLayer};
 use valence_server::keepalive::Ping;
 use valence_server::layer::UpdateLayersPreClientSet;
 use valence_server::protocol::encode::PacketWriter;
@@ -15,7 +16,8 @@ use valence_server::protocol::packets::play::{
 use valence_server::protocol::WritePacket;
 use valence_server::text::IntoText;
 use valence_server::uuid::Uuid;
-use valence_server::{Despawned, GameMode, Server, Text, UniqueId};
+use valence_server::vanish::{Vanished, VanishedListing};
+use valence_server::{ChunkLayer, Despawned, GameMode, Server, Text, UniqueId};
 
 pub struct PlayerListPlugin;
 
@@ -39,8 +41,11 @@ impl Plugin for PlayerListPlugin {
                     apply_deferred, // So new clients get the packets for their own entry.
                     update_entries,
                     init_player_list_for_clients,
+                    update_scoped_entries,
                     remove_despawned_entries,
                     write_player_list_changes,
+                    update_vanished_entries,
+                    update_tab_header_footers,
                 )
                     .in_set(PlayerListSet)
                     .chain(),
@@ -99,6 +104,69 @@ impl PlayerList {
     }
 }
 
+/// A player list header and footer which can be animated cheaply.
+///
+/// When put on a client entity, the header and footer are only shown to that
+/// client. When put on a [`ChunkLayer`] entity, they are shown to every client
+/// viewing the layer without its own `TabHeaderFooter`. Both take precedence
+/// over the header and footer of the [`PlayerList`].
+///
+/// Changes are only sent when the text actually differs from what was last
+/// sent, so animations can rewrite the text every tick. Set
+/// [`min_update_interval`](Self::min_update_interval) to limit how often
+/// updates are sent.
+#[derive(Component, Clone, Default, Debug)]
+pub struct TabHeaderFooter {
+    pub header: Text,
+    pub footer: Text,
+    /// The minimum number of ticks between two updates sent to clients.
+    /// Changes made in between are sent together once the interval has
+    /// passed. If `0`, every change is sent on the tick it's made.
+    pub min_update_interval: u32,
+    sent: Option<(Text, Text)>,
+    last_update_tick: Option<i64>,
+}
+
+impl TabHeaderFooter {
+    pub fn new<'a, 'b, H: IntoText<'a>, F: IntoText<'b>>(header: H, footer: F) -> Self {
+        Self {
+            header: header.into_cow_text().into_owned(),
+            footer: footer.into_cow_text().into_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_min_update_interval(mut self, ticks: u32) -> Self {
+        self.min_update_interval = ticks;
+        self
+    }
+
+    fn needs_update(&self, tick: i64) -> bool {
+        let changed = self.sent.as_ref().map_or(true, |(header, footer)| {
+            *header != self.header || *footer != self.footer
+        });
+
+        let interval_passed = self.last_update_tick.map_or(true, |last| {
+            tick - last >= i64::from(self.min_update_interval)
+        });
+
+        changed && interval_passed
+    }
+
+    fn mark_sent(&mut self, tick: i64) {
+        self.sent = Some((self.header.clone(), self.footer.clone()));
+        self.last_update_tick = Some(tick);
+    }
+
+    fn packet(&self) -> PlayerListHeaderS2c<'_> {
+        PlayerListHeaderS2c {
+            header: Cow::Borrowed(&self.header),
+            footer: Cow::Borrowed(&self.footer),
+        }
+    }
+}
+
 /// Bundle for spawning new player list entries. All components are required
 /// unless otherwise stated.
 ///
@@ -126,6 +194,35 @@ pub struct PlayerListEntry;
 #[derive(Component, Default, Debug, Deref, DerefMut)]
 pub struct DisplayName(pub Option<Text>);
 
+/// Restricts a player list entry to some clients, such as the players of one
+/// world or minigame. Entries without a scope are shown to every client.
+///
+/// Clients need the entry of a player to see its player entity, so players
+/// which can see each other should share their scope.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub enum PlayerListScope {
+    /// The entry is shown to clients viewing any of these [`ChunkLayer`]s.
+    Layers(Vec<Entity>),
+    /// The entry is shown to these clients.
+    Clients(Vec<Entity>),
+}
+
+impl PlayerListScope {
+    /// A scope with the clients viewing `layer`.
+    pub fn layer(layer: Entity) -> Self {
+        Self::Layers(vec![layer])
+    }
+
+    /// Returns whether the entry is shown to `client`, which is viewing
+    /// `visible_layer`.
+    pub fn contains(&self, client: Entity, visible_layer: Entity) -> bool {
+        match self {
+            Self::Layers(layers) => layers.contains(&visible_layer),
+            Self::Clients(clients) => clients.contains(&client),
+        }
+    }
+}
+
 /// If a player list entry is visible. Defaults to `true`.
 #[derive(Component, Copy, Clone, Debug, Deref, DerefMut)]
 pub struct Listed(pub bool);
@@ -150,12 +247,13 @@ fn update_header_footer(player_list: ResMut<PlayerList>, server: Res<Server>) {
             footer: (&player_list.footer).into(),
         });
 
-        player_list.changed_header_or_footer = false;
+        // The flag is cleared in `update_tab_header_footers`, which sends
+        // `TabHeaderFooter`s again so they aren't overwritten.
     }
 }
 
 fn add_new_clients_to_player_list(
-    clients: Query<Entity, Added<Client>>,
+    clients: Query<Entity, (Added<Client>, Without<Despawned>)>,
     player_list: Res<PlayerList>,
     mut commands: Commands,
 ) {
@@ -183,17 +281,13 @@ fn init_player_list_for_clients(
             &DisplayName,
             &Listed,
         ),
-        With<PlayerListEntry>,
+        // Scoped entries are sent in `update_scoped_entries`.
+        (With<PlayerListEntry>, Without<PlayerListScope>),
     >,
 ) {
     if player_list.manage_clients {
         for mut client in &mut clients {
-            let actions = packet::PlayerListActions::new()
-                .with_add_player(true)
-                .with_update_game_mode(true)
-                .with_update_listed(true)
-                .with_update_latency(true)
-                .with_update_display_name(true);
+            let actions = all_actions();
 
             let entries: Vec<_> = entries
                 .iter()
@@ -271,6 +365,7 @@ fn update_entries(
         ),
         (
             With<PlayerListEntry>,
+            Without<PlayerListScope>,
             Or<(
                 Changed<UniqueId>,
                 Changed<Username>,
@@ -293,47 +388,157 @@ fn update_entries(
     );
 
     for (uuid, username, props, game_mode, ping, display_name, listed) in &entries {
-        let mut actions = packet::PlayerListActions::new();
+        let actions = changed_actions(
+            &uuid,
+            &username,
+            &props,
+            &game_mode,
+            &ping,
+            &display_name,
+            &listed,
+        );
 
-        // Did a change occur that would force us to overwrite the entry? This also adds
-        // new entries.
-        if uuid.is_changed() || username.is_changed() || props.is_changed() {
-            actions.set_add_player(true);
+        debug_assert_ne!(u8::from(actions), 0);
 
-            if *game_mode != GameMode::default() {
-                actions.set_update_game_mode(true);
-            }
+        let entry = packet::PlayerListEntry {
+            player_uuid: uuid.0,
+            username: &username.0,
+            properties: Cow::Borrowed(&props.0),
+            chat_data: None,
+            listed: listed.0,
+            ping: ping.0,
+            game_mode: *game_mode,
+            display_name: display_name.0.as_ref().map(|x| x.into()),
+        };
 
-            if ping.0 != 0 {
-                actions.set_update_latency(true);
-            }
+        writer.write_packet(&PlayerListS2c {
+            actions,
+            entries: Cow::Borrowed(&[entry]),
+        });
+    }
+}
 
-            if display_name.0.is_some() {
-                actions.set_update_display_name(true);
-            }
+/// The actions sending every part of an entry.
+fn all_actions() -> packet::PlayerListActions {
+    packet::PlayerListActions::new()
+        .with_add_player(true)
+        .with_update_game_mode(true)
+        .with_update_listed(true)
+        .with_update_latency(true)
+        .with_update_display_name(true)
+}
 
-            if listed.0 {
-                actions.set_update_listed(true);
-            }
-        } else {
-            if game_mode.is_changed() {
-                actions.set_update_game_mode(true);
-            }
+/// The actions sending the parts of an entry which changed.
+fn changed_actions(
+    uuid: &Ref<UniqueId>,
+    username: &Ref<Username>,
+    props: &Ref<Properties>,
+    game_mode: &Ref<GameMode>,
+    ping: &Ref<Ping>,
+    display_name: &Ref<DisplayName>,
+    listed: &Ref<Listed>,
+) -> packet::PlayerListActions {
+    let mut actions = packet::PlayerListActions::new();
+
+    // Did a change occur that would force us to overwrite the entry? This also adds
+    // new entries.
+    if uuid.is_changed() || username.is_changed() || props.is_changed() {
+        actions.set_add_player(true);
+
+        if **game_mode != GameMode::default() {
+            actions.set_update_game_mode(true);
+        }
 
-            if ping.is_changed() {
-                actions.set_update_latency(true);
-            }
+        if ping.0 != 0 {
+            actions.set_update_latency(true);
+        }
 
-            if display_name.is_changed() {
-                actions.set_update_display_name(true);
-            }
+        if display_name.0.is_some() {
+            actions.set_update_display_name(true);
+        }
 
-            if listed.is_changed() {
-                actions.set_update_listed(true);
-            }
+        if listed.0 {
+            actions.set_update_listed(true);
+        }
+    } else {
+        if game_mode.is_changed() {
+            actions.set_update_game_mode(true);
+        }
+
+        if ping.is_changed() {
+            actions.set_update_latency(true);
+        }
+
+        if display_name.is_changed() {
+            actions.set_update_display_name(true);
+        }
 
-            debug_assert_ne!(u8::from(actions), 0);
+        if listed.is_changed() {
+            actions.set_update_listed(true);
         }
+    }
+
+    actions
+}
+
+/// Sends scoped entries to the clients entering their scope, removes them
+/// from the clients leaving it, and sends their changes to the clients in it.
+#[allow(clippy::type_complexity)]
+fn update_scoped_entries(
+    entries: Query<
+        (
+            Entity,
+            Ref<PlayerListScope>,
+            Ref<UniqueId>,
+            Ref<Username>,
+            Ref<Properties>,
+            Ref<GameMode>,
+            Ref<Ping>,
+            Ref<DisplayName>,
+            Ref<Listed>,
+        ),
+        (With<PlayerListEntry>, Without<Despawned>),
+    >,
+    unscoped_entries: Query<
+        (
+            &UniqueId,
+            &Username,
+            &Properties,
+            &GameMode,
+            &Ping,
+            &DisplayName,
+            &Listed,
+        ),
+        (
+            With<PlayerListEntry>,
+            Without<PlayerListScope>,
+            Without<Despawned>,
+        ),
+    >,
+    mut clients: Query<(Entity, &mut Client, Ref<VisibleChunkLayer>), Without<Despawned>>,
+    despawned_entries: Query<Entity, (With<PlayerListScope>, Added<Despawned>)>,
+    mut removed_scopes: RemovedComponents<PlayerListScope>,
+    // The clients each scoped entry was sent to.
+    mut shown: Local<HashMap<Entity, Vec<Entity>>>,
+) {
+    // Despawned entries are removed from every client in
+    // `remove_despawned_entries`.
+    for entity in &despawned_entries {
+        shown.remove(&entity);
+    }
+
+    // Entries which are no longer scoped are shown to everyone again. New clients
+    // were already sent them.
+    for entity in removed_scopes.read() {
+        let Some(shown_to) = shown.remove(&entity) else {
+            continue;
+        };
+
+        let Ok((uuid, username, props, game_mode, ping, display_name, listed)) =
+            unscoped_entries.get(entity)
+        else {
+            continue;
+        };
 
         let entry = packet::PlayerListEntry {
             player_uuid: uuid.0,
@@ -343,13 +548,85 @@ fn update_entries(
             listed: listed.0,
             ping: ping.0,
             game_mode: *game_mode,
-            display_name: display_name.0.as_ref().map(|x| x.into()),
+            display_name: display_name.0.as_ref().map(Cow::Borrowed),
         };
 
-        writer.write_packet(&PlayerListS2c {
-            actions,
-            entries: Cow::Borrowed(&[entry]),
+        for (client_entity, mut client, visible_layer) in &mut clients {
+            if !visible_layer.is_added() && !shown_to.contains(&client_entity) {
+                client.write_packet(&PlayerListS2c {
+                    actions: all_actions(),
+                    entries: Cow::Borrowed(std::slice::from_ref(&entry)),
+                });
+            }
+        }
+    }
+
+    let clients_changed = clients
+        .iter()
+        .any(|(_, _, visible_layer)| visible_layer.is_changed());
+
+    for (entity, scope, uuid, username, props, game_mode, ping, display_name, listed) in &entries {
+        let actions = changed_actions(
+            &uuid,
+            &username,
+            &props,
+            &game_mode,
+            &ping,
+            &display_name,
+            &listed,
+        );
+        let entry_changed = u8::from(actions) != 0;
+
+        if !clients_changed && !scope.is_changed() && !entry_changed {
+            continue;
+        }
+
+        let shown_to = shown.remove(&entity).unwrap_or_else(|| {
+            if scope.is_added() && !uuid.is_added() {
+                // The entry was shown to everyone before it was scoped.
+                clients
+                    .iter()
+                    .filter(|(_, _, visible_layer)| !visible_layer.is_added())
+                    .map(|(client, _, _)| client)
+                    .collect()
+            } else {
+                vec![]
+            }
         });
+
+        let entry = packet::PlayerListEntry {
+            player_uuid: uuid.0,
+            username: &username.0,
+            properties: Cow::Borrowed(&props.0),
+            chat_data: None,
+            listed: listed.0,
+            ping: ping.0,
+            game_mode: *game_mode,
+            display_name: display_name.0.as_ref().map(Cow::Borrowed),
+        };
+
+        let mut in_scope = vec![];
+
+        for (client_entity, mut client, visible_layer) in &mut clients {
+            let was_shown = shown_to.contains(&client_entity);
+
+            if scope.contains(client_entity, visible_layer.0) {
+                in_scope.push(client_entity);
+
+                if !was_shown || entry_changed {
+                    client.write_packet(&PlayerListS2c {
+                        actions: if was_shown { actions } else { all_actions() },
+                        entries: Cow::Borrowed(std::slice::from_ref(&entry)),
+                    });
+                }
+            } else if was_shown {
+                client.write_packet(&PlayerRemoveS2c {
+                    uuids: Cow::Borrowed(&[uuid.0]),
+                });
+            }
+        }
+
+        shown.insert(entity, in_scope);
     }
 }
 
@@ -367,3 +644,146 @@ fn write_player_list_changes(
         player_list.cached_update_packets.clear();
     }
 }
+
+/// Unlists or greys out the entries of [`Vanished`] clients for the other
+/// clients, and restores them once the clients reappear.
+#[allow(clippy::type_complexity)]
+fn update_vanished_entries(
+    entries: Query<
+        (
+            Entity,
+            Ref<Vanished>,
+            Ref<UniqueId>,
+            Ref<Username>,
+            Ref<Properties>,
+            Ref<GameMode>,
+            Ref<Listed>,
+        ),
+        (With<PlayerListEntry>, Without<Despawned>),
+    >,
+    reappeared: Query<
+        (&UniqueId, &GameMode, &Listed),
+        (With<PlayerListEntry>, Without<Vanished>, Without<Despawned>),
+    >,
+    mut clients: Query<(Entity, &mut Client), Without<Despawned>>,
+    mut removed: RemovedComponents<Vanished>,
+) {
+    let actions = packet::PlayerListActions::new()
+        .with_update_game_mode(true)
+        .with_update_listed(true);
+
+    let mut write_to_others = |entity: Entity, entry: packet::PlayerListEntry, all: bool| {
+        for (client_entity, mut client) in &mut clients {
+            if client_entity != entity && (all || client.is_added()) {
+                client.write_packet(&PlayerListS2c {
+                    actions,
+                    entries: Cow::Borrowed(std::slice::from_ref(&entry)),
+                });
+            }
+        }
+    };
+
+    for entity in removed.read() {
+        if let Ok((uuid, game_mode, listed)) = reappeared.get(entity) {
+            let entry = packet::PlayerListEntry {
+                player_uuid: uuid.0,
+                listed: listed.0,
+                game_mode: *game_mode,
+                ..Default::default()
+            };
+
+            write_to_others(entity, entry, true);
+        }
+    }
+
+    for (entity, vanished, uuid, username, props, game_mode, listed) in &entries {
+        if vanished.player_list == VanishedListing::Shown && !vanished.is_changed() {
+            continue;
+        }
+
+        // Entries are overwritten when they're added again.
+        let changed = vanished.is_changed()
+            || uuid.is_changed()
+            || username.is_changed()
+            || props.is_changed()
+            || game_mode.is_changed()
+            || listed.is_changed();
+
+        let entry = packet::PlayerListEntry {
+            player_uuid: uuid.0,
+            listed: listed.0 && vanished.player_list != VanishedListing::Hidden,
+            game_mode: if vanished.player_list == VanishedListing::Greyed {
+                GameMode::Spectator
+            } else {
+                *game_mode
+            },
+            ..Default::default()
+        };
+
+        write_to_others(entity, entry, changed);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_tab_header_footers(
+    mut layers: Query<(Entity, &mut TabHeaderFooter), (With<ChunkLayer>, Without<Client>)>,
+    mut clients: Query<
+        (
+            &mut Client,
+            Ref<VisibleChunkLayer>,
+            Option<&mut TabHeaderFooter>,
+        ),
+        Without<Despawned>,
+    >,
+    mut player_list: ResMut<PlayerList>,
+    server: Res<Server>,
+    mut updated_layers: Local<Vec<Entity>>,
+) {
+    let tick = server.current_tick();
+    let resend = player_list.changed_header_or_footer;
+
+    debug_assert!(updated_layers.is_empty());
+
+    for (entity, mut tab) in &mut layers {
+        if tab.needs_update(tick) {
+            tab.mark_sent(tick);
+            updated_layers.push(entity);
+        }
+    }
+
+    for (mut client, visible_layer, tab) in &mut clients {
+        if let Some(mut tab) = tab {
+            if resend || tab.needs_update(tick) {
+                tab.mark_sent(tick);
+                client.write_packet(&tab.packet());
+            }
+
+            continue;
+        }
+
+        match layers.get(visible_layer.0) {
+            Ok((_, tab)) => {
+                if resend || visible_layer.is_changed() || updated_layers.contains(&visible_layer.0)
+                {
+                    client.write_packet(&tab.packet());
+                }
+            }
+            // Clients leaving a layer with a header and footer go back to those of the
+            // player list.
+            Err(_) => {
+                if visible_layer.is_changed() && !visible_layer.is_added() {
+                    client.write_packet(&PlayerListHeaderS2c {
+                        header: Cow::Borrowed(&player_list.header),
+                        footer: Cow::Borrowed(&player_list.footer),
+                    });
+                }
+            }
+        }
+    }
+
+    updated_layers.clear();
+
+    if resend {
+        player_list.changed_header_or_footer = false;
+    }
+}
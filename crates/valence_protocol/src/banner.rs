@@ -0,0 +1,337 @@
+//! Patterns of banners and shields.
+//!
+//! The patterns are stored as NBT in the banner block entity, and in the
+//! `BlockEntityTag` of banner and shield items. [`BannerPatterns`] builds and
+//! reads that NBT:
+//!
+//! ```
+//! # use valence_protocol::banner::{BannerPattern, BannerPatterns};
+//! # use valence_protocol::{DyeColor, ItemKind};
+//! let shield = BannerPatterns::new()
+//!     .with_layer(BannerPattern::Creeper, DyeColor::Lime)
+//!     .with_layer(BannerPattern::Border, DyeColor::Black)
+//!     .shield(DyeColor::Green);
+//!
+//! assert_eq!(shield.item, ItemKind::Shield);
+//! ```
+
+use valence_nbt::{compound, Compound, List, Value};
+
+use crate::{DyeColor, ItemKind, ItemStack};
+
+/// A pattern which can be put on a banner with a loom.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BannerPattern {
+    Base,
+    SquareBottomLeft,
+    SquareBottomRight,
+    SquareTopLeft,
+    SquareTopRight,
+    StripeBottom,
+    StripeTop,
+    StripeLeft,
+    StripeRight,
+    StripeCenter,
+    StripeMiddle,
+    StripeDownright,
+    StripeDownleft,
+    SmallStripes,
+    Cross,
+    StraightCross,
+    TriangleBottom,
+    TriangleTop,
+    TrianglesBottom,
+    TrianglesTop,
+    DiagonalLeft,
+    DiagonalUpRight,
+    DiagonalUpLeft,
+    DiagonalRight,
+    Circle,
+    Rhombus,
+    HalfVertical,
+    HalfHorizontal,
+    HalfVerticalRight,
+    HalfHorizontalBottom,
+    Border,
+    CurlyBorder,
+    Gradient,
+    GradientUp,
+    Bricks,
+    Globe,
+    Creeper,
+    Skull,
+    Flower,
+    Mojang,
+    Piglin,
+}
+
+impl BannerPattern {
+    pub const ALL: [Self; 41] = [
+        Self::Base,
+        Self::SquareBottomLeft,
+        Self::SquareBottomRight,
+        Self::SquareTopLeft,
+        Self::SquareTopRight,
+        Self::StripeBottom,
+        Self::StripeTop,
+        Self::StripeLeft,
+        Self::StripeRight,
+        Self::StripeCenter,
+        Self::StripeMiddle,
+        Self::StripeDownright,
+        Self::StripeDownleft,
+        Self::SmallStripes,
+        Self::Cross,
+        Self::StraightCross,
+        Self::TriangleBottom,
+        Self::TriangleTop,
+        Self::TrianglesBottom,
+        Self::TrianglesTop,
+        Self::DiagonalLeft,
+        Self::DiagonalUpRight,
+        Self::DiagonalUpLeft,
+        Self::DiagonalRight,
+        Self::Circle,
+        Self::Rhombus,
+        Self::HalfVertical,
+        Self::HalfHorizontal,
+        Self::HalfVerticalRight,
+        Self::HalfHorizontalBottom,
+        Self::Border,
+        Self::CurlyBorder,
+        Self::Gradient,
+        Self::GradientUp,
+        Self::Bricks,
+        Self::Globe,
+        Self::Creeper,
+        Self::Skull,
+        Self::Flower,
+        Self::Mojang,
+        Self::Piglin,
+    ];
+
+    /// The name of the pattern in the `banner_pattern` registry, without the
+    /// `minecraft:` namespace.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Base => "base",
+            Self::SquareBottomLeft => "square_bottom_left",
+            Self::SquareBottomRight => "square_bottom_right",
+            Self::SquareTopLeft => "square_top_left",
+            Self::SquareTopRight => "square_top_right",
+            Self::StripeBottom => "stripe_bottom",
+            Self::StripeTop => "stripe_top",
+            Self::StripeLeft => "stripe_left",
+            Self::StripeRight => "stripe_right",
+            Self::StripeCenter => "stripe_center",
+            Self::StripeMiddle => "stripe_middle",
+            Self::StripeDownright => "stripe_downright",
+            Self::StripeDownleft => "stripe_downleft",
+            Self::SmallStripes => "small_stripes",
+            Self::Cross => "cross",
+            Self::StraightCross => "straight_cross",
+            Self::TriangleBottom => "triangle_bottom",
+            Self::TriangleTop => "triangle_top",
+            Self::TrianglesBottom => "triangles_bottom",
+            Self::TrianglesTop => "triangles_top",
+            Self::DiagonalLeft => "diagonal_left",
+            Self::DiagonalUpRight => "diagonal_up_right",
+            Self::DiagonalUpLeft => "diagonal_up_left",
+            Self::DiagonalRight => "diagonal_right",
+            Self::Circle => "circle",
+            Self::Rhombus => "rhombus",
+            Self::HalfVertical => "half_vertical",
+            Self::HalfHorizontal => "half_horizontal",
+            Self::HalfVerticalRight => "half_vertical_right",
+            Self::HalfHorizontalBottom => "half_horizontal_bottom",
+            Self::Border => "border",
+            Self::CurlyBorder => "curly_border",
+            Self::Gradient => "gradient",
+            Self::GradientUp => "gradient_up",
+            Self::Bricks => "bricks",
+            Self::Globe => "globe",
+            Self::Creeper => "creeper",
+            Self::Skull => "skull",
+            Self::Flower => "flower",
+            Self::Mojang => "mojang",
+            Self::Piglin => "piglin",
+        }
+    }
+
+    /// The short code identifying the pattern in NBT.
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::Base => "b",
+            Self::SquareBottomLeft => "bl",
+            Self::SquareBottomRight => "br",
+            Self::SquareTopLeft => "tl",
+            Self::SquareTopRight => "tr",
+            Self::StripeBottom => "bs",
+            Self::StripeTop => "ts",
+            Self::StripeLeft => "ls",
+            Self::StripeRight => "rs",
+            Self::StripeCenter => "cs",
+            Self::StripeMiddle => "ms",
+            Self::StripeDownright => "drs",
+            Self::StripeDownleft => "dls",
+            Self::SmallStripes => "ss",
+            Self::Cross => "cr",
+            Self::StraightCross => "sc",
+            Self::TriangleBottom => "bt",
+            Self::TriangleTop => "tt",
+            Self::TrianglesBottom => "bts",
+            Self::TrianglesTop => "tts",
+            Self::DiagonalLeft => "ld",
+            Self::DiagonalUpRight => "rd",
+            Self::DiagonalUpLeft => "lud",
+            Self::DiagonalRight => "rud",
+            Self::Circle => "mc",
+            Self::Rhombus => "mr",
+            Self::HalfVertical => "vh",
+            Self::HalfHorizontal => "hh",
+            Self::HalfVerticalRight => "vhr",
+            Self::HalfHorizontalBottom => "hhb",
+            Self::Border => "bo",
+            Self::CurlyBorder => "cbo",
+            Self::Gradient => "gra",
+            Self::GradientUp => "gru",
+            Self::Bricks => "bri",
+            Self::Globe => "glb",
+            Self::Creeper => "cre",
+            Self::Skull => "sku",
+            Self::Flower => "flo",
+            Self::Mojang => "moj",
+            Self::Piglin => "pig",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pattern| pattern.code() == code)
+    }
+}
+
+/// A pattern drawn on a banner in a single color.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BannerLayer {
+    pub pattern: BannerPattern,
+    pub color: DyeColor,
+}
+
+/// The layers of patterns on a banner or shield, from the bottom up.
+///
+/// Clients only draw the first 16 layers. Looms don't add more than 6.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct BannerPatterns {
+    pub layers: Vec<BannerLayer>,
+}
+
+impl BannerPatterns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a layer on top of the existing ones.
+    #[must_use]
+    pub fn with_layer(mut self, pattern: BannerPattern, color: DyeColor) -> Self {
+        self.layers.push(BannerLayer { pattern, color });
+        self
+    }
+
+    /// The `Patterns` list of the NBT.
+    pub fn to_nbt_list(&self) -> List {
+        List::Compound(
+            self.layers
+                .iter()
+                .map(|layer| {
+                    compound! {
+                        "Pattern" => layer.pattern.code(),
+                        "Color" => layer.color.id(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Reads the layers from the NBT of a banner block entity or the
+    /// `BlockEntityTag` of a banner or shield. Unknown layers are skipped.
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let Some(Value::List(List::Compound(patterns))) = nbt.get("Patterns") else {
+            return Self::default();
+        };
+
+        let layers = patterns
+            .iter()
+            .filter_map(|layer| {
+                let pattern = match layer.get("Pattern") {
+                    Some(Value::String(code)) => BannerPattern::from_code(code)?,
+                    _ => return None,
+                };
+
+                let color = match layer.get("Color") {
+                    Some(Value::Int(id)) => DyeColor::from_id(*id)?,
+                    _ => return None,
+                };
+
+                Some(BannerLayer { pattern, color })
+            })
+            .collect();
+
+        Self { layers }
+    }
+
+    /// The NBT of a banner block entity with these patterns.
+    pub fn to_block_entity_nbt(&self) -> Compound {
+        compound! {
+            "Patterns" => self.to_nbt_list(),
+        }
+    }
+
+    /// A banner item of color `base` with these patterns.
+    pub fn banner(&self, base: DyeColor) -> ItemStack {
+        let nbt = compound! {
+            "BlockEntityTag" => self.to_block_entity_nbt(),
+        };
+
+        ItemStack::new(base.banner(), 1, Some(nbt))
+    }
+
+    /// A shield decorated with a banner of color `base` with these patterns.
+    pub fn shield(&self, base: DyeColor) -> ItemStack {
+        let nbt = compound! {
+            "BlockEntityTag" => compound! {
+                "Base" => base.id(),
+                "Patterns" => self.to_nbt_list(),
+            },
+        };
+
+        ItemStack::new(ItemKind::Shield, 1, Some(nbt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_codes_round_trip() {
+        for pattern in BannerPattern::ALL {
+            assert_eq!(BannerPattern::from_code(pattern.code()), Some(pattern));
+        }
+    }
+
+    #[test]
+    fn banner_patterns_round_trip() {
+        let patterns = BannerPatterns::new()
+            .with_layer(BannerPattern::Creeper, DyeColor::Lime)
+            .with_layer(BannerPattern::Border, DyeColor::Black);
+
+        let shield = patterns.shield(DyeColor::Green);
+
+        let Some(Value::Compound(tag)) = shield.nbt.as_ref().unwrap().get("BlockEntityTag") else {
+            panic!("shield has no block entity tag");
+        };
+
+        assert_eq!(tag.get("Base"), Some(&Value::Int(DyeColor::Green.id())));
+        assert_eq!(BannerPatterns::from_nbt(tag), patterns);
+    }
+}
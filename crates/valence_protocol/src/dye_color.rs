@@ -0,0 +1,123 @@
+use crate::ItemKind;
+
+/// One of the 16 colors of dye.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum DyeColor {
+    #[default]
+    White,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+}
+
+impl DyeColor {
+    pub const ALL: [Self; 16] = [
+        Self::White,
+        Self::Orange,
+        Self::Magenta,
+        Self::LightBlue,
+        Self::Yellow,
+        Self::Lime,
+        Self::Pink,
+        Self::Gray,
+        Self::LightGray,
+        Self::Cyan,
+        Self::Purple,
+        Self::Blue,
+        Self::Brown,
+        Self::Green,
+        Self::Red,
+        Self::Black,
+    ];
+
+    /// The numeric ID of the color, as used in NBT.
+    pub const fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub const fn from_id(id: i32) -> Option<Self> {
+        if 0 <= id && id < Self::ALL.len() as i32 {
+            Some(Self::ALL[id as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The snake_case name of the color.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::White => "white",
+            Self::Orange => "orange",
+            Self::Magenta => "magenta",
+            Self::LightBlue => "light_blue",
+            Self::Yellow => "yellow",
+            Self::Lime => "lime",
+            Self::Pink => "pink",
+            Self::Gray => "gray",
+            Self::LightGray => "light_gray",
+            Self::Cyan => "cyan",
+            Self::Purple => "purple",
+            Self::Blue => "blue",
+            Self::Brown => "brown",
+            Self::Green => "green",
+            Self::Red => "red",
+            Self::Black => "black",
+        }
+    }
+
+    /// The RGB color of firework explosions made with this dye.
+    pub const fn firework_color(self) -> u32 {
+        match self {
+            Self::White => 0xf0f0f0,
+            Self::Orange => 0xeb8844,
+            Self::Magenta => 0xc354cd,
+            Self::LightBlue => 0x6689d3,
+            Self::Yellow => 0xdecf2a,
+            Self::Lime => 0x41cd34,
+            Self::Pink => 0xd88198,
+            Self::Gray => 0x434343,
+            Self::LightGray => 0xababab,
+            Self::Cyan => 0x287697,
+            Self::Purple => 0x7b2fbe,
+            Self::Blue => 0x253192,
+            Self::Brown => 0x51301a,
+            Self::Green => 0x3b511a,
+            Self::Red => 0xb3312c,
+            Self::Black => 0x1e1b1b,
+        }
+    }
+
+    /// The banner item of this color.
+    pub const fn banner(self) -> ItemKind {
+        match self {
+            Self::White => ItemKind::WhiteBanner,
+            Self::Orange => ItemKind::OrangeBanner,
+            Self::Magenta => ItemKind::MagentaBanner,
+            Self::LightBlue => ItemKind::LightBlueBanner,
+            Self::Yellow => ItemKind::YellowBanner,
+            Self::Lime => ItemKind::LimeBanner,
+            Self::Pink => ItemKind::PinkBanner,
+            Self::Gray => ItemKind::GrayBanner,
+            Self::LightGray => ItemKind::LightGrayBanner,
+            Self::Cyan => ItemKind::CyanBanner,
+            Self::Purple => ItemKind::PurpleBanner,
+            Self::Blue => ItemKind::BlueBanner,
+            Self::Brown => ItemKind::BrownBanner,
+            Self::Green => ItemKind::GreenBanner,
+            Self::Red => ItemKind::RedBanner,
+            Self::Black => ItemKind::BlackBanner,
+        }
+    }
+}
@@ -1,13 +1,18 @@
 use std::io::Write;
+use std::mem;
 
 #[cfg(feature = "encryption")]
 use aes::cipher::generic_array::GenericArray;
 #[cfg(feature = "encryption")]
 use aes::cipher::{BlockEncryptMut, BlockSizeUser, KeyIvInit};
 use anyhow::ensure;
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use tracing::warn;
 
+#[cfg(feature = "compression")]
+use crate::decode::PacketDecoder;
+use crate::decode::PacketFrame;
+use crate::packets::play::BundleSplitterS2c;
 use crate::var_int::VarInt;
 use crate::{CompressionThreshold, Encode, Packet, MAX_PACKET_SIZE};
 
@@ -16,15 +21,63 @@ use crate::{CompressionThreshold, Encode, Packet, MAX_PACKET_SIZE};
 #[cfg(feature = "encryption")]
 type Cipher = cfb8::Encryptor<aes::Aes128>;
 
-#[derive(Default)]
+/// The zlib compression level used to compress packets unless configured
+/// otherwise. Levels range from 0 (no compression) to 9 (best compression).
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 4;
+
+/// The maximum number of packets in a bundle accepted by the client.
+pub const MAX_BUNDLE_PACKETS: usize = 4096;
+
+/// How urgently a packet should be sent relative to the packets written
+/// before it. See [`WritePacket::write_packet_with_priority`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum PacketPriority {
+    /// Packets which must arrive on time, such as keepalives and teleports.
+    /// They're sent ahead of the other packets which haven't been sent yet.
+    Critical,
+    /// Packets which are sent in the order they're written.
+    #[default]
+    Normal,
+    /// Large packets such as chunks. They're still sent in order, but
+    /// separately from the rest of the data so that critical packets can be
+    /// sent between them.
+    Bulk,
+}
+
 pub struct PacketEncoder {
+    /// Packets written with [`PacketPriority::Critical`], which are taken
+    /// before the other packets.
+    critical: BytesMut,
+    /// Packet data written before `buf`. This includes data shared with other
+    /// encoders, which is reference counted instead of copied.
+    chunks: Vec<Bytes>,
     buf: BytesMut,
     #[cfg(feature = "compression")]
     compress_buf: Vec<u8>,
     #[cfg(feature = "compression")]
     threshold: CompressionThreshold,
+    #[cfg(feature = "compression")]
+    compression_level: u32,
     #[cfg(feature = "encryption")]
-    cipher: Option<Cipher>,
+    cipher: Option<PacketEncryptor>,
+}
+
+impl Default for PacketEncoder {
+    fn default() -> Self {
+        Self {
+            critical: BytesMut::new(),
+            chunks: Vec::new(),
+            buf: BytesMut::new(),
+            #[cfg(feature = "compression")]
+            compress_buf: Vec::new(),
+            #[cfg(feature = "compression")]
+            threshold: CompressionThreshold::default(),
+            #[cfg(feature = "compression")]
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+            #[cfg(feature = "encryption")]
+            cipher: None,
+        }
+    }
 }
 
 impl PacketEncoder {
@@ -37,10 +90,91 @@ impl PacketEncoder {
         self.buf.extend_from_slice(bytes)
     }
 
+    /// Appends packet data that may be shared with other encoders, such as
+    /// packets broadcast to many clients. Unlike
+    /// [`append_bytes`](Self::append_bytes), the data isn't copied unless it
+    /// has to be encrypted by this encoder.
+    pub fn append_shared_bytes(&mut self, bytes: Bytes) {
+        #[cfg(feature = "encryption")]
+        if self.cipher.is_some() {
+            self.append_bytes(&bytes);
+            return;
+        }
+
+        if bytes.is_empty() {
+            return;
+        }
+
+        self.split_chunk();
+        self.chunks.push(bytes);
+    }
+
+    /// Moves the data in `buf` to a new chunk.
+    fn split_chunk(&mut self) {
+        if !self.buf.is_empty() {
+            self.chunks.push(self.buf.split().freeze());
+        }
+    }
+
+    /// Calls `f` with this encoder, and gives the packets it writes the
+    /// priority `priority`.
+    pub fn with_priority<F, R>(&mut self, priority: PacketPriority, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        match priority {
+            PacketPriority::Critical => {
+                let chunks = mem::take(&mut self.chunks);
+                let buf = self.buf.split();
+
+                let res = f(self);
+
+                // Shared data is copied into the critical packets.
+                for chunk in self.chunks.drain(..) {
+                    self.critical.extend_from_slice(&chunk);
+                }
+
+                self.critical.extend_from_slice(&self.buf);
+                self.buf.clear();
+
+                self.chunks = chunks;
+                self.buf.unsplit(buf);
+
+                res
+            }
+            PacketPriority::Normal => f(self),
+            PacketPriority::Bulk => {
+                // Encrypted data must be sent in order anyway.
+                #[cfg(feature = "encryption")]
+                if self.cipher.is_some() {
+                    return f(self);
+                }
+
+                self.split_chunk();
+                let res = f(self);
+                self.split_chunk();
+
+                res
+            }
+        }
+    }
+
     pub fn prepend_packet<P>(&mut self, pkt: &P) -> anyhow::Result<()>
     where
         P: Packet + Encode,
     {
+        if !self.chunks.is_empty() {
+            let rest = self.buf.split();
+            let res = self.append_packet(pkt);
+            let pkt_bytes = self.buf.split().freeze();
+            self.buf.unsplit(rest);
+            res?;
+
+            self.chunks.insert(0, pkt_bytes);
+
+            return Ok(());
+        }
+
         let start_len = self.buf.len();
         self.append_packet(pkt)?;
 
@@ -67,6 +201,56 @@ impl PacketEncoder {
 
         pkt.encode_with_id((&mut self.buf).writer())?;
 
+        self.frame_data(start_len)
+    }
+
+    /// Appends a packet that was already decoded into a [`PacketFrame`],
+    /// compressing it according to this encoder's settings.
+    pub fn append_frame(&mut self, frame: &PacketFrame) -> anyhow::Result<()> {
+        let start_len = self.buf.len();
+
+        VarInt(frame.id).encode((&mut self.buf).writer())?;
+        self.buf.extend_from_slice(&frame.body);
+
+        self.frame_data(start_len)
+    }
+
+    /// Appends packet data that was encoded with the compression `threshold`.
+    /// If `threshold` differs from this encoder's, the packets are decoded and
+    /// encoded again, which is much slower than copying them with
+    /// [`append_bytes`](Self::append_bytes).
+    pub fn append_bytes_with_threshold(
+        &mut self,
+        bytes: &[u8],
+        threshold: CompressionThreshold,
+    ) -> anyhow::Result<()> {
+        #[cfg(feature = "compression")]
+        if threshold != self.threshold {
+            let mut dec = PacketDecoder::new();
+            dec.set_compression(threshold);
+            dec.queue_slice(bytes);
+
+            while let Some(frame) = dec.try_next_packet()? {
+                self.append_frame(&frame)?;
+            }
+
+            return Ok(());
+        }
+
+        #[cfg(not(feature = "compression"))]
+        ensure!(
+            threshold.0 < 0,
+            "\"compression\" feature must be enabled to read compressed packets"
+        );
+
+        self.append_bytes(bytes);
+
+        Ok(())
+    }
+
+    /// Adds the length prefix, and compresses if needed, to the packet data
+    /// written after `start_len`.
+    fn frame_data(&mut self, start_len: usize) -> anyhow::Result<()> {
         let data_len = self.buf.len() - start_len;
 
         #[cfg(feature = "compression")]
@@ -77,7 +261,10 @@ impl PacketEncoder {
             use flate2::Compression;
 
             if data_len > self.threshold.0 as usize {
-                let mut z = ZlibEncoder::new(&self.buf[start_len..], Compression::new(4));
+                let mut z = ZlibEncoder::new(
+                    &self.buf[start_len..],
+                    Compression::new(self.compression_level),
+                );
 
                 self.compress_buf.clear();
 
@@ -147,27 +334,119 @@ impl PacketEncoder {
 
     /// Takes all the packets written so far and encrypts them if encryption is
     /// enabled.
+    ///
+    /// Shared data from [`append_shared_bytes`](Self::append_shared_bytes) is
+    /// copied into the returned buffer. Use [`take_chunks`](Self::take_chunks)
+    /// to avoid the copy.
     pub fn take(&mut self) -> BytesMut {
+        let critical = self.take_critical();
+
+        if self.chunks.is_empty() {
+            let mut bytes = critical;
+            bytes.unsplit(self.take_buf());
+            return bytes;
+        }
+
+        let len =
+            critical.len() + self.chunks.iter().map(Bytes::len).sum::<usize>() + self.buf.len();
+        let mut bytes = BytesMut::with_capacity(len);
+
+        bytes.extend_from_slice(&critical);
+
+        for chunk in self.chunks.drain(..) {
+            bytes.extend_from_slice(&chunk);
+        }
+
+        bytes.unsplit(self.take_buf());
+        bytes
+    }
+
+    /// Like [`take`](Self::take), but returns the packets as a sequence of
+    /// chunks so that shared data doesn't need to be copied.
+    pub fn take_chunks(&mut self) -> impl Iterator<Item = Bytes> + '_ {
+        let critical = self.take_critical().freeze();
+        let last = self.take_buf().freeze();
+
+        (!critical.is_empty())
+            .then_some(critical)
+            .into_iter()
+            .chain(self.chunks.drain(..))
+            .chain((!last.is_empty()).then_some(last))
+    }
+
+    /// Takes only the packets written with [`PacketPriority::Critical`] and
+    /// encrypts them if encryption is enabled, so that they can be sent ahead
+    /// of the packets taken before.
+    pub fn take_critical(&mut self) -> BytesMut {
+        let mut bytes = self.critical.split();
+
         #[cfg(feature = "encryption")]
         if let Some(cipher) = &mut self.cipher {
-            for chunk in self.buf.chunks_mut(Cipher::block_size()) {
-                let gen_arr = GenericArray::from_mut_slice(chunk);
-                cipher.encrypt_block_mut(gen_arr);
-            }
+            cipher.encrypt(&mut bytes);
+        }
+
+        bytes
+    }
+
+    fn take_buf(&mut self) -> BytesMut {
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = &mut self.cipher {
+            cipher.encrypt(&mut self.buf);
         }
 
         self.buf.split()
     }
 
     pub fn clear(&mut self) {
+        self.critical.clear();
+        self.chunks.clear();
         self.buf.clear();
     }
 
+    /// Returns an empty encoder which compresses packets like this one, but
+    /// doesn't encrypt them.
+    pub fn clone_settings(&self) -> Self {
+        Self {
+            #[cfg(feature = "compression")]
+            threshold: self.threshold,
+            #[cfg(feature = "compression")]
+            compression_level: self.compression_level,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the compression threshold of this encoder. Compression is
+    /// always disabled without the `compression` feature.
+    pub fn compression(&self) -> CompressionThreshold {
+        #[cfg(feature = "compression")]
+        {
+            self.threshold
+        }
+
+        #[cfg(not(feature = "compression"))]
+        {
+            CompressionThreshold::DEFAULT
+        }
+    }
+
     #[cfg(feature = "compression")]
     pub fn set_compression(&mut self, threshold: CompressionThreshold) {
         self.threshold = threshold;
     }
 
+    #[cfg(feature = "compression")]
+    pub fn compression_level(&self) -> u32 {
+        self.compression_level
+    }
+
+    /// Sets the zlib compression level of packets encoded from now on. Higher
+    /// levels produce smaller packets at the cost of more CPU time. `level` is
+    /// clamped to `0..=9`. The default is [`DEFAULT_COMPRESSION_LEVEL`].
+    #[cfg(feature = "compression")]
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression_level = level.min(9);
+    }
+
     /// Initializes the cipher with the given key. All future packets **and any
     /// that have not been [taken] yet** are encrypted.
     ///
@@ -179,7 +458,58 @@ impl PacketEncoder {
     #[cfg(feature = "encryption")]
     pub fn enable_encryption(&mut self, key: &[u8; 16]) {
         assert!(self.cipher.is_none(), "encryption is already enabled");
-        self.cipher = Some(Cipher::new_from_slices(key, key).expect("invalid key"));
+
+        if !self.chunks.is_empty() {
+            let bytes = self.take();
+            self.buf.unsplit(bytes);
+        }
+
+        self.cipher = Some(PacketEncryptor {
+            cipher: Cipher::new_from_slices(key, key).expect("invalid key"),
+        });
+    }
+
+    /// Returns whether the packets taken from this encoder are encrypted.
+    /// Encryption is always disabled without the `encryption` feature.
+    pub fn is_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        {
+            self.cipher.is_some()
+        }
+
+        #[cfg(not(feature = "encryption"))]
+        {
+            false
+        }
+    }
+
+    /// Removes the cipher from this encoder so that packet data can be
+    /// encrypted somewhere else, such as on another thread. Data
+    /// [taken](Self::take) from now on is not encrypted and must be passed to
+    /// the returned [`PacketEncryptor`] in the same order.
+    ///
+    /// Returns `None` if encryption is not enabled.
+    #[cfg(feature = "encryption")]
+    pub fn take_encryptor(&mut self) -> Option<PacketEncryptor> {
+        self.cipher.take()
+    }
+}
+
+/// Encrypts packet data separately from a [`PacketEncoder`]. See
+/// [`PacketEncoder::take_encryptor`].
+#[cfg(feature = "encryption")]
+pub struct PacketEncryptor {
+    cipher: Cipher,
+}
+
+#[cfg(feature = "encryption")]
+impl PacketEncryptor {
+    /// Encrypts `data` in place.
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for chunk in data.chunks_mut(Cipher::block_size()) {
+            let gen_arr = GenericArray::from_mut_slice(chunk);
+            self.cipher.encrypt_block_mut(gen_arr);
+        }
     }
 }
 
@@ -205,6 +535,114 @@ pub trait WritePacket {
     /// Copies raw packet data directly into this object. Don't use this unless
     /// you know what you're doing.
     fn write_packet_bytes(&mut self, bytes: &[u8]);
+
+    /// Like [`write_packet`](Self::write_packet), but with a
+    /// [`PacketPriority`] for the packet. Writers which don't send packets
+    /// themselves ignore the priority.
+    fn write_packet_with_priority<P>(&mut self, packet: &P, priority: PacketPriority)
+    where
+        P: Packet + Encode,
+    {
+        let _ = priority;
+        self.write_packet(packet)
+    }
+
+    /// Like [`write_packet_bytes`](Self::write_packet_bytes), but with a
+    /// [`PacketPriority`] for the packets.
+    fn write_packet_bytes_with_priority(&mut self, bytes: &[u8], priority: PacketPriority) {
+        let _ = priority;
+        self.write_packet_bytes(bytes)
+    }
+
+    /// Writes the packets `f` writes as a bundle, which the client handles
+    /// all at once on the same frame. This avoids glitches like entities
+    /// appearing for a frame before their metadata is applied.
+    ///
+    /// Nothing is written if `f` doesn't write any packets, and bundles with
+    /// more than [`MAX_BUNDLE_PACKETS`] packets are split. Priorities of the
+    /// packets in the bundle are ignored so they stay together. Bundles can't
+    /// be nested, so bundles written to the [`BundleWriter`] become part of the
+    /// outer bundle.
+    fn write_bundle<F, R>(&mut self, f: F) -> R
+    where
+        Self: Sized,
+        F: FnOnce(&mut BundleWriter<&mut Self>) -> R,
+    {
+        let nested = self.is_bundling();
+
+        let mut writer = BundleWriter {
+            writer: self,
+            count: 0,
+            nested,
+        };
+
+        let res = f(&mut writer);
+        writer.end();
+        res
+    }
+
+    /// Returns whether the packets written to this object are already part of
+    /// a bundle. Writers wrapping other writers should forward this.
+    fn is_bundling(&self) -> bool {
+        false
+    }
+}
+
+/// A [`WritePacket`] which puts the packets written to it into a bundle. See
+/// [`WritePacket::write_bundle`].
+#[derive(Debug)]
+pub struct BundleWriter<W: WritePacket> {
+    writer: W,
+    /// The number of packets in the current bundle.
+    count: usize,
+    /// If the packets are written to an outer bundle instead.
+    nested: bool,
+}
+
+impl<W: WritePacket> BundleWriter<W> {
+    fn begin(&mut self) {
+        if self.nested {
+            return;
+        }
+
+        if self.count == MAX_BUNDLE_PACKETS {
+            self.end();
+        }
+
+        if self.count == 0 {
+            self.writer.write_packet(&BundleSplitterS2c);
+        }
+
+        self.count += 1;
+    }
+
+    fn end(&mut self) {
+        if self.count > 0 {
+            self.writer.write_packet(&BundleSplitterS2c);
+            self.count = 0;
+        }
+    }
+}
+
+impl<W: WritePacket> WritePacket for BundleWriter<W> {
+    fn write_packet_fallible<P>(&mut self, packet: &P) -> anyhow::Result<()>
+    where
+        P: Packet + Encode,
+    {
+        self.begin();
+        self.writer.write_packet_fallible(packet)
+    }
+
+    /// The bytes are counted as a single packet towards
+    /// [`MAX_BUNDLE_PACKETS`].
+    fn write_packet_bytes(&mut self, bytes: &[u8]) {
+        self.begin();
+        self.writer.write_packet_bytes(bytes)
+    }
+
+    fn is_bundling(&self) -> bool {
+        true
+    }
 }
 
 impl<W: WritePacket> WritePacket for &mut W {
@@ -218,6 +656,21 @@ impl<W: WritePacket> WritePacket for &mut W {
     fn write_packet_bytes(&mut self, bytes: &[u8]) {
         (*self).write_packet_bytes(bytes)
     }
+
+    fn write_packet_with_priority<P>(&mut self, packet: &P, priority: PacketPriority)
+    where
+        P: Packet + Encode,
+    {
+        (*self).write_packet_with_priority(packet, priority)
+    }
+
+    fn write_packet_bytes_with_priority(&mut self, bytes: &[u8], priority: PacketPriority) {
+        (*self).write_packet_bytes_with_priority(bytes, priority)
+    }
+
+    fn is_bundling(&self) -> bool {
+        (**self).is_bundling()
+    }
 }
 
 impl<T: WritePacket> WritePacket for bevy_ecs::world::Mut<'_, T> {
@@ -231,6 +684,22 @@ impl<T: WritePacket> WritePacket for bevy_ecs::world::Mut<'_, T> {
     fn write_packet_bytes(&mut self, bytes: &[u8]) {
         self.as_mut().write_packet_bytes(bytes)
     }
+
+    fn write_packet_with_priority<P>(&mut self, packet: &P, priority: PacketPriority)
+    where
+        P: Packet + Encode,
+    {
+        self.as_mut().write_packet_with_priority(packet, priority)
+    }
+
+    fn write_packet_bytes_with_priority(&mut self, bytes: &[u8], priority: PacketPriority) {
+        self.as_mut()
+            .write_packet_bytes_with_priority(bytes, priority)
+    }
+
+    fn is_bundling(&self) -> bool {
+        (**self).is_bundling()
+    }
 }
 
 /// An implementor of [`WritePacket`] backed by a `Vec` mutable reference.
@@ -298,6 +767,17 @@ impl WritePacket for PacketEncoder {
     fn write_packet_bytes(&mut self, bytes: &[u8]) {
         self.append_bytes(bytes)
     }
+
+    fn write_packet_with_priority<P>(&mut self, packet: &P, priority: PacketPriority)
+    where
+        P: Packet + Encode,
+    {
+        self.with_priority(priority, |enc| enc.write_packet(packet))
+    }
+
+    fn write_packet_bytes_with_priority(&mut self, bytes: &[u8], priority: PacketPriority) {
+        self.with_priority(priority, |enc| enc.append_bytes(bytes))
+    }
 }
 
 fn encode_packet<P>(buf: &mut Vec<u8>, pkt: &P) -> anyhow::Result<()>
@@ -347,7 +827,10 @@ where
     let data_len = buf.len() - start_len;
 
     if data_len > threshold as usize {
-        let mut z = ZlibEncoder::new(&buf[start_len..], Compression::new(4));
+        let mut z = ZlibEncoder::new(
+            &buf[start_len..],
+            Compression::new(DEFAULT_COMPRESSION_LEVEL),
+        );
 
         let mut scratch = vec![];
 
@@ -1,8 +1,38 @@
+use crate::packets::play::client_settings_c2s::MainArm;
 use crate::{Decode, Encode};
 
-#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Encode, Decode)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug, Encode, Decode)]
 pub enum Hand {
     #[default]
     Main,
     Off,
 }
+
+impl Hand {
+    /// Returns the other hand.
+    pub const fn opposite(self) -> Self {
+        match self {
+            Hand::Main => Hand::Off,
+            Hand::Off => Hand::Main,
+        }
+    }
+
+    /// Returns the arm holding the items of this hand for a player whose main
+    /// arm is `main_arm`.
+    pub const fn arm(self, main_arm: MainArm) -> MainArm {
+        match (self, main_arm) {
+            (Hand::Main, arm) => arm,
+            (Hand::Off, MainArm::Left) => MainArm::Right,
+            (Hand::Off, MainArm::Right) => MainArm::Left,
+        }
+    }
+
+    /// Returns the hand whose items are held in `arm` by a player whose main
+    /// arm is `main_arm`.
+    pub const fn from_arm(arm: MainArm, main_arm: MainArm) -> Self {
+        match (arm, main_arm) {
+            (MainArm::Left, MainArm::Left) | (MainArm::Right, MainArm::Right) => Hand::Main,
+            _ => Hand::Off,
+        }
+    }
+}
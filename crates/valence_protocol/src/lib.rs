@@ -13,6 +13,7 @@ pub mod __private {
 extern crate self as valence_protocol;
 
 mod array;
+pub mod banner;
 mod biome_pos;
 mod bit_set;
 pub mod block_pos;
@@ -23,6 +24,7 @@ pub mod chunk_section_pos;
 pub mod decode;
 mod difficulty;
 mod direction;
+mod dye_color;
 pub mod encode;
 pub mod game_mode;
 mod global_pos;
@@ -53,7 +55,8 @@ pub use decode::PacketDecoder;
 use derive_more::{From, Into};
 pub use difficulty::Difficulty;
 pub use direction::Direction;
-pub use encode::{PacketEncoder, WritePacket};
+pub use dye_color::DyeColor;
+pub use encode::{BundleWriter, PacketEncoder, PacketPriority, WritePacket};
 pub use game_mode::GameMode;
 pub use global_pos::GlobalPos;
 pub use hand::Hand;
@@ -64,7 +67,7 @@ pub use raw::RawBytes;
 use serde::{Deserialize, Serialize};
 pub use sound::Sound;
 pub use text::Text;
-pub use valence_generated::{block, packet_id, status_effects};
+pub use valence_generated::{block, packet_id, potions, status_effects};
 pub use valence_ident::Ident;
 pub use valence_protocol_macros::{Decode, Encode, Packet};
 pub use var_int::VarInt;
@@ -121,6 +124,10 @@ impl Default for CompressionThreshold {
 /// values are assigned to variants using rules similar to regular enum
 /// discriminants.
 ///
+/// Fields added or removed in a protocol update can be marked with
+/// `#[packet(since = ...)]` or `#[packet(before = ...)]`. They are only
+/// encoded if [`PROTOCOL_VERSION`] is in range. See [`Decode`] for more.
+///
 /// ```
 /// use valence_protocol::Encode;
 ///
@@ -209,6 +216,17 @@ pub trait Encode {
 /// values are assigned to variants using rules similar to regular enum
 /// discriminants.
 ///
+/// Trailing fields marked with `#[packet(default)]` are set to their
+/// [`Default`] value if the input ends before them. Only trailing fields can be
+/// marked this way.
+///
+/// Fields marked with `#[packet(since = ...)]` only exist in protocol versions
+/// greater than or equal to the given version, and fields marked with
+/// `#[packet(before = ...)]` only exist in versions less than it. Fields
+/// missing from the current [`PROTOCOL_VERSION`] are neither encoded nor
+/// decoded, and are set to their [`Default`] value when decoding. This keeps
+/// protocol updates from having to change every packet using the field.
+///
 /// ```
 /// use valence_protocol::Decode;
 ///
@@ -345,6 +363,19 @@ mod tests {
     #[packet(id = 6, side = PacketSide::Clientbound)]
     struct TupleStructWithGenerics<'z, T = ()>(&'z str, i32, T);
 
+    #[derive(PartialEq, Debug, Encode, Decode)]
+    struct OptionalFields {
+        a: i32,
+        #[packet(since = 1)]
+        b: u8,
+        #[packet(before = 1)]
+        c: u8,
+        #[packet(default)]
+        d: i32,
+        #[packet(default)]
+        e: bool,
+    }
+
     #[allow(unconditional_recursion, clippy::extra_unused_type_parameters)]
     fn assert_has_impls<'a, T>()
     where
@@ -453,4 +484,147 @@ mod tests {
         check_test_packet(&mut dec, "fourth");
         check_test_packet(&mut dec, "third");
     }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn packets_recompressed_for_other_threshold() {
+        let mut shared = PacketEncoder::new();
+        shared.set_compression(0.into());
+        shared.append_packet(&TestPacket::new("first")).unwrap();
+        shared.append_packet(&TestPacket::new("second")).unwrap();
+        let shared = shared.take();
+
+        let mut enc = PacketEncoder::new();
+        enc.set_compression_level(9);
+        enc.append_bytes_with_threshold(&shared, 0.into()).unwrap();
+
+        let mut dec = PacketDecoder::new();
+        dec.queue_bytes(enc.take());
+
+        check_test_packet(&mut dec, "first");
+        check_test_packet(&mut dec, "second");
+        assert!(dec.try_next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "encryption")]
+    fn packets_encrypted_separately() {
+        let mut enc = PacketEncoder::new();
+        enc.enable_encryption(&CRYPT_KEY);
+
+        let mut encryptor = enc.take_encryptor().unwrap();
+        assert!(enc.take_encryptor().is_none());
+
+        let mut buf = BytesMut::new();
+
+        for string in ["first", "second"] {
+            enc.append_packet(&TestPacket::new(string)).unwrap();
+            let mut bytes = enc.take();
+            encryptor.encrypt(&mut bytes);
+            buf.unsplit(bytes);
+        }
+
+        let mut dec = PacketDecoder::new();
+        dec.enable_encryption(&CRYPT_KEY);
+        dec.queue_bytes(buf);
+
+        check_test_packet(&mut dec, "first");
+        check_test_packet(&mut dec, "second");
+    }
+
+    #[test]
+    fn shared_bytes_not_copied() {
+        let mut shared = PacketEncoder::new();
+        shared.append_packet(&TestPacket::new("shared")).unwrap();
+        let shared = shared.take().freeze();
+
+        let mut enc = PacketEncoder::new();
+        enc.append_packet(&TestPacket::new("first")).unwrap();
+        enc.append_shared_bytes(shared.clone());
+        enc.append_packet(&TestPacket::new("last")).unwrap();
+        enc.prepend_packet(&TestPacket::new("prepended")).unwrap();
+
+        let chunks: Vec<_> = enc.take_chunks().collect();
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[2].as_ptr(), shared.as_ptr());
+
+        let mut dec = PacketDecoder::new();
+
+        for chunk in chunks {
+            dec.queue_slice(&chunk);
+        }
+
+        check_test_packet(&mut dec, "prepended");
+        check_test_packet(&mut dec, "first");
+        check_test_packet(&mut dec, "shared");
+        check_test_packet(&mut dec, "last");
+        assert!(enc.take().is_empty());
+    }
+
+    #[test]
+    fn packet_priorities() {
+        let mut enc = PacketEncoder::new();
+        enc.append_packet(&TestPacket::new("first")).unwrap();
+        enc.write_packet_with_priority(&TestPacket::new("bulk"), PacketPriority::Bulk);
+        enc.append_packet(&TestPacket::new("last")).unwrap();
+        enc.write_packet_with_priority(&TestPacket::new("critical"), PacketPriority::Critical);
+
+        let chunks: Vec<_> = enc.take_chunks().collect();
+
+        // The bulk packet is in a chunk of its own.
+        assert_eq!(chunks.len(), 4);
+
+        let mut dec = PacketDecoder::new();
+
+        for chunk in chunks {
+            dec.queue_slice(&chunk);
+        }
+
+        check_test_packet(&mut dec, "critical");
+        check_test_packet(&mut dec, "first");
+        check_test_packet(&mut dec, "bulk");
+        check_test_packet(&mut dec, "last");
+        assert!(enc.take().is_empty());
+    }
+
+    #[test]
+    fn optional_fields() {
+        let value = OptionalFields {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: true,
+        };
+
+        let mut buf = vec![];
+        value.encode(&mut buf).unwrap();
+
+        // `c` was removed before the current protocol version.
+        assert_eq!(buf, [0, 0, 0, 1, 2, 0, 0, 0, 4, 1]);
+
+        let mut r = buf.as_slice();
+        let decoded = OptionalFields::decode(&mut r).unwrap();
+
+        assert_eq!(decoded, OptionalFields { c: 0, ..value });
+        assert!(r.is_empty());
+
+        let mut r = &buf[..5];
+        let decoded = OptionalFields::decode(&mut r).unwrap();
+
+        assert_eq!(
+            decoded,
+            OptionalFields {
+                a: 1,
+                b: 2,
+                c: 0,
+                d: 0,
+                e: false,
+            }
+        );
+
+        // Trailing fields are only defaulted when they're missing entirely.
+        assert!(OptionalFields::decode(&mut &buf[..7]).is_err());
+    }
 }
@@ -46,12 +46,27 @@ pub enum Suggestion {
 #[derive(Clone, Debug, PartialEq)]
 pub enum Parser {
     Bool,
-    Float { min: Option<f32>, max: Option<f32> },
-    Double { min: Option<f64>, max: Option<f64> },
-    Integer { min: Option<i32>, max: Option<i32> },
-    Long { min: Option<i64>, max: Option<i64> },
+    Float {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Long {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
     String(StringArg),
-    Entity { single: bool, only_players: bool },
+    Entity {
+        single: bool,
+        only_players: bool,
+    },
     GameProfile,
     BlockPos,
     ColumnPos,
@@ -74,7 +89,9 @@ pub enum Parser {
     Angle,
     Rotation,
     ScoreboardSlot,
-    ScoreHolder { allow_multiple: bool },
+    ScoreHolder {
+        allow_multiple: bool,
+    },
     Swizzle,
     Team,
     ItemSlot,
@@ -85,14 +102,99 @@ pub enum Parser {
     FloatRange,
     Dimension,
     GameMode,
-    Time,
-    ResourceOrTag { registry: Ident<String> },
-    ResourceOrTagKey { registry: Ident<String> },
-    Resource { registry: Ident<String> },
-    ResourceKey { registry: Ident<String> },
+    /// `min` is the smallest number of ticks allowed.
+    Time {
+        min: i32,
+    },
+    ResourceOrTag {
+        registry: Ident<String>,
+    },
+    ResourceOrTagKey {
+        registry: Ident<String>,
+    },
+    Resource {
+        registry: Ident<String>,
+    },
+    ResourceKey {
+        registry: Ident<String>,
+    },
     TemplateMirror,
     TemplateRotation,
+    Heightmap,
     Uuid,
+    /// Any parser the client knows of, identified by its name in the
+    /// `minecraft:command_argument_type` registry. `properties` are written
+    /// after the parser ID as is, so they must match what the client expects
+    /// for the parser.
+    Custom {
+        parser: Ident<String>,
+        properties: Vec<u8>,
+    },
+}
+
+impl Parser {
+    /// The names of the parsers in the `minecraft:command_argument_type`
+    /// registry, indexed by their ID.
+    pub const NAMES: [&'static str; 49] = [
+        "brigadier:bool",
+        "brigadier:float",
+        "brigadier:double",
+        "brigadier:integer",
+        "brigadier:long",
+        "brigadier:string",
+        "minecraft:entity",
+        "minecraft:game_profile",
+        "minecraft:block_pos",
+        "minecraft:column_pos",
+        "minecraft:vec3",
+        "minecraft:vec2",
+        "minecraft:block_state",
+        "minecraft:block_predicate",
+        "minecraft:item_stack",
+        "minecraft:item_predicate",
+        "minecraft:color",
+        "minecraft:component",
+        "minecraft:message",
+        "minecraft:nbt_compound_tag",
+        "minecraft:nbt_tag",
+        "minecraft:nbt_path",
+        "minecraft:objective",
+        "minecraft:objective_criteria",
+        "minecraft:operation",
+        "minecraft:particle",
+        "minecraft:angle",
+        "minecraft:rotation",
+        "minecraft:scoreboard_slot",
+        "minecraft:score_holder",
+        "minecraft:swizzle",
+        "minecraft:team",
+        "minecraft:item_slot",
+        "minecraft:resource_location",
+        "minecraft:function",
+        "minecraft:entity_anchor",
+        "minecraft:int_range",
+        "minecraft:float_range",
+        "minecraft:dimension",
+        "minecraft:gamemode",
+        "minecraft:time",
+        "minecraft:resource_or_tag",
+        "minecraft:resource_or_tag_key",
+        "minecraft:resource",
+        "minecraft:resource_key",
+        "minecraft:template_mirror",
+        "minecraft:template_rotation",
+        "minecraft:heightmap",
+        "minecraft:uuid",
+    ];
+
+    /// Returns the ID of the parser with the given name, if the client knows
+    /// of it.
+    pub fn id_of(name: &str) -> Option<i32> {
+        Self::NAMES
+            .iter()
+            .position(|&n| n == name)
+            .map(|id| id as i32)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Encode, Decode)]
@@ -309,7 +411,10 @@ impl Encode for Parser {
             Parser::FloatRange => 37_u8.encode(&mut w)?,
             Parser::Dimension => 38_u8.encode(&mut w)?,
             Parser::GameMode => 39_u8.encode(&mut w)?,
-            Parser::Time => 40_u8.encode(&mut w)?,
+            Parser::Time { min } => {
+                40_u8.encode(&mut w)?;
+                min.encode(&mut w)?;
+            }
             Parser::ResourceOrTag { registry } => {
                 41_u8.encode(&mut w)?;
                 registry.encode(&mut w)?;
@@ -328,7 +433,16 @@ impl Encode for Parser {
             }
             Parser::TemplateMirror => 45_u8.encode(&mut w)?,
             Parser::TemplateRotation => 46_u8.encode(&mut w)?,
-            Parser::Uuid => 47_u8.encode(&mut w)?,
+            Parser::Heightmap => 47_u8.encode(&mut w)?,
+            Parser::Uuid => 48_u8.encode(&mut w)?,
+            Parser::Custom { parser, properties } => {
+                let Some(id) = Parser::id_of(parser.as_str()) else {
+                    bail!("unknown command parser \"{parser}\"");
+                };
+
+                VarInt(id).encode(&mut w)?;
+                w.write_all(properties)?;
+            }
         }
 
         Ok(())
@@ -418,7 +532,9 @@ impl<'a> Decode<'a> for Parser {
             37 => Self::FloatRange,
             38 => Self::Dimension,
             39 => Self::GameMode,
-            40 => Self::Time,
+            40 => Self::Time {
+                min: i32::decode(r)?,
+            },
             41 => Self::ResourceOrTag {
                 registry: Ident::decode(r)?,
             },
@@ -433,8 +549,68 @@ impl<'a> Decode<'a> for Parser {
             },
             45 => Self::TemplateMirror,
             46 => Self::TemplateRotation,
-            47 => Self::Uuid,
+            47 => Self::Heightmap,
+            48 => Self::Uuid,
             n => bail!("unknown command parser ID of {n}"),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use valence_ident::ident;
+
+    use super::*;
+
+    fn encoded(parser: &Parser) -> Vec<u8> {
+        let mut buf = vec![];
+        parser.encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn parser_ids_match_names() {
+        assert_eq!(encoded(&Parser::Heightmap), [47]);
+        assert_eq!(encoded(&Parser::Uuid), [48]);
+        assert_eq!(Parser::id_of("minecraft:uuid"), Some(48));
+        assert_eq!(Parser::id_of("minecraft:nonexistent"), None);
+    }
+
+    #[test]
+    fn parser_round_trip() {
+        for parser in [
+            Parser::Time { min: 20 },
+            Parser::Integer {
+                min: Some(1),
+                max: None,
+            },
+            Parser::Heightmap,
+            Parser::Uuid,
+        ] {
+            let buf = encoded(&parser);
+            assert_eq!(Parser::decode(&mut buf.as_slice()).unwrap(), parser);
+        }
+    }
+
+    #[test]
+    fn custom_parser_encodes_like_builtin() {
+        let custom = Parser::Custom {
+            parser: ident!("minecraft:score_holder").into(),
+            properties: vec![1],
+        };
+
+        assert_eq!(
+            encoded(&custom),
+            encoded(&Parser::ScoreHolder {
+                allow_multiple: true
+            })
+        );
+
+        let unknown = Parser::Custom {
+            parser: ident!("mymod:thing").into(),
+            properties: vec![],
+        };
+
+        assert!(unknown.encode(&mut vec![]).is_err());
+    }
+}
@@ -15,3 +15,15 @@ pub enum EntityInteraction {
     Attack,
     InteractAt { target: Vec3, hand: Hand },
 }
+
+impl EntityInteraction {
+    /// Returns the hand used for the interaction. Attacks are always made with
+    /// the main hand.
+    pub fn hand(&self) -> Hand {
+        match *self {
+            EntityInteraction::Interact(hand) => hand,
+            EntityInteraction::Attack => Hand::Main,
+            EntityInteraction::InteractAt { hand, .. } => hand,
+        }
+    }
+}
@@ -18,7 +18,7 @@ pub enum SoundId<'a> {
     },
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Encode, Decode)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Encode, Decode)]
 pub enum SoundCategory {
     Master,
     Music,
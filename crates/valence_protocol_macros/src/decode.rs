@@ -3,7 +3,9 @@ use quote::quote;
 use syn::spanned::Spanned;
 use syn::{parse2, parse_quote, Data, DeriveInput, Error, Fields, Result};
 
-use crate::{add_trait_bounds, decode_split_for_impl, pair_variants_with_discriminants};
+use crate::{
+    add_trait_bounds, decode_split_for_impl, pair_variants_with_discriminants, parse_field_attrs,
+};
 
 pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(item)?;
@@ -27,13 +29,16 @@ pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
 
     match input.data {
         Data::Struct(struct_) => {
+            let attrs = parse_field_attrs(&struct_.fields)?;
+
             let decode_fields = match struct_.fields {
                 Fields::Named(fields) => {
-                    let init = fields.named.iter().map(|f| {
+                    let init = fields.named.iter().zip(&attrs).map(|(f, attr)| {
                         let name = f.ident.as_ref().unwrap();
                         let ctx = format!("failed to decode field `{name}` in `{input_name}`");
+                        let decode = attr.decode(&ctx);
                         quote! {
-                            #name: Decode::decode(_r).context(#ctx)?,
+                            #name: #decode,
                         }
                     });
 
@@ -43,12 +48,15 @@ pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
                         }
                     }
                 }
-                Fields::Unnamed(fields) => {
-                    let init = (0..fields.unnamed.len())
-                        .map(|i| {
+                Fields::Unnamed(_) => {
+                    let init = attrs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, attr)| {
                             let ctx = format!("failed to decode field `{i}` in `{input_name}`");
+                            let decode = attr.decode(&ctx);
                             quote! {
-                                Decode::decode(_r).context(#ctx)?,
+                                #decode,
                             }
                         })
                         .collect::<TokenStream>();
@@ -88,20 +96,23 @@ pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
                 .iter()
                 .map(|(disc, variant)| {
                     let name = &variant.ident;
+                    let attrs = parse_field_attrs(&variant.fields)?;
 
-                    match &variant.fields {
+                    Ok(match &variant.fields {
                         Fields::Named(fields) => {
                             let fields = fields
                                 .named
                                 .iter()
-                                .map(|f| {
+                                .zip(&attrs)
+                                .map(|(f, attr)| {
                                     let field = f.ident.as_ref().unwrap();
                                     let ctx = format!(
                                         "failed to decode field `{field}` in variant `{name}` in \
                                          `{input_name}`",
                                     );
+                                    let decode = attr.decode(&ctx);
                                     quote! {
-                                        #field: Decode::decode(_r).context(#ctx)?,
+                                        #field: #decode,
                                     }
                                 })
                                 .collect::<TokenStream>();
@@ -110,15 +121,18 @@ pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
                                 #disc => Ok(Self::#name { #fields }),
                             }
                         }
-                        Fields::Unnamed(fields) => {
-                            let init = (0..fields.unnamed.len())
-                                .map(|i| {
+                        Fields::Unnamed(_) => {
+                            let init = attrs
+                                .iter()
+                                .enumerate()
+                                .map(|(i, attr)| {
                                     let ctx = format!(
                                         "failed to decode field `{i}` in variant `{name}` in \
                                          `{input_name}`",
                                     );
+                                    let decode = attr.decode(&ctx);
                                     quote! {
-                                        Decode::decode(_r).context(#ctx)?,
+                                        #decode,
                                     }
                                 })
                                 .collect::<TokenStream>();
@@ -128,9 +142,9 @@ pub(super) fn derive_decode(item: TokenStream) -> Result<TokenStream> {
                             }
                         }
                         Fields::Unit => quote!(#disc => Ok(Self::#name),),
-                    }
+                    })
                 })
-                .collect::<TokenStream>();
+                .collect::<Result<TokenStream>>()?;
 
             add_trait_bounds(
                 &mut input.generics,
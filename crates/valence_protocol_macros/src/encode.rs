@@ -3,7 +3,7 @@ use quote::quote;
 use syn::spanned::Spanned;
 use syn::{parse2, Data, DeriveInput, Error, Fields, LitInt, Result};
 
-use crate::{add_trait_bounds, pair_variants_with_discriminants};
+use crate::{add_trait_bounds, pair_variants_with_discriminants, parse_field_attrs};
 
 pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(item)?;
@@ -19,25 +19,26 @@ pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
 
     match input.data {
         Data::Struct(struct_) => {
+            let attrs = parse_field_attrs(&struct_.fields)?;
+
             let encode_fields = match &struct_.fields {
                 Fields::Named(fields) => fields
                     .named
                     .iter()
-                    .map(|f| {
+                    .zip(&attrs)
+                    .map(|(f, attr)| {
                         let name = &f.ident.as_ref().unwrap();
                         let ctx = format!("failed to encode field `{name}` in `{input_name}`");
-                        quote! {
-                            self.#name.encode(&mut _w).context(#ctx)?;
-                        }
+                        attr.encode(quote!(self.#name), &ctx)
                     })
                     .collect(),
-                Fields::Unnamed(fields) => (0..fields.unnamed.len())
-                    .map(|i| {
+                Fields::Unnamed(_) => attrs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, attr)| {
                         let lit = LitInt::new(&i.to_string(), Span::call_site());
                         let ctx = format!("failed to encode field `{lit}` in `{input_name}`");
-                        quote! {
-                            self.#lit.encode(&mut _w).context(#ctx)?;
-                        }
+                        attr.encode(quote!(self.#lit), &ctx)
                     })
                     .collect(),
                 Fields::Unit => TokenStream::new(),
@@ -65,13 +66,14 @@ pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
                 .iter()
                 .map(|(disc, variant)| {
                     let variant_name = &variant.ident;
+                    let attrs = parse_field_attrs(&variant.fields)?;
 
                     let disc_ctx = format!(
                         "failed to encode enum discriminant {disc} for variant `{variant_name}` \
                          in `{input_name}`",
                     );
 
-                    match &variant.fields {
+                    Ok(match &variant.fields {
                         Fields::Named(fields) => {
                             let field_names = fields
                                 .named
@@ -81,15 +83,14 @@ pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
 
                             let encode_fields = field_names
                                 .iter()
-                                .map(|name| {
+                                .zip(&attrs)
+                                .map(|(name, attr)| {
                                     let ctx = format!(
                                         "failed to encode field `{name}` in variant \
                                          `{variant_name}` in `{input_name}`",
                                     );
 
-                                    quote! {
-                                        #name.encode(&mut _w).context(#ctx)?;
-                                    }
+                                    attr.encode(quote!(#name), &ctx)
                                 })
                                 .collect::<TokenStream>();
 
@@ -109,15 +110,14 @@ pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
 
                             let encode_fields = field_names
                                 .iter()
-                                .map(|name| {
+                                .zip(&attrs)
+                                .map(|(name, attr)| {
                                     let ctx = format!(
                                         "failed to encode field `{name}` in variant \
                                          `{variant_name}` in `{input_name}`"
                                     );
 
-                                    quote! {
-                                        #name.encode(&mut _w).context(#ctx)?;
-                                    }
+                                    attr.encode(quote!(#name), &ctx)
                                 })
                                 .collect::<TokenStream>();
 
@@ -137,9 +137,9 @@ pub(super) fn derive_encode(item: TokenStream) -> Result<TokenStream> {
                                     .context(#disc_ctx)?
                             ),
                         },
-                    }
+                    })
                 })
-                .collect::<TokenStream>();
+                .collect::<Result<TokenStream>>()?;
 
             Ok(quote! {
                 #[allow(unused_imports, unreachable_code)]
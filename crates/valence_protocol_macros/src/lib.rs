@@ -2,10 +2,11 @@
 
 use proc_macro::TokenStream as StdTokenStream;
 use proc_macro2::TokenStream;
-use quote::ToTokens;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
 use syn::{
-    parse_quote, Attribute, GenericParam, Generics, Lifetime, LifetimeParam, LitInt, Result,
-    Variant,
+    parse_quote, Attribute, Error, Fields, GenericParam, Generics, Lifetime, LifetimeParam, LitInt,
+    Result, Variant,
 };
 
 mod decode;
@@ -75,6 +76,116 @@ fn parse_tag_attr(attrs: &[Attribute]) -> Result<Option<i32>> {
     Ok(None)
 }
 
+/// The `#[packet(...)]` attribute on a field.
+#[derive(Default)]
+struct FieldAttr {
+    /// `#[packet(default)]`: The field is set to its default value when the
+    /// input ends before it.
+    default: bool,
+    /// `#[packet(since = ...)]`: The first protocol version with the field.
+    since: Option<LitInt>,
+    /// `#[packet(before = ...)]`: The protocol version the field was removed
+    /// in.
+    before: Option<LitInt>,
+}
+
+impl FieldAttr {
+    /// Returns an expression evaluating to whether the field is part of the
+    /// current protocol version, or `None` if it always is.
+    fn present(&self) -> Option<TokenStream> {
+        let since = self
+            .since
+            .as_ref()
+            .map(|v| quote!(::valence_protocol::PROTOCOL_VERSION >= #v));
+        let before = self
+            .before
+            .as_ref()
+            .map(|v| quote!(::valence_protocol::PROTOCOL_VERSION < #v));
+
+        match (since, before) {
+            (Some(since), Some(before)) => Some(quote!(#since && #before)),
+            (since, before) => since.or(before),
+        }
+    }
+
+    /// Returns an expression decoding the field from `_r`.
+    fn decode(&self, ctx: &str) -> TokenStream {
+        let decode = quote!(Decode::decode(_r).context(#ctx)?);
+        let default = quote!(::core::default::Default::default());
+
+        let decode = if self.default {
+            quote!(if _r.is_empty() { #default } else { #decode })
+        } else {
+            decode
+        };
+
+        match self.present() {
+            Some(present) => quote!(if #present { #decode } else { #default }),
+            None => decode,
+        }
+    }
+
+    /// Returns a statement encoding `value` to `_w`.
+    fn encode(&self, value: TokenStream, ctx: &str) -> TokenStream {
+        let encode = quote!(#value.encode(&mut _w).context(#ctx)?;);
+
+        match self.present() {
+            Some(present) => quote!(if #present { #encode }),
+            None => encode,
+        }
+    }
+}
+
+fn parse_field_attr(attrs: &[Attribute]) -> Result<FieldAttr> {
+    let mut res = FieldAttr::default();
+
+    for attr in attrs {
+        if attr.path().is_ident("packet") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    res.default = true;
+                    Ok(())
+                } else if meta.path.is_ident("since") {
+                    res.since = Some(meta.value()?.parse::<LitInt>()?);
+                    Ok(())
+                } else if meta.path.is_ident("before") {
+                    res.before = Some(meta.value()?.parse::<LitInt>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized argument"))
+                }
+            })?;
+        }
+    }
+
+    Ok(res)
+}
+
+/// Parses the attributes of every field, checking that fields with
+/// `#[packet(default)]` are only followed by other such fields.
+fn parse_field_attrs(fields: &Fields) -> Result<Vec<FieldAttr>> {
+    let attrs = fields
+        .iter()
+        .map(|f| parse_field_attr(&f.attrs))
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(first) = attrs.iter().position(|a| a.default) {
+        if let Some((field, _)) = fields
+            .iter()
+            .zip(&attrs)
+            .skip(first)
+            .find(|(_, a)| !a.default)
+        {
+            return Err(Error::new(
+                field.span(),
+                "fields after a `#[packet(default)]` field must also be `#[packet(default)]`",
+            ));
+        }
+    }
+
+    Ok(attrs)
+}
+
 /// Adding our lifetime to the generics before calling `.split_for_impl()` would
 /// also add it to the resulting `ty_generics`, which we don't want. So I'm
 /// doing this hack.
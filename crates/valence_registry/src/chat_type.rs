@@ -0,0 +1,216 @@
+//! Contains chat types and the chat type registry. Minecraft's default chat
+//! types are added to the registry by default.
+//!
+//! Chat types decide how chat messages are formatted and narrated by clients.
+//! Custom chat types can be registered by name and referenced by their
+//! [`ChatTypeId`] when sending messages:
+//!
+//! ```
+//! # use valence_registry::chat_type::{ChatType, ChatTypeParameter, ChatTypeRegistry};
+//! # use valence_ident::ident;
+//! fn register(registry: &mut ChatTypeRegistry) {
+//!     registry.insert(
+//!         ident!("my_server:team"),
+//!         ChatType::new(
+//!             "chat.type.team.text",
+//!             [
+//!                 ChatTypeParameter::Target,
+//!                 ChatTypeParameter::Sender,
+//!                 ChatTypeParameter::Content,
+//!             ],
+//!         ),
+//!     );
+//! }
+//! ```
+//!
+//! ### **NOTE:**
+//! - Modifying the chat type registry after the server has started can break
+//!   invariants within clients! Make sure there are no clients spawned before
+//!   mutating.
+
+use std::ops::{Deref, DerefMut};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use valence_ident::{ident, Ident};
+use valence_nbt::serde::CompoundSerializer;
+use valence_nbt::Compound;
+
+use crate::codec::{RegistryCodec, RegistryValue};
+use crate::{Registry, RegistryIdx, RegistrySet};
+
+pub struct ChatTypePlugin;
+
+impl Plugin for ChatTypePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatTypeRegistry>()
+            .add_systems(PreStartup, load_default_chat_types)
+            .add_systems(PostUpdate, update_chat_type_registry.before(RegistrySet));
+    }
+}
+
+fn load_default_chat_types(mut reg: ResMut<ChatTypeRegistry>, codec: Res<RegistryCodec>) {
+    let mut helper = move || -> anyhow::Result<()> {
+        for value in codec.registry(ChatTypeRegistry::KEY) {
+            let chat_type = ChatType::deserialize(value.element.clone())?;
+
+            reg.insert(value.name.clone(), chat_type);
+        }
+
+        // Move "chat" to the front so that `ChatTypeId::default()` is the ID of chat.
+        reg.swap_to_front(ident!("chat"));
+
+        Ok(())
+    };
+
+    if let Err(e) = helper() {
+        error!("failed to load default chat types from registry codec: {e:#}");
+    }
+}
+
+fn update_chat_type_registry(reg: Res<ChatTypeRegistry>, mut codec: ResMut<RegistryCodec>) {
+    if reg.is_changed() {
+        let chat_types = codec.registry_mut(ChatTypeRegistry::KEY);
+
+        chat_types.clear();
+
+        chat_types.extend(reg.iter().map(|(_, name, chat_type)| {
+            RegistryValue {
+                name: name.into(),
+                element: chat_type
+                    .serialize(CompoundSerializer)
+                    .expect("failed to serialize chat type"),
+            }
+        }));
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ChatTypeRegistry {
+    reg: Registry<ChatTypeId, ChatType>,
+}
+
+impl ChatTypeRegistry {
+    pub const KEY: Ident<&'static str> = ident!("chat_type");
+}
+
+impl Deref for ChatTypeRegistry {
+    type Target = Registry<ChatTypeId, ChatType>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.reg
+    }
+}
+
+impl DerefMut for ChatTypeRegistry {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.reg
+    }
+}
+
+/// The index of a chat type in the [`ChatTypeRegistry`], which is what chat
+/// message packets refer to.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct ChatTypeId(u32);
+
+impl ChatTypeId {
+    /// The ID of `minecraft:chat`, used for regular chat messages.
+    pub const DEFAULT: Self = ChatTypeId(0);
+
+    /// The raw ID of the chat type, as sent in packets.
+    pub const fn to_raw(self) -> i32 {
+        self.0 as i32
+    }
+}
+
+impl RegistryIdx for ChatTypeId {
+    const MAX: usize = i32::MAX as usize;
+
+    #[inline]
+    fn to_index(self) -> usize {
+        self.0 as usize
+    }
+
+    #[inline]
+    fn from_index(idx: usize) -> Self {
+        Self(idx as u32)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ChatType {
+    /// How messages of this type are shown in the chat.
+    pub chat: ChatTypeDecoration,
+    /// How messages of this type are read by the narrator.
+    pub narration: ChatTypeDecoration,
+}
+
+impl ChatType {
+    /// Creates a chat type showing and narrating messages with the same
+    /// translation.
+    pub fn new<K, P>(translation_key: K, parameters: P) -> Self
+    where
+        K: Into<String>,
+        P: IntoIterator<Item = ChatTypeParameter>,
+    {
+        let decoration = ChatTypeDecoration::new(translation_key, parameters);
+
+        Self {
+            chat: decoration.clone(),
+            narration: decoration,
+        }
+    }
+
+    /// Sets the narration of the chat type.
+    #[must_use]
+    pub fn with_narration(mut self, narration: ChatTypeDecoration) -> Self {
+        self.narration = narration;
+        self
+    }
+}
+
+/// The translation used to format a message, and the parameters passed to it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ChatTypeDecoration {
+    pub translation_key: String,
+    pub parameters: Vec<ChatTypeParameter>,
+    /// The style of the formatted message, such as `{color: "gray", italic:
+    /// 1b}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<Compound>,
+}
+
+impl ChatTypeDecoration {
+    pub fn new<K, P>(translation_key: K, parameters: P) -> Self
+    where
+        K: Into<String>,
+        P: IntoIterator<Item = ChatTypeParameter>,
+    {
+        Self {
+            translation_key: translation_key.into(),
+            parameters: parameters.into_iter().collect(),
+            style: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_style(mut self, style: Compound) -> Self {
+        self.style = Some(style);
+        self
+    }
+}
+
+/// A value passed to the translation of a [`ChatTypeDecoration`].
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatTypeParameter {
+    /// The name of the sender of the message.
+    Sender,
+    /// The name of the receiver of the message, such as the team of a team
+    /// message.
+    Target,
+    /// The message itself.
+    Content,
+}
@@ -1,6 +1,7 @@
 #![doc = include_str!("../README.md")]
 
 pub mod biome;
+pub mod chat_type;
 pub mod codec;
 pub mod dimension_type;
 pub mod tags;
@@ -13,6 +14,7 @@ use std::ops::{Index, IndexMut};
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 pub use biome::BiomeRegistry;
+pub use chat_type::ChatTypeRegistry;
 pub use codec::RegistryCodec;
 pub use dimension_type::DimensionTypeRegistry;
 use indexmap::map::Entry;
@@ -1,11 +1,13 @@
 #![doc = include_str!("../README.md")]
 
 mod components;
+mod team;
 use std::collections::BTreeSet;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 pub use components::*;
+pub use team::*;
 use tracing::{debug, warn};
 use valence_server::client::{Client, OldVisibleEntityLayers, VisibleEntityLayers};
 use valence_server::entity::EntityLayerId;
@@ -49,6 +51,8 @@ impl Plugin for ScoreboardPlugin {
                 .after(handle_new_clients)
                 .in_set(ScoreboardSet),
         );
+
+        team::build(app);
     }
 }
 
@@ -0,0 +1,330 @@
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use derive_more::Deref;
+use tracing::warn;
+use valence_server::client::{Client, OldVisibleEntityLayers, VisibleEntityLayers};
+use valence_server::entity::EntityLayerId;
+use valence_server::protocol::packets::play::team_s2c::{
+    CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags,
+};
+use valence_server::protocol::packets::play::TeamS2c;
+use valence_server::protocol::WritePacket;
+use valence_server::text::IntoText;
+use valence_server::{Despawned, EntityLayer, Text, UniqueId};
+
+use crate::ScoreboardSet;
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        (
+            create_or_update_teams,
+            update_team_members.after(create_or_update_teams),
+            remove_despawned_teams,
+            handle_new_clients,
+        )
+            .in_set(ScoreboardSet),
+    );
+}
+
+/// A string that identifies a team. It's generally not safe to modify this
+/// after it's been created.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component, Deref)]
+pub struct Team(pub(crate) String);
+
+impl Team {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self(name.into())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How a team and its members are displayed. The [`color`](Self::color) is
+/// applied to the name tags of all members.
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct TeamInfo {
+    pub display_name: Text,
+    pub color: TeamColor,
+    /// Text shown before the name of every member.
+    pub prefix: Text,
+    /// Text shown after the name of every member.
+    pub suffix: Text,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    pub friendly_fire: bool,
+    pub see_invisible_teammates: bool,
+}
+
+impl TeamInfo {
+    fn flags(&self) -> TeamFlags {
+        TeamFlags::new()
+            .with_friendly_fire(self.friendly_fire)
+            .with_see_invisible_teammates(self.see_invisible_teammates)
+    }
+}
+
+impl Default for TeamInfo {
+    fn default() -> Self {
+        Self {
+            display_name: "".into_text(),
+            color: TeamColor::Reset,
+            prefix: "".into_text(),
+            suffix: "".into_text(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            friendly_fire: true,
+            see_invisible_teammates: true,
+        }
+    }
+}
+
+/// The members of a team. Players are referenced by their username, and all
+/// other entities by their UUID.
+#[derive(Debug, Clone, Default, PartialEq, Component)]
+pub struct TeamMembers(pub(crate) BTreeSet<String>);
+
+impl TeamMembers {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn contains(&self, member: &str) -> bool {
+        self.0.contains(member)
+    }
+
+    /// Adds a member. Returns `false` if it was already on the team.
+    pub fn insert<M: Into<String>>(&mut self, member: M) -> bool {
+        self.0.insert(member.into())
+    }
+
+    /// Adds a non-player entity. Returns `false` if it was already on the team.
+    pub fn insert_entity(&mut self, id: UniqueId) -> bool {
+        self.insert(id.0.to_string())
+    }
+
+    /// Removes a member. Returns `false` if it wasn't on the team.
+    pub fn remove(&mut self, member: &str) -> bool {
+        self.0.remove(member)
+    }
+
+    /// Removes a non-player entity. Returns `false` if it wasn't on the team.
+    pub fn remove_entity(&mut self, id: UniqueId) -> bool {
+        self.remove(&id.0.to_string())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The members that were last sent to clients.
+#[derive(Debug, Clone, Default, PartialEq, Component)]
+pub struct OldTeamMembers(pub(crate) BTreeSet<String>);
+
+#[derive(Bundle)]
+pub struct TeamBundle {
+    pub name: Team,
+    pub info: TeamInfo,
+    pub members: TeamMembers,
+    pub old_members: OldTeamMembers,
+    pub layer: EntityLayerId,
+}
+
+impl Default for TeamBundle {
+    fn default() -> Self {
+        Self {
+            name: Team::new(""),
+            info: Default::default(),
+            members: Default::default(),
+            old_members: Default::default(),
+            layer: Default::default(),
+        }
+    }
+}
+
+fn create_packet<'a>(team: &'a Team, info: &'a TeamInfo, members: &'a TeamMembers) -> TeamS2c<'a> {
+    TeamS2c {
+        team_name: &team.0,
+        mode: Mode::CreateTeam {
+            team_display_name: (&info.display_name).into_cow_text(),
+            friendly_flags: info.flags(),
+            name_tag_visibility: info.name_tag_visibility,
+            collision_rule: info.collision_rule,
+            team_color: info.color,
+            team_prefix: (&info.prefix).into_cow_text(),
+            team_suffix: (&info.suffix).into_cow_text(),
+            entities: members.iter().collect(),
+        },
+    }
+}
+
+fn create_or_update_teams(
+    mut teams: Query<
+        (
+            &Team,
+            Ref<TeamInfo>,
+            &TeamMembers,
+            &mut OldTeamMembers,
+            &EntityLayerId,
+        ),
+        (Changed<TeamInfo>, Without<Despawned>),
+    >,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (team, info, members, mut old_members, entity_layer) in &mut teams {
+        if team.name().is_empty() {
+            warn!("Team name is empty");
+        }
+
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't update team",
+                entity_layer
+            );
+            continue;
+        };
+
+        if info.is_added() {
+            layer.write_packet(&create_packet(team, &info, members));
+            // The initial members are sent with the team.
+            old_members.0.clone_from(&members.0);
+        } else {
+            layer.write_packet(&TeamS2c {
+                team_name: &team.0,
+                mode: Mode::UpdateTeamInfo {
+                    team_display_name: (&info.display_name).into_cow_text(),
+                    friendly_flags: info.flags(),
+                    name_tag_visibility: info.name_tag_visibility,
+                    collision_rule: info.collision_rule,
+                    team_color: info.color,
+                    team_prefix: (&info.prefix).into_cow_text(),
+                    team_suffix: (&info.suffix).into_cow_text(),
+                },
+            });
+        }
+    }
+}
+
+/// Must occur after `create_or_update_teams`.
+fn update_team_members(
+    mut teams: Query<
+        (&Team, &TeamMembers, &mut OldTeamMembers, &EntityLayerId),
+        (Changed<TeamMembers>, Without<Despawned>),
+    >,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (team, members, mut old_members, entity_layer) in &mut teams {
+        let removed: Vec<_> = old_members
+            .0
+            .difference(&members.0)
+            .map(String::as_str)
+            .collect();
+        let added: Vec<_> = members
+            .0
+            .difference(&old_members.0)
+            .map(String::as_str)
+            .collect();
+
+        if removed.is_empty() && added.is_empty() {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't update team members",
+                entity_layer
+            );
+            continue;
+        };
+
+        if !removed.is_empty() {
+            layer.write_packet(&TeamS2c {
+                team_name: &team.0,
+                mode: Mode::RemoveEntities { entities: removed },
+            });
+        }
+
+        if !added.is_empty() {
+            layer.write_packet(&TeamS2c {
+                team_name: &team.0,
+                mode: Mode::AddEntities { entities: added },
+            });
+        }
+
+        old_members.0.clone_from(&members.0);
+    }
+}
+
+fn remove_despawned_teams(
+    mut commands: Commands,
+    teams: Query<(Entity, &Team, &EntityLayerId), With<Despawned>>,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (entity, team, entity_layer) in &teams {
+        commands.entity(entity).despawn();
+        let Ok(mut layer) = layers.get_mut(entity_layer.0) else {
+            warn!(
+                "No layer found for entity layer ID {:?}, can't remove team",
+                entity_layer
+            );
+            continue;
+        };
+
+        layer.write_packet(&TeamS2c {
+            team_name: &team.0,
+            mode: Mode::RemoveTeam,
+        });
+    }
+}
+
+fn handle_new_clients(
+    mut clients: Query<
+        (&mut Client, &VisibleEntityLayers, &OldVisibleEntityLayers),
+        Or<(Added<Client>, Changed<VisibleEntityLayers>)>,
+    >,
+    teams: Query<(&Team, &TeamInfo, &TeamMembers, &EntityLayerId), Without<Despawned>>,
+) {
+    for (mut client, visible_layers, old_visible_layers) in &mut clients {
+        // Teams in layers the client can no longer see are removed, and teams in
+        // newly visible layers are created. New clients get every visible team.
+        let removed_layers: BTreeSet<_> = old_visible_layers
+            .get()
+            .difference(&visible_layers.0)
+            .copied()
+            .collect();
+
+        let added_layers = if client.is_added() {
+            visible_layers.0.clone()
+        } else {
+            visible_layers
+                .0
+                .difference(old_visible_layers.get())
+                .copied()
+                .collect()
+        };
+
+        for (team, info, members, layer) in &teams {
+            if removed_layers.contains(&layer.0) {
+                client.write_packet(&TeamS2c {
+                    team_name: &team.0,
+                    mode: Mode::RemoveTeam,
+                });
+            } else if added_layers.contains(&layer.0) {
+                client.write_packet(&create_packet(team, info, members));
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! The types exchanged between the server and scripts.
+//!
+//! Both events and commands are encoded as JSON objects with a `type` field
+//! naming the variant in `snake_case`, e.g.
+//! `{"type":"send_message","client":4294967296,"message":"Hi!"}`. Clients
+//! are identified by the bits of their [`Entity`](bevy_ecs::entity::Entity).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The version of the host API. Scripts built for a different version are
+/// refused.
+pub const ABI_VERSION: i32 = 1;
+
+/// Something that happened on the server, sent to scripts.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ScriptEvent {
+    /// Sent once after the script is loaded or reloaded.
+    Load,
+    /// Sent at the start of every tick.
+    Tick { tick: i64 },
+    /// A client joined the server.
+    ClientJoin { client: u64, username: String },
+    /// A client left the server.
+    ClientLeave { client: u64 },
+    /// A client sent a chat message.
+    ChatMessage { client: u64, message: String },
+    /// An event sent with [`SendScriptEvent`](crate::SendScriptEvent).
+    Custom { name: String, data: Value },
+}
+
+/// Something a script asks the server to do.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ScriptCommand {
+    /// Sends a system message to a client.
+    SendMessage { client: u64, message: String },
+    /// Displays a message in the action bar of a client.
+    SendActionBar { client: u64, message: String },
+    /// Sends a system message to every client.
+    Broadcast { message: String },
+    /// Sets a block in the [`ChunkLayer`](valence_server::ChunkLayer) the
+    /// script is attached to. `state` is the raw ID of the block state.
+    SetBlock { pos: [i32; 3], state: u16 },
+    /// A command left for the server to handle by reading
+    /// [`ScriptCommandEvent`](crate::ScriptCommandEvent)s.
+    Custom { name: String, data: Value },
+}
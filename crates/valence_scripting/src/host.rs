@@ -0,0 +1,114 @@
+use anyhow::{bail, ensure, Context};
+use wasmtime::{Caller, Engine, Extern, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::api::{ScriptCommand, ScriptEvent, ABI_VERSION};
+
+/// An instantiated script module.
+pub(crate) struct ScriptInstance {
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_event: TypedFunc<(i32, i32), ()>,
+}
+
+#[derive(Default)]
+struct HostState {
+    commands: Vec<ScriptCommand>,
+}
+
+impl ScriptInstance {
+    pub(crate) fn new(engine: &Engine, module: &Module, fuel: u64) -> anyhow::Result<Self> {
+        let mut linker = Linker::new(engine);
+
+        linker.func_wrap(
+            "valence",
+            "command",
+            |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+                let bytes = read_guest_bytes(&mut caller, ptr, len)?;
+                let command = serde_json::from_slice(&bytes).context("invalid command")?;
+
+                caller.data_mut().commands.push(command);
+
+                Ok(())
+            },
+        )?;
+
+        linker.func_wrap(
+            "valence",
+            "log",
+            |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+                let bytes = read_guest_bytes(&mut caller, ptr, len)?;
+
+                tracing::info!("{}", String::from_utf8_lossy(&bytes));
+
+                Ok(())
+            },
+        )?;
+
+        let mut store = Store::new(engine, HostState::default());
+        store.set_fuel(fuel)?;
+
+        let instance = linker.instantiate(&mut store, module)?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "valence_abi_version")?
+            .call(&mut store, ())?;
+
+        ensure!(
+            version == ABI_VERSION,
+            "script was built for version {version} of the host API, but the server uses version \
+             {ABI_VERSION}"
+        );
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("missing `memory` export")?;
+
+        let alloc = instance.get_typed_func(&mut store, "valence_alloc")?;
+        let on_event = instance.get_typed_func(&mut store, "valence_on_event")?;
+
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            on_event,
+        })
+    }
+
+    /// Passes `event` to the script and returns the commands it queued while
+    /// handling it. The script can't use more than `fuel` units of fuel.
+    pub(crate) fn handle(
+        &mut self,
+        event: &ScriptEvent,
+        fuel: u64,
+    ) -> anyhow::Result<Vec<ScriptCommand>> {
+        self.store.data_mut().commands.clear();
+        self.store.set_fuel(fuel)?;
+
+        let bytes = serde_json::to_vec(event)?;
+        let len = i32::try_from(bytes.len())?;
+
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &bytes)?;
+
+        self.on_event.call(&mut self.store, (ptr, len))?;
+
+        Ok(std::mem::take(&mut self.store.data_mut().commands))
+    }
+}
+
+fn read_guest_bytes(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> anyhow::Result<Vec<u8>> {
+    let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+        bail!("missing `memory` export");
+    };
+
+    let mut buf = vec![0; len as u32 as usize];
+    memory.read(&caller, ptr as u32 as usize, &mut buf)?;
+
+    Ok(buf)
+}
@@ -0,0 +1,337 @@
+#![doc = include_str!("../README.md")]
+
+pub mod api;
+mod host;
+#[cfg(test)]
+mod tests;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use api::{ScriptCommand, ScriptEvent};
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use flume::{Receiver, TryRecvError};
+use host::ScriptInstance;
+use tracing::{info, warn};
+use valence_server::client::{Client, Username};
+use valence_server::message::{ChatMessageEvent, SendMessage};
+use valence_server::{BlockState, ChunkLayer, Server};
+use wasmtime::{Config, Engine, Module};
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+
+        let engine = Engine::new(&config).expect("failed to create the WebAssembly engine");
+
+        app.insert_resource(ScriptEngine(engine))
+            .init_resource::<ScriptSettings>()
+            .add_event::<SendScriptEvent>()
+            .add_event::<ScriptCommandEvent>()
+            .add_systems(
+                Update,
+                (load_scripts, dispatch_script_events, apply_script_commands).chain(),
+            );
+    }
+}
+
+/// Settings for [`ScriptingPlugin`].
+#[derive(Resource, Clone, Debug)]
+pub struct ScriptSettings {
+    /// The number of ticks between checks for changes to the files of
+    /// scripts. Scripts are never reloaded if this is 0.
+    pub reload_interval: u32,
+    /// The amount of fuel a script can use to handle a single event. A script
+    /// running out of fuel is unloaded, which prevents infinite loops from
+    /// freezing the server.
+    pub fuel_per_event: u64,
+}
+
+impl Default for ScriptSettings {
+    fn default() -> Self {
+        Self {
+            reload_interval: 20,
+            fuel_per_event: 10_000_000,
+        }
+    }
+}
+
+/// A WebAssembly module handling events on the server. The module is loaded
+/// from `path`, and reloaded when the file changes. Modules are compiled on
+/// another thread, and the previous module keeps handling events until the
+/// new one is ready.
+///
+/// When attached to an entity with a [`ChunkLayer`], the script can set the
+/// blocks of that layer.
+#[derive(Component)]
+pub struct Script {
+    path: PathBuf,
+    /// The modification time of the file when it was last loaded.
+    modified: Option<SystemTime>,
+    instance: Option<ScriptInstance>,
+    /// The module being compiled, if any.
+    pending: Option<Receiver<anyhow::Result<ScriptInstance>>>,
+}
+
+impl Script {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+            instance: None,
+            pending: None,
+        }
+    }
+
+    /// Returns the path of the file the module is loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether the module is currently loaded. A module which failed to
+    /// load or trapped while handling an event stays unloaded until its file
+    /// changes.
+    pub fn is_loaded(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    /// Returns whether the module is being compiled after its file changed.
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn handle(
+        &mut self,
+        entity: Entity,
+        event: &ScriptEvent,
+        fuel: u64,
+        commands: &mut EventWriter<ScriptCommandEvent>,
+    ) {
+        let Some(instance) = &mut self.instance else {
+            return;
+        };
+
+        match instance.handle(event, fuel) {
+            Ok(cmds) => {
+                commands.send_batch(cmds.into_iter().map(|command| ScriptCommandEvent {
+                    script: entity,
+                    command,
+                }));
+            }
+            Err(e) => {
+                warn!(
+                    "script `{}` failed to handle an event and was unloaded: {e:#}",
+                    self.path.display()
+                );
+                self.instance = None;
+            }
+        }
+    }
+}
+
+/// Sends a [`ScriptEvent`] to a script, or to every script if `script` is
+/// `None`.
+#[derive(Event, Clone, Debug)]
+pub struct SendScriptEvent {
+    pub script: Option<Entity>,
+    pub event: ScriptEvent,
+}
+
+/// A command queued by a script. [`ScriptingPlugin`] handles every command
+/// except [`ScriptCommand::Custom`].
+#[derive(Event, Clone, Debug)]
+pub struct ScriptCommandEvent {
+    /// The entity with the [`Script`] component.
+    pub script: Entity,
+    pub command: ScriptCommand,
+}
+
+#[derive(Resource)]
+struct ScriptEngine(Engine);
+
+fn load_module(engine: &Engine, path: &Path, fuel: u64) -> anyhow::Result<ScriptInstance> {
+    let module = Module::from_file(engine, path).context("failed to compile module")?;
+
+    ScriptInstance::new(engine, &module, fuel)
+}
+
+fn load_scripts(
+    mut scripts: Query<(Entity, &mut Script)>,
+    engine: Res<ScriptEngine>,
+    settings: Res<ScriptSettings>,
+    server: Res<Server>,
+    mut commands: EventWriter<ScriptCommandEvent>,
+) {
+    let check_files = settings.reload_interval != 0
+        && server.current_tick() % i64::from(settings.reload_interval) == 0;
+
+    for (entity, mut script) in &mut scripts {
+        let script = &mut *script;
+
+        if let Some(pending) = &script.pending {
+            let result = match pending.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => Err(anyhow::anyhow!("compilation panicked")),
+            };
+
+            script.pending = None;
+
+            match result {
+                Ok(instance) => {
+                    info!("loaded script `{}`", script.path.display());
+                    script.instance = Some(instance);
+                    script.handle(
+                        entity,
+                        &ScriptEvent::Load,
+                        settings.fuel_per_event,
+                        &mut commands,
+                    );
+                }
+                Err(e) => {
+                    warn!("failed to load script `{}`: {e:#}", script.path.display());
+                    script.instance = None;
+                }
+            }
+        }
+
+        if script.modified.is_some() && !check_files {
+            continue;
+        }
+
+        let modified = match fs::metadata(&script.path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                if script.modified.is_none() {
+                    warn!("failed to read script `{}`: {e}", script.path.display());
+                    // Don't warn again until the file shows up.
+                    script.modified = Some(SystemTime::UNIX_EPOCH);
+                }
+                continue;
+            }
+        };
+
+        if script.modified == Some(modified) {
+            continue;
+        }
+
+        script.modified = Some(modified);
+
+        // Compiling can take a while, so it's done on another thread. Replacing
+        // the receiver of a module which is still compiling discards it.
+        let (sender, receiver) = flume::bounded(1);
+        let engine = engine.0.clone();
+        let path = script.path.clone();
+        let fuel = settings.fuel_per_event;
+
+        thread::spawn(move || {
+            let _ = sender.send(load_module(&engine, &path, fuel));
+        });
+
+        script.pending = Some(receiver);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dispatch_script_events(
+    mut scripts: Query<(Entity, &mut Script)>,
+    settings: Res<ScriptSettings>,
+    server: Res<Server>,
+    joined_clients: Query<(Entity, &Username), Added<Client>>,
+    mut left_clients: RemovedComponents<Client>,
+    mut chat_messages: EventReader<ChatMessageEvent>,
+    mut sent_events: EventReader<SendScriptEvent>,
+    mut commands: EventWriter<ScriptCommandEvent>,
+) {
+    let mut events = vec![ScriptEvent::Tick {
+        tick: server.current_tick(),
+    }];
+
+    events.extend(
+        joined_clients
+            .iter()
+            .map(|(client, username)| ScriptEvent::ClientJoin {
+                client: client.to_bits(),
+                username: username.0.clone(),
+            }),
+    );
+
+    events.extend(left_clients.read().map(|client| ScriptEvent::ClientLeave {
+        client: client.to_bits(),
+    }));
+
+    events.extend(chat_messages.read().map(|msg| ScriptEvent::ChatMessage {
+        client: msg.client.to_bits(),
+        message: msg.message.to_string(),
+    }));
+
+    let sent_events: Vec<_> = sent_events.read().collect();
+
+    for (entity, mut script) in &mut scripts {
+        if !script.is_loaded() {
+            continue;
+        }
+
+        let events = events.iter().chain(
+            sent_events
+                .iter()
+                .filter(|sent| sent.script.map_or(true, |s| s == entity))
+                .map(|sent| &sent.event),
+        );
+
+        for event in events {
+            script.handle(entity, event, settings.fuel_per_event, &mut commands);
+        }
+    }
+}
+
+fn apply_script_commands(
+    mut events: EventReader<ScriptCommandEvent>,
+    mut clients: Query<&mut Client>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    for event in events.read() {
+        match &event.command {
+            ScriptCommand::SendMessage { client, message } => {
+                if let Some(mut client) = client_from_bits(&mut clients, *client) {
+                    client.send_chat_message(message.clone());
+                }
+            }
+            ScriptCommand::SendActionBar { client, message } => {
+                if let Some(mut client) = client_from_bits(&mut clients, *client) {
+                    client.send_action_bar_message(message.clone());
+                }
+            }
+            ScriptCommand::Broadcast { message } => {
+                for mut client in &mut clients {
+                    client.send_chat_message(message.clone());
+                }
+            }
+            ScriptCommand::SetBlock { pos, state } => {
+                let Ok(mut layer) = layers.get_mut(event.script) else {
+                    warn!("script tried to set a block, but it isn't attached to a chunk layer");
+                    continue;
+                };
+
+                let Some(state) = BlockState::from_raw(*state) else {
+                    warn!("script tried to set invalid block state {state}");
+                    continue;
+                };
+
+                layer.set_block(*pos, state);
+            }
+            ScriptCommand::Custom { .. } => {}
+        }
+    }
+}
+
+fn client_from_bits<'a>(clients: &'a mut Query<&mut Client>, bits: u64) -> Option<Mut<'a, Client>> {
+    clients.get_mut(Entity::try_from_bits(bits).ok()?).ok()
+}
@@ -0,0 +1,178 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+use tempfile::TempDir;
+use valence::layer::chunk::UnloadedChunk;
+use valence::protocol::packets::play::GameMessageS2c;
+use valence::testing::ScenarioSingleClient;
+use valence::{BlockPos, BlockState, ChunkLayer};
+
+use crate::api::{ScriptCommand, ABI_VERSION};
+use crate::{Script, ScriptCommandEvent, ScriptSettings, ScriptingPlugin};
+
+/// Builds a module which queues `command` when it handles its first event,
+/// and runs the `later` instructions for every event after that.
+fn module(abi_version: i32, command: &ScriptCommand, later: &str) -> Vec<u8> {
+    let json = serde_json::to_string(command).unwrap();
+    let data = json.replace('\\', "\\\\").replace('"', "\\\"");
+    let len = json.len();
+
+    wat::parse_str(format!(
+        r#"
+        (module
+            (import "valence" "command" (func $command (param i32 i32)))
+            (memory (export "memory") 1)
+            (global $handled (mut i32) (i32.const 0))
+            (data (i32.const 0) "{data}")
+            (func (export "valence_abi_version") (result i32)
+                (i32.const {abi_version}))
+            (func (export "valence_alloc") (param i32) (result i32)
+                (i32.const 1024))
+            (func (export "valence_on_event") (param i32 i32)
+                (if (global.get $handled)
+                    (then {later} (return)))
+                (global.set $handled (i32.const 1))
+                (call $command (i32.const 0) (i32.const {len}))))
+        "#
+    ))
+    .unwrap()
+}
+
+/// Writes `module` to a file and attaches a [`Script`] loading it to
+/// `entity`, then waits until the module is compiled.
+fn load(app: &mut App, entity: Entity, module: &[u8]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("script.wasm");
+    std::fs::write(&path, module).unwrap();
+
+    app.world_mut().entity_mut(entity).insert(Script::new(path));
+    app.update();
+
+    let start = Instant::now();
+
+    while app.world().get::<Script>(entity).unwrap().is_loading() {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "script took too long to compile"
+        );
+        thread::sleep(Duration::from_millis(1));
+        app.update();
+    }
+
+    dir
+}
+
+fn commands(app: &App) -> Vec<ScriptCommand> {
+    let events = app.world().resource::<Events<ScriptCommandEvent>>();
+
+    events
+        .get_reader()
+        .read(events)
+        .map(|event| event.command.clone())
+        .collect()
+}
+
+#[test]
+fn script_sets_blocks() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+    app.add_plugins(ScriptingPlugin);
+
+    app.world_mut()
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .insert_chunk([0, 0], UnloadedChunk::new());
+
+    let command = ScriptCommand::SetBlock {
+        pos: [1, 0, 3],
+        state: BlockState::STONE.to_raw(),
+    };
+
+    let _dir = load(&mut app, layer, &module(ABI_VERSION, &command, ""));
+
+    assert!(app.world().get::<Script>(layer).unwrap().is_loaded());
+    assert_eq!(commands(&app), [command]);
+
+    let state = app
+        .world()
+        .get::<ChunkLayer>(layer)
+        .unwrap()
+        .block(BlockPos::new(1, 0, 3))
+        .unwrap()
+        .state;
+    assert_eq!(state, BlockState::STONE);
+}
+
+#[test]
+fn script_sends_messages() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+    app.add_plugins(ScriptingPlugin);
+
+    app.update();
+    helper.clear_received();
+
+    let command = ScriptCommand::SendMessage {
+        client: client.to_bits(),
+        message: "Hello from a script!".into(),
+    };
+
+    let _dir = load(&mut app, layer, &module(ABI_VERSION, &command, ""));
+
+    assert_eq!(commands(&app), [command]);
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<GameMessageS2c>(1);
+    assert_eq!(
+        recvd.first::<GameMessageS2c>().chat.to_legacy_lossy(),
+        "Hello from a script!"
+    );
+}
+
+#[test]
+fn script_with_other_abi_version_is_refused() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+    app.add_plugins(ScriptingPlugin);
+
+    let command = ScriptCommand::Broadcast {
+        message: "Hi!".into(),
+    };
+
+    let _dir = load(&mut app, layer, &module(ABI_VERSION + 1, &command, ""));
+
+    assert!(!app.world().get::<Script>(layer).unwrap().is_loaded());
+    assert!(commands(&app).is_empty());
+}
+
+#[test]
+fn script_out_of_fuel_is_unloaded() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+    app.add_plugins(ScriptingPlugin)
+        .insert_resource(ScriptSettings {
+            fuel_per_event: 10_000,
+            ..Default::default()
+        });
+
+    let command = ScriptCommand::Broadcast {
+        message: "Loaded".into(),
+    };
+
+    // Handles the load event, then loops forever on the first tick.
+    let _dir = load(
+        &mut app,
+        layer,
+        &module(ABI_VERSION, &command, "(loop $spin (br $spin))"),
+    );
+
+    assert_eq!(commands(&app), [command]);
+    assert!(!app.world().get::<Script>(layer).unwrap().is_loaded());
+
+    // The script stays unloaded until its file changes.
+    app.update();
+    assert!(!app.world().get::<Script>(layer).unwrap().is_loaded());
+}
@@ -3,7 +3,7 @@ use bevy_ecs::prelude::*;
 use derive_more::{Deref, DerefMut};
 pub use valence_protocol::packets::play::player_abilities_s2c::PlayerAbilitiesFlags;
 use valence_protocol::packets::play::{PlayerAbilitiesS2c, UpdatePlayerAbilitiesC2s};
-use valence_protocol::{GameMode, WritePacket};
+use valence_protocol::WritePacket;
 
 use crate::client::{update_game_mode, Client, UpdateClientsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
@@ -53,11 +53,11 @@ pub struct PlayerStopFlyingEvent {
 ///    [`FlyingSpeed`] and [`FovModifier`] changes => Send
 ///    [`PlayerAbilitiesS2c`] to update the client's abilities
 ///
-/// 3. `update_player_abilities`: Watch [`GameMode`] changes => Update
-///    [`PlayerAbilitiesFlags`] according to the [`GameMode`]
-///
-/// 4. `update_server_player_abilities`: Watch [`UpdatePlayerAbilitiesC2s`]
+/// 3. `update_server_player_abilities`: Watch [`UpdatePlayerAbilitiesC2s`]
 ///    packets => Update [`PlayerAbilitiesFlags`] according to the packet
+///
+/// [`PlayerAbilitiesFlags`] are updated according to the game mode by
+/// [`GameModePlugin`](crate::game_mode::GameModePlugin).
 pub struct AbilitiesPlugin;
 
 impl Plugin for AbilitiesPlugin {
@@ -66,10 +66,7 @@ impl Plugin for AbilitiesPlugin {
             .add_event::<PlayerStopFlyingEvent>()
             .add_systems(
                 PostUpdate,
-                (
-                    update_client_player_abilities,
-                    update_player_abilities.before(update_client_player_abilities),
-                )
+                update_client_player_abilities
                     .in_set(UpdateClientsSet)
                     .after(update_game_mode),
             )
@@ -101,46 +98,6 @@ fn update_client_player_abilities(
     }
 }
 
-/// /!\ This system does not trigger change detection on
-/// [`PlayerAbilitiesFlags`]
-fn update_player_abilities(
-    mut player_start_flying_event_writer: EventWriter<PlayerStartFlyingEvent>,
-    mut player_stop_flying_event_writer: EventWriter<PlayerStopFlyingEvent>,
-    mut client_query: Query<(Entity, &mut PlayerAbilitiesFlags, &GameMode), Changed<GameMode>>,
-) {
-    for (entity, mut mut_flags, gamemode) in &mut client_query {
-        let flags = mut_flags.bypass_change_detection();
-        match gamemode {
-            GameMode::Creative => {
-                flags.set_invulnerable(true);
-                flags.set_allow_flying(true);
-                flags.set_instant_break(true);
-            }
-            GameMode::Spectator => {
-                flags.set_invulnerable(true);
-                flags.set_allow_flying(true);
-                flags.set_instant_break(false);
-                flags.set_flying(true);
-                player_start_flying_event_writer.send(PlayerStartFlyingEvent { client: entity });
-            }
-            GameMode::Survival => {
-                flags.set_invulnerable(false);
-                flags.set_allow_flying(false);
-                flags.set_instant_break(false);
-                flags.set_flying(false);
-                player_stop_flying_event_writer.send(PlayerStopFlyingEvent { client: entity });
-            }
-            GameMode::Adventure => {
-                flags.set_invulnerable(false);
-                flags.set_allow_flying(false);
-                flags.set_instant_break(false);
-                flags.set_flying(false);
-                player_stop_flying_event_writer.send(PlayerStopFlyingEvent { client: entity });
-            }
-        }
-    }
-}
-
 /// /!\ This system does not trigger change detection on
 /// [`PlayerAbilitiesFlags`]
 fn update_server_player_abilities(
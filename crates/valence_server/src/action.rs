@@ -1,18 +1,23 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use derive_more::Deref;
+use valence_entity::Position;
 use valence_protocol::packets::play::player_action_c2s::PlayerAction;
 use valence_protocol::packets::play::{PlayerActionC2s, PlayerActionResponseS2c};
-use valence_protocol::{BlockPos, Direction, VarInt, WritePacket};
+use valence_protocol::{BlockPos, Direction, GameMode, VarInt, WritePacket};
 
-use crate::client::{Client, UpdateClientsSet};
+use crate::block_overlay::BlockOverlay;
+use crate::client::{Client, UpdateClientsSet, VisibleChunkLayer};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::interaction_rules::{resend_blocks, InteractionRules};
+use crate::layer::ChunkLayer;
 
 pub struct ActionPlugin;
 
 impl Plugin for ActionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<DiggingEvent>()
+        app.init_resource::<InteractionRules>()
+            .add_event::<DiggingEvent>()
             .add_systems(EventLoopPreUpdate, handle_player_action)
             .add_systems(
                 PostUpdate,
@@ -36,33 +41,104 @@ pub enum DiggingState {
     Stop,
 }
 
-#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug, Deref)]
-pub struct ActionSequence(i32);
+/// The greatest sequence number of the actions a client sent this tick.
+///
+/// Digging, interacting with blocks and using items make the client predict
+/// the outcome and send a sequence number. Once the action is acknowledged, the
+/// client stops predicting and applies the block updates it received from the
+/// server in the meantime. The sequence is acknowledged automatically at the
+/// end of the tick, after every system had a chance to handle the action.
+///
+/// Actions are accepted unless they're [rejected](Self::reject), in which case
+/// the blocks the client predicted are sent to it again before the
+/// acknowledgement, undoing the prediction. If
+/// [`InteractionRules::reconcile_blocks`] is enabled, the blocks the client
+/// changed are resent even when the action isn't rejected, so they always
+/// match the server once it's acknowledged.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug, Deref)]
+pub struct ActionSequence {
+    #[deref]
+    sequence: i32,
+    /// Blocks to resend before the acknowledgement.
+    rejected: Vec<BlockPos>,
+    /// Blocks the client predicted changes to.
+    predicted: Vec<BlockPos>,
+}
 
 impl ActionSequence {
     pub fn update(&mut self, val: i32) {
-        self.0 = self.0.max(val);
+        self.sequence = self.sequence.max(val);
     }
 
     pub fn get(&self) -> i32 {
-        self.0
+        self.sequence
+    }
+
+    /// Rejects the action affecting the block at `position`, so the client is
+    /// told the block's real state instead of keeping what it predicted. When
+    /// a block was placed, both the block clicked and the block it was placed
+    /// against should be rejected.
+    pub fn reject(&mut self, position: BlockPos) {
+        if !self.rejected.contains(&position) {
+            self.rejected.push(position);
+        }
+    }
+
+    /// Returns the blocks whose actions were rejected this tick.
+    pub fn rejected(&self) -> &[BlockPos] {
+        &self.rejected
+    }
+
+    /// Records that the client predicted a change to the block at `position`.
+    pub(crate) fn predict(&mut self, position: BlockPos) {
+        if !self.predicted.contains(&position) {
+            self.predicted.push(position);
+        }
     }
 }
 
 fn handle_player_action(
-    mut clients: Query<&mut ActionSequence>,
+    mut clients: Query<(&mut ActionSequence, &Position, &GameMode)>,
+    rules: Res<InteractionRules>,
     mut packets: EventReader<PacketEvent>,
     mut digging_events: EventWriter<DiggingEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<PlayerActionC2s>() {
-            if let Ok(mut seq) = clients.get_mut(packet.client) {
-                seq.update(pkt.sequence.0);
+            let Ok((mut seq, pos, game_mode)) = clients.get_mut(packet.client) else {
+                continue;
+            };
+
+            seq.update(pkt.sequence.0);
+
+            let is_digging = matches!(
+                pkt.action,
+                PlayerAction::StartDestroyBlock
+                    | PlayerAction::AbortDestroyBlock
+                    | PlayerAction::StopDestroyBlock
+            );
+
+            if is_digging
+                && (!rules.can_dig(*game_mode)
+                    || !InteractionRules::is_in_reach(pos.0, pkt.position, rules.max_dig_distance))
+            {
+                seq.reject(pkt.position);
+                continue;
             }
 
-            // TODO: check that digging is happening within configurable distance to client.
             // TODO: check that blocks are being broken at the appropriate speeds.
 
+            let state = match pkt.action {
+                PlayerAction::StartDestroyBlock => Some(DiggingState::Start),
+                PlayerAction::StopDestroyBlock => Some(DiggingState::Stop),
+                _ => None,
+            };
+
+            if state.is_some_and(|state| rules.breaks_block(*game_mode, state)) {
+                // The client predicts that the block is broken.
+                seq.predict(pkt.position);
+            }
+
             match pkt.action {
                 PlayerAction::StartDestroyBlock => {
                     digging_events.send(DiggingEvent {
@@ -98,15 +174,46 @@ fn handle_player_action(
 }
 
 fn acknowledge_player_actions(
-    mut clients: Query<(&mut Client, &mut ActionSequence), Changed<ActionSequence>>,
+    mut clients: Query<
+        (
+            &mut Client,
+            &mut ActionSequence,
+            &VisibleChunkLayer,
+            Option<&BlockOverlay>,
+        ),
+        Changed<ActionSequence>,
+    >,
+    layers: Query<&ChunkLayer>,
+    rules: Res<InteractionRules>,
 ) {
-    for (mut client, mut action_seq) in &mut clients {
-        if action_seq.0 != 0 {
+    for (mut client, mut action_seq, visible_layer, overlay) in &mut clients {
+        let action_seq = &mut *action_seq;
+
+        if rules.reconcile_blocks {
+            for pos in action_seq.predicted.drain(..) {
+                if !action_seq.rejected.contains(&pos) {
+                    action_seq.rejected.push(pos);
+                }
+            }
+        } else {
+            action_seq.predicted.clear();
+        }
+
+        if !action_seq.rejected.is_empty() {
+            // The block updates have to arrive before the acknowledgement.
+            if let Ok(layer) = layers.get(visible_layer.0) {
+                resend_blocks(&mut client, layer, overlay, action_seq.rejected.drain(..));
+            } else {
+                action_seq.rejected.clear();
+            }
+        }
+
+        if action_seq.sequence != 0 {
             client.write_packet(&PlayerActionResponseS2c {
-                sequence: VarInt(action_seq.0),
+                sequence: VarInt(action_seq.sequence),
             });
 
-            action_seq.0 = 0;
+            action_seq.sequence = 0;
         }
     }
 }
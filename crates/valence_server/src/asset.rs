@@ -0,0 +1,246 @@
+//! Reloading asset files while the server is running.
+//!
+//! Register files such as schematics, configs and language files with the
+//! [`AssetManager`] resource. When a registered file changes on disk, it's read
+//! again at the start of the next tick and an [`AssetReloadedEvent`] is sent,
+//! so the changes can be applied without restarting the server.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use flume::{Receiver, Sender};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tracing::warn;
+
+pub struct AssetPlugin;
+
+impl Plugin for AssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetManager>()
+            .add_event::<AssetReloadedEvent>()
+            .add_systems(PreUpdate, reload_changed_assets);
+    }
+}
+
+/// Identifies an asset registered with the [`AssetManager`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct AssetId(u64);
+
+/// Sent when the contents of a registered asset changed on disk.
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct AssetReloadedEvent {
+    pub asset: AssetId,
+    pub path: PathBuf,
+}
+
+/// Keeps the contents of registered asset files up to date. See the [module
+/// documentation](self).
+#[derive(Resource)]
+pub struct AssetManager {
+    /// Created when the first asset is registered, so servers without assets
+    /// don't pay for a watcher and its thread.
+    watcher: AssetWatcher,
+    changes_send: Sender<notify::Result<notify::Event>>,
+    changes: Receiver<notify::Result<notify::Event>>,
+    assets: FxHashMap<AssetId, Asset>,
+    ids: FxHashMap<PathBuf, AssetId>,
+    /// The number of assets in each watched directory.
+    dirs: FxHashMap<PathBuf, usize>,
+    next_id: u64,
+}
+
+struct Asset {
+    path: PathBuf,
+    bytes: Vec<u8>,
+}
+
+enum AssetWatcher {
+    NotCreated,
+    /// The watcher isn't `Sync` on every platform.
+    Created(Mutex<RecommendedWatcher>),
+    /// The watcher couldn't be created, so assets are only reloaded with
+    /// [`AssetManager::reload`].
+    Failed,
+}
+
+impl AssetManager {
+    pub fn new() -> Self {
+        let (changes_send, changes_recv) = flume::unbounded();
+
+        Self {
+            watcher: AssetWatcher::NotCreated,
+            changes_send,
+            changes: changes_recv,
+            assets: FxHashMap::default(),
+            ids: FxHashMap::default(),
+            dirs: FxHashMap::default(),
+            next_id: 0,
+        }
+    }
+
+    /// Reads the file at `path` and starts watching it for changes.
+    /// Registering a file which is already registered returns its existing
+    /// ID.
+    pub fn register<P: AsRef<Path>>(&mut self, path: P) -> io::Result<AssetId> {
+        let path = path.as_ref().canonicalize()?;
+
+        if let Some(&id) = self.ids.get(&path) {
+            return Ok(id);
+        }
+
+        let bytes = fs::read(&path)?;
+
+        // Editors often replace files instead of writing to them, which
+        // removes watches on the file itself, so the directory is watched
+        // instead.
+        let dir = path.parent().unwrap_or(&path).to_path_buf();
+        let count = self.dirs.entry(dir.clone()).or_insert(0);
+
+        if *count == 0 {
+            if let AssetWatcher::NotCreated = self.watcher {
+                let changes_send = self.changes_send.clone();
+
+                self.watcher = match notify::recommended_watcher(move |res| {
+                    let _ = changes_send.send(res);
+                }) {
+                    Ok(watcher) => AssetWatcher::Created(Mutex::new(watcher)),
+                    Err(e) => {
+                        warn!("failed to create the asset file watcher: {e}");
+                        AssetWatcher::Failed
+                    }
+                };
+            }
+
+            if let AssetWatcher::Created(watcher) = &mut self.watcher {
+                watcher
+                    .get_mut()
+                    .watch(&dir, RecursiveMode::NonRecursive)
+                    .map_err(io::Error::other)?;
+            }
+        }
+
+        *count += 1;
+
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+
+        self.ids.insert(path.clone(), id);
+        self.assets.insert(id, Asset { path, bytes });
+
+        Ok(id)
+    }
+
+    /// Stops watching an asset and returns its path, or `None` if it wasn't
+    /// registered.
+    pub fn unregister(&mut self, id: AssetId) -> Option<PathBuf> {
+        let asset = self.assets.remove(&id)?;
+        self.ids.remove(&asset.path);
+
+        let dir = asset.path.parent().unwrap_or(&asset.path);
+
+        if let Some(count) = self.dirs.get_mut(dir) {
+            *count -= 1;
+
+            if *count == 0 {
+                self.dirs.remove(dir);
+
+                if let AssetWatcher::Created(watcher) = &mut self.watcher {
+                    let _ = watcher.get_mut().unwatch(dir);
+                }
+            }
+        }
+
+        Some(asset.path)
+    }
+
+    /// Returns the contents of an asset as of the last time it was read.
+    pub fn get(&self, id: AssetId) -> Option<&[u8]> {
+        self.assets.get(&id).map(|asset| asset.bytes.as_slice())
+    }
+
+    /// Returns the canonical path of an asset.
+    pub fn path(&self, id: AssetId) -> Option<&Path> {
+        self.assets.get(&id).map(|asset| asset.path.as_path())
+    }
+
+    /// Returns an iterator over the registered assets and their paths.
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId, &Path)> + '_ {
+        self.assets
+            .iter()
+            .map(|(id, asset)| (*id, asset.path.as_path()))
+    }
+
+    /// Reads an asset from disk again. Returns whether its contents changed.
+    /// No [`AssetReloadedEvent`] is sent.
+    pub fn reload(&mut self, id: AssetId) -> io::Result<bool> {
+        let asset = self
+            .assets
+            .get_mut(&id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "asset isn't registered"))?;
+
+        let bytes = fs::read(&asset.path)?;
+
+        if bytes == asset.bytes {
+            return Ok(false);
+        }
+
+        asset.bytes = bytes;
+
+        Ok(true)
+    }
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn reload_changed_assets(
+    mut manager: ResMut<AssetManager>,
+    mut events: EventWriter<AssetReloadedEvent>,
+) {
+    let mut changed = vec![];
+
+    for res in manager.changes.try_iter() {
+        match res {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    changed.extend(
+                        event
+                            .paths
+                            .iter()
+                            .filter_map(|p| manager.ids.get(p).copied()),
+                    );
+                }
+            }
+            Err(e) => warn!("failed to watch asset files: {e}"),
+        }
+    }
+
+    // A single write often produces several events.
+    changed.sort_unstable();
+    changed.dedup();
+
+    for id in changed {
+        match manager.reload(id) {
+            Ok(true) => {
+                events.send(AssetReloadedEvent {
+                    asset: id,
+                    path: manager.assets[&id].path.clone(),
+                });
+            }
+            Ok(false) => {}
+            // The file may be removed or incomplete while it's being saved, in which case
+            // the old contents are kept until the next change.
+            Err(e) => warn!(
+                "failed to reload asset `{}`: {e}",
+                manager.assets[&id].path.display()
+            ),
+        }
+    }
+}
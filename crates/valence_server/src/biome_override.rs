@@ -0,0 +1,125 @@
+//! Showing clients different biomes than the ones in their chunk layer.
+//!
+//! Adding a [`BiomeOverride`] to a client rewrites the biome palettes of the
+//! chunks it's sent, so a single player can see the world as a crimson forest
+//! while everyone else sees the real biomes. This is useful for per-player
+//! ambience such as fog color, particles and music in hub servers. Only what
+//! the client sees changes. The biomes stored in the [`ChunkLayer`] stay the
+//! same.
+//!
+//! Adding, changing or removing the component resends the biomes of the chunks
+//! in view of the client.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+use valence_protocol::encode::WritePacket;
+use valence_protocol::packets::play::chunk_biome_data_s2c::ChunkBiome;
+use valence_protocol::packets::play::ChunkBiomeDataS2c;
+use valence_registry::biome::BiomeId;
+
+use crate::client::{update_view_and_layers, Client, UpdateClientsSet, View, VisibleChunkLayer};
+use crate::layer::ChunkLayer;
+
+pub struct BiomeOverridePlugin;
+
+impl Plugin for BiomeOverridePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            resend_overridden_biomes
+                .in_set(UpdateClientsSet)
+                .after(update_view_and_layers),
+        );
+    }
+}
+
+/// A [`Component`] for clients replacing the biomes of the chunks they're
+/// sent.
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
+pub struct BiomeOverride {
+    /// The biome shown in place of every biome. Takes priority over
+    /// `replace`.
+    pub fill: Option<BiomeId>,
+    /// Biomes shown in place of other biomes.
+    pub replace: FxHashMap<BiomeId, BiomeId>,
+}
+
+impl BiomeOverride {
+    /// Shows `biome` in place of every biome.
+    pub fn filled(biome: BiomeId) -> Self {
+        Self {
+            fill: Some(biome),
+            replace: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the biome shown in place of `biome`.
+    pub fn get(&self, biome: BiomeId) -> BiomeId {
+        self.fill
+            .or_else(|| self.replace.get(&biome).copied())
+            .unwrap_or(biome)
+    }
+
+    /// Returns whether this override leaves every biome as is.
+    pub fn is_empty(&self) -> bool {
+        self.fill.is_none() && self.replace.is_empty()
+    }
+}
+
+fn resend_overridden_biomes(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        View,
+        &VisibleChunkLayer,
+        Option<Ref<BiomeOverride>>,
+    )>,
+    mut removed: RemovedComponents<BiomeOverride>,
+    chunk_layers: Query<&ChunkLayer>,
+) {
+    let changed: Vec<_> = clients
+        .iter()
+        .filter_map(|(entity, _, _, _, biome_override)| {
+            biome_override.filter(|o| o.is_changed()).map(|_| entity)
+        })
+        .collect();
+
+    for entity in removed.read().chain(changed) {
+        let Ok((_, mut client, view, visible_chunk_layer, biome_override)) =
+            clients.get_mut(entity)
+        else {
+            continue;
+        };
+
+        let Ok(layer) = chunk_layers.get(visible_chunk_layer.0) else {
+            continue;
+        };
+
+        let biome_override = biome_override.as_deref();
+
+        let data: Vec<_> = view
+            .get()
+            .iter()
+            .filter_map(|pos| {
+                let chunk = layer.chunk(pos)?;
+                let mut data = vec![];
+
+                chunk.encode_biomes(&mut data, layer.info(), |b| {
+                    biome_override.map_or(b, |o| o.get(b))
+                });
+
+                Some((pos, data))
+            })
+            .collect();
+
+        if !data.is_empty() {
+            client.write_packet(&ChunkBiomeDataS2c {
+                chunks: data
+                    .iter()
+                    .map(|(pos, data)| ChunkBiome { pos: *pos, data })
+                    .collect(),
+            });
+        }
+    }
+}
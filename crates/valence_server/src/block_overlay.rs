@@ -0,0 +1,192 @@
+//! Blocks shown to a single client on top of its chunk layer.
+//!
+//! A [`BlockOverlay`] on a client replaces blocks of its [`VisibleChunkLayer`]
+//! with blocks only that client can see, like a door that's only open for
+//! players who finished a quest. The layer stays shared with every other client
+//! instead of being copied for each one.
+//!
+//! Overlaid blocks aren't part of the chunk data. They're sent to the client
+//! as separate [`BlockUpdateS2c`] packets right after the chunk is loaded, and
+//! again whenever a block underneath changes.
+//!
+//! The overlay is purely visual. Server-side logic like collisions and
+//! [`ChunkLayer::block`] still sees the blocks of the layer. To show entities
+//! to a single client, spawn them in an
+//! [`EntityLayer`](crate::layer::EntityLayer) that only that client has in its
+//! [`VisibleEntityLayers`](crate::client::VisibleEntityLayers).
+//!
+//! Removing the component doesn't restore the blocks underneath. Use
+//! [`BlockOverlay::clear`] for that instead.
+
+use std::mem;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+use valence_protocol::packets::play::BlockUpdateS2c;
+use valence_protocol::{BlockPos, BlockState, ChunkPos, WritePacket};
+
+use crate::client::{update_view_and_layers, Client, UpdateClientsSet, View, VisibleChunkLayer};
+use crate::layer::ChunkLayer;
+
+pub struct BlockOverlayPlugin;
+
+impl Plugin for BlockOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            send_block_overlay_changes
+                .in_set(UpdateClientsSet)
+                .after(update_view_and_layers),
+        );
+    }
+}
+
+/// A [`Component`] for clients containing the blocks they see in place of the
+/// blocks of their [`VisibleChunkLayer`].
+#[derive(Component, Clone, Default, Debug)]
+pub struct BlockOverlay {
+    chunks: FxHashMap<ChunkPos, FxHashMap<BlockPos, BlockState>>,
+    /// Positions changed since the overlay was last sent to the client.
+    changed: Vec<BlockPos>,
+}
+
+impl BlockOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the block state shown at `pos`, if it's overlaid.
+    pub fn get(&self, pos: impl Into<BlockPos>) -> Option<BlockState> {
+        let pos = pos.into();
+
+        self.chunks
+            .get(&ChunkPos::from(pos))
+            .and_then(|blocks| blocks.get(&pos))
+            .copied()
+    }
+
+    /// Shows `state` at `pos` instead of the block of the layer. The previously
+    /// overlaid block state is returned.
+    pub fn set(&mut self, pos: impl Into<BlockPos>, state: BlockState) -> Option<BlockState> {
+        let pos = pos.into();
+
+        self.changed.push(pos);
+        self.chunks
+            .entry(ChunkPos::from(pos))
+            .or_default()
+            .insert(pos, state)
+    }
+
+    /// Shows the block of the layer at `pos` again. The previously overlaid
+    /// block state is returned.
+    pub fn remove(&mut self, pos: impl Into<BlockPos>) -> Option<BlockState> {
+        let pos = pos.into();
+        let chunk_pos = ChunkPos::from(pos);
+
+        let blocks = self.chunks.get_mut(&chunk_pos)?;
+        let old = blocks.remove(&pos)?;
+
+        if blocks.is_empty() {
+            self.chunks.remove(&chunk_pos);
+        }
+
+        self.changed.push(pos);
+        Some(old)
+    }
+
+    /// Shows the blocks of the layer everywhere again.
+    pub fn clear(&mut self) {
+        for (_, blocks) in self.chunks.drain() {
+            self.changed.extend(blocks.into_keys());
+        }
+    }
+
+    /// Returns the number of overlaid blocks.
+    pub fn len(&self) -> usize {
+        self.chunks.values().map(|blocks| blocks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns an iterator over the overlaid blocks in an arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPos, BlockState)> + '_ {
+        self.chunks
+            .values()
+            .flatten()
+            .map(|(pos, state)| (*pos, *state))
+    }
+
+    /// Returns an iterator over the overlaid blocks in the chunk at `pos` in an
+    /// arbitrary order.
+    pub fn iter_chunk(
+        &self,
+        pos: impl Into<ChunkPos>,
+    ) -> impl Iterator<Item = (BlockPos, BlockState)> + '_ {
+        self.chunks
+            .get(&pos.into())
+            .into_iter()
+            .flatten()
+            .map(|(pos, state)| (*pos, *state))
+    }
+}
+
+/// Writes the overlaid blocks in the chunk at `pos`. This has to happen after
+/// the chunk or any of its blocks were sent.
+pub(crate) fn write_overlay_chunk(
+    client: &mut Client,
+    overlay: Option<&BlockOverlay>,
+    pos: ChunkPos,
+) {
+    let Some(overlay) = overlay else {
+        return;
+    };
+
+    for (position, block_id) in overlay.iter_chunk(pos) {
+        client.write_packet(&BlockUpdateS2c { position, block_id });
+    }
+}
+
+fn send_block_overlay_changes(
+    mut clients: Query<
+        (&mut Client, &mut BlockOverlay, View, &VisibleChunkLayer),
+        Changed<BlockOverlay>,
+    >,
+    layers: Query<&ChunkLayer>,
+) {
+    for (mut client, mut overlay, view, visible_layer) in &mut clients {
+        let overlay = overlay.bypass_change_detection();
+        let mut changed = mem::take(&mut overlay.changed);
+
+        let Ok(layer) = layers.get(visible_layer.0) else {
+            continue;
+        };
+
+        let view = view.get();
+
+        changed.sort_unstable();
+        changed.dedup();
+
+        for pos in changed {
+            if !view.contains(ChunkPos::from(pos)) {
+                // Sent when the chunk is loaded instead.
+                continue;
+            }
+
+            let block_id = match overlay.get(pos) {
+                Some(state) => state,
+                None => match layer.block(pos) {
+                    Some(block) => block.state,
+                    None => continue,
+                },
+            };
+
+            client.write_packet(&BlockUpdateS2c {
+                position: pos,
+                block_id,
+            });
+        }
+    }
+}
@@ -1,5 +1,31 @@
-use valence_protocol::packets::play::CustomPayloadS2c;
-use valence_protocol::{ident, Bounded, Encode, VarInt, WritePacket};
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use derive_more::{Deref, DerefMut};
+use valence_protocol::packets::play::{CustomPayloadC2s, CustomPayloadS2c};
+use valence_protocol::{ident, Bounded, Decode, Encode, VarInt, WritePacket};
+
+use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+
+pub struct BrandPlugin;
+
+impl Plugin for BrandPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(EventLoopPreUpdate, handle_client_brand);
+    }
+}
+
+/// The brand of a client's game, such as `vanilla` or the name of a mod
+/// loader. Sent by the client after joining, so this [`Component`] is only
+/// inserted once the brand arrives.
+#[derive(Component, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+pub struct ClientBrand(pub String);
+
+impl ClientBrand {
+    /// Returns whether the client claims to be the unmodified game.
+    pub fn is_vanilla(&self) -> bool {
+        self.0 == "vanilla"
+    }
+}
 
 pub trait SetBrand {
     /// Sets the brand of the server.
@@ -26,3 +52,25 @@ impl<T: WritePacket> SetBrand for T {
         });
     }
 }
+
+fn handle_client_brand(mut packets: EventReader<PacketEvent>, mut commands: Commands) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<CustomPayloadC2s>() else {
+            continue;
+        };
+
+        if pkt.channel.as_str() != "minecraft:brand" {
+            continue;
+        }
+
+        let mut data = pkt.data.0 .0;
+
+        let Ok(brand) = <&str>::decode(&mut data) else {
+            continue;
+        };
+
+        if let Some(mut client) = commands.get_entity(packet.client) {
+            client.insert(ClientBrand(brand.into()));
+        }
+    }
+}
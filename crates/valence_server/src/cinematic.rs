@@ -0,0 +1,344 @@
+//! Moving a client's viewpoint along a path for cutscenes and map intros.
+//!
+//! A [`CameraPath`] is a list of keyframes with a position and a look at a
+//! given tick. Positions between keyframes are interpolated along a smooth
+//! curve passing through every keyframe, and rotations take the shortest way
+//! around. Adding a [`Cinematic`] to a client plays the path one tick at a
+//! time. A [`CinematicEndEvent`] is sent and the component is removed once the
+//! last keyframe is reached.
+//!
+//! There are two ways to move the viewpoint, chosen with [`CameraMode`]:
+//!
+//! - [`CameraMode::Teleport`] teleports the client every tick. This works in
+//!   every game mode but looks choppy, since the client doesn't interpolate
+//!   between teleports.
+//! - [`CameraMode::Entity`] spawns an invisible camera entity and makes the
+//!   client spectate it. The client interpolates the entity's movement, so the
+//!   path looks smooth. The client is still teleported along the path to keep
+//!   the chunks around the camera loaded.
+//!
+//! In both modes the client can still move and look around on its own. Putting
+//! it in spectator mode or freezing it during the cinematic is up to you.
+//! Removing the component stops the cinematic early without sending an event.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::armor_stand::ArmorStandEntityBundle;
+use valence_entity::entity::{Flags, NoGravity};
+use valence_entity::{EntityId, EntityLayerId, HeadYaw, InitEntitiesSet, Look, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::SetCameraEntityS2c;
+use valence_protocol::{VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::{handle_layer_messages, update_view_and_layers, Client, UpdateClientsSet};
+
+pub struct CinematicPlugin;
+
+impl Plugin for CinematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CinematicEndEvent>().add_systems(
+            PostUpdate,
+            (
+                advance_cinematics.before(InitEntitiesSet),
+                (attach_cameras, detach_cameras)
+                    .in_set(UpdateClientsSet)
+                    .after(handle_layer_messages)
+                    .after(update_view_and_layers),
+            ),
+        );
+    }
+}
+
+/// The position and look of the viewpoint at a point in a [`CameraPath`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CameraKeyframe {
+    /// The tick of the keyframe, counted from the start of the path.
+    pub tick: u32,
+    pub position: DVec3,
+    pub look: Look,
+}
+
+/// A list of keyframes the viewpoint of a client moves through.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct CameraPath {
+    /// Sorted by tick, without duplicate ticks.
+    keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a keyframe at `tick`, replacing the keyframe already at `tick`.
+    pub fn with_keyframe(mut self, tick: u32, position: impl Into<DVec3>, look: Look) -> Self {
+        self.insert(CameraKeyframe {
+            tick,
+            position: position.into(),
+            look,
+        });
+        self
+    }
+
+    /// Adds `keyframe` to the path, replacing the keyframe already at its tick.
+    /// The replaced keyframe is returned.
+    pub fn insert(&mut self, keyframe: CameraKeyframe) -> Option<CameraKeyframe> {
+        match self
+            .keyframes
+            .binary_search_by_key(&keyframe.tick, |k| k.tick)
+        {
+            Ok(idx) => Some(std::mem::replace(&mut self.keyframes[idx], keyframe)),
+            Err(idx) => {
+                self.keyframes.insert(idx, keyframe);
+                None
+            }
+        }
+    }
+
+    /// Returns the keyframes sorted by tick.
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    /// Returns the tick of the last keyframe, or zero if there are no
+    /// keyframes.
+    pub fn duration(&self) -> u32 {
+        self.keyframes.last().map_or(0, |k| k.tick)
+    }
+
+    /// Returns the position and look of the viewpoint at `tick`. Ticks before
+    /// the first or after the last keyframe are clamped to them. Returns `None`
+    /// if there are no keyframes.
+    pub fn sample(&self, tick: f64) -> Option<(DVec3, Look)> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        if tick <= f64::from(first.tick) {
+            return Some((first.position, first.look));
+        }
+
+        if tick >= f64::from(last.tick) {
+            return Some((last.position, last.look));
+        }
+
+        // The index of the first keyframe after `tick`, which is at least one.
+        let i = self
+            .keyframes
+            .partition_point(|k| f64::from(k.tick) <= tick);
+
+        let k0 = &self.keyframes[i - 1];
+        let k1 = &self.keyframes[i];
+
+        let dt = f64::from(k1.tick - k0.tick);
+        let t = (tick - f64::from(k0.tick)) / dt;
+
+        // Cubic Hermite interpolation with the velocities of a Catmull-Rom
+        // spline, which also works for keyframes spaced unevenly in time.
+        let m0 = self.velocity(i - 1) * dt;
+        let m1 = self.velocity(i) * dt;
+
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        let position = (2.0 * t3 - 3.0 * t2 + 1.0) * k0.position
+            + (t3 - 2.0 * t2 + t) * m0
+            + (-2.0 * t3 + 3.0 * t2) * k1.position
+            + (t3 - t2) * m1;
+
+        let t = t as f32;
+        let yaw_delta = (k1.look.yaw - k0.look.yaw + 180.0).rem_euclid(360.0) - 180.0;
+
+        let look = Look {
+            yaw: k0.look.yaw + yaw_delta * t,
+            pitch: k0.look.pitch + (k1.look.pitch - k0.look.pitch) * t,
+        };
+
+        Some((position, look))
+    }
+
+    /// The velocity of the viewpoint at keyframe `i`, in blocks per tick.
+    fn velocity(&self, i: usize) -> DVec3 {
+        let prev = &self.keyframes[i.saturating_sub(1)];
+        let next = &self.keyframes[(i + 1).min(self.keyframes.len() - 1)];
+
+        if prev.tick == next.tick {
+            DVec3::ZERO
+        } else {
+            (next.position - prev.position) / f64::from(next.tick - prev.tick)
+        }
+    }
+}
+
+/// How the viewpoint of a client is moved along a [`CameraPath`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum CameraMode {
+    /// Teleport the client along the path.
+    #[default]
+    Teleport,
+    /// Make the client spectate an invisible entity moving along the path.
+    Entity,
+}
+
+/// A [`Component`] for clients playing a [`CameraPath`].
+#[derive(Component, Clone, Debug)]
+pub struct Cinematic {
+    pub path: CameraPath,
+    /// The mode can't be changed once the cinematic has started.
+    pub mode: CameraMode,
+    tick: u32,
+}
+
+impl Cinematic {
+    pub fn new(path: CameraPath, mode: CameraMode) -> Self {
+        Self {
+            path,
+            mode,
+            tick: 0,
+        }
+    }
+
+    /// Returns the tick of the path the viewpoint is at.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+}
+
+/// Sent when a client reaches the end of its [`Cinematic`].
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CinematicEndEvent {
+    pub client: Entity,
+}
+
+/// The camera entity a client is spectating during a [`Cinematic`] in
+/// [`CameraMode::Entity`].
+#[derive(Component, Debug)]
+struct CinematicCamera {
+    entity: Entity,
+    /// Whether the client was told to spectate the camera.
+    attached: bool,
+}
+
+/// Points from a camera entity to the client using it.
+#[derive(Component, Debug)]
+struct CameraOwner(Entity);
+
+#[allow(clippy::type_complexity)]
+fn advance_cinematics(
+    mut clients: Query<
+        (
+            Entity,
+            &mut Cinematic,
+            &mut Position,
+            &mut Look,
+            &EntityLayerId,
+            Option<&CinematicCamera>,
+        ),
+        With<Client>,
+    >,
+    mut cameras: Query<(&mut Position, &mut Look, &mut HeadYaw), Without<Client>>,
+    mut end_events: EventWriter<CinematicEndEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut cinematic, mut pos, mut look, layer, camera) in &mut clients {
+        let Some((new_pos, new_look)) = cinematic.path.sample(f64::from(cinematic.tick)) else {
+            // Nothing to play.
+            end_events.send(CinematicEndEvent { client: entity });
+            commands.entity(entity).remove::<Cinematic>();
+            continue;
+        };
+
+        pos.set_if_neq(Position(new_pos));
+
+        match camera {
+            Some(camera) => {
+                if let Ok((mut camera_pos, mut camera_look, mut head_yaw)) =
+                    cameras.get_mut(camera.entity)
+                {
+                    camera_pos.set_if_neq(Position(new_pos));
+                    camera_look.set_if_neq(new_look);
+                    head_yaw.set_if_neq(HeadYaw(new_look.yaw));
+                }
+            }
+            None if cinematic.mode == CameraMode::Entity && cinematic.tick == 0 => {
+                let mut flags = Flags::default();
+                flags.set_invisible(true);
+
+                let camera = commands
+                    .spawn((
+                        ArmorStandEntityBundle {
+                            layer: *layer,
+                            position: Position(new_pos),
+                            look: new_look,
+                            head_yaw: HeadYaw(new_look.yaw),
+                            entity_flags: flags,
+                            entity_no_gravity: NoGravity(true),
+                            ..Default::default()
+                        },
+                        CameraOwner(entity),
+                    ))
+                    .id();
+
+                commands.entity(entity).insert(CinematicCamera {
+                    entity: camera,
+                    attached: false,
+                });
+            }
+            None => {
+                look.set_if_neq(new_look);
+            }
+        }
+
+        if cinematic.tick >= cinematic.path.duration() {
+            end_events.send(CinematicEndEvent { client: entity });
+            commands.entity(entity).remove::<Cinematic>();
+        } else {
+            cinematic.tick += 1;
+        }
+    }
+}
+
+/// Makes clients spectate their camera once it's been spawned for them.
+fn attach_cameras(
+    mut clients: Query<(&mut Client, &mut CinematicCamera)>,
+    cameras: Query<&EntityId>,
+) {
+    for (mut client, mut camera) in &mut clients {
+        if camera.attached {
+            continue;
+        }
+
+        if let Ok(id) = cameras.get(camera.entity) {
+            client.write_packet(&SetCameraEntityS2c {
+                entity_id: id.get().into(),
+            });
+
+            camera.attached = true;
+        }
+    }
+}
+
+/// Despawns the cameras of clients which no longer have a [`Cinematic`] and
+/// gives them back their own viewpoint.
+fn detach_cameras(
+    cameras: Query<(Entity, &CameraOwner), Without<Despawned>>,
+    mut clients: Query<(&mut Client, Has<Cinematic>), With<CinematicCamera>>,
+    mut commands: Commands,
+) {
+    for (camera, owner) in &cameras {
+        if let Ok((mut client, has_cinematic)) = clients.get_mut(owner.0) {
+            if has_cinematic {
+                continue;
+            }
+
+            // Clients see themselves as entity 0.
+            client.write_packet(&SetCameraEntityS2c {
+                entity_id: VarInt(0),
+            });
+
+            commands.entity(owner.0).remove::<CinematicCamera>();
+        }
+
+        commands.entity(camera).insert(Despawned);
+    }
+}
@@ -11,7 +11,7 @@ use bevy_ecs::world::Command;
 use byteorder::{NativeEndian, ReadBytesExt};
 use bytes::{Bytes, BytesMut};
 use derive_more::{Deref, DerefMut, From, Into};
-use tracing::warn;
+use tracing::{debug, info, info_span, warn, Span};
 use uuid::Uuid;
 use valence_entity::attributes::{EntityAttributes, TrackedEntityAttributes};
 use valence_entity::living::Health;
@@ -19,10 +19,10 @@ use valence_entity::player::{Food, PlayerEntityBundle, Saturation};
 use valence_entity::query::EntityInitQuery;
 use valence_entity::tracked_data::TrackedData;
 use valence_entity::{
-    ClearEntityChangesSet, EntityId, EntityStatus, OldPosition, Position, Velocity,
+    ClearEntityChangesSet, EntityId, EntityStatus, InitEntitiesSet, OldPosition, Position, Velocity,
 };
 use valence_math::{DVec3, Vec3};
-use valence_protocol::encode::{PacketEncoder, WritePacket};
+use valence_protocol::encode::{PacketEncoder, PacketPriority, WritePacket};
 use valence_protocol::packets::play::chunk_biome_data_s2c::ChunkBiome;
 use valence_protocol::packets::play::game_state_change_s2c::GameEventKind;
 use valence_protocol::packets::play::particle_s2c::Particle;
@@ -36,10 +36,13 @@ use valence_protocol::profile::Property;
 use valence_protocol::sound::{Sound, SoundCategory, SoundId};
 use valence_protocol::text::{IntoText, Text};
 use valence_protocol::var_int::VarInt;
-use valence_protocol::{BlockPos, ChunkPos, Encode, GameMode, Packet};
+use valence_protocol::{BlockPos, ChunkPos, CompressionThreshold, Encode, GameMode, Packet};
 use valence_registry::RegistrySet;
 use valence_server_common::{Despawned, UniqueId};
 
+use crate::biome_override::BiomeOverride;
+use crate::block_overlay::{write_overlay_chunk, BlockOverlay};
+use crate::effect_filter::{EffectScope, ParticleFilter, SoundFilter};
 use crate::layer::{ChunkLayer, EntityLayer, UpdateLayersPostClientSet, UpdateLayersPreClientSet};
 use crate::ChunkView;
 
@@ -69,6 +72,7 @@ impl Plugin for ClientPlugin {
             (
                 (
                     crate::spawn::initial_join.after(RegistrySet),
+                    crate::spawn::update_join_chunk_radius.before(update_view_and_layers),
                     update_chunk_load_dist,
                     handle_layer_messages.after(update_chunk_load_dist),
                     update_view_and_layers
@@ -77,6 +81,8 @@ impl Plugin for ClientPlugin {
                     cleanup_chunks_after_client_despawn.after(update_view_and_layers),
                     crate::spawn::update_respawn_position.after(update_view_and_layers),
                     crate::spawn::respawn.after(crate::spawn::update_respawn_position),
+                    crate::spawn::update_reduced_debug_info.after(crate::spawn::initial_join),
+                    crate::spawn::update_respawn_screen.after(crate::spawn::initial_join),
                     update_old_view_dist.after(update_view_and_layers),
                     update_game_mode,
                     update_food_saturation_health,
@@ -86,9 +92,17 @@ impl Plugin for ClientPlugin {
                     init_tracked_attributes,
                 )
                     .in_set(UpdateClientsSet),
+                crate::spawn::spawn_at_respawn_position.before(InitEntitiesSet),
                 flush_packets.in_set(FlushPacketsSet),
+                log_disconnected_clients.after(FlushPacketsSet),
             ),
         )
+        .add_systems(
+            PreUpdate,
+            (log_joined_clients, send_client_login_events).after(SpawnClientsSet),
+        )
+        .add_event::<ClientLoginEvent>()
+        .init_resource::<crate::spawn::JoinConfiguration>()
         .configure_sets(PreUpdate, SpawnClientsSet)
         .configure_sets(
             PostUpdate,
@@ -112,11 +126,13 @@ impl Plugin for ClientPlugin {
 pub struct ClientBundle {
     pub marker: ClientMarker,
     pub client: Client,
+    pub span: ClientSpan,
     pub settings: crate::client_settings::ClientSettings,
     pub entity_remove_buf: EntityRemoveBuf,
     pub username: Username,
     pub ip: Ip,
     pub properties: Properties,
+    pub protocol_version: ProtocolVersion,
     pub respawn_pos: crate::spawn::RespawnPosition,
     pub op_level: crate::op_level::OpLevel,
     pub action_sequence: crate::action::ActionSequence,
@@ -131,6 +147,7 @@ pub struct ClientBundle {
     pub teleport_state: crate::teleport::TeleportState,
     pub game_mode: GameMode,
     pub prev_game_mode: crate::spawn::PrevGameMode,
+    pub game_mode_state: crate::game_mode::GameModeState,
     pub death_location: crate::spawn::DeathLocation,
     pub is_hardcore: crate::spawn::IsHardcore,
     pub hashed_seed: crate::spawn::HashedSeed,
@@ -142,6 +159,8 @@ pub struct ClientBundle {
     pub flying_speed: crate::abilities::FlyingSpeed,
     pub fov_modifier: crate::abilities::FovModifier,
     pub player_abilities_flags: crate::abilities::PlayerAbilitiesFlags,
+    pub experience: crate::experience::Experience,
+    pub player_input: crate::client_command::PlayerInput,
     pub player: PlayerEntityBundle,
 }
 
@@ -151,13 +170,17 @@ impl ClientBundle {
             marker: ClientMarker,
             client: Client {
                 conn: args.conn,
+                low_priority_enc: args.enc.clone_settings(),
                 enc: args.enc,
+                broadcast_threshold: args.broadcast_threshold,
             },
+            span: ClientSpan::new(&args.username, args.uuid, args.ip),
             settings: Default::default(),
             entity_remove_buf: Default::default(),
             username: Username(args.username),
             ip: Ip(args.ip),
             properties: Properties(args.properties),
+            protocol_version: ProtocolVersion(args.protocol_version),
             respawn_pos: Default::default(),
             op_level: Default::default(),
             action_sequence: Default::default(),
@@ -172,6 +195,7 @@ impl ClientBundle {
             teleport_state: crate::teleport::TeleportState::new(),
             game_mode: GameMode::default(),
             prev_game_mode: Default::default(),
+            game_mode_state: Default::default(),
             death_location: Default::default(),
             is_hardcore: Default::default(),
             is_flat: Default::default(),
@@ -183,6 +207,8 @@ impl ClientBundle {
             flying_speed: Default::default(),
             fov_modifier: Default::default(),
             player_abilities_flags: Default::default(),
+            experience: Default::default(),
+            player_input: Default::default(),
             player: PlayerEntityBundle {
                 uuid: UniqueId(args.uuid),
                 ..Default::default()
@@ -201,10 +227,20 @@ pub struct ClientBundleArgs {
     pub ip: IpAddr,
     /// Properties of this client from the game profile.
     pub properties: Vec<Property>,
+    /// The protocol version the client sent in its handshake.
+    pub protocol_version: i32,
     /// The abstract socket connection.
     pub conn: Box<dyn ClientConnection>,
     /// The packet encoder to use. This should be in sync with [`Self::conn`].
+    /// Its compression threshold may differ from the server's, for instance to
+    /// disable compression for clients on the local network.
     pub enc: PacketEncoder,
+    /// The compression threshold of packet data shared between clients, such
+    /// as the messages of layers. This is normally the
+    /// [server's](valence_server_common::Server::compression_threshold). Shared
+    /// data is compressed again for clients whose encoder uses a different
+    /// threshold.
+    pub broadcast_threshold: CompressionThreshold,
 }
 
 /// Marker [`Component`] for client entities. This component should exist even
@@ -221,6 +257,24 @@ pub struct ClientMarker;
 pub struct Client {
     conn: Box<dyn ClientConnection>,
     pub(crate) enc: PacketEncoder,
+    /// Packets which may be dropped, encoded separately so that they're sent
+    /// as whole packets.
+    low_priority_enc: PacketEncoder,
+    broadcast_threshold: CompressionThreshold,
+}
+
+/// The [`tracing`] span of a client, with the client's username, UUID and IP
+/// address as fields. Logs about the client are emitted in this span, so they
+/// can be filtered by player.
+///
+/// The component stays on the entity after the client disconnects.
+#[derive(Component, Clone, Debug)]
+pub struct ClientSpan(pub Span);
+
+impl ClientSpan {
+    pub fn new(username: &str, uuid: Uuid, ip: IpAddr) -> Self {
+        Self(info_span!("client", username, %uuid, %ip))
+    }
 }
 
 /// Represents the bidirectional packet channel between the server and a client
@@ -229,12 +283,47 @@ pub trait ClientConnection: Send + Sync + 'static {
     /// Sends encoded clientbound packet data. This function must not block and
     /// the data should be sent as soon as possible.
     fn try_send(&mut self, bytes: BytesMut) -> anyhow::Result<()>;
+    /// Like [`Self::try_send`], but the data may be shared with other clients.
+    ///
+    /// The default implementation copies the data if it is shared and calls
+    /// [`Self::try_send`]. Connections should override this to avoid the copy.
+    fn try_send_shared(&mut self, bytes: Bytes) -> anyhow::Result<()> {
+        let bytes = bytes
+            .try_into_mut()
+            .unwrap_or_else(|bytes| BytesMut::from(&bytes[..]));
+
+        self.try_send(bytes)
+    }
+    /// Like [`Self::try_send_shared`], but the data is less important and may
+    /// be dropped if the connection is congested. The data always contains
+    /// whole packets.
+    ///
+    /// The default implementation calls [`Self::try_send_shared`].
+    fn try_send_low_priority(&mut self, bytes: Bytes) -> anyhow::Result<()> {
+        self.try_send_shared(bytes)
+    }
+    /// Like [`Self::try_send_shared`], but the data contains whole
+    /// [critical](PacketPriority::Critical) packets, which may be sent ahead
+    /// of the data sent before that hasn't been written to the connection
+    /// yet.
+    ///
+    /// The default implementation calls [`Self::try_send_shared`].
+    fn try_send_critical(&mut self, bytes: Bytes) -> anyhow::Result<()> {
+        self.try_send_shared(bytes)
+    }
     /// Receives the next pending serverbound packet. This must return
     /// immediately without blocking.
     fn try_recv(&mut self) -> anyhow::Result<Option<ReceivedPacket>>;
     /// The number of pending packets waiting to be received via
     /// [`Self::try_recv`].
     fn len(&self) -> usize;
+    /// The number of bytes sent which haven't been written to the connection
+    /// yet. A growing number means the connection can't keep up.
+    ///
+    /// The default implementation returns `0`.
+    fn queued_bytes(&self) -> usize {
+        0
+    }
 
     fn is_empty(&self) -> bool {
         self.len() == 0
@@ -269,7 +358,30 @@ impl WritePacket for Client {
     }
 
     fn write_packet_bytes(&mut self, bytes: &[u8]) {
-        self.enc.write_packet_bytes(bytes)
+        if let Err(e) = self
+            .enc
+            .append_bytes_with_threshold(bytes, self.broadcast_threshold)
+        {
+            warn!("failed to write packet bytes: {e:#}");
+        }
+    }
+
+    fn write_packet_with_priority<P>(&mut self, packet: &P, priority: PacketPriority)
+    where
+        P: Packet + Encode,
+    {
+        self.enc
+            .with_priority(priority, |enc| enc.write_packet(packet))
+    }
+
+    fn write_packet_bytes_with_priority(&mut self, bytes: &[u8], priority: PacketPriority) {
+        let threshold = self.broadcast_threshold;
+
+        self.enc.with_priority(priority, |enc| {
+            if let Err(e) = enc.append_bytes_with_threshold(bytes, threshold) {
+                warn!("failed to write packet bytes: {e:#}");
+            }
+        })
     }
 }
 
@@ -290,11 +402,53 @@ impl Client {
     ///
     /// Returns an error if flushing was unsuccessful.
     pub fn flush_packets(&mut self) -> anyhow::Result<()> {
-        let bytes = self.enc.take();
-        if !bytes.is_empty() {
-            self.conn.try_send(bytes)
+        // Critical packets can only be sent ahead of the data sent before if
+        // the data is encrypted after it's sent to the connection.
+        if !self.enc.is_encrypted() {
+            let critical = self.enc.take_critical();
+
+            if !critical.is_empty() {
+                self.conn.try_send_critical(critical.freeze())?;
+            }
+        }
+
+        for bytes in self.enc.take_chunks() {
+            self.conn.try_send_shared(bytes)?;
+        }
+
+        for bytes in self.low_priority_enc.take_chunks() {
+            self.conn.try_send_low_priority(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a packet which may be dropped when the connection can't keep
+    /// up, such as particles. Low priority packets are sent after the other
+    /// packets written in the same tick.
+    ///
+    /// The packet is written like any other if the packets of this client are
+    /// encrypted before they're sent to the connection, since dropping
+    /// encrypted data would break the cipher.
+    pub fn write_low_priority_packet<P>(&mut self, packet: &P)
+    where
+        P: Packet + Encode,
+    {
+        if self.enc.is_encrypted() {
+            self.enc.write_packet(packet);
         } else {
-            Ok(())
+            self.low_priority_enc.write_packet(packet);
+        }
+    }
+
+    /// Writes packet data shared with other clients, such as the messages of
+    /// layers. Unlike [`WritePacket::write_packet_bytes`], the data is
+    /// reference counted instead of copied when possible.
+    pub(crate) fn write_shared_bytes(&mut self, bytes: Bytes) {
+        if self.enc.compression() == self.broadcast_threshold {
+            self.enc.append_shared_bytes(bytes);
+        } else {
+            self.write_packet_bytes(&bytes);
         }
     }
 
@@ -328,7 +482,7 @@ impl Client {
         P: Into<DVec3>,
         O: Into<Vec3>,
     {
-        self.write_packet(&ParticleS2c {
+        self.write_low_priority_packet(&ParticleS2c {
             particle: Cow::Borrowed(particle),
             long_distance,
             position: position.into(),
@@ -381,6 +535,26 @@ impl Client {
     }
 }
 
+/// Sent on the tick a client is spawned with the details it logged in with,
+/// before it joins the game.
+///
+/// Unlike the asynchronous login callback of the network plugin, this can be
+/// handled with access to the world, for instance to look for other accounts
+/// from the same address. Clients disconnected with [`DisconnectClient`] before
+/// [`PostUpdate`] are turned away without joining, so other players never see
+/// them.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct ClientLoginEvent {
+    pub client: Entity,
+    pub username: String,
+    pub uuid: Uuid,
+    pub ip: IpAddr,
+    /// The properties from the game profile of the client.
+    pub properties: Vec<Property>,
+    /// The protocol version the client sent in its handshake.
+    pub protocol_version: i32,
+}
+
 /// A [`Command`] to disconnect a [`Client`] with a displayed reason.
 #[derive(Clone, PartialEq, Debug)]
 pub struct DisconnectClient {
@@ -391,6 +565,10 @@ pub struct DisconnectClient {
 impl Command for DisconnectClient {
     fn apply(self, world: &mut World) {
         if let Some(mut entity) = world.get_entity_mut(self.client) {
+            if let Some(span) = entity.get::<ClientSpan>() {
+                debug!(parent: &span.0, "disconnecting client: {}", self.reason);
+            }
+
             if let Some(mut client) = entity.get_mut::<Client>() {
                 client.write_packet(&DisconnectS2c {
                     reason: self.reason.into(),
@@ -448,9 +626,14 @@ impl fmt::Display for Username {
 pub struct Properties(pub Vec<Property>);
 
 impl Properties {
+    /// Finds the property with the name `name`.
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
     /// Finds the property with the name "textures".
     pub fn textures(&self) -> Option<&Property> {
-        self.0.iter().find(|p| p.name == "textures")
+        self.get("textures")
     }
 
     /// Finds the property with the name "textures" mutably.
@@ -494,6 +677,12 @@ pub struct PropertyValue {
 #[derive(Component, Clone, PartialEq, Eq, Debug, Deref)]
 pub struct Ip(pub IpAddr);
 
+/// The protocol version the client sent in its handshake. See
+/// [`PROTOCOL_VERSION`](valence_protocol::PROTOCOL_VERSION) for the version
+/// of the server.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug, Deref)]
+pub struct ProtocolVersion(pub i32);
+
 #[derive(Component, Clone, PartialEq, Eq, Debug, Deref)]
 pub struct ViewDistance(u8);
 
@@ -630,7 +819,7 @@ fn update_chunk_load_dist(
     }
 }
 
-fn handle_layer_messages(
+pub(crate) fn handle_layer_messages(
     mut clients: Query<(
         Entity,
         &EntityId,
@@ -640,6 +829,10 @@ fn handle_layer_messages(
         &OldVisibleChunkLayer,
         &mut VisibleEntityLayers,
         &OldVisibleEntityLayers,
+        Option<&ParticleFilter>,
+        Option<&SoundFilter>,
+        Option<&BiomeOverride>,
+        Option<&BlockOverlay>,
     )>,
     chunk_layers: Query<&ChunkLayer>,
     entity_layers: Query<&EntityLayer>,
@@ -655,6 +848,10 @@ fn handle_layer_messages(
             old_visible_chunk_layer,
             mut visible_entity_layers,
             old_visible_entity_layers,
+            particle_filter,
+            sound_filter,
+            biome_override,
+            block_overlay,
         )| {
             let block_pos = BlockPos::from(old_view.old_pos.get());
             let old_view = old_view.get();
@@ -675,26 +872,37 @@ fn handle_layer_messages(
                 for (msg, range) in messages.iter_global() {
                     match msg {
                         crate::layer::chunk::GlobalMsg::Packet => {
-                            client.write_packet_bytes(&bytes[range]);
+                            client.write_shared_bytes(bytes.slice(range));
                         }
                         crate::layer::chunk::GlobalMsg::PacketExcept { except } => {
                             if self_entity != except {
-                                client.write_packet_bytes(&bytes[range]);
+                                client.write_shared_bytes(bytes.slice(range));
                             }
                         }
                     }
                 }
 
+                // Biomes rewritten for this client by its `BiomeOverride`.
+                let mut overridden_biomes = vec![];
                 let mut chunk_biome_buf = vec![];
+                // Chunks which need their overlaid blocks sent again.
+                let mut overlay_chunks = vec![];
 
                 // Local messages
                 messages.query_local(old_view, |msg, range| match msg {
                     crate::layer::chunk::LocalMsg::PacketAt { .. } => {
-                        client.write_packet_bytes(&bytes[range]);
+                        client.write_shared_bytes(bytes.slice(range));
                     }
                     crate::layer::chunk::LocalMsg::PacketAtExcept { except, .. } => {
                         if self_entity != except {
-                            client.write_packet_bytes(&bytes[range]);
+                            client.write_shared_bytes(bytes.slice(range));
+                        }
+                    }
+                    crate::layer::chunk::LocalMsg::BlockChangeAt { pos } => {
+                        client.write_shared_bytes(bytes.slice(range));
+
+                        if block_overlay.is_some_and(|o| o.iter_chunk(pos).next().is_some()) {
+                            overlay_chunks.push(pos);
                         }
                     }
                     crate::layer::chunk::LocalMsg::RadiusAt {
@@ -702,7 +910,7 @@ fn handle_layer_messages(
                         radius_squared,
                     } => {
                         if in_radius(block_pos, center, radius_squared) {
-                            client.write_packet_bytes(&bytes[range]);
+                            client.write_shared_bytes(bytes.slice(range));
                         }
                     }
                     crate::layer::chunk::LocalMsg::RadiusAtExcept {
@@ -711,17 +919,54 @@ fn handle_layer_messages(
                         except,
                     } => {
                         if self_entity != except && in_radius(block_pos, center, radius_squared) {
-                            client.write_packet_bytes(&bytes[range]);
+                            client.write_shared_bytes(bytes.slice(range));
+                        }
+                    }
+                    crate::layer::chunk::LocalMsg::ParticleAt { center, scope } => {
+                        if EffectScope::from(scope).allows(self_entity, block_pos, center)
+                            && particle_filter
+                                .copied()
+                                .unwrap_or_default()
+                                .allows(block_pos, center)
+                        {
+                            client.write_shared_bytes(bytes.slice(range));
+                        }
+                    }
+                    crate::layer::chunk::LocalMsg::SoundAt {
+                        center,
+                        category,
+                        scope,
+                    } => {
+                        if EffectScope::from(scope).allows(self_entity, block_pos, center)
+                            && sound_filter
+                                .copied()
+                                .unwrap_or_default()
+                                .allows(block_pos, center, category)
+                        {
+                            client.write_shared_bytes(bytes.slice(range));
                         }
                     }
                     crate::layer::chunk::LocalMsg::ChangeBiome { pos } => {
-                        chunk_biome_buf.push(ChunkBiome {
-                            pos,
-                            data: &bytes[range],
-                        });
+                        match biome_override.filter(|o| !o.is_empty()) {
+                            Some(biome_override) => {
+                                if let Some(chunk) = chunk_layer.chunk(pos) {
+                                    let mut data = vec![];
+                                    chunk.encode_biomes(&mut data, chunk_layer.info(), |b| {
+                                        biome_override.get(b)
+                                    });
+                                    overridden_biomes.push((pos, data));
+                                }
+                            }
+                            None => chunk_biome_buf.push(ChunkBiome {
+                                pos,
+                                data: &bytes[range],
+                            }),
+                        }
                     }
                     crate::layer::chunk::LocalMsg::ChangeChunkState { pos } => {
-                        match &bytes[range] {
+                        let states = &bytes[range];
+
+                        match states {
                             [ChunkLayer::LOAD, .., ChunkLayer::UNLOAD] => {
                                 // Chunk is being loaded and unloaded on the
                                 // same tick, so there's no need to do anything.
@@ -730,8 +975,18 @@ fn handle_layer_messages(
                             [.., ChunkLayer::LOAD | ChunkLayer::OVERWRITE] => {
                                 // Load chunk.
                                 let chunk = chunk_layer.chunk(pos).expect("chunk must exist");
-                                chunk.write_init_packets(&mut *client, pos, chunk_layer.info());
-                                chunk.inc_viewer_count();
+                                chunk.write_init_packets(
+                                    &mut *client,
+                                    pos,
+                                    chunk_layer.info(),
+                                    biome_override,
+                                );
+                                // Overwritten chunks were already counted, unless they were
+                                // also loaded this tick.
+                                if states.contains(&ChunkLayer::LOAD) {
+                                    chunk.inc_viewer_count();
+                                }
+                                overlay_chunks.push(pos);
                             }
                             [.., ChunkLayer::UNLOAD] => {
                                 // Unload chunk.
@@ -743,6 +998,16 @@ fn handle_layer_messages(
                     }
                 });
 
+                for pos in overlay_chunks {
+                    write_overlay_chunk(&mut client, block_overlay, pos);
+                }
+
+                chunk_biome_buf.extend(
+                    overridden_biomes
+                        .iter()
+                        .map(|(pos, data)| ChunkBiome { pos: *pos, data }),
+                );
+
                 if !chunk_biome_buf.is_empty() {
                     client.write_packet(&ChunkBiomeDataS2c {
                         chunks: chunk_biome_buf.into(),
@@ -760,11 +1025,11 @@ fn handle_layer_messages(
                     for (msg, range) in messages.iter_global() {
                         match msg {
                             crate::layer::entity::GlobalMsg::Packet => {
-                                client.write_packet_bytes(&bytes[range]);
+                                client.write_shared_bytes(bytes.slice(range));
                             }
                             crate::layer::entity::GlobalMsg::PacketExcept { except } => {
                                 if self_entity != except {
-                                    client.write_packet_bytes(&bytes[range]);
+                                    client.write_shared_bytes(bytes.slice(range));
                                 }
                             }
                             crate::layer::entity::GlobalMsg::DespawnLayer => {
@@ -846,11 +1111,11 @@ fn handle_layer_messages(
                             }
                         }
                         crate::layer::entity::LocalMsg::PacketAt { .. } => {
-                            client.write_packet_bytes(&bytes[range]);
+                            client.write_shared_bytes(bytes.slice(range));
                         }
                         crate::layer::entity::LocalMsg::PacketAtExcept { except, .. } => {
                             if self_entity != except {
-                                client.write_packet_bytes(&bytes[range]);
+                                client.write_shared_bytes(bytes.slice(range));
                             }
                         }
                         crate::layer::entity::LocalMsg::RadiusAt {
@@ -858,7 +1123,7 @@ fn handle_layer_messages(
                             radius_squared,
                         } => {
                             if in_radius(block_pos, center, radius_squared) {
-                                client.write_packet_bytes(&bytes[range]);
+                                client.write_shared_bytes(bytes.slice(range));
                             }
                         }
                         crate::layer::entity::LocalMsg::RadiusAtExcept {
@@ -868,7 +1133,7 @@ fn handle_layer_messages(
                         } => {
                             if self_entity != except && in_radius(block_pos, center, radius_squared)
                             {
-                                client.write_packet_bytes(&bytes[range]);
+                                client.write_shared_bytes(bytes.slice(range));
                             }
                         }
                     });
@@ -914,6 +1179,8 @@ pub(crate) fn update_view_and_layers(
             &OldPosition,
             &ViewDistance,
             &OldViewDistance,
+            Option<&BiomeOverride>,
+            Option<&BlockOverlay>,
         ),
         Or<(
             Changed<VisibleChunkLayer>,
@@ -951,6 +1218,8 @@ pub(crate) fn update_view_and_layers(
             old_pos,
             view_dist,
             old_view_dist,
+            biome_override,
+            block_overlay,
         )| {
             let view = ChunkView::new(ChunkPos::from(pos.0), view_dist.0);
             let old_view = ChunkView::new(ChunkPos::from(old_pos.get()), old_view_dist.0);
@@ -981,8 +1250,14 @@ pub(crate) fn update_view_and_layers(
                 if let Ok(layer) = chunk_layers.get(chunk_layer.0) {
                     for pos in view.iter() {
                         if let Some(chunk) = layer.chunk(pos) {
-                            chunk.write_init_packets(&mut *client, pos, layer.info());
+                            chunk.write_init_packets(
+                                &mut *client,
+                                pos,
+                                layer.info(),
+                                biome_override,
+                            );
                             chunk.inc_viewer_count();
+                            write_overlay_chunk(&mut client, block_overlay, pos);
                         }
                     }
                 }
@@ -1113,8 +1388,14 @@ pub(crate) fn update_view_and_layers(
                     if let Ok(layer) = chunk_layers.get(chunk_layer.0) {
                         for pos in view.diff(old_view) {
                             if let Some(chunk) = layer.chunk(pos) {
-                                chunk.write_init_packets(&mut *client, pos, layer.info());
+                                chunk.write_init_packets(
+                                    &mut *client,
+                                    pos,
+                                    layer.info(),
+                                    biome_override,
+                                );
                                 chunk.inc_viewer_count();
+                                write_overlay_chunk(&mut client, block_overlay, pos);
                             }
                         }
                     }
@@ -1230,17 +1511,61 @@ fn update_old_view_dist(
 }
 
 fn flush_packets(
-    mut clients: Query<(Entity, &mut Client), Changed<Client>>,
+    mut clients: Query<(Entity, &mut Client, Option<&ClientSpan>), Changed<Client>>,
     mut commands: Commands,
 ) {
-    for (entity, mut client) in &mut clients {
+    for (entity, mut client, span) in &mut clients {
         if let Err(e) = client.flush_packets() {
+            let _span = span.map(|span| span.0.enter());
             warn!("Failed to flush packet queue for client {entity:?}: {e:#}.");
             commands.entity(entity).remove::<Client>();
         }
     }
 }
 
+fn log_joined_clients(clients: Query<&ClientSpan, Added<Client>>) {
+    for span in &clients {
+        info!(parent: &span.0, "client joined");
+    }
+}
+
+fn send_client_login_events(
+    clients: Query<
+        (
+            Entity,
+            &Username,
+            &UniqueId,
+            &Ip,
+            &Properties,
+            &ProtocolVersion,
+        ),
+        Added<Client>,
+    >,
+    mut events: EventWriter<ClientLoginEvent>,
+) {
+    for (client, username, uuid, ip, properties, protocol_version) in &clients {
+        events.send(ClientLoginEvent {
+            client,
+            username: username.0.clone(),
+            uuid: uuid.0,
+            ip: ip.0,
+            properties: properties.0.clone(),
+            protocol_version: protocol_version.0,
+        });
+    }
+}
+
+fn log_disconnected_clients(
+    mut disconnected_clients: RemovedComponents<Client>,
+    spans: Query<&ClientSpan>,
+) {
+    for entity in disconnected_clients.read() {
+        if let Ok(span) = spans.get(entity) {
+            info!(parent: &span.0, "client left");
+        }
+    }
+}
+
 fn init_tracked_data(mut clients: Query<(&mut Client, &TrackedData), Added<TrackedData>>) {
     for (mut client, tracked_data) in &mut clients {
         if let Some(init_data) = tracked_data.init_data() {
@@ -1,7 +1,6 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use valence_entity::entity::Flags;
-use valence_entity::{entity, Pose};
 pub use valence_protocol::packets::play::client_command_c2s::ClientCommand;
 use valence_protocol::packets::play::ClientCommandC2s;
 
@@ -15,10 +14,26 @@ impl Plugin for ClientCommandPlugin {
             .add_event::<SneakEvent>()
             .add_event::<JumpWithHorseEvent>()
             .add_event::<LeaveBedEvent>()
+            .add_event::<StartFlyingWithElytraEvent>()
             .add_systems(EventLoopPreUpdate, handle_client_command);
     }
 }
 
+/// The movement inputs a client reported with [`ClientCommandC2s`] packets.
+///
+/// The component is only marked as changed when one of the inputs actually
+/// changes, so `Changed<PlayerInput>` can be used to react to them. The
+/// [`SneakEvent`], [`SprintEvent`] and [`JumpWithHorseEvent`] are sent for
+/// every packet.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct PlayerInput {
+    pub sneaking: bool,
+    pub sprinting: bool,
+    /// The power of the horse jump the client is charging in `0..=100`, or
+    /// `None` if it isn't jumping with a horse.
+    pub jumping_with_horse: Option<u8>,
+}
+
 #[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
 pub struct SprintEvent {
     pub client: Entity,
@@ -63,21 +78,29 @@ pub struct LeaveBedEvent {
     pub client: Entity,
 }
 
+/// Sent when a client asks to start gliding with an elytra. The client only
+/// glides once the server sets the fall flying flag in its [`Flags`].
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StartFlyingWithElytraEvent {
+    pub client: Entity,
+}
+
 fn handle_client_command(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<(&mut entity::Pose, &mut Flags)>,
+    mut clients: Query<(&mut Flags, &mut PlayerInput)>,
     mut sprinting_events: EventWriter<SprintEvent>,
     mut sneaking_events: EventWriter<SneakEvent>,
     mut jump_with_horse_events: EventWriter<JumpWithHorseEvent>,
     mut leave_bed_events: EventWriter<LeaveBedEvent>,
+    mut start_flying_events: EventWriter<StartFlyingWithElytraEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<ClientCommandC2s>() {
             match pkt.action {
                 ClientCommand::StartSneaking => {
-                    if let Ok((mut pose, mut flags)) = clients.get_mut(packet.client) {
-                        pose.0 = Pose::Sneaking;
+                    if let Ok((mut flags, mut input)) = clients.get_mut(packet.client) {
                         flags.set_sneaking(true);
+                        set_input(&mut input, |input| input.sneaking = true);
                     }
 
                     sneaking_events.send(SneakEvent {
@@ -86,9 +109,9 @@ fn handle_client_command(
                     });
                 }
                 ClientCommand::StopSneaking => {
-                    if let Ok((mut pose, mut flags)) = clients.get_mut(packet.client) {
-                        pose.0 = Pose::Standing;
+                    if let Ok((mut flags, mut input)) = clients.get_mut(packet.client) {
                         flags.set_sneaking(false);
+                        set_input(&mut input, |input| input.sneaking = false);
                     }
 
                     sneaking_events.send(SneakEvent {
@@ -102,8 +125,9 @@ fn handle_client_command(
                     });
                 }
                 ClientCommand::StartSprinting => {
-                    if let Ok((_, mut flags)) = clients.get_mut(packet.client) {
+                    if let Ok((mut flags, mut input)) = clients.get_mut(packet.client) {
                         flags.set_sprinting(true);
+                        set_input(&mut input, |input| input.sprinting = true);
                     }
 
                     sprinting_events.send(SprintEvent {
@@ -112,8 +136,9 @@ fn handle_client_command(
                     });
                 }
                 ClientCommand::StopSprinting => {
-                    if let Ok((_, mut flags)) = clients.get_mut(packet.client) {
+                    if let Ok((mut flags, mut input)) = clients.get_mut(packet.client) {
                         flags.set_sprinting(false);
+                        set_input(&mut input, |input| input.sprinting = false);
                     }
 
                     sprinting_events.send(SprintEvent {
@@ -122,14 +147,22 @@ fn handle_client_command(
                     });
                 }
                 ClientCommand::StartJumpWithHorse => {
+                    let power = pkt.jump_boost.0 as u8;
+
+                    if let Ok((_, mut input)) = clients.get_mut(packet.client) {
+                        set_input(&mut input, |input| input.jumping_with_horse = Some(power));
+                    }
+
                     jump_with_horse_events.send(JumpWithHorseEvent {
                         client: packet.client,
-                        state: JumpWithHorseState::Start {
-                            power: pkt.jump_boost.0 as u8,
-                        },
+                        state: JumpWithHorseState::Start { power },
                     });
                 }
                 ClientCommand::StopJumpWithHorse => {
+                    if let Ok((_, mut input)) = clients.get_mut(packet.client) {
+                        set_input(&mut input, |input| input.jumping_with_horse = None);
+                    }
+
                     jump_with_horse_events.send(JumpWithHorseEvent {
                         client: packet.client,
                         state: JumpWithHorseState::Stop,
@@ -137,13 +170,19 @@ fn handle_client_command(
                 }
                 ClientCommand::OpenHorseInventory => {} // TODO
                 ClientCommand::StartFlyingWithElytra => {
-                    if let Ok((mut pose, _)) = clients.get_mut(packet.client) {
-                        pose.0 = Pose::FallFlying;
-                    }
-
-                    // TODO.
+                    start_flying_events.send(StartFlyingWithElytraEvent {
+                        client: packet.client,
+                    });
                 }
             }
         }
     }
 }
+
+/// Applies `f` to the input without triggering change detection unless the
+/// input changed.
+fn set_input<F: FnOnce(&mut PlayerInput)>(input: &mut Mut<PlayerInput>, f: F) {
+    let mut new = **input;
+    f(&mut new);
+    input.set_if_neq(new);
+}
@@ -11,20 +11,40 @@ pub struct ClientSettingsPlugin;
 
 impl Plugin for ClientSettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(EventLoopPreUpdate, handle_client_settings);
+        app.add_event::<SettingsChangedEvent>()
+            .add_systems(EventLoopPreUpdate, handle_client_settings);
     }
 }
 
 /// Component containing client-controlled settings about a client.
-#[derive(Component, Default, Debug)]
+#[derive(Component, Clone, PartialEq, Eq, Default, Debug)]
 pub struct ClientSettings {
     pub locale: Box<str>,
+    /// The view distance requested by the client, or zero if the client hasn't
+    /// sent its settings yet. The view distance actually used is
+    /// [`ViewDistance`], which may be limited by
+    /// [`MaxViewDistance`](crate::view_distance::MaxViewDistance).
+    pub view_distance: u8,
     pub chat_mode: ChatMode,
     pub chat_colors: bool,
     pub enable_text_filtering: bool,
     pub allow_server_listings: bool,
 }
 
+/// Sent when a client changes its settings, including the displayed skin
+/// parts and main hand, which are applied to the player entity.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct SettingsChangedEvent {
+    pub client: Entity,
+    /// The [`ClientSettings`] before the change. The new settings are in the
+    /// client's component.
+    pub old: ClientSettings,
+    /// The displayed skin parts before the change.
+    pub old_model_parts: PlayerModelParts,
+    /// The main arm before the change.
+    pub old_main_arm: player::MainArm,
+}
+
 fn handle_client_settings(
     mut packets: EventReader<PacketEvent>,
     mut clients: Query<(
@@ -33,22 +53,45 @@ fn handle_client_settings(
         &mut PlayerModelParts,
         &mut player::MainArm,
     )>,
+    mut events: EventWriter<SettingsChangedEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<ClientSettingsC2s>() {
             if let Ok((mut view_dist, mut settings, mut model_parts, mut main_arm)) =
                 clients.get_mut(packet.client)
             {
-                view_dist.set_if_neq(ViewDistance::new(pkt.view_distance));
+                let new_settings = ClientSettings {
+                    locale: pkt.locale.into(),
+                    view_distance: pkt.view_distance,
+                    chat_mode: pkt.chat_mode,
+                    chat_colors: pkt.chat_colors,
+                    enable_text_filtering: pkt.enable_text_filtering,
+                    allow_server_listings: pkt.allow_server_listings,
+                };
+
+                if settings.view_distance != new_settings.view_distance {
+                    view_dist.set_if_neq(ViewDistance::new(pkt.view_distance));
+                }
+
+                let old_model_parts = *model_parts;
+                let old_main_arm = *main_arm;
+
+                // Other players see the skin layers and main hand through the player
+                // entity's tracked data.
+                let model_parts_changed = model_parts
+                    .set_if_neq(PlayerModelParts(u8::from(pkt.displayed_skin_parts) as i8));
+                let main_arm_changed = main_arm.set_if_neq(player::MainArm(pkt.main_arm as i8));
 
-                settings.locale = pkt.locale.into();
-                settings.chat_mode = pkt.chat_mode;
-                settings.chat_colors = pkt.chat_colors;
-                settings.enable_text_filtering = pkt.enable_text_filtering;
-                settings.allow_server_listings = pkt.allow_server_listings;
+                let old = settings.replace_if_neq(new_settings);
 
-                model_parts.set_if_neq(PlayerModelParts(u8::from(pkt.displayed_skin_parts) as i8));
-                main_arm.set_if_neq(player::MainArm(pkt.main_arm as i8));
+                if old.is_some() || model_parts_changed || main_arm_changed {
+                    events.send(SettingsChangedEvent {
+                        client: packet.client,
+                        old: old.unwrap_or_else(|| settings.clone()),
+                        old_model_parts,
+                        old_main_arm,
+                    });
+                }
             }
         }
     }
@@ -0,0 +1,230 @@
+//! Loading settings from a TOML file.
+//!
+//! [`ConfigPlugin`] reads a TOML file at startup. Resources registered with
+//! [`AddConfig::add_config`] are deserialized from a table in that file, so
+//! they can be changed without recompiling the server:
+//!
+//! ```toml
+//! [lobby]
+//! max_players = 20
+//! pvp = false
+//! ```
+//!
+//! When watching is enabled, the file is reloaded through the
+//! [`AssetManager`] when it changes on disk, and the registered resources are
+//! replaced with the new values.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use serde::de::DeserializeOwned;
+use toml::Table;
+use tracing::{info, warn};
+
+use crate::asset::{AssetId, AssetManager, AssetReloadedEvent};
+
+/// Reads the config file. Must be added before any [`AddConfig::add_config`]
+/// call, and after [`AssetPlugin`](crate::asset::AssetPlugin) if the file is
+/// watched.
+pub struct ConfigPlugin {
+    path: PathBuf,
+    watch: bool,
+}
+
+impl ConfigPlugin {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            watch: false,
+        }
+    }
+
+    /// Sets whether the registered resources are updated when the file
+    /// changes. Defaults to `false`.
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let table = match fs::read_to_string(&self.path) {
+            Ok(text) => text.parse::<Table>().unwrap_or_else(|e| {
+                panic!("failed to parse config file `{}`: {e}", self.path.display())
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!(
+                    "config file `{}` not found, using the default settings",
+                    self.path.display()
+                );
+                Table::new()
+            }
+            Err(e) => panic!("failed to read config file `{}`: {e}", self.path.display()),
+        };
+
+        let asset = if self.watch {
+            let mut assets = app.world_mut().get_resource_mut::<AssetManager>().expect(
+                "`AssetPlugin` must be added before `ConfigPlugin` to watch the config file",
+            );
+
+            match assets.register(&self.path) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    warn!("failed to watch config file `{}`: {e}", self.path.display());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        app.insert_resource(ConfigFile {
+            path: self.path.clone(),
+            asset,
+            table,
+            sections: vec![],
+            changed: false,
+        });
+
+        if asset.is_some() {
+            app.add_systems(
+                PreUpdate,
+                (read_changed_config, apply_changed_config)
+                    .chain()
+                    .after(crate::asset::reload_changed_assets),
+            );
+        }
+    }
+}
+
+/// Binds resources to tables in the config file.
+pub trait AddConfig {
+    /// Deserializes `T` from the table named `section` in the config file and
+    /// inserts it as a resource. `T::default()` is used if the file has no
+    /// such table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`ConfigPlugin`] wasn't added, or if the table can't be
+    /// deserialized.
+    fn add_config<T: Resource + DeserializeOwned + Default>(&mut self, section: &str) -> &mut Self;
+}
+
+impl AddConfig for App {
+    fn add_config<T: Resource + DeserializeOwned + Default>(&mut self, section: &str) -> &mut Self {
+        let world = self.world_mut();
+
+        let mut config = world
+            .get_resource_mut::<ConfigFile>()
+            .expect("`ConfigPlugin` must be added before `add_config`");
+
+        config
+            .sections
+            .push((section.into(), load_section::<T> as LoadSection));
+
+        let table = config.table.clone();
+
+        if let Err(e) = load_section::<T>(world, section, &table) {
+            panic!("failed to deserialize config section `{section}`: {e}");
+        }
+
+        self
+    }
+}
+
+/// The contents of the config file.
+#[derive(Resource)]
+pub struct ConfigFile {
+    path: PathBuf,
+    asset: Option<AssetId>,
+    table: Table,
+    sections: Vec<(String, LoadSection)>,
+    /// Whether `table` changed since the resources were last loaded.
+    changed: bool,
+}
+
+type LoadSection = fn(&mut World, &str, &Table) -> Result<(), toml::de::Error>;
+
+impl ConfigFile {
+    /// Returns the path of the config file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the parsed contents of the config file. Tables which aren't
+    /// bound to a resource can be read from here.
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+}
+
+fn load_section<T: Resource + DeserializeOwned + Default>(
+    world: &mut World,
+    section: &str,
+    table: &Table,
+) -> Result<(), toml::de::Error> {
+    let value = match table.get(section) {
+        Some(value) => value.clone().try_into()?,
+        None => T::default(),
+    };
+
+    world.insert_resource(value);
+
+    Ok(())
+}
+
+fn read_changed_config(
+    mut config: ResMut<ConfigFile>,
+    mut events: EventReader<AssetReloadedEvent>,
+    assets: Res<AssetManager>,
+) {
+    let Some(asset) = config.asset else {
+        return;
+    };
+
+    if !events.read().any(|event| event.asset == asset) {
+        return;
+    }
+
+    let Some(bytes) = assets.get(asset) else {
+        return;
+    };
+
+    let table = match String::from_utf8_lossy(bytes).parse::<Table>() {
+        Ok(table) => table,
+        Err(e) => {
+            warn!(
+                "failed to parse config file `{}`, keeping the old settings: {e}",
+                config.path.display()
+            );
+            return;
+        }
+    };
+
+    config.table = table;
+    config.changed = true;
+}
+
+fn apply_changed_config(world: &mut World) {
+    let mut config = world.resource_mut::<ConfigFile>();
+
+    if !config.changed {
+        return;
+    }
+
+    config.changed = false;
+
+    let sections = config.sections.clone();
+    let table = config.table.clone();
+
+    for (section, load) in sections {
+        if let Err(e) = load(world, &section, &table) {
+            warn!("failed to deserialize config section `{section}`, keeping the old value: {e}");
+        }
+    }
+
+    info!("reloaded config file");
+}
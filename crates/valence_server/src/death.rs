@@ -0,0 +1,326 @@
+//! Handles clients dying and respawning.
+//!
+//! Clients die when their [`Health`] reaches zero. The death screen is shown,
+//! a [`PlayerDeathEvent`] is sent and the client is marked with [`Dead`]. Once
+//! the client clicks the respawn button, it is moved to its respawn point,
+//! reset according to the [`RespawnRules`] resource and a
+//! [`PlayerRespawnEvent`] is sent.
+//!
+//! Experience is dropped on death unless the [`GameRules::keep_inventory`] of
+//! the client's layer is `true`. The dropped orbs are only simulated if
+//! [`ExperiencePlugin`](crate::experience::ExperiencePlugin) is added too.
+//!
+//! [`DeathPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately. It takes over every [`RequestRespawnEvent`], so servers that
+//! respawn clients themselves shouldn't add it.
+
+use std::borrow::Cow;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::attributes::{EntityAttribute, EntityAttributes};
+use valence_entity::living::Health;
+use valence_entity::player::{Food, Saturation};
+use valence_entity::query::EntityInitQuery;
+use valence_entity::{
+    EntityId, EntityLayerId, EntityStatus, EntityStatuses, Look, OldPosition, Position,
+    UpdateTrackedDataSet,
+};
+use valence_math::DVec3;
+use valence_protocol::packets::play::{DeathMessageS2c, EntitiesDestroyS2c, PlayerRespawnS2c};
+use valence_protocol::text::Text;
+use valence_protocol::{BlockPos, GameMode, GlobalPos, VarInt, WritePacket};
+
+use crate::abilities::PlayerAbilitiesFlags;
+use crate::client::{Client, UpdateClientsSet, Username};
+use crate::event_loop::EventLoopUpdate;
+use crate::experience::{split_experience, Experience, ExperienceOrbBundle};
+use crate::game_rules::GameRules;
+use crate::layer::{ChunkLayer, EntityLayer, Layer, UpdateLayersPreClientSet};
+use crate::spawn::{ClientSpawnQueryReadOnly, DeathLocation, RespawnPosition};
+use crate::status::RequestRespawnEvent;
+use crate::teleport::TeleportState;
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RespawnRules>()
+            .add_event::<PlayerDeathEvent>()
+            .add_event::<PlayerRespawnEvent>()
+            .add_systems(
+                PostUpdate,
+                (
+                    handle_deaths.before(UpdateClientsSet),
+                    respawn_entity_for_viewers
+                        .after(UpdateTrackedDataSet)
+                        .before(UpdateLayersPreClientSet),
+                ),
+            )
+            .add_systems(EventLoopUpdate, handle_respawn_requests);
+    }
+}
+
+/// Controls what happens to clients when they die and respawn.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct RespawnRules {
+    /// Where clients respawn after dying.
+    pub respawn_point: RespawnPoint,
+}
+
+impl Default for RespawnRules {
+    fn default() -> Self {
+        Self {
+            respawn_point: RespawnPoint::Personal,
+        }
+    }
+}
+
+/// The point clients respawn at. See [`RespawnRules::respawn_point`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RespawnPoint {
+    /// Respawn at the client's own [`RespawnPosition`]. Beds and respawn
+    /// anchors take priority this way, since they update the
+    /// [`RespawnPosition`] of the client using them.
+    Personal,
+    /// Respawn every client at the same position, ignoring their
+    /// [`RespawnPosition`].
+    World(RespawnPosition),
+}
+
+/// Marker component for clients that are dead and have not respawned yet.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Dead;
+
+/// Sent when a client's [`Health`] reaches zero.
+///
+/// The death screen shows a generic death message. To show a different one,
+/// call [`Client::kill`] in response to this event.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct PlayerDeathEvent {
+    pub client: Entity,
+    /// Where the client died.
+    pub position: DVec3,
+}
+
+/// Sent when a dead client has respawned.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct PlayerRespawnEvent {
+    pub client: Entity,
+    /// Where the client respawned.
+    pub position: DVec3,
+}
+
+fn handle_deaths(
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &Health,
+            &Username,
+            &Position,
+            &EntityLayerId,
+            &GameMode,
+            &mut Experience,
+            &mut DeathLocation,
+            &mut EntityStatuses,
+        ),
+        (Changed<Health>, Without<Dead>),
+    >,
+    chunk_layers: Query<(&ChunkLayer, Option<&GameRules>)>,
+    mut events: EventWriter<PlayerDeathEvent>,
+    mut commands: Commands,
+) {
+    for (
+        entity,
+        mut client,
+        health,
+        username,
+        pos,
+        layer,
+        game_mode,
+        mut experience,
+        mut death_loc,
+        mut statuses,
+    ) in &mut clients
+    {
+        if health.0 > 0.0 {
+            continue;
+        }
+
+        client.write_packet(&DeathMessageS2c {
+            player_id: VarInt(0),
+            message: Cow::Owned(Text::translate(
+                "death.attack.generic",
+                [Text::text(username.0.clone())],
+            )),
+        });
+
+        // Plays the death sound for everyone else.
+        statuses.trigger(EntityStatus::PlayDeathSoundOrAddProjectileHitParticles);
+
+        let mut keep_inventory = false;
+
+        if let Ok((chunk_layer, rules)) = chunk_layers.get(layer.0) {
+            death_loc.0 = Some((
+                chunk_layer.dimension_type_name().to_string_ident(),
+                BlockPos::from(pos.0),
+            ));
+
+            keep_inventory = rules.is_some_and(|rules| rules.keep_inventory);
+        }
+
+        if !keep_inventory && *game_mode != GameMode::Spectator {
+            // Vanilla drops 7 points per level, capped at 100.
+            let points = (experience.level * 7).min(100);
+
+            for value in split_experience(points) {
+                commands.spawn(ExperienceOrbBundle::new(layer.0, pos.0, value));
+            }
+
+            *experience = Experience::default();
+        }
+
+        commands.entity(entity).insert(Dead);
+
+        events.send(PlayerDeathEvent {
+            client: entity,
+            position: pos.0,
+        });
+    }
+}
+
+fn handle_respawn_requests(
+    mut requests: EventReader<RequestRespawnEvent>,
+    mut clients: Query<
+        (
+            &mut Client,
+            &mut Health,
+            &mut Food,
+            &mut Saturation,
+            &mut Position,
+            &mut Look,
+            &mut TeleportState,
+            &mut Experience,
+            &mut PlayerAbilitiesFlags,
+            &RespawnPosition,
+            &EntityLayerId,
+            ClientSpawnQueryReadOnly,
+            Option<&EntityAttributes>,
+        ),
+        With<Dead>,
+    >,
+    chunk_layers: Query<&ChunkLayer>,
+    rules: Res<RespawnRules>,
+    mut events: EventWriter<PlayerRespawnEvent>,
+    mut commands: Commands,
+) {
+    for request in requests.read() {
+        let Ok((
+            mut client,
+            mut health,
+            mut food,
+            mut saturation,
+            mut pos,
+            mut look,
+            mut teleport_state,
+            mut experience,
+            mut abilities,
+            respawn_pos,
+            layer,
+            spawn,
+            attributes,
+        )) = clients.get_mut(request.client)
+        else {
+            continue;
+        };
+
+        let Ok(chunk_layer) = chunk_layers.get(layer.0) else {
+            continue;
+        };
+
+        let dimension_name = chunk_layer.dimension_type_name();
+
+        let last_death_location = spawn.death_loc.0.as_ref().map(|(id, pos)| GlobalPos {
+            dimension_name: id.as_str_ident().into(),
+            position: *pos,
+        });
+
+        client.write_packet(&PlayerRespawnS2c {
+            dimension_type_name: dimension_name.into(),
+            dimension_name: dimension_name.into(),
+            hashed_seed: spawn.hashed_seed.0,
+            game_mode: *spawn.game_mode,
+            previous_game_mode: spawn.prev_game_mode.0.into(),
+            is_debug: spawn.is_debug.0,
+            is_flat: spawn.is_flat.0,
+            copy_metadata: true,
+            last_death_location,
+            portal_cooldown: VarInt(0),
+        });
+
+        let respawn_pos = match rules.respawn_point {
+            RespawnPoint::Personal => *respawn_pos,
+            RespawnPoint::World(respawn_pos) => respawn_pos,
+        };
+
+        let max_health = attributes
+            .and_then(|attrs| attrs.get_compute_value(EntityAttribute::GenericMaxHealth))
+            .unwrap_or(20.0) as f32;
+
+        health.0 = max_health;
+        *food = Food::default();
+        saturation.0 = 5.0;
+
+        pos.0 = DVec3::new(
+            f64::from(respawn_pos.pos.x) + 0.5,
+            f64::from(respawn_pos.pos.y),
+            f64::from(respawn_pos.pos.z) + 0.5,
+        );
+        look.yaw = respawn_pos.yaw;
+        look.pitch = 0.0;
+
+        // The client forgets its position after respawning, so make sure it is
+        // always teleported.
+        teleport_state.synced_pos = DVec3::NAN;
+
+        // The client also forgets its experience and abilities.
+        experience.set_changed();
+        abilities.set_changed();
+
+        commands.entity(request.client).remove::<Dead>();
+
+        events.send(PlayerRespawnEvent {
+            client: request.client,
+            position: pos.0,
+        });
+    }
+}
+
+/// Respawns the player entity for everyone else so the death animation stops.
+/// This runs after tracked data is updated so viewers see the restored health.
+fn respawn_entity_for_viewers(
+    mut events: EventReader<PlayerRespawnEvent>,
+    clients: Query<(EntityInitQuery, &EntityId, &EntityLayerId, &OldPosition)>,
+    mut entity_layers: Query<&mut EntityLayer>,
+) {
+    for event in events.read() {
+        let Ok((init, entity_id, layer, old_pos)) = clients.get(event.client) else {
+            continue;
+        };
+
+        let Ok(mut entity_layer) = entity_layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        // The layer sends movement to viewers of the new position after this, so the
+        // entity is respawned where viewers last saw it.
+        let mut writer = entity_layer.view_except_writer(old_pos.get(), event.client);
+
+        writer.write_packet(&EntitiesDestroyS2c {
+            entity_ids: Cow::Borrowed(&[VarInt(entity_id.get())]),
+        });
+
+        init.write_init_packets(old_pos.get(), &mut writer);
+    }
+}
@@ -0,0 +1,248 @@
+//! Drawing shapes for debugging.
+//!
+//! Insert [`DebugShapes`] on a client to draw shapes that only that client can
+//! see. Shapes are drawn with dust particles every
+//! [`DebugShapesSettings::interval`] ticks, so they can be added, removed and
+//! toggled at any time. Besides shapes added by hand, the borders of the chunk
+//! the client is in and the hitboxes of nearby entities can be drawn
+//! automatically.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::hitbox::Hitbox;
+use valence_entity::{EntityLayerId, Position};
+use valence_math::{Aabb, DVec3, Vec3};
+use valence_protocol::packets::play::particle_s2c::Particle;
+use valence_protocol::ChunkPos;
+use valence_server_common::Server;
+
+use crate::client::{Client, FlushPacketsSet, VisibleEntityLayers};
+
+pub struct DebugShapesPlugin;
+
+impl Plugin for DebugShapesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugShapesSettings>()
+            .add_systems(PostUpdate, draw_debug_shapes.before(FlushPacketsSet));
+    }
+}
+
+/// Global configuration for drawing [`DebugShapes`].
+#[derive(Resource, Clone, Debug)]
+pub struct DebugShapesSettings {
+    /// The number of ticks between redraws. Particles fade after a short
+    /// while, so shapes flicker if this is too high.
+    pub interval: u64,
+    /// The distance between particles along lines, in blocks.
+    pub spacing: f64,
+    /// The maximum distance from the client at which hitboxes are drawn.
+    pub hitbox_distance: f64,
+}
+
+impl Default for DebugShapesSettings {
+    fn default() -> Self {
+        Self {
+            interval: 10,
+            spacing: 0.25,
+            hitbox_distance: 16.0,
+        }
+    }
+}
+
+/// A shape to draw with [`DebugShapes`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DebugShape {
+    Point(DVec3),
+    Line {
+        from: DVec3,
+        to: DVec3,
+    },
+    /// The edges of a box.
+    Aabb(Aabb),
+}
+
+/// Shapes drawn for a single client. Colors are RGB values in `0.0..=1.0`.
+#[derive(Component, Clone, Default, Debug)]
+pub struct DebugShapes {
+    /// Whether to draw the borders of the chunk the client is in.
+    pub chunk_borders: bool,
+    /// Whether to draw the hitboxes of entities near the client.
+    pub hitboxes: bool,
+    shapes: Vec<(DebugShape, Vec3)>,
+}
+
+impl DebugShapes {
+    pub const CHUNK_BORDER_COLOR: Vec3 = Vec3::new(1.0, 1.0, 0.0);
+    pub const HITBOX_COLOR: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shapes added to this client and their colors.
+    pub fn shapes(&self) -> &[(DebugShape, Vec3)] {
+        &self.shapes
+    }
+
+    pub fn add(&mut self, shape: DebugShape, color: Vec3) {
+        self.shapes.push((shape, color));
+    }
+
+    pub fn point(&mut self, position: DVec3, color: Vec3) {
+        self.add(DebugShape::Point(position), color);
+    }
+
+    pub fn line(&mut self, from: DVec3, to: DVec3, color: Vec3) {
+        self.add(DebugShape::Line { from, to }, color);
+    }
+
+    pub fn aabb(&mut self, aabb: Aabb, color: Vec3) {
+        self.add(DebugShape::Aabb(aabb), color);
+    }
+
+    /// Adds lines between consecutive points, such as the nodes of a path.
+    pub fn path<I: IntoIterator<Item = DVec3>>(&mut self, points: I, color: Vec3) {
+        let mut points = points.into_iter();
+
+        let Some(mut prev) = points.next() else {
+            return;
+        };
+
+        for point in points {
+            self.line(prev, point, color);
+            prev = point;
+        }
+    }
+
+    /// Adds a line of `length` blocks from `origin` in `direction`, such as a
+    /// raycast.
+    pub fn ray(&mut self, origin: DVec3, direction: DVec3, length: f64, color: Vec3) {
+        self.line(
+            origin,
+            origin + direction.normalize_or_zero() * length,
+            color,
+        );
+    }
+
+    /// Removes all shapes added to this client. This doesn't affect
+    /// [`DebugShapes::chunk_borders`] and [`DebugShapes::hitboxes`].
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+}
+
+/// Returns the 12 edges of `aabb`.
+fn aabb_edges(aabb: Aabb) -> [(DVec3, DVec3); 12] {
+    let min = aabb.min();
+    let max = aabb.max();
+
+    let corner = |x: bool, y: bool, z: bool| {
+        DVec3::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        )
+    };
+
+    let mut edges = [(DVec3::ZERO, DVec3::ZERO); 12];
+    let mut i = 0;
+
+    for a in [false, true] {
+        for b in [false, true] {
+            edges[i] = (corner(false, a, b), corner(true, a, b));
+            edges[i + 1] = (corner(a, false, b), corner(a, true, b));
+            edges[i + 2] = (corner(a, b, false), corner(a, b, true));
+            i += 3;
+        }
+    }
+
+    edges
+}
+
+fn draw_line(client: &mut Client, from: DVec3, to: DVec3, particle: &Particle, spacing: f64) {
+    let steps = (from.distance(to) / spacing).ceil().max(1.0) as u32;
+
+    for i in 0..=steps {
+        let position = from.lerp(to, f64::from(i) / f64::from(steps));
+        client.play_particle(particle, true, position, Vec3::ZERO, 0.0, 1);
+    }
+}
+
+fn draw_shape(client: &mut Client, shape: DebugShape, color: Vec3, spacing: f64) {
+    let particle = Particle::Dust {
+        rgb: color,
+        scale: 1.0,
+    };
+
+    match shape {
+        DebugShape::Point(position) => {
+            client.play_particle(&particle, true, position, Vec3::ZERO, 0.0, 1);
+        }
+        DebugShape::Line { from, to } => draw_line(client, from, to, &particle, spacing),
+        DebugShape::Aabb(aabb) => {
+            for (from, to) in aabb_edges(aabb) {
+                draw_line(client, from, to, &particle, spacing);
+            }
+        }
+    }
+}
+
+fn draw_debug_shapes(
+    mut clients: Query<(
+        Entity,
+        &mut Client,
+        Ref<DebugShapes>,
+        &Position,
+        &VisibleEntityLayers,
+    )>,
+    entities: Query<(Entity, &Hitbox, &Position, &EntityLayerId)>,
+    settings: Res<DebugShapesSettings>,
+    server: Res<Server>,
+) {
+    let redraw = server.current_tick() % settings.interval.max(1) == 0;
+
+    for (self_entity, mut client, shapes, pos, visible_layers) in &mut clients {
+        if !redraw && !shapes.is_changed() {
+            continue;
+        }
+
+        for &(shape, color) in &shapes.shapes {
+            draw_shape(&mut client, shape, color, settings.spacing);
+        }
+
+        if shapes.chunk_borders {
+            let chunk = ChunkPos::from(pos.0);
+            let min = DVec3::new(
+                f64::from(chunk.x * 16),
+                pos.0.y - 8.0,
+                f64::from(chunk.z * 16),
+            );
+            let border = Aabb::new(min, min + DVec3::new(16.0, 16.0, 16.0));
+
+            draw_shape(
+                &mut client,
+                DebugShape::Aabb(border),
+                DebugShapes::CHUNK_BORDER_COLOR,
+                settings.spacing,
+            );
+        }
+
+        if shapes.hitboxes {
+            for (entity, hitbox, entity_pos, layer) in &entities {
+                if entity == self_entity
+                    || !visible_layers.0.contains(&layer.0)
+                    || entity_pos.0.distance(pos.0) > settings.hitbox_distance
+                {
+                    continue;
+                }
+
+                draw_shape(
+                    &mut client,
+                    DebugShape::Aabb(hitbox.get()),
+                    DebugShapes::HITBOX_COLOR,
+                    settings.spacing,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Per-client filters for particles and sounds.
+//!
+//! Particles and sounds played with [`ChunkLayer::play_particle`] and
+//! [`ChunkLayer::play_sound`] are sent to every client in view by default.
+//! Adding a [`ParticleFilter`] or [`SoundFilter`] to a client skips the ones it
+//! doesn't want, which saves traffic for clients that set particles to minimal
+//! or muted some sounds.
+//!
+//! The other way around, an [`EffectScope`] passed to
+//! [`ChunkLayer::play_particle_scoped`] or [`ChunkLayer::play_sound_scoped`]
+//! limits which clients a single effect is sent to.
+//!
+//! [`ChunkLayer::play_particle`]: crate::ChunkLayer::play_particle
+//! [`ChunkLayer::play_sound`]: crate::ChunkLayer::play_sound
+//! [`ChunkLayer::play_particle_scoped`]: crate::ChunkLayer::play_particle_scoped
+//! [`ChunkLayer::play_sound_scoped`]: crate::ChunkLayer::play_sound_scoped
+
+use bevy_ecs::prelude::*;
+use valence_protocol::sound::SoundCategory;
+use valence_protocol::BlockPos;
+
+/// Limits which of the clients in view receive a particle or sound. The
+/// default sends it to all of them.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct EffectScope {
+    /// Only clients within this many blocks of the effect receive it. `None`
+    /// sends it to every client in view.
+    pub radius: Option<u32>,
+    /// A client which doesn't receive the effect, such as the player causing
+    /// it.
+    pub except: Option<Entity>,
+}
+
+impl EffectScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_radius(mut self, radius: u32) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    pub fn with_except(mut self, except: Entity) -> Self {
+        self.except = Some(except);
+        self
+    }
+
+    /// Returns whether an effect at `center` is sent to `client` at `pos`.
+    pub fn allows(&self, client: Entity, pos: BlockPos, center: BlockPos) -> bool {
+        self.except != Some(client) && in_distance(pos, center, self.radius.map(f64::from))
+    }
+}
+
+/// A [`Component`] for clients limiting the particles they're sent.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct ParticleFilter {
+    /// Whether particles are sent to the client at all.
+    pub enabled: bool,
+    /// The greatest distance from the client particles are sent from, in
+    /// blocks. `None` sends particles from anywhere in view.
+    pub max_distance: Option<f64>,
+}
+
+impl Default for ParticleFilter {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_distance: None,
+        }
+    }
+}
+
+impl ParticleFilter {
+    /// Returns whether a particle at `center` is sent to a client at `pos`.
+    pub fn allows(&self, pos: BlockPos, center: BlockPos) -> bool {
+        self.enabled && in_distance(pos, center, self.max_distance)
+    }
+}
+
+/// A [`Component`] for clients limiting the sounds they're sent.
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct SoundFilter {
+    /// A bit for every muted [`SoundCategory`].
+    muted: u16,
+    /// The greatest distance from the client sounds are sent from, in blocks.
+    /// `None` sends sounds from anywhere in view.
+    pub max_distance: Option<f64>,
+}
+
+impl SoundFilter {
+    /// Stops sounds in `category` from being sent.
+    pub fn mute(&mut self, category: SoundCategory) {
+        self.muted |= 1 << category as u16;
+    }
+
+    /// Sends sounds in `category` again.
+    pub fn unmute(&mut self, category: SoundCategory) {
+        self.muted &= !(1 << category as u16);
+    }
+
+    pub fn is_muted(&self, category: SoundCategory) -> bool {
+        self.muted & (1 << category as u16) != 0
+    }
+
+    /// Returns whether a sound in `category` at `center` is sent to a client at
+    /// `pos`.
+    pub fn allows(&self, pos: BlockPos, center: BlockPos, category: SoundCategory) -> bool {
+        !self.is_muted(category) && in_distance(pos, center, self.max_distance)
+    }
+}
+
+fn in_distance(p0: BlockPos, p1: BlockPos, max_distance: Option<f64>) -> bool {
+    let Some(max) = max_distance else {
+        return true;
+    };
+
+    let dist_squared = f64::from(p1.x - p0.x).powi(2)
+        + f64::from(p1.y - p0.y).powi(2)
+        + f64::from(p1.z - p0.z).powi(2);
+
+    dist_squared <= max * max
+}
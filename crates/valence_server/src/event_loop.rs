@@ -6,10 +6,10 @@ use bevy_ecs::prelude::*;
 use bevy_ecs::schedule::ScheduleLabel;
 use bevy_ecs::system::SystemState;
 use bytes::Bytes;
-use tracing::{debug, warn};
+use tracing::{debug, trace, warn};
 use valence_protocol::{Decode, Packet};
 
-use crate::client::Client;
+use crate::client::{Client, ClientSpan};
 
 pub struct EventLoopPlugin;
 
@@ -76,6 +76,7 @@ impl PacketEvent {
                     }
 
                     warn!(
+                        client = ?self.client,
                         "missed {} bytes while decoding packet {} (ID = {})",
                         r.len(),
                         P::NAME,
@@ -84,7 +85,11 @@ impl PacketEvent {
                     debug!("complete packet after partial decode: {pkt:?}");
                 }
                 Err(e) => {
-                    warn!("failed to decode packet with ID of {}: {e:#}", P::ID);
+                    warn!(
+                        client = ?self.client,
+                        "failed to decode packet with ID of {}: {e:#}",
+                        P::ID
+                    );
                 }
             }
         }
@@ -104,7 +109,7 @@ fn run_event_loop_schedules(world: &mut World) {
 fn run_event_loop(
     world: &mut World,
     state: &mut SystemState<(
-        Query<(Entity, &mut Client)>,
+        Query<(Entity, &mut Client, Option<&ClientSpan>)>,
         EventWriter<PacketEvent>,
         Commands,
     )>,
@@ -114,9 +119,13 @@ fn run_event_loop(
 
     let (mut clients, mut event_writer, mut commands) = state.get_mut(world);
 
-    for (entity, mut client) in &mut clients {
+    for (entity, mut client, span) in &mut clients {
+        let _span = span.map(|span| span.0.enter());
+
         match client.connection_mut().try_recv() {
             Ok(Some(pkt)) => {
+                trace!(id = pkt.id, "received packet");
+
                 event_writer.send(PacketEvent {
                     client: entity,
                     timestamp: pkt.timestamp,
@@ -148,9 +157,13 @@ fn run_event_loop(
         check_again.retain_mut(|(entity, remaining)| {
             debug_assert!(*remaining > 0);
 
-            if let Ok((_, mut client)) = clients.get_mut(*entity) {
+            if let Ok((_, mut client, span)) = clients.get_mut(*entity) {
+                let _span = span.map(|span| span.0.enter());
+
                 match client.connection_mut().try_recv() {
                     Ok(Some(pkt)) => {
+                        trace!(id = pkt.id, "received packet");
+
                         event_writer.send(PacketEvent {
                             client: *entity,
                             timestamp: pkt.timestamp,
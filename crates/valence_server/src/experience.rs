@@ -0,0 +1,336 @@
+//! Player experience and experience orbs.
+//!
+//! Every client has an [`Experience`] component that is kept in sync with its
+//! experience bar. Orbs spawned with [`ExperienceOrbBundle`] are pulled towards
+//! nearby clients and add their value to the [`Experience`] of the client that
+//! picks them up, after which a [`PickupExperienceEvent`] is sent.
+//!
+//! [`ExperiencePlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use valence_entity::entity::NoGravity;
+use valence_entity::experience_orb::ExperienceOrbEntityBundle;
+use valence_entity::{EntityId, EntityLayerId, ObjectData, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::{ExperienceBarUpdateS2c, ItemPickupAnimationS2c};
+use valence_protocol::sound::{Sound, SoundCategory};
+use valence_protocol::{GameMode, VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::{Client, UpdateClientsSet};
+use crate::layer::{EntityLayer, Layer, UpdateLayersPreClientSet};
+
+pub struct ExperiencePlugin;
+
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickupExperienceEvent>().add_systems(
+            PostUpdate,
+            (
+                (tick_experience_orbs, pickup_experience_orbs)
+                    .chain()
+                    .before(UpdateLayersPreClientSet),
+                update_experience_bar
+                    .in_set(UpdateClientsSet)
+                    .after(crate::spawn::initial_join),
+            ),
+        );
+    }
+}
+
+/// The experience of a client, as shown in the experience bar.
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct Experience {
+    /// The current experience level.
+    pub level: i32,
+    /// The progress towards the next level in `0.0..1.0`.
+    pub progress: f32,
+    /// The total amount of experience points collected.
+    pub total: i32,
+}
+
+impl Experience {
+    /// Returns the number of points needed to go from `level` to the next
+    /// level.
+    pub const fn points_to_next_level(level: i32) -> i32 {
+        if level >= 30 {
+            112 + (level - 30) * 9
+        } else if level >= 15 {
+            37 + (level - 15) * 5
+        } else {
+            7 + level * 2
+        }
+    }
+
+    /// Adds `points` experience points, leveling up as needed.
+    pub fn add_points(&mut self, points: i32) {
+        self.progress += points as f32 / Self::points_to_next_level(self.level) as f32;
+        self.total = self.total.saturating_add(points).max(0);
+
+        while self.progress < 0.0 {
+            let prev = self.progress * Self::points_to_next_level(self.level) as f32;
+
+            if self.level > 0 {
+                self.level -= 1;
+                self.progress = 1.0 + prev / Self::points_to_next_level(self.level) as f32;
+            } else {
+                self.progress = 0.0;
+            }
+        }
+
+        while self.progress >= 1.0 {
+            self.progress = (self.progress - 1.0) * Self::points_to_next_level(self.level) as f32;
+            self.level += 1;
+            self.progress /= Self::points_to_next_level(self.level) as f32;
+        }
+    }
+}
+
+/// Sent when a client picks up an experience orb.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PickupExperienceEvent {
+    pub client: Entity,
+    pub orb: Entity,
+    /// The number of experience points the orb was worth.
+    pub value: i32,
+}
+
+/// Simulation state for experience orbs spawned with
+/// [`ExperienceOrbBundle`]. The value of the orb is stored in the entity's
+/// [`ObjectData`].
+#[derive(Component, Copy, Clone, PartialEq, Default, Debug)]
+pub struct ExperienceOrb {
+    /// The number of ticks this orb has existed for. Orbs are despawned once
+    /// this reaches [`ExperienceOrb::MAX_AGE`].
+    pub age: u32,
+    /// The current velocity of the orb in blocks per tick.
+    pub velocity: DVec3,
+}
+
+impl ExperienceOrb {
+    /// The number of ticks an orb lives for before despawning.
+    pub const MAX_AGE: u32 = 6000;
+    /// The distance in blocks from which orbs are pulled towards players.
+    pub const ATTRACT_RANGE: f64 = 8.0;
+}
+
+/// Bundle for spawning an experience orb that is attracted to and picked up by
+/// nearby clients.
+///
+/// Valence does not simulate block collisions, so orbs float in place until
+/// they are pulled towards a client.
+#[derive(Bundle)]
+pub struct ExperienceOrbBundle {
+    pub orb: ExperienceOrb,
+    pub entity: ExperienceOrbEntityBundle,
+}
+
+impl ExperienceOrbBundle {
+    pub fn new<P: Into<DVec3>>(layer: Entity, position: P, value: i32) -> Self {
+        Self {
+            orb: ExperienceOrb::default(),
+            entity: ExperienceOrbEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(position),
+                object_data: ObjectData(value),
+                entity_no_gravity: NoGravity(true),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Splits `points` into the orb values vanilla would use, largest first.
+/// Spawning one orb per returned value avoids flooding clients with
+/// single-point orbs.
+pub fn split_experience(mut points: i32) -> impl Iterator<Item = i32> {
+    std::iter::from_fn(move || {
+        if points <= 0 {
+            return None;
+        }
+
+        let value = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3]
+            .into_iter()
+            .find(|&v| points >= v)
+            .unwrap_or(1);
+
+        points -= value;
+
+        Some(value)
+    })
+}
+
+fn tick_experience_orbs(
+    mut orbs: Query<
+        (Entity, &mut ExperienceOrb, &mut Position, &EntityLayerId),
+        Without<Despawned>,
+    >,
+    clients: Query<
+        (&Position, &EntityLayerId, &GameMode),
+        (With<Experience>, Without<ExperienceOrb>),
+    >,
+    mut commands: Commands,
+) {
+    for (entity, mut orb, mut pos, layer) in &mut orbs {
+        orb.age += 1;
+
+        if orb.age >= ExperienceOrb::MAX_AGE {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        let target = clients
+            .iter()
+            .filter(|(_, client_layer, game_mode)| {
+                client_layer.0 == layer.0 && **game_mode != GameMode::Spectator
+            })
+            // Pull towards the middle of the player's body.
+            .map(|(client_pos, _, _)| client_pos.0 + DVec3::new(0.0, 0.81, 0.0) - pos.0)
+            .filter(|diff| diff.length_squared() < ExperienceOrb::ATTRACT_RANGE.powi(2))
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+
+        if let Some(diff) = target {
+            let strength = 1.0 - diff.length() / ExperienceOrb::ATTRACT_RANGE;
+            orb.velocity += diff.normalize_or_zero() * strength * strength * 0.1;
+        }
+
+        if orb.velocity != DVec3::ZERO {
+            pos.0 += orb.velocity;
+            orb.velocity *= 0.98;
+
+            if orb.velocity.length_squared() < 1e-6 {
+                orb.velocity = DVec3::ZERO;
+            }
+        }
+    }
+}
+
+/// Returns whether an orb at `orb_pos` touches a player standing at
+/// `player_pos`.
+fn touches_player(orb_pos: DVec3, player_pos: DVec3) -> bool {
+    // Half the orb's width plus half the player's width.
+    const HORIZONTAL: f64 = 0.25 + 0.3;
+    // Orb and player heights.
+    const ORB_HEIGHT: f64 = 0.5;
+    const PLAYER_HEIGHT: f64 = 1.8;
+
+    let diff = orb_pos - player_pos;
+
+    diff.x.abs() <= HORIZONTAL
+        && diff.z.abs() <= HORIZONTAL
+        && diff.y >= -ORB_HEIGHT
+        && diff.y <= PLAYER_HEIGHT
+}
+
+fn pickup_experience_orbs(
+    orbs: Query<
+        (Entity, &Position, &EntityLayerId, &EntityId, &ObjectData),
+        (With<ExperienceOrb>, Without<Despawned>),
+    >,
+    mut clients: Query<
+        (
+            Entity,
+            &mut Client,
+            &mut Experience,
+            &Position,
+            &EntityLayerId,
+            &EntityId,
+            &GameMode,
+        ),
+        Without<ExperienceOrb>,
+    >,
+    mut layers: Query<&mut EntityLayer>,
+    mut events: EventWriter<PickupExperienceEvent>,
+    mut commands: Commands,
+) {
+    let mut rng = rand::thread_rng();
+    let mut picked_up = vec![];
+
+    for (
+        client_entity,
+        mut client,
+        mut experience,
+        client_pos,
+        client_layer,
+        client_id,
+        game_mode,
+    ) in &mut clients
+    {
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        // Like vanilla, clients pick up at most one orb at a time.
+        let Some((orb_entity, orb_pos, _, orb_id, value)) =
+            orbs.iter().find(|(orb_entity, orb_pos, orb_layer, _, _)| {
+                orb_layer.0 == client_layer.0
+                    && touches_player(orb_pos.0, client_pos.0)
+                    && !picked_up.contains(orb_entity)
+            })
+        else {
+            continue;
+        };
+
+        let value = value.0;
+
+        if let Ok(mut layer) = layers.get_mut(client_layer.0) {
+            layer
+                .view_except_writer(orb_pos.0, client_entity)
+                .write_packet(&ItemPickupAnimationS2c {
+                    collected_entity_id: VarInt(orb_id.get()),
+                    collector_entity_id: VarInt(client_id.get()),
+                    pickup_item_count: VarInt(1),
+                });
+        }
+
+        // The collecting client knows itself as entity 0.
+        client.write_packet(&ItemPickupAnimationS2c {
+            collected_entity_id: VarInt(orb_id.get()),
+            collector_entity_id: VarInt(0),
+            pickup_item_count: VarInt(1),
+        });
+
+        client.play_sound(
+            Sound::EntityExperienceOrbPickup,
+            SoundCategory::Player,
+            client_pos.0,
+            0.1,
+            0.5 * ((rng.gen::<f32>() - rng.gen::<f32>()) * 0.7 + 1.8),
+        );
+
+        let old_level = experience.level;
+        experience.add_points(value);
+
+        if experience.level > old_level && experience.level % 5 == 0 {
+            client.play_sound(
+                Sound::EntityPlayerLevelup,
+                SoundCategory::Player,
+                client_pos.0,
+                0.75,
+                1.0,
+            );
+        }
+
+        commands.entity(orb_entity).insert(Despawned);
+        picked_up.push(orb_entity);
+
+        events.send(PickupExperienceEvent {
+            client: client_entity,
+            orb: orb_entity,
+            value,
+        });
+    }
+}
+
+fn update_experience_bar(mut clients: Query<(&mut Client, &Experience), Changed<Experience>>) {
+    for (mut client, experience) in &mut clients {
+        client.write_packet(&ExperienceBarUpdateS2c {
+            bar: experience.progress,
+            level: VarInt(experience.level),
+            total_xp: VarInt(experience.total),
+        });
+    }
+}
@@ -0,0 +1,463 @@
+//! Explosions.
+//!
+//! [`ChunkLayer::explode`] destroys blocks the same way vanilla explosions do
+//! and returns the destroyed blocks so that drops can be handled. Later in the
+//! tick, entities in the layer are damaged and knocked back depending on how
+//! exposed they are to the explosion, and nearby clients are sent the
+//! explosion, which plays its sound and particles. An [`ExplosionEvent`] is
+//! sent afterwards.
+//!
+//! [`ExplosionPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately before using [`ChunkLayer::explode`].
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::hitbox::Hitbox;
+use valence_entity::living::Health;
+use valence_entity::{EntityLayerId, Position, UpdateTrackedDataSet, Velocity};
+use valence_math::{Aabb, DVec3, IVec3, Vec3};
+use valence_protocol::block::BlockKind;
+use valence_protocol::packets::play::ExplosionS2c;
+use valence_protocol::{BlockPos, BlockState, GameMode, WritePacket};
+
+use crate::client::{Client, VisibleChunkLayer};
+use crate::layer::chunk::Block;
+use crate::layer::ChunkLayer;
+
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExplosionEvent>()
+            .add_systems(PostUpdate, affect_entities.before(UpdateTrackedDataSet));
+    }
+}
+
+/// Clients farther away than this from an explosion are not sent it.
+const MAX_CLIENT_DISTANCE: f64 = 64.0;
+
+/// Configures what an explosion affects.
+#[derive(Copy, Clone, Debug)]
+pub struct ExplosionOptions {
+    /// Whether blocks are destroyed.
+    pub destroy_blocks: bool,
+    /// Whether the [`Health`] of entities is reduced.
+    pub damage_entities: bool,
+    /// Whether entities are knocked back.
+    pub knockback: bool,
+    /// Returns how well a block resists explosions.
+    pub blast_resistance: fn(BlockState) -> f32,
+}
+
+impl Default for ExplosionOptions {
+    fn default() -> Self {
+        Self {
+            destroy_blocks: true,
+            damage_entities: true,
+            knockback: true,
+            blast_resistance: default_blast_resistance,
+        }
+    }
+}
+
+/// The result of [`ChunkLayer::explode`].
+#[derive(Clone, Debug)]
+pub struct Explosion {
+    pub position: DVec3,
+    /// The power of the explosion. TNT has a power of 4.
+    pub power: f32,
+    pub options: ExplosionOptions,
+    /// The destroyed blocks as they were before the explosion.
+    pub destroyed_blocks: Vec<(BlockPos, Block)>,
+}
+
+/// An entity hit by an explosion.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ExplosionHit {
+    pub entity: Entity,
+    /// The damage dealt. This is computed even if
+    /// [`ExplosionOptions::damage_entities`] is `false`.
+    pub damage: f32,
+    /// The velocity added to the entity, in m/s.
+    pub knockback: Vec3,
+}
+
+/// Sent after an explosion has affected entities.
+#[derive(Event, Clone, Debug)]
+pub struct ExplosionEvent {
+    /// The [`ChunkLayer`] the explosion happened in.
+    pub layer: Entity,
+    pub explosion: Explosion,
+    pub hits: Vec<ExplosionHit>,
+}
+
+impl ChunkLayer {
+    /// Causes an explosion of the given power at `position`.
+    ///
+    /// Blocks are destroyed immediately. Entities on the [`EntityLayerId`] of
+    /// this layer are affected later in the tick by [`ExplosionPlugin`].
+    pub fn explode<P: Into<DVec3>>(
+        &mut self,
+        position: P,
+        power: f32,
+        options: ExplosionOptions,
+    ) -> Explosion {
+        let position = position.into();
+
+        let mut explosion = Explosion {
+            position,
+            power,
+            options,
+            destroyed_blocks: vec![],
+        };
+
+        if options.destroy_blocks {
+            for pos in self.blocks_in_blast(position, power, options.blast_resistance) {
+                if let Some(block) = self.set_block(pos, BlockState::AIR) {
+                    if !block.state.is_air() {
+                        explosion.destroyed_blocks.push((pos, block));
+                    }
+                }
+            }
+        }
+
+        self.explosions.push(explosion.clone());
+
+        explosion
+    }
+
+    /// Casts rays from the center of the explosion that lose strength as they
+    /// go through blocks, like vanilla.
+    fn blocks_in_blast(
+        &self,
+        position: DVec3,
+        power: f32,
+        blast_resistance: fn(BlockState) -> f32,
+    ) -> BTreeSet<BlockPos> {
+        const STEP: f64 = 0.3;
+
+        let mut blocks = BTreeSet::new();
+
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    if !(x == 0 || x == 15 || y == 0 || y == 15 || z == 0 || z == 15) {
+                        continue;
+                    }
+
+                    let direction =
+                        (DVec3::new(f64::from(x), f64::from(y), f64::from(z)) / 15.0 * 2.0 - 1.0)
+                            .normalize();
+
+                    let mut strength = power * (0.7 + rand::random::<f32>() * 0.6);
+                    let mut pos = position;
+
+                    while strength > 0.0 {
+                        let block_pos = BlockPos::from(pos);
+
+                        let Some(block) = self.block(block_pos) else {
+                            break;
+                        };
+
+                        if !block.state.is_air() {
+                            strength -= (blast_resistance(block.state) + 0.3) * 0.3;
+
+                            if strength > 0.0 {
+                                blocks.insert(block_pos);
+                            }
+                        }
+
+                        pos += direction * STEP;
+                        strength -= 0.225_000_01;
+                    }
+                }
+            }
+        }
+
+        blocks
+    }
+
+    /// Returns the fraction of `aabb` that can be seen from `position` without
+    /// blocks in the way, between `0.0` and `1.0`.
+    pub fn exposure(&self, position: DVec3, aabb: Aabb) -> f32 {
+        let size = aabb.max() - aabb.min();
+        let step = 1.0 / (size * 2.0 + 1.0);
+
+        // Center the samples horizontally.
+        let offset_x = (1.0 - (1.0 / step.x).floor() * step.x) / 2.0;
+        let offset_z = (1.0 - (1.0 / step.z).floor() * step.z) / 2.0;
+
+        let mut visible = 0;
+        let mut total = 0;
+
+        let mut x = 0.0;
+        while x <= 1.0 {
+            let mut y = 0.0;
+            while y <= 1.0 {
+                let mut z = 0.0;
+                while z <= 1.0 {
+                    let sample = aabb.min() + size * DVec3::new(x, y, z);
+                    let sample = DVec3::new(sample.x + offset_x, sample.y, sample.z + offset_z);
+
+                    if !self.segment_blocked(sample, position) {
+                        visible += 1;
+                    }
+
+                    total += 1;
+                    z += step.z;
+                }
+                y += step.y;
+            }
+            x += step.x;
+        }
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        visible as f32 / total as f32
+    }
+
+    /// Returns whether the collision shape of a block intersects the line
+    /// segment from `from` to `to`.
+    fn segment_blocked(&self, from: DVec3, to: DVec3) -> bool {
+        let direction = to - from;
+
+        let mut block = from.floor().as_ivec3();
+        let end = to.floor().as_ivec3();
+
+        let mut step = IVec3::ZERO;
+        let mut t_max = DVec3::INFINITY;
+        let mut t_delta = DVec3::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = (f64::from(block[axis] + 1) - from[axis]) / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = (f64::from(block[axis]) - from[axis]) / direction[axis];
+            } else {
+                continue;
+            }
+
+            t_delta[axis] = (1.0 / direction[axis]).abs();
+        }
+
+        loop {
+            let pos = BlockPos::new(block.x, block.y, block.z);
+
+            if let Some(state) = self.block(pos).map(|b| b.state) {
+                let offset = DVec3::new(f64::from(pos.x), f64::from(pos.y), f64::from(pos.z));
+
+                let hit = state.collision_shapes().any(|shape| {
+                    (shape + offset)
+                        .ray_intersection(from, direction)
+                        .is_some_and(|[near, _]| near <= 1.0)
+                });
+
+                if hit {
+                    return true;
+                }
+            }
+
+            if block == end {
+                return false;
+            }
+
+            let axis = if t_max.x < t_max.y && t_max.x < t_max.z {
+                0
+            } else if t_max.y < t_max.z {
+                1
+            } else {
+                2
+            };
+
+            if t_max[axis] > 1.0 {
+                return false;
+            }
+
+            block[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+}
+
+/// Returns vanilla's blast resistance for common blocks and an approximation
+/// based on the name of the block for the rest.
+pub fn default_blast_resistance(state: BlockState) -> f32 {
+    let kind = state.to_kind();
+
+    match kind {
+        BlockKind::Bedrock
+        | BlockKind::Barrier
+        | BlockKind::Light
+        | BlockKind::EndPortal
+        | BlockKind::EndPortalFrame
+        | BlockKind::EndGateway
+        | BlockKind::MovingPiston
+        | BlockKind::CommandBlock
+        | BlockKind::ChainCommandBlock
+        | BlockKind::RepeatingCommandBlock
+        | BlockKind::StructureBlock
+        | BlockKind::Jigsaw => 3_600_000.0,
+        BlockKind::Obsidian
+        | BlockKind::CryingObsidian
+        | BlockKind::RespawnAnchor
+        | BlockKind::AncientDebris
+        | BlockKind::NetheriteBlock
+        | BlockKind::EnchantingTable
+        | BlockKind::Anvil
+        | BlockKind::ChippedAnvil
+        | BlockKind::DamagedAnvil
+        | BlockKind::ReinforcedDeepslate => 1200.0,
+        BlockKind::EnderChest => 600.0,
+        BlockKind::Water | BlockKind::Lava => 100.0,
+        BlockKind::EndStone => 9.0,
+        BlockKind::IronBlock | BlockKind::IronBars | BlockKind::Bell => 6.0,
+        BlockKind::Tnt => 0.0,
+        _ => {
+            if state.is_air() || !state.blocks_motion() {
+                return 0.0;
+            }
+
+            let name = kind.to_str();
+            let has = |part: &str| name.contains(part);
+
+            if name.ends_with("_ore") {
+                3.0
+            } else if has("stone") || has("deepslate") || has("brick") || has("cobble") {
+                6.0
+            } else if has("glass") {
+                0.3
+            } else if has("leaves") {
+                0.2
+            } else if has("wool") {
+                0.8
+            } else if has("dirt") || has("sand") || has("gravel") || has("grass") {
+                0.5
+            } else {
+                3.0
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn affect_entities(
+    mut layers: Query<(Entity, &mut ChunkLayer)>,
+    mut entities: Query<(
+        Entity,
+        &Position,
+        &EntityLayerId,
+        &Hitbox,
+        &mut Velocity,
+        Option<&mut Health>,
+        Option<&GameMode>,
+    )>,
+    mut clients: Query<(Entity, &mut Client, &Position, &VisibleChunkLayer)>,
+    mut events: EventWriter<ExplosionEvent>,
+) {
+    for (layer, mut chunk_layer) in &mut layers {
+        if chunk_layer.explosions.is_empty() {
+            continue;
+        }
+
+        for explosion in std::mem::take(&mut chunk_layer.explosions) {
+            let center = explosion.position;
+            let radius = f64::from(explosion.power) * 2.0;
+
+            let mut hits = vec![];
+
+            for (entity, pos, entity_layer, hitbox, mut velocity, health, game_mode) in
+                &mut entities
+            {
+                if entity_layer.0 != layer
+                    || matches!(game_mode, Some(GameMode::Creative | GameMode::Spectator))
+                {
+                    continue;
+                }
+
+                let distance = pos.0.distance(center) / radius;
+
+                if distance > 1.0 {
+                    continue;
+                }
+
+                // Entities are pushed away from the explosion towards their eyes.
+                let aabb = hitbox.get();
+                let eyes = DVec3::new(
+                    pos.0.x,
+                    pos.0.y + (aabb.max().y - aabb.min().y) * 0.85,
+                    pos.0.z,
+                );
+                let direction = (eyes - center).normalize_or_zero();
+
+                let exposure = f64::from(chunk_layer.exposure(center, aabb));
+                let impact = (1.0 - distance) * exposure;
+                let damage = ((impact * impact + impact) / 2.0 * 7.0 * radius + 1.0).floor() as f32;
+
+                // Vanilla's knockback is in blocks per tick.
+                let knockback = (direction * impact * 20.0).as_vec3();
+
+                if explosion.options.damage_entities {
+                    if let Some(mut health) = health {
+                        health.0 = (health.0 - damage).max(0.0);
+                    }
+                }
+
+                if explosion.options.knockback {
+                    velocity.0 += knockback;
+                }
+
+                hits.push(ExplosionHit {
+                    entity,
+                    damage,
+                    knockback,
+                });
+            }
+
+            // Clients are sent their own knockback in the explosion packet.
+            for (entity, mut client, pos, visible_layer) in &mut clients {
+                if visible_layer.0 != layer || pos.0.distance(center) > MAX_CLIENT_DISTANCE {
+                    continue;
+                }
+
+                let player_motion = hits
+                    .iter()
+                    .find(|hit| hit.entity == entity)
+                    .filter(|_| explosion.options.knockback)
+                    .map_or(Vec3::ZERO, |hit| hit.knockback / 20.0);
+
+                let affected_blocks: Vec<_> = explosion
+                    .destroyed_blocks
+                    .iter()
+                    .filter_map(|(block_pos, _)| {
+                        // Blocks too far away for the packet are still updated normally.
+                        Some([
+                            i8::try_from(block_pos.x - center.x.floor() as i32).ok()?,
+                            i8::try_from(block_pos.y - center.y.floor() as i32).ok()?,
+                            i8::try_from(block_pos.z - center.z.floor() as i32).ok()?,
+                        ])
+                    })
+                    .collect();
+
+                client.write_packet(&ExplosionS2c {
+                    pos: center,
+                    strength: explosion.power,
+                    affected_blocks: Cow::Owned(affected_blocks),
+                    player_motion,
+                });
+            }
+
+            events.send(ExplosionEvent {
+                layer,
+                explosion,
+                hits,
+            });
+        }
+    }
+}
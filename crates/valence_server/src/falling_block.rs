@@ -0,0 +1,193 @@
+//! Falling blocks.
+//!
+//! [`FallingBlockPlugin`] simulates falling blocks spawned with
+//! [`FallingBlockBundle`] like vanilla. They fall and collide with the blocks
+//! of their [`ChunkLayer`], and are turned back into a block when they land.
+//! The plugin is not part of `DefaultPlugins` and has to be added separately.
+//!
+//! Valence doesn't make unsupported blocks such as sand fall by itself. Replace
+//! the block with air and spawn a [`FallingBlockBundle`] in its place to do so.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::falling_block::{self, FallingBlockEntityBundle};
+use valence_entity::{EntityLayerId, ObjectData, OnGround, Position, Velocity};
+use valence_math::{Aabb, DVec3};
+use valence_protocol::{BlockPos, BlockState};
+use valence_server_common::Despawned;
+
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+
+pub struct FallingBlockPlugin;
+
+impl Plugin for FallingBlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FallingBlockLandEvent>().add_systems(
+            PostUpdate,
+            tick_falling_blocks.before(UpdateLayersPreClientSet),
+        );
+    }
+}
+
+/// Simulation state for falling blocks spawned with [`FallingBlockBundle`].
+/// The block state is also stored in the entity's [`ObjectData`] for clients.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct FallingBlock {
+    pub state: BlockState,
+    /// The number of ticks this block has been falling for. Blocks that haven't
+    /// landed after [`FallingBlock::MAX_AGE`] ticks are despawned.
+    pub age: u32,
+}
+
+impl FallingBlock {
+    /// The number of ticks a block can fall for before despawning.
+    pub const MAX_AGE: u32 = 600;
+}
+
+/// Bundle for spawning a falling block.
+#[derive(Bundle)]
+pub struct FallingBlockBundle {
+    pub falling_block: FallingBlock,
+    pub entity: FallingBlockEntityBundle,
+}
+
+impl FallingBlockBundle {
+    pub fn new<P: Into<DVec3>>(layer: Entity, position: P, state: BlockState) -> Self {
+        let position = position.into();
+
+        Self {
+            falling_block: FallingBlock { state, age: 0 },
+            entity: FallingBlockEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position(position),
+                object_data: ObjectData(state.to_raw().into()),
+                falling_block_block_pos: falling_block::BlockPos(BlockPos::from(position)),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Creates a falling block in the middle of the block at `pos`.
+    pub fn from_block<P: Into<BlockPos>>(layer: Entity, pos: P, state: BlockState) -> Self {
+        let pos = pos.into();
+
+        Self::new(
+            layer,
+            [
+                f64::from(pos.x) + 0.5,
+                f64::from(pos.y),
+                f64::from(pos.z) + 0.5,
+            ],
+            state,
+        )
+    }
+}
+
+/// Sent when a falling block lands or despawns of old age.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FallingBlockLandEvent {
+    pub entity: Entity,
+    /// The [`ChunkLayer`] the block fell in.
+    pub layer: Entity,
+    pub position: BlockPos,
+    pub state: BlockState,
+    /// Whether the block was placed. Blocks are not placed if they land in a
+    /// block that can't be replaced, in which case vanilla drops them as an
+    /// item instead.
+    pub placed: bool,
+}
+
+/// Applies one tick of vanilla's gravity, drag and block collisions to an
+/// entity with a 0.98 block wide hitbox. `velocity` is in blocks per tick.
+/// Returns whether the entity is on the ground.
+pub(crate) fn tick_physics(layer: &ChunkLayer, pos: &mut DVec3, velocity: &mut DVec3) -> bool {
+    const SIZE: f64 = 0.98;
+    const GRAVITY: f64 = 0.04;
+
+    velocity.y -= GRAVITY;
+
+    let aabb = Aabb::from_bottom_size(*pos, DVec3::splat(SIZE));
+    let movement = layer.collide(aabb, *velocity);
+
+    *pos += movement;
+
+    let on_ground = velocity.y < 0.0 && movement.y != velocity.y;
+
+    for axis in 0..3 {
+        if movement[axis] != velocity[axis] {
+            velocity[axis] = 0.0;
+        }
+    }
+
+    *velocity *= 0.98;
+
+    if on_ground {
+        *velocity *= DVec3::new(0.7, -0.5, 0.7);
+    }
+
+    on_ground
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_falling_blocks(
+    mut blocks: Query<
+        (
+            Entity,
+            &mut FallingBlock,
+            &mut Position,
+            &mut Velocity,
+            &mut OnGround,
+            &EntityLayerId,
+        ),
+        Without<Despawned>,
+    >,
+    mut layers: Query<&mut ChunkLayer>,
+    mut events: EventWriter<FallingBlockLandEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut block, mut pos, mut velocity, mut on_ground, layer) in &mut blocks {
+        let Ok(mut chunk_layer) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        block.age += 1;
+
+        let mut v = velocity.0.as_dvec3() / 20.0;
+        let landed = tick_physics(&chunk_layer, &mut pos.0, &mut v);
+
+        // Clients simulate falling blocks themselves, so only changes made by
+        // others are sent.
+        velocity.bypass_change_detection().0 = (v * 20.0).as_vec3();
+        on_ground.set_if_neq(OnGround(landed));
+
+        let block_pos = BlockPos::from(pos.0);
+
+        let placed = if landed {
+            let replaceable = chunk_layer
+                .block(block_pos)
+                .is_some_and(|b| b.state.is_replaceable());
+
+            if replaceable {
+                chunk_layer.set_block(block_pos, block.state);
+            }
+
+            replaceable
+        } else if block.age >= FallingBlock::MAX_AGE
+            || pos.0.y < f64::from(chunk_layer.min_y()) - 64.0
+        {
+            false
+        } else {
+            continue;
+        };
+
+        commands.entity(entity).insert(Despawned);
+
+        events.send(FallingBlockLandEvent {
+            entity,
+            layer: layer.0,
+            position: block_pos,
+            state: block.state,
+            placed,
+        });
+    }
+}
@@ -0,0 +1,294 @@
+//! Crops and farmland.
+//!
+//! [`FarmingPlugin`] grows crops and hydrates farmland on
+//! [random ticks](crate::random_tick) like vanilla. Crops grow faster on wet
+//! farmland surrounded by more farmland, farmland near water stays wet, and dry
+//! farmland without a crop on it turns back into dirt. A [`CropMatureEvent`] is
+//! sent when a crop is ready to harvest. Valence has no light engine, so crops
+//! grow regardless of light. The plugin is not part of `DefaultPlugins` and has
+//! to be added separately.
+//!
+//! Growing crops with bone meal depends on the item in the client's hand, so it
+//! is handled by `valence_inventory` with [`apply_bone_meal`].
+//!
+//! This module is also a good starting point for other blocks that tick
+//! randomly.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use valence_protocol::block::{BlockKind, PropName, PropValue};
+use valence_protocol::{BlockPos, BlockState};
+
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+use crate::random_tick::{RandomTickEvent, RandomTickSet, RandomTicks};
+
+pub struct FarmingPlugin;
+
+impl Plugin for FarmingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RandomTicks>()
+            .add_event::<CropMatureEvent>()
+            .add_systems(
+                PostUpdate,
+                tick_farming_blocks
+                    .after(RandomTickSet)
+                    .before(UpdateLayersPreClientSet),
+            );
+
+        let mut ticks = app.world_mut().resource_mut::<RandomTicks>();
+
+        for kind in [
+            BlockKind::Wheat,
+            BlockKind::Carrots,
+            BlockKind::Potatoes,
+            BlockKind::Beetroots,
+            BlockKind::NetherWart,
+            BlockKind::Farmland,
+        ] {
+            ticks.insert(kind);
+        }
+    }
+}
+
+/// Sent when a crop grows to its final stage and is ready to harvest.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CropMatureEvent {
+    /// The [`ChunkLayer`] the crop is in.
+    pub layer: Entity,
+    pub position: BlockPos,
+    pub state: BlockState,
+}
+
+/// The wettest farmland can be.
+pub const MAX_MOISTURE: u16 = 7;
+
+/// Returns the growth stage of the crop `state` and its final stage, or `None`
+/// if it isn't a crop.
+pub fn crop_age(state: BlockState) -> Option<(u16, u16)> {
+    let max = match state.to_kind() {
+        BlockKind::Wheat | BlockKind::Carrots | BlockKind::Potatoes => 7,
+        BlockKind::Beetroots | BlockKind::NetherWart => 3,
+        _ => return None,
+    };
+
+    let age = state.get(PropName::Age).and_then(PropValue::to_u16)?;
+
+    Some((age, max))
+}
+
+/// Returns whether `state` is a crop at its final stage.
+pub fn is_mature(state: BlockState) -> bool {
+    crop_age(state).is_some_and(|(age, max)| age >= max)
+}
+
+/// Sets the crop at `pos` to grow `stages` stages, up to its final stage.
+/// Returns the new state of the crop, or `None` if there is no crop at `pos`
+/// or it can't grow any further.
+pub fn grow_crop(layer: &mut ChunkLayer, pos: BlockPos, stages: u16) -> Option<BlockState> {
+    let state = layer.block(pos)?.state;
+    let (age, max) = crop_age(state)?;
+
+    if age >= max {
+        return None;
+    }
+
+    let new_state = state.set(PropName::Age, PropValue::from_u16((age + stages).min(max))?);
+    layer.set_block(pos, new_state);
+
+    Some(new_state)
+}
+
+/// Grows the crop at `pos` like bone meal does. Returns the new state of the
+/// crop, or `None` if bone meal can't be used on it. Like vanilla, beetroots
+/// don't always grow.
+pub fn apply_bone_meal(layer: &mut ChunkLayer, pos: BlockPos) -> Option<BlockState> {
+    let state = layer.block(pos)?.state;
+    let (age, max) = crop_age(state)?;
+
+    if state.to_kind() == BlockKind::NetherWart || age >= max {
+        return None;
+    }
+
+    let mut stages = rand::thread_rng().gen_range(2..=5);
+
+    if state.to_kind() == BlockKind::Beetroots {
+        stages /= 3;
+    }
+
+    if stages == 0 {
+        return Some(state);
+    }
+
+    grow_crop(layer, pos, stages)
+}
+
+/// Returns how well the crop at `pos` grows, like vanilla. Wet farmland and
+/// farmland around the crop help, while crops of the same kind in a row don't.
+fn available_moisture(layer: &ChunkLayer, pos: BlockPos, kind: BlockKind) -> f32 {
+    let state_at = |pos: BlockPos| layer.block(pos).map(|block| block.state);
+
+    let mut moisture = 1.0;
+
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let Some(soil) = state_at(pos.offset(dx, -1, dz)) else {
+                continue;
+            };
+
+            if soil.to_kind() != BlockKind::Farmland {
+                continue;
+            }
+
+            let wet = soil
+                .get(PropName::Moisture)
+                .and_then(PropValue::to_u16)
+                .is_some_and(|m| m > 0);
+
+            let value = if wet { 3.0 } else { 1.0 };
+
+            moisture += if dx == 0 && dz == 0 {
+                value
+            } else {
+                value / 4.0
+            };
+        }
+    }
+
+    let same = |dx, dz| state_at(pos.offset(dx, 0, dz)).is_some_and(|s| s.to_kind() == kind);
+
+    let along_x = same(-1, 0) || same(1, 0);
+    let along_z = same(0, -1) || same(0, 1);
+    let diagonal = same(-1, -1) || same(1, -1) || same(1, 1) || same(-1, 1);
+
+    if (along_x && along_z) || diagonal {
+        moisture /= 2.0;
+    }
+
+    moisture
+}
+
+fn tick_crop<R: Rng>(
+    layer: &mut ChunkLayer,
+    pos: BlockPos,
+    state: BlockState,
+    rng: &mut R,
+) -> bool {
+    let kind = state.to_kind();
+
+    let grows = match kind {
+        BlockKind::NetherWart => rng.gen_range(0..10) == 0,
+        _ => {
+            if kind == BlockKind::Beetroots && rng.gen_range(0..3) == 0 {
+                return false;
+            }
+
+            let moisture = available_moisture(layer, pos, kind);
+            rng.gen_range(0..(25.0 / moisture) as u32 + 1) == 0
+        }
+    };
+
+    grows && grow_crop(layer, pos, 1).is_some()
+}
+
+/// Returns whether there is water within four blocks of the farmland at `pos`.
+fn is_water_nearby(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    for dx in -4..=4 {
+        for dy in 0..=1 {
+            for dz in -4..=4 {
+                let Some(block) = layer.block(pos.offset(dx, dy, dz)) else {
+                    continue;
+                };
+
+                if block.state.to_kind() == BlockKind::Water
+                    || block.state.get(PropName::Waterlogged) == Some(PropValue::True)
+                {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Returns whether `state` keeps the farmland below it from drying out into
+/// dirt.
+fn maintains_farmland(state: BlockState) -> bool {
+    matches!(
+        state.to_kind(),
+        BlockKind::Wheat
+            | BlockKind::Carrots
+            | BlockKind::Potatoes
+            | BlockKind::Beetroots
+            | BlockKind::MelonStem
+            | BlockKind::PumpkinStem
+            | BlockKind::AttachedMelonStem
+            | BlockKind::AttachedPumpkinStem
+            | BlockKind::TorchflowerCrop
+            | BlockKind::PitcherCrop
+    )
+}
+
+fn tick_farmland(layer: &mut ChunkLayer, pos: BlockPos, state: BlockState) {
+    let moisture = state
+        .get(PropName::Moisture)
+        .and_then(PropValue::to_u16)
+        .unwrap_or(0);
+
+    let new_state = if is_water_nearby(layer, pos) {
+        if moisture == MAX_MOISTURE {
+            return;
+        }
+
+        PropValue::from_u16(MAX_MOISTURE).map(|m| state.set(PropName::Moisture, m))
+    } else if moisture > 0 {
+        PropValue::from_u16(moisture - 1).map(|m| state.set(PropName::Moisture, m))
+    } else if layer
+        .block(pos.offset(0, 1, 0))
+        .is_some_and(|block| maintains_farmland(block.state))
+    {
+        return;
+    } else {
+        Some(BlockState::DIRT)
+    };
+
+    if let Some(new_state) = new_state {
+        layer.set_block(pos, new_state);
+    }
+}
+
+fn tick_farming_blocks(
+    mut random_ticks: EventReader<RandomTickEvent>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut events: EventWriter<CropMatureEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for tick in random_ticks.read() {
+        let Ok(mut layer) = layers.get_mut(tick.layer) else {
+            continue;
+        };
+
+        // Another system may have changed the block since the tick was chosen.
+        if layer.block(tick.position).map(|block| block.state) != Some(tick.state) {
+            continue;
+        }
+
+        if tick.state.to_kind() == BlockKind::Farmland {
+            tick_farmland(&mut layer, tick.position, tick.state);
+        } else if tick_crop(&mut layer, tick.position, tick.state, &mut rng) {
+            let Some(state) = layer.block(tick.position).map(|block| block.state) else {
+                continue;
+            };
+
+            if is_mature(state) {
+                events.send(CropMatureEvent {
+                    layer: tick.layer,
+                    position: tick.position,
+                    state,
+                });
+            }
+        }
+    }
+}
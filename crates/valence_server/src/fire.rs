@@ -0,0 +1,638 @@
+//! Fire.
+//!
+//! [`FirePlugin`] adds vanilla's fire mechanics. Fires lit with
+//! [`Fires::light`] age over time, spread to nearby flammable blocks, burn them
+//! away and eventually go out unless they sit on netherrack or magma. Living
+//! entities that touch fire or lava take damage and are set on fire, which is
+//! represented by the [`Burning`] component and shown to clients with the on
+//! fire flag. Burning entities take damage every second until the fire runs
+//! out or they touch water. The plugin is not part of `DefaultPlugins` and has
+//! to be added separately.
+//!
+//! Lighting fires with flint and steel depends on the item in the client's
+//! hand, so it is handled by `valence_inventory` for layers with [`Fires`].
+
+use std::ops::Range;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use valence_entity::active_status_effects::ActiveStatusEffects;
+use valence_entity::entity::Flags;
+use valence_entity::hitbox::Hitbox;
+use valence_entity::living::Health;
+use valence_entity::{EntityLayerId, UpdateTrackedDataSet};
+use valence_math::{Aabb, DVec3};
+use valence_protocol::block::{BlockKind, PropName, PropValue};
+use valence_protocol::status_effects::StatusEffect;
+use valence_protocol::{BlockPos, BlockState, Direction, GameMode};
+use valence_server_common::{Despawned, Server};
+
+use crate::game_mode::NotSpectator;
+use crate::game_rules::GameRules;
+use crate::layer::chunk::Block;
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+
+pub struct FirePlugin;
+
+impl Plugin for FirePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BlockBurnEvent>().add_systems(
+            PostUpdate,
+            (
+                init_fires,
+                tick_fires.before(UpdateLayersPreClientSet),
+                (touch_fire, tick_burning, update_burning_flags)
+                    .chain()
+                    .before(UpdateTrackedDataSet),
+            ),
+        );
+    }
+}
+
+/// The number of ticks between updates of a fire block.
+const FIRE_TICK_DELAY: Range<i64> = 30..40;
+
+/// The fire blocks of a [`ChunkLayer`] that are updated by [`FirePlugin`].
+/// This is inserted on every chunk layer automatically.
+#[derive(Component, Default, Debug)]
+pub struct Fires {
+    /// Positions to light a fire at on the next tick.
+    lit: Vec<BlockPos>,
+    /// The fires that are burning and the tick they are next updated at.
+    scheduled: FxHashMap<BlockPos, i64>,
+}
+
+impl Fires {
+    /// Lights a fire at `pos` on the next tick. Nothing happens if `pos` isn't
+    /// air or a fire couldn't burn there.
+    pub fn light(&mut self, pos: BlockPos) {
+        self.lit.push(pos);
+    }
+
+    /// Starts updating an existing fire block, such as one placed with
+    /// [`ChunkLayer::set_block`]. Fires lit with [`Fires::light`] or spread
+    /// from other fires are updated automatically.
+    pub fn track(&mut self, pos: BlockPos) {
+        self.scheduled.entry(pos).or_insert(i64::MIN);
+    }
+
+    /// Returns whether the fire at `pos` is being updated.
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        self.scheduled.contains_key(&pos)
+    }
+
+    /// Returns the positions of all fires being updated.
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.scheduled.keys().copied()
+    }
+}
+
+/// Present on entities that are on fire. Burning entities take damage every
+/// second until the fire runs out. Remove this component to put the fire out.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Burning {
+    /// The number of ticks until the fire goes out.
+    pub ticks: u32,
+}
+
+impl Burning {
+    pub const fn from_seconds(seconds: u32) -> Self {
+        Self {
+            ticks: seconds * 20,
+        }
+    }
+}
+
+/// Sent when a fire burns a block away, so that blocks such as TNT can react.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct BlockBurnEvent {
+    /// The [`ChunkLayer`] the block was in.
+    pub layer: Entity,
+    pub position: BlockPos,
+    /// The block as it was before it burned.
+    pub block: Block,
+}
+
+/// How easily a block catches fire.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Flammability {
+    /// How likely fires are to spread into the air next to the block.
+    pub spread: u32,
+    /// How likely the block is to burn away when it is next to a fire.
+    pub burn: u32,
+}
+
+/// Returns vanilla's flammability of `state`, or `None` if it doesn't burn.
+pub fn flammability(state: BlockState) -> Option<Flammability> {
+    const WOODS: [&str; 9] = [
+        "oak", "spruce", "birch", "jungle", "acacia", "dark_oak", "mangrove", "cherry", "bamboo",
+    ];
+
+    const PLANTS: [&str; 21] = [
+        "grass",
+        "fern",
+        "tall_grass",
+        "large_fern",
+        "dead_bush",
+        "dandelion",
+        "poppy",
+        "blue_orchid",
+        "allium",
+        "azure_bluet",
+        "oxeye_daisy",
+        "cornflower",
+        "lily_of_the_valley",
+        "wither_rose",
+        "sunflower",
+        "lilac",
+        "rose_bush",
+        "peony",
+        "torchflower",
+        "pitcher_plant",
+        "pink_petals",
+    ];
+
+    if state.get(PropName::Waterlogged) == Some(PropValue::True) {
+        return None;
+    }
+
+    let name = state.to_kind().to_str();
+
+    let (spread, burn) = match name {
+        "bookshelf" | "chiseled_bookshelf" | "lectern" => (30, 20),
+        "tnt" => (15, 100),
+        "vine" | "glow_lichen" | "big_dripleaf" | "big_dripleaf_stem" => (15, 100),
+        "coal_block" => (5, 5),
+        "hay_block" => (60, 20),
+        "target" => (15, 20),
+        "dried_kelp_block" | "azalea" | "flowering_azalea" | "hanging_roots" => (30, 60),
+        "scaffolding" | "spore_blossom" => (60, 60),
+        "beehive" | "bee_nest" | "composter" | "mangrove_roots" | "bamboo_mosaic" => (5, 20),
+        "moss_block" | "moss_carpet" => (5, 100),
+        _ if PLANTS.contains(&name) || name.ends_with("_tulip") => (60, 100),
+        _ if name.ends_with("_wool") || name.ends_with("_leaves") => (30, 60),
+        _ if name.ends_with("_carpet") => (60, 20),
+        _ => {
+            let base = name.strip_prefix("stripped_").unwrap_or(name);
+
+            let wood = WOODS.iter().find_map(|wood| {
+                base.strip_prefix(wood)
+                    .and_then(|rest| rest.strip_prefix('_'))
+            })?;
+
+            match wood {
+                "log" | "wood" | "block" => (5, 5),
+                "planks" | "slab" | "stairs" | "fence" | "fence_gate" | "mosaic_slab"
+                | "mosaic_stairs" => (5, 20),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(Flammability { spread, burn })
+}
+
+const DIRECTIONS: [Direction; 6] = [
+    Direction::Down,
+    Direction::Up,
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+fn state_at(layer: &ChunkLayer, pos: BlockPos) -> Option<BlockState> {
+    layer.block(pos).map(|block| block.state)
+}
+
+fn is_flammable(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    state_at(layer, pos).is_some_and(|state| flammability(state).is_some())
+}
+
+/// Returns the highest spread chance of the blocks around `pos`.
+fn spread_chance(layer: &ChunkLayer, pos: BlockPos) -> u32 {
+    DIRECTIONS
+        .into_iter()
+        .filter_map(|dir| state_at(layer, pos.get_in_direction(dir)).and_then(flammability))
+        .map(|f| f.spread)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Fire can burn on top of solid blocks and next to flammable blocks.
+fn can_burn_at(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    let below = pos.get_in_direction(Direction::Down);
+
+    state_at(layer, below).is_some_and(BlockState::blocks_motion)
+        || DIRECTIONS
+            .into_iter()
+            .any(|dir| is_flammable(layer, pos.get_in_direction(dir)))
+}
+
+/// Returns the fire block at `pos`. Fires that aren't on top of a block cling
+/// to the flammable blocks around them.
+fn fire_state(layer: &ChunkLayer, pos: BlockPos, age: u16) -> BlockState {
+    let mut state = PropValue::from_u16(age).map_or(BlockState::FIRE, |age| {
+        BlockState::FIRE.set(PropName::Age, age)
+    });
+
+    let below = pos.get_in_direction(Direction::Down);
+
+    if state_at(layer, below).is_some_and(BlockState::blocks_motion) || is_flammable(layer, below) {
+        return state;
+    }
+
+    for (dir, prop) in [
+        (Direction::Up, PropName::Up),
+        (Direction::North, PropName::North),
+        (Direction::South, PropName::South),
+        (Direction::West, PropName::West),
+        (Direction::East, PropName::East),
+    ] {
+        if is_flammable(layer, pos.get_in_direction(dir)) {
+            state = state.set(prop, PropValue::True);
+        }
+    }
+
+    state
+}
+
+/// Places a fire at `pos` if there's room for one. Returns whether a fire that
+/// needs updating was placed.
+fn place_fire(layer: &mut ChunkLayer, pos: BlockPos, age: u16) -> bool {
+    if !state_at(layer, pos).is_some_and(BlockState::is_air) {
+        return false;
+    }
+
+    let below = state_at(layer, pos.get_in_direction(Direction::Down)).map(BlockState::to_kind);
+
+    // Soul fire never spreads or burns out.
+    if matches!(below, Some(BlockKind::SoulSand | BlockKind::SoulSoil)) {
+        layer.set_block(pos, BlockState::SOUL_FIRE);
+        return false;
+    }
+
+    if !can_burn_at(layer, pos) {
+        return false;
+    }
+
+    let state = fire_state(layer, pos, age);
+    layer.set_block(pos, state);
+
+    true
+}
+
+/// Updates the fire at `pos` like vanilla. Returns whether the fire is still
+/// burning.
+fn tick_fire<R: Rng>(
+    layer: &mut ChunkLayer,
+    pos: BlockPos,
+    rng: &mut R,
+    new_fires: &mut Vec<BlockPos>,
+    burned: &mut Vec<(BlockPos, Block)>,
+) -> bool {
+    let Some(state) = state_at(layer, pos) else {
+        return false;
+    };
+
+    if state.to_kind() != BlockKind::Fire {
+        return false;
+    }
+
+    if !can_burn_at(layer, pos) {
+        layer.set_block(pos, BlockState::AIR);
+        return false;
+    }
+
+    let below = pos.get_in_direction(Direction::Down);
+    let below_state = state_at(layer, below);
+
+    let infinite = below_state
+        .is_some_and(|s| matches!(s.to_kind(), BlockKind::Netherrack | BlockKind::MagmaBlock));
+
+    let age = state
+        .get(PropName::Age)
+        .and_then(PropValue::to_u16)
+        .unwrap_or(0);
+    let new_age = (age + rng.gen_range(0..3) / 2).min(15);
+
+    if new_age != age {
+        layer.set_block(pos, fire_state(layer, pos, new_age));
+    }
+
+    if !infinite {
+        let has_fuel = DIRECTIONS
+            .into_iter()
+            .any(|dir| is_flammable(layer, pos.get_in_direction(dir)));
+
+        if !has_fuel {
+            if !below_state.is_some_and(BlockState::blocks_motion) || age > 3 {
+                layer.set_block(pos, BlockState::AIR);
+                return false;
+            }
+        } else if age == 15 && rng.gen_range(0..4) == 0 && !is_flammable(layer, below) {
+            layer.set_block(pos, BlockState::AIR);
+            return false;
+        }
+    }
+
+    // Burn the blocks next to the fire.
+    for dir in DIRECTIONS {
+        let target = pos.get_in_direction(dir);
+        let odds = match dir {
+            Direction::Down | Direction::Up => 250,
+            _ => 300,
+        };
+
+        let Some(burn) = state_at(layer, target)
+            .and_then(flammability)
+            .map(|f| f.burn)
+        else {
+            continue;
+        };
+
+        if rng.gen_range(0..odds) >= burn {
+            continue;
+        }
+
+        let block = if rng.gen_range(0..u32::from(age) + 10) < 5 {
+            let fire_age = (age + rng.gen_range(0..5) / 4).min(15);
+            let block = layer.set_block(target, BlockState::AIR);
+
+            if place_fire(layer, target, fire_age) {
+                new_fires.push(target);
+            }
+
+            block
+        } else {
+            layer.set_block(target, BlockState::AIR)
+        };
+
+        if let Some(block) = block {
+            burned.push((target, block));
+        }
+    }
+
+    // Spread to the air around the fire, mostly upwards.
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            for dy in -1..=4 {
+                if dx == 0 && dy == 0 && dz == 0 {
+                    continue;
+                }
+
+                let target = pos.offset(dx, dy, dz);
+
+                if !state_at(layer, target).is_some_and(BlockState::is_air) {
+                    continue;
+                }
+
+                let encouragement = spread_chance(layer, target);
+
+                if encouragement == 0 {
+                    continue;
+                }
+
+                let odds = if dy > 1 { 100 * dy as u32 } else { 100 };
+
+                // Vanilla's spread chance on normal difficulty.
+                let chance = (encouragement + 40 + 14) / (u32::from(age) + 30);
+
+                if rng.gen_range(0..odds) <= chance {
+                    let fire_age = (age + rng.gen_range(0..5) / 4).min(15);
+
+                    if place_fire(layer, target, fire_age) {
+                        new_fires.push(target);
+                    }
+                }
+            }
+        }
+    }
+
+    true
+}
+
+fn init_fires(layers: Query<Entity, (With<ChunkLayer>, Without<Fires>)>, mut commands: Commands) {
+    for entity in &layers {
+        commands.entity(entity).insert(Fires::default());
+    }
+}
+
+fn tick_fires(
+    mut layers: Query<(Entity, &mut ChunkLayer, &mut Fires, Option<&GameRules>)>,
+    server: Res<Server>,
+    mut events: EventWriter<BlockBurnEvent>,
+) {
+    let mut rng = rand::thread_rng();
+    let now = server.current_tick();
+
+    for (layer_entity, mut layer, mut fires, rules) in &mut layers {
+        let fires = &mut *fires;
+
+        for pos in std::mem::take(&mut fires.lit) {
+            if place_fire(&mut layer, pos, 0) {
+                fires
+                    .scheduled
+                    .insert(pos, now + rng.gen_range(FIRE_TICK_DELAY));
+            }
+        }
+
+        if !rules.copied().unwrap_or_default().do_fire_tick {
+            continue;
+        }
+
+        let due: Vec<_> = fires
+            .scheduled
+            .iter()
+            .filter(|&(_, &tick)| tick <= now)
+            .map(|(&pos, _)| pos)
+            .collect();
+
+        let mut new_fires = vec![];
+        let mut burned = vec![];
+
+        for pos in due {
+            if tick_fire(&mut layer, pos, &mut rng, &mut new_fires, &mut burned) {
+                fires
+                    .scheduled
+                    .insert(pos, now + rng.gen_range(FIRE_TICK_DELAY));
+            } else {
+                fires.scheduled.remove(&pos);
+            }
+        }
+
+        for pos in new_fires {
+            fires
+                .scheduled
+                .insert(pos, now + rng.gen_range(FIRE_TICK_DELAY));
+        }
+
+        for (position, block) in burned {
+            events.send(BlockBurnEvent {
+                layer: layer_entity,
+                position,
+                block,
+            });
+        }
+    }
+}
+
+/// Returns whether an entity with `game_mode` and `effects` is unharmed by
+/// fire.
+fn is_fire_immune(game_mode: Option<&GameMode>, effects: Option<&ActiveStatusEffects>) -> bool {
+    matches!(game_mode, Some(GameMode::Creative | GameMode::Spectator))
+        || effects.is_some_and(|e| e.has_effect(StatusEffect::FireResistance))
+}
+
+/// What an entity is touching.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+struct Contact {
+    water: bool,
+    fire: bool,
+    soul_fire: bool,
+    lava: bool,
+}
+
+fn contact(layer: &ChunkLayer, aabb: Aabb) -> Contact {
+    // Like vanilla, entities only touch blocks they are slightly inside of.
+    let min = (aabb.min() + DVec3::splat(0.001)).floor().as_ivec3();
+    let max = (aabb.max() - DVec3::splat(0.001)).floor().as_ivec3();
+
+    let mut contact = Contact::default();
+
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let Some(state) = state_at(layer, BlockPos::new(x, y, z)) else {
+                    continue;
+                };
+
+                match state.to_kind() {
+                    BlockKind::Water | BlockKind::BubbleColumn => contact.water = true,
+                    BlockKind::Fire => contact.fire = true,
+                    BlockKind::SoulFire => contact.soul_fire = true,
+                    BlockKind::Lava => contact.lava = true,
+                    _ => {
+                        if state.get(PropName::Waterlogged) == Some(PropValue::True) {
+                            contact.water = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    contact
+}
+
+#[allow(clippy::type_complexity)]
+fn touch_fire(
+    mut entities: Query<
+        (
+            Entity,
+            &Hitbox,
+            &EntityLayerId,
+            &mut Health,
+            Option<&mut Burning>,
+            Option<&GameMode>,
+            Option<&ActiveStatusEffects>,
+        ),
+        (Without<Despawned>, NotSpectator),
+    >,
+    layers: Query<&ChunkLayer>,
+    server: Res<Server>,
+    mut commands: Commands,
+) {
+    // Entities can only be hurt twice a second by blocks.
+    let hurt = server.current_tick() % 10 == 0;
+
+    for (entity, hitbox, layer, mut health, burning, game_mode, effects) in &mut entities {
+        let Ok(layer) = layers.get(layer.0) else {
+            continue;
+        };
+
+        let contact = contact(layer, hitbox.get());
+
+        if contact.water {
+            if burning.is_some() {
+                commands.entity(entity).remove::<Burning>();
+            }
+
+            continue;
+        }
+
+        let (damage, seconds) = if contact.lava {
+            (4.0, 15)
+        } else if contact.soul_fire {
+            (2.0, 8)
+        } else if contact.fire {
+            (1.0, 8)
+        } else {
+            continue;
+        };
+
+        let ignited = Burning::from_seconds(seconds);
+
+        match burning {
+            Some(mut burning) => burning.ticks = burning.ticks.max(ignited.ticks),
+            None => {
+                commands.entity(entity).insert(ignited);
+            }
+        }
+
+        if hurt && !is_fire_immune(game_mode, effects) && health.0 > 0.0 {
+            health.0 = (health.0 - damage).max(0.0);
+        }
+    }
+}
+
+fn tick_burning(
+    mut entities: Query<(
+        Entity,
+        &mut Burning,
+        Option<&mut Health>,
+        Option<&GameMode>,
+        Option<&ActiveStatusEffects>,
+    )>,
+    mut commands: Commands,
+) {
+    for (entity, mut burning, health, game_mode, effects) in &mut entities {
+        burning.ticks = burning.ticks.saturating_sub(1);
+
+        if burning.ticks % 20 == 0 && !is_fire_immune(game_mode, effects) {
+            if let Some(mut health) = health {
+                if health.0 > 0.0 {
+                    health.0 = (health.0 - 1.0).max(0.0);
+                }
+            }
+        }
+
+        if burning.ticks == 0 {
+            commands.entity(entity).remove::<Burning>();
+        }
+    }
+}
+
+fn update_burning_flags(
+    mut entities: Query<&mut Flags>,
+    ignited: Query<Entity, Added<Burning>>,
+    mut extinguished: RemovedComponents<Burning>,
+) {
+    for entity in &ignited {
+        if let Ok(mut flags) = entities.get_mut(entity) {
+            if !flags.on_fire() {
+                flags.set_on_fire(true);
+            }
+        }
+    }
+
+    for entity in extinguished.read() {
+        if let Ok(mut flags) = entities.get_mut(entity) {
+            if flags.on_fire() {
+                flags.set_on_fire(false);
+            }
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! Firework rockets and the NBT of their explosions.
+//!
+//! [`Fireworks`] and [`FireworkEffect`] build the NBT of firework rocket
+//! items, so rockets with custom explosions don't need hand-written
+//! compounds. Rockets spawned with [`FireworkBundle`] fly up and explode once
+//! their lifetime runs out, like vanilla. Clients draw the explosion from the
+//! rocket's item, and a [`FireworkExplodeEvent`] is sent so damage can be
+//! dealt if desired.
+//!
+//! Rockets attached to a player with [`FireworkBundle::attached_to`] follow it
+//! instead, which is how gliding players are boosted.
+//!
+//! [`FireworkPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+//!
+//! ```
+//! # use bevy_ecs::prelude::*;
+//! # use valence_server::firework::*;
+//! # use valence_server::DyeColor;
+//! fn launch(mut commands: Commands, layer: Entity) {
+//!     let fireworks = Fireworks::new().with_flight(2).with_effect(
+//!         FireworkEffect::new(FireworkShape::Star)
+//!             .with_color(DyeColor::Yellow.firework_color())
+//!             .with_trail(),
+//!     );
+//!
+//!     commands.spawn(FireworkBundle::new(layer, [0.5, 64.0, 0.5], &fireworks));
+//! }
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use valence_entity::firework_rocket::{self, FireworkRocketEntityBundle, ShooterEntityId};
+use valence_entity::{
+    EntityId, EntityLayerId, EntityStatus, EntityStatuses, Position, UpdateTrackedDataSet, Velocity,
+};
+use valence_math::{DVec3, Vec3};
+use valence_nbt::{compound, Compound, List, Value};
+use valence_protocol::{ItemKind, ItemStack};
+use valence_server_common::Despawned;
+
+pub struct FireworkPlugin;
+
+impl Plugin for FireworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FireworkExplodeEvent>().add_systems(
+            PostUpdate,
+            (attach_fireworks, tick_fireworks)
+                .chain()
+                .before(UpdateTrackedDataSet),
+        );
+    }
+}
+
+/// The shape of a firework explosion.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum FireworkShape {
+    #[default]
+    SmallBall,
+    LargeBall,
+    Star,
+    Creeper,
+    Burst,
+}
+
+impl FireworkShape {
+    pub const fn id(self) -> i8 {
+        self as i8
+    }
+
+    pub const fn from_id(id: i8) -> Option<Self> {
+        match id {
+            0 => Some(Self::SmallBall),
+            1 => Some(Self::LargeBall),
+            2 => Some(Self::Star),
+            3 => Some(Self::Creeper),
+            4 => Some(Self::Burst),
+            _ => None,
+        }
+    }
+}
+
+/// A single explosion of a firework rocket, or of a firework star.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct FireworkEffect {
+    pub shape: FireworkShape,
+    /// The RGB colors of the explosion.
+    pub colors: Vec<u32>,
+    /// The RGB colors the explosion fades to.
+    pub fade_colors: Vec<u32>,
+    /// Whether the explosion twinkles.
+    pub flicker: bool,
+    /// Whether the explosion leaves a trail.
+    pub trail: bool,
+}
+
+impl FireworkEffect {
+    pub fn new(shape: FireworkShape) -> Self {
+        Self {
+            shape,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_color(mut self, rgb: u32) -> Self {
+        self.colors.push(rgb);
+        self
+    }
+
+    #[must_use]
+    pub fn with_fade_color(mut self, rgb: u32) -> Self {
+        self.fade_colors.push(rgb);
+        self
+    }
+
+    #[must_use]
+    pub fn with_flicker(mut self) -> Self {
+        self.flicker = true;
+        self
+    }
+
+    #[must_use]
+    pub fn with_trail(mut self) -> Self {
+        self.trail = true;
+        self
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let colors = |colors: &[u32]| colors.iter().map(|&rgb| rgb as i32).collect::<Vec<_>>();
+
+        compound! {
+            "Type" => self.shape.id(),
+            "Colors" => colors(&self.colors),
+            "FadeColors" => colors(&self.fade_colors),
+            "Flicker" => self.flicker,
+            "Trail" => self.trail,
+        }
+    }
+
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let colors = |key: &str| match nbt.get(key) {
+            Some(Value::IntArray(colors)) => colors.iter().map(|&rgb| rgb as u32).collect(),
+            _ => vec![],
+        };
+
+        let flag = |key: &str| matches!(nbt.get(key), Some(Value::Byte(b)) if *b != 0);
+
+        Self {
+            shape: match nbt.get("Type") {
+                Some(Value::Byte(id)) => FireworkShape::from_id(*id).unwrap_or_default(),
+                _ => FireworkShape::default(),
+            },
+            colors: colors("Colors"),
+            fade_colors: colors("FadeColors"),
+            flicker: flag("Flicker"),
+            trail: flag("Trail"),
+        }
+    }
+
+    /// A firework star with this explosion, for crafting rockets.
+    pub fn star(&self) -> ItemStack {
+        let nbt = compound! {
+            "Explosion" => self.to_nbt(),
+        };
+
+        ItemStack::new(ItemKind::FireworkStar, 1, Some(nbt))
+    }
+}
+
+/// The contents of a firework rocket.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Fireworks {
+    /// The flight duration shown on the rocket, from 1 to 3 in vanilla.
+    /// Rockets fly for roughly `10 * (flight + 1)` ticks.
+    pub flight: i8,
+    /// The explosions of the rocket. Rockets without explosions don't
+    /// explode, but still make a sound when their lifetime runs out.
+    pub effects: Vec<FireworkEffect>,
+}
+
+impl Default for Fireworks {
+    fn default() -> Self {
+        Self {
+            flight: 1,
+            effects: vec![],
+        }
+    }
+}
+
+impl Fireworks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_flight(mut self, flight: i8) -> Self {
+        self.flight = flight;
+        self
+    }
+
+    #[must_use]
+    pub fn with_effect(mut self, effect: FireworkEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    /// The `Fireworks` compound of a firework rocket item.
+    pub fn to_nbt(&self) -> Compound {
+        let explosions = self.effects.iter().map(FireworkEffect::to_nbt).collect();
+
+        compound! {
+            "Flight" => self.flight,
+            "Explosions" => List::Compound(explosions),
+        }
+    }
+
+    /// Reads the `Fireworks` compound of a firework rocket item.
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let flight = match nbt.get("Flight") {
+            Some(Value::Byte(flight)) => *flight,
+            _ => 0,
+        };
+
+        let effects = match nbt.get("Explosions") {
+            Some(Value::List(List::Compound(explosions))) => {
+                explosions.iter().map(FireworkEffect::from_nbt).collect()
+            }
+            _ => vec![],
+        };
+
+        Self { flight, effects }
+    }
+
+    /// Reads the contents of a firework rocket item. Like vanilla, rockets
+    /// without a `Fireworks` compound have a flight of 0.
+    pub fn from_rocket(stack: &ItemStack) -> Self {
+        match stack.nbt.as_ref().and_then(|nbt| nbt.get("Fireworks")) {
+            Some(Value::Compound(nbt)) => Self::from_nbt(nbt),
+            _ => Self {
+                flight: 0,
+                effects: vec![],
+            },
+        }
+    }
+
+    /// A stack of `count` firework rockets with these contents.
+    pub fn rocket(&self, count: i8) -> ItemStack {
+        let nbt = compound! {
+            "Fireworks" => self.to_nbt(),
+        };
+
+        ItemStack::new(ItemKind::FireworkRocket, count, Some(nbt))
+    }
+
+    /// Returns a random lifetime for a rocket with these contents, in ticks,
+    /// like vanilla.
+    pub fn random_lifetime(&self) -> u32 {
+        let mut rng = rand::thread_rng();
+
+        10 * (self.flight.max(0) as u32 + 1) + rng.gen_range(0..6) + rng.gen_range(0..7)
+    }
+}
+
+/// Simulation state for firework rockets spawned with [`FireworkBundle`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Firework {
+    /// The number of ticks until the rocket explodes.
+    pub remaining_ticks: u32,
+    /// The entity the rocket is attached to, if any.
+    pub attached_to: Option<Entity>,
+}
+
+/// Bundle for spawning firework rockets.
+#[derive(Bundle)]
+pub struct FireworkBundle {
+    pub firework: Firework,
+    pub entity: FireworkRocketEntityBundle,
+}
+
+impl FireworkBundle {
+    /// Creates a rocket launched straight up with a random lifetime.
+    pub fn new<P: Into<DVec3>>(layer: Entity, position: P, fireworks: &Fireworks) -> Self {
+        Self::from_rocket(layer, position, fireworks.rocket(1))
+    }
+
+    /// Creates a rocket from a firework rocket item, such as one used by a
+    /// client.
+    pub fn from_rocket<P: Into<DVec3>>(layer: Entity, position: P, rocket: ItemStack) -> Self {
+        let mut rng = rand::thread_rng();
+
+        // Vanilla pushes the rocket sideways a tiny bit. Velocity is in m/s.
+        let velocity = Vec3::new(
+            rng.gen_range(-0.046..0.046),
+            0.05 * 20.0,
+            rng.gen_range(-0.046..0.046),
+        );
+
+        Self {
+            firework: Firework {
+                remaining_ticks: Fireworks::from_rocket(&rocket).random_lifetime(),
+                attached_to: None,
+            },
+            entity: FireworkRocketEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(position),
+                velocity: Velocity(velocity),
+                firework_rocket_item: firework_rocket::Item(rocket),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Attaches the rocket to `entity`, which it follows until it explodes.
+    /// Rockets attached to a gliding player boost it.
+    #[must_use]
+    pub fn attached_to(mut self, entity: Entity) -> Self {
+        self.firework.attached_to = Some(entity);
+        self.entity.velocity = Velocity::default();
+        self
+    }
+
+    #[must_use]
+    pub fn with_lifetime(mut self, ticks: u32) -> Self {
+        self.firework.remaining_ticks = ticks;
+        self
+    }
+}
+
+/// Spawns a rocket with a single explosion at `position`.
+pub fn spawn_firework<P: Into<DVec3>>(
+    commands: &mut Commands,
+    layer: Entity,
+    position: P,
+    effect: FireworkEffect,
+) -> Entity {
+    let fireworks = Fireworks::new().with_effect(effect);
+
+    commands
+        .spawn(FireworkBundle::new(layer, position, &fireworks))
+        .id()
+}
+
+/// Sent when a firework rocket explodes.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct FireworkExplodeEvent {
+    pub firework: Entity,
+    pub position: DVec3,
+    /// The entity the rocket was attached to.
+    pub attached_to: Option<Entity>,
+    pub effects: Vec<FireworkEffect>,
+}
+
+/// Tells clients which entity new rockets are attached to.
+fn attach_fireworks(
+    mut fireworks: Query<(&Firework, &mut ShooterEntityId), Added<Firework>>,
+    entities: Query<&EntityId>,
+) {
+    for (firework, mut shooter) in &mut fireworks {
+        if let Some(id) = firework
+            .attached_to
+            .and_then(|entity| entities.get(entity).ok())
+        {
+            shooter.0 = Some(id.get());
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_fireworks(
+    mut fireworks: Query<
+        (
+            Entity,
+            &mut Firework,
+            &mut Position,
+            &mut Velocity,
+            &mut EntityStatuses,
+            &firework_rocket::Item,
+        ),
+        Without<Despawned>,
+    >,
+    positions: Query<&Position, Without<Firework>>,
+    mut explode_events: EventWriter<FireworkExplodeEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut firework, mut pos, mut velocity, mut statuses, item) in &mut fireworks {
+        if firework.remaining_ticks == 0 {
+            // The rocket exploded last tick.
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        // Clients move rockets themselves, so the position is only kept
+        // up to date for the explosion.
+        match firework.attached_to {
+            Some(attached_to) => {
+                if let Ok(attached_pos) = positions.get(attached_to) {
+                    pos.bypass_change_detection().0 = attached_pos.0;
+                }
+            }
+            None => {
+                let v = velocity.0 * Vec3::new(1.15, 1.0, 1.15) + Vec3::new(0.0, 0.04 * 20.0, 0.0);
+
+                velocity.bypass_change_detection().0 = v;
+                pos.bypass_change_detection().0 += v.as_dvec3() / 20.0;
+            }
+        }
+
+        firework.remaining_ticks -= 1;
+
+        if firework.remaining_ticks == 0 {
+            statuses.trigger(EntityStatus::ExplodeFireworkClient);
+
+            explode_events.send(FireworkExplodeEvent {
+                firework: entity,
+                position: pos.0,
+                attached_to: firework.attached_to,
+                effects: Fireworks::from_rocket(&item.0).effects,
+            });
+        }
+    }
+}
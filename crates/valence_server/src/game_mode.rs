@@ -0,0 +1,185 @@
+//! Keeping clients consistent when their [`GameMode`] changes.
+//!
+//! Changing the [`GameMode`] component of a client is all that's needed to
+//! switch game modes. [`GameModePlugin`] then takes care of everything else
+//! that depends on the game mode in one place:
+//!
+//! - The [`PlayerAbilitiesFlags`] are set like vanilla, so creative and
+//!   spectator clients can fly and survival clients are knocked out of the air.
+//! - Clients entering spectator mode are made invisible to others, put out and
+//!   taken off whatever they were riding. Leaving spectator mode restores their
+//!   visibility.
+//! - The [`PrevGameMode`] is set to the old game mode, which the F3+F4 game
+//!   mode switcher uses.
+//! - The [`Spectator`] marker is inserted on spectators, so that systems can
+//!   leave them out with the [`NotSpectator`] filter.
+//!
+//! A [`GameModeChangeEvent`] is sent for every change after the client has
+//! joined.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::entity::Flags;
+use valence_entity::passengers::Vehicle;
+use valence_entity::{InitEntitiesSet, UpdateTrackedDataSet};
+use valence_protocol::GameMode;
+
+use crate::abilities::{PlayerAbilitiesFlags, PlayerStartFlyingEvent, PlayerStopFlyingEvent};
+use crate::spawn::PrevGameMode;
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameModeChangeEvent>()
+            .configure_sets(
+                PostUpdate,
+                GameModeTransitionSet
+                    .before(InitEntitiesSet)
+                    .before(UpdateTrackedDataSet),
+            )
+            .add_systems(
+                PostUpdate,
+                transition_game_modes.in_set(GameModeTransitionSet),
+            );
+    }
+}
+
+/// The system set where clients are updated for their new [`GameMode`].
+/// Systems changing the game mode should run before this.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct GameModeTransitionSet;
+
+/// Sent when the [`GameMode`] of a client changes.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GameModeChangeEvent {
+    pub client: Entity,
+    pub old: GameMode,
+    pub new: GameMode,
+}
+
+/// Marks clients in spectator mode. Inserted and removed when the [`GameMode`]
+/// of a client is transitioned.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct Spectator;
+
+/// A query filter for entities which aren't clients in spectator mode.
+/// Spectators fly through everything, so systems colliding with, targeting or
+/// hurting entities should use this.
+pub type NotSpectator = Without<Spectator>;
+
+/// The state of a client from its last game mode transition. This is part of
+/// the client bundle.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct GameModeState {
+    game_mode: GameMode,
+    /// Whether the client was invisible before becoming a spectator.
+    was_invisible: bool,
+}
+
+impl GameModeState {
+    /// The game mode the client was last transitioned to.
+    pub fn game_mode(&self) -> GameMode {
+        self.game_mode
+    }
+}
+
+/// Sets `flags` for `game_mode` like vanilla. Creative mode doesn't change
+/// whether the client is flying.
+fn set_abilities(flags: &mut PlayerAbilitiesFlags, game_mode: GameMode) {
+    match game_mode {
+        GameMode::Creative => {
+            flags.set_invulnerable(true);
+            flags.set_allow_flying(true);
+            flags.set_instant_break(true);
+        }
+        GameMode::Spectator => {
+            flags.set_invulnerable(true);
+            flags.set_allow_flying(true);
+            flags.set_instant_break(false);
+            flags.set_flying(true);
+        }
+        GameMode::Survival | GameMode::Adventure => {
+            flags.set_invulnerable(false);
+            flags.set_allow_flying(false);
+            flags.set_instant_break(false);
+            flags.set_flying(false);
+        }
+    }
+}
+
+fn transition_game_modes(
+    mut clients: Query<
+        (
+            Entity,
+            Ref<GameMode>,
+            &mut GameModeState,
+            &mut PrevGameMode,
+            &mut PlayerAbilitiesFlags,
+            Option<&mut Flags>,
+            Has<Vehicle>,
+        ),
+        Changed<GameMode>,
+    >,
+    mut change_events: EventWriter<GameModeChangeEvent>,
+    mut start_flying_events: EventWriter<PlayerStartFlyingEvent>,
+    mut stop_flying_events: EventWriter<PlayerStopFlyingEvent>,
+    mut commands: Commands,
+) {
+    for (entity, game_mode, mut state, mut prev_game_mode, mut abilities, flags, has_vehicle) in
+        &mut clients
+    {
+        let old = state.game_mode;
+        let new = *game_mode;
+
+        if !game_mode.is_added() {
+            if old == new {
+                continue;
+            }
+
+            prev_game_mode.set_if_neq(PrevGameMode(Some(old)));
+
+            change_events.send(GameModeChangeEvent {
+                client: entity,
+                old,
+                new,
+            });
+        }
+
+        let mut new_abilities = *abilities;
+        set_abilities(&mut new_abilities, new);
+
+        if new_abilities.flying() != abilities.flying() {
+            if new_abilities.flying() {
+                start_flying_events.send(PlayerStartFlyingEvent { client: entity });
+            } else {
+                stop_flying_events.send(PlayerStopFlyingEvent { client: entity });
+            }
+        }
+
+        // Changing the flags sends them to the client.
+        abilities.set_if_neq(new_abilities);
+
+        if let Some(mut flags) = flags {
+            if new == GameMode::Spectator && old != GameMode::Spectator {
+                state.was_invisible = flags.invisible();
+                flags.set_invisible(true);
+                flags.set_on_fire(false);
+            } else if new != GameMode::Spectator && old == GameMode::Spectator {
+                flags.set_invisible(state.was_invisible);
+            }
+        }
+
+        if new == GameMode::Spectator {
+            commands.entity(entity).insert(Spectator);
+
+            if has_vehicle {
+                commands.entity(entity).remove::<Vehicle>();
+            }
+        } else if old == GameMode::Spectator {
+            commands.entity(entity).remove::<Spectator>();
+        }
+
+        state.game_mode = new;
+    }
+}
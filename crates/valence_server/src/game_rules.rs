@@ -0,0 +1,97 @@
+//! Per-layer game rules.
+//!
+//! Add [`GameRules`] to a [`ChunkLayer`] entity to change the rules for
+//! clients in the layer. Systems treat layers without the component as if they
+//! had the default rules, except that [`ReducedDebugInfo`] and
+//! [`HasRespawnScreen`] are left alone so they can still be set per client.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+
+use crate::client::{UpdateClientsSet, VisibleChunkLayer};
+use crate::layer::ChunkLayer;
+use crate::spawn::{HasRespawnScreen, ReducedDebugInfo};
+
+pub struct GameRulesPlugin;
+
+impl Plugin for GameRulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, sync_client_rules.before(UpdateClientsSet));
+    }
+}
+
+/// The game rules of a [`ChunkLayer`], named after their vanilla counterparts.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GameRules {
+    /// `doDaylightCycle`: Whether the time of day advances. See
+    /// [`WorldTime`](crate::world_time::WorldTime).
+    pub do_daylight_cycle: bool,
+    /// `doFireTick`: Whether fire spreads and burns out. See
+    /// [`FirePlugin`](crate::fire::FirePlugin).
+    pub do_fire_tick: bool,
+    /// `doImmediateRespawn`: Whether clients respawn without seeing the death
+    /// screen.
+    pub do_immediate_respawn: bool,
+    /// `keepInventory`: Whether clients keep their inventory and experience
+    /// when they die.
+    pub keep_inventory: bool,
+    /// `naturalRegeneration`: Whether players with enough food regenerate
+    /// health.
+    pub natural_regeneration: bool,
+    /// `playersSleepingPercentage`: The percentage of players that need to be
+    /// asleep to skip the night.
+    pub players_sleeping_percentage: u32,
+    /// `randomTickSpeed`: The number of blocks chosen in each chunk section
+    /// every tick to be [ticked randomly](crate::random_tick).
+    pub random_tick_speed: u32,
+    /// `reducedDebugInfo`: Whether the debug screen hides details such as the
+    /// client's coordinates.
+    pub reduced_debug_info: bool,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            do_daylight_cycle: true,
+            do_fire_tick: true,
+            do_immediate_respawn: false,
+            keep_inventory: false,
+            natural_regeneration: true,
+            players_sleeping_percentage: 100,
+            random_tick_speed: 3,
+            reduced_debug_info: false,
+        }
+    }
+}
+
+impl GameRules {
+    /// Returns the number of players out of `players` that need to be asleep
+    /// to skip the night. This is always at least one.
+    pub fn sleepers_needed(&self, players: u32) -> u32 {
+        let needed = u64::from(players) * u64::from(self.players_sleeping_percentage);
+        (needed.div_ceil(100) as u32).max(1)
+    }
+}
+
+/// Copies the rules that clients know about to the components sent in the
+/// game join and respawn packets when a client changes layers or the rules
+/// change.
+fn sync_client_rules(
+    mut clients: Query<(
+        Ref<VisibleChunkLayer>,
+        &mut ReducedDebugInfo,
+        &mut HasRespawnScreen,
+    )>,
+    layers: Query<Ref<GameRules>, With<ChunkLayer>>,
+) {
+    for (layer, mut reduced_debug_info, mut has_respawn_screen) in &mut clients {
+        let Ok(rules) = layers.get(layer.0) else {
+            continue;
+        };
+
+        if layer.is_changed() || rules.is_changed() {
+            reduced_debug_info.set_if_neq(ReducedDebugInfo(rules.reduced_debug_info));
+            has_respawn_screen.set_if_neq(HasRespawnScreen(!rules.do_immediate_respawn));
+        }
+    }
+}
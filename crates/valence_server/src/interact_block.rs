@@ -1,17 +1,20 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use valence_entity::Position;
 use valence_math::Vec3;
 use valence_protocol::packets::play::PlayerInteractBlockC2s;
-use valence_protocol::{BlockPos, Direction, Hand};
+use valence_protocol::{BlockPos, Direction, GameMode, Hand};
 
 use crate::action::ActionSequence;
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::interaction_rules::InteractionRules;
 
 pub struct InteractBlockPlugin;
 
 impl Plugin for InteractBlockPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<InteractBlockEvent>()
+        app.init_resource::<InteractionRules>()
+            .add_event::<InteractBlockEvent>()
             .add_systems(EventLoopPreUpdate, handle_interact_block);
     }
 }
@@ -33,18 +36,33 @@ pub struct InteractBlockEvent {
     pub sequence: i32,
 }
 
-fn handle_interact_block(
+pub(crate) fn handle_interact_block(
     mut packets: EventReader<PacketEvent>,
-    mut clients: Query<&mut ActionSequence>,
+    mut clients: Query<(&mut ActionSequence, &Position, &GameMode)>,
+    rules: Res<InteractionRules>,
     mut events: EventWriter<InteractBlockEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<PlayerInteractBlockC2s>() {
-            if let Ok(mut action_seq) = clients.get_mut(packet.client) {
-                action_seq.update(pkt.sequence.0);
-            }
+            let Ok((mut action_seq, pos, game_mode)) = clients.get_mut(packet.client) else {
+                continue;
+            };
+
+            action_seq.update(pkt.sequence.0);
+
+            // The client may have placed a block against the face.
+            let placed_pos = pkt.position.get_in_direction(pkt.face);
+            action_seq.predict(pkt.position);
+            action_seq.predict(placed_pos);
 
-            // TODO: check that the block interaction is valid.
+            if !rules.can_interact(*game_mode)
+                || !InteractionRules::is_in_reach(pos.0, pkt.position, rules.max_interact_distance)
+            {
+                action_seq.reject(pkt.position);
+                action_seq.reject(placed_pos);
+
+                continue;
+            }
 
             events.send(InteractBlockEvent {
                 client: packet.client,
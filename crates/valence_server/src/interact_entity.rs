@@ -3,8 +3,10 @@ use bevy_ecs::prelude::*;
 use valence_entity::EntityManager;
 pub use valence_protocol::packets::play::player_interact_entity_c2s::EntityInteraction;
 use valence_protocol::packets::play::PlayerInteractEntityC2s;
+use valence_protocol::Hand;
 
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::game_mode::Spectator;
 
 pub struct InteractEntityPlugin;
 
@@ -15,6 +17,8 @@ impl Plugin for InteractEntityPlugin {
     }
 }
 
+/// Sent when a client interacts with an entity. Interactions by and with
+/// clients in spectator mode aren't sent.
 #[derive(Event, Copy, Clone, Debug)]
 pub struct InteractEntityEvent {
     pub client: Entity,
@@ -26,18 +30,35 @@ pub struct InteractEntityEvent {
     pub interact: EntityInteraction,
 }
 
+impl InteractEntityEvent {
+    /// Returns the hand the client interacted with. Attacks are always made
+    /// with the main hand.
+    pub fn hand(&self) -> Hand {
+        self.interact.hand()
+    }
+}
+
 fn handle_interact_entity(
     mut packets: EventReader<PacketEvent>,
     entities: Res<EntityManager>,
+    spectators: Query<(), With<Spectator>>,
     mut events: EventWriter<InteractEntityEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<PlayerInteractEntityC2s>() {
+            // Spectators pass through entities, and can't be hit themselves.
+            if spectators.contains(packet.client) {
+                continue;
+            }
+
             // TODO: check that the entity is in the same instance as the player.
             // TODO: check that the distance between the player and the interacted entity is
             // within some configurable tolerance level.
 
-            if let Some(entity) = entities.get_by_id(pkt.entity_id.0) {
+            if let Some(entity) = entities
+                .get_by_id(pkt.entity_id.0)
+                .filter(|&entity| !spectators.contains(entity))
+            {
                 events.send(InteractEntityEvent {
                     client: packet.client,
                     entity,
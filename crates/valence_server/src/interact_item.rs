@@ -22,7 +22,7 @@ pub struct InteractItemEvent {
     pub sequence: i32,
 }
 
-fn handle_player_interact_item(
+pub(crate) fn handle_player_interact_item(
     mut packets: EventReader<PacketEvent>,
     mut clients: Query<&mut ActionSequence>,
     mut events: EventWriter<InteractItemEvent>,
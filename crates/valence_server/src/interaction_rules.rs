@@ -0,0 +1,122 @@
+//! Server-side rules for how clients may interact with blocks.
+//!
+//! Clients only check their reach and game mode themselves, so a modified
+//! client can break and place blocks anywhere. The [`InteractionRules`]
+//! resource is checked before [`DiggingEvent`]s and [`InteractBlockEvent`]s are
+//! sent. Packets breaking the rules are dropped, and the blocks involved are
+//! resent to the client so it doesn't keep a block that isn't really there.
+//! With [`InteractionRules::reconcile_blocks`], this is done for every dig and
+//! placement, so the client's blocks always end up matching the server's.
+//!
+//! [`DiggingEvent`]: crate::action::DiggingEvent
+//! [`InteractBlockEvent`]: crate::interact_block::InteractBlockEvent
+
+use bevy_ecs::prelude::*;
+use valence_math::DVec3;
+use valence_protocol::packets::play::BlockUpdateS2c;
+use valence_protocol::{BlockPos, GameMode, WritePacket};
+
+use crate::action::DiggingState;
+use crate::block_overlay::BlockOverlay;
+use crate::client::Client;
+use crate::layer::ChunkLayer;
+
+/// The height of a standing player's eyes above their position.
+pub const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// A [`Resource`] with the rules clients have to follow when interacting with
+/// blocks. The defaults match vanilla.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct InteractionRules {
+    /// The greatest distance between the eyes of a client and the center of a
+    /// block it digs.
+    pub max_dig_distance: f64,
+    /// The greatest distance between the eyes of a client and the center of a
+    /// block it interacts with or places a block against.
+    pub max_interact_distance: f64,
+    /// Whether clients in [`GameMode::Adventure`] can dig blocks. Vanilla only
+    /// allows it with tools that can destroy the block, which Valence doesn't
+    /// check.
+    pub adventure_digging: bool,
+    /// Whether clients in [`GameMode::Creative`] break blocks as soon as they
+    /// start digging them, like vanilla. See
+    /// [`breaks_block`](Self::breaks_block).
+    pub creative_instant_break: bool,
+    /// Whether the blocks a client predicted changes to when digging or
+    /// placing are always resent before the action is acknowledged, even when
+    /// the action isn't [rejected](crate::action::ActionSequence::reject).
+    /// This prevents ghost blocks when the server handles an action
+    /// differently than the client expected, like placing a torch on the floor
+    /// instead of the wall. Disabling it saves a few packets for servers that
+    /// always reject what they don't accept.
+    pub reconcile_blocks: bool,
+}
+
+impl Default for InteractionRules {
+    fn default() -> Self {
+        Self {
+            max_dig_distance: 6.0,
+            max_interact_distance: 8.0,
+            adventure_digging: false,
+            creative_instant_break: true,
+            reconcile_blocks: true,
+        }
+    }
+}
+
+impl InteractionRules {
+    /// Returns whether a client in `game_mode` may dig blocks at all.
+    pub fn can_dig(&self, game_mode: GameMode) -> bool {
+        match game_mode {
+            GameMode::Survival | GameMode::Creative => true,
+            GameMode::Adventure => self.adventure_digging,
+            GameMode::Spectator => false,
+        }
+    }
+
+    /// Returns whether a client in `game_mode` may interact with blocks and
+    /// place blocks.
+    pub fn can_interact(&self, game_mode: GameMode) -> bool {
+        game_mode != GameMode::Spectator
+    }
+
+    /// Returns whether a client in `game_mode` has broken the block it's
+    /// digging once digging reaches `state`.
+    pub fn breaks_block(&self, game_mode: GameMode, state: DiggingState) -> bool {
+        if !self.can_dig(game_mode) {
+            return false;
+        }
+
+        if game_mode == GameMode::Creative && self.creative_instant_break {
+            state == DiggingState::Start
+        } else {
+            state == DiggingState::Stop
+        }
+    }
+
+    /// Returns whether the block at `block` is within `max_distance` of the
+    /// eyes of a client at `position`.
+    pub fn is_in_reach(position: DVec3, block: BlockPos, max_distance: f64) -> bool {
+        let eyes = position + DVec3::new(0.0, PLAYER_EYE_HEIGHT, 0.0);
+        let center = DVec3::new(block.x as f64, block.y as f64, block.z as f64) + 0.5;
+
+        eyes.distance_squared(center) <= max_distance * max_distance
+    }
+}
+
+/// Resends the blocks at `positions` to the client, undoing any changes it
+/// predicted. Blocks in the client's [`BlockOverlay`] are resent as overlaid.
+pub(crate) fn resend_blocks<I: IntoIterator<Item = BlockPos>>(
+    client: &mut Client,
+    layer: &ChunkLayer,
+    overlay: Option<&BlockOverlay>,
+    positions: I,
+) {
+    for position in positions {
+        let overlaid = overlay.and_then(|o| o.get(position));
+
+        if let Some(block_id) = overlaid.or_else(|| Some(layer.block(position)?.state)) {
+            client.write_packet(&BlockUpdateS2c { position, block_id });
+        }
+    }
+}
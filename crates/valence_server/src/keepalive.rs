@@ -5,7 +5,7 @@ use bevy_ecs::prelude::*;
 use derive_more::Deref;
 use tracing::warn;
 use valence_protocol::packets::play::{KeepAliveC2s, KeepAliveS2c};
-use valence_protocol::WritePacket;
+use valence_protocol::{PacketPriority, WritePacket};
 
 use crate::client::{Client, UpdateClientsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
@@ -77,7 +77,7 @@ fn send_keepalive(
         if now.duration_since(state.last_send) >= settings.period {
             if state.got_keepalive {
                 let id = rand::random();
-                client.write_packet(&KeepAliveS2c { id });
+                client.write_packet_with_priority(&KeepAliveS2c { id }, PacketPriority::Critical);
 
                 state.got_keepalive = false;
                 state.last_keepalive_id = id;
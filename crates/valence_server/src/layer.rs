@@ -7,12 +7,19 @@
 pub mod bvh;
 pub mod chunk;
 pub mod entity;
+pub mod limits;
 pub mod message;
+pub mod stats;
+pub mod ticket;
+pub mod unload;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 pub use chunk::ChunkLayer;
 pub use entity::EntityLayer;
+pub use limits::LayerLimits;
+pub use stats::LayerStats;
+pub use unload::ChunkUnloadPolicy;
 use valence_entity::{InitEntitiesSet, UpdateTrackedDataSet};
 use valence_protocol::encode::WritePacket;
 use valence_protocol::{BlockPos, ChunkPos, Ident};
@@ -46,6 +53,10 @@ impl Plugin for LayerPlugin {
 
         chunk::build(app);
         entity::build(app);
+        limits::build(app);
+        stats::build(app);
+        ticket::build(app);
+        unload::build(app);
     }
 }
 
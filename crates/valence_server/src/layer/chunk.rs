@@ -2,6 +2,7 @@
 mod chunk;
 pub mod loaded;
 mod paletted_container;
+pub mod snapshot;
 pub mod unloaded;
 
 use std::borrow::Cow;
@@ -13,8 +14,9 @@ use bevy_ecs::prelude::*;
 pub use chunk::{MAX_HEIGHT, *};
 pub use loaded::LoadedChunk;
 use rustc_hash::FxHashMap;
+pub use snapshot::ChunkLayerSnapshot;
 pub use unloaded::UnloadedChunk;
-use valence_math::{DVec3, Vec3};
+use valence_math::{Aabb, DVec3, IVec3, Vec3};
 use valence_nbt::Compound;
 use valence_protocol::encode::{PacketWriter, WritePacket};
 use valence_protocol::packets::play::particle_s2c::Particle;
@@ -27,7 +29,9 @@ use valence_server_common::Server;
 
 use super::bvh::GetChunkPos;
 use super::message::Messages;
+use super::ticket::{ChunkTicket, ChunkTickets, TicketId};
 use super::{Layer, UpdateLayersPostClientSet, UpdateLayersPreClientSet};
+use crate::effect_filter::EffectScope;
 
 /// A [`Component`] containing the [chunks](LoadedChunk) and [dimension
 /// information](valence_registry::dimension_type::DimensionTypeId) of a
@@ -37,6 +41,12 @@ pub struct ChunkLayer {
     messages: ChunkLayerMessages,
     chunks: FxHashMap<ChunkPos, LoadedChunk>,
     info: ChunkLayerInfo,
+    /// Explosions that have yet to affect entities.
+    pub(crate) explosions: Vec<crate::explosion::Explosion>,
+    pub(super) tickets: ChunkTickets,
+    /// The factor the volume of sounds is multiplied by, for each
+    /// [`SoundCategory`].
+    sound_volumes: [f32; SoundCategory::Voice as usize + 1],
 }
 
 /// Chunk layer information.
@@ -82,6 +92,12 @@ pub(crate) enum LocalMsg {
         pos: ChunkPos,
         except: Entity,
     },
+    /// Like [`LocalMsg::PacketAt`], but the packets change blocks. Clients
+    /// with a [`BlockOverlay`](crate::block_overlay::BlockOverlay) send their
+    /// overlaid blocks in the chunk again afterwards.
+    BlockChangeAt {
+        pos: ChunkPos,
+    },
     RadiusAt {
         center: BlockPos,
         radius_squared: u32,
@@ -104,6 +120,46 @@ pub(crate) enum LocalMsg {
     ChangeBiome {
         pos: ChunkPos,
     },
+    /// Send particle packets to clients in view of `center` which `scope`
+    /// allows, unless their
+    /// [`ParticleFilter`](crate::effect_filter::ParticleFilter) excludes them.
+    ParticleAt {
+        center: BlockPos,
+        scope: EffectScopeKey,
+    },
+    /// Send sound packets to clients in view of `center` which `scope` allows,
+    /// unless their [`SoundFilter`](crate::effect_filter::SoundFilter)
+    /// excludes them.
+    SoundAt {
+        center: BlockPos,
+        category: SoundCategory,
+        scope: EffectScopeKey,
+    },
+}
+
+/// An [`EffectScope`] in a form which can be sorted with the other messages.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) struct EffectScopeKey {
+    radius: Option<u32>,
+    except: Option<Entity>,
+}
+
+impl From<EffectScope> for EffectScopeKey {
+    fn from(scope: EffectScope) -> Self {
+        Self {
+            radius: scope.radius,
+            except: scope.except,
+        }
+    }
+}
+
+impl From<EffectScopeKey> for EffectScope {
+    fn from(key: EffectScopeKey) -> Self {
+        Self {
+            radius: key.radius,
+            except: key.except,
+        }
+    }
 }
 
 impl GetChunkPos for LocalMsg {
@@ -111,10 +167,13 @@ impl GetChunkPos for LocalMsg {
         match *self {
             LocalMsg::PacketAt { pos } => pos,
             LocalMsg::PacketAtExcept { pos, .. } => pos,
+            LocalMsg::BlockChangeAt { pos } => pos,
             LocalMsg::RadiusAt { center, .. } => center.into(),
             LocalMsg::RadiusAtExcept { center, .. } => center.into(),
             LocalMsg::ChangeBiome { pos } => pos,
             LocalMsg::ChangeChunkState { pos } => pos,
+            LocalMsg::ParticleAt { center, .. } => center.into(),
+            LocalMsg::SoundAt { center, .. } => center.into(),
         }
     }
 }
@@ -152,6 +211,9 @@ impl ChunkLayer {
                 biome_registry_len: biomes.iter().len(),
                 threshold: server.compression_threshold(),
             },
+            explosions: vec![],
+            tickets: ChunkTickets::default(),
+            sound_volumes: [1.0; SoundCategory::Voice as usize + 1],
         }
     }
 
@@ -230,6 +292,108 @@ impl ChunkLayer {
         });
     }
 
+    /// Copies all chunks in this layer into a [`ChunkLayerSnapshot`], which
+    /// can be restored with [`Self::restore`] as many times as needed.
+    pub fn snapshot(&self) -> ChunkLayerSnapshot {
+        ChunkLayerSnapshot::new(
+            self.info.height,
+            self.chunks
+                .iter()
+                .map(|(pos, chunk)| (*pos, chunk.to_unloaded()))
+                .collect(),
+        )
+    }
+
+    /// Replaces all chunks in this layer with the chunks in `snapshot`.
+    /// Chunks are [resized] to the height of this layer if the snapshot was
+    /// taken from a layer with a different height.
+    ///
+    /// [resized]: UnloadedChunk::set_height
+    pub fn restore(&mut self, snapshot: &ChunkLayerSnapshot) {
+        self.retain_chunks(|pos, _| snapshot.chunk(pos).is_some());
+
+        for (pos, chunk) in snapshot.chunks() {
+            self.insert_chunk(pos, chunk.clone());
+        }
+    }
+
+    /// Copies the chunks [modified] since the last call into a
+    /// [`ChunkLayerSnapshot`] and marks them as unmodified. This is useful for
+    /// periodic backups of large worlds, since only the changed chunks need
+    /// to be written.
+    ///
+    /// Removed chunks aren't tracked, so make sure to save modified chunks
+    /// before unloading them.
+    ///
+    /// [modified]: LoadedChunk::is_modified
+    pub fn take_modified_chunks(&mut self) -> ChunkLayerSnapshot {
+        ChunkLayerSnapshot::new(
+            self.info.height,
+            self.chunks
+                .iter_mut()
+                .filter(|(_, chunk)| chunk.is_modified())
+                .map(|(pos, chunk)| {
+                    chunk.set_modified(false);
+                    (*pos, chunk.to_unloaded())
+                })
+                .collect(),
+        )
+    }
+
+    /// Inserts the chunks in `snapshot` into this layer, replacing the chunks
+    /// at the same positions. Unlike [`Self::restore`], the other chunks are
+    /// kept. This is meant for applying the changes from
+    /// [`Self::take_modified_chunks`] on top of an older copy of the layer.
+    pub fn apply_diff(&mut self, diff: &ChunkLayerSnapshot) {
+        for (pos, chunk) in diff.chunks() {
+            self.insert_chunk(pos, chunk.clone());
+        }
+    }
+
+    /// Adds a [ticket](super::ticket) keeping the chunks within `radius` chunks
+    /// of `pos` from being unloaded, and simulated as if a client were nearby.
+    /// The ticket covers a square of `2 * radius + 1` chunks on each side. It
+    /// expires after `ttl` ticks, or lasts until it's removed if `ttl` is
+    /// `None`.
+    pub fn add_ticket<P: Into<ChunkPos>>(
+        &mut self,
+        pos: P,
+        radius: u32,
+        ttl: Option<u32>,
+    ) -> TicketId {
+        self.tickets.add(pos.into(), radius, ttl)
+    }
+
+    /// Removes the ticket with the given ID. Returns the ticket if it hadn't
+    /// expired yet.
+    pub fn remove_ticket(&mut self, id: TicketId) -> Option<ChunkTicket> {
+        self.tickets.remove(id)
+    }
+
+    /// Returns the ticket with the given ID, if it hasn't expired or been
+    /// removed.
+    pub fn ticket(&self, id: TicketId) -> Option<&ChunkTicket> {
+        self.tickets.get(id)
+    }
+
+    /// Returns an iterator over the tickets in this layer. The order is
+    /// undefined.
+    pub fn tickets(&self) -> impl Iterator<Item = (TicketId, &ChunkTicket)> + '_ {
+        self.tickets.iter()
+    }
+
+    /// Returns whether the chunk at `pos` is covered by a ticket. The chunk
+    /// doesn't need to be loaded.
+    pub fn has_ticket<P: Into<ChunkPos>>(&self, pos: P) -> bool {
+        self.tickets.contains(pos.into())
+    }
+
+    /// Returns an iterator over the positions of the chunks covered by a
+    /// ticket, loaded or not. The order is undefined.
+    pub fn ticketed_chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.tickets.positions()
+    }
+
     /// Get a [`ChunkEntry`] for the given position.
     pub fn chunk_entry<P: Into<ChunkPos>>(&mut self, pos: P) -> ChunkEntry {
         match self.chunks.entry(pos.into()) {
@@ -245,6 +409,17 @@ impl ChunkLayer {
         }
     }
 
+    /// Returns the number of loaded chunks in the instance.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns an estimate of the number of bytes of memory used by the loaded
+    /// chunks in the instance. See [`LoadedChunk::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        self.chunks.values().map(LoadedChunk::memory_usage).sum()
+    }
+
     /// Get an iterator over all loaded chunks in the instance. The order of the
     /// chunks is undefined.
     pub fn chunks(&self) -> impl Iterator<Item = (ChunkPos, &LoadedChunk)> + Clone + '_ {
@@ -311,6 +486,58 @@ impl ChunkLayer {
         Some(chunk.set_block(x, y, z, block))
     }
 
+    /// Sets many blocks at once, looking up each chunk only once. Blocks
+    /// outside of the loaded chunks or the world height are skipped. Returns
+    /// the number of blocks which were set.
+    ///
+    /// Like with [`Self::set_block`], the changes to each chunk section are
+    /// sent to clients at the end of the tick, as a single block update
+    /// packet if one block changed or a multi block change packet otherwise.
+    pub fn batch_block_updates<I, P, B>(&mut self, blocks: I) -> usize
+    where
+        I: IntoIterator<Item = (P, B)>,
+        P: Into<BlockPos>,
+        B: IntoBlock,
+    {
+        let mut by_chunk: FxHashMap<ChunkPos, Vec<(u32, u32, u32, B)>> = FxHashMap::default();
+
+        for (pos, block) in blocks {
+            let pos = pos.into();
+
+            let Some(y) = pos
+                .y
+                .checked_sub(self.info.min_y)
+                .and_then(|y| u32::try_from(y).ok())
+                .filter(|&y| y < self.info.height)
+            else {
+                continue;
+            };
+
+            let x = pos.x.rem_euclid(16) as u32;
+            let z = pos.z.rem_euclid(16) as u32;
+
+            by_chunk
+                .entry(ChunkPos::from(pos))
+                .or_default()
+                .push((x, y, z, block));
+        }
+
+        let mut count = 0;
+
+        for (pos, blocks) in by_chunk {
+            let Some(chunk) = self.chunks.get_mut(&pos) else {
+                continue;
+            };
+
+            for (x, y, z, block) in blocks {
+                chunk.set_block(x, y, z, block);
+                count += 1;
+            }
+        }
+
+        count
+    }
+
     pub fn block_entity_mut<P: Into<BlockPos>>(&mut self, pos: P) -> Option<&mut Compound> {
         let pos = pos.into();
 
@@ -331,6 +558,236 @@ impl ChunkLayer {
         chunk.block_entity_mut(x, y, z)
     }
 
+    /// Returns an iterator over the block entities in loaded chunks along with
+    /// their positions. The order of the block entities is undefined.
+    pub fn block_entities(&self) -> impl Iterator<Item = (BlockPos, &Compound)> + '_ {
+        let min_y = self.info.min_y;
+
+        self.chunks().flat_map(move |(pos, chunk)| {
+            chunk.block_entities().map(move |([x, y, z], nbt)| {
+                let pos = BlockPos::new(
+                    pos.x * 16 + x as i32,
+                    min_y + y as i32,
+                    pos.z * 16 + z as i32,
+                );
+
+                (pos, nbt)
+            })
+        })
+    }
+
+    /// Returns an iterator over the blocks overlapping `aabb` in loaded chunks
+    /// along with their positions. Blocks are visited chunk by chunk, so this
+    /// is faster than calling [`Self::block`] for every position in a region.
+    pub fn blocks_in(&self, aabb: Aabb) -> impl Iterator<Item = (BlockPos, BlockRef)> + '_ {
+        let min_y = self.info.min_y;
+        let (min, max) = self.block_bounds(aabb);
+
+        let min_chunk = ChunkPos::new(min.x.div_euclid(16), min.z.div_euclid(16));
+        let max_chunk = ChunkPos::new(max.x.div_euclid(16), max.z.div_euclid(16));
+
+        (min_chunk.x..=max_chunk.x)
+            .flat_map(move |x| (min_chunk.z..=max_chunk.z).map(move |z| ChunkPos::new(x, z)))
+            .filter_map(move |pos| Some((pos, self.chunk(pos)?)))
+            .flat_map(move |(pos, chunk)| {
+                let (base_x, base_z) = (pos.x * 16, pos.z * 16);
+                let (min_x, max_x) = (min.x.max(base_x), max.x.min(base_x + 15));
+                let (min_z, max_z) = (min.z.max(base_z), max.z.min(base_z + 15));
+
+                (min.y..=max.y).flat_map(move |y| {
+                    (min_z..=max_z).flat_map(move |z| {
+                        (min_x..=max_x).map(move |x| {
+                            let block = chunk.block(
+                                (x - base_x) as u32,
+                                (y - min_y) as u32,
+                                (z - base_z) as u32,
+                            );
+
+                            (BlockPos::new(x, y, z), block)
+                        })
+                    })
+                })
+            })
+    }
+
+    /// Returns the number of blocks of each block state overlapping `aabb` in
+    /// loaded chunks. Sections entirely inside `aabb` are counted from their
+    /// palettes, so this is much faster than [`Self::blocks_in`] for large
+    /// regions.
+    pub fn block_histogram(&self, aabb: Aabb) -> BlockHistogram {
+        let mut histogram = BlockHistogram::new();
+
+        let min_y = self.info.min_y;
+        let (min, max) = self.block_bounds(aabb);
+
+        if min.y > max.y {
+            return histogram;
+        }
+
+        let min_chunk = ChunkPos::new(min.x.div_euclid(16), min.z.div_euclid(16));
+        let max_chunk = ChunkPos::new(max.x.div_euclid(16), max.z.div_euclid(16));
+
+        let min_sect_y = (min.y - min_y) as u32 / 16;
+        let max_sect_y = (max.y - min_y) as u32 / 16;
+
+        for chunk_x in min_chunk.x..=max_chunk.x {
+            for chunk_z in min_chunk.z..=max_chunk.z {
+                let Some(chunk) = self.chunk([chunk_x, chunk_z]) else {
+                    continue;
+                };
+
+                let (base_x, base_z) = (chunk_x * 16, chunk_z * 16);
+                let (min_x, max_x) = (min.x.max(base_x), max.x.min(base_x + 15));
+                let (min_z, max_z) = (min.z.max(base_z), max.z.min(base_z + 15));
+
+                let whole_columns = min_x == base_x
+                    && max_x == base_x + 15
+                    && min_z == base_z
+                    && max_z == base_z + 15;
+
+                for sect_y in min_sect_y..=max_sect_y {
+                    let sect_min_y = min_y + sect_y as i32 * 16;
+                    let sect_max_y = sect_min_y + 15;
+
+                    if whole_columns && min.y <= sect_min_y && max.y >= sect_max_y {
+                        chunk.count_section_block_states(sect_y, &mut histogram);
+                        continue;
+                    }
+
+                    for y in min.y.max(sect_min_y)..=max.y.min(sect_max_y) {
+                        for z in min_z..=max_z {
+                            for x in min_x..=max_x {
+                                let state = chunk.block_state(
+                                    (x - base_x) as u32,
+                                    (y - min_y) as u32,
+                                    (z - base_z) as u32,
+                                );
+
+                                histogram.add(state, 1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        histogram
+    }
+
+    /// Returns the positions of the first and last blocks overlapping `aabb`,
+    /// with the Y coordinates clamped to the height of the layer.
+    fn block_bounds(&self, aabb: Aabb) -> (IVec3, IVec3) {
+        let min_y = self.info.min_y;
+
+        let mut min = aabb.min().floor().as_ivec3();
+        let mut max = aabb.max().ceil().as_ivec3() - IVec3::ONE;
+
+        min.y = min.y.max(min_y);
+        max.y = max.y.min(min_y + self.info.height as i32 - 1);
+
+        (min, max)
+    }
+
+    /// Calls `f` for every block overlapping `aabb` in loaded chunks. See
+    /// [`Self::blocks_in`].
+    pub fn for_each_block_in<F>(&self, aabb: Aabb, mut f: F)
+    where
+        F: FnMut(BlockPos, BlockRef),
+    {
+        for (pos, block) in self.blocks_in(aabb) {
+            f(pos, block);
+        }
+    }
+
+    /// Returns the positions of the blocks overlapping `aabb` in loaded chunks
+    /// for which `predicate` returns `true`. See [`Self::blocks_in`].
+    pub fn find_blocks<'a, F>(
+        &'a self,
+        aabb: Aabb,
+        mut predicate: F,
+    ) -> impl Iterator<Item = BlockPos> + 'a
+    where
+        F: FnMut(BlockRef) -> bool + 'a,
+    {
+        self.blocks_in(aabb)
+            .filter_map(move |(pos, block)| predicate(block).then_some(pos))
+    }
+
+    /// Returns how far a box can move by `movement` before it collides with
+    /// the collision shapes of blocks. Like vanilla, the box is moved along the
+    /// Y axis first, then X, then Z. Unloaded chunks have no collisions.
+    pub fn collide(&self, aabb: Aabb, movement: DVec3) -> DVec3 {
+        let region = aabb.union(aabb + movement);
+        // Some blocks, such as fences, are taller than a full block.
+        let min = region.min().floor().as_ivec3() - IVec3::Y;
+        let max = region.max().floor().as_ivec3();
+
+        let mut shapes = vec![];
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if let Some(block) = self.block([x, y, z]) {
+                        let offset = DVec3::new(f64::from(x), f64::from(y), f64::from(z));
+                        shapes.extend(block.state.collision_shapes().map(|s| s + offset));
+                    }
+                }
+            }
+        }
+
+        let mut aabb = aabb;
+        let mut result = DVec3::ZERO;
+
+        for axis in [1, 0, 2] {
+            let mut offset = movement[axis];
+
+            for &shape in &shapes {
+                offset = clip_axis(aabb, shape, axis, offset);
+            }
+
+            result[axis] = offset;
+
+            let mut delta = DVec3::ZERO;
+            delta[axis] = offset;
+            aabb = aabb + delta;
+        }
+
+        result
+    }
+
+    /// Returns whether `aabb` overlaps the collision shapes of blocks. Boxes
+    /// only touching a shape don't collide with it. Unloaded chunks have no
+    /// collisions.
+    pub fn collides(&self, aabb: Aabb) -> bool {
+        // Some blocks, such as fences, are taller than a full block.
+        let min = aabb.min().floor().as_ivec3() - IVec3::Y;
+        let max = aabb.max().floor().as_ivec3();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let Some(block) = self.block([x, y, z]) else {
+                        continue;
+                    };
+
+                    let offset = DVec3::new(f64::from(x), f64::from(y), f64::from(z));
+
+                    let overlaps = block.state.collision_shapes().any(|shape| {
+                        let shape = shape + offset;
+
+                        aabb.min().cmplt(shape.max()).all() && shape.min().cmplt(aabb.max()).all()
+                    });
+
+                    if overlaps {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
     pub fn biome<P: Into<BiomePos>>(&self, pos: P) -> Option<BiomeId> {
         let pos = pos.into();
 
@@ -375,14 +832,53 @@ impl ChunkLayer {
         &self.info
     }
 
+    /// Writes the packets initializing the chunk at `pos` for a client that
+    /// starts viewing it, serializing the chunk again instead of using the
+    /// cached packets. Returns `false` if the chunk isn't loaded.
+    ///
+    /// Only available with the `bench` feature, to benchmark chunk
+    /// serialization.
+    #[cfg(feature = "bench")]
+    #[doc(hidden)]
+    pub fn write_uncached_chunk_init_packets(
+        &mut self,
+        pos: impl Into<ChunkPos>,
+        writer: impl WritePacket,
+    ) -> bool {
+        let pos = pos.into();
+
+        let Some(chunk) = self.chunks.get_mut(&pos) else {
+            return false;
+        };
+
+        chunk.clear_cached_init_packets();
+        chunk.write_init_packets(writer, pos, &self.info, None);
+
+        true
+    }
+
     pub(crate) fn messages(&self) -> &ChunkLayerMessages {
         &self.messages
     }
 
+    /// Returns the factor the volume of sounds in `category` is multiplied by.
+    /// See [`Self::set_sound_volume`].
+    pub fn sound_volume(&self, category: SoundCategory) -> f32 {
+        self.sound_volumes[category as usize]
+    }
+
+    /// Sets the factor the volume of sounds in `category` played in this layer
+    /// is multiplied by, like the volume sliders of the client. The default is
+    /// `1.0`, and `0.0` silences the category.
+    pub fn set_sound_volume(&mut self, category: SoundCategory, volume: f32) {
+        self.sound_volumes[category as usize] = volume.max(0.0);
+    }
+
     // TODO: move to `valence_particle`.
     /// Puts a particle effect at the given position in the world. The particle
     /// effect is visible to all players in the instance with the
-    /// appropriate chunk in view.
+    /// appropriate chunk in view, unless their
+    /// [`ParticleFilter`](crate::effect_filter::ParticleFilter) excludes it.
     pub fn play_particle<P, O>(
         &mut self,
         particle: &Particle,
@@ -394,10 +890,44 @@ impl ChunkLayer {
     ) where
         P: Into<DVec3>,
         O: Into<Vec3>,
+    {
+        self.play_particle_scoped(
+            particle,
+            long_distance,
+            position,
+            offset,
+            max_speed,
+            count,
+            EffectScope::default(),
+        );
+    }
+
+    /// Like [`Self::play_particle`], but only the players in view which `scope`
+    /// allows see the particle effect.
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_particle_scoped<P, O>(
+        &mut self,
+        particle: &Particle,
+        long_distance: bool,
+        position: P,
+        offset: O,
+        max_speed: f32,
+        count: i32,
+        scope: EffectScope,
+    ) where
+        P: Into<DVec3>,
+        O: Into<Vec3>,
     {
         let position = position.into();
 
-        self.view_writer(position).write_packet(&ParticleS2c {
+        MessageWriter {
+            layer: self,
+            msg: LocalMsg::ParticleAt {
+                center: position.into(),
+                scope: scope.into(),
+            },
+        }
+        .write_packet(&ParticleS2c {
             particle: Cow::Borrowed(particle),
             long_distance,
             position,
@@ -410,7 +940,11 @@ impl ChunkLayer {
     // TODO: move to `valence_sound`.
     /// Plays a sound effect at the given position in the world. The sound
     /// effect is audible to all players in the instance with the
-    /// appropriate chunk in view.
+    /// appropriate chunk in view, unless their
+    /// [`SoundFilter`](crate::effect_filter::SoundFilter) excludes it.
+    ///
+    /// The volume is scaled by the [volume of the
+    /// category](Self::set_sound_volume).
     pub fn play_sound<P: Into<DVec3>>(
         &mut self,
         sound: Sound,
@@ -418,10 +952,40 @@ impl ChunkLayer {
         position: P,
         volume: f32,
         pitch: f32,
+    ) {
+        self.play_sound_scoped(
+            sound,
+            category,
+            position,
+            volume,
+            pitch,
+            EffectScope::default(),
+        );
+    }
+
+    /// Like [`Self::play_sound`], but only the players in view which `scope`
+    /// allows hear the sound effect.
+    pub fn play_sound_scoped<P: Into<DVec3>>(
+        &mut self,
+        sound: Sound,
+        category: SoundCategory,
+        position: P,
+        volume: f32,
+        pitch: f32,
+        scope: EffectScope,
     ) {
         let position = position.into();
+        let volume = volume * self.sound_volume(category);
 
-        self.view_writer(position).write_packet(&PlaySoundS2c {
+        MessageWriter {
+            layer: self,
+            msg: LocalMsg::SoundAt {
+                center: position.into(),
+                category,
+                scope: scope.into(),
+            },
+        }
+        .write_packet(&PlaySoundS2c {
             id: SoundId::Direct {
                 id: sound.to_ident().into(),
                 range: None,
@@ -435,6 +999,26 @@ impl ChunkLayer {
     }
 }
 
+/// Limits `offset` along `axis` so that `aabb` doesn't move into `shape`.
+fn clip_axis(aabb: Aabb, shape: Aabb, axis: usize, offset: f64) -> f64 {
+    // The boxes can only collide if they overlap on the other two axes.
+    for other in 0..3 {
+        if other != axis
+            && (aabb.max()[other] <= shape.min()[other] || aabb.min()[other] >= shape.max()[other])
+        {
+            return offset;
+        }
+    }
+
+    if offset > 0.0 && aabb.max()[axis] <= shape.min()[axis] {
+        offset.min(shape.min()[axis] - aabb.max()[axis])
+    } else if offset < 0.0 && aabb.min()[axis] >= shape.max()[axis] {
+        offset.max(shape.max()[axis] - aabb.min()[axis])
+    } else {
+        offset
+    }
+}
+
 impl Layer for ChunkLayer {
     type ExceptWriter<'a> = ExceptWriter<'a>;
 
@@ -543,6 +1127,29 @@ impl WritePacket for ExceptWriter<'_> {
     }
 }
 
+/// Writes packets to the layer as a specific local message.
+struct MessageWriter<'a> {
+    layer: &'a mut ChunkLayer,
+    msg: LocalMsg,
+}
+
+impl WritePacket for MessageWriter<'_> {
+    fn write_packet_fallible<P>(&mut self, packet: &P) -> anyhow::Result<()>
+    where
+        P: Packet + Encode,
+    {
+        self.layer.messages.send_local(self.msg, |b| {
+            PacketWriter::new(b, self.layer.info.threshold).write_packet_fallible(packet)
+        })
+    }
+
+    fn write_packet_bytes(&mut self, bytes: &[u8]) {
+        self.layer
+            .messages
+            .send_local_infallible(self.msg, |b| b.extend_from_slice(bytes));
+    }
+}
+
 pub struct ViewWriter<'a> {
     layer: &'a mut ChunkLayer,
     pos: ChunkPos,
@@ -695,7 +1302,14 @@ impl<'a> OccupiedChunkEntry<'a> {
         self.entry.get_mut()
     }
 
+    /// Replaces the chunk. Viewers are sent the blocks which changed if there
+    /// are few enough of them, or the whole chunk otherwise.
     pub fn insert(&mut self, chunk: UnloadedChunk) -> UnloadedChunk {
+        let chunk = match self.entry.get_mut().try_insert_as_changes(chunk) {
+            Ok(old) => return old,
+            Err(chunk) => chunk,
+        };
+
         self.messages.send_local_infallible(
             LocalMsg::ChangeChunkState {
                 pos: *self.entry.key(),
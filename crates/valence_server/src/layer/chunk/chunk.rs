@@ -1,5 +1,6 @@
+use rustc_hash::FxHashMap;
 use valence_nbt::Compound;
-use valence_protocol::BlockState;
+use valence_protocol::{BlockKind, BlockState};
 use valence_registry::biome::BiomeId;
 
 use super::paletted_container::PalettedContainer;
@@ -107,6 +108,35 @@ pub trait Chunk {
     #[track_caller]
     fn fill_block_state_section(&mut self, sect_y: u32, block: BlockState);
 
+    /// Returns the number of blocks of each block state in the entire chunk.
+    fn block_histogram(&self) -> BlockHistogram {
+        let mut histogram = BlockHistogram::new();
+
+        for sect_y in 0..self.height() / 16 {
+            self.count_section_block_states(sect_y, &mut histogram);
+        }
+
+        histogram
+    }
+
+    /// Adds the block states in a section to `histogram`. Implementors should
+    /// count from the palette of the section instead of looking at every
+    /// block.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the section offset is out of bounds.
+    #[track_caller]
+    fn count_section_block_states(&self, sect_y: u32, histogram: &mut BlockHistogram) {
+        for y in sect_y * 16..sect_y * 16 + 16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    histogram.add(self.block_state(x, y, z), 1);
+                }
+            }
+        }
+    }
+
     /// Gets the block entity at the provided position in this chunk. `x` and
     /// `z` are in the range `0..16` while `y` is in the range `0..height`.
     ///
@@ -238,6 +268,68 @@ impl<'a> BlockRef<'a> {
     }
 }
 
+/// The number of blocks of each [`BlockState`] in a chunk or region. See
+/// [`Chunk::block_histogram`] and
+/// [`ChunkLayer::block_histogram`](crate::ChunkLayer::block_histogram).
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct BlockHistogram {
+    counts: FxHashMap<BlockState, u64>,
+}
+
+impl BlockHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of blocks with the given state.
+    pub fn get(&self, state: BlockState) -> u64 {
+        self.counts.get(&state).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of blocks of the given kind in any state.
+    pub fn get_kind(&self, kind: BlockKind) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(state, _)| state.to_kind() == kind)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns the number of blocks that aren't air.
+    pub fn non_air(&self) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(state, _)| !state.is_air())
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns the number of blocks counted.
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Adds `count` blocks with the given state.
+    pub fn add(&mut self, state: BlockState, count: u64) {
+        if count > 0 {
+            *self.counts.entry(state).or_insert(0) += count;
+        }
+    }
+
+    /// Adds the blocks counted in `other` to this histogram.
+    pub fn merge(&mut self, other: &BlockHistogram) {
+        for (state, count) in other.iter() {
+            self.add(state, count);
+        }
+    }
+
+    /// Returns an iterator over the block states counted at least once along
+    /// with their counts, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockState, u64)> + '_ {
+        self.counts.iter().map(|(state, count)| (*state, *count))
+    }
+}
+
 pub trait IntoBlock {
     // TODO: parameterize this with block registry ref?
     fn into_block(self) -> Block;
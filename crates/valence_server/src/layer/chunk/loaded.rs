@@ -6,7 +6,7 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use parking_lot::Mutex; // Using nonstandard mutex to avoid poisoning API.
 use valence_generated::block::{PropName, PropValue};
 use valence_nbt::{compound, Compound, Value};
-use valence_protocol::encode::{PacketWriter, WritePacket};
+use valence_protocol::encode::{PacketPriority, PacketWriter, WritePacket};
 use valence_protocol::packets::play::chunk_data_s2c::ChunkDataBlockEntity;
 use valence_protocol::packets::play::chunk_delta_update_s2c::ChunkDeltaUpdateEntry;
 use valence_protocol::packets::play::{
@@ -17,12 +17,13 @@ use valence_registry::biome::BiomeId;
 use valence_registry::RegistryIdx;
 
 use super::chunk::{
-    bit_width, check_biome_oob, check_block_oob, check_section_oob, BiomeContainer,
-    BlockStateContainer, Chunk, SECTION_BLOCK_COUNT,
+    bit_width, check_biome_oob, check_block_oob, check_section_oob, BiomeContainer, BlockHistogram,
+    BlockStateContainer, Chunk, SECTION_BIOME_COUNT, SECTION_BLOCK_COUNT,
 };
 use super::paletted_container::PalettedContainer;
 use super::unloaded::{self, UnloadedChunk};
 use super::{ChunkLayerInfo, ChunkLayerMessages, LocalMsg};
+use crate::biome_override::BiomeOverride;
 
 #[derive(Debug)]
 pub struct LoadedChunk {
@@ -42,6 +43,9 @@ pub struct LoadedChunk {
     /// invalidated if empty. This should be cleared whenever the chunk is
     /// modified in an observable way, even if the chunk is not viewed.
     cached_init_packets: Mutex<Vec<u8>>,
+    /// If the chunk was modified since it was last saved. Unlike the changes
+    /// above, this isn't cleared every tick.
+    modified: bool,
 }
 
 #[derive(Clone, Default, Debug)]
@@ -51,6 +55,10 @@ struct Section {
     /// Contains modifications for the update section packet. (Or the regular
     /// block update packet if len == 1).
     updates: Vec<ChunkDeltaUpdateEntry>,
+    /// If the section was modified since the chunk was last marked as
+    /// unmodified. Unlike `updates`, this is tracked even if the chunk isn't
+    /// viewed.
+    dirty: bool,
 }
 
 impl Section {
@@ -80,6 +88,26 @@ impl Section {
         }
         count
     }
+
+    /// Encodes the biomes of this section with the palette rewritten by
+    /// `map_biome`. Mapping several biomes to the same one may leave duplicates
+    /// in the palette, which the client doesn't mind.
+    fn encode_biomes(
+        &self,
+        buf: &mut Vec<u8>,
+        info: &ChunkLayerInfo,
+        mut map_biome: impl FnMut(BiomeId) -> BiomeId,
+    ) {
+        self.biomes
+            .encode_mc_format(
+                buf,
+                |b| map_biome(b).to_index() as u64,
+                0,
+                3,
+                bit_width(info.biome_registry_len - 1),
+            )
+            .expect("paletted container encode should always succeed");
+    }
 }
 
 impl LoadedChunk {
@@ -91,6 +119,7 @@ impl LoadedChunk {
             changed_block_entities: BTreeSet::new(),
             changed_biomes: false,
             cached_init_packets: Mutex::new(vec![]),
+            modified: false,
         }
     }
 
@@ -110,6 +139,7 @@ impl LoadedChunk {
             .zip(chunk.sections)
             .map(|(sect, other_sect)| {
                 sect.updates.clear();
+                sect.dirty = true;
 
                 unloaded::Section {
                     block_states: mem::replace(&mut sect.block_states, other_sect.block_states),
@@ -121,6 +151,7 @@ impl LoadedChunk {
         self.changed_block_entities.clear();
         self.changed_biomes = false;
         self.cached_init_packets.get_mut().clear();
+        self.modified = true;
         self.assert_no_changes();
 
         UnloadedChunk {
@@ -155,6 +186,166 @@ impl LoadedChunk {
         }
     }
 
+    /// Like [`Self::insert`], but the differences to the current contents are
+    /// recorded as block, block entity and biome changes, so viewers don't
+    /// need to be sent the whole chunk again. Only the sections which differ
+    /// become [dirty].
+    ///
+    /// If the chunk isn't viewed or too many blocks differ for the changes to
+    /// be smaller than the chunk, nothing happens and the chunk is returned as
+    /// the error.
+    ///
+    /// [dirty]: Self::is_section_dirty
+    pub(crate) fn try_insert_as_changes(
+        &mut self,
+        mut chunk: UnloadedChunk,
+    ) -> Result<UnloadedChunk, UnloadedChunk> {
+        if *self.viewer_count.get_mut() == 0 {
+            return Err(chunk);
+        }
+
+        chunk.set_height(self.height());
+
+        let mut changed_blocks = 0;
+
+        for (sect, other_sect) in self.sections.iter().zip(chunk.sections.iter()) {
+            changed_blocks += changed_blocks_iter(&sect.block_states, &other_sect.block_states)
+                .take(MAX_OVERWRITE_BLOCK_CHANGES + 1)
+                .count();
+
+            if changed_blocks > MAX_OVERWRITE_BLOCK_CHANGES {
+                return Err(chunk);
+            }
+        }
+
+        let mut modified = false;
+
+        for (sect, other_sect) in self.sections.iter_mut().zip(chunk.sections.iter()) {
+            for (idx, block) in changed_blocks_iter(&sect.block_states, &other_sect.block_states) {
+                sect.updates.push(
+                    ChunkDeltaUpdateEntry::new()
+                        .with_off_x((idx % 16) as u8)
+                        .with_off_y((idx / 16 / 16) as u8)
+                        .with_off_z((idx / 16 % 16) as u8)
+                        .with_block_state(block.to_raw().into()),
+                );
+                sect.dirty = true;
+            }
+
+            if (0..SECTION_BIOME_COUNT).any(|i| sect.biomes.get(i) != other_sect.biomes.get(i)) {
+                self.changed_biomes = true;
+                sect.dirty = true;
+            }
+
+            modified |= sect.dirty;
+        }
+
+        for (&idx, nbt) in &chunk.block_entities {
+            if self.block_entities.get(&idx) != Some(nbt) {
+                self.changed_block_entities.insert(idx);
+                self.sections[idx as usize / SECTION_BLOCK_COUNT].dirty = true;
+                modified = true;
+            }
+        }
+
+        // Removed block entities only need to be saved. Viewers remove them along
+        // with their block.
+        for &idx in self.block_entities.keys() {
+            if !chunk.block_entities.contains_key(&idx) {
+                self.sections[idx as usize / SECTION_BLOCK_COUNT].dirty = true;
+                modified = true;
+            }
+        }
+
+        if modified {
+            self.cached_init_packets.get_mut().clear();
+            self.modified = true;
+        }
+
+        let old_sections = self
+            .sections
+            .iter_mut()
+            .zip(chunk.sections)
+            .map(|(sect, other_sect)| unloaded::Section {
+                block_states: mem::replace(&mut sect.block_states, other_sect.block_states),
+                biomes: mem::replace(&mut sect.biomes, other_sect.biomes),
+            })
+            .collect();
+        let old_block_entities = mem::replace(&mut self.block_entities, chunk.block_entities);
+
+        Ok(UnloadedChunk {
+            sections: old_sections,
+            block_entities: old_block_entities,
+        })
+    }
+
+    /// Returns a copy of the blocks, biomes and block entities in this chunk.
+    pub fn to_unloaded(&self) -> UnloadedChunk {
+        UnloadedChunk {
+            sections: self
+                .sections
+                .iter()
+                .map(|sect| unloaded::Section {
+                    block_states: sect.block_states.clone(),
+                    biomes: sect.biomes.clone(),
+                })
+                .collect(),
+            block_entities: self.block_entities.clone(),
+        }
+    }
+
+    /// Returns whether the blocks, biomes or block entities of this chunk
+    /// changed since it was inserted into the layer or last marked as
+    /// unmodified with [`Self::set_modified`]. Inserting a chunk counts as a
+    /// modification.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Sets whether this chunk counts as modified. Set this to `false` after
+    /// saving the chunk or when it's freshly loaded from disk, so only chunks
+    /// changed since then are saved again. This also clears the [dirty
+    /// sections](Self::dirty_sections) when set to `false`.
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+
+        if !modified {
+            self.clear_dirty_sections();
+        }
+    }
+
+    /// Returns whether the blocks, biomes or block entities in the section at
+    /// `sect_y` changed since the chunk was inserted or its dirty sections were
+    /// last cleared. Useful for only saving the parts of a chunk which changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sect_y` is out of bounds.
+    #[track_caller]
+    pub fn is_section_dirty(&self, sect_y: u32) -> bool {
+        check_section_oob(self, sect_y);
+
+        self.sections[sect_y as usize].dirty
+    }
+
+    /// Returns the indices of the [dirty](Self::is_section_dirty) sections,
+    /// from the bottom up.
+    pub fn dirty_sections(&self) -> impl Iterator<Item = u32> + Clone + '_ {
+        self.sections
+            .iter()
+            .enumerate()
+            .filter(|(_, sect)| sect.dirty)
+            .map(|(sect_y, _)| sect_y as u32)
+    }
+
+    /// Marks all sections as clean. Unlike [`Self::set_modified`], this
+    /// doesn't change whether the chunk counts as modified.
+    pub fn clear_dirty_sections(&mut self) {
+        for sect in self.sections.iter_mut() {
+            sect.dirty = false;
+        }
+    }
+
     /// Returns the number of clients in view of this chunk.
     pub fn viewer_count(&self) -> u32 {
         self.viewer_count.load(Ordering::Relaxed)
@@ -165,6 +356,35 @@ impl LoadedChunk {
         *self.viewer_count.get_mut()
     }
 
+    /// Returns an iterator over the block entities in this chunk along with
+    /// their `[x, y, z]` positions in the chunk, ordered by position.
+    pub fn block_entities(&self) -> impl Iterator<Item = ([u32; 3], &Compound)> + Clone + '_ {
+        self.block_entities
+            .iter()
+            .map(|(&idx, nbt)| ([idx % 16, idx / 16 / 16, idx / 16 % 16], nbt))
+    }
+
+    /// Returns an estimate of the number of bytes of memory used by this
+    /// chunk. The contents of block entities are not included.
+    pub fn memory_usage(&self) -> usize {
+        let sections: usize = self
+            .sections
+            .iter()
+            .map(|sect| {
+                mem::size_of::<Section>()
+                    + sect.block_states.heap_size()
+                    + sect.biomes.heap_size()
+                    + sect.updates.capacity() * mem::size_of::<ChunkDeltaUpdateEntry>()
+            })
+            .sum();
+
+        mem::size_of::<Self>()
+            + sections
+            + self.block_entities.len() * mem::size_of::<(u32, Compound)>()
+            + self.changed_block_entities.len() * mem::size_of::<u32>()
+            + self.cached_init_packets.lock().capacity()
+    }
+
     /// Increments the viewer count.
     pub(crate) fn inc_viewer_count(&self) {
         self.viewer_count.fetch_add(1, Ordering::Relaxed);
@@ -196,6 +416,8 @@ impl LoadedChunk {
 
         // Block states
         for (sect_y, sect) in self.sections.iter_mut().enumerate() {
+            dedup_updates(&mut sect.updates);
+
             match sect.updates.as_slice() {
                 &[] => {}
                 &[entry] => {
@@ -203,7 +425,7 @@ impl LoadedChunk {
                     let global_y = info.min_y + sect_y as i32 * 16 + i32::from(entry.off_y());
                     let global_z = pos.z * 16 + i32::from(entry.off_z());
 
-                    messages.send_local_infallible(LocalMsg::PacketAt { pos }, |buf| {
+                    messages.send_local_infallible(LocalMsg::BlockChangeAt { pos }, |buf| {
                         let mut writer = PacketWriter::new(buf, info.threshold);
 
                         writer.write_packet(&BlockUpdateS2c {
@@ -219,7 +441,7 @@ impl LoadedChunk {
                         z: pos.z,
                     };
 
-                    messages.send_local_infallible(LocalMsg::PacketAt { pos }, |buf| {
+                    messages.send_local_infallible(LocalMsg::BlockChangeAt { pos }, |buf| {
                         let mut writer = PacketWriter::new(buf, info.threshold);
 
                         writer.write_packet(&ChunkDeltaUpdateS2c {
@@ -273,17 +495,7 @@ impl LoadedChunk {
             self.changed_biomes = false;
 
             messages.send_local_infallible(LocalMsg::ChangeBiome { pos }, |buf| {
-                for sect in &self.sections {
-                    sect.biomes
-                        .encode_mc_format(
-                            &mut *buf,
-                            |b| b.to_index() as u64,
-                            0,
-                            3,
-                            bit_width(info.biome_registry_len - 1),
-                        )
-                        .expect("paletted container encode should always succeed");
-                }
+                self.encode_biomes(buf, info, |b| b);
             });
         }
 
@@ -372,87 +584,118 @@ impl LoadedChunk {
         Value::LongArray(encoded)
     }
 
-    /// Writes the packet data needed to initialize this chunk.
+    #[cfg(feature = "bench")]
+    pub(super) fn clear_cached_init_packets(&mut self) {
+        self.cached_init_packets.get_mut().clear();
+    }
+
+    /// Writes the packet data needed to initialize this chunk. The biomes are
+    /// replaced according to `biome_override`, if any, in which case the
+    /// packets are encoded again instead of using the cached ones.
     pub(crate) fn write_init_packets(
         &self,
         mut writer: impl WritePacket,
         pos: ChunkPos,
         info: &ChunkLayerInfo,
+        biome_override: Option<&BiomeOverride>,
     ) {
+        if let Some(biome_override) = biome_override.filter(|o| !o.is_empty()) {
+            let mut init_packets = vec![];
+            self.encode_init_packets(&mut init_packets, pos, info, |b| biome_override.get(b));
+            writer.write_packet_bytes_with_priority(&init_packets, PacketPriority::Bulk);
+            return;
+        }
+
         let mut init_packets = self.cached_init_packets.lock();
 
         if init_packets.is_empty() {
-            let heightmaps = compound! {
-                "MOTION_BLOCKING" => LoadedChunk::encode_heightmap(self.motion_blocking()),
-                // TODO Implement `WORLD_SURFACE` (or explain why we don't need it)
-                // "WORLD_SURFACE" => self.encode_heightmap(self.world_surface()),
-            };
+            self.encode_init_packets(&mut init_packets, pos, info, |b| b);
+        }
 
-            let mut blocks_and_biomes: Vec<u8> = vec![];
+        writer.write_packet_bytes_with_priority(&init_packets, PacketPriority::Bulk);
+    }
 
-            for sect in &self.sections {
-                sect.count_non_air_blocks()
-                    .encode(&mut blocks_and_biomes)
-                    .unwrap();
-
-                sect.block_states
-                    .encode_mc_format(
-                        &mut blocks_and_biomes,
-                        |b| b.to_raw().into(),
-                        4,
-                        8,
-                        bit_width(BlockState::max_raw().into()),
-                    )
-                    .expect("paletted container encode should always succeed");
-
-                sect.biomes
-                    .encode_mc_format(
-                        &mut blocks_and_biomes,
-                        |b| b.to_index() as u64,
-                        0,
-                        3,
-                        bit_width(info.biome_registry_len - 1),
-                    )
-                    .expect("paletted container encode should always succeed");
-            }
+    fn encode_init_packets(
+        &self,
+        buf: &mut Vec<u8>,
+        pos: ChunkPos,
+        info: &ChunkLayerInfo,
+        mut map_biome: impl FnMut(BiomeId) -> BiomeId,
+    ) {
+        let heightmaps = compound! {
+            "MOTION_BLOCKING" => LoadedChunk::encode_heightmap(self.motion_blocking()),
+            // TODO Implement `WORLD_SURFACE` (or explain why we don't need it)
+            // "WORLD_SURFACE" => self.encode_heightmap(self.world_surface()),
+        };
+
+        let mut blocks_and_biomes: Vec<u8> = vec![];
+
+        for sect in &self.sections {
+            sect.count_non_air_blocks()
+                .encode(&mut blocks_and_biomes)
+                .unwrap();
+
+            sect.block_states
+                .encode_mc_format(
+                    &mut blocks_and_biomes,
+                    |b| b.to_raw().into(),
+                    4,
+                    8,
+                    bit_width(BlockState::max_raw().into()),
+                )
+                .expect("paletted container encode should always succeed");
+
+            sect.encode_biomes(&mut blocks_and_biomes, info, &mut map_biome);
+        }
 
-            let block_entities: Vec<_> = self
-                .block_entities
-                .iter()
-                .filter_map(|(&idx, nbt)| {
-                    let x = idx % 16;
-                    let z = idx / 16 % 16;
-                    let y = idx / 16 / 16;
-
-                    let kind = self.sections[y as usize / 16]
-                        .block_states
-                        .get(idx as usize % SECTION_BLOCK_COUNT)
-                        .block_entity_kind();
-
-                    kind.map(|kind| ChunkDataBlockEntity {
-                        packed_xz: ((x << 4) | z) as i8,
-                        y: y as i16 + info.min_y as i16,
-                        kind,
-                        data: Cow::Borrowed(nbt),
-                    })
+        let block_entities: Vec<_> = self
+            .block_entities
+            .iter()
+            .filter_map(|(&idx, nbt)| {
+                let x = idx % 16;
+                let z = idx / 16 % 16;
+                let y = idx / 16 / 16;
+
+                let kind = self.sections[y as usize / 16]
+                    .block_states
+                    .get(idx as usize % SECTION_BLOCK_COUNT)
+                    .block_entity_kind();
+
+                kind.map(|kind| ChunkDataBlockEntity {
+                    packed_xz: ((x << 4) | z) as i8,
+                    y: y as i16 + info.min_y as i16,
+                    kind,
+                    data: Cow::Borrowed(nbt),
                 })
-                .collect();
-
-            PacketWriter::new(&mut init_packets, info.threshold).write_packet(&ChunkDataS2c {
-                pos,
-                heightmaps: Cow::Owned(heightmaps),
-                blocks_and_biomes: &blocks_and_biomes,
-                block_entities: Cow::Owned(block_entities),
-                sky_light_mask: Cow::Borrowed(&[]),
-                block_light_mask: Cow::Borrowed(&[]),
-                empty_sky_light_mask: Cow::Borrowed(&[]),
-                empty_block_light_mask: Cow::Borrowed(&[]),
-                sky_light_arrays: Cow::Borrowed(&[]),
-                block_light_arrays: Cow::Borrowed(&[]),
             })
-        }
+            .collect();
 
-        writer.write_packet_bytes(&init_packets);
+        PacketWriter::new(buf, info.threshold).write_packet(&ChunkDataS2c {
+            pos,
+            heightmaps: Cow::Owned(heightmaps),
+            blocks_and_biomes: &blocks_and_biomes,
+            block_entities: Cow::Owned(block_entities),
+            sky_light_mask: Cow::Borrowed(&[]),
+            block_light_mask: Cow::Borrowed(&[]),
+            empty_sky_light_mask: Cow::Borrowed(&[]),
+            empty_block_light_mask: Cow::Borrowed(&[]),
+            sky_light_arrays: Cow::Borrowed(&[]),
+            block_light_arrays: Cow::Borrowed(&[]),
+        })
+    }
+
+    /// Writes the biomes of every section in the format of
+    /// [`ChunkBiomeDataS2c`](valence_protocol::packets::play::ChunkBiomeDataS2c),
+    /// replacing each biome with the result of `map_biome`.
+    pub(crate) fn encode_biomes(
+        &self,
+        buf: &mut Vec<u8>,
+        info: &ChunkLayerInfo,
+        mut map_biome: impl FnMut(BiomeId) -> BiomeId,
+    ) {
+        for sect in &self.sections {
+            sect.encode_biomes(&mut *buf, info, &mut map_biome);
+        }
     }
 
     /// Asserts that no changes to this chunk are currently recorded.
@@ -495,6 +738,8 @@ impl Chunk for LoadedChunk {
 
         if block != old_block {
             self.cached_init_packets.get_mut().clear();
+            self.modified = true;
+            sect.dirty = true;
 
             if *self.viewer_count.get_mut() > 0 {
                 sect.updates.push(
@@ -518,6 +763,8 @@ impl Chunk for LoadedChunk {
         if let PalettedContainer::Single(b) = &sect.block_states {
             if *b != block {
                 self.cached_init_packets.get_mut().clear();
+                self.modified = true;
+                sect.dirty = true;
 
                 if *self.viewer_count.get_mut() > 0 {
                     // The whole section is being modified, so any previous modifications would
@@ -545,10 +792,12 @@ impl Chunk for LoadedChunk {
             for z in 0..16 {
                 for x in 0..16 {
                     for y in 0..16 {
-                        let idx = x + z * 16 + (sect_y * 16 + y) * (16 * 16);
+                        let idx = x + z * 16 + y * 16 * 16;
 
                         if block != sect.block_states.get(idx as usize) {
                             self.cached_init_packets.get_mut().clear();
+                            self.modified = true;
+                            sect.dirty = true;
 
                             if *self.viewer_count.get_mut() > 0 {
                                 sect.updates.push(
@@ -568,6 +817,14 @@ impl Chunk for LoadedChunk {
         sect.block_states.fill(block);
     }
 
+    fn count_section_block_states(&self, sect_y: u32, histogram: &mut BlockHistogram) {
+        check_section_oob(self, sect_y);
+
+        self.sections[sect_y as usize]
+            .block_states
+            .count(|state, count| histogram.add(state, count as u64));
+    }
+
     fn block_entity(&self, x: u32, y: u32, z: u32) -> Option<&Compound> {
         check_block_oob(self, x, y, z);
 
@@ -585,6 +842,8 @@ impl Chunk for LoadedChunk {
                 self.changed_block_entities.insert(idx);
             }
             self.cached_init_packets.get_mut().clear();
+            self.modified = true;
+            self.sections[y as usize / 16].dirty = true;
 
             Some(be)
         } else {
@@ -609,6 +868,8 @@ impl Chunk for LoadedChunk {
                     self.changed_block_entities.insert(idx);
                 }
                 self.cached_init_packets.get_mut().clear();
+                self.modified = true;
+                self.sections[y as usize / 16].dirty = true;
 
                 self.block_entities.insert(idx, nbt)
             }
@@ -617,6 +878,8 @@ impl Chunk for LoadedChunk {
 
                 if res.is_some() {
                     self.cached_init_packets.get_mut().clear();
+                    self.modified = true;
+                    self.sections[y as usize / 16].dirty = true;
                 }
 
                 res
@@ -630,6 +893,11 @@ impl Chunk for LoadedChunk {
         }
 
         self.cached_init_packets.get_mut().clear();
+        self.modified = true;
+
+        for &idx in self.block_entities.keys() {
+            self.sections[idx as usize / SECTION_BLOCK_COUNT].dirty = true;
+        }
 
         if *self.viewer_count.get_mut() > 0 {
             self.changed_block_entities
@@ -650,12 +918,13 @@ impl Chunk for LoadedChunk {
         check_biome_oob(self, x, y, z);
 
         let idx = x + z * 4 + y % 4 * 4 * 4;
-        let old_biome = self.sections[y as usize / 4]
-            .biomes
-            .set(idx as usize, biome);
+        let sect = &mut self.sections[y as usize / 4];
+        let old_biome = sect.biomes.set(idx as usize, biome);
 
         if biome != old_biome {
             self.cached_init_packets.get_mut().clear();
+            self.modified = true;
+            sect.dirty = true;
 
             if *self.viewer_count.get_mut() > 0 {
                 self.changed_biomes = true;
@@ -673,11 +942,15 @@ impl Chunk for LoadedChunk {
         if let PalettedContainer::Single(b) = &sect.biomes {
             if *b != biome {
                 self.cached_init_packets.get_mut().clear();
+                self.modified = true;
                 self.changed_biomes = *self.viewer_count.get_mut() > 0;
+                sect.dirty = true;
             }
         } else {
             self.cached_init_packets.get_mut().clear();
+            self.modified = true;
             self.changed_biomes = *self.viewer_count.get_mut() > 0;
+            sect.dirty = true;
         }
 
         sect.biomes.fill(biome);
@@ -694,6 +967,51 @@ impl Chunk for LoadedChunk {
     }
 }
 
+/// The maximum number of block changes sent to viewers of a chunk when it's
+/// overwritten. Resending the whole chunk is usually smaller than more changes.
+const MAX_OVERWRITE_BLOCK_CHANGES: usize = SECTION_BLOCK_COUNT;
+
+/// Returns the indices and new states of the blocks which differ between two
+/// sections.
+fn changed_blocks_iter<'a>(
+    old: &'a BlockStateContainer,
+    new: &'a BlockStateContainer,
+) -> impl Iterator<Item = (usize, BlockState)> + 'a {
+    let len = match (old, new) {
+        (PalettedContainer::Single(a), PalettedContainer::Single(b)) if a == b => 0,
+        _ => SECTION_BLOCK_COUNT,
+    };
+
+    (0..len).filter_map(move |idx| {
+        let block = new.get(idx);
+        (block != old.get(idx)).then_some((idx, block))
+    })
+}
+
+/// Removes all but the last update to each block, since clients only need the
+/// final state of blocks which changed several times in a tick.
+fn dedup_updates(updates: &mut Vec<ChunkDeltaUpdateEntry>) {
+    if updates.len() < 2 {
+        return;
+    }
+
+    let mut seen = [0_u64; SECTION_BLOCK_COUNT / 64];
+
+    // Walk the updates backwards so the last update to each block is kept.
+    updates.reverse();
+    updates.retain(|entry| {
+        let idx = usize::from(entry.off_x())
+            + usize::from(entry.off_z()) * 16
+            + usize::from(entry.off_y()) * 16 * 16;
+
+        let bit = 1 << (idx % 64);
+        let is_first = seen[idx / 64] & bit == 0;
+        seen[idx / 64] |= bit;
+        is_first
+    });
+    updates.reverse();
+}
+
 #[cfg(test)]
 mod tests {
     use valence_protocol::{ident, CompressionThreshold};
@@ -733,17 +1051,20 @@ mod tests {
             let mut writer = PacketWriter::new(&mut buf, CompressionThreshold(-1));
 
             // Rebuild cache.
-            chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info);
+            chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info, None);
 
             // Check that the cache is built.
             assert!(!chunk.cached_init_packets.get_mut().is_empty());
 
-            // Making a change should clear the cache.
+            chunk.set_modified(false);
+
+            // Making a change should clear the cache and mark the chunk as modified.
             change(chunk);
             assert!(chunk.cached_init_packets.get_mut().is_empty());
+            assert!(chunk.is_modified());
 
             // Rebuild cache again.
-            chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info);
+            chunk.write_init_packets(&mut writer, ChunkPos::new(3, 4), &info, None);
             assert!(!chunk.cached_init_packets.get_mut().is_empty());
         }
 
@@ -763,6 +1084,8 @@ mod tests {
         });
         check(&mut chunk, |c| c.set_block_entity(3, 40, 5, None));
 
+        chunk.set_modified(false);
+
         // Old block state is the same as new block state, so the cache should still be
         // intact.
         assert_eq!(
@@ -771,5 +1094,68 @@ mod tests {
         );
 
         assert!(!chunk.cached_init_packets.get_mut().is_empty());
+        assert!(!chunk.is_modified());
+    }
+
+    #[test]
+    fn loaded_chunk_dirty_sections() {
+        let mut chunk = LoadedChunk::new(512);
+        chunk.insert(UnloadedChunk::with_height(512));
+
+        // Inserting a chunk makes every section dirty.
+        assert_eq!(chunk.dirty_sections().count(), 512 / 16);
+
+        chunk.set_modified(false);
+        assert_eq!(chunk.dirty_sections().next(), None);
+
+        chunk.set_block_state(0, 20, 0, BlockState::STONE);
+        chunk.set_biome(0, 30, 0, BiomeId::from_index(3));
+        chunk.set_block_entity(0, 100, 0, Some(compound! {}));
+
+        assert_eq!(chunk.dirty_sections().collect::<Vec<_>>(), [1, 6, 7]);
+        assert!(chunk.is_section_dirty(6));
+        assert!(!chunk.is_section_dirty(0));
+
+        chunk.clear_dirty_sections();
+        assert_eq!(chunk.dirty_sections().next(), None);
+        assert!(chunk.is_modified());
+
+        // Filling a section with more than one kind of block.
+        chunk.fill_block_state_section(1, BlockState::STONE);
+        assert!(chunk.is_section_dirty(1));
+        assert_eq!(chunk.block_state(15, 31, 15), BlockState::STONE);
+
+        // Filling a section with the block it already has isn't a change.
+        chunk.clear_dirty_sections();
+        chunk.fill_block_state_section(1, BlockState::STONE);
+        assert!(!chunk.is_section_dirty(1));
+    }
+
+    #[test]
+    fn loaded_chunk_insert_as_changes() {
+        let mut chunk = LoadedChunk::new(512);
+        chunk.insert(UnloadedChunk::with_height(512));
+        chunk.set_modified(false);
+
+        let mut other = chunk.to_unloaded();
+        other.set_block_state(1, 2, 3, BlockState::STONE);
+        other.set_block_state(1, 40, 3, BlockState::DIRT);
+
+        // Unviewed chunks have no viewers to send changes to.
+        let other = chunk.try_insert_as_changes(other).unwrap_err();
+
+        chunk.inc_viewer_count();
+
+        let old = chunk.try_insert_as_changes(other).unwrap();
+        assert_eq!(old.block_state(1, 2, 3), BlockState::AIR);
+        assert_eq!(chunk.block_state(1, 2, 3), BlockState::STONE);
+        assert_eq!(chunk.dirty_sections().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(chunk.sections[0].updates.len(), 1);
+        assert_eq!(chunk.sections[2].updates.len(), 1);
+
+        // Too many changes to send individually.
+        let mut other = chunk.to_unloaded();
+        other.fill_block_states(BlockState::GLASS);
+        assert!(chunk.try_insert_as_changes(other).is_err());
     }
 }
@@ -1,8 +1,9 @@
-use std::array;
 use std::io::Write;
+use std::{array, mem};
 
+use anyhow::{bail, ensure, Context};
 use arrayvec::ArrayVec;
-use valence_protocol::{Encode, VarInt};
+use valence_protocol::{Decode, Encode, VarInt};
 
 use super::chunk::bit_width;
 
@@ -127,6 +128,48 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
         }
     }
 
+    /// Calls `f` with every distinct element in the container and the number of
+    /// times it occurs. This reads the palette instead of decoding every
+    /// element when possible.
+    pub(super) fn count(&self, mut f: impl FnMut(T, usize)) {
+        match self {
+            Self::Single(val) => f(*val, LEN),
+            Self::Indirect(ind) => {
+                let mut counts = [0_usize; 16];
+
+                for &byte in &ind.indices {
+                    counts[(byte & 0b1111) as usize] += 1;
+                    counts[(byte >> 4) as usize] += 1;
+                }
+
+                if LEN % 2 == 1 {
+                    // The upper half of the last byte isn't an element.
+                    counts[(ind.indices[HALF_LEN - 1] >> 4) as usize] -= 1;
+                }
+
+                for (val, count) in ind.palette.iter().zip(counts) {
+                    if count > 0 {
+                        f(*val, count);
+                    }
+                }
+            }
+            Self::Direct(elems) => {
+                for val in elems.iter() {
+                    f(*val, 1);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of bytes this container has allocated on the heap.
+    pub(super) fn heap_size(&self) -> usize {
+        match self {
+            Self::Single(_) => 0,
+            Self::Indirect(_) => mem::size_of::<Indirect<T, LEN, HALF_LEN>>(),
+            Self::Direct(_) => mem::size_of::<[T; LEN]>(),
+        }
+    }
+
     /// Encodes the paletted container in the format that Minecraft expects.
     ///
     /// - **`writer`**: The [`Write`] instance to write the paletted container
@@ -225,6 +268,96 @@ impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize>
 
         Ok(())
     }
+
+    /// Encodes the paletted container in the format of a
+    /// [`ChunkLayerSnapshot`](super::ChunkLayerSnapshot). Unlike the Minecraft
+    /// format, this mirrors the representation in memory so it can be decoded
+    /// without repacking any indices.
+    pub(super) fn encode_snapshot<W, F>(&self, mut writer: W, mut to_raw: F) -> anyhow::Result<()>
+    where
+        W: Write,
+        F: FnMut(T) -> u32,
+    {
+        match self {
+            Self::Single(val) => {
+                0_u8.encode(&mut writer)?;
+                VarInt(to_raw(*val) as i32).encode(writer)?;
+            }
+            Self::Indirect(ind) => {
+                1_u8.encode(&mut writer)?;
+
+                (ind.palette.len() as u8).encode(&mut writer)?;
+                for val in &ind.palette {
+                    VarInt(to_raw(*val) as i32).encode(&mut writer)?;
+                }
+
+                writer.write_all(&ind.indices)?;
+            }
+            Self::Direct(dir) => {
+                2_u8.encode(&mut writer)?;
+
+                for val in dir.iter() {
+                    VarInt(to_raw(*val) as i32).encode(&mut writer)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes a paletted container written by [`Self::encode_snapshot`].
+    /// `from_raw` returns `None` for invalid elements.
+    pub(super) fn decode_snapshot<F>(r: &mut &[u8], mut from_raw: F) -> anyhow::Result<Self>
+    where
+        F: FnMut(u32) -> Option<T>,
+    {
+        let mut decode_val = |r: &mut &[u8]| {
+            let raw = VarInt::decode(r)?.0 as u32;
+            from_raw(raw).with_context(|| format!("invalid paletted container element {raw}"))
+        };
+
+        match u8::decode(r)? {
+            0 => Ok(Self::Single(decode_val(r)?)),
+            1 => {
+                let palette_len = u8::decode(r)? as usize;
+                ensure!(
+                    (2..=16).contains(&palette_len),
+                    "invalid palette length of {palette_len}"
+                );
+
+                let mut palette = ArrayVec::new();
+                for _ in 0..palette_len {
+                    palette.push(decode_val(r)?);
+                }
+
+                ensure!(r.len() >= HALF_LEN, "not enough data for palette indices");
+                let (indices, rest) = r.split_at(HALF_LEN);
+                *r = rest;
+
+                let indices: [u8; HALF_LEN] = indices.try_into().unwrap();
+
+                ensure!(
+                    indices
+                        .iter()
+                        .flat_map(|byte| [byte & 0b1111, byte >> 4])
+                        .take(LEN)
+                        .all(|idx| (idx as usize) < palette_len),
+                    "palette index out of bounds"
+                );
+
+                Ok(Self::Indirect(Box::new(Indirect { palette, indices })))
+            }
+            2 => {
+                let mut dir = Box::new([T::default(); LEN]);
+                for val in dir.iter_mut() {
+                    *val = decode_val(r)?;
+                }
+
+                Ok(Self::Direct(dir))
+            }
+            n => bail!("unknown paletted container representation {n}"),
+        }
+    }
 }
 
 impl<T: Copy + Eq + Default, const LEN: usize, const HALF_LEN: usize> Default
@@ -336,4 +469,65 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn count_elements() {
+        const LEN: usize = 101;
+
+        let mut rng = rand::thread_rng();
+
+        let mut p = PalettedContainer::<u32, LEN, { LEN.div_ceil(2) }>::new();
+        let mut a = [0; LEN];
+
+        for _ in 0..LEN * 3 {
+            let idx = rng.gen_range(0..LEN);
+            // Few enough values to stay indirect most of the time.
+            let max = if rng.gen_bool(0.9) { 8 } else { 32 };
+            let val = rng.gen_range(0..max);
+
+            p.set(idx, val);
+            a[idx] = val;
+
+            let mut counts = [0; 32];
+            p.count(|val, count| counts[val as usize] += count);
+
+            for (val, &count) in counts.iter().enumerate() {
+                assert_eq!(count, a.iter().filter(|&&v| v == val as u32).count());
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        const LEN: usize = 101;
+        type Container = PalettedContainer<u32, LEN, { LEN.div_ceil(2) }>;
+
+        let mut rng = rand::thread_rng();
+
+        let mut single = Container::new();
+        single.fill(5);
+
+        let mut indirect = Container::new();
+        let mut direct = Container::new();
+
+        for i in 0..LEN {
+            indirect.set(i, rng.gen_range(0..8));
+            direct.set(i, rng.gen_range(0..64));
+        }
+
+        for p in [single, indirect, direct] {
+            let mut buf = vec![];
+            p.encode_snapshot(&mut buf, |v| v).unwrap();
+
+            let mut r = buf.as_slice();
+            let decoded = Container::decode_snapshot(&mut r, Some).unwrap();
+
+            assert!(r.is_empty());
+            assert!((0..LEN).all(|i| p.get(i) == decoded.get(i)));
+
+            // Elements rejected by `from_raw` are errors.
+            let mut r = buf.as_slice();
+            assert!(Container::decode_snapshot(&mut r, |v| (v < 5).then_some(v)).is_err());
+        }
+    }
 }
@@ -0,0 +1,189 @@
+//! Copies of a whole [`ChunkLayer`] for instancing map templates.
+//!
+//! Loading a minigame map from Anvil or a schematic every time a round starts
+//! is slow, since every chunk has to be decompressed and its palettes rebuilt.
+//! A [`ChunkLayerSnapshot`] instead keeps the chunks in the same form the
+//! layer does. Restoring one into a layer is mostly a matter of copying memory,
+//! so a map can be instantiated many times per minute.
+//!
+//! Snapshots can also be saved with [`ChunkLayerSnapshot::encode`] and loaded
+//! with [`ChunkLayerSnapshot::decode`]. The format mirrors the memory layout as
+//! well and isn't compressed, so compress it yourself if disk space matters.
+//! It isn't meant as a long-term storage format. Block states are stored by
+//! their raw IDs, which change between Minecraft versions.
+//!
+//! [`ChunkLayer`]: super::ChunkLayer
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use anyhow::{ensure, Context};
+use valence_nbt::Compound;
+use valence_protocol::{BlockState, ChunkPos, Decode, Encode, VarInt};
+use valence_registry::biome::BiomeId;
+use valence_registry::RegistryIdx;
+
+use super::chunk::{MAX_HEIGHT, SECTION_BLOCK_COUNT};
+use super::paletted_container::PalettedContainer;
+use super::unloaded::{self, UnloadedChunk};
+
+/// Identifies encoded snapshots.
+const MAGIC: [u8; 4] = *b"VLCS";
+/// The version of the encoding, increased whenever it changes.
+const VERSION: u8 = 1;
+
+/// A copy of the chunks in a [`ChunkLayer`](super::ChunkLayer). Create one
+/// with [`ChunkLayer::snapshot`](super::ChunkLayer::snapshot) and restore it
+/// with [`ChunkLayer::restore`](super::ChunkLayer::restore).
+#[derive(Clone, Default, Debug)]
+pub struct ChunkLayerSnapshot {
+    height: u32,
+    /// Sorted by position.
+    chunks: Vec<(ChunkPos, UnloadedChunk)>,
+}
+
+impl ChunkLayerSnapshot {
+    pub(super) fn new(height: u32, mut chunks: Vec<(ChunkPos, UnloadedChunk)>) -> Self {
+        chunks.sort_unstable_by_key(|(pos, _)| *pos);
+
+        Self { height, chunks }
+    }
+
+    /// Returns the height of the layer the snapshot was taken from.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the chunk at `pos`, if the snapshot contains it.
+    pub fn chunk<P: Into<ChunkPos>>(&self, pos: P) -> Option<&UnloadedChunk> {
+        let pos = pos.into();
+
+        self.chunks
+            .binary_search_by_key(&pos, |(p, _)| *p)
+            .ok()
+            .map(|idx| &self.chunks[idx].1)
+    }
+
+    /// Returns an iterator over the chunks in the snapshot, ordered by
+    /// position.
+    pub fn chunks(&self) -> impl ExactSizeIterator<Item = (ChunkPos, &UnloadedChunk)> + '_ {
+        self.chunks.iter().map(|(pos, chunk)| (*pos, chunk))
+    }
+
+    /// Writes the snapshot to `writer` in a compact binary format.
+    pub fn encode<W: Write>(&self, mut writer: W) -> anyhow::Result<()> {
+        writer.write_all(&MAGIC)?;
+        VERSION.encode(&mut writer)?;
+        VarInt(self.height as i32).encode(&mut writer)?;
+        VarInt(self.chunks.len() as i32).encode(&mut writer)?;
+
+        for (pos, chunk) in &self.chunks {
+            pos.encode(&mut writer)?;
+
+            VarInt(chunk.sections.len() as i32).encode(&mut writer)?;
+            for sect in &chunk.sections {
+                sect.block_states
+                    .encode_snapshot(&mut writer, |b| u32::from(b.to_raw()))?;
+                sect.biomes
+                    .encode_snapshot(&mut writer, |b| b.to_index() as u32)?;
+            }
+
+            VarInt(chunk.block_entities.len() as i32).encode(&mut writer)?;
+            for (idx, nbt) in &chunk.block_entities {
+                VarInt(*idx as i32).encode(&mut writer)?;
+                nbt.encode(&mut writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`Self::encode`].
+    pub fn decode(mut bytes: &[u8]) -> anyhow::Result<Self> {
+        let r = &mut bytes;
+
+        ensure!(
+            r.starts_with(&MAGIC),
+            "data is not an encoded chunk layer snapshot"
+        );
+        *r = &r[MAGIC.len()..];
+
+        let version = u8::decode(r)?;
+        ensure!(
+            version == VERSION,
+            "unsupported chunk layer snapshot version {version}"
+        );
+
+        let height = VarInt::decode(r)?.0 as u32;
+        ensure!(
+            height <= MAX_HEIGHT && height % 16 == 0,
+            "invalid height of {height}"
+        );
+
+        let chunk_count = VarInt::decode(r)?.0;
+        ensure!(chunk_count >= 0, "negative chunk count");
+
+        // The counts aren't trusted for preallocating, so corrupt data can't make
+        // this allocate huge amounts of memory.
+        let mut chunks = vec![];
+
+        for _ in 0..chunk_count {
+            let pos = ChunkPos::decode(r)?;
+
+            let sect_count = VarInt::decode(r)?.0;
+            ensure!(
+                (0..=MAX_HEIGHT as i32 / 16).contains(&sect_count),
+                "invalid section count of {sect_count} in chunk {pos:?}"
+            );
+
+            let mut sections = vec![];
+            for _ in 0..sect_count {
+                let block_states = PalettedContainer::decode_snapshot(r, |raw| {
+                    BlockState::from_raw(raw.try_into().ok()?)
+                })
+                .with_context(|| format!("decoding block states in chunk {pos:?}"))?;
+
+                let biomes = PalettedContainer::decode_snapshot(r, |raw| {
+                    Some(BiomeId::from_index(raw as usize))
+                })
+                .with_context(|| format!("decoding biomes in chunk {pos:?}"))?;
+
+                sections.push(unloaded::Section {
+                    block_states,
+                    biomes,
+                });
+            }
+
+            let block_entity_count = VarInt::decode(r)?.0;
+            let mut block_entities = BTreeMap::new();
+
+            for _ in 0..block_entity_count {
+                let idx = VarInt::decode(r)?.0 as u32;
+                ensure!(
+                    idx < sect_count as u32 * SECTION_BLOCK_COUNT as u32,
+                    "block entity out of bounds in chunk {pos:?}"
+                );
+
+                let nbt = Compound::decode(r)?;
+
+                block_entities.insert(idx, nbt);
+            }
+
+            chunks.push((
+                pos,
+                UnloadedChunk {
+                    sections,
+                    block_entities,
+                },
+            ));
+        }
+
+        ensure!(r.is_empty(), "trailing data after chunk layer snapshot");
+
+        Ok(Self::new(height, chunks))
+    }
+}
@@ -6,8 +6,8 @@ use valence_protocol::BlockState;
 use valence_registry::biome::BiomeId;
 
 use super::chunk::{
-    check_biome_oob, check_block_oob, check_section_oob, BiomeContainer, BlockStateContainer,
-    Chunk, MAX_HEIGHT, SECTION_BLOCK_COUNT,
+    check_biome_oob, check_block_oob, check_section_oob, BiomeContainer, BlockHistogram,
+    BlockStateContainer, Chunk, MAX_HEIGHT, SECTION_BLOCK_COUNT,
 };
 
 #[derive(Clone, Default, Debug)]
@@ -92,6 +92,14 @@ impl Chunk for UnloadedChunk {
         self.sections[sect_y as usize].block_states.fill(block);
     }
 
+    fn count_section_block_states(&self, sect_y: u32, histogram: &mut BlockHistogram) {
+        check_section_oob(self, sect_y);
+
+        self.sections[sect_y as usize]
+            .block_states
+            .count(|state, count| histogram.add(state, count as u64));
+    }
+
     fn block_entity(&self, x: u32, y: u32, z: u32) -> Option<&Compound> {
         check_block_oob(self, x, y, z);
 
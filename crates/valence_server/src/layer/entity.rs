@@ -4,11 +4,14 @@ use std::collections::BTreeSet;
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use rustc_hash::FxHashMap;
+use valence_entity::movement::EntityMovementSettings;
 use valence_entity::query::UpdateEntityQuery;
 use valence_entity::{EntityId, EntityLayerId, OldEntityLayerId, OldPosition, Position};
+use valence_math::{Aabb, DVec3};
 use valence_protocol::encode::{PacketWriter, WritePacket};
 use valence_protocol::{BlockPos, ChunkPos, CompressionThreshold, Encode, Packet};
 use valence_server_common::{Despawned, Server};
+use valence_spatial::grid::SpatialGrid;
 
 use super::bvh::GetChunkPos;
 use super::message::Messages;
@@ -20,6 +23,8 @@ use crate::client::Client;
 pub struct EntityLayer {
     messages: EntityLayerMessages,
     entities: FxHashMap<ChunkPos, BTreeSet<Entity>>,
+    /// The positions of the entities in this layer.
+    index: SpatialGrid<Entity>,
     threshold: CompressionThreshold,
 }
 
@@ -94,6 +99,7 @@ impl EntityLayer {
         Self {
             messages: Messages::new(),
             entities: Default::default(),
+            index: SpatialGrid::new(16.0),
             threshold: server.compression_threshold(),
         }
     }
@@ -110,6 +116,64 @@ impl EntityLayer {
             .flat_map(|entities| entities.iter().copied())
     }
 
+    /// Returns the number of entities in the given chunk position in this
+    /// layer.
+    pub fn entity_count_at<P: Into<ChunkPos>>(&self, pos: P) -> usize {
+        self.entities.get(&pos.into()).map_or(0, BTreeSet::len)
+    }
+
+    /// Returns the total number of entities in this layer.
+    pub fn entity_count(&self) -> usize {
+        self.entities.values().map(BTreeSet::len).sum()
+    }
+
+    /// Returns an iterator over the chunk positions containing entities in this
+    /// layer, along with the number of entities in each. The order of the
+    /// chunks is undefined.
+    pub fn entity_counts(&self) -> impl Iterator<Item = (ChunkPos, usize)> + Clone + '_ {
+        self.entities
+            .iter()
+            .filter(|(_, entities)| !entities.is_empty())
+            .map(|(pos, entities)| (*pos, entities.len()))
+    }
+
+    /// Returns the spatial index of the entities in this layer, which contains
+    /// their positions as of the last time layers were updated. Entities moved
+    /// since then are at their old positions until [`UpdateLayersPreClientSet`]
+    /// runs again.
+    pub fn spatial_index(&self) -> &SpatialGrid<Entity> {
+        &self.index
+    }
+
+    /// Returns an iterator over the entities in this layer within `radius` of
+    /// `center` in no particular order. See [`Self::spatial_index`].
+    pub fn query_sphere(&self, center: DVec3, radius: f64) -> impl Iterator<Item = Entity> + '_ {
+        self.index
+            .query_sphere(to_vek(center), radius)
+            .map(|(entity, _)| entity)
+    }
+
+    /// Returns an iterator over the entities in this layer positioned inside
+    /// `aabb` in no particular order. See [`Self::spatial_index`].
+    pub fn query_aabb(&self, aabb: Aabb) -> impl Iterator<Item = Entity> + '_ {
+        let aabb = vek::Aabb {
+            min: to_vek(aabb.min()),
+            max: to_vek(aabb.max()),
+        };
+
+        self.index.query_aabb(aabb).map(|(entity, _)| entity)
+    }
+
+    /// Returns the `k` entities in this layer closest to `center`, from nearest
+    /// to farthest. See [`Self::spatial_index`].
+    pub fn query_nearest(&self, center: DVec3, k: usize) -> Vec<Entity> {
+        self.index
+            .nearest(to_vek(center), k)
+            .into_iter()
+            .map(|(entity, _, _)| entity)
+            .collect()
+    }
+
     pub(crate) fn messages(&self) -> &EntityLayerMessages {
         &self.messages
     }
@@ -351,6 +415,7 @@ pub(super) fn build(app: &mut App) {
         (
             (
                 change_entity_positions,
+                update_spatial_indices,
                 send_entity_update_messages,
                 send_layer_despawn_messages,
                 ready_entity_layers,
@@ -469,9 +534,47 @@ fn change_entity_positions(
     }
 }
 
+fn update_spatial_indices(
+    entities: Query<
+        (
+            Entity,
+            &Position,
+            &EntityLayerId,
+            &OldEntityLayerId,
+            Has<Despawned>,
+        ),
+        (
+            With<EntityId>,
+            Or<(Changed<Position>, Changed<EntityLayerId>, With<Despawned>)>,
+        ),
+    >,
+    mut layers: Query<&mut EntityLayer>,
+) {
+    for (entity, pos, layer_id, old_layer_id, despawned) in &entities {
+        if despawned || old_layer_id != layer_id {
+            if let Ok(mut old_layer) = layers.get_mut(old_layer_id.get()) {
+                old_layer.index.remove(entity);
+            }
+        }
+
+        if let Ok(mut layer) = layers.get_mut(layer_id.0) {
+            if despawned {
+                layer.index.remove(entity);
+            } else {
+                layer.index.insert(entity, to_vek(pos.0));
+            }
+        }
+    }
+}
+
+fn to_vek(v: DVec3) -> vek::Vec3<f64> {
+    vek::Vec3::new(v.x, v.y, v.z)
+}
+
 fn send_entity_update_messages(
     entities: Query<(Entity, UpdateEntityQuery, Has<Client>), Without<Despawned>>,
     mut layers: Query<&mut EntityLayer>,
+    settings: Res<EntityMovementSettings>,
 ) {
     for layer in &mut layers {
         let layer = layer.into_inner();
@@ -494,7 +597,8 @@ fn send_entity_update_messages(
                     };
 
                     layer.messages.send_local_infallible(msg, |b| {
-                        update.write_update_packets(PacketWriter::new(b, layer.threshold))
+                        update
+                            .write_update_packets(PacketWriter::new(b, layer.threshold), &settings)
                     });
                 } else {
                     panic!(
@@ -0,0 +1,197 @@
+//! Caps on the number of entities and chunks in a layer.
+//!
+//! Adding [`LayerLimits`] to a layer entity guards shared servers against
+//! gameplay code spawning entities or loading chunks without bound. A
+//! [`LayerLimitEvent`] is sent whenever entities or chunks are added to a layer
+//! which is over one of its limits. With [`LayerLimits::reject`], the entities
+//! spawned and chunks loaded past the limit that tick are removed again before
+//! any client sees them.
+//!
+//! Clients are counted towards the entity limit, but they're never rejected.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use valence_entity::{EntityLayerId, InitEntitiesSet};
+use valence_protocol::ChunkPos;
+use valence_server_common::Despawned;
+
+use super::{ChunkLayer, UpdateLayersPreClientSet};
+use crate::client::Client;
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<LayerLimitEvent>().add_systems(
+        PostUpdate,
+        (enforce_entity_limits, enforce_chunk_limits)
+            .after(InitEntitiesSet)
+            .before(UpdateLayersPreClientSet),
+    );
+}
+
+/// A [`Component`] for layer entities limiting the number of entities and
+/// chunks in them. See the [module documentation](self).
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct LayerLimits {
+    /// The maximum number of entities in the entity layer.
+    pub max_entities: Option<usize>,
+    /// The maximum number of loaded chunks in the chunk layer.
+    pub max_chunks: Option<usize>,
+    /// Whether entities and chunks added past the limits are removed. Entities
+    /// moved from other layers aren't removed.
+    pub reject: bool,
+}
+
+impl LayerLimits {
+    #[must_use]
+    pub fn with_max_entities(mut self, max: usize) -> Self {
+        self.max_entities = Some(max);
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_chunks(mut self, max: usize) -> Self {
+        self.max_chunks = Some(max);
+        self
+    }
+
+    #[must_use]
+    pub fn rejecting(mut self) -> Self {
+        self.reject = true;
+        self
+    }
+}
+
+/// Sent when entities or chunks are added to a layer beyond its
+/// [`LayerLimits`].
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LayerLimitEvent {
+    pub layer: Entity,
+    pub limit: LayerLimit,
+    /// The number of entities or chunks in the layer, before any were
+    /// rejected.
+    pub count: usize,
+    /// The limit which was exceeded.
+    pub max: usize,
+    /// The number of entities or chunks which were removed again.
+    pub rejected: usize,
+}
+
+/// Which of the [`LayerLimits`] was exceeded.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LayerLimit {
+    Entities,
+    Chunks,
+}
+
+fn enforce_entity_limits(
+    layers: Query<(Entity, &LayerLimits)>,
+    entities: Query<(Entity, Ref<EntityLayerId>, Has<Client>), Without<Despawned>>,
+    mut events: EventWriter<LayerLimitEvent>,
+    mut commands: Commands,
+    // The number of entities in each layer, whether any entities were added to
+    // it this tick, and the entities spawned in it this tick.
+    mut counts: Local<FxHashMap<Entity, (usize, bool, Vec<Entity>)>>,
+) {
+    if !layers
+        .iter()
+        .any(|(_, limits)| limits.max_entities.is_some())
+    {
+        return;
+    }
+
+    for (entity, layer_id, is_client) in &entities {
+        let (count, added, spawned) = counts.entry(layer_id.0).or_default();
+
+        *count += 1;
+
+        if layer_id.is_changed() {
+            *added = true;
+
+            // Entities moved from other layers aren't rejected, so they aren't lost.
+            if layer_id.is_added() && !is_client {
+                spawned.push(entity);
+            }
+        }
+    }
+
+    for (layer, limits) in &layers {
+        let Some(max) = limits.max_entities else {
+            continue;
+        };
+
+        let Some((count, added, spawned)) = counts.get(&layer) else {
+            continue;
+        };
+
+        if *count <= max || !added {
+            continue;
+        }
+
+        let mut rejected = 0;
+
+        if limits.reject {
+            for &entity in spawned.iter().take(count - max) {
+                commands.entity(entity).insert(Despawned);
+                rejected += 1;
+            }
+        }
+
+        events.send(LayerLimitEvent {
+            layer,
+            limit: LayerLimit::Entities,
+            count: *count,
+            max,
+            rejected,
+        });
+    }
+
+    counts.clear();
+}
+
+fn enforce_chunk_limits(
+    mut layers: Query<(Entity, &mut ChunkLayer, &LayerLimits)>,
+    mut events: EventWriter<LayerLimitEvent>,
+    // The chunks loaded in each limited layer at the end of the last tick.
+    mut loaded: Local<FxHashMap<Entity, FxHashSet<ChunkPos>>>,
+) {
+    loaded.retain(|layer, _| layers.contains(*layer));
+
+    for (layer, mut chunk_layer, limits) in &mut layers {
+        let Some(max) = limits.max_chunks else {
+            loaded.remove(&layer);
+            continue;
+        };
+
+        let known = loaded.entry(layer).or_default();
+
+        let new_chunks: Vec<_> = chunk_layer
+            .chunks()
+            .map(|(pos, _)| pos)
+            .filter(|pos| !known.contains(pos))
+            .collect();
+
+        let count = chunk_layer.chunk_count();
+
+        if count > max && !new_chunks.is_empty() {
+            let mut rejected = 0;
+
+            if limits.reject {
+                for &pos in new_chunks.iter().take(count - max) {
+                    chunk_layer.remove_chunk(pos);
+                    rejected += 1;
+                }
+            }
+
+            events.send(LayerLimitEvent {
+                layer,
+                limit: LayerLimit::Chunks,
+                count,
+                max,
+                rejected,
+            });
+        }
+
+        known.clear();
+        known.extend(chunk_layer.chunks().map(|(pos, _)| pos));
+    }
+}
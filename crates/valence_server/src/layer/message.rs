@@ -2,6 +2,7 @@ use core::fmt;
 use std::convert::Infallible;
 use std::ops::Range;
 
+use bytes::{Bytes, BytesMut};
 use valence_protocol::ChunkPos;
 
 use crate::layer::bvh::{ChunkBvh, GetChunkPos};
@@ -32,7 +33,9 @@ pub struct Messages<G, L> {
     local: Vec<(L, Range<u32>)>,
     bvh: ChunkBvh<MessagePair<L>>,
     staging: Vec<u8>,
-    ready: Vec<u8>,
+    /// The sorted message bytes. These are reference counted so clients can
+    /// share them without copying.
+    ready: Bytes,
     is_ready: bool,
 }
 
@@ -118,12 +121,12 @@ where
 
         debug_assert!(self.ready.is_empty());
 
-        self.ready.reserve_exact(self.staging.len());
+        let mut ready = BytesMut::with_capacity(self.staging.len());
 
         fn sort_and_merge<M: Clone + Ord>(
             msgs: &mut Vec<(M, Range<u32>)>,
             staging: &[u8],
-            ready: &mut Vec<u8>,
+            ready: &mut BytesMut,
         ) {
             // Sort must be stable.
             msgs.sort_by_key(|(msg, _)| msg.clone());
@@ -167,8 +170,10 @@ where
             });
         }
 
-        sort_and_merge(&mut self.global, &self.staging, &mut self.ready);
-        sort_and_merge(&mut self.local, &self.staging, &mut self.ready);
+        sort_and_merge(&mut self.global, &self.staging, &mut ready);
+        sort_and_merge(&mut self.local, &self.staging, &mut ready);
+
+        self.ready = ready.freeze();
 
         self.bvh.build(
             self.local
@@ -185,7 +190,7 @@ where
         self.local.clear();
         self.global.clear();
         self.staging.clear();
-        self.ready.clear();
+        self.ready = Bytes::new();
     }
 
     pub(crate) fn shrink_to_fit(&mut self) {
@@ -193,12 +198,12 @@ where
         self.local.shrink_to_fit();
         self.bvh.shrink_to_fit();
         self.staging.shrink_to_fit();
-        self.ready.shrink_to_fit();
     }
 
     /// All message bytes. Use this in conjunction with [`Self::iter_global`]
-    /// and [`Self::query_local`].
-    pub fn bytes(&self) -> &[u8] {
+    /// and [`Self::query_local`]. Spans of the bytes can be
+    /// [sliced](Bytes::slice) to share them without copying.
+    pub fn bytes(&self) -> &Bytes {
         debug_assert!(self.is_ready);
 
         &self.ready
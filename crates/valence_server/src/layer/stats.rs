@@ -0,0 +1,134 @@
+//! Statistics about the chunks and entities in layers, useful for debugging
+//! memory growth and autoscaling long-running servers.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+
+use super::{ChunkLayer, EntityLayer, UpdateLayersPostClientSet};
+
+pub(super) fn build(app: &mut App) {
+    app.init_resource::<LayerStats>().add_systems(
+        PostUpdate,
+        update_layer_stats.after(UpdateLayersPostClientSet),
+    );
+}
+
+/// A [`Resource`] containing statistics about every [`ChunkLayer`] and
+/// [`EntityLayer`]. Updated at the end of every tick, after clients are
+/// updated.
+#[derive(Resource, Default, Debug)]
+pub struct LayerStats {
+    chunk_layers: FxHashMap<Entity, ChunkLayerStats>,
+    entity_layers: FxHashMap<Entity, EntityLayerStats>,
+}
+
+/// Statistics about a [`ChunkLayer`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ChunkLayerStats {
+    /// The number of loaded chunks.
+    pub chunks: usize,
+    /// The number of loaded chunks in view of at least one client.
+    pub viewed_chunks: usize,
+    /// An estimate of the number of bytes of memory used by the loaded chunks.
+    /// See [`ChunkLayer::memory_usage`].
+    pub memory_usage: usize,
+}
+
+/// Statistics about an [`EntityLayer`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct EntityLayerStats {
+    /// The number of entities.
+    pub entities: usize,
+    /// The number of chunk positions containing at least one entity.
+    pub occupied_chunks: usize,
+    /// The largest number of entities in a single chunk position.
+    pub max_entities_per_chunk: usize,
+}
+
+impl LayerStats {
+    /// Returns the statistics of the chunk layer on `layer`, if there is one.
+    pub fn chunk_layer(&self, layer: Entity) -> Option<&ChunkLayerStats> {
+        self.chunk_layers.get(&layer)
+    }
+
+    /// Returns the statistics of the entity layer on `layer`, if there is one.
+    pub fn entity_layer(&self, layer: Entity) -> Option<&EntityLayerStats> {
+        self.entity_layers.get(&layer)
+    }
+
+    /// Returns an iterator over the statistics of all chunk layers. The order
+    /// is undefined.
+    pub fn chunk_layers(&self) -> impl Iterator<Item = (Entity, &ChunkLayerStats)> + '_ {
+        self.chunk_layers
+            .iter()
+            .map(|(layer, stats)| (*layer, stats))
+    }
+
+    /// Returns an iterator over the statistics of all entity layers. The order
+    /// is undefined.
+    pub fn entity_layers(&self) -> impl Iterator<Item = (Entity, &EntityLayerStats)> + '_ {
+        self.entity_layers
+            .iter()
+            .map(|(layer, stats)| (*layer, stats))
+    }
+
+    /// Returns the number of loaded chunks in all chunk layers.
+    pub fn total_chunks(&self) -> usize {
+        self.chunk_layers.values().map(|stats| stats.chunks).sum()
+    }
+
+    /// Returns the estimated memory usage of all chunk layers, in bytes.
+    pub fn total_memory_usage(&self) -> usize {
+        self.chunk_layers
+            .values()
+            .map(|stats| stats.memory_usage)
+            .sum()
+    }
+
+    /// Returns the number of entities in all entity layers.
+    pub fn total_entities(&self) -> usize {
+        self.entity_layers
+            .values()
+            .map(|stats| stats.entities)
+            .sum()
+    }
+}
+
+fn update_layer_stats(
+    chunk_layers: Query<(Entity, &ChunkLayer)>,
+    entity_layers: Query<(Entity, &EntityLayer)>,
+    mut stats: ResMut<LayerStats>,
+) {
+    let stats = stats.as_mut();
+
+    stats.chunk_layers.clear();
+    stats.entity_layers.clear();
+
+    for (entity, layer) in &chunk_layers {
+        let mut layer_stats = ChunkLayerStats::default();
+
+        for (_, chunk) in layer.chunks() {
+            layer_stats.chunks += 1;
+            layer_stats.memory_usage += chunk.memory_usage();
+
+            if chunk.viewer_count() > 0 {
+                layer_stats.viewed_chunks += 1;
+            }
+        }
+
+        stats.chunk_layers.insert(entity, layer_stats);
+    }
+
+    for (entity, layer) in &entity_layers {
+        let mut layer_stats = EntityLayerStats::default();
+
+        for (_, count) in layer.entity_counts() {
+            layer_stats.entities += count;
+            layer_stats.occupied_chunks += 1;
+            layer_stats.max_entities_per_chunk = layer_stats.max_entities_per_chunk.max(count);
+        }
+
+        stats.entity_layers.insert(entity, layer_stats);
+    }
+}
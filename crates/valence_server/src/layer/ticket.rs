@@ -0,0 +1,168 @@
+//! Keeping chunks loaded and simulated without clients nearby.
+//!
+//! A ticket added with [`ChunkLayer::add_ticket`] covers a square of chunks
+//! around a position, like the spawn chunks in vanilla. Chunks covered by a
+//! ticket aren't unloaded by a [`ChunkUnloadPolicy`] or by `valence_anvil`,
+//! and are simulated as if a client were nearby, even if no client can see
+//! them. This is useful for spawn chunks, farms and scripted events.
+//!
+//! Tickets can expire after a number of ticks, or stay until they're removed
+//! with [`ChunkLayer::remove_ticket`]. Adding a ticket doesn't load chunks by
+//! itself, but `valence_anvil` loads the chunks covered by tickets.
+//!
+//! [`ChunkUnloadPolicy`]: super::ChunkUnloadPolicy
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+use valence_protocol::ChunkPos;
+
+use super::ChunkLayer;
+
+pub(super) fn build(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        expire_tickets.before(super::unload::unload_unviewed_chunks),
+    );
+}
+
+/// Identifies a ticket in a [`ChunkLayer`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TicketId(u64);
+
+/// Keeps the chunks around a position loaded. See the [module
+/// documentation](self).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChunkTicket {
+    pos: ChunkPos,
+    radius: u32,
+    /// The number of ticks until the ticket expires, or `None` if it never
+    /// does.
+    ttl: Option<u32>,
+}
+
+impl ChunkTicket {
+    /// Returns the position of the chunk at the center of the ticket.
+    pub fn pos(&self) -> ChunkPos {
+        self.pos
+    }
+
+    /// Returns the distance in chunks from the center to the edges of the
+    /// square covered by the ticket.
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Returns the number of ticks until the ticket expires, or `None` if it
+    /// lasts until it's removed.
+    pub fn ttl(&self) -> Option<u32> {
+        self.ttl
+    }
+
+    /// Returns whether the chunk at `pos` is covered by this ticket.
+    pub fn contains<P: Into<ChunkPos>>(&self, pos: P) -> bool {
+        let pos = pos.into();
+
+        self.pos.x.abs_diff(pos.x) <= self.radius && self.pos.z.abs_diff(pos.z) <= self.radius
+    }
+
+    /// Returns an iterator over the positions of the chunks covered by this
+    /// ticket.
+    pub fn iter(&self) -> impl Iterator<Item = ChunkPos> + Clone {
+        let r = self.radius as i32;
+        let center = self.pos;
+
+        (-r..=r).flat_map(move |z| (-r..=r).map(move |x| ChunkPos::new(center.x + x, center.z + z)))
+    }
+}
+
+/// The tickets of a [`ChunkLayer`].
+#[derive(Default, Debug)]
+pub(crate) struct ChunkTickets {
+    next_id: u64,
+    tickets: FxHashMap<TicketId, ChunkTicket>,
+    /// The number of tickets covering each chunk.
+    counts: FxHashMap<ChunkPos, u32>,
+}
+
+impl ChunkTickets {
+    pub(crate) fn add(&mut self, pos: ChunkPos, radius: u32, ttl: Option<u32>) -> TicketId {
+        let id = TicketId(self.next_id);
+        self.next_id += 1;
+
+        let ticket = ChunkTicket { pos, radius, ttl };
+
+        for pos in ticket.iter() {
+            *self.counts.entry(pos).or_insert(0) += 1;
+        }
+
+        self.tickets.insert(id, ticket);
+
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: TicketId) -> Option<ChunkTicket> {
+        let ticket = self.tickets.remove(&id)?;
+
+        for pos in ticket.iter() {
+            if let Some(count) = self.counts.get_mut(&pos) {
+                *count -= 1;
+
+                if *count == 0 {
+                    self.counts.remove(&pos);
+                }
+            }
+        }
+
+        Some(ticket)
+    }
+
+    pub(crate) fn get(&self, id: TicketId) -> Option<&ChunkTicket> {
+        self.tickets.get(&id)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (TicketId, &ChunkTicket)> + '_ {
+        self.tickets.iter().map(|(id, ticket)| (*id, ticket))
+    }
+
+    pub(crate) fn contains(&self, pos: ChunkPos) -> bool {
+        self.counts.contains_key(&pos)
+    }
+
+    pub(crate) fn positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.counts.keys().copied()
+    }
+
+    fn has_ttl(&self) -> bool {
+        self.tickets.values().any(|ticket| ticket.ttl.is_some())
+    }
+
+    /// Counts down the time to live of the tickets and removes the expired
+    /// ones.
+    fn tick(&mut self) {
+        let mut expired = vec![];
+
+        for (id, ticket) in &mut self.tickets {
+            if let Some(ttl) = &mut ticket.ttl {
+                *ttl = ttl.saturating_sub(1);
+
+                if *ttl == 0 {
+                    expired.push(*id);
+                }
+            }
+        }
+
+        for id in expired {
+            self.remove(id);
+        }
+    }
+}
+
+fn expire_tickets(mut layers: Query<&mut ChunkLayer>) {
+    for mut layer in &mut layers {
+        // Avoid triggering change detection for layers without expiring tickets.
+        if layer.tickets.has_ttl() {
+            layer.tickets.tick();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Unloading chunks that have gone unviewed for a while.
+//!
+//! Chunks stay loaded until they are removed from their [`ChunkLayer`]. Adding
+//! a [`ChunkUnloadPolicy`] to a layer entity unloads its chunks once no client
+//! has viewed them for a number of ticks. Keeping unviewed chunks around for a
+//! little while avoids reloading them when clients walk back and forth across
+//! chunk borders.
+//!
+//! Chunks covered by a [ticket](super::ticket) are never unloaded this way.
+//!
+//! An [`UnviewedChunkUnloadEvent`] containing the unloaded chunk is sent for
+//! every chunk unloaded this way, so the chunk can be saved before it's gone.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use valence_protocol::ChunkPos;
+use valence_server_common::Server;
+
+use super::chunk::UnloadedChunk;
+use super::ChunkLayer;
+
+pub(super) fn build(app: &mut App) {
+    app.add_event::<UnviewedChunkUnloadEvent>()
+        .add_systems(PreUpdate, unload_unviewed_chunks);
+}
+
+/// A [`Component`] for [`ChunkLayer`] entities which unloads chunks that
+/// haven't been in view of a client for [`grace_ticks`](Self::grace_ticks)
+/// ticks.
+#[derive(Component, Clone, Debug)]
+pub struct ChunkUnloadPolicy {
+    /// The number of ticks a chunk must go unviewed before it is unloaded. With
+    /// zero, chunks are unloaded on the tick after they leave the view of the
+    /// last client.
+    pub grace_ticks: u32,
+    /// Chunks which are never unloaded by the policy.
+    pub ignored_chunks: FxHashSet<ChunkPos>,
+    /// The tick each unviewed chunk was first seen unviewed on.
+    unviewed_since: FxHashMap<ChunkPos, i64>,
+}
+
+impl ChunkUnloadPolicy {
+    pub fn new(grace_ticks: u32) -> Self {
+        Self {
+            grace_ticks,
+            ignored_chunks: FxHashSet::default(),
+            unviewed_since: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the number of ticks the chunk at `pos` has gone unviewed, or
+    /// `None` if it is in view or wasn't seen unviewed yet.
+    pub fn unviewed_ticks(&self, pos: ChunkPos, server: &Server) -> Option<i64> {
+        self.unviewed_since
+            .get(&pos)
+            .map(|since| server.current_tick() - since)
+    }
+}
+
+impl Default for ChunkUnloadPolicy {
+    /// Unloads chunks after 30 seconds.
+    fn default() -> Self {
+        Self::new(600)
+    }
+}
+
+/// Sent when a chunk is unloaded because of a [`ChunkUnloadPolicy`]. Contains
+/// the chunk, so it can be saved.
+#[derive(Event, Debug)]
+pub struct UnviewedChunkUnloadEvent {
+    /// The [`ChunkLayer`] the chunk was unloaded from.
+    pub layer: Entity,
+    /// The position of the chunk in the layer.
+    pub pos: ChunkPos,
+    pub chunk: UnloadedChunk,
+}
+
+/// Unloads chunks which have gone unviewed for long enough.
+///
+/// This needs to run in `PreUpdate` where the chunk viewer counts have been
+/// updated from the previous tick.
+pub(super) fn unload_unviewed_chunks(
+    mut layers: Query<(Entity, &mut ChunkLayer, &mut ChunkUnloadPolicy)>,
+    server: Res<Server>,
+    mut to_unload: Local<Vec<ChunkPos>>,
+    mut events: EventWriter<UnviewedChunkUnloadEvent>,
+) {
+    let tick = server.current_tick();
+
+    for (entity, mut layer, policy) in &mut layers {
+        let policy = policy.into_inner();
+
+        // Forget chunks which were unloaded by something else.
+        policy
+            .unviewed_since
+            .retain(|pos, _| layer.chunk(*pos).is_some());
+
+        for (pos, chunk) in layer.chunks() {
+            if chunk.viewer_count() > 0
+                || policy.ignored_chunks.contains(&pos)
+                || layer.has_ticket(pos)
+            {
+                policy.unviewed_since.remove(&pos);
+                continue;
+            }
+
+            let since = *policy.unviewed_since.entry(pos).or_insert(tick);
+
+            if tick - since >= i64::from(policy.grace_ticks) {
+                to_unload.push(pos);
+            }
+        }
+
+        for pos in to_unload.drain(..) {
+            policy.unviewed_since.remove(&pos);
+
+            if let Some(chunk) = layer.remove_chunk(pos) {
+                events.send(UnviewedChunkUnloadEvent {
+                    layer: entity,
+                    pos,
+                    chunk,
+                });
+            }
+        }
+    }
+}
@@ -2,36 +2,67 @@
 
 pub mod abilities;
 pub mod action;
+pub mod asset;
+pub mod biome_override;
+pub mod block_overlay;
 pub mod brand;
 mod chunk_view;
+pub mod cinematic;
 pub mod client;
 pub mod client_command;
 pub mod client_settings;
+pub mod config;
 pub mod custom_payload;
+pub mod death;
+pub mod debug_shapes;
+pub mod effect_filter;
 pub mod event_loop;
+pub mod experience;
+pub mod explosion;
+pub mod falling_block;
+pub mod farming;
+pub mod fire;
+pub mod firework;
+pub mod game_mode;
+pub mod game_rules;
 pub mod hand_swing;
 pub mod interact_block;
 pub mod interact_entity;
 pub mod interact_item;
+pub mod interaction_rules;
 pub mod keepalive;
 pub mod layer;
 pub mod message;
 pub mod movement;
+pub mod moving_platform;
 pub mod op_level;
+pub mod pose;
+pub mod potion;
+pub mod random_tick;
+pub mod replay;
 pub mod resource_pack;
+pub mod sleep;
 pub mod spawn;
+pub mod spawner;
+pub mod spectate;
 pub mod status;
 pub mod status_effect;
+pub mod steering;
 pub mod teleport;
 pub mod title;
+pub mod tnt;
+pub mod use_item;
+pub mod vanish;
+pub mod view_distance;
+pub mod world_time;
 
 pub use chunk_view::ChunkView;
 pub use event_loop::{EventLoopPostUpdate, EventLoopPreUpdate, EventLoopUpdate};
 pub use layer::{ChunkLayer, EntityLayer, Layer, LayerBundle};
 pub use valence_protocol::{
-    block, ident, item, math, text, uuid, BiomePos, BlockPos, BlockState, ChunkPos,
-    CompressionThreshold, Difficulty, Direction, GameMode, Hand, Ident, ItemKind, ItemStack, Text,
-    MINECRAFT_VERSION, PROTOCOL_VERSION,
+    banner, block, ident, item, math, text, uuid, BiomePos, BlockPos, BlockState, ChunkPos,
+    CompressionThreshold, Difficulty, Direction, DyeColor, GameMode, Hand, Ident, ItemKind,
+    ItemStack, Text, MINECRAFT_VERSION, PROTOCOL_VERSION,
 };
 pub use valence_server_common::*;
 pub use {
@@ -1,19 +1,166 @@
 // TODO: delete this module in favor of valence_chat.
 
+use std::collections::VecDeque;
+
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use valence_protocol::encode::WritePacket;
-use valence_protocol::packets::play::{ChatMessageC2s, GameMessageS2c};
+use valence_protocol::packets::play::chat_message_s2c::MessageSignature;
+use valence_protocol::packets::play::{
+    ChatMessageC2s, GameMessageS2c, ProfilelessChatMessageS2c, RemoveMessageS2c, ServerMetadataS2c,
+};
 use valence_protocol::text::IntoText;
+use valence_protocol::{Text, VarInt};
+use valence_registry::chat_type::ChatTypeId;
 
+use crate::client::{Client, FlushPacketsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+use crate::Server;
 
 pub struct MessagePlugin;
 
 impl Plugin for MessagePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ChatMessageEvent>()
-            .add_systems(EventLoopPreUpdate, handle_chat_message);
+            .add_systems(EventLoopPreUpdate, handle_chat_message)
+            .add_systems(
+                PostUpdate,
+                (
+                    send_no_chat_reports_metadata.run_if(resource_exists::<NoChatReports>),
+                    send_queued_system_messages.before(FlushPacketsSet),
+                ),
+            );
+    }
+}
+
+/// Opts the server out of chat signing, so that chat messages can't be
+/// reported.
+///
+/// Valence never verifies or forwards the signatures of chat messages. Chat
+/// is only ever sent to clients as unsigned system or profileless messages,
+/// so there is nothing to report. With this resource inserted, joining
+/// clients are also told that secure chat isn't enforced. Clients then
+/// consistently treat the server as one without signed chat, instead of
+/// warning about messages which can't be verified.
+///
+/// Server list pings always report that secure chat isn't enforced.
+#[derive(Resource, Clone, PartialEq, Default, Debug)]
+pub struct NoChatReports {
+    /// The MOTD sent along with the secure chat flag. Clients show it in the
+    /// server list entry of the server until the list is refreshed.
+    pub motd: Text,
+}
+
+/// Queues system messages for a client and sends them at a limited rate, so
+/// that chatty systems can't flood the client with packets or the narrator
+/// with text.
+///
+/// Chat messages are sent in order, at most
+/// [`max_chat_per_tick`](Self::max_chat_per_tick) per tick. A message equal to
+/// one already waiting in the queue is dropped. Action bar messages replace
+/// each other, so only the latest one is sent once
+/// [`min_action_bar_interval`](Self::min_action_bar_interval) has passed.
+#[derive(Component, Clone, Debug)]
+pub struct SystemMessageQueue {
+    chat: VecDeque<Text>,
+    action_bar: Option<Text>,
+    last_action_bar_tick: Option<i64>,
+    /// The maximum number of chat messages sent per tick.
+    ///
+    /// # Default Value
+    ///
+    /// `2`
+    pub max_chat_per_tick: usize,
+    /// The maximum number of chat messages waiting in the queue. Messages
+    /// pushed beyond it are handled according to [`overflow`](Self::overflow).
+    ///
+    /// # Default Value
+    ///
+    /// `32`
+    pub capacity: usize,
+    /// # Default Value
+    ///
+    /// [`MessageOverflow::DropOldest`]
+    pub overflow: MessageOverflow,
+    /// The minimum number of ticks between two action bar messages.
+    ///
+    /// # Default Value
+    ///
+    /// `2`
+    pub min_action_bar_interval: u32,
+}
+
+/// What a [`SystemMessageQueue`] does with chat messages pushed while it is
+/// full.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum MessageOverflow {
+    /// Drops the oldest message in the queue to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Drops the new message.
+    DropNewest,
+}
+
+impl Default for SystemMessageQueue {
+    fn default() -> Self {
+        Self {
+            chat: VecDeque::new(),
+            action_bar: None,
+            last_action_bar_tick: None,
+            max_chat_per_tick: 2,
+            capacity: 32,
+            overflow: MessageOverflow::default(),
+            min_action_bar_interval: 2,
+        }
+    }
+}
+
+impl SystemMessageQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a system message visible in the chat. Returns `false` if the
+    /// message was dropped.
+    pub fn push_chat<'a, M: IntoText<'a>>(&mut self, msg: M) -> bool {
+        let msg = msg.into_cow_text().into_owned();
+
+        if self.chat.contains(&msg) {
+            return false;
+        }
+
+        if self.chat.len() >= self.capacity {
+            match self.overflow {
+                MessageOverflow::DropOldest if self.capacity > 0 => {
+                    self.chat.pop_front();
+                }
+                MessageOverflow::DropOldest | MessageOverflow::DropNewest => return false,
+            }
+        }
+
+        self.chat.push_back(msg);
+        true
+    }
+
+    /// Queues a message for the action bar, replacing the one waiting to be
+    /// sent.
+    pub fn push_action_bar<'a, M: IntoText<'a>>(&mut self, msg: M) {
+        self.action_bar = Some(msg.into_cow_text().into_owned());
+    }
+
+    /// The number of chat messages waiting to be sent.
+    pub fn chat_len(&self) -> usize {
+        self.chat.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chat.is_empty() && self.action_bar.is_none()
+    }
+
+    /// Drops every message waiting to be sent.
+    pub fn clear(&mut self) {
+        self.chat.clear();
+        self.action_bar = None;
     }
 }
 
@@ -22,6 +169,35 @@ pub trait SendMessage {
     fn send_chat_message<'a>(&mut self, msg: impl IntoText<'a>);
     /// Displays a message in the player's action bar (text above the hotbar).
     fn send_action_bar_message<'a>(&mut self, msg: impl IntoText<'a>);
+    /// Sends a message formatted by a chat type of the
+    /// [`ChatTypeRegistry`](valence_registry::ChatTypeRegistry), such as
+    /// `minecraft:say_command`. `sender` is the sender parameter of the chat
+    /// type.
+    fn send_chat_type_message<'a, 'b, S: IntoText<'a>, M: IntoText<'b>>(
+        &mut self,
+        chat_type: ChatTypeId,
+        sender: S,
+        msg: M,
+    );
+    /// Like [`send_chat_type_message`](Self::send_chat_type_message), for chat
+    /// types with a target parameter such as
+    /// `minecraft:team_msg_command_incoming`.
+    fn send_targeted_chat_type_message<'a, 'b, 'c, S, T, M>(
+        &mut self,
+        chat_type: ChatTypeId,
+        sender: S,
+        target: T,
+        msg: M,
+    ) where
+        S: IntoText<'a>,
+        T: IntoText<'b>,
+        M: IntoText<'c>;
+    /// Removes the signed chat message with the given signature from the chat.
+    /// Clients replace the message with a "message deleted" notice.
+    ///
+    /// Written to a layer, this retracts the message for every client viewing
+    /// it.
+    fn delete_chat_message(&mut self, signature: &[u8; 256]);
 }
 
 impl<T: WritePacket> SendMessage for T {
@@ -38,6 +214,49 @@ impl<T: WritePacket> SendMessage for T {
             overlay: true,
         });
     }
+
+    fn send_chat_type_message<'a, 'b, S: IntoText<'a>, M: IntoText<'b>>(
+        &mut self,
+        chat_type: ChatTypeId,
+        sender: S,
+        msg: M,
+    ) {
+        self.write_packet(&ProfilelessChatMessageS2c {
+            message: msg.into_cow_text(),
+            chat_type: VarInt(chat_type.to_raw()),
+            chat_type_name: sender.into_cow_text(),
+            target_name: None,
+        });
+    }
+
+    fn send_targeted_chat_type_message<'a, 'b, 'c, S, T, M>(
+        &mut self,
+        chat_type: ChatTypeId,
+        sender: S,
+        target: T,
+        msg: M,
+    ) where
+        S: IntoText<'a>,
+        T: IntoText<'b>,
+        M: IntoText<'c>,
+    {
+        self.write_packet(&ProfilelessChatMessageS2c {
+            message: msg.into_cow_text(),
+            chat_type: VarInt(chat_type.to_raw()),
+            chat_type_name: sender.into_cow_text(),
+            target_name: Some(target.into_cow_text()),
+        });
+    }
+
+    fn delete_chat_message(&mut self, signature: &[u8; 256]) {
+        self.write_packet(&RemoveMessageS2c {
+            // An ID of -1 means the full signature follows.
+            signature: MessageSignature {
+                message_id: -1,
+                signature: Some(signature),
+            },
+        });
+    }
 }
 
 #[derive(Event, Clone, Debug)]
@@ -47,6 +266,51 @@ pub struct ChatMessageEvent {
     pub timestamp: u64,
 }
 
+fn send_no_chat_reports_metadata(
+    mut clients: Query<&mut Client, Added<Client>>,
+    settings: Res<NoChatReports>,
+) {
+    for mut client in &mut clients {
+        client.write_packet(&ServerMetadataS2c {
+            motd: (&settings.motd).into(),
+            icon: None,
+            enforce_secure_chat: false,
+        });
+    }
+}
+
+fn send_queued_system_messages(
+    mut clients: Query<(&mut Client, &mut SystemMessageQueue)>,
+    server: Res<Server>,
+) {
+    let tick = server.current_tick();
+
+    for (mut client, queue) in &mut clients {
+        if queue.is_empty() {
+            continue;
+        }
+
+        let queue = queue.into_inner();
+
+        let count = queue.max_chat_per_tick.min(queue.chat.len());
+
+        for msg in queue.chat.drain(..count) {
+            client.send_chat_message(msg);
+        }
+
+        let interval_passed = queue.last_action_bar_tick.map_or(true, |last| {
+            tick - last >= i64::from(queue.min_action_bar_interval)
+        });
+
+        if interval_passed {
+            if let Some(msg) = queue.action_bar.take() {
+                client.send_action_bar_message(msg);
+                queue.last_action_bar_tick = Some(tick);
+            }
+        }
+    }
+}
+
 pub fn handle_chat_message(
     mut packets: EventReader<PacketEvent>,
     mut events: EventWriter<ChatMessageEvent>,
@@ -1,11 +1,14 @@
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use valence_entity::{HeadYaw, Look, OnGround, Position};
-use valence_math::DVec3;
+use valence_math::{DVec3, Vec3, Vec3Swizzles};
 use valence_protocol::packets::play::{
-    FullC2s, LookAndOnGroundC2s, OnGroundOnlyC2s, PositionAndOnGroundC2s, VehicleMoveC2s,
+    FullC2s, LookAndOnGroundC2s, OnGroundOnlyC2s, PlayPingS2c, PlayPongC2s, PositionAndOnGroundC2s,
+    VehicleMoveC2s,
 };
+use valence_protocol::WritePacket;
 
+use crate::client::{Client, FlushPacketsSet};
 use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
 use crate::teleport::TeleportState;
 
@@ -15,7 +18,13 @@ impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<MovementSettings>()
             .add_event::<MovementEvent>()
-            .add_systems(EventLoopPreUpdate, handle_client_movement);
+            .add_systems(EventLoopPreUpdate, handle_client_movement)
+            .add_systems(
+                PostUpdate,
+                (advance_velocities, send_velocities)
+                    .chain()
+                    .before(FlushPacketsSet),
+            );
     }
 }
 
@@ -23,6 +32,130 @@ impl Plugin for MovementPlugin {
 #[derive(Resource, Default)]
 pub struct MovementSettings; // TODO
 
+/// Opt-in server authority over the velocity of a client, such as knockback
+/// from explosions or attacks.
+///
+/// Clients are in charge of their own movement, so velocity sent with
+/// [`Client::set_velocity`] is easily ignored. Velocity applied through this
+/// component is instead followed on the server for [`Self::window`] ticks. If
+/// the client strays more than [`Self::tolerance`] blocks short of the
+/// horizontal path the velocity should have taken it along, its movement is
+/// rejected and it is teleported back onto the path.
+///
+/// The velocity is followed by a ping, and the path only starts once the
+/// client answers it. Positions sent before that were sent before the client
+/// received the velocity, so lagging clients aren't corrected. The path moves
+/// once per tick, and only packets with a position are checked.
+#[derive(Component, Clone, Debug)]
+pub struct VelocityAuthority {
+    /// How far in blocks the client may fall behind the expected path.
+    pub tolerance: f64,
+    /// The number of ticks after applying velocity during which movement is
+    /// checked.
+    pub window: u32,
+    queued: Option<Vec3>,
+    active: Option<ActiveVelocity>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ActiveVelocity {
+    start: DVec3,
+    expected: DVec3,
+    /// The remaining horizontal velocity in blocks per tick.
+    velocity: DVec3,
+    ticks: u32,
+    /// The ID of the ping sent after the velocity.
+    ping_id: i32,
+    /// Whether the client answered the ping, i.e. whether it has received the
+    /// velocity.
+    confirmed: bool,
+}
+
+impl VelocityAuthority {
+    pub fn new(tolerance: f64, window: u32) -> Self {
+        Self {
+            tolerance,
+            window,
+            queued: None,
+            active: None,
+        }
+    }
+
+    /// Sends `velocity` to the client and starts checking that it is
+    /// followed. `velocity` is in m/s.
+    pub fn apply(&mut self, velocity: Vec3) {
+        self.queued = Some(velocity);
+    }
+
+    /// Returns whether the client's movement is currently being checked.
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Starts the expected path at `position` if `id` is the ID of the ping
+    /// sent after the velocity.
+    fn confirm(&mut self, id: i32, position: DVec3) {
+        if let Some(active) = &mut self.active {
+            if !active.confirmed && active.ping_id == id {
+                active.confirmed = true;
+                active.start = position;
+                active.expected = position;
+            }
+        }
+    }
+
+    /// Advances the expected path by a tick once the client has received the
+    /// velocity.
+    fn advance(&mut self, on_ground: bool) {
+        let Some(active) = &mut self.active else {
+            return;
+        };
+
+        if !active.confirmed {
+            return;
+        }
+
+        active.expected += active.velocity;
+        active.ticks += 1;
+
+        // Vanilla's horizontal drag in the air and on regular blocks.
+        active.velocity *= if on_ground { 0.91 * 0.6 } else { 0.91 };
+
+        if active.ticks >= self.window {
+            self.active = None;
+        }
+    }
+
+    /// Returns the position to teleport the client to if `position` is too far
+    /// behind the expected path.
+    fn check(&mut self, position: DVec3) -> Option<DVec3> {
+        let active = self.active.as_ref().filter(|active| active.confirmed)?;
+
+        let expected = (active.expected - active.start).xz();
+        let actual = (position - active.start).xz();
+        let expected_distance = expected.length();
+
+        // Only movement along the velocity is checked. Clients may still add their
+        // own movement on top of it.
+        let actual_distance = actual.dot(expected.normalize_or_zero());
+
+        let corrected = (actual_distance < expected_distance - self.tolerance)
+            .then(|| DVec3::new(active.expected.x, position.y, active.expected.z));
+
+        if corrected.is_some() {
+            self.active = None;
+        }
+
+        corrected
+    }
+}
+
+impl Default for VelocityAuthority {
+    fn default() -> Self {
+        Self::new(1.0, 10)
+    }
+}
+
 /// Event sent when a client successfully moves.
 #[derive(Event, Clone, Debug)]
 pub struct MovementEvent {
@@ -43,12 +176,13 @@ fn handle_client_movement(
         &mut HeadYaw,
         &mut OnGround,
         &mut TeleportState,
+        Option<&mut VelocityAuthority>,
     )>,
     mut movement_events: EventWriter<MovementEvent>,
 ) {
     for packet in packets.read() {
         if let Some(pkt) = packet.decode::<PositionAndOnGroundC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((pos, look, head_yaw, on_ground, teleport_state, authority)) =
                 clients.get_mut(packet.client)
             {
                 let mov = MovementEvent {
@@ -63,16 +197,18 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    true,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    authority,
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<FullC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((pos, look, head_yaw, on_ground, teleport_state, authority)) =
                 clients.get_mut(packet.client)
             {
                 let mov = MovementEvent {
@@ -90,16 +226,18 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    true,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    authority,
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<LookAndOnGroundC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((pos, look, head_yaw, on_ground, teleport_state, authority)) =
                 clients.get_mut(packet.client)
             {
                 let mov = MovementEvent {
@@ -117,16 +255,18 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    false,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    authority,
                     &mut movement_events,
                 );
             }
         } else if let Some(pkt) = packet.decode::<OnGroundOnlyC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((pos, look, head_yaw, on_ground, teleport_state, authority)) =
                 clients.get_mut(packet.client)
             {
                 let mov = MovementEvent {
@@ -141,16 +281,22 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    false,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    authority,
                     &mut movement_events,
                 );
             }
+        } else if let Some(pkt) = packet.decode::<PlayPongC2s>() {
+            if let Ok((pos, _, _, _, _, Some(mut authority))) = clients.get_mut(packet.client) {
+                authority.confirm(pkt.id, pos.0);
+            }
         } else if let Some(pkt) = packet.decode::<VehicleMoveC2s>() {
-            if let Ok((pos, look, head_yaw, on_ground, teleport_state)) =
+            if let Ok((pos, look, head_yaw, on_ground, teleport_state, authority)) =
                 clients.get_mut(packet.client)
             {
                 let mov = MovementEvent {
@@ -168,11 +314,13 @@ fn handle_client_movement(
 
                 handle(
                     mov,
+                    true,
                     pos,
                     look,
                     head_yaw,
                     on_ground,
                     teleport_state,
+                    authority,
                     &mut movement_events,
                 );
             }
@@ -180,19 +328,33 @@ fn handle_client_movement(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle(
     mov: MovementEvent,
+    has_position: bool,
     mut pos: Mut<Position>,
     mut look: Mut<Look>,
     mut head_yaw: Mut<HeadYaw>,
     mut on_ground: Mut<OnGround>,
     mut teleport_state: Mut<TeleportState>,
+    authority: Option<Mut<VelocityAuthority>>,
     movement_events: &mut EventWriter<MovementEvent>,
 ) {
     if teleport_state.pending_teleports() != 0 {
         return;
     }
 
+    // Look-only packets carry the last position, which says nothing about
+    // following the velocity.
+    if let Some(mut authority) = authority.filter(|_| has_position) {
+        if let Some(corrected) = authority.check(mov.position) {
+            // The position no longer matches the synced position, so the client is
+            // teleported.
+            pos.set_if_neq(Position(corrected));
+            return;
+        }
+    }
+
     // TODO: check that the client isn't moving too fast / flying.
     // TODO: check that the client isn't clipping through blocks.
 
@@ -205,3 +367,32 @@ fn handle(
 
     movement_events.send(mov);
 }
+
+fn advance_velocities(mut clients: Query<(&mut VelocityAuthority, &OnGround)>) {
+    for (mut authority, on_ground) in &mut clients {
+        authority.advance(on_ground.0);
+    }
+}
+
+fn send_velocities(mut clients: Query<(&mut Client, &mut VelocityAuthority, &Position)>) {
+    for (mut client, mut authority, pos) in &mut clients {
+        if let Some(velocity) = authority.queued.take() {
+            client.set_velocity(velocity);
+
+            // The client answers the ping after it has received the velocity.
+            let ping_id = rand::random();
+            client.write_packet(&PlayPingS2c { id: ping_id });
+
+            let per_tick = velocity.as_dvec3() / 20.0;
+
+            authority.active = Some(ActiveVelocity {
+                start: pos.0,
+                expected: pos.0,
+                velocity: DVec3::new(per_tick.x, 0.0, per_tick.z),
+                ticks: 0,
+                ping_id,
+                confirmed: false,
+            });
+        }
+    }
+}
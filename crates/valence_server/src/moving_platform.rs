@@ -0,0 +1,441 @@
+//! Platforms of blocks moving along a path, carrying the players on top.
+//!
+//! Spawn a [`MovingPlatformBundle`] to get a box of blocks following the
+//! waypoints of its [`MovingPlatform`], such as an elevator or a parkour
+//! platform sliding back and forth. The blocks are drawn by a block display,
+//! and invisible shulkers moving along with it give the platform collisions on
+//! the client, so players can stand on it and bump into it.
+//!
+//! Clients don't move players standing on entities, so players on top of the
+//! platform are carried along by the server as chosen with [`CarryMode`].
+//!
+//! The platform has one shulker for every block in it, so keep platforms
+//! small. Despawning the platform despawns its shulkers as well.
+//!
+//! [`MovingPlatformPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::block_display::{self, BlockDisplayEntityBundle};
+use valence_entity::display::Scale;
+use valence_entity::entity::{Flags, NoGravity, Silent};
+use valence_entity::mob::MobFlags;
+use valence_entity::shulker::ShulkerEntityBundle;
+use valence_entity::{EntityLayerId, InitEntitiesSet, Position};
+use valence_math::{DVec3, UVec3};
+use valence_protocol::BlockState;
+use valence_server_common::Despawned;
+
+use crate::client::Client;
+use crate::game_mode::NotSpectator;
+
+pub struct MovingPlatformPlugin;
+
+impl Plugin for MovingPlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (spawn_colliders, move_platforms, despawn_colliders)
+                .chain()
+                .before(InitEntitiesSet),
+        );
+    }
+}
+
+/// A [`Component`] moving a block display along a path. The position of the
+/// display is the corner of the platform with the lowest coordinates.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct MovingPlatform {
+    /// The positions the platform moves through, in order. The platform moves
+    /// from where it's spawned to the first waypoint first.
+    pub waypoints: Vec<DVec3>,
+    /// The speed of the platform, in blocks per tick.
+    pub speed: f64,
+    /// The number of ticks the platform waits at every waypoint.
+    pub wait: u32,
+    pub path_mode: PathMode,
+    pub carry: CarryMode,
+    /// The size of the platform, in blocks.
+    size: UVec3,
+    /// The index of the waypoint the platform is moving to.
+    target: usize,
+    /// Whether the platform is going through the waypoints backwards.
+    reverse: bool,
+    /// The number of ticks the platform still waits at its waypoint.
+    waiting: u32,
+    finished: bool,
+}
+
+impl MovingPlatform {
+    /// Creates a platform of `size` blocks following `waypoints` at one block
+    /// per tick.
+    pub fn new(size: impl Into<UVec3>, waypoints: Vec<DVec3>) -> Self {
+        Self {
+            waypoints,
+            speed: 1.0,
+            wait: 0,
+            path_mode: PathMode::default(),
+            carry: CarryMode::default(),
+            size: size.into(),
+            target: 0,
+            reverse: false,
+            waiting: 0,
+            finished: false,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_wait(mut self, wait: u32) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    pub fn with_path_mode(mut self, path_mode: PathMode) -> Self {
+        self.path_mode = path_mode;
+        self
+    }
+
+    pub fn with_carry(mut self, carry: CarryMode) -> Self {
+        self.carry = carry;
+        self
+    }
+
+    pub fn size(&self) -> UVec3 {
+        self.size
+    }
+
+    /// Returns whether the platform reached the end of a [`PathMode::Once`]
+    /// path.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves the platform at `pos` along its path for one tick and returns its
+    /// new position.
+    fn advance(&mut self, mut pos: DVec3) -> DVec3 {
+        let mut remaining = self.speed;
+
+        // Waypoints at the same position could make this loop forever.
+        for _ in 0..=self.waypoints.len() {
+            if self.finished || remaining <= 0.0 {
+                break;
+            }
+
+            if self.waiting > 0 {
+                self.waiting -= 1;
+                break;
+            }
+
+            let Some(&target) = self.waypoints.get(self.target) else {
+                break;
+            };
+
+            let dist = pos.distance(target);
+
+            if dist > remaining {
+                pos += (target - pos) / dist * remaining;
+                break;
+            }
+
+            pos = target;
+            remaining -= dist;
+            self.waiting = self.wait;
+            self.next_waypoint();
+
+            if self.waiting > 0 {
+                break;
+            }
+        }
+
+        pos
+    }
+
+    fn next_waypoint(&mut self) {
+        let last = self.waypoints.len().saturating_sub(1);
+
+        match self.path_mode {
+            PathMode::Once => {
+                if self.target >= last {
+                    self.finished = true;
+                } else {
+                    self.target += 1;
+                }
+            }
+            PathMode::Loop => {
+                self.target = if self.target >= last {
+                    0
+                } else {
+                    self.target + 1
+                };
+            }
+            PathMode::PingPong => {
+                if self.reverse && self.target == 0 || !self.reverse && self.target >= last {
+                    self.reverse = !self.reverse;
+                }
+
+                self.target = if self.reverse {
+                    self.target.saturating_sub(1)
+                } else {
+                    (self.target + 1).min(last)
+                };
+            }
+        }
+    }
+}
+
+/// What a [`MovingPlatform`] does after reaching its last waypoint.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum PathMode {
+    /// Stop at the last waypoint.
+    Once,
+    /// Move on to the first waypoint again.
+    Loop,
+    /// Go back through the waypoints in reverse.
+    #[default]
+    PingPong,
+}
+
+/// How players standing on a [`MovingPlatform`] are moved along with it.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum CarryMode {
+    /// Teleport the players by the distance the platform moved. Players stay
+    /// on the platform exactly, but their own movement stutters while riding.
+    #[default]
+    Teleport,
+    /// Set the velocity of the players to the velocity of the platform.
+    /// Players move freely, but slowly slide off the platform since the
+    /// client slows them down with friction.
+    Velocity,
+    /// Don't move the players.
+    None,
+}
+
+/// Bundle for spawning a [`MovingPlatform`].
+#[derive(Bundle)]
+pub struct MovingPlatformBundle {
+    pub platform: MovingPlatform,
+    pub display: BlockDisplayEntityBundle,
+}
+
+impl MovingPlatformBundle {
+    /// Creates a platform made of `block` at `position` in `layer`.
+    pub fn new<P: Into<DVec3>>(
+        layer: Entity,
+        position: P,
+        block: BlockState,
+        platform: MovingPlatform,
+    ) -> Self {
+        let size = platform.size.as_vec3();
+
+        Self {
+            platform,
+            display: BlockDisplayEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(position),
+                block_display_block_state: block_display::BlockState(block),
+                display_scale: Scale(size),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The shulkers giving a [`MovingPlatform`] its collisions.
+#[derive(Component, Debug)]
+struct PlatformColliders(Vec<Entity>);
+
+/// Marks the shulkers of a [`MovingPlatform`].
+#[derive(Component, Debug)]
+struct PlatformCollider;
+
+/// The position of the shulker for the block at `offset` in a platform at
+/// `pos`. Shulkers stand in the middle of their block.
+fn collider_position(pos: DVec3, offset: UVec3) -> DVec3 {
+    pos + offset.as_dvec3() + DVec3::new(0.5, 0.0, 0.5)
+}
+
+fn collider_offsets(size: UVec3) -> impl Iterator<Item = UVec3> {
+    (0..size.x).flat_map(move |x| {
+        (0..size.y).flat_map(move |y| (0..size.z).map(move |z| UVec3::new(x, y, z)))
+    })
+}
+
+fn spawn_colliders(
+    platforms: Query<(Entity, &MovingPlatform, &Position, &EntityLayerId), Added<MovingPlatform>>,
+    mut commands: Commands,
+) {
+    for (entity, platform, pos, layer) in &platforms {
+        let mut flags = Flags::default();
+        flags.set_invisible(true);
+
+        let mut mob_flags = MobFlags::default();
+        mob_flags.set_ai_disabled(true);
+
+        let colliders = collider_offsets(platform.size)
+            .map(|offset| {
+                commands
+                    .spawn((
+                        ShulkerEntityBundle {
+                            layer: *layer,
+                            position: Position(collider_position(pos.0, offset)),
+                            entity_flags: flags.clone(),
+                            entity_no_gravity: NoGravity(true),
+                            entity_silent: Silent(true),
+                            mob_mob_flags: mob_flags.clone(),
+                            ..Default::default()
+                        },
+                        PlatformCollider,
+                    ))
+                    .id()
+            })
+            .collect();
+
+        commands.entity(entity).insert(PlatformColliders(colliders));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn move_platforms(
+    mut platforms: Query<
+        (
+            &mut MovingPlatform,
+            &mut Position,
+            &EntityLayerId,
+            Option<&PlatformColliders>,
+        ),
+        Without<Client>,
+    >,
+    mut colliders: Query<
+        (&mut Position, &mut EntityLayerId),
+        (
+            With<PlatformCollider>,
+            Without<MovingPlatform>,
+            Without<Client>,
+        ),
+    >,
+    mut clients: Query<
+        (&mut Client, &mut Position, &EntityLayerId),
+        (Without<MovingPlatform>, NotSpectator),
+    >,
+) {
+    for (mut platform, mut pos, layer, platform_colliders) in &mut platforms {
+        let old_pos = pos.0;
+        let new_pos = platform.advance(old_pos);
+
+        pos.set_if_neq(Position(new_pos));
+
+        if let Some(platform_colliders) = platform_colliders {
+            for (&collider, offset) in platform_colliders
+                .0
+                .iter()
+                .zip(collider_offsets(platform.size))
+            {
+                if let Ok((mut collider_pos, mut collider_layer)) = colliders.get_mut(collider) {
+                    collider_pos.set_if_neq(Position(collider_position(new_pos, offset)));
+                    collider_layer.set_if_neq(*layer);
+                }
+            }
+        }
+
+        let delta = new_pos - old_pos;
+
+        if delta == DVec3::ZERO || platform.carry == CarryMode::None {
+            continue;
+        }
+
+        let size = platform.size.as_dvec3();
+        let top = old_pos.y + size.y;
+
+        for (mut client, mut client_pos, client_layer) in &mut clients {
+            // Players are 0.6 blocks wide, so they can stand on the edge.
+            let on_top = client_layer == layer
+                && (client_pos.y - top).abs() < 0.25
+                && client_pos.x > old_pos.x - 0.3
+                && client_pos.x < old_pos.x + size.x + 0.3
+                && client_pos.z > old_pos.z - 0.3
+                && client_pos.z < old_pos.z + size.z + 0.3;
+
+            if !on_top {
+                continue;
+            }
+
+            match platform.carry {
+                CarryMode::Teleport => client_pos.0 += delta,
+                // The velocity is in blocks per second.
+                CarryMode::Velocity => client.set_velocity(delta.as_vec3() * 20.0),
+                CarryMode::None => {}
+            }
+        }
+    }
+}
+
+fn despawn_colliders(
+    platforms: Query<&PlatformColliders, With<Despawned>>,
+    mut commands: Commands,
+) {
+    for platform_colliders in &platforms {
+        for &collider in &platform_colliders.0 {
+            if let Some(mut collider) = commands.get_entity(collider) {
+                collider.insert(Despawned);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(path_mode: PathMode) -> MovingPlatform {
+        MovingPlatform::new([1, 1, 1], vec![DVec3::ZERO, DVec3::new(2.0, 0.0, 0.0)])
+            .with_path_mode(path_mode)
+    }
+
+    #[test]
+    fn path_modes() {
+        let mut once = platform(PathMode::Once);
+        let mut ping_pong = platform(PathMode::PingPong);
+        let mut looping = platform(PathMode::Loop);
+
+        let mut once_pos = DVec3::ZERO;
+        let mut ping_pong_pos = DVec3::ZERO;
+        let mut loop_pos = DVec3::ZERO;
+
+        let mut ping_pong_xs = vec![];
+        let mut loop_xs = vec![];
+
+        for _ in 0..6 {
+            once_pos = once.advance(once_pos);
+            ping_pong_pos = ping_pong.advance(ping_pong_pos);
+            loop_pos = looping.advance(loop_pos);
+
+            ping_pong_xs.push(ping_pong_pos.x);
+            loop_xs.push(loop_pos.x);
+        }
+
+        assert_eq!(once_pos.x, 2.0);
+        assert!(once.is_finished());
+        assert_eq!(ping_pong_xs, [1.0, 2.0, 1.0, 0.0, 1.0, 2.0]);
+        // Going back to the first waypoint takes two ticks.
+        assert_eq!(loop_xs, [1.0, 2.0, 1.0, 0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn waiting_at_waypoints() {
+        let mut platform = platform(PathMode::PingPong).with_wait(2);
+        let mut pos = DVec3::ZERO;
+        let mut xs = vec![];
+
+        for _ in 0..6 {
+            pos = platform.advance(pos);
+            xs.push(pos.x);
+        }
+
+        assert_eq!(xs, [0.0, 0.0, 0.0, 1.0, 2.0, 2.0]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Keeping the poses of players in sync with what they're doing.
+//!
+//! Clients animate their own player, but other clients only see the pose in
+//! the player's tracked data. [`PosePlugin`] updates the [`Pose`] of every
+//! client each tick like vanilla: gliding, swimming, riptide spins, sleeping
+//! and sneaking each have their own pose. A player which doesn't fit in the
+//! space it's in, such as under a slab, sneaks, or crawls if it doesn't fit
+//! while sneaking either.
+//!
+//! Clients also start swimming here. A client is swimming while it sprints
+//! with its eyes under water, and stops once it stops sprinting or leaves the
+//! water.
+//!
+//! Poses set by the server, such as [`Pose::Dying`] or a custom animation,
+//! would be overwritten on the next tick. Insert [`ManualPose`] on a client to
+//! keep its pose as it is; it's updated again once the component is removed.
+//!
+//! [`Pose`]: entity::Pose
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryData;
+use valence_entity::entity::Flags;
+use valence_entity::living::{LivingFlags, SleepingPosition};
+use valence_entity::passengers::Vehicle;
+use valence_entity::{entity, Pose, Position, UpdateTrackedDataSet};
+use valence_math::{Aabb, DVec3};
+use valence_protocol::block::{BlockKind, PropName, PropValue};
+use valence_protocol::{BlockPos, BlockState, GameMode};
+
+use crate::abilities::PlayerAbilitiesFlags;
+use crate::client::{Client, VisibleChunkLayer};
+use crate::client_command::PlayerInput;
+use crate::layer::ChunkLayer;
+
+pub struct PosePlugin;
+
+/// The [`SystemSet`] in [`PostUpdate`] where the poses of clients are
+/// updated. Systems changing what poses depend on, such as the fall flying
+/// flag, should run before it.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UpdatePosesSet;
+
+impl Plugin for PosePlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(PostUpdate, UpdatePosesSet.before(UpdateTrackedDataSet))
+            .add_systems(PostUpdate, update_poses.in_set(UpdatePosesSet));
+    }
+}
+
+/// Returns the size of a player's hitbox in `pose`.
+fn player_size(pose: Pose) -> DVec3 {
+    match pose {
+        Pose::Sleeping | Pose::Dying => DVec3::splat(0.2),
+        Pose::FallFlying | Pose::Swimming | Pose::SpinAttack => DVec3::splat(0.6),
+        Pose::Sneaking => DVec3::new(0.6, 1.5, 0.6),
+        _ => DVec3::new(0.6, 1.8, 0.6),
+    }
+}
+
+/// Returns the height of a player's eyes above its position in `pose`.
+fn eye_height(pose: Pose) -> f64 {
+    match pose {
+        Pose::Sleeping | Pose::Dying => 0.2,
+        Pose::FallFlying | Pose::Swimming | Pose::SpinAttack => 0.4,
+        Pose::Sneaking => 1.27,
+        _ => 1.62,
+    }
+}
+
+fn is_water(state: BlockState) -> bool {
+    matches!(
+        state.to_kind(),
+        BlockKind::Water
+            | BlockKind::BubbleColumn
+            | BlockKind::Kelp
+            | BlockKind::KelpPlant
+            | BlockKind::Seagrass
+            | BlockKind::TallSeagrass
+    ) || state.get(PropName::Waterlogged) == Some(PropValue::True)
+}
+
+fn water_at(layer: &ChunkLayer, pos: DVec3) -> bool {
+    layer
+        .block(BlockPos::from(pos))
+        .is_some_and(|block| is_water(block.state))
+}
+
+/// Returns whether a player in `pose` at `pos` doesn't collide with blocks.
+fn fits(layer: &ChunkLayer, pos: DVec3, pose: Pose) -> bool {
+    let aabb = Aabb::from_bottom_size(pos, player_size(pose));
+
+    // Like vanilla, the box is shrunk a little so players standing right next
+    // to blocks fit.
+    let aabb = Aabb::new(aabb.min() + 1e-7, aabb.max() - 1e-7);
+
+    !layer.collides(aabb)
+}
+
+/// Stops [`PosePlugin`] from changing the [`Pose`](entity::Pose) of the
+/// client this is attached to, so the pose can be controlled manually. The
+/// swimming flag is still updated.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ManualPose;
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct PoseQuery {
+    pose: &'static mut entity::Pose,
+    flags: &'static mut Flags,
+    pos: &'static Position,
+    living_flags: &'static LivingFlags,
+    sleeping_pos: &'static SleepingPosition,
+    input: &'static PlayerInput,
+    abilities: &'static PlayerAbilitiesFlags,
+    game_mode: &'static GameMode,
+    layer: &'static VisibleChunkLayer,
+    riding: Has<Vehicle>,
+    manual: Has<ManualPose>,
+}
+
+fn update_poses(mut clients: Query<PoseQuery, With<Client>>, layers: Query<&ChunkLayer>) {
+    for mut client in &mut clients {
+        let layer = layers.get(client.layer.0).ok();
+        let pos = client.pos.0;
+
+        let sprinting = client.flags.sprinting() && !client.riding;
+
+        let swimming = match layer {
+            _ if !sprinting || *client.game_mode == GameMode::Spectator => false,
+            // Swimming players only stop once they're out of the water
+            // entirely.
+            Some(layer) if client.flags.swimming() => {
+                water_at(layer, pos) || water_at(layer, pos + DVec3::new(0.0, 0.6, 0.0))
+            }
+            Some(layer) => water_at(layer, pos + DVec3::new(0.0, eye_height(client.pose.0), 0.0)),
+            None => false,
+        };
+
+        if client.flags.swimming() != swimming {
+            client.flags.set_swimming(swimming);
+        }
+
+        if client.manual {
+            continue;
+        }
+
+        let desired = if client.flags.fall_flying() {
+            Pose::FallFlying
+        } else if client.sleeping_pos.0.is_some() {
+            Pose::Sleeping
+        } else if swimming {
+            Pose::Swimming
+        } else if client.living_flags.using_riptide() {
+            Pose::SpinAttack
+        } else if client.input.sneaking && !client.abilities.flying() {
+            Pose::Sneaking
+        } else {
+            Pose::Standing
+        };
+
+        let pose = match layer {
+            Some(layer)
+                if *client.game_mode != GameMode::Spectator
+                    && !client.riding
+                    && desired != Pose::Sleeping
+                    && !fits(layer, pos, desired) =>
+            {
+                if fits(layer, pos, Pose::Sneaking) {
+                    Pose::Sneaking
+                } else {
+                    Pose::Swimming
+                }
+            }
+            _ => desired,
+        };
+
+        client.pose.set_if_neq(entity::Pose(pose));
+    }
+}
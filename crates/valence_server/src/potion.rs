@@ -0,0 +1,270 @@
+//! Potions, splash potions, lingering potions and tipped arrows.
+//!
+//! All of these items share the same NBT: a base [`Potion`] type, an optional
+//! custom color and a list of custom status effects. [`PotionContents`]
+//! builds and reads that NBT, so systems handling drinking or arrow hits can
+//! get the effects of a stack with [`PotionContents::from_stack`].
+//!
+//! ```
+//! # use valence_server::potion::{Potion, PotionContents};
+//! # use valence_server::entity::active_status_effects::ActiveStatusEffect;
+//! # use valence_server::protocol::status_effects::StatusEffect;
+//! # use valence_server::ItemKind;
+//! let potion = PotionContents::new(Potion::StrongSwiftness)
+//!     .with_effect(ActiveStatusEffect::from_effect(StatusEffect::Glowing).with_duration(200))
+//!     .with_color(0xff8800)
+//!     .splash_potion();
+//!
+//! assert_eq!(potion.item, ItemKind::SplashPotion);
+//!
+//! let contents = PotionContents::from_stack(&potion).unwrap();
+//! assert_eq!(contents.effects().len(), 2);
+//! ```
+
+use valence_entity::active_status_effects::ActiveStatusEffect;
+use valence_nbt::{compound, Compound, List, Value};
+pub use valence_protocol::potions::{Potion, PotionEffect};
+use valence_protocol::status_effects::StatusEffect;
+use valence_protocol::{Ident, ItemKind, ItemStack};
+
+/// The color of potions without any effects, such as water bottles.
+pub const WATER_COLOR: u32 = 0x385dc6;
+/// The color of potions of the [`Potion::Empty`] type, shown as "Uncraftable
+/// Potion" by clients.
+pub const EMPTY_COLOR: u32 = 0xf800f8;
+
+/// Returns whether `item` holds potion contents.
+pub const fn is_potion_item(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::Potion
+            | ItemKind::SplashPotion
+            | ItemKind::LingeringPotion
+            | ItemKind::TippedArrow
+    )
+}
+
+/// The contents of a potion or tipped arrow.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct PotionContents {
+    /// The base potion type, which gives its own effects.
+    pub potion: Potion,
+    /// The RGB color of the item and its particles. If `None`, the color is
+    /// mixed from the colors of the effects.
+    pub custom_color: Option<u32>,
+    /// Effects given on top of those of the base potion.
+    pub custom_effects: Vec<ActiveStatusEffect>,
+}
+
+impl PotionContents {
+    pub fn new(potion: Potion) -> Self {
+        Self {
+            potion,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_color(mut self, rgb: u32) -> Self {
+        self.custom_color = Some(rgb);
+        self
+    }
+
+    #[must_use]
+    pub fn with_effect(mut self, effect: ActiveStatusEffect) -> Self {
+        self.custom_effects.push(effect);
+        self
+    }
+
+    /// All effects given by the potion: those of the base potion followed by
+    /// the custom effects.
+    ///
+    /// In vanilla, tipped arrows only apply an eighth of the duration of the
+    /// base potion's effects, and lingering potions a quarter.
+    pub fn effects(&self) -> Vec<ActiveStatusEffect> {
+        self.potion
+            .effects()
+            .iter()
+            .map(|effect| {
+                ActiveStatusEffect::from_effect(effect.effect)
+                    .with_amplifier(effect.amplifier)
+                    .with_duration(effect.duration)
+            })
+            .chain(self.custom_effects.iter().cloned())
+            .collect()
+    }
+
+    /// The RGB color of the item and its particles, computed like vanilla.
+    ///
+    /// Like vanilla, potions of the [`Potion::Empty`] type are always
+    /// [`EMPTY_COLOR`] unless they have a custom color, even if they have
+    /// custom effects.
+    pub fn color(&self) -> u32 {
+        if let Some(rgb) = self.custom_color {
+            return rgb;
+        }
+
+        if self.potion == Potion::Empty {
+            return EMPTY_COLOR;
+        }
+
+        let effects = self.effects();
+
+        if effects.is_empty() {
+            return WATER_COLOR;
+        }
+
+        let mut rgb = [0.0_f32; 3];
+        let mut total_weight = 0;
+
+        for effect in effects.iter().filter(|effect| effect.show_particles()) {
+            let color = effect.status_effect().color();
+            let weight = u32::from(effect.amplifier()) + 1;
+
+            for (i, channel) in rgb.iter_mut().enumerate() {
+                let value = (color >> (16 - 8 * i)) & 0xff;
+                *channel += weight as f32 * value as f32 / 255.0;
+            }
+
+            total_weight += weight;
+        }
+
+        if total_weight == 0 {
+            return 0;
+        }
+
+        rgb.iter().fold(0, |color, channel| {
+            (color << 8) | (channel / total_weight as f32 * 255.0) as u32
+        })
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let potion = String::from(self.potion.to_ident());
+        let mut nbt = compound! {
+            "Potion" => potion,
+        };
+
+        if let Some(rgb) = self.custom_color {
+            nbt.insert("CustomPotionColor", rgb as i32);
+        }
+
+        if !self.custom_effects.is_empty() {
+            let effects = self.custom_effects.iter().map(effect_to_nbt).collect();
+            nbt.insert("CustomPotionEffects", List::Compound(effects));
+        }
+
+        nbt
+    }
+
+    /// Reads the contents from the NBT of a potion or tipped arrow. Unknown
+    /// potions and effects are skipped.
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let potion = match nbt.get("Potion") {
+            Some(Value::String(id)) => Ident::new(id.as_str())
+                .ok()
+                .and_then(|id| Potion::from_ident(id.as_str_ident()))
+                .unwrap_or_default(),
+            _ => Potion::default(),
+        };
+
+        let custom_color = match nbt.get("CustomPotionColor") {
+            Some(Value::Int(rgb)) => Some(*rgb as u32),
+            _ => None,
+        };
+
+        let custom_effects = match nbt.get("CustomPotionEffects") {
+            Some(Value::List(List::Compound(effects))) => {
+                effects.iter().filter_map(effect_from_nbt).collect()
+            }
+            _ => vec![],
+        };
+
+        Self {
+            potion,
+            custom_color,
+            custom_effects,
+        }
+    }
+
+    /// Reads the contents of a potion or tipped arrow. Returns `None` if the
+    /// stack isn't one of those.
+    pub fn from_stack(stack: &ItemStack) -> Option<Self> {
+        if !is_potion_item(stack.item) {
+            return None;
+        }
+
+        Some(
+            stack
+                .nbt
+                .as_ref()
+                .map_or_else(Self::default, Self::from_nbt),
+        )
+    }
+
+    /// A stack of `count` items of `item` with these contents.
+    pub fn item(&self, item: ItemKind, count: i8) -> ItemStack {
+        ItemStack::new(item, count, Some(self.to_nbt()))
+    }
+
+    /// A drinkable potion with these contents.
+    pub fn potion(&self) -> ItemStack {
+        self.item(ItemKind::Potion, 1)
+    }
+
+    /// A splash potion with these contents.
+    pub fn splash_potion(&self) -> ItemStack {
+        self.item(ItemKind::SplashPotion, 1)
+    }
+
+    /// A lingering potion with these contents.
+    pub fn lingering_potion(&self) -> ItemStack {
+        self.item(ItemKind::LingeringPotion, 1)
+    }
+
+    /// A stack of `count` tipped arrows with these contents.
+    pub fn tipped_arrows(&self, count: i8) -> ItemStack {
+        self.item(ItemKind::TippedArrow, count)
+    }
+}
+
+fn effect_to_nbt(effect: &ActiveStatusEffect) -> Compound {
+    compound! {
+        "Id" => i32::from(effect.status_effect().to_raw()),
+        "Amplifier" => effect.amplifier() as i8,
+        "Duration" => effect.initial_duration().unwrap_or(-1),
+        "Ambient" => effect.ambient(),
+        "ShowParticles" => effect.show_particles(),
+        "ShowIcon" => effect.show_icon(),
+    }
+}
+
+fn effect_from_nbt(nbt: &Compound) -> Option<ActiveStatusEffect> {
+    let effect = match nbt.get("Id") {
+        Some(Value::Int(id)) => StatusEffect::from_raw(u16::try_from(*id).ok()?)?,
+        _ => return None,
+    };
+
+    let flag = |key: &str| match nbt.get(key) {
+        Some(Value::Byte(b)) => Some(*b != 0),
+        _ => None,
+    };
+
+    let amplifier = match nbt.get("Amplifier") {
+        Some(Value::Byte(amplifier)) => *amplifier as u8,
+        _ => 0,
+    };
+
+    let show_particles = flag("ShowParticles").unwrap_or(true);
+
+    let effect = ActiveStatusEffect::from_effect(effect)
+        .with_amplifier(amplifier)
+        .with_ambient(flag("Ambient").unwrap_or(false))
+        .with_show_particles(show_particles)
+        .with_show_icon(flag("ShowIcon").unwrap_or(show_particles));
+
+    Some(match nbt.get("Duration") {
+        Some(Value::Int(-1)) => effect.with_infinite(),
+        Some(Value::Int(duration)) => effect.with_duration(*duration),
+        _ => effect.with_duration(0),
+    })
+}
@@ -0,0 +1,133 @@
+//! Random block ticks.
+//!
+//! Like vanilla, a few random blocks in every chunk section are chosen every
+//! tick. The number of blocks is set by [`GameRules::random_tick_speed`].
+//! Blocks whose kind was added to [`RandomTicks`] send a [`RandomTickEvent`]
+//! when chosen, which is how crops grow and leaves decay in vanilla. Only
+//! chunks in view of a client or covered by a [ticket](crate::layer::ticket)
+//! are ticked, or the [`SimulatedChunks`] if the layer has a [simulation
+//! distance](crate::view_distance::SimulationDistance).
+//!
+//! To make a block tick randomly, add its kind to [`RandomTicks`] and read the
+//! events in a system ordered after [`RandomTickSet`].
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use rustc_hash::FxHashSet;
+use valence_protocol::block::BlockKind;
+use valence_protocol::{BlockPos, BlockState};
+
+use crate::game_rules::GameRules;
+use crate::layer::chunk::Chunk;
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+use crate::view_distance::SimulatedChunks;
+
+pub struct RandomTickPlugin;
+
+impl Plugin for RandomTickPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RandomTicks>()
+            .add_event::<RandomTickEvent>()
+            .configure_sets(PostUpdate, RandomTickSet.before(UpdateLayersPreClientSet))
+            .add_systems(PostUpdate, random_tick_blocks.in_set(RandomTickSet));
+    }
+}
+
+/// The system set random ticks are chosen and [`RandomTickEvent`]s are sent
+/// in.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RandomTickSet;
+
+/// The kinds of blocks that are ticked randomly. This is empty by default.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct RandomTicks {
+    kinds: FxHashSet<BlockKind>,
+}
+
+impl RandomTicks {
+    /// Makes blocks of `kind` tick randomly. Returns `false` if they already
+    /// did.
+    pub fn insert(&mut self, kind: BlockKind) -> bool {
+        self.kinds.insert(kind)
+    }
+
+    /// Stops blocks of `kind` from ticking randomly. Returns `false` if they
+    /// didn't.
+    pub fn remove(&mut self, kind: BlockKind) -> bool {
+        self.kinds.remove(&kind)
+    }
+
+    pub fn contains(&self, kind: BlockKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+/// Sent when a block of a kind in [`RandomTicks`] is ticked randomly.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RandomTickEvent {
+    /// The [`ChunkLayer`] the block is in.
+    pub layer: Entity,
+    pub position: BlockPos,
+    pub state: BlockState,
+}
+
+fn random_tick_blocks(
+    layers: Query<(
+        Entity,
+        &ChunkLayer,
+        Option<&GameRules>,
+        Option<&SimulatedChunks>,
+    )>,
+    ticks: Res<RandomTicks>,
+    mut events: EventWriter<RandomTickEvent>,
+) {
+    if ticks.kinds.is_empty() {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+
+    for (entity, layer, rules, simulated) in &layers {
+        let speed = rules.copied().unwrap_or_default().random_tick_speed;
+
+        if speed == 0 {
+            continue;
+        }
+
+        for (pos, chunk) in layer.chunks() {
+            let is_simulated = match simulated {
+                Some(simulated) => simulated.contains(pos),
+                None => chunk.viewer_count() > 0 || layer.has_ticket(pos),
+            };
+
+            if !is_simulated {
+                continue;
+            }
+
+            for section in 0..chunk.height() / 16 {
+                for _ in 0..speed {
+                    let x = rng.gen_range(0..16);
+                    let y = section * 16 + rng.gen_range(0..16);
+                    let z = rng.gen_range(0..16);
+
+                    let state = chunk.block_state(x, y, z);
+
+                    if !ticks.kinds.contains(&state.to_kind()) {
+                        continue;
+                    }
+
+                    events.send(RandomTickEvent {
+                        layer: entity,
+                        position: BlockPos::new(
+                            pos.x * 16 + x as i32,
+                            layer.min_y() + y as i32,
+                            pos.z * 16 + z as i32,
+                        ),
+                        state,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! Recording the packets sent in a region of a layer and playing them back.
+//!
+//! A [`ReplayRecorder`] works like a spectator which never moves: it records
+//! the packets a client would receive while viewing a [`ChunkView`] of a layer,
+//! one tick at a time. The first tick of a recording contains the chunks and
+//! entities already in view, so a [`Replay`] can be watched from the start
+//! without the original world. Replays are saved and loaded with
+//! [`Replay::write_to`] and [`Replay::read_from`].
+//!
+//! A [`ReplayPlayer`] sends a replay to all viewers of a [`ChunkLayer`] again,
+//! one recorded tick per tick. The layer is meant to be empty, since the
+//! chunks and entities of the replay are sent on top of whatever clients see
+//! already. Clients should be in the layer before playback starts and near
+//! the recorded region, or they miss the chunks sent in the first tick.
+//!
+//! Only what's sent through the layer is recorded. Packets written to clients
+//! directly, like the player list, aren't part of a replay, so players in a
+//! replay are only visible to clients which have them in their player list.
+//!
+//! [`ReplayPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+
+use std::io::{Read, Write};
+
+use anyhow::{bail, ensure};
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use byteorder::{BigEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
+use bytes::Bytes;
+use tracing::warn;
+use valence_entity::query::EntityInitQuery;
+use valence_entity::{OldPosition, Position};
+use valence_protocol::encode::{PacketEncoder, PacketWriter, WritePacket};
+use valence_protocol::packets::play::chunk_biome_data_s2c::ChunkBiome;
+use valence_protocol::packets::play::{ChunkBiomeDataS2c, EntitiesDestroyS2c, UnloadChunkS2c};
+use valence_protocol::{CompressionThreshold, VarInt, PROTOCOL_VERSION};
+use valence_server_common::Server;
+
+use crate::layer::{ChunkLayer, EntityLayer, UpdateLayersPostClientSet, UpdateLayersPreClientSet};
+use crate::ChunkView;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReplayEndEvent>().add_systems(
+            PostUpdate,
+            (
+                play_replays.before(UpdateLayersPreClientSet),
+                record_replays
+                    .after(UpdateLayersPreClientSet)
+                    .before(UpdateLayersPostClientSet),
+            ),
+        );
+    }
+}
+
+/// Identifies replay files, followed by the format version.
+const MAGIC: [u8; 8] = *b"VALENCE\0";
+const FORMAT_VERSION: u32 = 1;
+
+/// The packets sent in each tick of a recording. See the [module
+/// documentation](self).
+///
+/// Cloning a replay is cheap, since the packet data is reference counted.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Replay {
+    /// The compression threshold the packets are encoded with.
+    threshold: CompressionThreshold,
+    ticks: Vec<Bytes>,
+}
+
+impl Replay {
+    /// Returns the compression threshold the packets in this replay are
+    /// encoded with.
+    pub fn compression_threshold(&self) -> CompressionThreshold {
+        self.threshold
+    }
+
+    /// Returns the number of recorded ticks.
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /// Returns the encoded packets sent in the given tick, counted from the
+    /// start of the recording.
+    pub fn tick(&self, tick: usize) -> Option<&Bytes> {
+        self.ticks.get(tick)
+    }
+
+    /// Writes the replay in the replay file format.
+    pub fn write_to(&self, mut w: impl Write) -> std::io::Result<()> {
+        w.write_all(&MAGIC)?;
+        w.write_u32::<BigEndian>(FORMAT_VERSION)?;
+        w.write_i32::<BigEndian>(PROTOCOL_VERSION)?;
+        w.write_i32::<BigEndian>(self.threshold.0)?;
+        w.write_u32::<BigEndian>(self.ticks.len() as u32)?;
+
+        for tick in &self.ticks {
+            w.write_u32::<BigEndian>(tick.len() as u32)?;
+            w.write_all(tick)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a replay written by [`Self::write_to`]. Fails if the replay was
+    /// recorded with another protocol version.
+    pub fn read_from(mut r: impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        ensure!(magic == MAGIC, "not a replay file");
+
+        let version = r.read_u32::<BigEndian>()?;
+        ensure!(
+            version == FORMAT_VERSION,
+            "unsupported replay format version {version}"
+        );
+
+        let protocol_version = r.read_i32::<BigEndian>()?;
+        ensure!(
+            protocol_version == PROTOCOL_VERSION,
+            "replay was recorded with protocol version {protocol_version}, expected \
+             {PROTOCOL_VERSION}"
+        );
+
+        let threshold = CompressionThreshold(r.read_i32::<BigEndian>()?);
+        let tick_count = r.read_u32::<BigEndian>()?;
+
+        // Not preallocated, since the counts of a corrupt file can be anything.
+        let mut ticks = vec![];
+
+        for _ in 0..tick_count {
+            let len = r.read_u32::<BigEndian>()?;
+
+            let mut data = vec![];
+            r.by_ref().take(len.into()).read_to_end(&mut data)?;
+
+            if data.len() != len as usize {
+                bail!("replay file ends in the middle of a tick");
+            }
+
+            ticks.push(data.into());
+        }
+
+        Ok(Self { threshold, ticks })
+    }
+}
+
+/// A [`Component`] recording the packets sent in a region of a layer. See the
+/// [module documentation](self).
+///
+/// The recorder isn't a viewer of the layer, so it doesn't keep chunks from
+/// being unloaded.
+#[derive(Component, Debug)]
+pub struct ReplayRecorder {
+    /// The layer entity with the [`ChunkLayer`] and [`EntityLayer`] to record.
+    pub layer: Entity,
+    /// The recorded region.
+    pub view: ChunkView,
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(layer: Entity, view: ChunkView) -> Self {
+        Self {
+            layer,
+            view,
+            replay: Replay::default(),
+        }
+    }
+
+    /// Returns the ticks recorded so far.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the ticks recorded so far and starts a new recording, such as
+    /// for the next round of a minigame.
+    pub fn take_replay(&mut self) -> Replay {
+        std::mem::take(&mut self.replay)
+    }
+}
+
+/// A [`Component`] playing a [`Replay`] to the viewers of a [`ChunkLayer`].
+/// A [`ReplayEndEvent`] is sent and the component is removed after the last
+/// tick. See the [module documentation](self).
+#[derive(Component, Debug)]
+pub struct ReplayPlayer {
+    /// The layer entity with the [`ChunkLayer`] to send the replay to.
+    pub layer: Entity,
+    /// Whether playback is paused.
+    pub paused: bool,
+    replay: Replay,
+    tick: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(layer: Entity, replay: Replay) -> Self {
+        Self {
+            layer,
+            paused: false,
+            replay,
+            tick: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the number of ticks played so far.
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+}
+
+/// Sent when a [`ReplayPlayer`] reaches the end of its replay.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ReplayEndEvent {
+    /// The entity the [`ReplayPlayer`] was removed from.
+    pub player: Entity,
+}
+
+fn play_replays(
+    mut players: Query<(Entity, &mut ReplayPlayer)>,
+    mut layers: Query<&mut ChunkLayer>,
+    server: Res<Server>,
+    mut end_events: EventWriter<ReplayEndEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut player) in &mut players {
+        if player.paused {
+            continue;
+        }
+
+        let player = &mut *player;
+
+        if let Some(bytes) = player.replay.tick(player.tick) {
+            player.tick += 1;
+
+            if let Ok(mut layer) = layers.get_mut(player.layer) {
+                let threshold = server.compression_threshold();
+
+                if player.replay.threshold == threshold {
+                    layer.write_packet_bytes(bytes);
+                } else {
+                    // The replay was recorded with another compression threshold.
+                    let mut enc = PacketEncoder::new();
+                    enc.set_compression(threshold);
+
+                    match enc.append_bytes_with_threshold(bytes, player.replay.threshold) {
+                        Ok(()) => layer.write_packet_bytes(&enc.take()),
+                        Err(e) => warn!("failed to encode replay packets: {e:#}"),
+                    }
+                }
+            }
+        }
+
+        if player.tick >= player.replay.len() {
+            commands.entity(entity).remove::<ReplayPlayer>();
+            end_events.send(ReplayEndEvent { player: entity });
+        }
+    }
+}
+
+fn record_replays(
+    mut recorders: Query<&mut ReplayRecorder>,
+    chunk_layers: Query<&ChunkLayer>,
+    entity_layers: Query<&EntityLayer>,
+    entities: Query<(EntityInitQuery, &Position, &OldPosition)>,
+    server: Res<Server>,
+) {
+    for mut recorder in &mut recorders {
+        let recorder = &mut *recorder;
+        let chunk_layer = chunk_layers.get(recorder.layer).ok();
+        let entity_layer = entity_layers.get(recorder.layer).ok();
+
+        let mut buf = vec![];
+
+        if recorder.replay.is_empty() {
+            recorder.replay.threshold = server.compression_threshold();
+
+            write_snapshot(
+                PacketWriter::new(&mut buf, recorder.replay.threshold),
+                recorder.view,
+                chunk_layer,
+                entity_layer,
+                &entities,
+            );
+        } else {
+            let mut writer = PacketWriter::new(&mut buf, recorder.replay.threshold);
+
+            if let Some(layer) = chunk_layer {
+                write_chunk_messages(&mut writer, recorder.view, layer);
+            }
+
+            if let Some(layer) = entity_layer {
+                write_entity_messages(&mut writer, recorder.view, recorder.layer, layer, &entities);
+            }
+        }
+
+        recorder.replay.ticks.push(buf.into());
+    }
+}
+
+/// Writes the packets initializing the chunks and entities in `view`. Packets
+/// sent through the layer this tick are left out, since their effects are
+/// already part of the snapshot.
+fn write_snapshot(
+    mut writer: impl WritePacket,
+    view: ChunkView,
+    chunk_layer: Option<&ChunkLayer>,
+    entity_layer: Option<&EntityLayer>,
+    entities: &Query<(EntityInitQuery, &Position, &OldPosition)>,
+) {
+    if let Some(layer) = chunk_layer {
+        for pos in view.iter() {
+            if let Some(chunk) = layer.chunk(pos) {
+                chunk.write_init_packets(&mut writer, pos, layer.info(), None);
+            }
+        }
+    }
+
+    if let Some(layer) = entity_layer {
+        for pos in view.iter() {
+            for entity in layer.entities_at(pos) {
+                if let Ok((init, pos, _)) = entities.get(entity) {
+                    init.write_init_packets(pos.get(), &mut writer);
+                }
+            }
+        }
+    }
+}
+
+fn write_chunk_messages(mut writer: impl WritePacket, view: ChunkView, layer: &ChunkLayer) {
+    let messages = layer.messages();
+    let bytes = messages.bytes();
+
+    for (msg, range) in messages.iter_global() {
+        match msg {
+            crate::layer::chunk::GlobalMsg::Packet
+            | crate::layer::chunk::GlobalMsg::PacketExcept { .. } => {
+                writer.write_packet_bytes(&bytes[range]);
+            }
+        }
+    }
+
+    let mut chunk_biome_buf = vec![];
+
+    messages.query_local(view, |msg, range| match msg {
+        crate::layer::chunk::LocalMsg::ChangeBiome { pos } => {
+            chunk_biome_buf.push(ChunkBiome {
+                pos,
+                data: &bytes[range],
+            });
+        }
+        crate::layer::chunk::LocalMsg::ChangeChunkState { pos } => match &bytes[range] {
+            [ChunkLayer::LOAD, .., ChunkLayer::UNLOAD] => {}
+            [.., ChunkLayer::LOAD | ChunkLayer::OVERWRITE] => {
+                let chunk = layer.chunk(pos).expect("chunk must exist");
+                chunk.write_init_packets(&mut writer, pos, layer.info(), None);
+            }
+            [.., ChunkLayer::UNLOAD] => writer.write_packet(&UnloadChunkS2c { pos }),
+            _ => unreachable!("invalid message data while changing chunk state"),
+        },
+        // The recorder sees everything in its view, wherever it is.
+        _ => writer.write_packet_bytes(&bytes[range]),
+    });
+
+    if !chunk_biome_buf.is_empty() {
+        writer.write_packet(&ChunkBiomeDataS2c {
+            chunks: chunk_biome_buf.into(),
+        });
+    }
+}
+
+fn write_entity_messages(
+    mut writer: impl WritePacket,
+    view: ChunkView,
+    layer_id: Entity,
+    layer: &EntityLayer,
+    entities: &Query<(EntityInitQuery, &Position, &OldPosition)>,
+) {
+    let messages = layer.messages();
+    let bytes = messages.bytes();
+
+    for (msg, range) in messages.iter_global() {
+        match msg {
+            crate::layer::entity::GlobalMsg::Packet
+            | crate::layer::entity::GlobalMsg::PacketExcept { .. } => {
+                writer.write_packet_bytes(&bytes[range]);
+            }
+            crate::layer::entity::GlobalMsg::DespawnLayer => {}
+        }
+    }
+
+    let mut removed = vec![];
+
+    messages.query_local(view, |msg, range| match msg {
+        crate::layer::entity::LocalMsg::DespawnEntity { dest_layer, .. } => {
+            if dest_layer != layer_id {
+                let mut bytes = &bytes[range];
+
+                while let Ok(id) = bytes.read_i32::<NativeEndian>() {
+                    removed.push(VarInt(id));
+                }
+            }
+        }
+        crate::layer::entity::LocalMsg::DespawnEntityTransition { dest_pos, .. } => {
+            if !view.contains(dest_pos) {
+                let mut bytes = &bytes[range];
+
+                while let Ok(id) = bytes.read_i32::<NativeEndian>() {
+                    removed.push(VarInt(id));
+                }
+            }
+        }
+        crate::layer::entity::LocalMsg::SpawnEntity { src_layer, .. } => {
+            if src_layer != layer_id {
+                write_removed(&mut writer, &mut removed);
+                write_spawned(&mut writer, &bytes[range], entities);
+            }
+        }
+        crate::layer::entity::LocalMsg::SpawnEntityTransition { src_pos, .. } => {
+            if !view.contains(src_pos) {
+                write_removed(&mut writer, &mut removed);
+                write_spawned(&mut writer, &bytes[range], entities);
+            }
+        }
+        crate::layer::entity::LocalMsg::PacketAt { .. }
+        | crate::layer::entity::LocalMsg::PacketAtExcept { .. }
+        | crate::layer::entity::LocalMsg::RadiusAt { .. }
+        | crate::layer::entity::LocalMsg::RadiusAtExcept { .. } => {
+            writer.write_packet_bytes(&bytes[range]);
+        }
+    });
+
+    write_removed(&mut writer, &mut removed);
+}
+
+fn write_removed(mut writer: impl WritePacket, removed: &mut Vec<VarInt>) {
+    if !removed.is_empty() {
+        writer.write_packet(&EntitiesDestroyS2c {
+            entity_ids: removed.as_slice().into(),
+        });
+
+        removed.clear();
+    }
+}
+
+fn write_spawned(
+    mut writer: impl WritePacket,
+    mut bytes: &[u8],
+    entities: &Query<(EntityInitQuery, &Position, &OldPosition)>,
+) {
+    while let Ok(bits) = bytes.read_u64::<NativeEndian>() {
+        if let Ok((init, _, old_pos)) = entities.get(Entity::from_bits(bits)) {
+            // Spawned at the old position, like for clients, since a relative
+            // movement packet may follow.
+            init.write_init_packets(old_pos.get(), &mut writer);
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! Sleeping in beds.
+//!
+//! Using a bed sets the client's [`RespawnPosition`] and, if it is night, puts
+//! the client to sleep. Once enough players in a layer have been asleep for
+//! [`Sleeping::SKIP_NIGHT_TICKS`], the layer's [`WorldTime`] is advanced to the
+//! next morning and everyone wakes up. By default every player needs to be
+//! asleep, which can be changed with
+//! [`GameRules::players_sleeping_percentage`].
+//!
+//! Beds don't work in dimensions where [`DimensionType::bed_works`] is `false`.
+//! Vanilla makes the bed explode in this case, which is left up to users by
+//! reading [`SleepFailedEvent`]s.
+//!
+//! [`SleepPlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+//!
+//! [`DimensionType::bed_works`]: valence_registry::dimension_type::DimensionType::bed_works
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryData;
+use rustc_hash::FxHashMap;
+use valence_entity::living::SleepingPosition;
+use valence_entity::{
+    entity, EntityAnimation, EntityAnimations, Look, Pose, Position, UpdateTrackedDataSet,
+};
+use valence_math::DVec3;
+use valence_protocol::block::{PropName, PropValue};
+use valence_protocol::packets::play::EntityAnimationS2c;
+use valence_protocol::text::Text;
+use valence_protocol::{BlockKind, BlockPos, BlockState, GameMode, Hand, VarInt, WritePacket};
+use valence_registry::DimensionTypeRegistry;
+
+use crate::client::{Client, VisibleChunkLayer};
+use crate::client_command::LeaveBedEvent;
+use crate::death::Dead;
+use crate::event_loop::EventLoopUpdate;
+use crate::game_rules::GameRules;
+use crate::interact_block::InteractBlockEvent;
+use crate::layer::ChunkLayer;
+use crate::message::SendMessage;
+use crate::spawn::RespawnPosition;
+use crate::world_time::WorldTime;
+
+pub struct SleepPlugin;
+
+impl Plugin for SleepPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartSleepingEvent>()
+            .add_event::<StopSleepingEvent>()
+            .add_event::<SleepFailedEvent>()
+            .add_event::<SetRespawnPositionEvent>()
+            .add_event::<SkipNightEvent>()
+            .add_systems(EventLoopUpdate, (handle_bed_interactions, handle_leave_bed))
+            .add_systems(PostUpdate, tick_sleeping.before(UpdateTrackedDataSet));
+    }
+}
+
+/// Present on clients that are sleeping in a bed.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Sleeping {
+    /// The position of the head part of the bed.
+    pub bed: BlockPos,
+    /// The number of ticks the client has been asleep for.
+    pub ticks: u32,
+}
+
+impl Sleeping {
+    /// The number of ticks everyone needs to be asleep for before the night is
+    /// skipped.
+    pub const SKIP_NIGHT_TICKS: u32 = 100;
+}
+
+/// Sent when a client gets into a bed.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StartSleepingEvent {
+    pub client: Entity,
+    /// The position of the head part of the bed.
+    pub bed: BlockPos,
+}
+
+/// Sent when a client gets out of a bed, either by leaving it or by being woken
+/// up.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StopSleepingEvent {
+    pub client: Entity,
+    /// The position of the head part of the bed.
+    pub bed: BlockPos,
+}
+
+/// Sent when a client uses a bed but can't sleep in it.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SleepFailedEvent {
+    pub client: Entity,
+    /// The position of the head part of the bed.
+    pub bed: BlockPos,
+    pub reason: SleepFailure,
+}
+
+/// The reason a client could not sleep. See [`SleepFailedEvent`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SleepFailure {
+    /// Beds don't work in the dimension of the layer.
+    NotPossibleHere,
+    /// It is not night yet.
+    NotPossibleNow,
+    /// Someone else is already sleeping in the bed.
+    Occupied,
+    /// The client is too far away from the bed.
+    TooFarAway,
+}
+
+/// Sent when a client's [`RespawnPosition`] is changed by using a bed or a
+/// respawn anchor.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SetRespawnPositionEvent {
+    pub client: Entity,
+    /// The position of the bed or respawn anchor.
+    pub position: BlockPos,
+}
+
+/// Sent when the night is skipped in a layer because everyone was asleep.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SkipNightEvent {
+    /// The chunk layer whose [`WorldTime`] was advanced.
+    pub layer: Entity,
+}
+
+/// Returns whether `kind` is one of the bed blocks.
+pub const fn is_bed(kind: BlockKind) -> bool {
+    matches!(
+        kind,
+        BlockKind::WhiteBed
+            | BlockKind::OrangeBed
+            | BlockKind::MagentaBed
+            | BlockKind::LightBlueBed
+            | BlockKind::YellowBed
+            | BlockKind::LimeBed
+            | BlockKind::PinkBed
+            | BlockKind::GrayBed
+            | BlockKind::LightGrayBed
+            | BlockKind::CyanBed
+            | BlockKind::PurpleBed
+            | BlockKind::BlueBed
+            | BlockKind::BrownBed
+            | BlockKind::GreenBed
+            | BlockKind::RedBed
+            | BlockKind::BlackBed
+    )
+}
+
+/// Returns the positions of the head and foot parts of the bed at `pos`.
+fn bed_parts(state: BlockState, pos: BlockPos) -> Option<(BlockPos, BlockPos)> {
+    if !is_bed(state.to_kind()) {
+        return None;
+    }
+
+    // Beds face from the foot towards the head.
+    let (x, z) = match state.get(PropName::Facing)? {
+        PropValue::North => (0, -1),
+        PropValue::South => (0, 1),
+        PropValue::West => (-1, 0),
+        PropValue::East => (1, 0),
+        _ => return None,
+    };
+
+    match state.get(PropName::Part)? {
+        PropValue::Head => Some((pos, pos.offset(-x, 0, -z))),
+        PropValue::Foot => Some((pos.offset(x, 0, z), pos)),
+        _ => None,
+    }
+}
+
+/// Sets the `occupied` property of both parts of the bed with its head at
+/// `head`.
+fn set_bed_occupied(layer: &mut ChunkLayer, head: BlockPos, occupied: bool) {
+    let Some(state) = layer.block(head).map(|block| block.state) else {
+        return;
+    };
+
+    let Some((head, foot)) = bed_parts(state, head) else {
+        return;
+    };
+
+    for pos in [head, foot] {
+        if let Some(state) = layer.block(pos).map(|block| block.state) {
+            if is_bed(state.to_kind()) {
+                layer.set_block(
+                    pos,
+                    state.set(PropName::Occupied, PropValue::from_bool(occupied)),
+                );
+            }
+        }
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct SleeperQuery {
+    entity: Entity,
+    client: &'static mut Client,
+    pose: &'static mut entity::Pose,
+    sleeping_pos: &'static mut SleepingPosition,
+    animations: &'static mut EntityAnimations,
+    layer: &'static VisibleChunkLayer,
+}
+
+impl SleeperQueryItem<'_> {
+    fn wake_up(&mut self, bed: BlockPos, layer: Option<&mut ChunkLayer>) {
+        self.pose.0 = Pose::Standing;
+        self.sleeping_pos.0 = None;
+
+        // Other players see the client get out of bed through the animation, but the
+        // client itself needs to be told separately.
+        self.animations.trigger(EntityAnimation::WakeUp);
+        self.client.write_packet(&EntityAnimationS2c {
+            entity_id: VarInt(0),
+            animation: EntityAnimation::WakeUp as u8,
+        });
+
+        if let Some(layer) = layer {
+            set_bed_occupied(layer, bed, false);
+        }
+    }
+}
+
+fn send_sleep_failure(
+    client: &mut Client,
+    events: &mut EventWriter<SleepFailedEvent>,
+    event: SleepFailedEvent,
+) {
+    let key = match event.reason {
+        // Vanilla blows up the bed instead of showing a message.
+        SleepFailure::NotPossibleHere => None,
+        SleepFailure::NotPossibleNow => Some("block.minecraft.bed.no_sleep"),
+        SleepFailure::Occupied => Some("block.minecraft.bed.occupied"),
+        SleepFailure::TooFarAway => Some("block.minecraft.bed.too_far_away"),
+    };
+
+    if let Some(key) = key {
+        client.send_action_bar_message(Text::translate(key, []));
+    }
+
+    events.send(event);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_bed_interactions(
+    mut events: EventReader<InteractBlockEvent>,
+    mut clients: Query<
+        (
+            SleeperQuery,
+            &Position,
+            &Look,
+            &GameMode,
+            &mut RespawnPosition,
+        ),
+        (Without<Sleeping>, Without<Dead>),
+    >,
+    mut layers: Query<(&mut ChunkLayer, Option<&WorldTime>)>,
+    dimensions: Res<DimensionTypeRegistry>,
+    mut start_events: EventWriter<StartSleepingEvent>,
+    mut failed_events: EventWriter<SleepFailedEvent>,
+    mut respawn_events: EventWriter<SetRespawnPositionEvent>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        // Clients send an interaction for each hand.
+        if event.hand != Hand::Main {
+            continue;
+        }
+
+        let Ok((mut sleeper, pos, look, game_mode, mut respawn_pos)) =
+            clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        // Sneaking clients place blocks against beds instead of using them.
+        if *game_mode == GameMode::Spectator || sleeper.pose.0 == Pose::Sneaking {
+            continue;
+        }
+
+        let Ok((mut layer, time)) = layers.get_mut(sleeper.layer.0) else {
+            continue;
+        };
+
+        let Some(state) = layer.block(event.position).map(|block| block.state) else {
+            continue;
+        };
+
+        let Some((head, _)) = bed_parts(state, event.position) else {
+            continue;
+        };
+
+        let failure = |reason| SleepFailedEvent {
+            client: event.client,
+            bed: head,
+            reason,
+        };
+
+        if dimensions
+            .get(layer.dimension_type_name())
+            .is_some_and(|dim| !dim.bed_works)
+        {
+            send_sleep_failure(
+                &mut sleeper.client,
+                &mut failed_events,
+                failure(SleepFailure::NotPossibleHere),
+            );
+            continue;
+        }
+
+        let diff = DVec3::new(
+            f64::from(head.x) + 0.5,
+            f64::from(head.y),
+            f64::from(head.z) + 0.5,
+        ) - pos.0;
+
+        if diff.x.hypot(diff.z) > 3.0 || diff.y.abs() > 2.0 {
+            send_sleep_failure(
+                &mut sleeper.client,
+                &mut failed_events,
+                failure(SleepFailure::TooFarAway),
+            );
+            continue;
+        }
+
+        // Like vanilla, the respawn position is set even if the client can't sleep
+        // right now.
+        if respawn_pos.pos != head {
+            *respawn_pos = RespawnPosition {
+                pos: head,
+                yaw: look.yaw,
+            };
+
+            sleeper
+                .client
+                .send_action_bar_message(Text::translate("block.minecraft.set_spawn", []));
+
+            respawn_events.send(SetRespawnPositionEvent {
+                client: event.client,
+                position: head,
+            });
+        }
+
+        // Layers without a world time leave the time up to clients, so sleeping is
+        // always allowed.
+        if time.is_some_and(|time| !time.is_night()) {
+            send_sleep_failure(
+                &mut sleeper.client,
+                &mut failed_events,
+                failure(SleepFailure::NotPossibleNow),
+            );
+            continue;
+        }
+
+        let occupied = layer
+            .block(head)
+            .and_then(|block| block.state.get(PropName::Occupied))
+            .and_then(PropValue::to_bool)
+            .unwrap_or(false);
+
+        if occupied {
+            send_sleep_failure(
+                &mut sleeper.client,
+                &mut failed_events,
+                failure(SleepFailure::Occupied),
+            );
+            continue;
+        }
+
+        set_bed_occupied(&mut layer, head, true);
+
+        sleeper.pose.0 = Pose::Sleeping;
+        sleeper.sleeping_pos.0 = Some(head);
+
+        commands.entity(event.client).insert(Sleeping {
+            bed: head,
+            ticks: 0,
+        });
+
+        start_events.send(StartSleepingEvent {
+            client: event.client,
+            bed: head,
+        });
+    }
+}
+
+fn handle_leave_bed(
+    mut events: EventReader<LeaveBedEvent>,
+    mut clients: Query<(SleeperQuery, &Sleeping)>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut stop_events: EventWriter<StopSleepingEvent>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        let Ok((mut sleeper, sleeping)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let layer = layers.get_mut(sleeper.layer.0).ok();
+        sleeper.wake_up(sleeping.bed, layer.map(Mut::into_inner));
+
+        commands.entity(event.client).remove::<Sleeping>();
+
+        stop_events.send(StopSleepingEvent {
+            client: event.client,
+            bed: sleeping.bed,
+        });
+    }
+}
+
+fn tick_sleeping(
+    mut clients: Query<(SleeperQuery, Option<&mut Sleeping>, &GameMode, Has<Dead>)>,
+    mut layers: Query<(&mut ChunkLayer, Option<&mut WorldTime>, Option<&GameRules>)>,
+    mut stop_events: EventWriter<StopSleepingEvent>,
+    mut skip_events: EventWriter<SkipNightEvent>,
+    mut commands: Commands,
+) {
+    // The number of players in each layer that have been asleep for long enough,
+    // and the number of players that could be.
+    let mut layers_asleep = FxHashMap::<Entity, (u32, u32)>::default();
+
+    for (mut sleeper, sleeping, game_mode, dead) in &mut clients {
+        let Some(mut sleeping) = sleeping else {
+            // Spectators don't need to sleep.
+            if *game_mode != GameMode::Spectator {
+                layers_asleep.entry(sleeper.layer.0).or_default().1 += 1;
+            }
+
+            continue;
+        };
+
+        sleeping.ticks += 1;
+
+        let bed_exists = layers
+            .get(sleeper.layer.0)
+            .ok()
+            .and_then(|(layer, _, _)| layer.block(sleeping.bed))
+            .is_some_and(|block| is_bed(block.state.to_kind()));
+
+        let asleep = if dead || !bed_exists {
+            let layer = layers.get_mut(sleeper.layer.0).ok();
+            sleeper.wake_up(sleeping.bed, layer.map(|(layer, _, _)| layer.into_inner()));
+
+            commands.entity(sleeper.entity).remove::<Sleeping>();
+
+            stop_events.send(StopSleepingEvent {
+                client: sleeper.entity,
+                bed: sleeping.bed,
+            });
+
+            false
+        } else {
+            sleeping.ticks >= Sleeping::SKIP_NIGHT_TICKS
+        };
+
+        let (sleepers, players) = layers_asleep.entry(sleeper.layer.0).or_default();
+        *sleepers += u32::from(asleep);
+        *players += 1;
+    }
+
+    for (layer_entity, (sleepers, players)) in layers_asleep {
+        let Ok((_, Some(mut time), rules)) = layers.get_mut(layer_entity) else {
+            continue;
+        };
+
+        if sleepers < rules.copied().unwrap_or_default().sleepers_needed(players) {
+            continue;
+        }
+
+        time.skip_to_morning();
+
+        skip_events.send(SkipNightEvent {
+            layer: layer_entity,
+        });
+
+        for (mut sleeper, sleeping, _, _) in &mut clients {
+            let Some(sleeping) = sleeping else {
+                continue;
+            };
+
+            if sleeper.layer.0 != layer_entity {
+                continue;
+            }
+
+            let layer = layers.get_mut(layer_entity).ok();
+            sleeper.wake_up(sleeping.bed, layer.map(|(layer, _, _)| layer.into_inner()));
+
+            commands.entity(sleeper.entity).remove::<Sleeping>();
+
+            stop_events.send(StopSleepingEvent {
+                client: sleeper.entity,
+                bed: sleeping.bed,
+            });
+        }
+    }
+}
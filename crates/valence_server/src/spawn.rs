@@ -6,14 +6,20 @@ use std::collections::BTreeSet;
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::QueryData;
 use derive_more::{Deref, DerefMut};
-use valence_entity::EntityLayerId;
-use valence_protocol::packets::play::{GameJoinS2c, PlayerRespawnS2c, PlayerSpawnPositionS2c};
+use valence_entity::{EntityLayerId, Look, Position};
+use valence_protocol::packets::play::game_state_change_s2c::GameEventKind;
+use valence_protocol::packets::play::{
+    EntityStatusS2c, GameJoinS2c, GameStateChangeS2c, PlayerRespawnS2c, PlayerSpawnPositionS2c,
+};
 use valence_protocol::{BlockPos, GameMode, GlobalPos, Ident, VarInt, WritePacket};
 use valence_registry::tags::TagsRegistry;
 use valence_registry::{BiomeRegistry, RegistryCodec};
+use valence_server_common::Despawned;
 
 use crate::client::{Client, ViewDistance, VisibleChunkLayer};
+use crate::client_settings::ClientSettings;
 use crate::layer::ChunkLayer;
+use crate::view_distance::SimulationDistance;
 
 // Components for the join game and respawn packet.
 
@@ -65,6 +71,46 @@ pub struct RespawnPosition {
     pub yaw: f32,
 }
 
+/// A [`Resource`] controlling what happens when clients join.
+///
+/// By default, all chunks in the [`ViewDistance`] of a client are sent on the
+/// tick it joins, which can take the client a while to process with large view
+/// distances. Setting an [`initial_chunk_radius`](Self::initial_chunk_radius)
+/// sends only the chunks close to the client before it's spawned, and then
+/// loads the rest of its view a few rings at a time.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct JoinConfiguration {
+    /// The radius of the chunks sent to a client before it's spawned in, in
+    /// chunks. If `None`, the full view distance is sent.
+    pub initial_chunk_radius: Option<u8>,
+    /// The number of rings of chunks added to the view of a client every tick
+    /// until it reaches its [`ViewDistance`], when an
+    /// [`initial_chunk_radius`](Self::initial_chunk_radius) is set.
+    pub chunk_radius_step: u8,
+    /// Whether joining clients are moved to their [`RespawnPosition`], like
+    /// vanilla players joining a world for the first time. Otherwise, clients
+    /// join at their [`Position`].
+    pub spawn_at_respawn_position: bool,
+}
+
+impl Default for JoinConfiguration {
+    fn default() -> Self {
+        Self {
+            initial_chunk_radius: None,
+            chunk_radius_step: 1,
+            spawn_at_respawn_position: false,
+        }
+    }
+}
+
+/// The view distance of a client still loading in after joining. Removed once
+/// its view has grown to the full [`ViewDistance`].
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct JoinChunkRadius {
+    current: u8,
+    target: u8,
+}
+
 /// A convenient [`QueryData`] for obtaining client spawn components. Also see
 /// [`ClientSpawnQueryReadOnly`].
 #[derive(QueryData)]
@@ -86,11 +132,15 @@ pub struct ClientSpawnQuery {
 pub(super) fn initial_join(
     codec: Res<RegistryCodec>,
     tags: Res<TagsRegistry>,
-    mut clients: Query<(&mut Client, &VisibleChunkLayer, ClientSpawnQueryReadOnly), Added<Client>>,
-    chunk_layers: Query<&ChunkLayer>,
+    // Clients disconnected on the tick they were spawned never join.
+    mut clients: Query<
+        (&mut Client, &VisibleChunkLayer, ClientSpawnQueryReadOnly),
+        (Added<Client>, Without<Despawned>),
+    >,
+    chunk_layers: Query<(&ChunkLayer, Option<&SimulationDistance>)>,
 ) {
     for (mut client, visible_chunk_layer, spawn) in &mut clients {
-        let Ok(chunk_layer) = chunk_layers.get(visible_chunk_layer.0) else {
+        let Ok((chunk_layer, sim_dist)) = chunk_layers.get(visible_chunk_layer.0) else {
             continue;
         };
 
@@ -121,7 +171,9 @@ pub(super) fn initial_join(
             hashed_seed: spawn.hashed_seed.0 as i64,
             max_players: VarInt(0), // Ignored by clients.
             view_distance: VarInt(i32::from(spawn.view_distance.get())),
-            simulation_distance: VarInt(16), // TODO.
+            simulation_distance: VarInt(i32::from(
+                sim_dist.map_or(spawn.view_distance.get(), |dist| dist.0),
+            )),
             reduced_debug_info: spawn.reduced_debug_info.0,
             enable_respawn_screen: spawn.has_respawn_screen.0,
             is_debug: spawn.is_debug.0,
@@ -191,6 +243,80 @@ pub(super) fn respawn(
     }
 }
 
+/// Moves joining clients to their [`RespawnPosition`] if the
+/// [`JoinConfiguration`] asks for it.
+pub(super) fn spawn_at_respawn_position(
+    mut clients: Query<(&mut Position, &mut Look, &RespawnPosition), Added<Client>>,
+    config: Res<JoinConfiguration>,
+) {
+    if !config.spawn_at_respawn_position {
+        return;
+    }
+
+    for (mut pos, mut look, respawn_pos) in &mut clients {
+        let block = respawn_pos.pos;
+
+        pos.set([
+            f64::from(block.x) + 0.5,
+            f64::from(block.y),
+            f64::from(block.z) + 0.5,
+        ]);
+        look.yaw = respawn_pos.yaw;
+    }
+}
+
+/// Limits the [`ViewDistance`] of joining clients to the initial chunk radius
+/// of the [`JoinConfiguration`] and grows it afterwards. Changes made to the
+/// view distance in the meantime, such as from the client's settings, become
+/// the new target instead of being applied at once.
+pub(super) fn update_join_chunk_radius(
+    mut clients: Query<(
+        Entity,
+        Ref<Client>,
+        &mut ViewDistance,
+        &ClientSettings,
+        Option<&mut JoinChunkRadius>,
+    )>,
+    config: Res<JoinConfiguration>,
+    mut commands: Commands,
+) {
+    for (entity, client, mut view_dist, settings, radius) in &mut clients {
+        if client.is_added() {
+            if let Some(initial) = config.initial_chunk_radius {
+                let target = view_dist.get();
+                view_dist.set_if_neq(ViewDistance::new(initial.min(target)));
+
+                commands.entity(entity).insert(JoinChunkRadius {
+                    current: view_dist.get(),
+                    target,
+                });
+            }
+
+            continue;
+        }
+
+        let Some(mut radius) = radius else {
+            continue;
+        };
+
+        if view_dist.get() != radius.current {
+            radius.target = view_dist.get();
+        }
+
+        radius.current = radius
+            .current
+            .saturating_add(config.chunk_radius_step)
+            .min(radius.target);
+
+        view_dist.set_if_neq(ViewDistance::new(radius.current));
+
+        // The view distance the client asked for may not have arrived yet.
+        if radius.current == radius.target && settings.view_distance != 0 {
+            commands.entity(entity).remove::<JoinChunkRadius>();
+        }
+    }
+}
+
 /// Sets the client's respawn and compass position.
 ///
 /// This also closes the "downloading terrain" screen when first joining, so
@@ -205,3 +331,38 @@ pub(super) fn update_respawn_position(
         });
     }
 }
+
+/// Sends changes to [`ReducedDebugInfo`] made after the client joined.
+pub(super) fn update_reduced_debug_info(
+    mut clients: Query<(&mut Client, &ReducedDebugInfo), Changed<ReducedDebugInfo>>,
+) {
+    for (mut client, reduced_debug_info) in &mut clients {
+        if client.is_added() {
+            // Sent in the game join packet.
+            continue;
+        }
+
+        client.write_packet(&EntityStatusS2c {
+            entity_id: 0,
+            entity_status: if reduced_debug_info.0 { 22 } else { 23 },
+        });
+    }
+}
+
+/// Sends changes to [`HasRespawnScreen`] made after the client joined.
+pub(super) fn update_respawn_screen(
+    mut clients: Query<(&mut Client, &HasRespawnScreen), Changed<HasRespawnScreen>>,
+) {
+    for (mut client, has_respawn_screen) in &mut clients {
+        if client.is_added() {
+            // Sent in the game join packet.
+            continue;
+        }
+
+        client.write_packet(&GameStateChangeS2c {
+            kind: GameEventKind::EnableRespawnScreen,
+            // 0 shows the respawn screen and 1 respawns immediately.
+            value: if has_respawn_screen.0 { 0.0 } else { 1.0 },
+        });
+    }
+}
@@ -0,0 +1,431 @@
+//! Monster spawners.
+//!
+//! The configuration of a spawner is stored in its block entity like in
+//! vanilla, and can be built with [`SpawnerData`]. Its `SpawnData` tag tells
+//! clients which entity to show spinning inside the spawner cage, so placing
+//! a spawner with [`ChunkLayer::set_block`] is enough to display it.
+//!
+//! [`SpawnerPlugin`] simulates the spawners in a layer's [`Spawners`]. While a
+//! player is within [`SpawnerData::required_player_range`], the delay counts
+//! down, and once it runs out the spawner tries to spawn
+//! [`SpawnerData::spawn_count`] entities at random positions around it.
+//! Nothing is spawned if there are already
+//! [`SpawnerData::max_nearby_entities`] entities of the same kind nearby or
+//! the chosen position is blocked. Entities are spawned with the function
+//! registered for their kind in [`SpawnerEntities`], and a
+//! [`SpawnerSpawnEvent`] is sent for every spawned entity so that it can be
+//! customized further. The plugin is not part of `DefaultPlugins` and has to
+//! be added separately.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use valence_entity::{EntityKind, EntityLayerId, InitEntitiesSet, Position};
+use valence_math::DVec3;
+use valence_nbt::{compound, Compound, Value};
+use valence_protocol::block::BlockKind;
+use valence_protocol::packets::play::BlockEventS2c;
+use valence_protocol::{BlockPos, BlockState, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::Client;
+use crate::game_mode::NotSpectator;
+use crate::layer::chunk::{Block, IntoBlock};
+use crate::layer::{ChunkLayer, UpdateLayersPreClientSet};
+use crate::Layer;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnerEntities>()
+            .add_event::<SpawnerSpawnEvent>()
+            .configure_sets(
+                PostUpdate,
+                SpawnerSet
+                    .before(InitEntitiesSet)
+                    .before(UpdateLayersPreClientSet),
+            )
+            .add_systems(
+                PostUpdate,
+                (init_spawners, tick_spawners.in_set(SpawnerSet)),
+            );
+    }
+}
+
+/// The system set spawners are ticked and [`SpawnerSpawnEvent`]s are sent in.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SpawnerSet;
+
+/// The block action which makes clients reset the spin of a spawner.
+const RESET_DELAY_BLOCK_ACTION: u8 = 1;
+
+/// The contents of a spawner block entity.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SpawnerData {
+    /// The kind of entity that is spawned and shown inside the spawner.
+    pub entity: EntityKind,
+    /// The number of ticks until the first spawn.
+    pub delay: i16,
+    /// The minimum number of ticks between spawns.
+    pub min_spawn_delay: i16,
+    /// The maximum number of ticks between spawns.
+    pub max_spawn_delay: i16,
+    /// The number of entities the spawner tries to spawn at once.
+    pub spawn_count: i16,
+    /// The spawner stops spawning while this many entities of the same kind
+    /// are within [`spawn_range`](Self::spawn_range).
+    pub max_nearby_entities: i16,
+    /// How close a player needs to be for the spawner to be active, in blocks.
+    pub required_player_range: i16,
+    /// How far away from the spawner entities are spawned, in blocks.
+    pub spawn_range: i16,
+}
+
+impl SpawnerData {
+    /// Creates a spawner for `entity` with vanilla's defaults.
+    pub const fn new(entity: EntityKind) -> Self {
+        Self {
+            entity,
+            delay: 20,
+            min_spawn_delay: 200,
+            max_spawn_delay: 800,
+            spawn_count: 4,
+            max_nearby_entities: 6,
+            required_player_range: 16,
+            spawn_range: 4,
+        }
+    }
+
+    /// Reads spawner data from a spawner's block entity. Missing values are
+    /// set to their defaults. Returns `None` if the entity kind is missing or
+    /// unknown.
+    pub fn from_nbt(nbt: &Compound) -> Option<Self> {
+        let Some(Value::Compound(spawn_data)) = nbt.get("SpawnData") else {
+            return None;
+        };
+
+        let Some(Value::Compound(entity)) = spawn_data.get("entity") else {
+            return None;
+        };
+
+        let Some(Value::String(id)) = entity.get("id") else {
+            return None;
+        };
+
+        let entity = EntityKind::from_str(id.strip_prefix("minecraft:").unwrap_or(id))?;
+
+        let defaults = Self::new(entity);
+        let short = |key: &str, default: i16| match nbt.get(key) {
+            Some(Value::Short(value)) => *value,
+            _ => default,
+        };
+
+        Some(Self {
+            entity,
+            delay: short("Delay", defaults.delay),
+            min_spawn_delay: short("MinSpawnDelay", defaults.min_spawn_delay),
+            max_spawn_delay: short("MaxSpawnDelay", defaults.max_spawn_delay),
+            spawn_count: short("SpawnCount", defaults.spawn_count),
+            max_nearby_entities: short("MaxNearbyEntities", defaults.max_nearby_entities),
+            required_player_range: short("RequiredPlayerRange", defaults.required_player_range),
+            spawn_range: short("SpawnRange", defaults.spawn_range),
+        })
+    }
+
+    /// Writes the spawner data to a compound in the format of a spawner's
+    /// block entity.
+    pub fn to_nbt(&self) -> Compound {
+        let mut nbt = compound! {
+            "Delay" => self.delay,
+            "MinSpawnDelay" => self.min_spawn_delay,
+            "MaxSpawnDelay" => self.max_spawn_delay,
+            "SpawnCount" => self.spawn_count,
+            "MaxNearbyEntities" => self.max_nearby_entities,
+            "RequiredPlayerRange" => self.required_player_range,
+            "SpawnRange" => self.spawn_range,
+        };
+
+        if let Some(name) = self.entity.to_str() {
+            nbt.insert(
+                "SpawnData",
+                compound! {
+                    "entity" => compound! {
+                        "id" => format!("minecraft:{name}"),
+                    },
+                },
+            );
+        }
+
+        nbt
+    }
+
+    fn next_delay<R: Rng>(&self, rng: &mut R) -> i16 {
+        if self.max_spawn_delay <= self.min_spawn_delay {
+            self.min_spawn_delay
+        } else {
+            rng.gen_range(self.min_spawn_delay..self.max_spawn_delay)
+        }
+    }
+}
+
+/// A spawner block with this data.
+impl IntoBlock for SpawnerData {
+    fn into_block(self) -> Block {
+        Block::new(BlockState::SPAWNER, Some(self.to_nbt()))
+    }
+}
+
+/// The spawner blocks of a [`ChunkLayer`] that are ticked by
+/// [`SpawnerPlugin`]. This is inserted on every chunk layer automatically.
+#[derive(Component, Default, Debug)]
+pub struct Spawners {
+    /// Spawners to place on the next tick.
+    placed: Vec<(BlockPos, SpawnerData)>,
+    /// The ticks until each spawner spawns again, or `None` if the delay hasn't
+    /// been read from the block entity yet.
+    delays: FxHashMap<BlockPos, Option<i16>>,
+}
+
+impl Spawners {
+    /// Places a spawner at `pos` on the next tick and starts ticking it.
+    pub fn place(&mut self, pos: BlockPos, data: SpawnerData) {
+        self.placed.push((pos, data));
+    }
+
+    /// Starts ticking an existing spawner block, such as one placed with
+    /// [`ChunkLayer::set_block`] or loaded from disk. Spawners are no longer
+    /// ticked once they are removed or their chunk is unloaded.
+    pub fn track(&mut self, pos: BlockPos) {
+        self.delays.entry(pos).or_insert(None);
+    }
+
+    /// Stops ticking the spawner at `pos`. The block itself is left alone.
+    pub fn untrack(&mut self, pos: BlockPos) -> bool {
+        self.delays.remove(&pos).is_some()
+    }
+
+    /// Returns whether the spawner at `pos` is being ticked.
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        self.delays.contains_key(&pos)
+    }
+
+    /// Returns the positions of all spawners being ticked.
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.delays.keys().copied()
+    }
+}
+
+/// Spawns an entity of a spawner's kind in the layer entity at the position,
+/// and returns the new entity.
+pub type SpawnFn = fn(&mut Commands, Entity, DVec3) -> Entity;
+
+/// The functions spawners use to spawn each kind of entity. Spawners for
+/// kinds without a function still count down, but never spawn anything. This
+/// is empty by default.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use valence_entity::pig::PigEntityBundle;
+/// # use valence_entity::{EntityKind, EntityLayerId, Position};
+/// # use valence_server::spawner::SpawnerEntities;
+/// fn register_pigs(mut entities: ResMut<SpawnerEntities>) {
+///     entities.insert(EntityKind::PIG, |commands, layer, position| {
+///         commands
+///             .spawn(PigEntityBundle {
+///                 layer: EntityLayerId(layer),
+///                 position: Position(position),
+///                 ..Default::default()
+///             })
+///             .id()
+///     });
+/// }
+/// ```
+#[derive(Resource, Clone, Default, Debug)]
+pub struct SpawnerEntities {
+    spawn_fns: FxHashMap<EntityKind, SpawnFn>,
+}
+
+impl SpawnerEntities {
+    /// Sets the function used to spawn entities of `kind`, returning the
+    /// previous one.
+    pub fn insert(&mut self, kind: EntityKind, spawn: SpawnFn) -> Option<SpawnFn> {
+        self.spawn_fns.insert(kind, spawn)
+    }
+
+    pub fn remove(&mut self, kind: EntityKind) -> Option<SpawnFn> {
+        self.spawn_fns.remove(&kind)
+    }
+
+    pub fn get(&self, kind: EntityKind) -> Option<SpawnFn> {
+        self.spawn_fns.get(&kind).copied()
+    }
+}
+
+/// Sent when a spawner spawns an entity. The entity exists once the commands
+/// of [`SpawnerSet`] have been applied.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SpawnerSpawnEvent {
+    /// The [`ChunkLayer`] the spawner is in.
+    pub layer: Entity,
+    /// The position of the spawner.
+    pub spawner: BlockPos,
+    /// The spawned entity.
+    pub entity: Entity,
+    pub kind: EntityKind,
+}
+
+/// Entities can only be spawned where they don't collide with blocks. Only
+/// the block at `position` and the one above it are checked.
+fn has_room(layer: &ChunkLayer, position: DVec3) -> bool {
+    let pos = BlockPos::from(position);
+
+    [pos, pos.offset(0, 1, 0)].into_iter().all(|pos| {
+        layer
+            .block(pos)
+            .is_some_and(|block| !block.state.blocks_motion())
+    })
+}
+
+fn init_spawners(
+    layers: Query<Entity, (With<ChunkLayer>, Without<Spawners>)>,
+    mut commands: Commands,
+) {
+    for entity in &layers {
+        commands.entity(entity).insert(Spawners::default());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_spawners(
+    mut layers: Query<(Entity, &mut ChunkLayer, &mut Spawners)>,
+    players: Query<(&Position, &EntityLayerId), (With<Client>, NotSpectator, Without<Despawned>)>,
+    entities: Query<(&EntityKind, &Position, &EntityLayerId), Without<Despawned>>,
+    spawn_fns: Res<SpawnerEntities>,
+    mut commands: Commands,
+    mut events: EventWriter<SpawnerSpawnEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (layer_entity, mut layer, mut spawners) in &mut layers {
+        let spawners = &mut *spawners;
+
+        for (pos, data) in std::mem::take(&mut spawners.placed) {
+            layer.set_block(pos, data);
+            spawners.delays.insert(pos, None);
+        }
+
+        let mut removed = vec![];
+
+        for (&pos, delay) in &mut spawners.delays {
+            let data = layer.block(pos).and_then(|block| {
+                if block.state.to_kind() == BlockKind::Spawner {
+                    block.nbt.and_then(SpawnerData::from_nbt)
+                } else {
+                    None
+                }
+            });
+
+            let Some(data) = data else {
+                removed.push(pos);
+                continue;
+            };
+
+            let center = DVec3::new(
+                f64::from(pos.x) + 0.5,
+                f64::from(pos.y) + 0.5,
+                f64::from(pos.z) + 0.5,
+            );
+            let player_range = f64::from(data.required_player_range);
+
+            let active = players.iter().any(|(position, player_layer)| {
+                player_layer.0 == layer_entity
+                    && position.0.distance_squared(center) < player_range * player_range
+            });
+
+            if !active {
+                continue;
+            }
+
+            let ticks = delay.get_or_insert(data.delay);
+
+            if *ticks > 0 {
+                *ticks -= 1;
+                continue;
+            }
+
+            // Entities within the spawn range of the spawner's block count
+            // towards the limit.
+            let spawn_range = f64::from(data.spawn_range);
+            let min =
+                DVec3::new(f64::from(pos.x), f64::from(pos.y), f64::from(pos.z)) - spawn_range;
+            let max = min + 1.0 + spawn_range * 2.0;
+
+            let mut nearby = entities
+                .iter()
+                .filter(|&(kind, position, entity_layer)| {
+                    *kind == data.entity
+                        && entity_layer.0 == layer_entity
+                        && position.0.cmpge(min).all()
+                        && position.0.cmple(max).all()
+                })
+                .count();
+
+            let spawn_fn = spawn_fns.get(data.entity);
+            let mut reset = spawn_fn.is_none();
+
+            if let Some(spawn_fn) = spawn_fn {
+                for _ in 0..data.spawn_count {
+                    if nearby >= data.max_nearby_entities.max(0) as usize {
+                        reset = true;
+                        break;
+                    }
+
+                    let position = DVec3::new(
+                        f64::from(pos.x)
+                            + (rng.gen::<f64>() - rng.gen::<f64>()) * spawn_range
+                            + 0.5,
+                        f64::from(pos.y + rng.gen_range(-1..=1)),
+                        f64::from(pos.z)
+                            + (rng.gen::<f64>() - rng.gen::<f64>()) * spawn_range
+                            + 0.5,
+                    );
+
+                    if !has_room(&layer, position) {
+                        continue;
+                    }
+
+                    let entity = spawn_fn(&mut commands, layer_entity, position);
+
+                    events.send(SpawnerSpawnEvent {
+                        layer: layer_entity,
+                        spawner: pos,
+                        entity,
+                        kind: data.entity,
+                    });
+
+                    nearby += 1;
+                    reset = true;
+                }
+            }
+
+            // Spawners without room for their entities try again on the next
+            // tick.
+            if reset {
+                *ticks = data.next_delay(&mut rng);
+
+                layer.view_writer(pos).write_packet(&BlockEventS2c {
+                    position: pos,
+                    action_id: RESET_DELAY_BLOCK_ACTION,
+                    action_parameter: 0,
+                    block_type: BlockKind::Spawner,
+                });
+            }
+        }
+
+        for pos in removed {
+            spawners.delays.remove(&pos);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Making a client follow another player or entity, such as for staff
+//! watching a suspected cheater.
+//!
+//! Adding [`Spectating`] to a client moves it along with the target every
+//! tick, so the chunks around the target stay loaded for it. If the target is
+//! a client, the follower is also moved to its chunk layer. The target must be
+//! in one of the follower's [`VisibleEntityLayers`] for the camera to work.
+//!
+//! There are two ways to follow the target, chosen with [`SpectateMode`]:
+//!
+//! - [`SpectateMode::Camera`] makes the client see through the eyes of the
+//!   target, like a spectator clicking on a player in vanilla.
+//! - [`SpectateMode::Teleport`] teleports the client to the target and copies
+//!   its look, but the client is free to look around.
+//!
+//! Other players see the follower moving with the target, so it's usually
+//! combined with [`GameMode::Spectator`] and [`Vanished`].
+//!
+//! When the target despawns or disconnects, the component is removed and a
+//! [`SpectateEndEvent`] is sent. Removing the component stops following the
+//! target without sending an event.
+//!
+//! [`SpectatePlugin`] is not part of `DefaultPlugins` and has to be added
+//! separately.
+//!
+//! [`VisibleEntityLayers`]: crate::client::VisibleEntityLayers
+//! [`GameMode::Spectator`]: crate::GameMode::Spectator
+//! [`Vanished`]: crate::vanish::Vanished
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::{EntityId, InitEntitiesSet, Look, Position};
+use valence_protocol::packets::play::SetCameraEntityS2c;
+use valence_protocol::{VarInt, WritePacket};
+use valence_server_common::Despawned;
+
+use crate::client::{
+    handle_layer_messages, update_view_and_layers, Client, UpdateClientsSet, VisibleChunkLayer,
+};
+
+pub struct SpectatePlugin;
+
+impl Plugin for SpectatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpectateEndEvent>().add_systems(
+            PostUpdate,
+            (
+                follow_targets.before(InitEntitiesSet),
+                (attach_spectator_cameras, detach_spectator_cameras)
+                    .in_set(UpdateClientsSet)
+                    .after(handle_layer_messages)
+                    .after(update_view_and_layers),
+            ),
+        );
+    }
+}
+
+/// A [`Component`] for clients following another entity. See the [module
+/// documentation](self).
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Spectating {
+    /// The entity being followed.
+    pub target: Entity,
+    pub mode: SpectateMode,
+}
+
+impl Spectating {
+    pub fn new(target: Entity, mode: SpectateMode) -> Self {
+        Self { target, mode }
+    }
+}
+
+/// How a client follows the target of [`Spectating`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum SpectateMode {
+    /// See through the eyes of the target.
+    #[default]
+    Camera,
+    /// Teleport to the target every tick.
+    Teleport,
+}
+
+/// Sent when the target of a [`Spectating`] client despawns or disconnects.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SpectateEndEvent {
+    pub client: Entity,
+    pub target: Entity,
+}
+
+/// The entity a client was told to use as its camera.
+#[derive(Component, Debug)]
+struct SpectatorCamera(Entity);
+
+fn follow_targets(
+    clients: Query<(Entity, &Spectating), With<Client>>,
+    mut entities: Query<
+        (&mut Position, &mut Look, Option<&mut VisibleChunkLayer>),
+        Without<Despawned>,
+    >,
+    mut end_events: EventWriter<SpectateEndEvent>,
+    mut commands: Commands,
+) {
+    for (entity, spectating) in &clients {
+        let target = spectating.target;
+
+        let Ok((&target_pos, &target_look, target_chunk_layer)) = entities.get(target) else {
+            end_events.send(SpectateEndEvent {
+                client: entity,
+                target,
+            });
+            commands.entity(entity).remove::<Spectating>();
+            continue;
+        };
+
+        let target_chunk_layer = target_chunk_layer.copied();
+
+        let Ok((mut pos, mut look, chunk_layer)) = entities.get_mut(entity) else {
+            continue;
+        };
+
+        pos.set_if_neq(target_pos);
+
+        if spectating.mode == SpectateMode::Teleport {
+            look.set_if_neq(target_look);
+        }
+
+        if let (Some(mut chunk_layer), Some(target_chunk_layer)) = (chunk_layer, target_chunk_layer)
+        {
+            chunk_layer.set_if_neq(target_chunk_layer);
+        }
+    }
+}
+
+/// Makes clients in [`SpectateMode::Camera`] see through the eyes of their
+/// target, once it's been spawned for them.
+fn attach_spectator_cameras(
+    mut clients: Query<(Entity, &mut Client, &Spectating, Option<&SpectatorCamera>)>,
+    targets: Query<&EntityId>,
+    mut commands: Commands,
+) {
+    for (entity, mut client, spectating, camera) in &mut clients {
+        let camera = camera.map(|c| c.0);
+
+        if spectating.mode == SpectateMode::Camera {
+            if camera == Some(spectating.target) {
+                continue;
+            }
+
+            if let Ok(id) = targets.get(spectating.target) {
+                client.write_packet(&SetCameraEntityS2c {
+                    entity_id: id.get().into(),
+                });
+
+                commands
+                    .entity(entity)
+                    .insert(SpectatorCamera(spectating.target));
+            }
+        } else if camera.is_some() {
+            reset_camera(&mut client);
+            commands.entity(entity).remove::<SpectatorCamera>();
+        }
+    }
+}
+
+/// Gives clients which stopped following their target their own viewpoint
+/// back.
+fn detach_spectator_cameras(
+    mut clients: Query<(Entity, &mut Client), (With<SpectatorCamera>, Without<Spectating>)>,
+    mut commands: Commands,
+) {
+    for (entity, mut client) in &mut clients {
+        reset_camera(&mut client);
+        commands.entity(entity).remove::<SpectatorCamera>();
+    }
+}
+
+fn reset_camera(client: &mut Client) {
+    // Clients see themselves as entity 0.
+    client.write_packet(&SetCameraEntityS2c {
+        entity_id: VarInt(0),
+    });
+}
@@ -0,0 +1,123 @@
+//! Client input for the vehicles clients ride.
+//!
+//! Clients riding a [`Vehicle`] send the keys they press instead of moving
+//! themselves, and vehicles they control, such as boats and saddled horses,
+//! are moved by the client too. These packets are turned into events for the
+//! vehicle, so custom mounts can be driven by the riding client:
+//!
+//! - [`SteerVehicleEvent`] for the movement, jump and sneak keys.
+//! - [`PaddleBoatEvent`] for the paddles of a boat.
+//! - [`VehicleMoveEvent`] for the position the client moved its vehicle to.
+//!
+//! Clients only move vehicles they think they control, so moving other
+//! vehicles is up to the server.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::passengers::Vehicle;
+use valence_entity::{Look, Position};
+use valence_math::DVec3;
+use valence_protocol::packets::play::{BoatPaddleStateC2s, PlayerInputC2s, VehicleMoveC2s};
+
+use crate::event_loop::{EventLoopPreUpdate, PacketEvent};
+
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SteerVehicleEvent>()
+            .add_event::<PaddleBoatEvent>()
+            .add_event::<VehicleMoveEvent>()
+            .add_systems(EventLoopPreUpdate, handle_steering);
+    }
+}
+
+/// Sent every tick a client riding a vehicle sends its input.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct SteerVehicleEvent {
+    pub client: Entity,
+    pub vehicle: Entity,
+    /// How far the client steers to the left, in `-1.0..=1.0`. Negative values
+    /// steer to the right.
+    pub sideways: f32,
+    /// How far the client steers forward, in `-1.0..=1.0`. Negative values
+    /// steer backward.
+    pub forward: f32,
+    /// Whether the client is pressing the jump key.
+    pub jump: bool,
+    /// Whether the client is pressing the sneak key to dismount. The client
+    /// keeps riding until its [`Vehicle`] is removed.
+    pub unmount: bool,
+}
+
+/// Sent when a client riding a boat starts or stops turning its paddles.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PaddleBoatEvent {
+    pub client: Entity,
+    pub vehicle: Entity,
+    pub left_paddle_turning: bool,
+    pub right_paddle_turning: bool,
+}
+
+/// Sent when a client moves the vehicle it controls. The [`Position`] of the
+/// vehicle isn't changed.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct VehicleMoveEvent {
+    pub client: Entity,
+    pub vehicle: Entity,
+    pub position: DVec3,
+    /// The position of the vehicle before the move.
+    pub old_position: DVec3,
+    pub look: Look,
+}
+
+fn handle_steering(
+    mut packets: EventReader<PacketEvent>,
+    clients: Query<&Vehicle>,
+    vehicles: Query<&Position>,
+    mut steer_events: EventWriter<SteerVehicleEvent>,
+    mut paddle_events: EventWriter<PaddleBoatEvent>,
+    mut move_events: EventWriter<VehicleMoveEvent>,
+) {
+    for packet in packets.read() {
+        // Input from clients which aren't riding anything is ignored.
+        let Ok(vehicle) = clients.get(packet.client) else {
+            continue;
+        };
+
+        let vehicle = vehicle.get();
+
+        if let Some(pkt) = packet.decode::<PlayerInputC2s>() {
+            steer_events.send(SteerVehicleEvent {
+                client: packet.client,
+                vehicle,
+                sideways: pkt.sideways.clamp(-1.0, 1.0),
+                forward: pkt.forward.clamp(-1.0, 1.0),
+                jump: pkt.flags.jump(),
+                unmount: pkt.flags.unmount(),
+            });
+        } else if let Some(pkt) = packet.decode::<BoatPaddleStateC2s>() {
+            paddle_events.send(PaddleBoatEvent {
+                client: packet.client,
+                vehicle,
+                left_paddle_turning: pkt.left_paddle_turning,
+                right_paddle_turning: pkt.right_paddle_turning,
+            });
+        } else if let Some(pkt) = packet.decode::<VehicleMoveC2s>() {
+            let Ok(old_position) = vehicles.get(vehicle) else {
+                continue;
+            };
+
+            move_events.send(VehicleMoveEvent {
+                client: packet.client,
+                vehicle,
+                position: pkt.position,
+                old_position: old_position.0,
+                look: Look {
+                    yaw: pkt.yaw,
+                    pitch: pkt.pitch,
+                },
+            });
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Primed TNT.
+//!
+//! [`TntPlugin`] simulates primed TNT spawned with [`PrimedTntBundle`] like
+//! vanilla. It falls and collides with the blocks of its [`ChunkLayer`], and
+//! explodes with [`ChunkLayer::explode`] once its fuse runs out. TNT blocks
+//! destroyed by explosions are primed with a short random fuse, so chain
+//! reactions work too. The plugin is not part of `DefaultPlugins` and has to
+//! be added separately.
+//!
+//! As with other explosions, the TNT's layer entity must have both a
+//! [`ChunkLayer`] and an [`EntityLayer`](crate::EntityLayer), and
+//! [`ExplosionPlugin`](crate::explosion::ExplosionPlugin) must be added as
+//! well.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rand::Rng;
+use valence_entity::tnt::{Fuse, TntEntityBundle};
+use valence_entity::{EntityLayerId, InitEntitiesSet, OnGround, Position, Velocity};
+use valence_math::{DVec3, Vec3};
+use valence_protocol::block::BlockKind;
+use valence_protocol::BlockPos;
+use valence_server_common::Despawned;
+
+use crate::explosion::{ExplosionEvent, ExplosionOptions};
+use crate::falling_block::tick_physics;
+use crate::layer::ChunkLayer;
+
+pub struct TntPlugin;
+
+impl Plugin for TntPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                prime_exploded_tnt.before(InitEntitiesSet),
+                tick_primed_tnt.before(crate::explosion::affect_entities),
+            ),
+        );
+    }
+}
+
+/// Simulation state for TNT spawned with [`PrimedTntBundle`]. The remaining
+/// fuse is stored in the entity's [`Fuse`], in ticks.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct PrimedTnt {
+    /// The power of the explosion.
+    pub power: f32,
+    pub options: ExplosionOptions,
+}
+
+impl PrimedTnt {
+    /// The fuse of TNT ignited by players, in ticks.
+    pub const DEFAULT_FUSE: i32 = 80;
+}
+
+impl Default for PrimedTnt {
+    fn default() -> Self {
+        Self {
+            power: 4.0,
+            options: ExplosionOptions::default(),
+        }
+    }
+}
+
+/// Bundle for spawning primed TNT.
+#[derive(Bundle)]
+pub struct PrimedTntBundle {
+    pub tnt: PrimedTnt,
+    pub entity: TntEntityBundle,
+}
+
+impl PrimedTntBundle {
+    /// Creates primed TNT with the default fuse, and the small random push
+    /// vanilla gives TNT ignited by players.
+    pub fn new<P: Into<DVec3>>(layer: Entity, position: P) -> Self {
+        let angle = rand::random::<f32>() * std::f32::consts::TAU;
+
+        Self {
+            tnt: PrimedTnt::default(),
+            entity: TntEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(position),
+                velocity: Velocity(Vec3::new(-angle.sin() * 0.4, 4.0, -angle.cos() * 0.4)),
+                tnt_fuse: Fuse(PrimedTnt::DEFAULT_FUSE),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Creates primed TNT in the middle of the block at `pos`.
+    pub fn from_block<P: Into<BlockPos>>(layer: Entity, pos: P) -> Self {
+        let pos = pos.into();
+
+        Self::new(
+            layer,
+            [
+                f64::from(pos.x) + 0.5,
+                f64::from(pos.y),
+                f64::from(pos.z) + 0.5,
+            ],
+        )
+    }
+
+    #[must_use]
+    pub fn with_fuse(mut self, fuse: i32) -> Self {
+        self.entity.tnt_fuse = Fuse(fuse);
+        self
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tick_primed_tnt(
+    mut tnt: Query<
+        (
+            Entity,
+            &PrimedTnt,
+            &mut Fuse,
+            &mut Position,
+            &mut Velocity,
+            &mut OnGround,
+            &EntityLayerId,
+        ),
+        Without<Despawned>,
+    >,
+    mut layers: Query<&mut ChunkLayer>,
+    mut commands: Commands,
+) {
+    for (entity, primed, mut fuse, mut pos, mut velocity, mut on_ground, layer) in &mut tnt {
+        let Ok(mut chunk_layer) = layers.get_mut(layer.0) else {
+            continue;
+        };
+
+        let mut v = velocity.0.as_dvec3() / 20.0;
+        let landed = tick_physics(&chunk_layer, &mut pos.0, &mut v);
+
+        // Clients simulate TNT and count down its fuse themselves, so only
+        // changes made by others are sent.
+        velocity.bypass_change_detection().0 = (v * 20.0).as_vec3();
+        on_ground.set_if_neq(OnGround(landed));
+        fuse.bypass_change_detection().0 -= 1;
+
+        if fuse.0 <= 0 {
+            commands.entity(entity).insert(Despawned);
+
+            // Like vanilla, TNT explodes slightly above its feet.
+            chunk_layer.explode(
+                pos.0 + DVec3::new(0.0, 0.98 * 0.0625, 0.0),
+                primed.power,
+                primed.options,
+            );
+        }
+    }
+}
+
+fn prime_exploded_tnt(mut events: EventReader<ExplosionEvent>, mut commands: Commands) {
+    let mut rng = rand::thread_rng();
+
+    for event in events.read() {
+        for (pos, block) in &event.explosion.destroyed_blocks {
+            if block.state.to_kind() != BlockKind::Tnt {
+                continue;
+            }
+
+            // Vanilla gives TNT destroyed by explosions a shorter fuse.
+            let fuse = PrimedTnt::DEFAULT_FUSE / 4;
+            let fuse = rng.gen_range(fuse / 2..fuse + fuse / 2);
+
+            commands.spawn(PrimedTntBundle::from_block(event.layer, *pos).with_fuse(fuse));
+        }
+    }
+}
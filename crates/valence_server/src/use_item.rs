@@ -0,0 +1,119 @@
+//! A single event for clients using the item in their hand.
+//!
+//! Right clicking makes the client send a
+//! [`PlayerInteractBlockC2s`](valence_protocol::packets::play::PlayerInteractBlockC2s)
+//! packet when looking at a block, and then a
+//! [`PlayerInteractItemC2s`](valence_protocol::packets::play::PlayerInteractItemC2s)
+//! packet, for each hand in turn. [`UseItemEvent`] combines these into at most
+//! one event per hand per tick, so gameplay code doesn't have to handle the
+//! same click twice.
+//!
+//! The sequence numbers of all the packets, including the ones that didn't
+//! result in an event, are acknowledged through the
+//! [`ActionSequence`](crate::action::ActionSequence) of the client.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use rustc_hash::FxHashMap;
+use valence_math::Vec3;
+use valence_protocol::{BlockPos, Direction, GameMode, Hand};
+use valence_server_common::Server;
+
+use crate::event_loop::EventLoopPreUpdate;
+use crate::interact_block::{handle_interact_block, InteractBlockEvent};
+use crate::interact_item::{handle_player_interact_item, InteractItemEvent};
+use crate::interaction_rules::InteractionRules;
+
+pub struct UseItemPlugin;
+
+impl Plugin for UseItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InteractionRules>()
+            .add_event::<InteractBlockEvent>()
+            .add_event::<InteractItemEvent>()
+            .add_event::<UseItemEvent>()
+            .add_systems(
+                EventLoopPreUpdate,
+                send_use_item_events
+                    .after(handle_interact_block)
+                    .after(handle_player_interact_item),
+            );
+    }
+}
+
+/// Sent when a client uses the item in `hand`, either on a block or in the
+/// air.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct UseItemEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    /// The block the item was used on, or `None` if it was used in the air.
+    pub target: Option<UseItemTarget>,
+    /// The sequence number of the packet this event was sent for.
+    pub sequence: i32,
+}
+
+/// The block targeted by a [`UseItemEvent`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct UseItemTarget {
+    pub position: BlockPos,
+    /// The face of the block that was clicked.
+    pub face: Direction,
+    /// The position inside of the block that was clicked on.
+    pub cursor_pos: Vec3,
+    /// Whether the player's head is inside a block.
+    pub head_inside_block: bool,
+}
+
+fn send_use_item_events(
+    mut block_events: EventReader<InteractBlockEvent>,
+    mut item_events: EventReader<InteractItemEvent>,
+    clients: Query<&GameMode>,
+    server: Res<Server>,
+    rules: Res<InteractionRules>,
+    // The tick each client last used each hand in.
+    mut last_used: Local<FxHashMap<(Entity, Hand), i64>>,
+    mut events: EventWriter<UseItemEvent>,
+) {
+    let tick = server.current_tick();
+
+    last_used.retain(|_, used| *used == tick);
+
+    // Clients send the packet for using an item on a block first.
+    let uses = block_events
+        .read()
+        .map(|event| UseItemEvent {
+            client: event.client,
+            hand: event.hand,
+            target: Some(UseItemTarget {
+                position: event.position,
+                face: event.face,
+                cursor_pos: event.cursor_pos,
+                head_inside_block: event.head_inside_block,
+            }),
+            sequence: event.sequence,
+        })
+        .chain(item_events.read().map(|event| UseItemEvent {
+            client: event.client,
+            hand: event.hand,
+            target: None,
+            sequence: event.sequence,
+        }));
+
+    for event in uses {
+        let Ok(game_mode) = clients.get(event.client) else {
+            continue;
+        };
+
+        if !rules.can_interact(*game_mode) {
+            continue;
+        }
+
+        if last_used.insert((event.client, event.hand), tick).is_some() {
+            // This hand was already used this tick.
+            continue;
+        }
+
+        events.send(event);
+    }
+}
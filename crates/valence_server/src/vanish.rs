@@ -0,0 +1,163 @@
+//! Hiding players from everyone else on the server.
+//!
+//! Inserting [`Vanished`] on a client hides it from other players at once: its
+//! entity is moved to the [`VanishLayer`], its player list entry is unlisted
+//! or greyed out, and it's left out of the player count in the server list.
+//! Removing the component brings it back to the entity layer it was on.
+//!
+//! Staff who should still see vanished players can add the [`VanishLayer`] to
+//! their [`VisibleEntityLayers`].
+//!
+//! Join and leave messages should be broadcast on [`AnnounceJoinEvent`] and
+//! [`AnnounceLeaveEvent`] rather than when clients connect and disconnect.
+//! These are also sent when a player vanishes or reappears, so that others
+//! see the player leave and join as usual.
+//!
+//! [`VisibleEntityLayers`]: crate::client::VisibleEntityLayers
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_entity::EntityLayerId;
+
+use crate::client::Client;
+use crate::layer::UpdateLayersPreClientSet;
+use crate::{Despawned, EntityLayer, Server};
+
+pub struct VanishPlugin;
+
+/// When clients are vanished and made to reappear.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct VanishSet;
+
+impl Plugin for VanishPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnnounceJoinEvent>()
+            .add_event::<AnnounceLeaveEvent>()
+            .configure_sets(PostUpdate, VanishSet.before(UpdateLayersPreClientSet))
+            .add_systems(Startup, spawn_vanish_layer)
+            .add_systems(PostUpdate, update_vanished_clients.in_set(VanishSet));
+    }
+}
+
+/// Hides a client from other players. See the [module
+/// documentation](self).
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Vanished {
+    /// How the player list entry of the client is shown to other clients.
+    pub player_list: VanishedListing,
+    /// Whether the client is left out of the number of online players in the
+    /// server list. `true` by default.
+    pub hide_from_server_list: bool,
+}
+
+impl Default for Vanished {
+    fn default() -> Self {
+        Self {
+            player_list: VanishedListing::default(),
+            hide_from_server_list: true,
+        }
+    }
+}
+
+/// How the player list entry of a [`Vanished`] client is shown to other
+/// clients. Clients need the entry to see the entity of the vanished player,
+/// so it's never removed completely.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum VanishedListing {
+    /// The entry isn't listed in the tab list.
+    #[default]
+    Hidden,
+    /// The entry is shown in grey, like a player in spectator mode.
+    Greyed,
+    /// The entry is shown as usual.
+    Shown,
+}
+
+/// A [`Resource`] with the entity of the [`EntityLayer`] vanished clients are
+/// moved to.
+#[derive(Resource, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VanishLayer(pub Entity);
+
+/// Sent when other players should be told that a client joined: when a client
+/// which isn't vanished joins, and when a vanished client reappears.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AnnounceJoinEvent {
+    pub client: Entity,
+}
+
+/// Sent when other players should be told that a client left: when a client
+/// which isn't vanished disconnects, and when a client vanishes.
+#[derive(Event, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AnnounceLeaveEvent {
+    pub client: Entity,
+}
+
+fn spawn_vanish_layer(mut commands: Commands, server: Res<Server>) {
+    let layer = commands.spawn(EntityLayer::new(&server)).id();
+    commands.insert_resource(VanishLayer(layer));
+}
+
+#[allow(clippy::type_complexity)]
+fn update_vanished_clients(
+    joined: Query<Entity, (Added<Client>, Without<Vanished>, Without<Despawned>)>,
+    mut vanished: Query<
+        (Entity, &mut EntityLayerId, Ref<Vanished>),
+        (With<Client>, Or<(Added<Vanished>, Changed<EntityLayerId>)>),
+    >,
+    mut reappeared: Query<&mut EntityLayerId, (Without<Vanished>, Without<Despawned>)>,
+    mut removed_vanished: RemovedComponents<Vanished>,
+    mut disconnected: RemovedComponents<Client>,
+    vanish_layer: Res<VanishLayer>,
+    mut join_events: EventWriter<AnnounceJoinEvent>,
+    mut leave_events: EventWriter<AnnounceLeaveEvent>,
+    // The entity layer each vanished client was on before it vanished.
+    mut hidden: Local<HashMap<Entity, Entity>>,
+    // The clients other players were told about.
+    mut announced: Local<HashSet<Entity>>,
+) {
+    for client in disconnected.read() {
+        hidden.remove(&client);
+
+        if announced.remove(&client) {
+            leave_events.send(AnnounceLeaveEvent { client });
+        }
+    }
+
+    for client in &joined {
+        announced.insert(client);
+        join_events.send(AnnounceJoinEvent { client });
+    }
+
+    for client in removed_vanished.read() {
+        let Some(layer) = hidden.remove(&client) else {
+            continue;
+        };
+
+        if let Ok(mut layer_id) = reappeared.get_mut(client) {
+            if layer_id.0 == vanish_layer.0 {
+                layer_id.0 = layer;
+            }
+
+            if announced.insert(client) {
+                join_events.send(AnnounceJoinEvent { client });
+            }
+        }
+    }
+
+    for (entity, mut layer_id, vanished) in &mut vanished {
+        // Clients moved to another layer while vanished reappear on that layer.
+        if layer_id.0 != vanish_layer.0 {
+            hidden.insert(entity, layer_id.0);
+            layer_id.0 = vanish_layer.0;
+        } else {
+            hidden.entry(entity).or_insert(vanish_layer.0);
+        }
+
+        // Clients which join vanished were never seen.
+        if vanished.is_added() && announced.remove(&entity) {
+            leave_events.send(AnnounceLeaveEvent { client: entity });
+        }
+    }
+}
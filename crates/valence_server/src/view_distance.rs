@@ -0,0 +1,311 @@
+//! Limits on how far clients can see and how far around them the world is
+//! simulated.
+//!
+//! Adding [`MaxViewDistance`] to a [`ChunkLayer`] entity caps the
+//! [`ViewDistance`] of clients viewing the layer, regardless of what the
+//! clients request in their settings.
+//!
+//! The [`ViewDistance`] of clients without either limit is left alone, so it
+//! can be set by hand.
+//!
+//! Adding [`AdaptiveViewDistance`] to a client entity lowers the client's
+//! view distance while its connection can't keep up, and raises it back once
+//! the connection recovers. This keeps slow clients from being disconnected
+//! when the data queued for them overflows.
+//!
+//! Adding [`SimulationDistance`] to a [`ChunkLayer`] entity makes the layer
+//! track the chunks near clients in [`SimulatedChunks`]. Systems that tick the
+//! world, such as [random ticks](crate::random_tick), only do their work in
+//! those chunks, so heavy simulation only happens near players. Layers without
+//! a simulation distance simulate every chunk in view of a client.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use derive_more::{Deref, DerefMut};
+use rustc_hash::FxHashSet;
+use valence_entity::Position;
+use valence_protocol::encode::WritePacket;
+use valence_protocol::packets::play::SimulationDistanceS2c;
+use valence_protocol::{ChunkPos, VarInt};
+
+use crate::client::{Client, UpdateClientsSet, ViewDistance, VisibleChunkLayer};
+use crate::client_settings::ClientSettings;
+use crate::keepalive::Ping;
+use crate::layer::UpdateLayersPreClientSet;
+use crate::random_tick::RandomTickSet;
+use crate::{ChunkLayer, ChunkView};
+
+pub struct ViewDistancePlugin;
+
+impl Plugin for ViewDistancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                adapt_view_distances.before(cap_view_distances),
+                cap_view_distances.before(UpdateClientsSet),
+                init_simulated_chunks.before(update_simulated_chunks),
+                update_simulated_chunks
+                    .after(cap_view_distances)
+                    .before(RandomTickSet)
+                    .before(UpdateLayersPreClientSet),
+                update_simulation_distance
+                    .in_set(UpdateClientsSet)
+                    .after(crate::spawn::respawn),
+            ),
+        );
+    }
+}
+
+/// A [`Component`] for [`ChunkLayer`] entities limiting the [`ViewDistance`]
+/// of the clients viewing the layer, in chunks. Clients requesting a smaller
+/// view distance in their settings keep it.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+pub struct MaxViewDistance(pub u8);
+
+/// A [`Component`] for client entities which lowers their [`ViewDistance`]
+/// when their connection shows signs of congestion, down to
+/// `min_view_distance`, and raises it back when the connection is healthy.
+///
+/// The connection is congested when the client's [`Ping`] or the amount of
+/// data queued for it exceeds the limits. It's healthy when both are below a
+/// quarter of the limits.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct AdaptiveViewDistance {
+    /// The ping in milliseconds above which the connection is congested.
+    pub max_ping: i32,
+    /// The number of bytes waiting to be sent above which the connection is
+    /// congested. See [`ClientConnection::queued_bytes`].
+    ///
+    /// [`ClientConnection::queued_bytes`]: crate::client::ClientConnection::queued_bytes
+    pub max_queued_bytes: usize,
+    /// The view distance isn't lowered below this.
+    pub min_view_distance: u8,
+    /// The number of ticks between adjustments of the view distance.
+    pub interval: u32,
+    /// The number of chunks the view distance is currently lowered by.
+    reduction: u8,
+    ticks: u32,
+}
+
+impl AdaptiveViewDistance {
+    /// Returns the number of chunks the view distance is currently lowered by.
+    pub fn reduction(&self) -> u8 {
+        self.reduction
+    }
+}
+
+impl Default for AdaptiveViewDistance {
+    fn default() -> Self {
+        Self {
+            max_ping: 300,
+            max_queued_bytes: 1024 * 1024,
+            min_view_distance: 4,
+            interval: 20,
+            reduction: 0,
+            ticks: 0,
+        }
+    }
+}
+
+/// A [`Component`] for [`ChunkLayer`] entities setting the distance around
+/// clients in which the layer is simulated, in chunks. Like vanilla, the
+/// distance is also limited by the [`ViewDistance`] of each client.
+///
+/// The simulated chunks are tracked in [`SimulatedChunks`], which is added
+/// automatically.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Debug, Deref, DerefMut)]
+pub struct SimulationDistance(pub u8);
+
+impl Default for SimulationDistance {
+    fn default() -> Self {
+        Self(10)
+    }
+}
+
+/// The positions of the chunks within the [`SimulationDistance`] of a client
+/// viewing the layer, and of the chunks covered by a
+/// [ticket](crate::layer::ticket). Updated every tick before layers are
+/// updated.
+#[derive(Component, Default, Debug)]
+pub struct SimulatedChunks(FxHashSet<ChunkPos>);
+
+impl SimulatedChunks {
+    /// Returns whether the chunk at `pos` should be simulated.
+    pub fn contains<P: Into<ChunkPos>>(&self, pos: P) -> bool {
+        self.0.contains(&pos.into())
+    }
+
+    /// Returns an iterator over the simulated chunk positions. The order is
+    /// undefined.
+    pub fn iter(&self) -> impl Iterator<Item = ChunkPos> + Clone + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn adapt_view_distances(
+    mut clients: Query<(&Client, &Ping, &ViewDistance, &mut AdaptiveViewDistance)>,
+) {
+    for (client, ping, view_dist, mut adaptive) in &mut clients {
+        // Counting ticks isn't a change of the settings.
+        let adaptive_ref = adaptive.bypass_change_detection();
+        adaptive_ref.ticks += 1;
+
+        if adaptive_ref.ticks < adaptive_ref.interval {
+            continue;
+        }
+
+        adaptive_ref.ticks = 0;
+
+        let queued = client.connection().queued_bytes();
+
+        if ping.0 > adaptive.max_ping || queued > adaptive.max_queued_bytes {
+            if view_dist.get() > adaptive.min_view_distance {
+                adaptive.reduction += 1;
+            }
+        } else if ping.0 <= adaptive.max_ping / 4
+            && queued <= adaptive.max_queued_bytes / 4
+            && adaptive.reduction > 0
+        {
+            adaptive.reduction -= 1;
+        }
+    }
+}
+
+/// Marks clients whose [`ViewDistance`] was limited by [`cap_view_distances`],
+/// so it can be restored when the limits no longer apply.
+#[derive(Component)]
+struct ViewDistanceLimited;
+
+/// Applies the [`MaxViewDistance`] of the client's layer and the reduction of
+/// its [`AdaptiveViewDistance`] to the view distance requested in its
+/// settings. Only runs when one of them changes, so the [`ViewDistance`] can
+/// still be overridden in between. Clients without either limit are skipped.
+#[allow(clippy::type_complexity)]
+fn cap_view_distances(
+    mut clients: Query<(
+        Entity,
+        &mut ViewDistance,
+        Ref<ClientSettings>,
+        Ref<VisibleChunkLayer>,
+        Option<Ref<AdaptiveViewDistance>>,
+        Has<ViewDistanceLimited>,
+    )>,
+    layers: Query<Ref<MaxViewDistance>>,
+    mut commands: Commands,
+) {
+    for (entity, mut view_dist, settings, visible_layer, adaptive, limited) in &mut clients {
+        let max = layers.get(visible_layer.0).ok();
+
+        if max.is_none() && adaptive.is_none() {
+            if limited {
+                // The limits no longer apply, so the client gets the distance it asked for.
+                if settings.view_distance != 0 {
+                    view_dist.set_if_neq(ViewDistance::new(settings.view_distance));
+                }
+
+                commands.entity(entity).remove::<ViewDistanceLimited>();
+            }
+
+            continue;
+        }
+
+        if !limited {
+            commands.entity(entity).insert(ViewDistanceLimited);
+        }
+
+        if limited
+            && !settings.is_changed()
+            && !visible_layer.is_changed()
+            && !max.as_ref().is_some_and(|max| max.is_changed())
+            && !adaptive
+                .as_ref()
+                .is_some_and(|adaptive| adaptive.is_changed())
+        {
+            continue;
+        }
+
+        // Settings from the client may not have arrived yet.
+        let requested = if settings.view_distance == 0 {
+            view_dist.get()
+        } else {
+            settings.view_distance
+        };
+
+        let max = max.map_or(u8::MAX, |max| max.0);
+        let mut dist = requested.min(max);
+
+        if let Some(adaptive) = adaptive {
+            dist = dist
+                .saturating_sub(adaptive.reduction)
+                .max(adaptive.min_view_distance.min(dist));
+        }
+
+        view_dist.set_if_neq(ViewDistance::new(dist));
+    }
+}
+
+fn init_simulated_chunks(
+    layers: Query<Entity, (Added<SimulationDistance>, Without<SimulatedChunks>)>,
+    mut commands: Commands,
+) {
+    for layer in &layers {
+        commands.entity(layer).insert(SimulatedChunks::default());
+    }
+}
+
+fn update_simulated_chunks(
+    mut layers: Query<(&ChunkLayer, &SimulationDistance, &mut SimulatedChunks)>,
+    clients: Query<(&Position, &ViewDistance, &VisibleChunkLayer), With<Client>>,
+) {
+    for (layer, _, mut simulated) in &mut layers {
+        simulated.0.clear();
+        simulated.0.extend(layer.ticketed_chunks());
+    }
+
+    for (pos, view_dist, visible_layer) in &clients {
+        let Ok((_, sim_dist, mut simulated)) = layers.get_mut(visible_layer.0) else {
+            continue;
+        };
+
+        let view = ChunkView::new(pos.0.into(), sim_dist.0.min(view_dist.get()));
+
+        simulated.0.extend(view.iter());
+    }
+}
+
+/// Sends the [`SimulationDistance`] of the client's layer when it changes or
+/// the client moves to another layer.
+fn update_simulation_distance(
+    mut clients: Query<(&mut Client, &ViewDistance, Ref<VisibleChunkLayer>)>,
+    layers: Query<Option<Ref<SimulationDistance>>, With<ChunkLayer>>,
+) {
+    for (mut client, view_dist, visible_layer) in &mut clients {
+        if client.is_added() {
+            // The game join packet includes the simulation distance.
+            continue;
+        }
+
+        let Ok(sim_dist) = layers.get(visible_layer.0) else {
+            continue;
+        };
+
+        if !visible_layer.is_changed() && !sim_dist.as_ref().is_some_and(|dist| dist.is_changed()) {
+            continue;
+        }
+
+        let dist = sim_dist.map_or(view_dist.get(), |dist| dist.0);
+
+        client.write_packet(&SimulationDistanceS2c {
+            simulation_distance: VarInt(dist.into()),
+        });
+    }
+}
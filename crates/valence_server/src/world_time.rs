@@ -0,0 +1,139 @@
+//! The time of day in chunk layers.
+//!
+//! Add [`WorldTime`] to a [`ChunkLayer`] entity to control the position of the
+//! sun and moon for clients viewing the layer. Layers without the component
+//! leave the time up to the clients. The time of day stops advancing if the
+//! layer's [`GameRules::do_daylight_cycle`] is `false`.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence_protocol::packets::play::WorldTimeUpdateS2c;
+use valence_protocol::WritePacket;
+use valence_server_common::Server;
+
+use crate::client::{Client, UpdateClientsSet, VisibleChunkLayer};
+use crate::game_rules::GameRules;
+use crate::layer::ChunkLayer;
+
+pub struct WorldTimePlugin;
+
+impl Plugin for WorldTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                (tick_world_time, broadcast_world_time)
+                    .chain()
+                    .before(UpdateClientsSet),
+                init_world_time_on_layer_join
+                    .in_set(UpdateClientsSet)
+                    .after(crate::spawn::initial_join),
+            ),
+        );
+    }
+}
+
+/// The number of ticks in a Minecraft day.
+pub const TICKS_PER_DAY: i64 = 24000;
+
+/// The time of a [`ChunkLayer`]. Both values advance by one every tick, unless
+/// the daylight cycle is disabled.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct WorldTime {
+    /// The number of ticks the layer has existed for.
+    pub world_age: i64,
+    /// The time of day in ticks. This keeps counting up past
+    /// [`TICKS_PER_DAY`], so it also contains the number of elapsed days.
+    /// 0 is sunrise, 6000 is noon, 12000 is sunset and 18000 is midnight.
+    pub time_of_day: i64,
+}
+
+impl WorldTime {
+    /// Returns a new `WorldTime` with the given time of day.
+    pub const fn new(time_of_day: i64) -> Self {
+        Self {
+            world_age: 0,
+            time_of_day,
+        }
+    }
+
+    /// Returns the time within the current day in `0..TICKS_PER_DAY`.
+    pub const fn day_time(&self) -> i64 {
+        self.time_of_day.rem_euclid(TICKS_PER_DAY)
+    }
+
+    /// Returns the number of full days that have passed.
+    pub const fn day(&self) -> i64 {
+        self.time_of_day.div_euclid(TICKS_PER_DAY)
+    }
+
+    /// Returns whether it is dark enough for players to sleep in beds, assuming
+    /// clear weather.
+    pub const fn is_night(&self) -> bool {
+        matches!(self.day_time(), 12542..=23459)
+    }
+
+    /// Advances the time of day to the next sunrise.
+    pub fn skip_to_morning(&mut self) {
+        self.time_of_day += TICKS_PER_DAY - self.day_time();
+    }
+}
+
+fn daylight_cycle(rules: Option<&GameRules>) -> bool {
+    rules.copied().unwrap_or_default().do_daylight_cycle
+}
+
+fn time_update_packet(time: &WorldTime, daylight_cycle: bool) -> WorldTimeUpdateS2c {
+    // A negative time of day stops clients from advancing it on their own.
+    let time_of_day = if daylight_cycle {
+        time.time_of_day
+    } else {
+        -time.time_of_day.max(1)
+    };
+
+    WorldTimeUpdateS2c {
+        world_age: time.world_age,
+        time_of_day,
+    }
+}
+
+fn tick_world_time(mut layers: Query<(&mut WorldTime, Option<&GameRules>), With<ChunkLayer>>) {
+    for (mut time, rules) in &mut layers {
+        // Ticking doesn't count as a change so that changes made by users are sent to
+        // clients immediately.
+        let time = time.bypass_change_detection();
+        time.world_age += 1;
+
+        if daylight_cycle(rules) {
+            time.time_of_day += 1;
+        }
+    }
+}
+
+fn broadcast_world_time(
+    mut layers: Query<(Ref<WorldTime>, Option<Ref<GameRules>>, &mut ChunkLayer)>,
+    server: Res<Server>,
+) {
+    // Clients advance the time on their own, so it only needs to be synced once in
+    // a while.
+    let resync = server.current_tick() % 20 == 0;
+
+    for (time, rules, mut layer) in &mut layers {
+        let rules_changed = rules.as_ref().is_some_and(|rules| rules.is_changed());
+
+        if resync || time.is_changed() || rules_changed {
+            layer.write_packet(&time_update_packet(&time, daylight_cycle(rules.as_deref())));
+        }
+    }
+}
+
+fn init_world_time_on_layer_join(
+    mut clients: Query<(&mut Client, &VisibleChunkLayer), Changed<VisibleChunkLayer>>,
+    layers: Query<(&WorldTime, Option<&GameRules>), With<ChunkLayer>>,
+) {
+    for (mut client, layer) in &mut clients {
+        if let Ok((time, rules)) = layers.get(layer.0) {
+            client.write_packet(&time_update_packet(time, daylight_cycle(rules)));
+        }
+    }
+}
@@ -10,6 +10,7 @@ use bevy_app::prelude::*;
 use bevy_app::ScheduleRunnerPlugin;
 use bevy_ecs::prelude::*;
 pub use despawn::*;
+use valence_protocol::encode::DEFAULT_COMPRESSION_LEVEL;
 use valence_protocol::CompressionThreshold;
 
 pub use crate::uuid::*;
@@ -49,6 +50,15 @@ pub struct ServerSettings {
     /// Compression is enabled with an unspecified value. This value may
     /// change in future versions.
     pub compression_threshold: CompressionThreshold,
+    /// The zlib compression level of packets sent to clients, from 0 (no
+    /// compression) to 9 (best compression). Higher levels save bandwidth at
+    /// the cost of CPU time. Packets broadcast through layers are always
+    /// compressed with the default level.
+    ///
+    /// # Default Value
+    ///
+    /// [`DEFAULT_COMPRESSION_LEVEL`]
+    pub compression_level: u32,
 }
 
 impl Default for ServerSettings {
@@ -56,6 +66,7 @@ impl Default for ServerSettings {
         Self {
             tick_rate: DEFAULT_TPS,
             compression_threshold: CompressionThreshold(256),
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
         }
     }
 }
@@ -72,6 +83,7 @@ impl Plugin for ServerPlugin {
         app.insert_resource(Server {
             current_tick: 0,
             threshold: settings.compression_threshold,
+            compression_level: settings.compression_level.min(9),
             tick_rate: settings.tick_rate,
         });
 
@@ -94,6 +106,7 @@ pub struct Server {
     /// Incremented on every tick.
     current_tick: i64,
     threshold: CompressionThreshold,
+    compression_level: u32,
     tick_rate: NonZeroU32,
 }
 
@@ -109,6 +122,12 @@ impl Server {
         self.threshold
     }
 
+    /// Returns the server's [compression
+    /// level](ServerSettings::compression_level).
+    pub fn compression_level(&self) -> u32 {
+        self.compression_level
+    }
+
     // Returns the server's [tick rate](ServerPlugin::tick_rate).
     pub fn tick_rate(&self) -> NonZeroU32 {
         self.tick_rate
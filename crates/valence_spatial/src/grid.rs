@@ -0,0 +1,287 @@
+//! A uniform grid of points which can be updated incrementally.
+//!
+//! Unlike the [`Bvh`](crate::bvh::Bvh), which has to be rebuilt whenever its
+//! objects move, a [`SpatialGrid`] is cheap to update one object at a time.
+//! This makes it a good fit for indexing objects which move every tick, like
+//! entities.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use vek::{Aabb, Vec3};
+
+/// A spatial index of points identified by keys of type `K`. Points are
+/// grouped into cubic cells, so queries only need to look at the cells they
+/// overlap.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid<K> {
+    cell_size: f64,
+    cells: HashMap<Vec3<i64>, Vec<K>>,
+    points: HashMap<K, Vec3<f64>>,
+}
+
+impl<K: Copy + Eq + Hash> SpatialGrid<K> {
+    /// Creates an empty grid with cells `cell_size` wide. Queries are fastest
+    /// when the cell size is close to the typical query radius.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` is not positive and finite.
+    #[track_caller]
+    pub fn new(cell_size: f64) -> Self {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "invalid cell size of {cell_size}"
+        );
+
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            points: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns the position of `key`, if it's in the grid.
+    pub fn get(&self, key: K) -> Option<Vec3<f64>> {
+        self.points.get(&key).copied()
+    }
+
+    /// Inserts `key` at `pos`, or moves it there if it's already in the grid.
+    /// The previous position is returned.
+    pub fn insert(&mut self, key: K, pos: Vec3<f64>) -> Option<Vec3<f64>> {
+        let cell = self.cell_of(pos);
+        let old = self.points.insert(key, pos);
+
+        if let Some(old) = old {
+            let old_cell = self.cell_of(old);
+
+            if old_cell == cell {
+                return Some(old);
+            }
+
+            self.remove_from_cell(old_cell, key);
+        }
+
+        self.cells.entry(cell).or_default().push(key);
+
+        old
+    }
+
+    /// Removes `key` from the grid and returns its position.
+    pub fn remove(&mut self, key: K) -> Option<Vec3<f64>> {
+        let old = self.points.remove(&key)?;
+        self.remove_from_cell(self.cell_of(old), key);
+        Some(old)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.points.clear();
+    }
+
+    /// Returns an iterator over the keys and positions in the grid in an
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, Vec3<f64>)> + Clone + '_ {
+        self.points.iter().map(|(key, pos)| (*key, *pos))
+    }
+
+    /// Returns an iterator over the keys and positions of the points inside
+    /// `aabb` in an arbitrary order. Points on the boundary are included.
+    pub fn query_aabb(&self, aabb: Aabb<f64>) -> impl Iterator<Item = (K, Vec3<f64>)> + '_ {
+        let min = self.cell_of(aabb.min);
+        let max = self.cell_of(aabb.max);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)))
+            .flat_map(move |(x, y)| (min.z..=max.z).map(move |z| Vec3::new(x, y, z)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .map(move |key| (*key, self.points[key]))
+            .filter(move |(_, pos)| aabb.contains_point(*pos))
+    }
+
+    /// Returns an iterator over the keys and positions of the points within
+    /// `radius` of `center` in an arbitrary order.
+    pub fn query_sphere(
+        &self,
+        center: Vec3<f64>,
+        radius: f64,
+    ) -> impl Iterator<Item = (K, Vec3<f64>)> + '_ {
+        let aabb = Aabb {
+            min: center - Vec3::broadcast(radius),
+            max: center + Vec3::broadcast(radius),
+        };
+
+        self.query_aabb(aabb)
+            .filter(move |(_, pos)| pos.distance_squared(center) <= radius * radius)
+    }
+
+    /// Returns the keys, positions and distances of the `k` points closest to
+    /// `center`, from nearest to farthest. Fewer are returned if the grid has
+    /// less than `k` points.
+    pub fn nearest(&self, center: Vec3<f64>, k: usize) -> Vec<(K, Vec3<f64>, f64)> {
+        let mut found: Vec<_> = vec![];
+
+        if k == 0 || self.is_empty() {
+            return found;
+        }
+
+        let center_cell = self.cell_of(center);
+        let mut cells_visited = 0;
+
+        // Search the cells in cubic shells of growing radius around the center.
+        // Every point in a shell is at least `(radius - 1) * cell_size` away, so
+        // the search can stop once `k` points closer than that are found.
+        for radius in 0_i64.. {
+            if found.len() >= k && found[k - 1].2 <= (radius - 1) as f64 * self.cell_size {
+                break;
+            }
+
+            let shell_cells = if radius == 0 {
+                1
+            } else {
+                (2 * radius + 1).pow(3) - (2 * radius - 1).pow(3)
+            };
+
+            if cells_visited + shell_cells as usize > self.cells.len() {
+                // Looking at every point is cheaper than visiting this many
+                // cells, which are mostly empty.
+                found = self
+                    .iter()
+                    .map(|(key, pos)| (key, pos, pos.distance(center)))
+                    .collect();
+
+                found.sort_unstable_by(|a, b| a.2.total_cmp(&b.2));
+                break;
+            }
+
+            cells_visited += shell_cells as usize;
+
+            for cell in shell(center_cell, radius) {
+                if let Some(keys) = self.cells.get(&cell) {
+                    found.extend(keys.iter().map(|key| {
+                        let pos = self.points[key];
+                        (*key, pos, pos.distance(center))
+                    }));
+                }
+            }
+
+            found.sort_unstable_by(|a, b| a.2.total_cmp(&b.2));
+        }
+
+        found.truncate(k);
+        found
+    }
+
+    fn cell_of(&self, pos: Vec3<f64>) -> Vec3<i64> {
+        pos.map(|c| (c / self.cell_size).floor() as i64)
+    }
+
+    fn remove_from_cell(&mut self, cell: Vec3<i64>, key: K) {
+        if let Some(keys) = self.cells.get_mut(&cell) {
+            if let Some(idx) = keys.iter().position(|k| *k == key) {
+                keys.swap_remove(idx);
+            }
+
+            if keys.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+}
+
+/// Returns the cells exactly `radius` cells away from `center` along at least
+/// one axis.
+fn shell(center: Vec3<i64>, radius: i64) -> impl Iterator<Item = Vec3<i64>> {
+    (-radius..=radius).flat_map(move |x| {
+        (-radius..=radius).flat_map(move |y| {
+            (-radius..=radius).filter_map(move |z| {
+                let on_shell = x.abs() == radius || y.abs() == radius || z.abs() == radius;
+                on_shell.then_some(center + Vec3::new(x, y, z))
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> SpatialGrid<u32> {
+        let mut grid = SpatialGrid::new(4.0);
+
+        for i in 0..100 {
+            let f = f64::from(i);
+            grid.insert(i, Vec3::new(f * 1.5 - 70.0, f.sin() * 10.0, f * -0.75));
+        }
+
+        grid
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let mut grid = grid();
+
+        // Move some points around and remove others.
+        for i in 0..20 {
+            grid.insert(i, Vec3::new(f64::from(i), 0.0, 0.0));
+        }
+        for i in 80..90 {
+            assert!(grid.remove(i).is_some());
+        }
+        assert_eq!(grid.len(), 90);
+
+        let center = Vec3::new(5.0, 1.0, -3.0);
+
+        let mut in_sphere: Vec<_> = grid.query_sphere(center, 12.0).map(|(k, _)| k).collect();
+        let mut expected: Vec<_> = grid
+            .iter()
+            .filter(|(_, pos)| pos.distance(center) <= 12.0)
+            .map(|(k, _)| k)
+            .collect();
+
+        in_sphere.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(in_sphere, expected);
+
+        let nearest: Vec<_> = grid.nearest(center, 10).iter().map(|n| n.2).collect();
+        let mut expected: Vec<_> = grid.iter().map(|(_, pos)| pos.distance(center)).collect();
+        expected.sort_unstable_by(f64::total_cmp);
+        expected.truncate(10);
+
+        assert_eq!(nearest, expected);
+        assert_eq!(grid.nearest(center, 1000).len(), 90);
+    }
+
+    #[test]
+    fn moving_within_a_cell() {
+        let mut grid = SpatialGrid::new(16.0);
+
+        grid.insert("a", Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            grid.insert("a", Vec3::new(2.0, 2.0, 2.0)),
+            Some(Vec3::new(1.0, 1.0, 1.0))
+        );
+
+        let aabb = Aabb {
+            min: Vec3::zero(),
+            max: Vec3::new(1.5, 1.5, 1.5),
+        };
+
+        assert_eq!(grid.query_aabb(aabb).count(), 0);
+        assert_eq!(grid.remove("a"), Some(Vec3::new(2.0, 2.0, 2.0)));
+        assert!(grid.is_empty());
+    }
+}
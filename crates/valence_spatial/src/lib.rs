@@ -3,6 +3,7 @@
 use vek::{Aabb, Vec3};
 
 pub mod bvh;
+pub mod grid;
 
 pub trait SpatialIndex<N = f64> {
     type Object: Bounded3D<N>;
@@ -0,0 +1,123 @@
+//! The statistics in the `minecraft:custom` category.
+
+macro_rules! custom_stats {
+    ($($variant:ident => $name:literal,)*) => {
+        /// A statistic in the `minecraft:custom` category. The variants are in
+        /// the order of their protocol IDs.
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum CustomStat {
+            $($variant,)*
+        }
+
+        impl CustomStat {
+            /// All custom statistics, ordered by protocol ID.
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// Returns the protocol ID of this statistic.
+            pub const fn id(self) -> i32 {
+                self as i32
+            }
+
+            /// Returns the custom statistic with the given protocol ID.
+            pub fn from_id(id: i32) -> Option<Self> {
+                usize::try_from(id).ok().and_then(|id| Self::ALL.get(id).copied())
+            }
+
+            /// Returns the name of this statistic without the `minecraft:`
+            /// namespace.
+            pub const fn to_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            /// Returns the custom statistic with the given name without the
+            /// `minecraft:` namespace.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+custom_stats! {
+    LeaveGame => "leave_game",
+    PlayTime => "play_time",
+    TotalWorldTime => "total_world_time",
+    TimeSinceDeath => "time_since_death",
+    TimeSinceRest => "time_since_rest",
+    SneakTime => "sneak_time",
+    WalkOneCm => "walk_one_cm",
+    CrouchOneCm => "crouch_one_cm",
+    SprintOneCm => "sprint_one_cm",
+    WalkOnWaterOneCm => "walk_on_water_one_cm",
+    FallOneCm => "fall_one_cm",
+    ClimbOneCm => "climb_one_cm",
+    FlyOneCm => "fly_one_cm",
+    WalkUnderWaterOneCm => "walk_under_water_one_cm",
+    MinecartOneCm => "minecart_one_cm",
+    BoatOneCm => "boat_one_cm",
+    PigOneCm => "pig_one_cm",
+    HorseOneCm => "horse_one_cm",
+    AviateOneCm => "aviate_one_cm",
+    SwimOneCm => "swim_one_cm",
+    StriderOneCm => "strider_one_cm",
+    Jump => "jump",
+    Drop => "drop",
+    DamageDealt => "damage_dealt",
+    DamageDealtAbsorbed => "damage_dealt_absorbed",
+    DamageDealtResisted => "damage_dealt_resisted",
+    DamageTaken => "damage_taken",
+    DamageBlockedByShield => "damage_blocked_by_shield",
+    DamageAbsorbed => "damage_absorbed",
+    DamageResisted => "damage_resisted",
+    Deaths => "deaths",
+    MobKills => "mob_kills",
+    AnimalsBred => "animals_bred",
+    PlayerKills => "player_kills",
+    FishCaught => "fish_caught",
+    TalkedToVillager => "talked_to_villager",
+    TradedWithVillager => "traded_with_villager",
+    EatCakeSlice => "eat_cake_slice",
+    FillCauldron => "fill_cauldron",
+    UseCauldron => "use_cauldron",
+    CleanArmor => "clean_armor",
+    CleanBanner => "clean_banner",
+    CleanShulkerBox => "clean_shulker_box",
+    InteractWithBrewingstand => "interact_with_brewingstand",
+    InteractWithBeacon => "interact_with_beacon",
+    InspectDropper => "inspect_dropper",
+    InspectHopper => "inspect_hopper",
+    InspectDispenser => "inspect_dispenser",
+    PlayNoteblock => "play_noteblock",
+    TuneNoteblock => "tune_noteblock",
+    PotFlower => "pot_flower",
+    TriggerTrappedChest => "trigger_trapped_chest",
+    OpenEnderchest => "open_enderchest",
+    EnchantItem => "enchant_item",
+    PlayRecord => "play_record",
+    InteractWithFurnace => "interact_with_furnace",
+    InteractWithCraftingTable => "interact_with_crafting_table",
+    OpenChest => "open_chest",
+    SleepInBed => "sleep_in_bed",
+    OpenShulkerBox => "open_shulker_box",
+    OpenBarrel => "open_barrel",
+    InteractWithBlastFurnace => "interact_with_blast_furnace",
+    InteractWithSmoker => "interact_with_smoker",
+    InteractWithLectern => "interact_with_lectern",
+    InteractWithCampfire => "interact_with_campfire",
+    InteractWithCartographyTable => "interact_with_cartography_table",
+    InteractWithLoom => "interact_with_loom",
+    InteractWithStonecutter => "interact_with_stonecutter",
+    BellRing => "bell_ring",
+    RaidTrigger => "raid_trigger",
+    RaidWin => "raid_win",
+    InteractWithAnvil => "interact_with_anvil",
+    InteractWithGrindstone => "interact_with_grindstone",
+    TargetHit => "target_hit",
+    InteractWithSmithingTable => "interact_with_smithing_table",
+}
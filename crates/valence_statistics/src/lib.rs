@@ -0,0 +1,461 @@
+#![doc = include_str!("../README.md")]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+pub use custom::CustomStat;
+use valence_server::abilities::PlayerAbilitiesFlags;
+use valence_server::block::BlockKind;
+use valence_server::client::{Client, SpawnClientsSet, VisibleChunkLayer};
+use valence_server::death::{Dead, PlayerDeathEvent};
+use valence_server::entity::entity::Flags;
+use valence_server::entity::EntityKind;
+use valence_server::event_loop::{EventLoopPreUpdate, EventLoopUpdate, PacketEvent};
+use valence_server::movement::MovementEvent;
+use valence_server::nbt::{Compound, Value};
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::statistics_s2c::Statistic;
+use valence_server::protocol::packets::play::{PlayerActionC2s, StatisticsS2c};
+use valence_server::protocol::{VarInt, WritePacket};
+use valence_server::sleep::{Sleeping, StartSleepingEvent};
+use valence_server::status::RequestStatsEvent;
+use valence_server::{ChunkLayer, GameMode, ItemKind};
+
+pub mod custom;
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        // The events are only sent if `DeathPlugin` and `SleepPlugin` are added.
+        app.init_resource::<StatisticsSettings>()
+            .add_event::<PlayerDeathEvent>()
+            .add_event::<StartSleepingEvent>()
+            .add_systems(PreUpdate, init_statistics.after(SpawnClientsSet))
+            .add_systems(
+                EventLoopPreUpdate,
+                track_blocks_mined.run_if(tracking_enabled),
+            )
+            .add_systems(
+                EventLoopUpdate,
+                (
+                    send_statistics,
+                    (track_movement, track_sleeping).run_if(tracking_enabled),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (track_deaths, track_time).run_if(tracking_enabled),
+            );
+    }
+}
+
+/// Global configuration for statistics.
+#[derive(Resource, Clone, Debug)]
+pub struct StatisticsSettings {
+    /// Whether the built-in systems that update vanilla statistics run. Turn
+    /// this off to update [`PlayerStatistics`] entirely yourself.
+    pub track_vanilla: bool,
+}
+
+impl Default for StatisticsSettings {
+    fn default() -> Self {
+        Self {
+            track_vanilla: true,
+        }
+    }
+}
+
+/// Identifies a single statistic.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum StatKey {
+    /// The number of times a block was mined.
+    Mined(BlockKind),
+    /// The number of times an item was crafted.
+    Crafted(ItemKind),
+    /// The number of times an item was used.
+    Used(ItemKind),
+    /// The number of times an item broke from running out of durability.
+    Broken(ItemKind),
+    /// The number of times an item was picked up.
+    PickedUp(ItemKind),
+    /// The number of times an item was dropped.
+    Dropped(ItemKind),
+    /// The number of times an entity was killed.
+    Killed(EntityKind),
+    /// The number of times the player was killed by an entity.
+    KilledBy(EntityKind),
+    /// One of the general statistics.
+    Custom(CustomStat),
+}
+
+impl StatKey {
+    /// Returns the protocol ID of the category of this statistic.
+    pub const fn category_id(self) -> i32 {
+        match self {
+            StatKey::Mined(_) => 0,
+            StatKey::Crafted(_) => 1,
+            StatKey::Used(_) => 2,
+            StatKey::Broken(_) => 3,
+            StatKey::PickedUp(_) => 4,
+            StatKey::Dropped(_) => 5,
+            StatKey::Killed(_) => 6,
+            StatKey::KilledBy(_) => 7,
+            StatKey::Custom(_) => 8,
+        }
+    }
+
+    /// Returns the name of the category of this statistic.
+    pub const fn category_name(self) -> &'static str {
+        match self {
+            StatKey::Mined(_) => "minecraft:mined",
+            StatKey::Crafted(_) => "minecraft:crafted",
+            StatKey::Used(_) => "minecraft:used",
+            StatKey::Broken(_) => "minecraft:broken",
+            StatKey::PickedUp(_) => "minecraft:picked_up",
+            StatKey::Dropped(_) => "minecraft:dropped",
+            StatKey::Killed(_) => "minecraft:killed",
+            StatKey::KilledBy(_) => "minecraft:killed_by",
+            StatKey::Custom(_) => "minecraft:custom",
+        }
+    }
+
+    /// Returns the protocol ID of this statistic within its category.
+    pub fn statistic_id(self) -> i32 {
+        match self {
+            StatKey::Mined(block) => i32::from(block.to_raw()),
+            StatKey::Crafted(item)
+            | StatKey::Used(item)
+            | StatKey::Broken(item)
+            | StatKey::PickedUp(item)
+            | StatKey::Dropped(item) => i32::from(item.to_raw()),
+            StatKey::Killed(entity) | StatKey::KilledBy(entity) => entity.get(),
+            StatKey::Custom(stat) => stat.id(),
+        }
+    }
+
+    /// Returns the name of this statistic within its category, without the
+    /// `minecraft:` namespace.
+    pub fn statistic_name(self) -> Option<&'static str> {
+        match self {
+            StatKey::Mined(block) => Some(block.to_str()),
+            StatKey::Crafted(item)
+            | StatKey::Used(item)
+            | StatKey::Broken(item)
+            | StatKey::PickedUp(item)
+            | StatKey::Dropped(item) => Some(item.to_str()),
+            StatKey::Killed(entity) | StatKey::KilledBy(entity) => entity_name(entity),
+            StatKey::Custom(stat) => Some(stat.to_str()),
+        }
+    }
+
+    /// Parses a statistic from its category name and statistic name. Both
+    /// names may be with or without the `minecraft:` namespace.
+    pub fn from_names(category: &str, name: &str) -> Option<Self> {
+        let category = category.strip_prefix("minecraft:").unwrap_or(category);
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        Some(match category {
+            "mined" => StatKey::Mined(BlockKind::from_str(name)?),
+            "crafted" => StatKey::Crafted(ItemKind::from_str(name)?),
+            "used" => StatKey::Used(ItemKind::from_str(name)?),
+            "broken" => StatKey::Broken(ItemKind::from_str(name)?),
+            "picked_up" => StatKey::PickedUp(ItemKind::from_str(name)?),
+            "dropped" => StatKey::Dropped(ItemKind::from_str(name)?),
+            "killed" => StatKey::Killed(entity_kind_from_str(name)?),
+            "killed_by" => StatKey::KilledBy(entity_kind_from_str(name)?),
+            "custom" => StatKey::Custom(CustomStat::from_str(name)?),
+            _ => return None,
+        })
+    }
+}
+
+impl From<CustomStat> for StatKey {
+    fn from(stat: CustomStat) -> Self {
+        StatKey::Custom(stat)
+    }
+}
+
+fn entity_name(kind: EntityKind) -> Option<&'static str> {
+    kind.translation_key()?.strip_prefix("entity.minecraft.")
+}
+
+fn entity_kind_from_str(name: &str) -> Option<EntityKind> {
+    // There are only a few hundred entity kinds, so a linear search is fine.
+    (0..512)
+        .map(EntityKind::new)
+        .find(|&kind| entity_name(kind) == Some(name))
+}
+
+/// The statistics of a player.
+///
+/// Statistics that change are sent to the client the next time it opens the
+/// statistics menu. Statistics that aren't present are zero.
+#[derive(Component, Clone, Default, Debug)]
+pub struct PlayerStatistics {
+    stats: BTreeMap<StatKey, i32>,
+    /// Statistics that changed since they were last sent to the client.
+    pending: BTreeSet<StatKey>,
+}
+
+impl PlayerStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of a statistic.
+    pub fn get<K: Into<StatKey>>(&self, key: K) -> i32 {
+        self.stats.get(&key.into()).copied().unwrap_or(0)
+    }
+
+    /// Sets the value of a statistic.
+    pub fn set<K: Into<StatKey>>(&mut self, key: K, value: i32) {
+        let key = key.into();
+
+        let old = if value == 0 {
+            self.stats.remove(&key)
+        } else {
+            self.stats.insert(key, value)
+        };
+
+        if old.unwrap_or(0) != value {
+            self.pending.insert(key);
+        }
+    }
+
+    /// Adds `amount` to the value of a statistic.
+    pub fn increment<K: Into<StatKey>>(&mut self, key: K, amount: i32) {
+        let key = key.into();
+        self.set(key, self.get(key).saturating_add(amount));
+    }
+
+    /// Returns an iterator over all nonzero statistics.
+    pub fn iter(&self) -> impl Iterator<Item = (StatKey, i32)> + '_ {
+        self.stats.iter().map(|(&key, &value)| (key, value))
+    }
+
+    /// Converts the statistics to NBT in the layout of the vanilla statistics
+    /// file.
+    pub fn to_compound(&self) -> Compound {
+        let mut categories = Compound::new();
+
+        for (key, value) in self.iter() {
+            let Some(name) = key.statistic_name() else {
+                continue;
+            };
+
+            let category = categories
+                .entry(key.category_name())
+                .or_insert_with(|| Value::Compound(Compound::new()));
+
+            if let Value::Compound(category) = category {
+                category.insert(format!("minecraft:{name}"), value);
+            }
+        }
+
+        let mut root = Compound::new();
+        root.insert("stats", categories);
+        root
+    }
+
+    /// Reads statistics written by [`PlayerStatistics::to_compound`]. Unknown
+    /// statistics are skipped. All statistics are sent to the client the next
+    /// time it opens the statistics menu.
+    pub fn from_compound(compound: &Compound) -> Self {
+        let mut stats = Self::new();
+
+        let Some(Value::Compound(categories)) = compound.get("stats") else {
+            return stats;
+        };
+
+        for (category_name, category) in categories {
+            let Value::Compound(category) = category else {
+                continue;
+            };
+
+            for (name, value) in category {
+                if let (Some(key), Value::Int(value)) =
+                    (StatKey::from_names(category_name, name), value)
+                {
+                    stats.set(key, *value);
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+fn tracking_enabled(settings: Res<StatisticsSettings>) -> bool {
+    settings.track_vanilla
+}
+
+fn init_statistics(
+    clients: Query<Entity, (Added<Client>, Without<PlayerStatistics>)>,
+    mut commands: Commands,
+) {
+    for entity in &clients {
+        commands.entity(entity).insert(PlayerStatistics::new());
+    }
+}
+
+fn send_statistics(
+    mut events: EventReader<RequestStatsEvent>,
+    mut clients: Query<(&mut Client, &mut PlayerStatistics)>,
+) {
+    for event in events.read() {
+        let Ok((mut client, mut stats)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let pending = std::mem::take(&mut stats.pending);
+
+        // The client waits for a response before showing the menu, so this is sent
+        // even if nothing changed.
+        client.write_packet(&StatisticsS2c {
+            statistics: pending
+                .into_iter()
+                .map(|key| Statistic {
+                    category_id: VarInt(key.category_id()),
+                    statistic_id: VarInt(key.statistic_id()),
+                    value: VarInt(stats.get(key)),
+                })
+                .collect(),
+        });
+    }
+}
+
+/// Reads the block before it is broken by user systems in
+/// [`EventLoopUpdate`].
+fn track_blocks_mined(
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<(&mut PlayerStatistics, &GameMode, &VisibleChunkLayer)>,
+    layers: Query<&ChunkLayer>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<PlayerActionC2s>() else {
+            continue;
+        };
+
+        if pkt.action != PlayerAction::StopDestroyBlock {
+            continue;
+        }
+
+        let Ok((mut stats, game_mode, layer)) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        if matches!(game_mode, GameMode::Creative | GameMode::Spectator) {
+            continue;
+        }
+
+        let Some(block) = layers
+            .get(layer.0)
+            .ok()
+            .and_then(|layer| layer.block(pkt.position))
+        else {
+            continue;
+        };
+
+        if !block.state.is_air() {
+            stats.increment(StatKey::Mined(block.state.to_kind()), 1);
+        }
+    }
+}
+
+fn track_movement(
+    mut events: EventReader<MovementEvent>,
+    mut clients: Query<(
+        &mut PlayerStatistics,
+        &Flags,
+        &PlayerAbilitiesFlags,
+        &GameMode,
+    )>,
+) {
+    for event in events.read() {
+        let Ok((mut stats, flags, abilities, game_mode)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        let delta = event.position - event.old_position;
+        let horizontal_cm = (delta.x.hypot(delta.z) * 100.0).round() as i32;
+        let total_cm = (delta.length() * 100.0).round() as i32;
+
+        if flags.swimming() {
+            stats.increment(CustomStat::SwimOneCm, total_cm);
+        } else if event.on_ground {
+            let stat = if flags.sneaking() {
+                CustomStat::CrouchOneCm
+            } else if flags.sprinting() {
+                CustomStat::SprintOneCm
+            } else {
+                CustomStat::WalkOneCm
+            };
+
+            stats.increment(stat, horizontal_cm);
+        } else if flags.fall_flying() {
+            stats.increment(CustomStat::AviateOneCm, total_cm);
+        } else {
+            // Like vanilla, tiny movements in the air are ignored.
+            if horizontal_cm > 25 {
+                stats.increment(CustomStat::FlyOneCm, horizontal_cm);
+            }
+
+            if delta.y < 0.0 && !abilities.flying() {
+                stats.increment(CustomStat::FallOneCm, (-delta.y * 100.0).round() as i32);
+            }
+        }
+
+        if event.old_on_ground && !event.on_ground && delta.y > 0.0 {
+            stats.increment(CustomStat::Jump, 1);
+        }
+    }
+}
+
+fn track_sleeping(
+    mut events: EventReader<StartSleepingEvent>,
+    mut clients: Query<&mut PlayerStatistics>,
+) {
+    for event in events.read() {
+        if let Ok(mut stats) = clients.get_mut(event.client) {
+            stats.increment(CustomStat::SleepInBed, 1);
+            stats.set(CustomStat::TimeSinceRest, 0);
+        }
+    }
+}
+
+fn track_deaths(
+    mut events: EventReader<PlayerDeathEvent>,
+    mut clients: Query<&mut PlayerStatistics>,
+) {
+    for event in events.read() {
+        if let Ok(mut stats) = clients.get_mut(event.client) {
+            stats.increment(CustomStat::Deaths, 1);
+            stats.set(CustomStat::TimeSinceDeath, 0);
+        }
+    }
+}
+
+fn track_time(mut clients: Query<(&mut PlayerStatistics, &Flags, Has<Dead>, Has<Sleeping>)>) {
+    for (mut stats, flags, dead, sleeping) in &mut clients {
+        stats.increment(CustomStat::PlayTime, 1);
+        stats.increment(CustomStat::TotalWorldTime, 1);
+
+        if !dead {
+            stats.increment(CustomStat::TimeSinceDeath, 1);
+        }
+
+        if !sleeping {
+            stats.increment(CustomStat::TimeSinceRest, 1);
+        }
+
+        if flags.sneaking() {
+            stats.increment(CustomStat::SneakTime, 1);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! `/gamemode <gamemode> [<target>]`.
+
+use bevy_ecs::prelude::*;
+use valence::client::Client;
+use valence::command::handler::CommandResultEvent;
+use valence::command::parsers::EntitySelector;
+use valence::command_macros::Command;
+use valence::message::SendMessage;
+use valence::GameMode;
+
+use crate::selector::TargetSelector;
+
+#[derive(Command, Clone, Debug)]
+#[paths("gamemode {mode} {target?}")]
+#[scopes("valence.command.gamemode")]
+pub struct GameModeCommand {
+    pub mode: GameMode,
+    pub target: Option<EntitySelector>,
+}
+
+pub(crate) fn handle_gamemode_command(
+    mut events: EventReader<CommandResultEvent<GameModeCommand>>,
+    selector: TargetSelector,
+    mut game_modes: Query<&mut GameMode, With<Client>>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let mode = event.result.mode;
+
+        let targets = match &event.result.target {
+            Some(target) => selector.select(executor, target).map(|targets| {
+                targets
+                    .into_iter()
+                    .filter(|&target| game_modes.contains(target))
+                    .collect::<Vec<_>>()
+            }),
+            None => Ok(vec![executor]),
+        };
+
+        let message = match targets {
+            Ok(targets) if targets.is_empty() => "No player was found".to_owned(),
+            Ok(targets) => {
+                for &target in &targets {
+                    if let Ok(mut game_mode) = game_modes.get_mut(target) {
+                        game_mode.set_if_neq(mode);
+                    }
+                }
+
+                if targets == [executor] {
+                    format!("Set own game mode to {mode:?} Mode")
+                } else {
+                    format!(
+                        "Set {}'s game mode to {mode:?} Mode",
+                        selector.describe(&targets)
+                    )
+                }
+            }
+            Err(e) => e.to_owned(),
+        };
+
+        if let Ok(mut client) = clients.get_mut(executor) {
+            client.send_chat_message(message);
+        }
+    }
+}
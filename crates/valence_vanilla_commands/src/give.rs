@@ -0,0 +1,135 @@
+//! `/give <targets> <item> [<count>]`.
+
+use std::ops::Range;
+
+use bevy_ecs::prelude::*;
+use valence::client::Client;
+use valence::command::handler::CommandResultEvent;
+use valence::command::parsers::{CommandArg, CommandArgParseError, EntitySelector, ParseInput};
+use valence::command_macros::Command;
+use valence::inventory::Inventory;
+use valence::message::SendMessage;
+use valence::protocol::packets::play::command_tree_s2c::Parser;
+use valence::{ItemKind, ItemStack};
+
+use crate::selector::TargetSelector;
+
+/// The slots of the player inventory which are filled first, like in vanilla.
+const HOTBAR_SLOTS: Range<u16> = 36..45;
+const MAIN_SLOTS: Range<u16> = 9..36;
+
+#[derive(Command, Clone, Debug)]
+#[paths("give {targets} {item} {count?}")]
+#[scopes("valence.command.give")]
+pub struct GiveCommand {
+    pub targets: EntitySelector,
+    pub item: ItemArg,
+    pub count: Option<i32>,
+}
+
+/// An item name like `diamond` or `minecraft:diamond`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemArg(pub ItemKind);
+
+impl CommandArg for ItemArg {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+
+        let word = input.pop_word();
+        let name = word.strip_prefix("minecraft:").unwrap_or(word);
+
+        match ItemKind::from_str(name) {
+            Some(ItemKind::Air) | None => Err(CommandArgParseError::InvalidArgument {
+                expected: "item".to_owned(),
+                got: word.to_owned(),
+            }),
+            Some(kind) => Ok(Self(kind)),
+        }
+    }
+
+    fn display() -> Parser {
+        Parser::ItemStack
+    }
+}
+
+pub(crate) fn handle_give_command(
+    mut events: EventReader<CommandResultEvent<GiveCommand>>,
+    selector: TargetSelector,
+    mut inventories: Query<&mut Inventory, With<Client>>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let GiveCommand {
+            targets,
+            item: ItemArg(item),
+            count,
+        } = &event.result;
+
+        let count = count.unwrap_or(1);
+
+        let message = if count < 1 {
+            format!("Count must be at least 1, found {count}")
+        } else {
+            match selector.select(executor, targets) {
+                Ok(targets) => {
+                    // Only players have inventories to give items to.
+                    let targets: Vec<_> = targets
+                        .into_iter()
+                        .filter(|&target| inventories.contains(target))
+                        .collect();
+
+                    if targets.is_empty() {
+                        "No player was found".to_owned()
+                    } else {
+                        for &target in &targets {
+                            let mut inventory = inventories.get_mut(target).unwrap();
+                            give(&mut inventory, *item, count);
+                        }
+
+                        format!(
+                            "Gave {count} [{}] to {}",
+                            item.to_str(),
+                            selector.describe(&targets)
+                        )
+                    }
+                }
+                Err(e) => e.to_owned(),
+            }
+        };
+
+        if let Ok(mut client) = clients.get_mut(executor) {
+            client.send_chat_message(message);
+        }
+    }
+}
+
+/// Adds `count` of `item` to a player inventory, topping up existing stacks
+/// before using empty slots. Items which don't fit are discarded.
+fn give(inventory: &mut Inventory, item: ItemKind, count: i32) {
+    let max_stack = item.max_stack();
+    let mut remaining = count;
+
+    while remaining > 0 {
+        let slot = [HOTBAR_SLOTS, MAIN_SLOTS]
+            .into_iter()
+            .find_map(|range| inventory.first_slot_with_item_in(item, max_stack, range))
+            .or_else(|| {
+                [HOTBAR_SLOTS, MAIN_SLOTS]
+                    .into_iter()
+                    .find_map(|range| inventory.first_empty_slot_in(range))
+            });
+
+        let Some(slot) = slot else {
+            break;
+        };
+
+        let current = inventory.slot(slot);
+        let current = if current.is_empty() { 0 } else { current.count };
+        let added = remaining.min(i32::from(max_stack - current));
+
+        inventory.set_slot(slot, ItemStack::new(item, current + added as i8, None));
+
+        remaining -= added;
+    }
+}
@@ -0,0 +1,39 @@
+#![doc = include_str!("../README.md")]
+
+pub mod gamemode;
+pub mod give;
+pub mod selector;
+pub mod teleport;
+#[cfg(test)]
+mod tests;
+pub mod time;
+
+use bevy_app::prelude::*;
+use valence::command::AddCommand;
+
+use crate::gamemode::GameModeCommand;
+use crate::give::GiveCommand;
+use crate::teleport::TeleportCommand;
+use crate::time::TimeCommand;
+
+/// Adds all the commands in this crate. Requires the `CommandPlugin` from the
+/// default plugins.
+pub struct VanillaCommandsPlugin;
+
+impl Plugin for VanillaCommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_command::<TeleportCommand>()
+            .add_command::<GiveCommand>()
+            .add_command::<GameModeCommand>()
+            .add_command::<TimeCommand>()
+            .add_systems(
+                Update,
+                (
+                    teleport::handle_teleport_command,
+                    give::handle_give_command,
+                    gamemode::handle_gamemode_command,
+                    time::handle_time_command,
+                ),
+            );
+    }
+}
@@ -0,0 +1,124 @@
+//! Finding the entities an [`EntitySelector`] refers to.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use valence::client::{Client, Username};
+use valence::command::parsers::entity_selector::EntitySelectors;
+use valence::command::parsers::EntitySelector;
+use valence::entity::{EntityLayerId, Position};
+use valence::math::DVec3;
+use valence::rand::seq::IteratorRandom;
+use valence::Despawned;
+
+/// A [`SystemParam`] for resolving the targets of commands.
+///
+/// Selectors with arguments in brackets, like `@e[type=cow]`, aren't supported
+/// and resolve to an error.
+#[derive(SystemParam)]
+pub struct TargetSelector<'w, 's> {
+    #[allow(clippy::type_complexity)]
+    entities: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Position,
+            &'static EntityLayerId,
+            Has<Client>,
+            Option<&'static Username>,
+        ),
+        Without<Despawned>,
+    >,
+}
+
+impl TargetSelector<'_, '_> {
+    /// Returns the entities `selector` refers to when used by `executor`, or a
+    /// message for the executor if there are none.
+    ///
+    /// `@e` and `@p` only consider entities in the executor's entity layer,
+    /// while `@a`, `@r` and player names consider all players.
+    pub fn select(
+        &self,
+        executor: Entity,
+        selector: &EntitySelector,
+    ) -> Result<Vec<Entity>, &'static str> {
+        let selector = match selector {
+            EntitySelector::SimpleSelector(selector) => selector,
+            EntitySelector::ComplexSelector(..) => {
+                return Err("Selector arguments are not supported");
+            }
+        };
+
+        let executor_layer = self.entities.get(executor).ok().map(|(_, _, l, ..)| l.0);
+        let players = || self.entities.iter().filter(|(.., is_client, _)| *is_client);
+
+        let targets: Vec<_> = match selector {
+            EntitySelectors::AllEntities => self
+                .entities
+                .iter()
+                .filter(|(_, _, layer, ..)| Some(layer.0) == executor_layer)
+                .map(|(entity, ..)| entity)
+                .collect(),
+            EntitySelectors::SinglePlayer(name) => players()
+                .filter(|(.., username)| username.is_some_and(|u| u.0 == *name))
+                .map(|(entity, ..)| entity)
+                .collect(),
+            EntitySelectors::AllPlayers => players().map(|(entity, ..)| entity).collect(),
+            EntitySelectors::SelfPlayer => self
+                .entities
+                .get(executor)
+                .map(|(entity, ..)| entity)
+                .into_iter()
+                .collect(),
+            EntitySelectors::NearestPlayer => {
+                let Ok((_, origin, ..)) = self.entities.get(executor) else {
+                    return Err("No player was found");
+                };
+
+                players()
+                    .filter(|(_, _, layer, ..)| Some(layer.0) == executor_layer)
+                    .min_by(|(_, a, ..), (_, b, ..)| {
+                        a.0.distance_squared(origin.0)
+                            .total_cmp(&b.0.distance_squared(origin.0))
+                    })
+                    .map(|(entity, ..)| entity)
+                    .into_iter()
+                    .collect()
+            }
+            EntitySelectors::RandomPlayer => players()
+                .choose(&mut valence::rand::thread_rng())
+                .map(|(entity, ..)| entity)
+                .into_iter()
+                .collect(),
+        };
+
+        if targets.is_empty() {
+            return Err(match selector {
+                EntitySelectors::AllEntities | EntitySelectors::SelfPlayer => "No entity was found",
+                _ => "No player was found",
+            });
+        }
+
+        Ok(targets)
+    }
+
+    /// Returns the position of `entity`, if it hasn't despawned.
+    pub fn position(&self, entity: Entity) -> Option<DVec3> {
+        self.entities.get(entity).ok().map(|(_, pos, ..)| pos.0)
+    }
+
+    /// Returns how feedback messages refer to `targets`: the name of a single
+    /// player, or the number of targets otherwise.
+    pub fn describe(&self, targets: &[Entity]) -> String {
+        if let [target] = targets {
+            if let Ok((.., Some(username))) = self.entities.get(*target) {
+                return username.0.clone();
+            }
+        }
+
+        match targets.len() {
+            1 => "1 entity".to_owned(),
+            n => format!("{n} entities"),
+        }
+    }
+}
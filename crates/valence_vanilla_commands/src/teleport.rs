@@ -0,0 +1,122 @@
+//! `/teleport <destination>`, `/teleport <targets> <destination>` and their
+//! alias `/tp`.
+
+use bevy_ecs::prelude::*;
+use valence::client::Client;
+use valence::command::handler::CommandResultEvent;
+use valence::command::parsers::{AbsoluteOrRelative, EntitySelector, Vec3};
+use valence::command_macros::Command;
+use valence::entity::Position;
+use valence::math::DVec3;
+use valence::message::SendMessage;
+
+use crate::selector::TargetSelector;
+
+#[derive(Command, Clone, Debug)]
+#[paths("teleport", "tp")]
+#[scopes("valence.command.teleport")]
+pub enum TeleportCommand {
+    #[paths = "{location}"]
+    ToLocation { location: Vec3 },
+    #[paths = "{destination}"]
+    ToEntity { destination: EntitySelector },
+    #[paths = "{targets} {location}"]
+    TargetsToLocation {
+        targets: EntitySelector,
+        location: Vec3,
+    },
+    #[paths = "{targets} {destination}"]
+    TargetsToEntity {
+        targets: EntitySelector,
+        destination: EntitySelector,
+    },
+}
+
+pub(crate) fn handle_teleport_command(
+    mut events: EventReader<CommandResultEvent<TeleportCommand>>,
+    // The selector reads the positions of entities.
+    mut params: ParamSet<(TargetSelector, Query<&mut Position>)>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let selector = params.p0();
+
+        // Relative coordinates are relative to the executor, like in vanilla.
+        let origin = selector.position(executor).unwrap_or_default();
+
+        let result = match &event.result {
+            TeleportCommand::ToLocation { location } => {
+                Ok((vec![executor], resolve(location, origin)))
+            }
+            TeleportCommand::ToEntity { destination } => {
+                destination_of(&selector, executor, destination)
+                    .map(|destination| (vec![executor], destination))
+            }
+            TeleportCommand::TargetsToLocation { targets, location } => selector
+                .select(executor, targets)
+                .map(|targets| (targets, resolve(location, origin))),
+            TeleportCommand::TargetsToEntity {
+                targets,
+                destination,
+            } => selector.select(executor, targets).and_then(|targets| {
+                destination_of(&selector, executor, destination)
+                    .map(|destination| (targets, destination))
+            }),
+        };
+
+        let message = match result {
+            Ok((targets, destination)) => {
+                let message = format!(
+                    "Teleported {} to {:.1}, {:.1}, {:.1}",
+                    selector.describe(&targets),
+                    destination.x,
+                    destination.y,
+                    destination.z
+                );
+
+                let mut positions = params.p1();
+
+                for target in targets {
+                    if let Ok(mut pos) = positions.get_mut(target) {
+                        pos.0 = destination;
+                    }
+                }
+
+                message
+            }
+            Err(e) => e.to_owned(),
+        };
+
+        if let Ok(mut client) = clients.get_mut(executor) {
+            client.send_chat_message(message);
+        }
+    }
+}
+
+fn resolve(location: &Vec3, origin: DVec3) -> DVec3 {
+    fn coord(c: AbsoluteOrRelative<f32>, origin: f64) -> f64 {
+        match c {
+            AbsoluteOrRelative::Absolute(c) => c.into(),
+            AbsoluteOrRelative::Relative(c) => origin + f64::from(c),
+        }
+    }
+
+    DVec3::new(
+        coord(location.x, origin.x),
+        coord(location.y, origin.y),
+        coord(location.z, origin.z),
+    )
+}
+
+/// Returns the position of the single entity `destination` refers to.
+fn destination_of(
+    selector: &TargetSelector,
+    executor: Entity,
+    destination: &EntitySelector,
+) -> Result<DVec3, &'static str> {
+    match selector.select(executor, destination)?.as_slice() {
+        [entity] => selector.position(*entity).ok_or("No entity was found"),
+        _ => Err("Only one entity is allowed, but the provided selector allows more than one"),
+    }
+}
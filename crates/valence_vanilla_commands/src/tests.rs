@@ -0,0 +1,301 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use valence::command::handler::CommandResultEvent;
+use valence::command::manager::CommandExecutionEvent;
+use valence::command::scopes::CommandScopes;
+use valence::command::{AddCommand, Command};
+use valence::command_macros::{Command, CommandArg};
+use valence::entity::Position;
+use valence::inventory::Inventory;
+use valence::keepalive::KeepaliveSettings;
+use valence::math::DVec3;
+use valence::network::NetworkPlugin;
+use valence::prelude::{BiomeRegistry, DimensionTypeRegistry};
+use valence::testing::create_mock_client;
+use valence::world_time::WorldTime;
+use valence::{
+    ident, ChunkLayer, DefaultPlugins, EntityLayer, GameMode, ItemKind, ItemStack, Server,
+    ServerSettings,
+};
+
+use crate::VanillaCommandsPlugin;
+
+/// Sets up an app like `ScenarioSingleClient`, but with the commands in this
+/// crate. Commands are registered on startup, so the plugin can't be added
+/// afterwards.
+fn setup() -> (App, Entity, Entity) {
+    setup_with(|_| {})
+}
+
+/// Like [`setup`], but lets `add_commands` add more commands before startup.
+fn setup_with(add_commands: impl FnOnce(&mut App)) -> (App, Entity, Entity) {
+    let mut app = App::new();
+
+    app.insert_resource(KeepaliveSettings {
+        period: Duration::MAX,
+    })
+    .insert_resource(ServerSettings {
+        compression_threshold: Default::default(),
+        ..Default::default()
+    })
+    .add_plugins(DefaultPlugins.build().disable::<NetworkPlugin>())
+    .add_plugins(VanillaCommandsPlugin);
+
+    add_commands(&mut app);
+
+    app.update();
+
+    let chunk_layer = ChunkLayer::new(
+        ident!("overworld"),
+        app.world().resource::<DimensionTypeRegistry>(),
+        app.world().resource::<BiomeRegistry>(),
+        app.world().resource::<Server>(),
+    );
+    let entity_layer = EntityLayer::new(app.world().resource::<Server>());
+    let layer = app.world_mut().spawn((chunk_layer, entity_layer)).id();
+
+    let (mut client, _helper) = create_mock_client("test");
+    client.player.layer.0 = layer;
+    client.visible_chunk_layer.0 = layer;
+    client.visible_entity_layers.0.insert(layer);
+    let client = app.world_mut().spawn(client).id();
+
+    app.update();
+
+    app.world_mut()
+        .get_mut::<CommandScopes>(client)
+        .unwrap()
+        .add("valence.command");
+
+    (app, client, layer)
+}
+
+fn run(app: &mut App, executor: Entity, command: &str) {
+    app.world_mut().send_event(CommandExecutionEvent {
+        command: command.into(),
+        executor,
+    });
+    app.update();
+}
+
+fn results<T: Command + Send + Sync + 'static>(app: &mut App) -> Vec<T> {
+    app.world_mut()
+        .resource_mut::<Events<CommandResultEvent<T>>>()
+        .drain()
+        .map(|event| event.result)
+        .collect()
+}
+
+#[test]
+fn teleport() {
+    let (mut app, client, _) = setup();
+
+    run(&mut app, client, "tp 1 2 3");
+    assert_eq!(
+        app.world().get::<Position>(client).unwrap().0,
+        DVec3::new(1.0, 2.0, 3.0)
+    );
+
+    run(&mut app, client, "teleport ~ ~5 ~-1");
+    assert_eq!(
+        app.world().get::<Position>(client).unwrap().0,
+        DVec3::new(1.0, 7.0, 2.0)
+    );
+}
+
+#[test]
+fn give_fills_stacks() {
+    let (mut app, client, _) = setup();
+
+    run(&mut app, client, "give @s minecraft:diamond 70");
+
+    let inventory = app.world().get::<Inventory>(client).unwrap();
+    assert_eq!(
+        inventory.slot(36),
+        &ItemStack::new(ItemKind::Diamond, 64, None)
+    );
+    assert_eq!(
+        inventory.slot(37),
+        &ItemStack::new(ItemKind::Diamond, 6, None)
+    );
+
+    run(&mut app, client, "give @s diamond");
+
+    let inventory = app.world().get::<Inventory>(client).unwrap();
+    assert_eq!(
+        inventory.slot(37),
+        &ItemStack::new(ItemKind::Diamond, 7, None)
+    );
+}
+
+#[test]
+fn gamemode() {
+    let (mut app, client, _) = setup();
+
+    run(&mut app, client, "gamemode creative");
+    assert_eq!(
+        app.world().get::<GameMode>(client),
+        Some(&GameMode::Creative)
+    );
+
+    run(&mut app, client, "gamemode adventure @s");
+    assert_eq!(
+        app.world().get::<GameMode>(client),
+        Some(&GameMode::Adventure)
+    );
+}
+
+#[test]
+fn time_set_and_add() {
+    let (mut app, client, layer) = setup();
+
+    // The time advances by one tick after every command.
+    run(&mut app, client, "time set noon");
+    assert_eq!(
+        app.world().get::<WorldTime>(layer).unwrap().day_time(),
+        6001
+    );
+
+    run(&mut app, client, "time add 1d");
+    let time = app.world().get::<WorldTime>(layer).unwrap();
+    assert_eq!(time.day(), 1);
+    assert_eq!(time.day_time(), 6002);
+}
+
+#[test]
+fn missing_scope_is_rejected() {
+    let (mut app, client, _) = setup();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(CommandScopes::new());
+
+    run(&mut app, client, "gamemode creative");
+    assert_ne!(
+        app.world().get::<GameMode>(client),
+        Some(&GameMode::Creative)
+    );
+}
+
+#[derive(CommandArg, Clone, Copy, Debug, PartialEq, Eq)]
+enum Tone {
+    Normal,
+    #[literal = "shout"]
+    Loud,
+    QuietWhisper,
+}
+
+#[derive(Command, Clone, Debug, PartialEq, Eq)]
+#[paths("shout {-s:silent} {tone} {text...}")]
+struct ShoutCommand {
+    silent: bool,
+    tone: Tone,
+    text: String,
+}
+
+#[derive(Command, Clone, Debug, PartialEq, Eq)]
+#[paths("say {-q:quiet} {text...}")]
+struct SayCommand {
+    quiet: bool,
+    text: String,
+}
+
+#[derive(Command, Clone, Debug, PartialEq, Eq)]
+#[paths("pick all {count}")]
+struct PickAllCommand {
+    count: i32,
+}
+
+#[derive(Command, Clone, Debug, PartialEq, Eq)]
+#[paths("pick {name}")]
+struct PickNameCommand {
+    name: String,
+}
+
+#[test]
+fn derive_flags_greedy_and_literal_enums() {
+    let (mut app, client, _) = setup_with(|app| {
+        app.add_command::<ShoutCommand>();
+    });
+
+    run(&mut app, client, "shout normal hello there");
+    run(&mut app, client, "shout -s shout hi");
+    run(&mut app, client, "shout quiet_whisper psst");
+    // `Loud` is only written as `shout`
+    run(&mut app, client, "shout loud hi");
+
+    assert_eq!(
+        results::<ShoutCommand>(&mut app),
+        [
+            ShoutCommand {
+                silent: false,
+                tone: Tone::Normal,
+                text: "hello there".into(),
+            },
+            ShoutCommand {
+                silent: true,
+                tone: Tone::Loud,
+                text: "hi".into(),
+            },
+            ShoutCommand {
+                silent: false,
+                tone: Tone::QuietWhisper,
+                text: "psst".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn flag_takes_precedence_over_arguments() {
+    let (mut app, client, _) = setup_with(|app| {
+        app.add_command::<SayCommand>();
+    });
+
+    // `-q` could also be the start of the greedy text, but a written out flag
+    // is never parsed as an argument
+    run(&mut app, client, "say -q hello");
+    run(&mut app, client, "say hello -q");
+
+    assert_eq!(
+        results::<SayCommand>(&mut app),
+        [
+            SayCommand {
+                quiet: true,
+                text: "hello".into(),
+            },
+            SayCommand {
+                quiet: false,
+                text: "hello -q".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn literals_are_tried_alongside_arguments() {
+    let (mut app, client, _) = setup_with(|app| {
+        app.add_command::<PickAllCommand>()
+            .add_command::<PickNameCommand>();
+    });
+
+    // `all` matches the literal, but only the argument leads to a complete
+    // command. literals which aren't flags don't hide the arguments next to them.
+    run(&mut app, client, "pick all");
+
+    assert!(results::<PickAllCommand>(&mut app).is_empty());
+    assert_eq!(
+        results::<PickNameCommand>(&mut app),
+        [PickNameCommand { name: "all".into() }]
+    );
+
+    run(&mut app, client, "pick all 3");
+
+    assert_eq!(
+        results::<PickAllCommand>(&mut app),
+        [PickAllCommand { count: 3 }]
+    );
+    assert!(results::<PickNameCommand>(&mut app).is_empty());
+}
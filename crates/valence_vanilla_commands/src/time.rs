@@ -0,0 +1,112 @@
+//! `/time set`, `/time add` and `/time query`.
+
+use bevy_ecs::prelude::*;
+use valence::client::{Client, VisibleChunkLayer};
+use valence::command::handler::CommandResultEvent;
+use valence::command::parsers::Time;
+use valence::command_macros::Command;
+use valence::entity::EntityLayerId;
+use valence::layer::ChunkLayer;
+use valence::message::SendMessage;
+use valence::world_time::{WorldTime, TICKS_PER_DAY};
+
+#[derive(Command, Clone, Debug)]
+#[paths("time")]
+#[scopes("valence.command.time")]
+pub enum TimeCommand {
+    #[paths = "set {time}"]
+    Set { time: Time },
+    #[paths = "set day"]
+    SetDay,
+    #[paths = "set noon"]
+    SetNoon,
+    #[paths = "set night"]
+    SetNight,
+    #[paths = "set midnight"]
+    SetMidnight,
+    #[paths = "add {time}"]
+    Add { time: Time },
+    #[paths = "query daytime"]
+    QueryDaytime,
+    #[paths = "query gametime"]
+    QueryGametime,
+    #[paths = "query day"]
+    QueryDay,
+}
+
+pub(crate) fn handle_time_command(
+    mut events: EventReader<CommandResultEvent<TimeCommand>>,
+    executors: Query<(Option<&VisibleChunkLayer>, Option<&EntityLayerId>)>,
+    mut layers: Query<Option<&mut WorldTime>, With<ChunkLayer>>,
+    mut clients: Query<&mut Client>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        // The time is changed in the layer the executor sees.
+        let layer = executors
+            .get(executor)
+            .ok()
+            .and_then(|(visible, layer_id)| visible.map(|l| l.0).or(layer_id.map(|l| l.0)))
+            .filter(|&layer| layers.contains(layer));
+
+        let message = match layer {
+            Some(layer) => {
+                let mut time = layers.get_mut(layer).unwrap();
+                let mut world_time = time.as_deref().copied().unwrap_or_default();
+
+                let message = match &event.result {
+                    TimeCommand::Set { time } => set(&mut world_time, ticks(*time)),
+                    TimeCommand::SetDay => set(&mut world_time, 1000),
+                    TimeCommand::SetNoon => set(&mut world_time, 6000),
+                    TimeCommand::SetNight => set(&mut world_time, 13000),
+                    TimeCommand::SetMidnight => set(&mut world_time, 18000),
+                    TimeCommand::Add { time } => {
+                        world_time.time_of_day += ticks(*time);
+                        format!("Set the time to {}", world_time.day_time())
+                    }
+                    TimeCommand::QueryDaytime => {
+                        format!("The time is {}", world_time.day_time())
+                    }
+                    TimeCommand::QueryGametime => {
+                        format!("The time is {}", world_time.world_age)
+                    }
+                    TimeCommand::QueryDay => format!("The time is {}", world_time.day()),
+                };
+
+                match &mut time {
+                    Some(time) => {
+                        time.set_if_neq(world_time);
+                    }
+                    None => {
+                        commands.entity(layer).insert(world_time);
+                    }
+                }
+
+                message
+            }
+            None => "No chunk layer was found".to_owned(),
+        };
+
+        if let Ok(mut client) = clients.get_mut(executor) {
+            client.send_chat_message(message);
+        }
+    }
+}
+
+/// Sets the time of day, keeping the number of elapsed days like vanilla.
+fn set(world_time: &mut WorldTime, day_time: i64) -> String {
+    world_time.time_of_day = world_time.day() * TICKS_PER_DAY + day_time;
+    format!("Set the time to {day_time}")
+}
+
+fn ticks(time: Time) -> i64 {
+    let ticks = match time {
+        Time::Ticks(t) => t,
+        Time::Seconds(s) => s * 20.0,
+        Time::Days(d) => d * TICKS_PER_DAY as f32,
+    };
+
+    ticks as i64
+}
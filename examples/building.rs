@@ -1,6 +1,7 @@
 #![allow(clippy::type_complexity)]
 
 use valence::interact_block::InteractBlockEvent;
+use valence::interaction_rules::InteractionRules;
 use valence::inventory::HeldItem;
 use valence::prelude::*;
 
@@ -104,6 +105,7 @@ fn toggle_gamemode_on_sneak(
 fn digging(
     clients: Query<&GameMode>,
     mut layers: Query<&mut ChunkLayer>,
+    rules: Res<InteractionRules>,
     mut events: EventReader<DiggingEvent>,
 ) {
     let mut layer = layers.single_mut();
@@ -113,9 +115,7 @@ fn digging(
             continue;
         };
 
-        if (*game_mode == GameMode::Creative && event.state == DiggingState::Start)
-            || (*game_mode == GameMode::Survival && event.state == DiggingState::Stop)
-        {
+        if rules.breaks_block(*game_mode, event.state) {
             layer.set_block(event.position, BlockState::AIR);
         }
     }
@@ -8,7 +8,7 @@ use command::parsers::entity_selector::{EntitySelector, EntitySelectors};
 use command::parsers::{CommandArg, GreedyString, QuotableString};
 use command::scopes::CommandScopes;
 use command::{parsers, AddCommand, Command, CommandScopeRegistry, ModifierValue};
-use command_macros::Command;
+use command_macros::{Command, CommandArg};
 use parsers::{Vec2 as Vec2Parser, Vec3 as Vec3Parser};
 use rand::prelude::IteratorRandom;
 use valence::entity::living::LivingEntity;
@@ -61,6 +61,25 @@ pub(crate) struct StructCommand {
     target: Option<EntitySelector>,
 }
 
+#[derive(CommandArg, Debug, Clone, Copy)]
+enum Tone {
+    Normal,
+    #[literal = "shout"]
+    Loud,
+    Whisper,
+}
+
+#[derive(Command, Debug, Clone)]
+#[paths("message {-s:silent} {tone} {target} {text...}")]
+#[scopes("valence.command.message")]
+#[allow(dead_code)]
+pub(crate) struct MessageCommand {
+    silent: bool,
+    tone: Tone,
+    target: EntitySelector,
+    text: String,
+}
+
 #[derive(Command, Debug, Clone)]
 #[paths("test", "t")]
 #[scopes("valence.command.test")]
@@ -178,6 +197,7 @@ pub fn main() {
         .add_command::<GamemodeCommand>()
         .add_command::<ComplexRedirectionCommand>()
         .add_command::<StructCommand>()
+        .add_command::<MessageCommand>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
@@ -190,6 +210,7 @@ pub fn main() {
                 handle_complex_command,
                 handle_gamemode_command,
                 handle_struct_command,
+                handle_message_command,
             ),
         )
         .run();
@@ -450,6 +471,19 @@ fn handle_struct_command(
     }
 }
 
+fn handle_message_command(
+    mut events: EventReader<CommandResultEvent<MessageCommand>>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        let client = &mut clients.get_mut(event.executor).unwrap();
+        client.send_chat_message(format!(
+            "Message command executed with data:\n {:#?}",
+            &event.result
+        ));
+    }
+}
+
 fn handle_gamemode_command(
     mut events: EventReader<CommandResultEvent<GamemodeCommand>>,
     mut clients: Query<(&mut Client, &mut GameMode, &Username, Entity)>,
@@ -3,9 +3,9 @@
 use std::net::SocketAddr;
 
 use rand::Rng;
+use valence::network::server_list::{Favicon, ServerListPingBuilder};
 use valence::network::{
-    async_trait, BroadcastToLan, CleanupFn, ConnectionMode, HandshakeData, PlayerSampleEntry,
-    ServerListPing,
+    async_trait, BroadcastToLan, CleanupFn, ConnectionMode, HandshakeData, ServerListPing,
 };
 use valence::prelude::*;
 use valence::MINECRAFT_VERSION;
@@ -14,14 +14,21 @@ pub fn main() {
     App::new()
         .insert_resource(NetworkSettings {
             connection_mode: ConnectionMode::Offline,
-            callbacks: MyCallbacks.into(),
+            callbacks: MyCallbacks {
+                // Images of other sizes are resized.
+                favicon: Favicon::from_image_bytes(include_bytes!("../assets/logo-64x64.png"))
+                    .unwrap(),
+            }
+            .into(),
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
         .run();
 }
 
-struct MyCallbacks;
+struct MyCallbacks {
+    favicon: Favicon,
+}
 
 #[async_trait]
 impl NetworkCallbacks for MyCallbacks {
@@ -33,20 +40,21 @@ impl NetworkCallbacks for MyCallbacks {
     ) -> ServerListPing {
         let max_players = 420;
 
-        ServerListPing::Respond {
-            online_players: rand::thread_rng().gen_range(0..=max_players),
-            max_players,
-            player_sample: vec![PlayerSampleEntry {
-                name: "foobar".into(),
-                id: Uuid::from_u128(12345),
-            }],
-            description: "Your IP address is ".into_text()
-                + remote_addr.to_string().color(Color::rgb(50, 50, 250)),
-            favicon_png: include_bytes!("../assets/logo-64x64.png"),
-            version_name: ("Valence ".color(Color::GOLD) + MINECRAFT_VERSION.color(Color::RED))
-                .to_legacy_lossy(),
-            protocol: handshake_data.protocol_version,
-        }
+        ServerListPingBuilder::new(rand::thread_rng().gen_range(0..=max_players), max_players)
+            .motd(
+                "A Valence Server".color(Color::GOLD),
+                "Your IP address is ".into_text()
+                    + remote_addr.to_string().color(Color::rgb(50, 50, 250)),
+            )
+            .sample_player("foobar", Uuid::from_u128(12345))
+            .sample_line("...and more!".italic())
+            .favicon(&self.favicon)
+            .version_name(
+                ("Valence ".color(Color::GOLD) + MINECRAFT_VERSION.color(Color::RED))
+                    .to_legacy_lossy(),
+            )
+            .protocol(handshake_data.protocol_version)
+            .build()
     }
 
     async fn broadcast_to_lan(&self, _shared: &SharedNetworkState) -> BroadcastToLan {
@@ -46,6 +46,7 @@ public class Main implements ModInitializer {
                new Effects(),
                new Misc(),
                new Packets(),
+               new Potions(),
                new Sounds(),
                new TranslationKeys(),
         };
@@ -0,0 +1,47 @@
+package rs.valence.extractor.extractors;
+
+import com.google.gson.JsonArray;
+import com.google.gson.JsonElement;
+import com.google.gson.JsonObject;
+import net.minecraft.registry.Registries;
+import rs.valence.extractor.Main;
+
+public class Potions implements Main.Extractor {
+    public Potions() {
+    }
+
+    @Override
+    public String fileName() {
+        return "potions.json";
+    }
+
+    @Override
+    public JsonElement extract() {
+        var potionsJson = new JsonArray();
+
+        for (var potion : Registries.POTION) {
+            var potionJson = new JsonObject();
+
+            potionJson.addProperty("id", Registries.POTION.getRawId(potion));
+            potionJson.addProperty("name", Registries.POTION.getId(potion).getPath());
+
+            var effectsJson = new JsonArray();
+
+            for (var effect : potion.getEffects()) {
+                var effectJson = new JsonObject();
+
+                effectJson.addProperty("effect", Registries.STATUS_EFFECT.getId(effect.getEffectType()).getPath());
+                effectJson.addProperty("amplifier", effect.getAmplifier());
+                effectJson.addProperty("duration", effect.getDuration());
+
+                effectsJson.add(effectJson);
+            }
+
+            potionJson.add("effects", effectsJson);
+
+            potionsJson.add(potionJson);
+        }
+
+        return potionsJson;
+    }
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use valence_anvil::parsing::parse_chunk_nbt;
+use valence_server::registry::BiomeRegistry;
+
+fuzz_target!(|data: &[u8]| {
+    let mut slice = data;
+
+    if let Ok((nbt, _)) = valence_nbt::from_binary::<String>(&mut slice) {
+        let _ = parse_chunk_nbt(nbt, &BiomeRegistry::default());
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut slice = data;
+
+    if let Ok((nbt, root_name)) = valence_nbt::from_binary::<String>(&mut slice) {
+        let mut buf = vec![];
+        valence_nbt::to_binary(&nbt, &mut buf, &root_name).unwrap();
+
+        // Anything we decode must encode back to the same bytes after a round
+        // trip. Bytes are compared instead of values because of NaN floats.
+        let (nbt, root_name) = valence_nbt::from_binary::<String>(&mut buf.as_slice()).unwrap();
+        let mut buf2 = vec![];
+        valence_nbt::to_binary(&nbt, &mut buf2, &root_name).unwrap();
+
+        assert_eq!(buf, buf2);
+    }
+});
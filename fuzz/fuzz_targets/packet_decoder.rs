@@ -0,0 +1,107 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use valence_protocol::decode::PacketDecoder;
+use valence_protocol::packets::handshaking::*;
+use valence_protocol::packets::login::*;
+use valence_protocol::packets::play::*;
+use valence_protocol::packets::status::*;
+use valence_protocol::{CompressionThreshold, Decode, Encode, Packet};
+
+/// Decodes the packet body as each packet type with a matching ID. Anything
+/// that decodes successfully must decode again after being encoded.
+macro_rules! check_packets {
+    ($id:expr, $body:expr, $($ty:ident),* $(,)?) => {
+        $(
+            if <$ty>::ID == $id {
+                if let Ok(pkt) = <$ty>::decode(&mut &$body[..]) {
+                    let mut buf = vec![];
+
+                    if pkt.encode(&mut buf).is_ok() {
+                        <$ty>::decode(&mut buf.as_slice()).unwrap();
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// The first byte selects whether compression is enabled. The rest is the
+// stream of packet frames sent by a client.
+fuzz_target!(|data: &[u8]| {
+    let Some((&compressed, data)) = data.split_first() else {
+        return;
+    };
+
+    let mut dec = PacketDecoder::new();
+
+    if compressed & 1 != 0 {
+        dec.set_compression(CompressionThreshold(256));
+    }
+
+    dec.queue_slice(data);
+
+    while let Ok(Some(frame)) = dec.try_next_packet() {
+        check_packets!(
+            frame.id,
+            &frame.body,
+            HandshakeC2s,
+            QueryPingC2s,
+            QueryRequestC2s,
+            LoginHelloC2s,
+            LoginKeyC2s,
+            LoginQueryResponseC2s,
+            AdvancementTabC2s,
+            BoatPaddleStateC2s,
+            BookUpdateC2s,
+            ButtonClickC2s,
+            ChatMessageC2s,
+            ClickSlotC2s,
+            ClientCommandC2s,
+            ClientSettingsC2s,
+            ClientStatusC2s,
+            CloseHandledScreenC2s,
+            CommandExecutionC2s,
+            CraftRequestC2s,
+            CreativeInventoryActionC2s,
+            CustomPayloadC2s,
+            FullC2s,
+            HandSwingC2s,
+            JigsawGeneratingC2s,
+            KeepAliveC2s,
+            LookAndOnGroundC2s,
+            MessageAcknowledgmentC2s,
+            OnGroundOnlyC2s,
+            PickFromInventoryC2s,
+            PlayPongC2s,
+            PlayerActionC2s,
+            PlayerInputC2s,
+            PlayerInteractBlockC2s,
+            PlayerInteractEntityC2s,
+            PlayerInteractItemC2s,
+            PlayerSessionC2s,
+            PositionAndOnGroundC2s,
+            QueryBlockNbtC2s,
+            QueryEntityNbtC2s,
+            RecipeBookDataC2s,
+            RecipeCategoryOptionsC2s,
+            RenameItemC2s,
+            RequestCommandCompletionsC2s,
+            ResourcePackStatusC2s,
+            SelectMerchantTradeC2s,
+            SpectatorTeleportC2s,
+            TeleportConfirmC2s,
+            UpdateBeaconC2s,
+            UpdateCommandBlockC2s,
+            UpdateCommandBlockMinecartC2s,
+            UpdateDifficultyC2s,
+            UpdateDifficultyLockC2s,
+            UpdateJigsawC2s,
+            UpdatePlayerAbilitiesC2s,
+            UpdateSelectedSlotC2s,
+            UpdateSignC2s,
+            UpdateStructureBlockC2s,
+            VehicleMoveC2s,
+        );
+    }
+});
@@ -36,6 +36,7 @@ mod tests;
 #[cfg(feature = "log")]
 pub use bevy_log as log;
 use registry::biome::BiomePlugin;
+use registry::chat_type::ChatTypePlugin;
 use registry::dimension_type::DimensionTypePlugin;
 #[cfg(feature = "advancement")]
 pub use valence_advancement as advancement;
@@ -49,6 +50,8 @@ pub use valence_command as command;
 pub use valence_command_macros as command_macros;
 #[cfg(feature = "equipment")]
 pub use valence_equipment as equipment;
+#[cfg(feature = "hunger")]
+pub use valence_hunger as hunger;
 #[cfg(feature = "inventory")]
 pub use valence_inventory as inventory;
 pub use valence_lang as lang;
@@ -59,15 +62,25 @@ pub use valence_player_list as player_list;
 use valence_registry::RegistryPlugin;
 #[cfg(feature = "scoreboard")]
 pub use valence_scoreboard as scoreboard;
+#[cfg(feature = "scripting")]
+pub use valence_scripting as scripting;
 use valence_server::abilities::AbilitiesPlugin;
 use valence_server::action::ActionPlugin;
+use valence_server::asset::AssetPlugin;
+use valence_server::biome_override::BiomeOverridePlugin;
+use valence_server::block_overlay::BlockOverlayPlugin;
+use valence_server::brand::BrandPlugin;
+use valence_server::cinematic::CinematicPlugin;
 use valence_server::client::ClientPlugin;
 use valence_server::client_command::ClientCommandPlugin;
 use valence_server::client_settings::ClientSettingsPlugin;
 use valence_server::custom_payload::CustomPayloadPlugin;
+use valence_server::debug_shapes::DebugShapesPlugin;
 use valence_server::entity::hitbox::HitboxPlugin;
 use valence_server::entity::EntityPlugin;
 use valence_server::event_loop::EventLoopPlugin;
+use valence_server::game_mode::GameModePlugin;
+use valence_server::game_rules::GameRulesPlugin;
 use valence_server::hand_swing::HandSwingPlugin;
 use valence_server::interact_block::InteractBlockPlugin;
 use valence_server::interact_entity::InteractEntityPlugin;
@@ -77,12 +90,21 @@ use valence_server::layer::LayerPlugin;
 use valence_server::message::MessagePlugin;
 use valence_server::movement::MovementPlugin;
 use valence_server::op_level::OpLevelPlugin;
+use valence_server::pose::PosePlugin;
 pub use valence_server::protocol::status_effects;
+use valence_server::random_tick::RandomTickPlugin;
 use valence_server::resource_pack::ResourcePackPlugin;
 use valence_server::status::StatusPlugin;
 use valence_server::status_effect::StatusEffectPlugin;
+use valence_server::steering::SteeringPlugin;
 use valence_server::teleport::TeleportPlugin;
+use valence_server::use_item::UseItemPlugin;
+use valence_server::vanish::VanishPlugin;
+use valence_server::view_distance::ViewDistancePlugin;
+use valence_server::world_time::WorldTimePlugin;
 pub use valence_server::*;
+#[cfg(feature = "statistics")]
+pub use valence_statistics as statistics;
 #[cfg(feature = "weather")]
 pub use valence_weather as weather;
 #[cfg(feature = "world_border")]
@@ -124,6 +146,7 @@ pub mod prelude {
     #[cfg(feature = "player_list")]
     pub use valence_player_list::{PlayerList, PlayerListEntry};
     pub use valence_registry::biome::{Biome, BiomeId, BiomeRegistry};
+    pub use valence_registry::chat_type::{ChatType, ChatTypeId, ChatTypeRegistry};
     pub use valence_registry::dimension_type::{DimensionType, DimensionTypeRegistry};
     pub use valence_server::action::{DiggingEvent, DiggingState};
     pub use valence_server::block::{BlockKind, BlockState, PropName, PropValue};
@@ -132,10 +155,13 @@ pub mod prelude {
         View, ViewDistance, VisibleChunkLayer, VisibleEntityLayers,
     };
     pub use valence_server::client_command::{
-        ClientCommand, JumpWithHorseEvent, JumpWithHorseState, LeaveBedEvent, SneakEvent,
-        SneakState, SprintEvent, SprintState,
+        ClientCommand, JumpWithHorseEvent, JumpWithHorseState, LeaveBedEvent, PlayerInput,
+        SneakEvent, SneakState, SprintEvent, SprintState, StartFlyingWithElytraEvent,
     };
-    pub use valence_server::entity::hitbox::{Hitbox, HitboxShape};
+    pub use valence_server::entity::armor_stand_pose::{ArmorStandPart, ArmorStandPose};
+    pub use valence_server::entity::display_transform::{DisplayInterpolation, DisplayTransform};
+    pub use valence_server::entity::hitbox::{Hitbox, HitboxOverride, HitboxShape, HitboxSize};
+    pub use valence_server::entity::passengers::{Passengers, Vehicle};
     pub use valence_server::entity::{
         EntityAnimation, EntityKind, EntityLayerId, EntityManager, EntityStatus, HeadYaw, Look,
         OldEntityLayerId, OldPosition, Position,
@@ -143,6 +169,9 @@ pub mod prelude {
     pub use valence_server::event_loop::{
         EventLoopPostUpdate, EventLoopPreUpdate, EventLoopUpdate,
     };
+    pub use valence_server::experience::{Experience, ExperienceOrbBundle};
+    pub use valence_server::explosion::{ExplosionEvent, ExplosionOptions};
+    pub use valence_server::game_rules::GameRules;
     pub use valence_server::ident::Ident;
     pub use valence_server::interact_entity::{EntityInteraction, InteractEntityEvent};
     pub use valence_server::layer::chunk::{
@@ -155,7 +184,11 @@ pub mod prelude {
     pub use valence_server::protocol::packets::play::particle_s2c::Particle;
     pub use valence_server::protocol::text::{Color, IntoText, Text};
     pub use valence_server::spawn::{ClientSpawnQuery, ClientSpawnQueryReadOnly, RespawnPosition};
+    pub use valence_server::steering::{PaddleBoatEvent, SteerVehicleEvent, VehicleMoveEvent};
     pub use valence_server::title::SetTitle as _;
+    pub use valence_server::use_item::{UseItemEvent, UseItemTarget};
+    pub use valence_server::vanish::{AnnounceJoinEvent, AnnounceLeaveEvent, Vanished};
+    pub use valence_server::world_time::WorldTime;
     pub use valence_server::{
         ident, BlockPos, ChunkPos, ChunkView, Despawned, Direction, GameMode, Hand, ItemKind,
         ItemStack, Server, UniqueId,
@@ -177,31 +210,47 @@ impl PluginGroup for DefaultPlugins {
         #[allow(unused_mut)]
         let mut group = PluginGroupBuilder::start::<Self>()
             .add(ServerPlugin)
+            .add(AssetPlugin)
             .add(RegistryPlugin)
             .add(BiomePlugin)
             .add(DimensionTypePlugin)
+            .add(ChatTypePlugin)
             .add(EntityPlugin)
             .add(HitboxPlugin)
             .add(LayerPlugin)
             .add(ClientPlugin)
+            .add(BiomeOverridePlugin)
+            .add(BlockOverlayPlugin)
             .add(EventLoopPlugin)
             .add(MovementPlugin)
             .add(ClientCommandPlugin)
             .add(KeepalivePlugin)
             .add(InteractEntityPlugin)
             .add(ClientSettingsPlugin)
+            .add(ViewDistancePlugin)
             .add(ActionPlugin)
             .add(TeleportPlugin)
             .add(MessagePlugin)
             .add(CustomPayloadPlugin)
+            .add(BrandPlugin)
             .add(HandSwingPlugin)
             .add(InteractBlockPlugin)
             .add(InteractItemPlugin)
+            .add(UseItemPlugin)
             .add(OpLevelPlugin)
             .add(ResourcePackPlugin)
             .add(StatusPlugin)
             .add(StatusEffectPlugin)
-            .add(AbilitiesPlugin);
+            .add(AbilitiesPlugin)
+            .add(GameModePlugin)
+            .add(GameRulesPlugin)
+            .add(RandomTickPlugin)
+            .add(WorldTimePlugin)
+            .add(DebugShapesPlugin)
+            .add(CinematicPlugin)
+            .add(PosePlugin)
+            .add(SteeringPlugin)
+            .add(VanishPlugin);
 
         #[cfg(feature = "log")]
         {
@@ -228,6 +277,16 @@ impl PluginGroup for DefaultPlugins {
             group = group.add(valence_inventory::InventoryPlugin)
         }
 
+        #[cfg(feature = "hunger")]
+        {
+            group = group.add(valence_hunger::HungerPlugin)
+        }
+
+        #[cfg(feature = "statistics")]
+        {
+            group = group.add(valence_statistics::StatisticsPlugin)
+        }
+
         #[cfg(feature = "anvil")]
         {
             group = group.add(valence_anvil::AnvilPlugin)
@@ -263,6 +322,11 @@ impl PluginGroup for DefaultPlugins {
             group = group.add(valence_scoreboard::ScoreboardPlugin)
         }
 
+        #[cfg(feature = "scripting")]
+        {
+            group = group.add(valence_scripting::ScriptingPlugin)
+        }
+
         group
     }
 }
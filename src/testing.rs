@@ -12,10 +12,16 @@ use valence_network::NetworkPlugin;
 use valence_registry::{BiomeRegistry, DimensionTypeRegistry};
 use valence_server::client::{ClientBundle, ClientBundleArgs, ClientConnection, ReceivedPacket};
 use valence_server::keepalive::KeepaliveSettings;
+use valence_server::math::DVec3;
 use valence_server::protocol::decode::PacketFrame;
-use valence_server::protocol::packets::play::{PlayerPositionLookS2c, TeleportConfirmC2s};
-use valence_server::protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder, VarInt};
-use valence_server::{ChunkLayer, EntityLayer, Server, ServerSettings};
+use valence_server::protocol::packets::play::{
+    ChatMessageC2s, CommandExecutionC2s, HandSwingC2s, PlayerPositionLookS2c,
+    PositionAndOnGroundC2s, TeleportConfirmC2s, UpdateSelectedSlotC2s,
+};
+use valence_server::protocol::{
+    Bounded, Decode, Encode, Packet, PacketDecoder, PacketEncoder, VarInt,
+};
+use valence_server::{ChunkLayer, EntityLayer, Hand, Server, ServerSettings, PROTOCOL_VERSION};
 
 use crate::DefaultPlugins;
 pub struct ScenarioSingleClient {
@@ -90,8 +96,10 @@ pub fn create_mock_client<N: Into<String>>(name: N) -> (ClientBundle, MockClient
         uuid: Uuid::from_bytes(rand::random()),
         ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
         properties: Default::default(),
+        protocol_version: PROTOCOL_VERSION,
         conn: Box::new(conn.clone()),
         enc: PacketEncoder::new(),
+        broadcast_threshold: Default::default(),
     });
 
     let helper = MockClientHelper::new(conn);
@@ -240,6 +248,178 @@ impl MockClientHelper {
     }
 }
 
+/// A sequence of actions for a [`FakeClient`] to perform, built ahead of time
+/// so it can be reused across tests.
+///
+/// Packets are queued in order and processed by the server on the next tick.
+#[derive(Clone, Default)]
+pub struct ClientScript {
+    steps: Vec<ScriptStep>,
+}
+
+#[derive(Clone)]
+enum ScriptStep {
+    /// An encoded (Packet ID + data) frame.
+    Send(BytesMut),
+    Wait(usize),
+}
+
+impl ClientScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends an arbitrary packet.
+    #[track_caller]
+    pub fn send<P>(mut self, packet: &P) -> Self
+    where
+        P: Packet + Encode,
+    {
+        let mut bytes = BytesMut::new();
+
+        packet
+            .encode_with_id((&mut bytes).writer())
+            .expect("failed to encode packet");
+
+        self.steps.push(ScriptStep::Send(bytes));
+        self
+    }
+
+    /// Runs a single tick.
+    pub fn tick(self) -> Self {
+        self.wait(1)
+    }
+
+    /// Runs `ticks` ticks.
+    pub fn wait(mut self, ticks: usize) -> Self {
+        self.steps.push(ScriptStep::Wait(ticks));
+        self
+    }
+
+    /// Moves the client to `position`, on the ground.
+    pub fn move_to<P: Into<DVec3>>(self, position: P) -> Self {
+        self.send(&PositionAndOnGroundC2s {
+            position: position.into(),
+            on_ground: true,
+        })
+    }
+
+    /// Sends an unsigned chat message.
+    pub fn chat(self, message: &str) -> Self {
+        self.send(&ChatMessageC2s {
+            message: Bounded(message),
+            timestamp: 0,
+            salt: 0,
+            signature: None,
+            message_count: VarInt(0),
+            acknowledgement: Default::default(),
+        })
+    }
+
+    /// Runs a command, without the leading `/`.
+    pub fn command(self, command: &str) -> Self {
+        self.send(&CommandExecutionC2s {
+            command: Bounded(command),
+            timestamp: 0,
+            salt: 0,
+            argument_signatures: vec![],
+            message_count: VarInt(0),
+            acknowledgement: Default::default(),
+        })
+    }
+
+    /// Selects a hotbar slot in `0..9`.
+    pub fn select_hotbar_slot(self, slot: u16) -> Self {
+        self.send(&UpdateSelectedSlotC2s { slot })
+    }
+
+    pub fn swing_hand(self, hand: Hand) -> Self {
+        self.send(&HandSwingC2s { hand })
+    }
+}
+
+/// A headless client which performs [`ClientScript`]s and records the packets
+/// it receives, for end-to-end tests of server behavior.
+pub struct FakeClient {
+    /// The client entity.
+    pub entity: Entity,
+    helper: MockClientHelper,
+    received: PacketFrames,
+}
+
+impl FakeClient {
+    /// Spawns a new client named `name` in `layer` and runs a tick so that it
+    /// joins the game.
+    pub fn join<N: Into<String>>(app: &mut App, name: N, layer: Entity) -> Self {
+        let (mut bundle, helper) = create_mock_client(name);
+        bundle.player.layer.0 = layer;
+        bundle.visible_chunk_layer.0 = layer;
+        bundle.visible_entity_layers.0.insert(layer);
+
+        let entity = app.world_mut().spawn(bundle).id();
+
+        let mut client = Self::new(entity, helper);
+        client.run(app, &ClientScript::new().tick());
+        client
+    }
+
+    /// Wraps a client which was already spawned, such as the one in
+    /// [`ScenarioSingleClient`].
+    pub fn new(entity: Entity, helper: MockClientHelper) -> Self {
+        Self {
+            entity,
+            helper,
+            received: PacketFrames(vec![]),
+        }
+    }
+
+    /// Performs the steps of `script` in order. A tick is run at the end if
+    /// packets were sent after the last tick of the script.
+    pub fn run(&mut self, app: &mut App, script: &ClientScript) {
+        let mut pending = false;
+
+        for step in &script.steps {
+            match step {
+                ScriptStep::Send(bytes) => {
+                    self.helper.conn.inject_send(bytes.clone());
+                    pending = true;
+                }
+                ScriptStep::Wait(ticks) => {
+                    for _ in 0..*ticks {
+                        self.update(app);
+                    }
+                    pending = false;
+                }
+            }
+        }
+
+        if pending {
+            self.update(app);
+        }
+    }
+
+    fn update(&mut self, app: &mut App) {
+        app.update();
+        self.received.0.extend(self.helper.collect_received().0);
+    }
+
+    /// Returns the packets received since the client was created or
+    /// [`FakeClient::clear_received`] was last called.
+    pub fn received(&self) -> &PacketFrames {
+        &self.received
+    }
+
+    pub fn clear_received(&mut self) {
+        self.received.0.clear();
+    }
+
+    /// Returns the helper of the underlying mock connection, for sending
+    /// packets or reading received packets directly.
+    pub fn helper(&mut self) -> &mut MockClientHelper {
+        &mut self.helper
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PacketFrames(pub Vec<PacketFrame>);
 
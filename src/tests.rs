@@ -1,12 +1,54 @@
+mod anvil;
+mod armor_stand;
+mod asset;
+mod biome_override;
+mod block_overlay;
 mod boss_bar;
+mod bundle;
+mod chat_type;
+mod cinematic;
 mod client;
+mod client_command;
+mod command_block;
+mod config;
+mod container_block;
+mod death;
+mod debug_shapes;
+mod display;
+mod effect_filter;
 mod equipment;
 mod example;
+mod experience;
+mod explosion;
+mod fake_client;
+mod falling_block;
+mod farming;
+mod fire;
+mod firework;
+mod flight;
+mod game_rules;
+mod hitbox;
 mod hunger;
+mod interaction;
 mod inventory;
+mod item_use;
+mod jukebox;
 mod layer;
+mod message;
+mod move_to;
+mod moving_platform;
 mod player_list;
+mod pose;
 mod potions;
+mod replay;
 mod scoreboard;
+mod sleep;
+mod spawner;
+mod spectate;
+mod statistics;
+mod steering;
+mod tnt;
+mod vanish;
+mod view_distance;
 mod weather;
 mod world_border;
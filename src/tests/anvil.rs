@@ -0,0 +1,204 @@
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::anvil::parsing::{parse_chunk_nbt, ParseChunkError};
+use crate::anvil::{RegionFolder, RegionIssue};
+use crate::layer::chunk::Chunk;
+use crate::nbt::{compound, Compound, List, Value};
+use crate::protocol::BlockKind;
+use crate::registry::BiomeRegistry;
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, BlockState};
+
+fn chunk_nbt(x: i32, z: i32) -> Compound {
+    compound! {
+        "xPos" => x,
+        "zPos" => z,
+        "sections" => List::End,
+        "block_entities" => List::End,
+    }
+}
+
+#[test]
+fn region_verify_and_repair() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut region = RegionFolder::new(dir.path());
+
+    for x in 0..4 {
+        region.set_chunk(x, 0, &chunk_nbt(x, 0)).unwrap();
+    }
+
+    // An intact region has nothing to repair.
+    assert!(region.verify_and_repair().unwrap().is_empty());
+
+    // Corrupt the header the way a crash might.
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(dir.path().join("r.0.0.mca"))
+        .unwrap();
+
+    let mut header = [0; 16];
+    file.read_exact(&mut header).unwrap();
+
+    // Chunk (1, 0) is missing from the header.
+    header[4..8].fill(0);
+    // Chunk (2, 0) points inside the header.
+    header[8..12].copy_from_slice(&0x0000_0101_u32.to_be_bytes());
+    // Chunk (3, 0) points to the data of chunk (0, 0).
+    header.copy_within(0..4, 12);
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(&header).unwrap();
+    drop(file);
+
+    let issues = region.verify_and_repair().unwrap();
+
+    assert!(issues.iter().any(|issue| matches!(
+        issue,
+        RegionIssue::InvalidSectorOffset {
+            chunk_x: 2,
+            chunk_z: 0
+        }
+    )));
+    assert!(issues.iter().any(|issue| matches!(
+        issue,
+        RegionIssue::OverlappingChunks {
+            chunk_x: 3,
+            chunk_z: 0,
+            other_x: 0,
+            other_z: 0
+        }
+    )));
+
+    // The data of every chunk is still in the file, so all of them are put back
+    // into the header.
+    for x in 1..4 {
+        assert!(
+            issues.iter().any(|issue| matches!(
+                issue,
+                RegionIssue::RecoveredChunk { chunk_x, chunk_z: 0 } if *chunk_x == x
+            )),
+            "{issues:?}"
+        );
+    }
+    assert_eq!(issues.len(), 5, "{issues:?}");
+
+    for x in 0..4 {
+        let chunk = region.get_chunk::<String>(x, 0).unwrap().unwrap();
+        assert_eq!(chunk.data, chunk_nbt(x, 0));
+    }
+
+    // The repaired region can be written to again.
+    region.set_chunk(4, 0, &chunk_nbt(4, 0)).unwrap();
+    assert!(region.verify_and_repair().unwrap().is_empty());
+}
+
+#[test]
+fn upgrade_legacy_chunks() {
+    const NAMES: [&str; 17] = [
+        "air",
+        "stone",
+        "granite",
+        "diorite",
+        "andesite",
+        "dirt",
+        "cobblestone",
+        "oak_planks",
+        "sand",
+        "gravel",
+        "gold_ore",
+        "iron_ore",
+        "coal_ore",
+        "oak_log",
+        "glass",
+        "sandstone",
+        "bricks",
+    ];
+
+    // Seventeen blocks need five bits per index, which span two longs in
+    // chunks from before 1.16.
+    let mut block_states = vec![0_i64; 4096 * 5 / 64];
+    for i in 0..4096 {
+        let idx = (i % NAMES.len()) as u64;
+        let bit = i * 5;
+        let (long, offset) = (bit / 64, bit % 64);
+
+        block_states[long] |= (idx << offset) as i64;
+        if offset + 5 > 64 {
+            block_states[long + 1] |= (idx >> (64 - offset)) as i64;
+        }
+    }
+
+    let palette = NAMES
+        .iter()
+        .map(|name| compound! { "Name" => format!("minecraft:{name}") })
+        .collect();
+
+    // Plains everywhere except for a desert in the lowest corner.
+    let mut biomes = vec![1; 1024];
+    biomes[0] = 2;
+
+    let nbt = compound! {
+        "DataVersion" => 2230,
+        "Level" => compound! {
+            "xPos" => 0,
+            "zPos" => 0,
+            "Sections" => List::Compound(vec![
+                // Only light, which is skipped.
+                compound! { "Y" => -1_i8 },
+                compound! {
+                    "Y" => 0_i8,
+                    "Palette" => List::Compound(palette),
+                    "BlockStates" => Value::LongArray(block_states),
+                },
+                compound! {
+                    "Y" => 3_i8,
+                    "Palette" => List::Compound(vec![
+                        compound! { "Name" => "minecraft:grass_path" },
+                    ]),
+                },
+            ]),
+            "Biomes" => Value::IntArray(biomes),
+            "TileEntities" => List::End,
+        },
+    };
+
+    let scenario = ScenarioSingleClient::new();
+    let registry = scenario.app.world().resource::<BiomeRegistry>();
+
+    let chunk = parse_chunk_nbt(nbt, registry).unwrap();
+
+    assert_eq!(chunk.height(), 256);
+
+    for i in 0..4096 {
+        let expected = BlockKind::from_str(NAMES[i as usize % NAMES.len()])
+            .unwrap()
+            .to_state();
+
+        assert_eq!(chunk.block_state(i % 16, i / 256, i / 16 % 16), expected);
+    }
+
+    assert_eq!(chunk.block_state(0, 16, 0), BlockState::AIR);
+    assert_eq!(chunk.block_state(5, 50, 5), BlockState::DIRT_PATH);
+
+    assert_eq!(
+        chunk.biome(0, 0, 0),
+        registry.index_of(ident!("desert")).unwrap()
+    );
+    assert_eq!(
+        chunk.biome(1, 0, 0),
+        registry.index_of(ident!("plains")).unwrap()
+    );
+
+    // Chunks from before the flattening in 1.13 can't be upgraded.
+    let nbt = compound! {
+        "DataVersion" => 1343,
+        "Level" => compound! {},
+    };
+
+    assert!(matches!(
+        parse_chunk_nbt(nbt, registry),
+        Err(ParseChunkError::UnsupportedDataVersion(1343))
+    ));
+}
@@ -0,0 +1,38 @@
+use valence_server::entity::armor_stand::{ArmorStandEntityBundle, TrackerHeadRotation};
+use valence_server::entity::armor_stand_pose::{ArmorStandPart, ArmorStandPose};
+use valence_server::entity::{EntityLayerId, EulerAngle};
+
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn armor_stand_pose_is_applied() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    let pose =
+        ArmorStandPose::default().with(ArmorStandPart::Head, EulerAngle::new(45.0, 0.0, 0.0));
+
+    let armor_stand = app
+        .world_mut()
+        .spawn((
+            ArmorStandEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            pose,
+        ))
+        .id();
+
+    app.update();
+
+    assert_eq!(
+        app.world_mut()
+            .get::<TrackerHeadRotation>(armor_stand)
+            .unwrap()
+            .0,
+        EulerAngle::new(45.0, 0.0, 0.0)
+    );
+
+    let halfway = ArmorStandPose::ZERO.lerp(&pose, 0.5);
+    assert_eq!(halfway.head, EulerAngle::new(22.5, 0.0, 0.0));
+    assert_eq!(halfway.left_arm, EulerAngle::new(-5.0, 0.0, -5.0));
+}
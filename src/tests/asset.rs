@@ -0,0 +1,30 @@
+use std::fs;
+
+use crate::asset::AssetManager;
+
+#[test]
+fn asset_manager_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+
+    fs::write(&path, "spawn = [0, 64, 0]").unwrap();
+
+    let mut manager = AssetManager::new();
+    let id = manager.register(&path).unwrap();
+
+    // Registering the same file again returns the same ID.
+    assert_eq!(manager.register(&path).unwrap(), id);
+    assert_eq!(manager.get(id), Some(&b"spawn = [0, 64, 0]"[..]));
+
+    // Unchanged files aren't reported as changed.
+    assert!(!manager.reload(id).unwrap());
+
+    fs::write(&path, "spawn = [8, 70, 8]").unwrap();
+
+    assert!(manager.reload(id).unwrap());
+    assert_eq!(manager.get(id), Some(&b"spawn = [8, 70, 8]"[..]));
+
+    assert_eq!(manager.unregister(id), Some(path.canonicalize().unwrap()));
+    assert_eq!(manager.get(id), None);
+    assert!(manager.reload(id).is_err());
+}
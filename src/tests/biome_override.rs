@@ -0,0 +1,74 @@
+use crate::biome_override::BiomeOverride;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::{ChunkBiomeDataS2c, ChunkDataS2c};
+use crate::registry::biome::{BiomeId, BiomeRegistry};
+use crate::registry::RegistryIdx;
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, BlockPos};
+
+/// The encoding of a section filled with `biome`.
+fn single_biome(biome: BiomeId) -> [u8; 3] {
+    // Bits per entry, the palette, and no data.
+    [0, biome.to_index() as u8, 0]
+}
+
+#[test]
+fn biomes_are_overridden_per_client() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let biomes = scenario.app.world().resource::<BiomeRegistry>();
+    let crimson_forest = biomes.index_of(ident!("crimson_forest")).unwrap();
+    let desert = biomes.index_of(ident!("desert")).unwrap();
+
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap()
+        .insert_chunk([0, 0], UnloadedChunk::new());
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .insert(BiomeOverride::filled(crimson_forest));
+
+    scenario.app.update();
+
+    // The chunk is loaded with the overridden biomes. Each section starts with
+    // the number of non-air blocks and the block states.
+    let frames = scenario.helper.collect_received();
+    let pkt = frames.first::<ChunkDataS2c>();
+    assert_eq!(pkt.blocks_and_biomes[5..8], single_biome(crimson_forest));
+
+    // Changing the biomes in the layer keeps them overridden.
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    let top = layer.min_y() + layer.height() as i32 - 1;
+    layer.set_biome(BlockPos::new(0, top, 0), desert);
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    let pkt = frames.first::<ChunkBiomeDataS2c>();
+    assert_eq!(pkt.chunks[0].data[..3], single_biome(crimson_forest));
+
+    // Removing the override resends the real biomes. The bottom section is
+    // still all plains.
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .remove::<BiomeOverride>();
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    let pkt = frames.first::<ChunkBiomeDataS2c>();
+    assert_eq!(pkt.chunks[0].data[..3], single_biome(BiomeId::DEFAULT));
+}
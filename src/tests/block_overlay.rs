@@ -0,0 +1,80 @@
+use crate::block_overlay::BlockOverlay;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::{BlockUpdateS2c, ChunkDataS2c};
+use crate::testing::{PacketFrames, ScenarioSingleClient};
+use crate::{BlockPos, BlockState};
+
+/// Returns the block updates in `frames` in the order they were sent.
+fn block_updates(frames: &PacketFrames) -> Vec<(BlockPos, BlockState)> {
+    frames
+        .0
+        .iter()
+        .filter_map(|f| f.decode::<BlockUpdateS2c>().ok())
+        .map(|pkt| (pkt.position, pkt.block_id))
+        .collect()
+}
+
+#[test]
+fn overlaid_blocks_are_shown_to_one_client() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(3, 0, 3);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(pos, BlockState::STONE);
+
+    let mut overlay = BlockOverlay::new();
+    overlay.set(pos, BlockState::GOLD_BLOCK);
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .insert(overlay);
+
+    scenario.app.update();
+
+    // The overlay is sent after the chunk.
+    let frames = scenario.helper.collect_received();
+    frames.assert_order::<(ChunkDataS2c, BlockUpdateS2c)>();
+    assert_eq!(
+        block_updates(&frames).last(),
+        Some(&(pos, BlockState::GOLD_BLOCK))
+    );
+
+    // Changing the block underneath doesn't replace the overlaid block.
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap()
+        .set_block(pos, BlockState::DIRT);
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    assert_eq!(
+        block_updates(&frames),
+        [(pos, BlockState::DIRT), (pos, BlockState::GOLD_BLOCK)]
+    );
+
+    // Removing the block from the overlay shows the block of the layer again.
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<BlockOverlay>(scenario.client)
+        .unwrap()
+        .remove(pos);
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    assert_eq!(block_updates(&frames), [(pos, BlockState::DIRT)]);
+}
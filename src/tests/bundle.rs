@@ -0,0 +1,105 @@
+use crate::client::Client;
+use crate::entity::cow::CowEntityBundle;
+use crate::entity::EntityLayerId;
+use crate::protocol::encode::MAX_BUNDLE_PACKETS;
+use crate::protocol::packets::play::{BundleSplitterS2c, ClearTitleS2c, EntitySpawnS2c};
+use crate::protocol::{Packet, WritePacket};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn client_write_bundle() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut client = app.world_mut().get_mut::<Client>(client).unwrap();
+
+    client.write_bundle(|w| {
+        w.write_packet(&ClearTitleS2c { reset: false });
+
+        // Nested bundles are part of the outer bundle.
+        w.write_bundle(|w| w.write_packet(&ClearTitleS2c { reset: true }));
+    });
+
+    // Empty bundles aren't written.
+    client.write_bundle(|_| {});
+
+    app.update();
+
+    let recvd = helper.collect_received();
+
+    let ids: Vec<_> = recvd
+        .0
+        .iter()
+        .filter(|f| f.id == BundleSplitterS2c::ID || f.id == ClearTitleS2c::ID)
+        .map(|f| f.id)
+        .collect();
+
+    assert_eq!(
+        ids,
+        [
+            BundleSplitterS2c::ID,
+            ClearTitleS2c::ID,
+            ClearTitleS2c::ID,
+            BundleSplitterS2c::ID
+        ]
+    );
+}
+
+#[test]
+fn large_bundles_are_split() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut client = app.world_mut().get_mut::<Client>(client).unwrap();
+
+    client.write_bundle(|w| {
+        for _ in 0..=MAX_BUNDLE_PACKETS {
+            w.write_packet(&ClearTitleS2c { reset: false });
+        }
+    });
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<BundleSplitterS2c>(4);
+}
+
+#[test]
+fn entity_spawn_is_bundled() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut().spawn(CowEntityBundle {
+        layer: EntityLayerId(layer),
+        ..Default::default()
+    });
+
+    app.update();
+
+    let recvd = helper.collect_received();
+
+    recvd.assert_count::<EntitySpawnS2c>(1);
+    recvd.assert_count::<BundleSplitterS2c>(2);
+}
@@ -0,0 +1,63 @@
+use crate::client::Client;
+use crate::message::SendMessage;
+use crate::protocol::packets::play::ProfilelessChatMessageS2c;
+use crate::registry::chat_type::{ChatType, ChatTypeId, ChatTypeParameter, ChatTypeRegistry};
+use crate::registry::RegistryCodec;
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, Text};
+
+#[test]
+fn custom_chat_types_are_sent_by_id() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let registry = app.world().resource::<ChatTypeRegistry>();
+
+    assert_eq!(registry.index_of(ident!("chat")), Some(ChatTypeId::DEFAULT));
+
+    let chat_type = app
+        .world_mut()
+        .resource_mut::<ChatTypeRegistry>()
+        .insert(
+            ident!("valence:team"),
+            ChatType::new(
+                "chat.type.team.text",
+                [
+                    ChatTypeParameter::Target,
+                    ChatTypeParameter::Sender,
+                    ChatTypeParameter::Content,
+                ],
+            ),
+        )
+        .unwrap();
+
+    app.update();
+
+    let codec = app.world().resource::<RegistryCodec>();
+
+    assert!(codec
+        .registry(ChatTypeRegistry::KEY)
+        .iter()
+        .any(|value| value.name == ident!("valence:team")));
+
+    helper.clear_received();
+
+    app.world_mut()
+        .get_mut::<Client>(client)
+        .unwrap()
+        .send_targeted_chat_type_message(chat_type, "Alice", "Red Team", "Hello");
+
+    app.update();
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<ProfilelessChatMessageS2c>(1);
+
+    let pkt = recvd.first::<ProfilelessChatMessageS2c>();
+    assert_eq!(pkt.chat_type.0, chat_type.to_raw());
+    assert_eq!(pkt.chat_type_name.as_ref(), &Text::from("Alice"));
+    assert_eq!(pkt.target_name.as_deref(), Some(&Text::from("Red Team")));
+}
@@ -0,0 +1,115 @@
+use bevy_ecs::prelude::*;
+
+use crate::cinematic::{CameraMode, CameraPath, Cinematic, CinematicEndEvent};
+use crate::client::Client;
+use crate::entity::{EntityId, Look, Position};
+use crate::math::DVec3;
+use crate::protocol::packets::play::SetCameraEntityS2c;
+use crate::testing::ScenarioSingleClient;
+
+fn path() -> CameraPath {
+    CameraPath::new()
+        .with_keyframe(0, [0.0, 10.0, 0.0], Look::new(170.0, 0.0))
+        .with_keyframe(4, [4.0, 10.0, 0.0], Look::new(-170.0, 20.0))
+        .with_keyframe(8, [4.0, 10.0, 4.0], Look::new(-150.0, 20.0))
+}
+
+#[test]
+fn camera_path_sampling() {
+    let path = path();
+
+    assert_eq!(path.duration(), 8);
+
+    // Keyframes are passed through exactly, and the ends are clamped.
+    assert_eq!(path.sample(-5.0).unwrap().0, DVec3::new(0.0, 10.0, 0.0));
+    assert_eq!(path.sample(4.0).unwrap().0, DVec3::new(4.0, 10.0, 0.0));
+    assert_eq!(path.sample(100.0).unwrap().0, DVec3::new(4.0, 10.0, 4.0));
+
+    // The yaw takes the short way around through 180.
+    let (pos, look) = path.sample(2.0).unwrap();
+    assert!(pos.x > 0.0 && pos.x < 4.0);
+    assert!((look.yaw - 180.0).abs() < 1e-4);
+    assert!((look.pitch - 10.0).abs() < 1e-4);
+
+    assert!(CameraPath::new().sample(0.0).is_none());
+}
+
+#[test]
+fn cinematic_teleports_client() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .insert(Cinematic::new(path(), CameraMode::Teleport));
+
+    for tick in 0..=8 {
+        scenario.app.update();
+
+        let expected = path().sample(f64::from(tick)).unwrap();
+        let client = scenario.app.world().entity(scenario.client);
+
+        assert_eq!(client.get::<Position>().unwrap().0, expected.0);
+        assert_eq!(*client.get::<Look>().unwrap(), expected.1);
+    }
+
+    let ends: Vec<_> = scenario
+        .app
+        .world()
+        .resource::<Events<CinematicEndEvent>>()
+        .iter_current_update_events()
+        .collect();
+
+    assert_eq!(
+        ends,
+        [&CinematicEndEvent {
+            client: scenario.client
+        }]
+    );
+    assert!(scenario
+        .app
+        .world()
+        .get::<Cinematic>(scenario.client)
+        .is_none());
+}
+
+#[test]
+fn cinematic_camera_entity() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .insert(Cinematic::new(path(), CameraMode::Entity));
+
+    scenario.app.update();
+
+    // The client spectates the camera.
+    let frames = scenario.helper.collect_received();
+    let pkt = frames.first::<SetCameraEntityS2c>();
+
+    let camera = scenario
+        .app
+        .world_mut()
+        .query_filtered::<&EntityId, Without<Client>>()
+        .iter(scenario.app.world())
+        .find(|id| id.get() == pkt.entity_id.0)
+        .copied();
+
+    assert!(camera.is_some());
+
+    // Stopping early gives the client its own viewpoint back.
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .remove::<Cinematic>();
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    let pkt = frames.first::<SetCameraEntityS2c>();
+    assert_eq!(pkt.entity_id.0, 0);
+}
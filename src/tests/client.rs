@@ -1,12 +1,34 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_ecs::schedule::ExecutorKind;
+
 use crate::abilities::PlayerAbilitiesFlags;
+use crate::brand::ClientBrand;
+use crate::client::{Client, ClientLoginEvent, ClientSpan, DisconnectClient, ProtocolVersion};
+use crate::client_settings::{ClientSettings, SettingsChangedEvent};
+use crate::entity::entity::Flags;
+use crate::entity::player::{MainArm, PlayerModelParts};
+use crate::entity::Position;
+use crate::game_mode::{GameModeChangeEvent, Spectator};
 use crate::layer::chunk::UnloadedChunk;
 use crate::layer::ChunkLayer;
-use crate::math::DVec3;
+use crate::math::{DVec3, Vec3};
+use crate::movement::VelocityAuthority;
+use crate::protocol::packets::play::client_settings_c2s::{
+    ChatMode, DisplayedSkinParts, MainArm as ClientMainArm,
+};
 use crate::protocol::packets::play::{
-    FullC2s, MoveRelativeS2c, PlayerPositionLookS2c, TeleportConfirmC2s,
+    ClientSettingsC2s, CustomPayloadC2s, DisconnectS2c, EntityVelocityUpdateS2c, FullC2s,
+    GameJoinS2c, GameStateChangeS2c, LookAndOnGroundC2s, MoveRelativeS2c, PlayPingS2c, PlayPongC2s,
+    PlayerAbilitiesS2c, PlayerPositionLookS2c, PositionAndOnGroundC2s, TeleportConfirmC2s,
 };
-use crate::testing::{create_mock_client, ScenarioSingleClient};
-use crate::{ChunkPos, GameMode};
+use crate::protocol::{Bounded, Encode, RawBytes};
+use crate::spawn::PrevGameMode;
+use crate::testing::{create_mock_client, MockClientHelper, ScenarioSingleClient};
+use crate::{ident, ChunkPos, GameMode, PROTOCOL_VERSION};
 
 #[test]
 fn client_teleport_and_move() {
@@ -101,3 +123,464 @@ fn client_gamemode_changed_ability() {
     assert!(!abilities.instant_break());
     assert!(!abilities.invulnerable());
 }
+
+#[test]
+fn spectator_game_mode_transition() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    *app.world_mut().get_mut::<GameMode>(client).unwrap() = GameMode::Spectator;
+
+    app.update();
+
+    // The new abilities are sent after the game mode.
+    let frames = helper.collect_received();
+    frames.assert_count::<PlayerAbilitiesS2c>(1);
+    frames.assert_order::<(GameStateChangeS2c, PlayerAbilitiesS2c)>();
+
+    let abilities = app.world().get::<PlayerAbilitiesFlags>(client).unwrap();
+    assert!(abilities.allow_flying());
+    assert!(abilities.flying());
+
+    assert!(app.world().get::<Flags>(client).unwrap().invisible());
+    assert_eq!(
+        app.world().get::<PrevGameMode>(client).unwrap().0,
+        Some(GameMode::Survival)
+    );
+
+    let events = app
+        .world()
+        .resource::<Events<GameModeChangeEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [&GameModeChangeEvent {
+            client,
+            old: GameMode::Survival,
+            new: GameMode::Spectator,
+        }]
+    );
+
+    *app.world_mut().get_mut::<GameMode>(client).unwrap() = GameMode::Survival;
+
+    app.update();
+
+    let abilities = app.world().get::<PlayerAbilitiesFlags>(client).unwrap();
+    assert!(!abilities.allow_flying());
+    assert!(!abilities.flying());
+
+    assert!(!app.world().get::<Flags>(client).unwrap().invisible());
+    assert_eq!(
+        app.world().get::<PrevGameMode>(client).unwrap().0,
+        Some(GameMode::Spectator)
+    );
+}
+
+#[test]
+fn spectators_are_marked() {
+    let ScenarioSingleClient {
+        mut app, client, ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    assert!(!app.world().entity(client).contains::<Spectator>());
+
+    *app.world_mut().get_mut::<GameMode>(client).unwrap() = GameMode::Spectator;
+
+    app.update();
+
+    assert!(app.world().entity(client).contains::<Spectator>());
+
+    *app.world_mut().get_mut::<GameMode>(client).unwrap() = GameMode::Creative;
+
+    app.update();
+
+    assert!(!app.world().entity(client).contains::<Spectator>());
+}
+
+/// Applies one block per tick of velocity along the x axis, and returns the ID
+/// of the ping sent after it.
+fn apply_velocity(app: &mut App, client: Entity, helper: &mut MockClientHelper) -> i32 {
+    app.update();
+
+    helper.send(&TeleportConfirmC2s {
+        teleport_id: 0.into(),
+    });
+
+    let mut authority = VelocityAuthority::default();
+    authority.apply(Vec3::new(20.0, 0.0, 0.0));
+    app.world_mut().entity_mut(client).insert(authority);
+
+    app.update();
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<EntityVelocityUpdateS2c>(1);
+    recvd.assert_order::<(EntityVelocityUpdateS2c, PlayPingS2c)>();
+
+    recvd.first::<PlayPingS2c>().id
+}
+
+#[test]
+fn velocity_authority_rejects_ignored_knockback() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let ping_id = apply_velocity(&mut app, client, &mut helper);
+
+    // The client receives the velocity, but stays in place instead of being
+    // knocked back.
+    helper.send(&PlayPongC2s { id: ping_id });
+
+    for _ in 0..3 {
+        helper.send(&PositionAndOnGroundC2s {
+            position: DVec3::ZERO,
+            on_ground: false,
+        });
+
+        app.update();
+    }
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerPositionLookS2c>(1);
+
+    let pos = app.world_mut().get::<Position>(client).unwrap().0;
+    assert!((pos.x - 1.91).abs() < 1e-6);
+    assert!(!app
+        .world_mut()
+        .get::<VelocityAuthority>(client)
+        .unwrap()
+        .is_active());
+}
+
+#[test]
+fn velocity_authority_waits_for_lagging_client() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let ping_id = apply_velocity(&mut app, client, &mut helper);
+
+    // The velocity hasn't reached the client yet, so it keeps sending its old
+    // position, and looks around.
+    for _ in 0..5 {
+        helper.send(&PositionAndOnGroundC2s {
+            position: DVec3::ZERO,
+            on_ground: false,
+        });
+        helper.send(&LookAndOnGroundC2s {
+            yaw: 90.0,
+            pitch: 0.0,
+            on_ground: false,
+        });
+
+        app.update();
+    }
+
+    // Once it has received the velocity, it follows it.
+    helper.send(&PlayPongC2s { id: ping_id });
+
+    for x in [0.0, 1.0, 1.91] {
+        helper.send(&PositionAndOnGroundC2s {
+            position: DVec3::new(x, 0.0, 0.0),
+            on_ground: false,
+        });
+        // Look-only packets don't move the expected path.
+        helper.send(&LookAndOnGroundC2s {
+            yaw: 90.0,
+            pitch: 0.0,
+            on_ground: false,
+        });
+
+        app.update();
+    }
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerPositionLookS2c>(0);
+
+    let pos = app.world_mut().get::<Position>(client).unwrap().0;
+    assert!((pos.x - 1.91).abs() < 1e-6);
+    assert!(app
+        .world_mut()
+        .get::<VelocityAuthority>(client)
+        .unwrap()
+        .is_active());
+}
+
+#[test]
+fn client_brand_and_protocol_version() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    assert_eq!(
+        app.world().get::<ProtocolVersion>(client).unwrap().0,
+        PROTOCOL_VERSION
+    );
+
+    app.update();
+
+    assert!(app.world().get::<ClientBrand>(client).is_none());
+
+    let mut data = vec![];
+    "fabric".encode(&mut data).unwrap();
+
+    helper.send(&CustomPayloadC2s {
+        channel: ident!("minecraft:brand").into(),
+        data: Bounded(RawBytes(&data)),
+    });
+
+    app.update();
+
+    let brand = app.world().get::<ClientBrand>(client).unwrap();
+
+    assert_eq!(brand.0, "fabric");
+    assert!(!brand.is_vanilla());
+}
+
+#[test]
+fn client_settings_change() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    helper.send(&ClientSettingsC2s {
+        locale: "en_us",
+        view_distance: 8,
+        chat_mode: ChatMode::Enabled,
+        chat_colors: true,
+        displayed_skin_parts: DisplayedSkinParts::new().with_cape(true).with_hat(true),
+        main_arm: ClientMainArm::Left,
+        enable_text_filtering: false,
+        allow_server_listings: true,
+    });
+
+    app.update();
+
+    let settings = app.world().get::<ClientSettings>(client).unwrap();
+    assert_eq!(&*settings.locale, "en_us");
+    assert_eq!(settings.view_distance, 8);
+
+    let model_parts = app.world().get::<PlayerModelParts>(client).unwrap();
+    assert_eq!(
+        model_parts.0 as u8,
+        u8::from(DisplayedSkinParts::new().with_cape(true).with_hat(true))
+    );
+    assert_eq!(app.world().get::<MainArm>(client).unwrap().0, 0);
+
+    let events = app
+        .world()
+        .resource::<Events<SettingsChangedEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client);
+    assert_eq!(events[0].old, ClientSettings::default());
+    assert_eq!(events[0].old_model_parts.0, 0);
+    assert_eq!(events[0].old_main_arm.0, 1);
+}
+
+#[test]
+fn settings_changed_event_has_old_skin_parts_and_main_arm() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let mut pkt = ClientSettingsC2s {
+        locale: "en_us",
+        view_distance: 8,
+        chat_mode: ChatMode::Enabled,
+        chat_colors: true,
+        displayed_skin_parts: DisplayedSkinParts::new().with_cape(true),
+        main_arm: ClientMainArm::Left,
+        enable_text_filtering: false,
+        allow_server_listings: true,
+    };
+
+    helper.send(&pkt);
+    app.update();
+
+    // Only the skin parts and main arm change.
+    pkt.displayed_skin_parts = DisplayedSkinParts::new().with_hat(true);
+    pkt.main_arm = ClientMainArm::Right;
+
+    helper.send(&pkt);
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<SettingsChangedEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].old_model_parts.0 as u8,
+        u8::from(DisplayedSkinParts::new().with_cape(true))
+    );
+    assert_eq!(events[0].old_main_arm.0, 0);
+    assert_eq!(
+        &events[0].old,
+        app.world().get::<ClientSettings>(client).unwrap()
+    );
+
+    let model_parts = app.world().get::<PlayerModelParts>(client).unwrap();
+    assert_eq!(
+        model_parts.0 as u8,
+        u8::from(DisplayedSkinParts::new().with_hat(true))
+    );
+    assert_eq!(app.world().get::<MainArm>(client).unwrap().0, 1);
+}
+
+#[test]
+fn clients_kicked_on_login_never_join() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_systems(
+        Update,
+        |mut events: EventReader<ClientLoginEvent>, mut commands: Commands| {
+            for event in events.read() {
+                commands.add(DisconnectClient {
+                    client: event.client,
+                    reason: "Banned".into(),
+                });
+            }
+        },
+    );
+
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<ClientLoginEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].client, client);
+    assert_eq!(events[0].username, "test");
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<DisconnectS2c>(1);
+    recvd.assert_count::<GameJoinS2c>(0);
+}
+
+/// Collects the logs written by a [`tracing_subscriber`] for inspection.
+#[derive(Clone, Default)]
+struct LogWriter(Arc<Mutex<Vec<u8>>>);
+
+impl LogWriter {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.0.lock().unwrap())
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl io::Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn client_span_logs_joins_and_leaves() {
+    let logs = LogWriter::default();
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .without_time()
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let ScenarioSingleClient {
+            mut app, client, ..
+        } = ScenarioSingleClient::new();
+
+        // The subscriber is only the default on this thread.
+        app.edit_schedule(PreUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        })
+        .edit_schedule(PostUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+
+        let span = app.world().get::<ClientSpan>(client).unwrap();
+        assert_eq!(span.0.metadata().unwrap().name(), "client");
+
+        app.update();
+
+        let joined = logs
+            .lines()
+            .into_iter()
+            .filter(|line| line.contains("client joined"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(joined.len(), 1);
+        assert!(joined[0].contains("client{"));
+        assert!(joined[0].contains("test"));
+
+        app.world_mut().entity_mut(client).remove::<Client>();
+        app.update();
+
+        let left = logs
+            .lines()
+            .into_iter()
+            .filter(|line| line.contains("client left"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(left.len(), 1);
+        assert!(left[0].contains("client{"));
+        assert!(left[0].contains("test"));
+
+        // The span stays after the client leaves.
+        assert!(app.world().get::<ClientSpan>(client).is_some());
+    });
+}
@@ -0,0 +1,90 @@
+use bevy_app::App;
+use bevy_ecs::entity::Entity;
+use valence_server::client_command::PlayerInput;
+use valence_server::protocol::packets::play::client_command_c2s::ClientCommand;
+use valence_server::protocol::packets::play::ClientCommandC2s;
+use valence_server::protocol::VarInt;
+
+use crate::testing::ScenarioSingleClient;
+
+fn command(action: ClientCommand, jump_boost: i32) -> ClientCommandC2s {
+    ClientCommandC2s {
+        entity_id: VarInt(0),
+        action,
+        jump_boost: VarInt(jump_boost),
+    }
+}
+
+fn input(app: &App, client: Entity) -> PlayerInput {
+    *app.world().get::<PlayerInput>(client).unwrap()
+}
+
+#[test]
+fn player_input_tracks_client_commands() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    assert_eq!(input(&app, client), PlayerInput::default());
+
+    helper.send(&command(ClientCommand::StartSneaking, 0));
+    helper.send(&command(ClientCommand::StartSprinting, 0));
+    helper.send(&command(ClientCommand::StartJumpWithHorse, 60));
+    app.update();
+
+    assert_eq!(
+        input(&app, client),
+        PlayerInput {
+            sneaking: true,
+            sprinting: true,
+            jumping_with_horse: Some(60),
+        }
+    );
+
+    helper.send(&command(ClientCommand::StopSneaking, 0));
+    helper.send(&command(ClientCommand::StopJumpWithHorse, 0));
+    app.update();
+
+    assert_eq!(
+        input(&app, client),
+        PlayerInput {
+            sneaking: false,
+            sprinting: true,
+            jumping_with_horse: None,
+        }
+    );
+}
+
+#[test]
+fn player_input_only_changes_with_new_input() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    helper.send(&command(ClientCommand::StartSneaking, 0));
+    app.update();
+
+    let last_changed = |app: &App| {
+        app.world()
+            .entity(client)
+            .get_ref::<PlayerInput>()
+            .unwrap()
+            .last_changed()
+    };
+
+    let changed = last_changed(&app);
+
+    // Repeating the same command doesn't change the input.
+    helper.send(&command(ClientCommand::StartSneaking, 0));
+    app.update();
+
+    assert_eq!(last_changed(&app), changed);
+}
@@ -0,0 +1,73 @@
+use valence_server::nbt::Value;
+use valence_server::op_level::OpLevel;
+use valence_server::protocol::packets::play::update_command_block_c2s::{
+    UpdateCommandBlockFlags, UpdateCommandBlockMode,
+};
+use valence_server::protocol::packets::play::UpdateCommandBlockC2s;
+
+use crate::block::BlockKind;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState, GameMode};
+
+fn command_of(scenario: &mut ScenarioSingleClient, pos: BlockPos) -> Option<String> {
+    let layer = scenario
+        .app
+        .world_mut()
+        .get::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    match layer.block(pos)?.nbt?.get("Command")? {
+        Value::String(command) => Some(command.clone()),
+        _ => None,
+    }
+}
+
+#[test]
+fn edit_command_block() {
+    let mut scenario = ScenarioSingleClient::new();
+    let pos = BlockPos::new(1, 0, 1);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(pos, BlockState::COMMAND_BLOCK);
+
+    scenario.app.update();
+
+    let packet = UpdateCommandBlockC2s {
+        position: pos,
+        command: "say hello",
+        mode: UpdateCommandBlockMode::Auto,
+        flags: UpdateCommandBlockFlags::new().with_track_output(true),
+    };
+
+    // Clients need to be operators in creative mode to edit command blocks.
+    scenario.helper.send(&packet);
+    scenario.app.update();
+
+    assert_eq!(command_of(&mut scenario, pos), None);
+
+    let mut client = scenario.app.world_mut().entity_mut(scenario.client);
+    client.get_mut::<OpLevel>().unwrap().set(2);
+    *client.get_mut::<GameMode>().unwrap() = GameMode::Creative;
+
+    scenario.helper.send(&packet);
+    scenario.app.update();
+
+    assert_eq!(command_of(&mut scenario, pos).as_deref(), Some("say hello"));
+
+    let state = scenario
+        .app
+        .world_mut()
+        .get::<ChunkLayer>(scenario.layer)
+        .unwrap()
+        .block(pos)
+        .unwrap()
+        .state;
+    assert_eq!(state.to_kind(), BlockKind::RepeatingCommandBlock);
+}
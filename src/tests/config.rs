@@ -0,0 +1,61 @@
+use std::fs;
+
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+use serde::Deserialize;
+
+use crate::config::{AddConfig, ConfigPlugin};
+
+#[derive(Resource, Deserialize, Default, PartialEq, Debug)]
+struct LobbySettings {
+    max_players: u32,
+    pvp: bool,
+}
+
+#[derive(Resource, Deserialize, Default, PartialEq, Debug)]
+struct ArenaSettings {
+    rounds: u32,
+}
+
+#[test]
+fn config_sections_bind_to_resources() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("valence.toml");
+
+    fs::write(&path, "[lobby]\nmax_players = 20\npvp = true\n").unwrap();
+
+    let mut app = App::new();
+
+    app.add_plugins(ConfigPlugin::new(&path))
+        .add_config::<LobbySettings>("lobby")
+        .add_config::<ArenaSettings>("arena");
+
+    assert_eq!(
+        app.world().resource::<LobbySettings>(),
+        &LobbySettings {
+            max_players: 20,
+            pvp: true
+        }
+    );
+
+    // Missing sections use the default value.
+    assert_eq!(
+        app.world().resource::<ArenaSettings>(),
+        &ArenaSettings::default()
+    );
+}
+
+#[test]
+fn missing_config_file_uses_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut app = App::new();
+
+    app.add_plugins(ConfigPlugin::new(dir.path().join("missing.toml")))
+        .add_config::<LobbySettings>("lobby");
+
+    assert_eq!(
+        app.world().resource::<LobbySettings>(),
+        &LobbySettings::default()
+    );
+}
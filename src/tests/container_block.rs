@@ -0,0 +1,108 @@
+use bevy_app::App;
+
+use crate::block::{PropName, PropValue};
+use crate::inventory::container_block::{ContainerAnimations, ContainerBlock};
+use crate::inventory::{Inventory, InventoryKind, OpenInventory};
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::{BlockEventS2c, PlaySoundS2c};
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState};
+
+const CONTAINER_POS: BlockPos = BlockPos::new(1, 0, 1);
+
+#[test]
+fn chest_opens_and_closes() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(CONTAINER_POS, BlockState::CHEST);
+
+    let chest = app
+        .world_mut()
+        .spawn((
+            Inventory::new(InventoryKind::Generic9x3),
+            ContainerBlock::new(layer, CONTAINER_POS),
+        ))
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(OpenInventory::new(chest));
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlaySoundS2c>(1);
+    let pkt = frames.first::<BlockEventS2c>();
+    assert_eq!(pkt.position, CONTAINER_POS);
+    assert_eq!((pkt.action_id, pkt.action_parameter), (1, 1));
+
+    // Sounds can be turned off for each kind of container.
+    app.world_mut()
+        .resource_mut::<ContainerAnimations>()
+        .chest
+        .sound = false;
+
+    app.world_mut().entity_mut(client).remove::<OpenInventory>();
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<PlaySoundS2c>(0);
+    let pkt = frames.first::<BlockEventS2c>();
+    assert_eq!((pkt.action_id, pkt.action_parameter), (1, 0));
+}
+
+#[test]
+fn barrel_changes_state() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(CONTAINER_POS, BlockState::BARREL);
+
+    let barrel = app
+        .world_mut()
+        .spawn((
+            Inventory::new(InventoryKind::Generic9x3),
+            ContainerBlock::new(layer, CONTAINER_POS),
+        ))
+        .id();
+
+    app.update();
+
+    let open = |app: &App| {
+        app.world()
+            .get::<ChunkLayer>(layer)
+            .unwrap()
+            .block(CONTAINER_POS)
+            .unwrap()
+            .state
+            .get(PropName::Open)
+    };
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(OpenInventory::new(barrel));
+    app.update();
+
+    assert_eq!(open(&app), Some(PropValue::True));
+
+    app.world_mut().entity_mut(client).remove::<OpenInventory>();
+    app.update();
+
+    assert_eq!(open(&app), Some(PropValue::False));
+}
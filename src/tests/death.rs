@@ -0,0 +1,90 @@
+use valence_server::death::{Dead, DeathPlugin};
+use valence_server::entity::living::Health;
+use valence_server::game_rules::GameRules;
+use valence_server::protocol::packets::play::{
+    ClientStatusC2s, DeathMessageS2c, InventoryS2c, PlayerRespawnS2c,
+};
+
+use crate::inventory::Inventory;
+use crate::testing::ScenarioSingleClient;
+use crate::{ItemKind, ItemStack};
+
+#[test]
+fn death_and_respawn() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(DeathPlugin);
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Diamond, 3, None));
+    app.world_mut().get_mut::<Health>(client).unwrap().0 = 0.0;
+
+    app.update();
+    app.update();
+
+    assert!(app.world_mut().get::<Dead>(client).is_some());
+    assert!(app
+        .world_mut()
+        .get::<Inventory>(client)
+        .unwrap()
+        .slot(36)
+        .is_empty());
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<DeathMessageS2c>(1);
+
+    helper.send(&ClientStatusC2s::PerformRespawn);
+
+    app.update();
+
+    assert!(app.world_mut().get::<Dead>(client).is_none());
+    assert_eq!(app.world_mut().get::<Health>(client).unwrap().0, 20.0);
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<PlayerRespawnS2c>(1);
+    sent_packets.assert_count::<InventoryS2c>(1);
+}
+
+#[test]
+fn keep_inventory() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(DeathPlugin);
+
+    app.world_mut().entity_mut(layer).insert(GameRules {
+        keep_inventory: true,
+        ..Default::default()
+    });
+
+    app.update();
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Diamond, 3, None));
+    app.world_mut().get_mut::<Health>(client).unwrap().0 = 0.0;
+
+    app.update();
+    app.update();
+
+    assert!(app.world_mut().get::<Dead>(client).is_some());
+    assert_eq!(
+        app.world_mut().get::<Inventory>(client).unwrap().slot(36),
+        &ItemStack::new(ItemKind::Diamond, 3, None)
+    );
+}
@@ -0,0 +1,27 @@
+use valence_server::debug_shapes::DebugShapes;
+use valence_server::protocol::packets::play::ParticleS2c;
+
+use crate::math::{DVec3, Vec3};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn draw_line_for_client() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut shapes = DebugShapes::new();
+    shapes.line(DVec3::ZERO, DVec3::new(1.0, 0.0, 0.0), Vec3::X);
+    app.world_mut().entity_mut(client).insert(shapes);
+
+    app.update();
+
+    // One particle every 0.25 blocks, including both ends.
+    helper.collect_received().assert_count::<ParticleS2c>(5);
+}
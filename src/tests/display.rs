@@ -0,0 +1,118 @@
+use valence_server::entity::block_display::BlockDisplayEntityBundle;
+use valence_server::entity::display::{InterpolationDuration, Scale, Translation};
+use valence_server::entity::display_transform::{DisplayInterpolation, DisplayTransform};
+use valence_server::entity::passengers::{Passengers, Vehicle};
+use valence_server::entity::zombie::ZombieEntityBundle;
+use valence_server::entity::{EntityId, EntityLayerId, Position};
+use valence_server::protocol::packets::play::EntityPassengersSetS2c;
+
+use crate::math::{DVec3, Quat, Vec3};
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn display_transform_is_packed() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    let display = app
+        .world_mut()
+        .spawn((
+            BlockDisplayEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            DisplayTransform::from_translation(Vec3::new(0.0, 1.0, 0.0))
+                .with_scale(Vec3::splat(2.0)),
+            DisplayInterpolation::new(10),
+        ))
+        .id();
+
+    app.update();
+
+    let entity = app.world_mut().entity(display);
+    assert_eq!(
+        entity.get::<Translation>().unwrap().0,
+        Vec3::new(0.0, 1.0, 0.0)
+    );
+    assert_eq!(entity.get::<Scale>().unwrap().0, Vec3::splat(2.0));
+    assert_eq!(entity.get::<InterpolationDuration>().unwrap().0, 10);
+
+    let transform = *entity.get::<DisplayTransform>().unwrap();
+    let matrix = transform.to_matrix();
+    let decomposed = DisplayTransform::from_matrix(matrix);
+    assert!(decomposed.scale.abs_diff_eq(transform.scale, 1e-6));
+    assert!(decomposed
+        .translation
+        .abs_diff_eq(transform.translation, 1e-6));
+    assert!(decomposed.left_rotation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+}
+
+#[test]
+fn attach_display_to_entity() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let zombie = app
+        .world_mut()
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position(DVec3::new(1.0, 2.0, 3.0)),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    let display = app
+        .world_mut()
+        .spawn((
+            BlockDisplayEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            Vehicle::new(zombie),
+        ))
+        .id();
+
+    app.update();
+
+    assert_eq!(
+        app.world_mut().get::<Position>(display).unwrap().0,
+        DVec3::new(1.0, 2.0, 3.0)
+    );
+    assert_eq!(
+        app.world_mut()
+            .get::<Passengers>(zombie)
+            .unwrap()
+            .entities(),
+        [display]
+    );
+
+    let zombie_id = app.world_mut().get::<EntityId>(zombie).unwrap().get();
+    let display_id = app.world_mut().get::<EntityId>(display).unwrap().get();
+
+    let pkt = helper.collect_received().first::<EntityPassengersSetS2c>();
+    assert_eq!(pkt.entity_id.0, zombie_id);
+    assert_eq!(
+        pkt.passengers.iter().map(|id| id.0).collect::<Vec<_>>(),
+        [display_id]
+    );
+
+    // Dismounting empties the passengers of the vehicle.
+    app.world_mut().entity_mut(display).remove::<Vehicle>();
+    app.update();
+
+    assert!(app
+        .world_mut()
+        .get::<Passengers>(zombie)
+        .unwrap()
+        .is_empty());
+
+    let pkt = helper.collect_received().first::<EntityPassengersSetS2c>();
+    assert_eq!(pkt.entity_id.0, zombie_id);
+    assert!(pkt.passengers.is_empty());
+}
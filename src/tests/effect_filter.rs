@@ -0,0 +1,136 @@
+use crate::effect_filter::{EffectScope, ParticleFilter, SoundFilter};
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::particle_s2c::Particle;
+use crate::protocol::packets::play::{ParticleS2c, PlaySoundS2c};
+use crate::protocol::sound::{Sound, SoundCategory};
+use crate::testing::ScenarioSingleClient;
+
+fn play_effects(scenario: &mut ScenarioSingleClient) {
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    for pos in [[2.0, 0.0, 2.0], [40.0, 0.0, 2.0]] {
+        layer.play_particle(&Particle::Flame, false, pos, [0.0; 3], 0.0, 1);
+        layer.play_sound(
+            Sound::BlockNoteBlockBass,
+            SoundCategory::Block,
+            pos,
+            1.0,
+            1.0,
+        );
+        layer.play_sound(
+            Sound::BlockNoteBlockBass,
+            SoundCategory::Player,
+            pos,
+            1.0,
+            1.0,
+        );
+    }
+}
+
+#[test]
+fn effects_are_filtered_per_client() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario.app.update();
+    scenario.helper.clear_received();
+
+    play_effects(&mut scenario);
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    frames.assert_count::<ParticleS2c>(2);
+    frames.assert_count::<PlaySoundS2c>(4);
+
+    let mut sound_filter = SoundFilter::default();
+    sound_filter.mute(SoundCategory::Block);
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(scenario.client)
+        .insert((
+            ParticleFilter {
+                enabled: true,
+                max_distance: Some(16.0),
+            },
+            sound_filter,
+        ));
+
+    play_effects(&mut scenario);
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    frames.assert_count::<ParticleS2c>(1);
+    frames.assert_count::<PlaySoundS2c>(2);
+
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<ParticleFilter>(scenario.client)
+        .unwrap()
+        .enabled = false;
+
+    play_effects(&mut scenario);
+    scenario.app.update();
+
+    scenario
+        .helper
+        .collect_received()
+        .assert_count::<ParticleS2c>(0);
+}
+
+#[test]
+fn effects_are_scoped_per_call() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario.app.update();
+    scenario.helper.clear_received();
+
+    let client = scenario.client;
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    for pos in [[2.0, 0.0, 2.0], [40.0, 0.0, 2.0]] {
+        layer.play_particle_scoped(
+            &Particle::Flame,
+            false,
+            pos,
+            [0.0; 3],
+            0.0,
+            1,
+            EffectScope::new().with_radius(16),
+        );
+    }
+
+    layer.play_sound_scoped(
+        Sound::BlockNoteBlockBass,
+        SoundCategory::Block,
+        [2.0, 0.0, 2.0],
+        1.0,
+        1.0,
+        EffectScope::new().with_except(client),
+    );
+
+    layer.set_sound_volume(SoundCategory::Player, 0.5);
+    layer.play_sound(
+        Sound::BlockNoteBlockBass,
+        SoundCategory::Player,
+        [2.0, 0.0, 2.0],
+        0.8,
+        1.0,
+    );
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+    frames.assert_count::<ParticleS2c>(1);
+    frames.assert_count::<PlaySoundS2c>(1);
+    assert_eq!(frames.first::<PlaySoundS2c>().volume, 0.4);
+}
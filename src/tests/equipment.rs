@@ -6,10 +6,11 @@ use valence_server::entity::item::ItemEntityBundle;
 use valence_server::entity::zombie::ZombieEntityBundle;
 use valence_server::entity::{EntityLayerId, Position};
 use valence_server::math::DVec3;
+use valence_server::protocol::packets::play::client_settings_c2s::MainArm;
 use valence_server::protocol::packets::play::{
     ClickSlotC2s, EntityEquipmentUpdateS2c, UpdateSelectedSlotC2s,
 };
-use valence_server::{ItemKind, ItemStack};
+use valence_server::{Hand, ItemKind, ItemStack};
 
 use crate::testing::ScenarioSingleClient;
 
@@ -541,3 +542,25 @@ fn test_held_item_change_from_client() {
         &ItemStack::new(ItemKind::IronSword, 1, None)
     );
 }
+
+#[test]
+fn test_hands_mirror_for_left_handed_players() {
+    let sword = ItemStack::new(ItemKind::DiamondSword, 1, None);
+    let shield = ItemStack::new(ItemKind::Shield, 1, None);
+
+    let mut equipment = Equipment::default();
+    equipment.set_hand(Hand::Main, sword.clone());
+    equipment.set_hand(Hand::Off, shield.clone());
+
+    // A left-handed player holds their main hand item in the left arm.
+    assert_eq!(Hand::Main.arm(MainArm::Left), MainArm::Left);
+    assert_eq!(Hand::Off.arm(MainArm::Left), MainArm::Right);
+    assert_eq!(Hand::from_arm(MainArm::Right, MainArm::Left), Hand::Off);
+    assert_eq!(Hand::Main.opposite(), Hand::Off);
+
+    // A right-handed entity mirroring them holds the items the other way round.
+    equipment.swap_hands();
+
+    assert_eq!(equipment.hand(Hand::Main), &shield);
+    assert_eq!(equipment.hand(Hand::Off), &sword);
+}
@@ -0,0 +1,104 @@
+use valence_server::entity::Position;
+use valence_server::experience::{
+    split_experience, Experience, ExperienceOrbBundle, ExperiencePlugin,
+};
+use valence_server::protocol::packets::play::{ExperienceBarUpdateS2c, ItemPickupAnimationS2c};
+use valence_server::protocol::VarInt;
+use valence_server::Despawned;
+
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn experience_orb_pickup() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(ExperiencePlugin);
+
+    app.update();
+    helper.clear_received();
+
+    let orb = app
+        .world_mut()
+        .spawn(ExperienceOrbBundle::new(layer, [0.0, 0.5, 0.0], 10))
+        .id();
+
+    app.update();
+
+    let experience = *app.world_mut().get::<Experience>(client).unwrap();
+    assert_eq!(experience.level, 1);
+    assert_eq!(experience.total, 10);
+    assert!((experience.progress - 3.0 / 9.0).abs() < 1e-6);
+
+    if let Some(orb) = app.world().get_entity(orb) {
+        assert!(orb.contains::<Despawned>());
+    }
+
+    let sent_packets = helper.collect_received();
+
+    sent_packets.assert_count::<ItemPickupAnimationS2c>(1);
+    sent_packets.assert_count::<ExperienceBarUpdateS2c>(1);
+
+    let packet = sent_packets.first::<ExperienceBarUpdateS2c>();
+    assert_eq!(packet.level, VarInt(1));
+    assert_eq!(packet.total_xp, VarInt(10));
+}
+
+#[test]
+fn experience_orb_pickup_animation_for_collector() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(ExperiencePlugin);
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .spawn(ExperienceOrbBundle::new(layer, [0.0, 0.5, 0.0], 1));
+
+    app.update();
+
+    let sent_packets = helper.collect_received();
+
+    // The collecting client only receives the packet meant for itself, which
+    // refers to it as entity 0.
+    sent_packets.assert_count::<ItemPickupAnimationS2c>(1);
+
+    let packet = sent_packets.first::<ItemPickupAnimationS2c>();
+    assert_eq!(packet.collector_entity_id, VarInt(0));
+}
+
+#[test]
+fn experience_orb_attraction() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    app.add_plugins(ExperiencePlugin);
+
+    app.update();
+
+    let orb = app
+        .world_mut()
+        .spawn(ExperienceOrbBundle::new(layer, [0.0, 0.5, 4.0], 1))
+        .id();
+
+    app.update();
+
+    let pos = app.world_mut().get::<Position>(orb).unwrap().0;
+    assert!(pos.z < 4.0);
+}
+
+#[test]
+fn split_experience_values() {
+    assert_eq!(split_experience(30).collect::<Vec<_>>(), [17, 7, 3, 3]);
+    assert_eq!(split_experience(2).collect::<Vec<_>>(), [1, 1]);
+    assert_eq!(split_experience(0).count(), 0);
+}
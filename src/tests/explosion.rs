@@ -0,0 +1,59 @@
+use valence_server::explosion::{ExplosionOptions, ExplosionPlugin};
+
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::ExplosionS2c;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState};
+
+#[test]
+fn explosion_destroys_blocks() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(ExplosionPlugin);
+
+    app.update();
+    helper.clear_received();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    for x in 0..16 {
+        for y in 0..4 {
+            for z in 0..16 {
+                chunk_layer.set_block([x, y, z], BlockState::STONE);
+            }
+        }
+    }
+
+    chunk_layer.set_block([8, 1, 8], BlockState::BEDROCK);
+
+    let explosion = chunk_layer.explode([8.5, 2.0, 8.5], 4.0, ExplosionOptions::default());
+
+    assert!(!explosion.destroyed_blocks.is_empty());
+    assert!(explosion
+        .destroyed_blocks
+        .iter()
+        .any(|(pos, _)| *pos == BlockPos::new(8, 2, 8)));
+
+    for (pos, block) in &explosion.destroyed_blocks {
+        assert_eq!(block.state, BlockState::STONE);
+        assert!(chunk_layer.block(*pos).unwrap().state.is_air());
+    }
+
+    assert_eq!(
+        chunk_layer.block([8, 1, 8]).unwrap().state,
+        BlockState::BEDROCK
+    );
+
+    app.update();
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<ExplosionS2c>(1);
+}
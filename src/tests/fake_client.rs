@@ -0,0 +1,39 @@
+use bevy_ecs::event::Events;
+
+use crate::entity::Position;
+use crate::math::DVec3;
+use crate::message::ChatMessageEvent;
+use crate::protocol::packets::play::GameJoinS2c;
+use crate::testing::{ClientScript, FakeClient, ScenarioSingleClient};
+
+#[test]
+fn fake_client_runs_script() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    let mut client = FakeClient::join(&mut app, "scripted", layer);
+
+    client.received().assert_count::<GameJoinS2c>(1);
+    client.clear_received();
+
+    let script = ClientScript::new()
+        .move_to([4.0, 64.0, 4.0])
+        .tick()
+        .move_to([5.0, 64.0, 5.0])
+        .chat("hello");
+
+    client.run(&mut app, &script);
+
+    assert_eq!(
+        app.world().get::<Position>(client.entity).unwrap().0,
+        DVec3::new(5.0, 64.0, 5.0)
+    );
+
+    let messages: Vec<_> = app
+        .world()
+        .resource::<Events<ChatMessageEvent>>()
+        .iter_current_update_events()
+        .map(|event| (event.client, event.message.clone()))
+        .collect();
+
+    assert_eq!(messages, [(client.entity, Box::from("hello"))]);
+}
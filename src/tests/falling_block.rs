@@ -0,0 +1,44 @@
+use valence_server::falling_block::{FallingBlock, FallingBlockBundle, FallingBlockPlugin};
+
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::BlockState;
+
+#[test]
+fn falling_block_lands() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(FallingBlockPlugin);
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([3, 0, 3], BlockState::STONE);
+
+    let block = app
+        .world_mut()
+        .spawn(FallingBlockBundle::from_block(
+            layer,
+            [3, 5, 3],
+            BlockState::SAND,
+        ))
+        .id();
+
+    for _ in 0..40 {
+        app.update();
+    }
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+
+    assert_eq!(
+        chunk_layer.block([3, 1, 3]).unwrap().state,
+        BlockState::SAND
+    );
+    assert!(app.world().get::<FallingBlock>(block).is_none());
+}
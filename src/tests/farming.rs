@@ -0,0 +1,88 @@
+use valence_server::farming::{crop_age, FarmingPlugin};
+use valence_server::game_rules::GameRules;
+use valence_server::protocol::packets::play::PlayerInteractBlockC2s;
+
+use crate::block::BlockKind;
+use crate::inventory::Inventory;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, Direction, Hand, ItemKind, ItemStack};
+
+#[test]
+fn farmland_dries_out_without_water() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    app.add_plugins(FarmingPlugin);
+
+    // Tick nearly every block in view so the test doesn't depend on luck.
+    app.world_mut().entity_mut(layer).insert(GameRules {
+        random_tick_speed: 4096,
+        ..Default::default()
+    });
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 0, 0], BlockState::FARMLAND);
+    chunk_layer.set_block([8, 0, 8], BlockState::FARMLAND);
+    chunk_layer.set_block([8, 1, 8], BlockState::WHEAT);
+
+    for _ in 0..60 {
+        app.update();
+    }
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+
+    assert_eq!(
+        chunk_layer.block([0, 0, 0]).unwrap().state,
+        BlockState::DIRT
+    );
+
+    // The crop keeps the farmland under it from turning into dirt.
+    assert_eq!(
+        chunk_layer.block([8, 0, 8]).unwrap().state.to_kind(),
+        BlockKind::Farmland
+    );
+}
+
+#[test]
+fn bone_meal_grows_crops() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 0, 0], BlockState::FARMLAND);
+    chunk_layer.set_block([0, 1, 0], BlockState::WHEAT);
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::BoneMeal, 2, None));
+
+    helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: [0, 1, 0].into(),
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 0.into(),
+    });
+
+    app.update();
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+    let (age, _) = crop_age(chunk_layer.block([0, 1, 0]).unwrap().state).unwrap();
+
+    assert!(age >= 2);
+    assert_eq!(
+        app.world().get::<Inventory>(client).unwrap().slot(36).count,
+        1
+    );
+}
@@ -0,0 +1,112 @@
+use valence_server::entity::entity::Flags;
+use valence_server::entity::living::Health;
+use valence_server::entity::Position;
+use valence_server::fire::{Burning, FirePlugin};
+use valence_server::protocol::packets::play::PlayerInteractBlockC2s;
+
+use crate::block::BlockKind;
+use crate::inventory::Inventory;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::nbt::Value;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, Direction, Hand, ItemKind, ItemStack};
+
+#[test]
+fn entity_in_fire_burns() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(FirePlugin);
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 0, 0], BlockState::STONE);
+    chunk_layer.set_block([0, 1, 0], BlockState::FIRE);
+
+    app.world_mut().get_mut::<Health>(client).unwrap().0 = 20.0;
+    app.world_mut()
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([0.5, 1.0, 0.5]);
+
+    for _ in 0..20 {
+        app.update();
+    }
+
+    assert!(app.world().get::<Burning>(client).is_some());
+    assert!(app.world().get::<Flags>(client).unwrap().on_fire());
+    assert!(app.world().get::<Health>(client).unwrap().0 < 20.0);
+
+    // Stepping out of the fire into water puts it out.
+    app.world_mut()
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block([1, 1, 0], BlockState::WATER);
+
+    app.world_mut()
+        .get_mut::<Position>(client)
+        .unwrap()
+        .set([1.5, 1.0, 0.5]);
+
+    for _ in 0..2 {
+        app.update();
+    }
+
+    assert!(app.world().get::<Burning>(client).is_none());
+    assert!(!app.world().get::<Flags>(client).unwrap().on_fire());
+}
+
+#[test]
+fn flint_and_steel_lights_fire() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(FirePlugin);
+    app.update();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 0, 0], BlockState::STONE);
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::FlintAndSteel, 1, None));
+
+    helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: [0, 0, 0].into(),
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 0.into(),
+    });
+
+    app.update();
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+
+    assert_eq!(
+        chunk_layer.block([0, 1, 0]).unwrap().state.to_kind(),
+        BlockKind::Fire
+    );
+
+    let stack = app.world().get::<Inventory>(client).unwrap().slot(36);
+
+    assert_eq!(stack.item, ItemKind::FlintAndSteel);
+    assert_eq!(
+        stack.nbt.as_ref().and_then(|nbt| nbt.get("Damage")),
+        Some(&Value::Int(1))
+    );
+}
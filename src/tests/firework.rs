@@ -0,0 +1,106 @@
+use bevy_ecs::event::Events;
+use valence_server::entity::firework_rocket::ShooterEntityId;
+use valence_server::entity::{EntityId, Position};
+use valence_server::firework::{
+    FireworkBundle, FireworkEffect, FireworkExplodeEvent, FireworkPlugin, FireworkShape, Fireworks,
+};
+use valence_server::nbt::Value;
+
+use crate::testing::ScenarioSingleClient;
+use crate::{DyeColor, ItemKind};
+
+#[test]
+fn fireworks_nbt_round_trips() {
+    let fireworks = Fireworks::new().with_flight(2).with_effect(
+        FireworkEffect::new(FireworkShape::Creeper)
+            .with_color(DyeColor::Lime.firework_color())
+            .with_fade_color(DyeColor::Black.firework_color())
+            .with_flicker(),
+    );
+
+    let rocket = fireworks.rocket(3);
+
+    assert_eq!(rocket.item, ItemKind::FireworkRocket);
+    assert_eq!(rocket.count, 3);
+    assert_eq!(Fireworks::from_rocket(&rocket), fireworks);
+
+    let Some(Value::Compound(nbt)) = rocket.nbt.as_ref().unwrap().get("Fireworks") else {
+        panic!("rocket has no fireworks compound");
+    };
+
+    assert_eq!(nbt.get("Flight"), Some(&Value::Byte(2)));
+}
+
+#[test]
+fn fireworks_explode_and_despawn() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    app.add_plugins(FireworkPlugin);
+
+    let effect = FireworkEffect::new(FireworkShape::Star).with_color(0xff0000);
+    let fireworks = Fireworks::new().with_effect(effect.clone());
+
+    let rocket = app
+        .world_mut()
+        .spawn(FireworkBundle::new(layer, [0.0, 64.0, 0.0], &fireworks).with_lifetime(3))
+        .id();
+
+    for _ in 0..2 {
+        app.update();
+    }
+
+    // Rockets fly up on their own.
+    assert!(app.world().get::<Position>(rocket).unwrap().0.y > 64.0);
+    assert!(app
+        .world()
+        .resource::<Events<FireworkExplodeEvent>>()
+        .is_empty());
+
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<FireworkExplodeEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].firework, rocket);
+    assert_eq!(events[0].effects, [effect]);
+
+    app.update();
+
+    assert!(app.world().get_entity(rocket).is_none());
+}
+
+#[test]
+fn fireworks_attach_to_entities() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(FireworkPlugin);
+
+    app.update();
+
+    let rocket = app
+        .world_mut()
+        .spawn(FireworkBundle::new(layer, [0.0, 64.0, 0.0], &Fireworks::new()).attached_to(client))
+        .id();
+
+    app.update();
+
+    let client_id = app.world().get::<EntityId>(client).unwrap().get();
+
+    assert_eq!(
+        app.world().get::<ShooterEntityId>(rocket).unwrap().0,
+        Some(client_id)
+    );
+    assert_eq!(
+        app.world().get::<Position>(rocket).unwrap().0,
+        app.world().get::<Position>(client).unwrap().0
+    );
+}
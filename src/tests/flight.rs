@@ -0,0 +1,197 @@
+use valence_server::entity::entity::Flags;
+use valence_server::entity::firework_rocket::ShooterEntityId;
+use valence_server::entity::living::LivingFlags;
+use valence_server::entity::OnGround;
+use valence_server::firework::FireworkPlugin;
+use valence_server::protocol::packets::play::client_command_c2s::ClientCommand;
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{
+    ClientCommandC2s, PlayerActionC2s, PlayerInteractItemC2s,
+};
+
+use crate::inventory::flight::{FlightRules, Gliding, RiptideSpin};
+use crate::inventory::player_inventory::PlayerInventory;
+use crate::inventory::Inventory;
+use crate::nbt::{compound, List};
+use crate::protocol::VarInt;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, Direction, Hand, ItemKind, ItemStack};
+
+fn start_flying() -> ClientCommandC2s {
+    ClientCommandC2s {
+        entity_id: VarInt(0),
+        action: ClientCommand::StartFlyingWithElytra,
+        jump_boost: VarInt(0),
+    }
+}
+
+fn use_item() -> PlayerInteractItemC2s {
+    PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: VarInt(1),
+    }
+}
+
+fn gliding_scenario() -> ScenarioSingleClient {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario.app.add_plugins(FireworkPlugin);
+    scenario.app.update();
+
+    let world = scenario.app.world_mut();
+
+    world
+        .get_mut::<Inventory>(scenario.client)
+        .unwrap()
+        .set_slot(
+            PlayerInventory::SLOT_CHEST,
+            ItemStack::new(ItemKind::Elytra, 1, None),
+        );
+    world.get_mut::<OnGround>(scenario.client).unwrap().0 = false;
+
+    scenario
+}
+
+#[test]
+fn elytra_gliding_starts_and_stops() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = gliding_scenario();
+
+    helper.send(&start_flying());
+    app.update();
+
+    assert!(app.world().get::<Gliding>(client).is_some());
+    assert!(app.world().get::<Flags>(client).unwrap().fall_flying());
+
+    // Landing stops the glide.
+    app.world_mut().get_mut::<OnGround>(client).unwrap().0 = true;
+    app.update();
+
+    assert!(app.world().get::<Gliding>(client).is_none());
+    assert!(!app.world().get::<Flags>(client).unwrap().fall_flying());
+}
+
+#[test]
+fn elytra_gliding_is_validated() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = gliding_scenario();
+
+    app.world_mut().resource_mut::<FlightRules>().elytra = false;
+
+    helper.send(&start_flying());
+    app.update();
+
+    assert!(app.world().get::<Gliding>(client).is_none());
+
+    // Gliding needs an elytra in the chest slot.
+    app.world_mut().resource_mut::<FlightRules>().elytra = true;
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(PlayerInventory::SLOT_CHEST, ItemStack::EMPTY);
+
+    helper.send(&start_flying());
+    app.update();
+
+    assert!(app.world().get::<Gliding>(client).is_none());
+    assert!(!app.world().get::<Flags>(client).unwrap().fall_flying());
+}
+
+#[test]
+fn firework_boosts_gliding_client() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = gliding_scenario();
+
+    helper.send(&start_flying());
+    app.update();
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::FireworkRocket, 2, None));
+
+    helper.send(&use_item());
+    app.update();
+
+    let rockets = app
+        .world_mut()
+        .query::<&ShooterEntityId>()
+        .iter(app.world())
+        .count();
+
+    assert_eq!(rockets, 1);
+    assert_eq!(
+        app.world().get::<Inventory>(client).unwrap().slot(36).count,
+        1
+    );
+}
+
+#[test]
+fn riptide_launches_client() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    let nbt = compound! {
+        "Enchantments" => List::Compound(vec![compound! {
+            "id" => "minecraft:riptide",
+            "lvl" => 3_i16,
+        }]),
+    };
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Trident, 1, Some(nbt)));
+
+    helper.send(&use_item());
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    helper.send(&PlayerActionC2s {
+        action: PlayerAction::ReleaseUseItem,
+        position: BlockPos::new(0, 0, 0),
+        direction: Direction::Down,
+        sequence: VarInt(0),
+    });
+
+    app.update();
+
+    let spin = *app.world().get::<RiptideSpin>(client).unwrap();
+    assert_eq!(spin.level, 3);
+    assert!(app
+        .world()
+        .get::<LivingFlags>(client)
+        .unwrap()
+        .using_riptide());
+
+    for _ in 0..RiptideSpin::DURATION {
+        app.update();
+    }
+
+    assert!(app.world().get::<RiptideSpin>(client).is_none());
+    assert!(!app
+        .world()
+        .get::<LivingFlags>(client)
+        .unwrap()
+        .using_riptide());
+}
@@ -0,0 +1,55 @@
+use valence_server::game_rules::GameRules;
+use valence_server::protocol::packets::play::{EntityStatusS2c, GameStateChangeS2c};
+use valence_server::spawn::{HasRespawnScreen, ReducedDebugInfo};
+use valence_server::world_time::WorldTime;
+
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn daylight_cycle_can_be_stopped() {
+    let ScenarioSingleClient { mut app, layer, .. } = ScenarioSingleClient::new();
+
+    app.world_mut().entity_mut(layer).insert((
+        WorldTime::new(1000),
+        GameRules {
+            do_daylight_cycle: false,
+            ..Default::default()
+        },
+    ));
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let time = app.world_mut().get::<WorldTime>(layer).unwrap();
+    assert_eq!(time.time_of_day, 1000);
+    assert_eq!(time.world_age, 10);
+}
+
+#[test]
+fn client_visible_rules_are_synced() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut().entity_mut(layer).insert(GameRules {
+        do_immediate_respawn: true,
+        reduced_debug_info: true,
+        ..Default::default()
+    });
+
+    app.update();
+
+    assert!(app.world_mut().get::<ReducedDebugInfo>(client).unwrap().0);
+    assert!(!app.world_mut().get::<HasRespawnScreen>(client).unwrap().0);
+
+    let frames = helper.collect_received();
+    frames.assert_count::<GameStateChangeS2c>(1);
+    assert_eq!(frames.first::<EntityStatusS2c>().entity_status, 22);
+}
@@ -0,0 +1,88 @@
+use bevy_ecs::entity::Entity;
+use valence_server::entity::hitbox::{Hitbox, HitboxKindOverrides, HitboxOverride, HitboxSize};
+use valence_server::entity::zombie::ZombieEntityBundle;
+use valence_server::entity::{entity, EntityKind, EntityLayerId, Pose};
+
+use crate::testing::ScenarioSingleClient;
+
+fn hitbox_height(scenario: &mut ScenarioSingleClient, entity: Entity) -> f64 {
+    let aabb = scenario
+        .app
+        .world_mut()
+        .get::<Hitbox>(entity)
+        .unwrap()
+        .get();
+    aabb.max().y - aabb.min().y
+}
+
+#[test]
+fn entity_hitbox_override() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let zombie = scenario
+        .app
+        .world_mut()
+        .spawn((
+            ZombieEntityBundle {
+                layer: EntityLayerId(scenario.layer),
+                ..Default::default()
+            },
+            HitboxOverride(HitboxSize::new([1.0, 3.0, 1.0])),
+        ))
+        .id();
+
+    scenario.app.update();
+    scenario.app.update();
+
+    assert_eq!(hitbox_height(&mut scenario, zombie), 3.0);
+
+    scenario
+        .app
+        .world_mut()
+        .entity_mut(zombie)
+        .remove::<HitboxOverride>();
+
+    scenario.app.update();
+
+    assert_eq!(hitbox_height(&mut scenario, zombie), 1.95);
+}
+
+#[test]
+fn kind_hitbox_override_with_pose() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    scenario
+        .app
+        .world_mut()
+        .resource_mut::<HitboxKindOverrides>()
+        .0
+        .insert(
+            EntityKind::ZOMBIE,
+            HitboxSize::new([0.6, 2.5, 0.6]).with_pose(Pose::Sneaking, [0.6, 1.0, 0.6]),
+        );
+
+    let zombie = scenario
+        .app
+        .world_mut()
+        .spawn(ZombieEntityBundle {
+            layer: EntityLayerId(scenario.layer),
+            ..Default::default()
+        })
+        .id();
+
+    scenario.app.update();
+    scenario.app.update();
+
+    assert_eq!(hitbox_height(&mut scenario, zombie), 2.5);
+
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<entity::Pose>(zombie)
+        .unwrap()
+        .0 = Pose::Sneaking;
+
+    scenario.app.update();
+
+    assert_eq!(hitbox_height(&mut scenario, zombie), 1.0);
+}
@@ -36,3 +36,65 @@ fn test_hunger() {
     assert_eq!(packet.food, VarInt(5));
     assert_eq!(packet.food_saturation, og_saturation);
 }
+
+#[cfg(feature = "hunger")]
+#[test]
+fn test_eating_restores_food() {
+    use valence_server::protocol::packets::play::PlayerInteractItemC2s;
+    use valence_server::{Hand, ItemKind, ItemStack};
+
+    use crate::inventory::Inventory;
+
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut().get_mut::<Food>(client).unwrap().0 = 10;
+    app.world_mut().get_mut::<Saturation>(client).unwrap().0 = 0.0;
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Bread, 2, None));
+
+    helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: VarInt(1),
+    });
+
+    for _ in 0..33 {
+        app.update();
+    }
+
+    // Bread restores 5 food and 5 * 0.6 * 2 saturation.
+    assert_eq!(app.world_mut().get::<Food>(client).unwrap().0, 15);
+    assert_eq!(app.world_mut().get::<Saturation>(client).unwrap().0, 6.0);
+    assert_eq!(
+        app.world_mut().get::<Inventory>(client).unwrap().slot(36),
+        &ItemStack::new(ItemKind::Bread, 1, None)
+    );
+}
+
+#[cfg(feature = "hunger")]
+#[test]
+fn test_starvation() {
+    let ScenarioSingleClient {
+        mut app, client, ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut().get_mut::<Food>(client).unwrap().0 = 0;
+    app.world_mut().get_mut::<Health>(client).unwrap().0 = 14.0;
+
+    // Starving players take damage every 80 ticks.
+    for _ in 0..80 {
+        app.update();
+    }
+
+    assert_eq!(app.world_mut().get::<Health>(client).unwrap().0, 13.0);
+}
@@ -0,0 +1,308 @@
+use bevy_app::Update;
+use bevy_ecs::event::Events;
+use bevy_ecs::prelude::*;
+
+use crate::action::{ActionSequence, DiggingEvent, DiggingState};
+use crate::interact_block::InteractBlockEvent;
+use crate::interaction_rules::InteractionRules;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::player_action_c2s::PlayerAction;
+use crate::protocol::packets::play::{
+    BlockUpdateS2c, PlayerActionC2s, PlayerActionResponseS2c, PlayerInteractBlockC2s,
+    PlayerInteractItemC2s,
+};
+use crate::testing::ScenarioSingleClient;
+use crate::use_item::UseItemEvent;
+use crate::{BlockPos, BlockState, Direction, GameMode, Hand};
+
+fn dig(scenario: &mut ScenarioSingleClient, position: BlockPos) -> Vec<DiggingEvent> {
+    scenario.helper.send(&PlayerActionC2s {
+        action: PlayerAction::StartDestroyBlock,
+        position,
+        direction: Direction::Up,
+        sequence: 1.into(),
+    });
+
+    scenario.app.update();
+
+    scenario
+        .app
+        .world()
+        .resource::<Events<DiggingEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+#[test]
+fn digging_out_of_reach_is_rejected() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let near = BlockPos::new(2, 0, 2);
+    let far = BlockPos::new(12, 0, 2);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(near, BlockState::STONE);
+    layer.set_block(far, BlockState::STONE);
+
+    scenario.app.update();
+    scenario.helper.clear_received();
+
+    let events = dig(&mut scenario, near);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].position, near);
+
+    scenario
+        .helper
+        .collect_received()
+        .assert_count::<BlockUpdateS2c>(0);
+
+    // The client is told the block is still there.
+    assert!(dig(&mut scenario, far).is_empty());
+
+    let frames = scenario.helper.collect_received();
+    frames.assert_count::<BlockUpdateS2c>(1);
+
+    let pkt = frames.first::<BlockUpdateS2c>();
+    assert_eq!(pkt.position, far);
+    assert_eq!(pkt.block_id, BlockState::STONE);
+
+    // Extending the reach allows digging it.
+    scenario
+        .app
+        .world_mut()
+        .resource_mut::<InteractionRules>()
+        .max_dig_distance = 16.0;
+
+    assert_eq!(dig(&mut scenario, far).len(), 1);
+}
+
+#[test]
+fn spectators_cannot_dig_or_interact() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(1, 0, 1);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(pos, BlockState::STONE);
+
+    *scenario
+        .app
+        .world_mut()
+        .get_mut::<GameMode>(scenario.client)
+        .unwrap() = GameMode::Spectator;
+
+    scenario.app.update();
+
+    assert!(dig(&mut scenario, pos).is_empty());
+
+    scenario.helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: pos,
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 2.into(),
+    });
+
+    scenario.app.update();
+
+    let events = scenario
+        .app
+        .world()
+        .resource::<Events<InteractBlockEvent>>();
+
+    assert!(events.iter_current_update_events().next().is_none());
+}
+
+#[test]
+fn creative_clients_break_blocks_instantly() {
+    let rules = InteractionRules::default();
+
+    assert!(rules.breaks_block(GameMode::Creative, DiggingState::Start));
+    assert!(!rules.breaks_block(GameMode::Survival, DiggingState::Start));
+    assert!(rules.breaks_block(GameMode::Survival, DiggingState::Stop));
+    assert!(!rules.breaks_block(GameMode::Adventure, DiggingState::Stop));
+    assert!(!rules.breaks_block(GameMode::Spectator, DiggingState::Start));
+}
+
+#[test]
+fn use_item_events_are_deduplicated() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(1, 0, 1);
+
+    scenario.app.update();
+
+    // A right click on a block sends these packets in one tick.
+    for hand in [Hand::Main, Hand::Off] {
+        scenario.helper.send(&PlayerInteractBlockC2s {
+            hand,
+            position: pos,
+            face: Direction::Up,
+            cursor_pos: Default::default(),
+            head_inside_block: false,
+            sequence: 1.into(),
+        });
+    }
+
+    for hand in [Hand::Main, Hand::Off] {
+        scenario.helper.send(&PlayerInteractItemC2s {
+            hand,
+            sequence: 2.into(),
+        });
+    }
+
+    scenario.app.update();
+
+    let events: Vec<UseItemEvent> = scenario
+        .app
+        .world()
+        .resource::<Events<UseItemEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].hand, Hand::Main);
+    assert_eq!(events[1].hand, Hand::Off);
+
+    for event in &events {
+        assert_eq!(event.target.unwrap().position, pos);
+    }
+
+    // Using an item in the air on a later tick is a new event.
+    scenario.helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: 3.into(),
+    });
+
+    scenario.app.update();
+
+    let events: Vec<UseItemEvent> = scenario
+        .app
+        .world()
+        .resource::<Events<UseItemEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].target, None);
+    assert_eq!(events[0].sequence, 3);
+}
+
+#[test]
+fn rejected_actions_are_rolled_back() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(1, 0, 1);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(pos, BlockState::STONE);
+
+    fn reject_interactions(
+        mut events: EventReader<InteractBlockEvent>,
+        mut clients: Query<&mut ActionSequence>,
+    ) {
+        for event in events.read() {
+            let mut action_seq = clients.get_mut(event.client).unwrap();
+            action_seq.reject(event.position);
+            action_seq.reject(event.position.get_in_direction(event.face));
+        }
+    }
+
+    scenario.app.add_systems(Update, reject_interactions);
+
+    scenario.app.update();
+    scenario.helper.clear_received();
+
+    scenario.helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: pos,
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 5.into(),
+    });
+
+    scenario.app.update();
+
+    let frames = scenario.helper.collect_received();
+
+    frames.assert_count::<BlockUpdateS2c>(2);
+    frames.assert_order::<(BlockUpdateS2c, PlayerActionResponseS2c)>();
+    assert_eq!(frames.first::<PlayerActionResponseS2c>().sequence.0, 5);
+
+    let pkt = frames.first::<BlockUpdateS2c>();
+    assert_eq!(pkt.position, pos);
+    assert_eq!(pkt.block_id, BlockState::STONE);
+}
+
+#[test]
+fn placements_are_reconciled() {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(1, 0, 1);
+
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.set_block(pos, BlockState::STONE);
+
+    scenario.app.update();
+    scenario.helper.clear_received();
+
+    let place = |scenario: &mut ScenarioSingleClient| {
+        scenario.helper.send(&PlayerInteractBlockC2s {
+            hand: Hand::Main,
+            position: pos,
+            face: Direction::Up,
+            cursor_pos: Default::default(),
+            head_inside_block: false,
+            sequence: 1.into(),
+        });
+
+        scenario.app.update();
+        scenario.helper.collect_received()
+    };
+
+    // Nothing handles the placement, so the client is told the block above is
+    // still air.
+    let frames = place(&mut scenario);
+    frames.assert_count::<BlockUpdateS2c>(2);
+    frames.assert_order::<(BlockUpdateS2c, PlayerActionResponseS2c)>();
+
+    scenario
+        .app
+        .world_mut()
+        .resource_mut::<InteractionRules>()
+        .reconcile_blocks = false;
+
+    let frames = place(&mut scenario);
+    frames.assert_count::<BlockUpdateS2c>(0);
+    frames.assert_count::<PlayerActionResponseS2c>(1);
+}
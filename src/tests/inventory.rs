@@ -2,18 +2,24 @@ use std::borrow::Cow;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use valence_server::entity::donkey::DonkeyEntityBundle;
+use valence_server::entity::{EntityId, EntityLayerId};
 
+use crate::inventory::audit::{SlotChangeCause, SlotChangeEvent};
+use crate::inventory::horse_inventory::HorseInventory;
+use crate::inventory::viewers::{InventoryClosedEvent, InventoryOpenedEvent};
 use crate::inventory::{
-    convert_to_player_slot_id, ClickMode, ClientInventoryState, CursorItem, DropItemStackEvent,
-    HeldItem, Inventory, InventoryKind, OpenInventory, SlotChange,
+    convert_to_player_slot_id, ClickHandling, ClickMode, ClientInventoryState, CursorItem,
+    DropItemStackEvent, HeldItem, HeldItemSync, HeldItemSyncMode, Inventory, InventoryKind,
+    InventorySettings, OpenInventory, SelectedSlotSource, SlotChange, UpdateSelectedSlotEvent,
 };
 use crate::protocol::packets::play::{
-    ClickSlotC2s, CloseScreenS2c, CreativeInventoryActionC2s, InventoryS2c, OpenScreenS2c,
-    ScreenHandlerSlotUpdateS2c, UpdateSelectedSlotC2s,
+    ClickSlotC2s, CloseScreenS2c, CreativeInventoryActionC2s, InventoryS2c, OpenHorseScreenS2c,
+    OpenScreenS2c, ScreenHandlerSlotUpdateS2c, UpdateSelectedSlotC2s, UpdateSelectedSlotS2c,
 };
 use crate::protocol::VarInt;
-use crate::testing::ScenarioSingleClient;
-use crate::{GameMode, ItemKind, ItemStack};
+use crate::testing::{FakeClient, ScenarioSingleClient};
+use crate::{GameMode, ItemKind, ItemStack, Text};
 
 #[test]
 fn test_should_open_inventory() {
@@ -192,6 +198,68 @@ fn test_should_modify_player_inventory_click_slot() {
     assert_eq!(cursor_item.0, ItemStack::new(ItemKind::Diamond, 2, None));
 }
 
+#[test]
+fn authoritative_clicks_ignore_client_changes() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world_mut()
+        .resource_mut::<InventorySettings>()
+        .click_handling = ClickHandling::Authoritative;
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(20, ItemStack::new(ItemKind::Diamond, 10, None));
+
+    app.update();
+    helper.clear_received();
+
+    let state_id = app
+        .world()
+        .get::<ClientInventoryState>(client)
+        .unwrap()
+        .state_id();
+
+    // A right click picks up half of the stack, but the client claims it picked
+    // up all of it.
+    helper.send(&ClickSlotC2s {
+        window_id: 0,
+        button: 1,
+        mode: ClickMode::Click,
+        state_id: VarInt(state_id.0),
+        slot_idx: 20,
+        slot_changes: vec![SlotChange {
+            idx: 20,
+            stack: ItemStack::EMPTY,
+        }]
+        .into(),
+        carried_item: ItemStack::new(ItemKind::Diamond, 10, None),
+    });
+
+    app.update();
+
+    // The client is told what actually happened.
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<InventoryS2c>(1);
+
+    let inventory = app.world().get::<Inventory>(client).unwrap();
+    assert_eq!(
+        inventory.slot(20),
+        &ItemStack::new(ItemKind::Diamond, 5, None)
+    );
+
+    let cursor_item = app.world().get::<CursorItem>(client).unwrap();
+    assert_eq!(cursor_item.0, ItemStack::new(ItemKind::Diamond, 5, None));
+}
+
 #[test]
 fn test_should_allow_non_modifying_inventory_clicks() {
     let ScenarioSingleClient {
@@ -1116,6 +1184,122 @@ fn test_should_handle_set_held_item() {
     assert_eq!(held.slot(), 40);
 }
 
+#[test]
+fn held_item_waits_for_client_acknowledgement() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(HeldItemSync::new(HeldItemSyncMode::Acknowledged));
+
+    app.world_mut()
+        .get_mut::<HeldItem>(client)
+        .unwrap()
+        .set_hotbar_idx(2);
+
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<UpdateSelectedSlotEvent>>()
+        .iter_current_update_events()
+        .map(|event| (event.slot, event.source))
+        .collect::<Vec<_>>();
+
+    assert_eq!(events, [(2, SelectedSlotSource::Server)]);
+
+    helper.clear_received();
+
+    // The client scrolled before it was told about the change. It is told to
+    // switch back.
+    helper.send(&UpdateSelectedSlotC2s { slot: 5 });
+    app.update();
+
+    assert_eq!(app.world().get::<HeldItem>(client).unwrap().hotbar_idx(), 2);
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<UpdateSelectedSlotS2c>(1);
+    assert_eq!(recvd.first::<UpdateSelectedSlotS2c>().slot, 2);
+
+    // Once the client reports the server's slot, it can change it again.
+    helper.send(&UpdateSelectedSlotC2s { slot: 2 });
+    helper.send(&UpdateSelectedSlotC2s { slot: 6 });
+    app.update();
+
+    assert_eq!(app.world().get::<HeldItem>(client).unwrap().hotbar_idx(), 6);
+}
+
+#[test]
+fn held_item_does_not_wait_for_unchanged_slot() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(HeldItemSync::new(HeldItemSyncMode::Acknowledged));
+
+    // Setting the slot the client already has selected. The client won't
+    // answer, so there is no acknowledgement to wait for.
+    app.world_mut()
+        .get_mut::<HeldItem>(client)
+        .unwrap()
+        .set_hotbar_idx(0);
+
+    app.update();
+
+    assert!(!app
+        .world()
+        .get::<HeldItemSync>(client)
+        .unwrap()
+        .is_awaiting_ack());
+
+    helper.send(&UpdateSelectedSlotC2s { slot: 3 });
+    app.update();
+
+    assert_eq!(app.world().get::<HeldItem>(client).unwrap().hotbar_idx(), 3);
+}
+
+#[test]
+fn locked_held_item_rejects_client_changes() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(HeldItemSync::new(HeldItemSyncMode::Locked));
+
+    app.update();
+    helper.clear_received();
+
+    helper.send(&UpdateSelectedSlotC2s { slot: 4 });
+    app.update();
+
+    assert_eq!(app.world().get::<HeldItem>(client).unwrap().hotbar_idx(), 0);
+
+    helper
+        .collect_received()
+        .assert_count::<UpdateSelectedSlotS2c>(1);
+}
+
 #[test]
 fn should_not_increment_state_id_on_cursor_item_change() {
     let ScenarioSingleClient {
@@ -2078,3 +2262,271 @@ fn dragging_items() {
         );
     }
 }
+
+#[test]
+fn should_open_horse_inventory() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let horse = app
+        .world_mut()
+        .spawn((
+            DonkeyEntityBundle {
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            Inventory::new(InventoryKind::Horse { chest_columns: 5 }),
+        ))
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(OpenInventory::new(horse));
+
+    app.update();
+
+    let sent_packets = helper.collect_received();
+
+    sent_packets.assert_count::<OpenHorseScreenS2c>(1);
+    sent_packets.assert_count::<OpenScreenS2c>(0);
+    sent_packets.assert_order::<(OpenHorseScreenS2c, InventoryS2c)>();
+
+    let horse_id = app.world().get::<EntityId>(horse).unwrap().get();
+
+    for pkt in sent_packets.0 {
+        if let Ok(pkt) = pkt.decode::<OpenHorseScreenS2c>() {
+            assert_eq!(pkt.slot_count.0, 17);
+            assert_eq!(pkt.entity_id, horse_id);
+        }
+    }
+
+    // The saddle slot only takes saddles.
+    let inv_state = app.world().get::<ClientInventoryState>(client).unwrap();
+    let window_id = inv_state.window_id();
+    let state_id = inv_state.state_id();
+
+    app.world_mut().get_mut::<CursorItem>(client).unwrap().0 =
+        ItemStack::new(ItemKind::Diamond, 1, None);
+    app.update();
+    helper.clear_received();
+
+    helper.send(&ClickSlotC2s {
+        window_id,
+        state_id: VarInt(state_id.0),
+        slot_idx: HorseInventory::SLOT_SADDLE as i16,
+        button: 0,
+        mode: ClickMode::Click,
+        slot_changes: vec![SlotChange {
+            idx: HorseInventory::SLOT_SADDLE as i16,
+            stack: ItemStack::new(ItemKind::Diamond, 1, None),
+        }]
+        .into(),
+        carried_item: ItemStack::EMPTY,
+    });
+
+    app.update();
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<InventoryS2c>(1);
+
+    let inventory = app.world().get::<Inventory>(horse).unwrap();
+    assert_eq!(
+        inventory.slot(HorseInventory::SLOT_SADDLE),
+        &ItemStack::EMPTY
+    );
+}
+
+#[test]
+fn slot_changes_are_audited() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let mut inventory = app.world_mut().get_mut::<Inventory>(client).unwrap();
+    inventory.set_slot(20, ItemStack::new(ItemKind::Dirt, 1, None));
+    inventory.set_slot(20, ItemStack::new(ItemKind::Diamond, 2, None));
+    // Changed back, so not reported.
+    inventory.set_slot(21, ItemStack::new(ItemKind::Dirt, 1, None));
+    inventory.set_slot(21, ItemStack::EMPTY);
+
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<SlotChangeEvent>>()
+        .iter_current_update_events()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [SlotChangeEvent {
+            client: Some(client),
+            window: client,
+            slot: 20,
+            old: ItemStack::EMPTY,
+            new: ItemStack::new(ItemKind::Diamond, 2, None),
+            cause: SlotChangeCause::Server,
+        }]
+    );
+
+    // Make the client pick up the diamonds.
+    let state_id = app
+        .world()
+        .get::<ClientInventoryState>(client)
+        .unwrap()
+        .state_id();
+
+    helper.send(&ClickSlotC2s {
+        window_id: 0,
+        button: 0,
+        mode: ClickMode::Click,
+        state_id: VarInt(state_id.0),
+        slot_idx: 20,
+        slot_changes: vec![SlotChange {
+            idx: 20,
+            stack: ItemStack::EMPTY,
+        }]
+        .into(),
+        carried_item: ItemStack::new(ItemKind::Diamond, 2, None),
+    });
+
+    app.update();
+
+    let events = app
+        .world()
+        .resource::<Events<SlotChangeEvent>>()
+        .iter_current_update_events()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        events,
+        [SlotChangeEvent {
+            client: Some(client),
+            window: client,
+            slot: 20,
+            old: ItemStack::new(ItemKind::Diamond, 2, None),
+            new: ItemStack::EMPTY,
+            cause: SlotChangeCause::Click,
+        }]
+    );
+}
+
+#[test]
+fn shared_inventory_viewers() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    // Process a tick to get past the "on join" logic.
+    app.update();
+
+    let mut other = FakeClient::join(&mut app, "other", layer);
+
+    let mut inventory = Inventory::with_title(InventoryKind::Generic9x3, "Chest");
+    inventory.set_viewer_title(other.entity, "Other chest");
+    let inventory_ent = app.world_mut().spawn(inventory).id();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(OpenInventory::new(inventory_ent));
+    app.world_mut()
+        .entity_mut(other.entity)
+        .insert(OpenInventory::new(inventory_ent));
+
+    helper.clear_received();
+    other.helper().clear_received();
+
+    app.update();
+
+    let inventory = app.world().get::<Inventory>(inventory_ent).unwrap();
+    assert_eq!(inventory.viewers().len(), 2);
+    assert!(inventory.viewers().contains(&client));
+    assert!(inventory.viewers().contains(&other.entity));
+
+    let opened = app
+        .world()
+        .resource::<Events<InventoryOpenedEvent>>()
+        .iter_current_update_events()
+        .count();
+    assert_eq!(opened, 1);
+
+    // Each viewer sees its own title.
+    let frames = helper.collect_received();
+    let pkt = frames.first::<OpenScreenS2c>();
+    assert_eq!(pkt.window_title.as_ref(), &Text::from("Chest"));
+
+    let frames = other.helper().collect_received();
+    let pkt = frames.first::<OpenScreenS2c>();
+    assert_eq!(pkt.window_title.as_ref(), &Text::from("Other chest"));
+
+    // Renaming the inventory only reopens it for viewers without their own title.
+    app.world_mut()
+        .get_mut::<Inventory>(inventory_ent)
+        .unwrap()
+        .set_title("Big chest");
+
+    app.update();
+
+    helper.collect_received().assert_count::<OpenScreenS2c>(1);
+    other
+        .helper()
+        .collect_received()
+        .assert_count::<OpenScreenS2c>(0);
+
+    // The inventory is only closed once the last viewer leaves.
+    app.world_mut().entity_mut(client).remove::<OpenInventory>();
+    app.update();
+
+    let inventory = app.world().get::<Inventory>(inventory_ent).unwrap();
+    assert_eq!(inventory.viewers(), [other.entity]);
+    assert!(app
+        .world()
+        .resource::<Events<InventoryClosedEvent>>()
+        .iter_current_update_events()
+        .next()
+        .is_none());
+
+    app.world_mut()
+        .entity_mut(other.entity)
+        .remove::<OpenInventory>();
+    app.update();
+
+    let inventory = app.world().get::<Inventory>(inventory_ent).unwrap();
+    assert!(inventory.viewers().is_empty());
+    assert_eq!(
+        inventory.viewer_title(other.entity),
+        &Text::from("Big chest")
+    );
+
+    let closed = app
+        .world()
+        .resource::<Events<InventoryClosedEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        closed,
+        [InventoryClosedEvent {
+            inventory: inventory_ent,
+            client: other.entity,
+        }]
+    );
+}
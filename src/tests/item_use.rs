@@ -0,0 +1,144 @@
+use valence_server::entity::living::LivingFlags;
+use valence_server::entity::player::Food;
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{
+    EntityStatusS2c, PlayerActionC2s, PlayerInteractItemC2s,
+};
+
+use crate::inventory::item_use::{ItemUseKind, UsingItem};
+use crate::inventory::Inventory;
+use crate::protocol::VarInt;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, Direction, Hand, ItemKind, ItemStack};
+
+#[test]
+fn eating_finishes_after_duration() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut().get_mut::<Food>(client).unwrap().0 = 10;
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Bread, 1, None));
+
+    helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    let using = *app.world_mut().get::<UsingItem>(client).unwrap();
+    assert_eq!(using.kind, ItemUseKind::Eat);
+    assert_eq!(using.ticks, 1);
+    assert!(app
+        .world_mut()
+        .get::<LivingFlags>(client)
+        .unwrap()
+        .using_item());
+
+    for _ in 0..30 {
+        app.update();
+    }
+
+    assert!(app.world_mut().get::<UsingItem>(client).is_some());
+    helper.clear_received();
+
+    app.update();
+
+    assert!(app.world_mut().get::<UsingItem>(client).is_none());
+    assert!(!app
+        .world_mut()
+        .get::<LivingFlags>(client)
+        .unwrap()
+        .using_item());
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<EntityStatusS2c>(1);
+}
+
+#[test]
+fn cannot_eat_when_full() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut().get_mut::<Food>(client).unwrap().0 = 20;
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Bread, 1, None));
+
+    helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Main,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    assert!(app.world_mut().get::<UsingItem>(client).is_none());
+}
+
+#[test]
+fn releasing_shield_stops_blocking() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(45, ItemStack::new(ItemKind::Shield, 1, None));
+
+    helper.send(&PlayerInteractItemC2s {
+        hand: Hand::Off,
+        sequence: VarInt(1),
+    });
+
+    app.update();
+
+    let flags = app.world_mut().get::<LivingFlags>(client).unwrap();
+    assert!(flags.using_item());
+    assert!(flags.off_hand_active());
+
+    // Shields are used until released.
+    for _ in 0..100 {
+        app.update();
+    }
+
+    assert!(app.world_mut().get::<UsingItem>(client).is_some());
+
+    helper.send(&PlayerActionC2s {
+        action: PlayerAction::ReleaseUseItem,
+        position: BlockPos::new(0, 0, 0),
+        direction: Direction::Down,
+        sequence: VarInt(0),
+    });
+
+    app.update();
+
+    assert!(app.world_mut().get::<UsingItem>(client).is_none());
+    assert!(!app
+        .world_mut()
+        .get::<LivingFlags>(client)
+        .unwrap()
+        .using_item());
+}
@@ -0,0 +1,87 @@
+use bevy_ecs::event::Events;
+use valence_server::entity::item::Stack;
+
+use crate::block::{PropName, PropValue};
+use crate::inventory::jukebox::RecordPlayingEvent;
+use crate::inventory::Inventory;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::{PlayerInteractBlockC2s, WorldEventS2c};
+use crate::testing::{MockClientHelper, ScenarioSingleClient};
+use crate::{BlockPos, BlockState, Direction, Hand, ItemKind, ItemStack};
+
+const JUKEBOX_POS: BlockPos = BlockPos::new(1, 0, 1);
+
+fn interact_block(helper: &mut MockClientHelper) {
+    helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position: JUKEBOX_POS,
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 0.into(),
+    });
+}
+
+#[test]
+fn jukebox_plays_and_ejects_records() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(JUKEBOX_POS, BlockState::JUKEBOX);
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::MusicDiscCat, 1, None));
+
+    app.update();
+    helper.clear_received();
+
+    interact_block(&mut helper);
+    app.update();
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+    let block = chunk_layer.block(JUKEBOX_POS).unwrap();
+    assert_eq!(block.state.get(PropName::HasRecord), Some(PropValue::True));
+    assert!(block.nbt.is_some_and(|nbt| nbt.get("RecordItem").is_some()));
+    assert!(app
+        .world()
+        .get::<Inventory>(client)
+        .unwrap()
+        .slot(36)
+        .is_empty());
+
+    let events = app
+        .world()
+        .resource::<Events<RecordPlayingEvent>>()
+        .iter_current_update_events()
+        .collect::<Vec<_>>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].record, ItemKind::MusicDiscCat);
+
+    let sent_packets = helper.collect_received();
+    sent_packets.assert_count::<WorldEventS2c>(1);
+
+    // Using the jukebox again takes the record out.
+    interact_block(&mut helper);
+    app.update();
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+    let block = chunk_layer.block(JUKEBOX_POS).unwrap();
+    assert_eq!(block.state.get(PropName::HasRecord), Some(PropValue::False));
+
+    let dropped = app
+        .world_mut()
+        .query::<&Stack>()
+        .iter(app.world())
+        .map(|stack| stack.0.item)
+        .collect::<Vec<_>>();
+    assert_eq!(dropped, [ItemKind::MusicDiscCat]);
+}
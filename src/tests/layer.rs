@@ -1,19 +1,25 @@
 use std::collections::BTreeSet;
 
+use bevy_ecs::event::Events;
 use bevy_ecs::world::EntityWorldMut;
 
 use crate::client::{ViewDistance, VisibleEntityLayers};
 use crate::entity::cow::CowEntityBundle;
+use crate::entity::movement::{EntityMovementSettings, SentPosition};
 use crate::entity::{EntityLayerId, Position};
-use crate::layer::chunk::UnloadedChunk;
-use crate::layer::{ChunkLayer, EntityLayer};
+use crate::layer::chunk::{Block, Chunk, ChunkLayerSnapshot, UnloadedChunk};
+use crate::layer::limits::{LayerLimitEvent, LayerLimits};
+use crate::layer::unload::UnviewedChunkUnloadEvent;
+use crate::layer::{ChunkLayer, ChunkUnloadPolicy, EntityLayer, LayerStats};
+use crate::math::{Aabb, DVec3};
+use crate::nbt::Compound;
 use crate::protocol::packets::play::{
-    BlockEntityUpdateS2c, ChunkDataS2c, ChunkDeltaUpdateS2c, EntitiesDestroyS2c, EntitySpawnS2c,
-    MoveRelativeS2c, UnloadChunkS2c,
+    BlockEntityUpdateS2c, BlockUpdateS2c, ChunkDataS2c, ChunkDeltaUpdateS2c, EntitiesDestroyS2c,
+    EntityPositionS2c, EntitySpawnS2c, MoveRelativeS2c, UnloadChunkS2c,
 };
 use crate::protocol::Packet;
 use crate::testing::ScenarioSingleClient;
-use crate::{BlockState, ChunkView, Despawned, Server};
+use crate::{BlockPos, BlockState, ChunkPos, ChunkView, Despawned, Server};
 
 #[test]
 fn block_create_destroy() {
@@ -465,3 +471,607 @@ fn chunk_entity_spawn_despawn() {
         recvd.assert_count::<EntitiesDestroyS2c>(0)
     };
 }
+
+#[test]
+fn small_entity_movement_is_suppressed() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world_mut()
+        .resource_mut::<EntityMovementSettings>()
+        .min_distance = 0.5;
+
+    let cow_ent = app
+        .world_mut()
+        .spawn(CowEntityBundle {
+            position: Position::new([8.0, 0.0, 8.0]),
+            layer: EntityLayerId(layer_ent),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    // Movement below the threshold is not sent until it adds up.
+    for _ in 0..5 {
+        app.world_mut().get_mut::<Position>(cow_ent).unwrap().0.x += 0.125;
+        app.update();
+    }
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<MoveRelativeS2c>(1);
+    recvd.assert_count::<EntityPositionS2c>(0);
+
+    // The rest of the movement is sent once the entity stops.
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<EntityPositionS2c>(1);
+
+    assert_eq!(
+        app.world_mut().get::<SentPosition>(cow_ent).unwrap().get(),
+        DVec3::new(8.625, 0.0, 8.0)
+    );
+}
+
+#[test]
+fn layer_stats() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    for pos in [[8.0, 0.0, 8.0], [9.0, 0.0, 8.0], [24.0, 0.0, 8.0]] {
+        app.world_mut().spawn(CowEntityBundle {
+            position: Position::new(pos),
+            layer: EntityLayerId(layer_ent),
+            ..Default::default()
+        });
+    }
+
+    app.update();
+
+    let stats = app.world().resource::<LayerStats>();
+    let chunk_stats = *stats.chunk_layer(layer_ent).unwrap();
+    let entity_stats = *stats.entity_layer(layer_ent).unwrap();
+
+    assert_eq!(chunk_stats.chunks, 2);
+    assert_eq!(chunk_stats.viewed_chunks, 2);
+    assert!(chunk_stats.memory_usage > 0);
+
+    let entity_layer = app.world().get::<EntityLayer>(layer_ent).unwrap();
+
+    assert_eq!(entity_layer.entity_count_at([1, 0]), 1);
+    assert_eq!(entity_stats.entities, entity_layer.entity_count());
+    assert!(entity_stats.entities >= 3);
+    assert!(entity_stats.max_entities_per_chunk >= 2);
+
+    // Mixing blocks in a section needs more memory than a single block.
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.set_block([0, 0, 0], BlockState::STONE);
+
+    app.update();
+
+    let stats = app.world().resource::<LayerStats>();
+
+    assert!(stats.chunk_layer(layer_ent).unwrap().memory_usage > chunk_stats.memory_usage);
+    assert_eq!(stats.total_chunks(), 2);
+}
+
+#[test]
+fn unviewed_chunks_unload_after_grace_period() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world_mut()
+        .entity_mut(layer_ent)
+        .insert(ChunkUnloadPolicy::new(5));
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([100, 100], UnloadedChunk::new());
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let layer = app.world().get::<ChunkLayer>(layer_ent).unwrap();
+    assert!(layer.chunk([100, 100]).is_some());
+
+    let mut unloaded = vec![];
+
+    for _ in 0..5 {
+        app.update();
+
+        let events = app
+            .world()
+            .resource::<Events<UnviewedChunkUnloadEvent>>()
+            .iter_current_update_events()
+            .map(|event| event.pos);
+
+        unloaded.extend(events);
+    }
+
+    let layer = app.world().get::<ChunkLayer>(layer_ent).unwrap();
+
+    // The chunk in view of the client stays loaded.
+    assert!(layer.chunk([0, 0]).is_some());
+    assert!(layer.chunk([100, 100]).is_none());
+    assert_eq!(unloaded, [ChunkPos::new(100, 100)]);
+}
+
+#[test]
+fn block_region_queries() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([-1, 0], UnloadedChunk::new());
+
+    layer.set_block([-2, 0, 0], BlockState::STONE);
+    layer.set_block([1, 0, 0], BlockState::STONE);
+    layer.set_block([5, 0, 0], BlockState::STONE);
+    layer.set_block(
+        [-1, 3, 2],
+        Block::new(BlockState::CHEST, Some(Compound::new())),
+    );
+
+    // The region spans both chunks and part of an unloaded one.
+    let region = Aabb::new(DVec3::new(-2.0, 0.0, -1.0), DVec3::new(2.0, 1.0, 1.0));
+
+    let mut visited = 0;
+    layer.for_each_block_in(region, |_, _| visited += 1);
+    assert_eq!(visited, 4);
+
+    let mut stone: Vec<_> = layer
+        .find_blocks(region, |block| block.state == BlockState::STONE)
+        .collect();
+    stone.sort_by_key(|pos| pos.x);
+
+    assert_eq!(stone, [BlockPos::new(-2, 0, 0), BlockPos::new(1, 0, 0)]);
+
+    let block_entities: Vec<_> = layer.block_entities().map(|(pos, _)| pos).collect();
+    assert_eq!(block_entities, [BlockPos::new(-1, 3, 2)]);
+}
+
+#[test]
+fn batch_block_updates_per_section() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    app.update();
+    helper.clear_received();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    let count = layer.batch_block_updates([
+        // Two blocks in one section, one of them changed twice.
+        ([1, 1, 1], BlockState::STONE),
+        ([2, 1, 1], BlockState::DIRT),
+        ([1, 1, 1], BlockState::GLASS),
+        // A single block in another section.
+        ([1, 20, 1], BlockState::STONE),
+        // Not loaded.
+        ([100, 1, 100], BlockState::STONE),
+    ]);
+
+    assert_eq!(count, 4);
+    assert_eq!(layer.block([1, 1, 1]).unwrap().state, BlockState::GLASS);
+
+    app.update();
+
+    let recvd = helper.collect_received();
+
+    recvd.assert_count::<ChunkDeltaUpdateS2c>(1);
+    recvd.assert_count::<BlockUpdateS2c>(1);
+
+    // Only the last change to each block is sent.
+    assert_eq!(recvd.first::<ChunkDeltaUpdateS2c>().blocks.len(), 2);
+}
+
+#[test]
+fn overwriting_viewed_chunk_sends_changes() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    app.update();
+    helper.clear_received();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    // A copy with a single different block is sent as a block update.
+    let mut chunk = layer.chunk([0, 0]).unwrap().to_unloaded();
+    chunk.set_block_state(1, 2, 3, BlockState::STONE);
+    layer.insert_chunk([0, 0], chunk);
+
+    assert!(layer.chunk([0, 0]).unwrap().is_section_dirty(0));
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+
+        recvd.assert_count::<BlockUpdateS2c>(1);
+        recvd.assert_count::<ChunkDataS2c>(0);
+    }
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    // Replacing most of the chunk resends it.
+    let mut chunk = UnloadedChunk::new();
+    chunk.fill_block_states(BlockState::STONE);
+    layer.insert_chunk([0, 0], chunk);
+
+    app.update();
+
+    let recvd = helper.collect_received();
+
+    recvd.assert_count::<BlockUpdateS2c>(0);
+    recvd.assert_count::<ChunkDataS2c>(1);
+}
+
+#[test]
+fn block_histograms() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    let min_y = layer.min_y();
+    let height = layer.height();
+
+    for x in 0..20 {
+        layer.set_block([x, min_y, 0], BlockState::STONE);
+    }
+    layer.set_block([3, min_y + 40, 3], BlockState::DIRT);
+
+    let chunk = layer.chunk([0, 0]).unwrap();
+    let histogram = chunk.block_histogram();
+
+    assert_eq!(histogram.total(), 16 * 16 * u64::from(height));
+    assert_eq!(histogram.get(BlockState::STONE), 16);
+    assert_eq!(histogram.get(BlockState::DIRT), 1);
+    assert_eq!(histogram.non_air(), 17);
+
+    // Covers all of the first chunk and part of the second.
+    let region = Aabb::new(
+        DVec3::new(0.0, f64::from(min_y), 0.0),
+        DVec3::new(18.0, f64::from(min_y) + 48.0, 16.0),
+    );
+
+    let histogram = layer.block_histogram(region);
+
+    assert_eq!(histogram.total(), 18 * 16 * 48);
+    assert_eq!(histogram.get(BlockState::STONE), 18);
+    assert_eq!(histogram.get_kind(BlockState::DIRT.to_kind()), 1);
+
+    let mut expected = 0;
+    layer.for_each_block_in(region, |_, block| {
+        if block.state == BlockState::STONE {
+            expected += 1;
+        }
+    });
+    assert_eq!(histogram.get(BlockState::STONE), expected);
+}
+
+#[test]
+fn entity_spatial_index() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let cows: Vec<_> = [[2.0, 0.0, 2.0], [10.0, 0.0, 2.0], [40.0, 0.0, 2.0]]
+        .into_iter()
+        .map(|pos| {
+            app.world_mut()
+                .spawn(CowEntityBundle {
+                    position: Position::new(pos),
+                    layer: EntityLayerId(layer_ent),
+                    ..Default::default()
+                })
+                .id()
+        })
+        .collect();
+
+    app.update();
+
+    let layer = app.world().get::<EntityLayer>(layer_ent).unwrap();
+
+    let near: BTreeSet<_> = layer.query_sphere(DVec3::new(4.0, 0.0, 2.0), 8.0).collect();
+    assert!(near.contains(&cows[0]));
+    assert!(near.contains(&cows[1]));
+    assert!(!near.contains(&cows[2]));
+
+    let nearest = layer.query_nearest(DVec3::new(35.0, 0.0, 2.0), 2);
+    assert_eq!(nearest[..2], [cows[2], cows[1]]);
+
+    // Moving and despawning entities updates the index.
+    app.world_mut().get_mut::<Position>(cows[2]).unwrap().0 = DVec3::new(3.0, 0.0, 2.0);
+    app.world_mut().entity_mut(cows[0]).insert(Despawned);
+
+    app.update();
+
+    let layer = app.world().get::<EntityLayer>(layer_ent).unwrap();
+
+    let region = Aabb::new(DVec3::new(1.0, -1.0, 1.0), DVec3::new(4.0, 1.0, 4.0));
+    let in_region: Vec<_> = layer.query_aabb(region).collect();
+    assert_eq!(in_region, [cows[2]]);
+}
+
+#[test]
+fn chunk_layer_snapshots() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([3, -2], UnloadedChunk::new());
+
+    for x in 0..16 {
+        layer.set_block([x, 0, 0], BlockState::STONE);
+    }
+    for i in 0..40 {
+        // Enough distinct states to need the direct representation.
+        layer.set_block(
+            [48 + i % 16, 1, -32 + i / 16],
+            BlockState::from_raw(i as u16 + 1).unwrap(),
+        );
+    }
+    layer.set_block(
+        [2, 3, 4],
+        Block::new(BlockState::CHEST, Some(Compound::new())),
+    );
+
+    let snapshot = layer.snapshot();
+
+    let mut bytes = vec![];
+    snapshot.encode(&mut bytes).unwrap();
+    let decoded = ChunkLayerSnapshot::decode(&bytes).unwrap();
+
+    assert_eq!(decoded.height(), snapshot.height());
+    assert_eq!(decoded.chunk_count(), 2);
+
+    // Change the layer and go back to the decoded snapshot.
+    layer.set_block([0, 0, 0], BlockState::DIRT);
+    layer.insert_chunk([5, 5], UnloadedChunk::new());
+
+    layer.restore(&decoded);
+
+    assert_eq!(layer.chunk_count(), 2);
+    assert!(layer.chunk([5, 5]).is_none());
+    assert_eq!(layer.block([0, 0, 0]).unwrap().state, BlockState::STONE);
+    assert_eq!(
+        layer.block([48 + 39 % 16, 1, -32 + 39 / 16]).unwrap().state,
+        BlockState::from_raw(40).unwrap()
+    );
+    assert!(layer.block([2, 3, 4]).unwrap().nbt.is_some());
+
+    assert!(ChunkLayerSnapshot::decode(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn chunk_layer_modified_chunks() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+    layer.insert_chunk([1, 0], UnloadedChunk::new());
+
+    // Inserted chunks count as modified.
+    let diff = layer.take_modified_chunks();
+    assert!(diff.chunk([0, 0]).is_some());
+    assert!(diff.chunk([1, 0]).is_some());
+    assert_eq!(layer.take_modified_chunks().chunk_count(), 0);
+
+    // Setting a block to what it already is isn't a modification.
+    layer.set_block([0, 0, 0], BlockState::AIR);
+    layer.set_block([16, 0, 0], BlockState::STONE);
+
+    let diff = layer.take_modified_chunks();
+    assert_eq!(diff.chunk_count(), 1);
+    assert_eq!(
+        diff.chunk([1, 0]).unwrap().block_state(0, 0, 0),
+        BlockState::STONE
+    );
+    assert!(!layer.chunk([1, 0]).unwrap().is_modified());
+
+    let mut bytes = vec![];
+    diff.encode(&mut bytes).unwrap();
+    let decoded = ChunkLayerSnapshot::decode(&bytes).unwrap();
+
+    // Applying the diff only replaces the chunks in it.
+    layer.set_block([0, 0, 0], BlockState::DIRT);
+    layer.set_block([16, 0, 0], BlockState::DIRT);
+
+    layer.apply_diff(&decoded);
+
+    assert_eq!(layer.block([0, 0, 0]).unwrap().state, BlockState::DIRT);
+    assert_eq!(layer.block([16, 0, 0]).unwrap().state, BlockState::STONE);
+}
+
+#[test]
+fn chunk_tickets_keep_chunks_loaded() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world_mut()
+        .entity_mut(layer_ent)
+        .insert(ChunkUnloadPolicy::new(0));
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    for pos in [[100, 100], [101, 101], [102, 102], [200, 200]] {
+        layer.insert_chunk(pos, UnloadedChunk::new());
+    }
+
+    let spawn = layer.add_ticket([100, 100], 1, None);
+    let event = layer.add_ticket([200, 200], 0, Some(3));
+
+    assert!(layer.has_ticket([101, 99]));
+    assert!(!layer.has_ticket([102, 102]));
+    assert_eq!(layer.ticket(event).unwrap().ttl(), Some(3));
+
+    app.update();
+    app.update();
+
+    let layer = app.world().get::<ChunkLayer>(layer_ent).unwrap();
+    assert!(layer.chunk([100, 100]).is_some());
+    assert!(layer.chunk([101, 101]).is_some());
+    assert!(layer.chunk([102, 102]).is_none());
+    assert!(layer.chunk([200, 200]).is_some());
+
+    // The ticket expires after three ticks.
+    app.update();
+    app.update();
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+    assert!(layer.ticket(event).is_none());
+    assert!(layer.chunk([200, 200]).is_none());
+
+    assert!(layer.remove_ticket(spawn).is_some());
+    assert_eq!(layer.ticketed_chunks().count(), 0);
+
+    app.update();
+    app.update();
+
+    let layer = app.world().get::<ChunkLayer>(layer_ent).unwrap();
+    assert!(layer.chunk([100, 100]).is_none());
+}
+
+#[test]
+fn layer_limits_reject_extra_entities_and_chunks() {
+    let ScenarioSingleClient {
+        mut app,
+        layer: layer_ent,
+        ..
+    } = ScenarioSingleClient::new();
+
+    // The client is already in the layer.
+    app.world_mut().entity_mut(layer_ent).insert(
+        LayerLimits::default()
+            .with_max_entities(3)
+            .with_max_chunks(2)
+            .rejecting(),
+    );
+
+    let mut layer = app.world_mut().get_mut::<ChunkLayer>(layer_ent).unwrap();
+
+    for x in 0..4 {
+        layer.insert_chunk([x, 0], UnloadedChunk::new());
+    }
+
+    let cows: Vec<_> = (0..4)
+        .map(|_| {
+            app.world_mut()
+                .spawn(CowEntityBundle {
+                    layer: EntityLayerId(layer_ent),
+                    ..Default::default()
+                })
+                .id()
+        })
+        .collect();
+
+    app.update();
+
+    assert_eq!(
+        app.world()
+            .get::<ChunkLayer>(layer_ent)
+            .unwrap()
+            .chunk_count(),
+        2
+    );
+
+    let remaining = cows
+        .iter()
+        .filter(|&&cow| {
+            app.world()
+                .get_entity(cow)
+                .is_some_and(|cow| !cow.contains::<Despawned>())
+        })
+        .count();
+
+    assert_eq!(remaining, 2);
+
+    let events: Vec<_> = app
+        .world()
+        .resource::<Events<LayerLimitEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(events.len(), 2);
+
+    for event in events {
+        assert_eq!(event.layer, layer_ent);
+        assert_eq!(event.rejected, 2);
+    }
+
+    // Nothing is sent while the layer stays within its limits.
+    app.update();
+
+    assert_eq!(
+        app.world()
+            .resource::<Events<LayerLimitEvent>>()
+            .iter_current_update_events()
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,138 @@
+use crate::client::Client;
+use crate::message::{MessageOverflow, NoChatReports, SendMessage, SystemMessageQueue};
+use crate::protocol::packets::play::{GameMessageS2c, RemoveMessageS2c, ServerMetadataS2c};
+use crate::protocol::Encode;
+use crate::testing::{create_mock_client, ScenarioSingleClient};
+
+#[test]
+fn no_chat_reports_tells_joining_clients() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    // Secure chat isn't mentioned unless the server opts out.
+    helper
+        .collect_received()
+        .assert_count::<ServerMetadataS2c>(0);
+
+    app.insert_resource(NoChatReports {
+        motd: "A server".into(),
+    });
+
+    let (mut client, mut client_helper) = create_mock_client("test_2");
+    client.player.layer.0 = layer;
+    client.visible_chunk_layer.0 = layer;
+    client.visible_entity_layers.0.insert(layer);
+
+    app.world_mut().spawn(client);
+
+    app.update();
+
+    let recvd = client_helper.collect_received();
+    recvd.assert_count::<ServerMetadataS2c>(1);
+
+    let pkt = recvd.first::<ServerMetadataS2c>();
+    assert!(!pkt.enforce_secure_chat);
+}
+
+#[test]
+fn system_message_queue_rate_limits() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut queue = SystemMessageQueue::new();
+
+    assert!(queue.push_chat("one"));
+    assert!(queue.push_chat("two"));
+    assert!(!queue.push_chat("one"));
+    assert!(queue.push_chat("three"));
+    queue.push_action_bar("first");
+    queue.push_action_bar("second");
+
+    app.world_mut().entity_mut(client).insert(queue);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<GameMessageS2c>(3);
+    }
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<GameMessageS2c>(1);
+    }
+
+    assert!(app
+        .world()
+        .get::<SystemMessageQueue>(client)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn system_message_queue_overflow() {
+    let mut queue = SystemMessageQueue {
+        capacity: 2,
+        ..Default::default()
+    };
+
+    queue.push_chat("one");
+    queue.push_chat("two");
+    assert!(queue.push_chat("three"));
+    assert_eq!(queue.chat_len(), 2);
+
+    queue.overflow = MessageOverflow::DropNewest;
+
+    assert!(!queue.push_chat("four"));
+    assert_eq!(queue.chat_len(), 2);
+}
+
+#[test]
+fn delete_chat_message_sends_full_signature() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let signature: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+    app.world_mut()
+        .get_mut::<Client>(client)
+        .unwrap()
+        .delete_chat_message(&signature);
+
+    app.update();
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<RemoveMessageS2c>(1);
+
+    let pkt = recvd.first::<RemoveMessageS2c>();
+    assert_eq!(pkt.signature.message_id, -1);
+    assert_eq!(pkt.signature.signature, Some(&signature));
+
+    // The message ID is sent as `VarInt(0)`, followed by the signature.
+    let mut bytes = vec![];
+    pkt.encode(&mut bytes).unwrap();
+    assert_eq!(bytes[0], 0);
+    assert_eq!(bytes[1..], signature);
+}
@@ -0,0 +1,56 @@
+use valence_server::entity::cow::CowEntityBundle;
+use valence_server::entity::move_to::{Easing, MoveTo};
+use valence_server::entity::{EntityLayerId, Position};
+use valence_server::protocol::packets::play::{EntityPositionS2c, MoveRelativeS2c};
+
+use crate::math::DVec3;
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn move_to_sends_relative_moves() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let cow = app
+        .world_mut()
+        .spawn(CowEntityBundle {
+            position: Position::new([8.0, 0.0, 8.0]),
+            layer: EntityLayerId(layer),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+    helper.clear_received();
+
+    // Further than a relative move can go in a single tick.
+    app.world_mut()
+        .entity_mut(cow)
+        .insert(MoveTo::new([8.0, 0.0, 28.0], 4).with_easing(Easing::QuadInOut));
+
+    app.update();
+
+    // A quarter of the time is an eighth of the distance with this easing.
+    assert_eq!(
+        app.world().get::<Position>(cow).unwrap().0,
+        DVec3::new(8.0, 0.0, 10.5)
+    );
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert_eq!(
+        app.world().get::<Position>(cow).unwrap().0,
+        DVec3::new(8.0, 0.0, 28.0)
+    );
+    assert!(app.world().get::<MoveTo>(cow).is_none());
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<MoveRelativeS2c>(4);
+    recvd.assert_count::<EntityPositionS2c>(0);
+}
@@ -0,0 +1,69 @@
+use bevy_ecs::prelude::*;
+use valence_server::moving_platform::{MovingPlatform, MovingPlatformBundle, MovingPlatformPlugin};
+
+use crate::entity::{EntityKind, Position};
+use crate::math::DVec3;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, Despawned};
+
+#[test]
+fn platform_carries_players() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(MovingPlatformPlugin);
+
+    // A 3x1x3 elevator with the client standing in the middle of it.
+    let platform = app
+        .world_mut()
+        .spawn(MovingPlatformBundle::new(
+            layer,
+            [-1.0, -1.0, -1.0],
+            BlockState::STONE,
+            MovingPlatform::new(
+                [3, 1, 3],
+                vec![DVec3::new(-1.0, -1.0, -1.0), DVec3::new(-1.0, 4.0, -1.0)],
+            )
+            .with_speed(0.5),
+        ))
+        .id();
+
+    app.world_mut().get_mut::<Position>(client).unwrap().0 = DVec3::ZERO;
+
+    app.update();
+
+    let shulkers = app
+        .world_mut()
+        .query::<&EntityKind>()
+        .iter(app.world())
+        .filter(|kind| **kind == EntityKind::SHULKER)
+        .count();
+
+    assert_eq!(shulkers, 9);
+
+    assert_eq!(
+        app.world().get::<Position>(platform).unwrap().0,
+        DVec3::new(-1.0, -0.5, -1.0)
+    );
+    assert_eq!(
+        app.world().get::<Position>(client).unwrap().0,
+        DVec3::new(0.0, 0.5, 0.0)
+    );
+
+    // Despawning the platform despawns its shulkers.
+    app.world_mut().entity_mut(platform).insert(Despawned);
+    app.update();
+
+    let shulkers = app
+        .world_mut()
+        .query_filtered::<&EntityKind, Without<Despawned>>()
+        .iter(app.world())
+        .filter(|kind| **kind == EntityKind::SHULKER)
+        .count();
+
+    assert_eq!(shulkers, 0);
+}
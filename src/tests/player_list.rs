@@ -1,7 +1,12 @@
+use bevy_ecs::entity::Entity;
+
 use crate::layer::chunk::UnloadedChunk;
-use crate::protocol::packets::play::{PlayerListS2c, PlayerSpawnS2c};
+use crate::player_list::{PlayerListEntryBundle, PlayerListScope, TabHeaderFooter};
+use crate::protocol::packets::play::{
+    PlayerListHeaderS2c, PlayerListS2c, PlayerRemoveS2c, PlayerSpawnS2c,
+};
 use crate::testing::{create_mock_client, ScenarioSingleClient};
-use crate::ChunkLayer;
+use crate::{ChunkLayer, Text};
 
 #[test]
 fn player_list_arrives_before_player_spawn() {
@@ -64,3 +69,156 @@ fn player_list_arrives_before_player_spawn() {
         assert_eq!(pkt.entries.len(), 2)
     };
 }
+
+#[test]
+fn tab_header_footer_is_diffed_and_rate_limited() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(TabHeaderFooter::new("Welcome", "Frame 0").with_min_update_interval(3));
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListHeaderS2c>(1);
+
+        let pkt = recvd.first::<PlayerListHeaderS2c>();
+        assert_eq!(pkt.footer.as_ref(), &Text::from("Frame 0"));
+    }
+
+    // Rewriting the same text doesn't send anything.
+    app.world_mut()
+        .get_mut::<TabHeaderFooter>(client)
+        .unwrap()
+        .footer = "Frame 0".into();
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerListHeaderS2c>(0);
+
+    // Changes within the interval are held back and sent once it has passed.
+    app.world_mut()
+        .get_mut::<TabHeaderFooter>(client)
+        .unwrap()
+        .footer = "Frame 1".into();
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerListHeaderS2c>(0);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListHeaderS2c>(1);
+
+        let pkt = recvd.first::<PlayerListHeaderS2c>();
+        assert_eq!(pkt.footer.as_ref(), &Text::from("Frame 1"));
+    }
+}
+
+#[test]
+fn tab_header_footer_on_layer() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(layer)
+        .insert(TabHeaderFooter::new("Lobby", ""));
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListHeaderS2c>(1);
+
+        let pkt = recvd.first::<PlayerListHeaderS2c>();
+        assert_eq!(pkt.header.as_ref(), &Text::from("Lobby"));
+    }
+
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<PlayerListHeaderS2c>(0);
+}
+
+#[test]
+fn scoped_player_list_entries() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    // An entry scoped to another layer isn't shown.
+    let entry = app
+        .world_mut()
+        .spawn((
+            PlayerListEntryBundle::default(),
+            PlayerListScope::layer(Entity::PLACEHOLDER),
+        ))
+        .id();
+
+    app.update();
+
+    helper.collect_received().assert_count::<PlayerListS2c>(0);
+
+    // Scoping it to the layer of the client adds it.
+    app.world_mut()
+        .entity_mut(entry)
+        .insert(PlayerListScope::layer(layer));
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListS2c>(1);
+
+        let pkt = recvd.first::<PlayerListS2c>();
+        assert!(pkt.actions.add_player());
+        assert_eq!(pkt.entries.len(), 1);
+    }
+
+    app.update();
+
+    helper.collect_received().assert_count::<PlayerListS2c>(0);
+
+    // Scoping it away removes it again.
+    app.world_mut()
+        .entity_mut(entry)
+        .insert(PlayerListScope::Clients(vec![]));
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerListS2c>(0);
+        recvd.assert_count::<PlayerRemoveS2c>(1);
+    }
+}
@@ -0,0 +1,158 @@
+use bevy_app::App;
+use bevy_ecs::entity::Entity;
+use valence_server::entity::entity::{self, Flags};
+use valence_server::entity::{Pose, Position};
+use valence_server::pose::ManualPose;
+use valence_server::protocol::packets::play::client_command_c2s::ClientCommand;
+use valence_server::protocol::packets::play::ClientCommandC2s;
+
+use crate::block::{PropName, PropValue};
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::VarInt;
+use crate::testing::ScenarioSingleClient;
+use crate::BlockState;
+
+fn command(action: ClientCommand) -> ClientCommandC2s {
+    ClientCommandC2s {
+        entity_id: VarInt(0),
+        action,
+        jump_boost: VarInt(0),
+    }
+}
+
+fn pose(app: &App, client: Entity) -> Pose {
+    app.world().get::<entity::Pose>(client).unwrap().0
+}
+
+fn set_block(app: &mut App, layer: Entity, pos: [i32; 3], state: BlockState) {
+    app.world_mut()
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block(pos, state);
+}
+
+fn scenario_on_floor() -> ScenarioSingleClient {
+    let mut scenario = ScenarioSingleClient::new();
+
+    let mut chunk_layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block([0, 0, 0], BlockState::STONE);
+
+    scenario
+        .app
+        .world_mut()
+        .get_mut::<Position>(scenario.client)
+        .unwrap()
+        .set([0.5, 1.0, 0.5]);
+
+    scenario.app.update();
+
+    scenario
+}
+
+#[test]
+fn sneaking_changes_pose() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = scenario_on_floor();
+
+    assert_eq!(pose(&app, client), Pose::Standing);
+
+    helper.send(&command(ClientCommand::StartSneaking));
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Sneaking);
+
+    helper.send(&command(ClientCommand::StopSneaking));
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Standing);
+}
+
+#[test]
+fn low_ceilings_make_players_crouch_and_crawl() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        layer,
+        ..
+    } = scenario_on_floor();
+
+    // There's just enough room to sneak under a top slab.
+    set_block(
+        &mut app,
+        layer,
+        [0, 2, 0],
+        BlockState::SMOOTH_STONE_SLAB.set(PropName::Type, PropValue::Top),
+    );
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Sneaking);
+
+    set_block(&mut app, layer, [0, 2, 0], BlockState::STONE);
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Swimming);
+
+    set_block(&mut app, layer, [0, 2, 0], BlockState::AIR);
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Standing);
+}
+
+#[test]
+fn sprinting_under_water_swims() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = scenario_on_floor();
+
+    set_block(&mut app, layer, [0, 1, 0], BlockState::WATER);
+    set_block(&mut app, layer, [0, 2, 0], BlockState::WATER);
+
+    helper.send(&command(ClientCommand::StartSprinting));
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Swimming);
+    assert!(app.world().get::<Flags>(client).unwrap().swimming());
+
+    helper.send(&command(ClientCommand::StopSprinting));
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Standing);
+    assert!(!app.world().get::<Flags>(client).unwrap().swimming());
+}
+
+#[test]
+fn manual_poses_are_kept() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = scenario_on_floor();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert((ManualPose, entity::Pose(Pose::Dying)));
+    helper.send(&command(ClientCommand::StartSneaking));
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Dying);
+
+    app.world_mut().entity_mut(client).remove::<ManualPose>();
+    app.update();
+
+    assert_eq!(pose(&app, client), Pose::Sneaking);
+}
@@ -1,9 +1,12 @@
 use valence_server::entity::active_status_effects::{ActiveStatusEffect, ActiveStatusEffects};
+use valence_server::nbt::Value;
+use valence_server::potion::{Potion, PotionContents, WATER_COLOR};
 use valence_server::protocol::packets::play::{EntityStatusEffectS2c, RemoveEntityStatusEffectS2c};
 use valence_server::protocol::status_effects::StatusEffect;
 use valence_server::protocol::VarInt;
 
 use crate::testing::ScenarioSingleClient;
+use crate::{ItemKind, ItemStack};
 
 #[test]
 fn test_status_effects_packets() {
@@ -69,3 +72,60 @@ fn test_status_effects_packets() {
     assert_eq!(packet.entity_id, VarInt(0)); // Client entity ID is always 0
     assert_eq!(packet.effect_id, VarInt(31)); // Bad Omen
 }
+
+#[test]
+fn potion_contents_nbt_round_trips() {
+    let contents = PotionContents::new(Potion::LongSwiftness)
+        .with_color(0x123456)
+        .with_effect(
+            ActiveStatusEffect::from_effect(StatusEffect::Glowing)
+                .with_amplifier(2)
+                .with_duration(200)
+                .with_show_icon(false),
+        )
+        .with_effect(ActiveStatusEffect::from_effect(StatusEffect::Luck).with_infinite());
+
+    let arrows = contents.tipped_arrows(16);
+
+    assert_eq!(arrows.item, ItemKind::TippedArrow);
+    assert_eq!(arrows.count, 16);
+    assert_eq!(
+        arrows.nbt.as_ref().unwrap().get("Potion"),
+        Some(&Value::String("minecraft:long_swiftness".into()))
+    );
+    assert_eq!(PotionContents::from_stack(&arrows), Some(contents.clone()));
+
+    let effects = contents.effects();
+
+    assert_eq!(effects.len(), 3);
+    assert_eq!(effects[0].status_effect(), StatusEffect::Speed);
+    assert_eq!(effects[0].initial_duration(), Some(9600));
+    assert_eq!(effects[2].initial_duration(), None);
+}
+
+#[test]
+fn potion_contents_from_stack() {
+    assert_eq!(
+        PotionContents::from_stack(&ItemStack::new(ItemKind::Arrow, 1, None)),
+        None
+    );
+
+    let uncraftable = PotionContents::from_stack(&ItemStack::new(ItemKind::Potion, 1, None));
+
+    assert_eq!(uncraftable, Some(PotionContents::new(Potion::Empty)));
+}
+
+#[test]
+fn potion_colors() {
+    assert_eq!(PotionContents::new(Potion::Water).color(), WATER_COLOR);
+    assert_eq!(
+        PotionContents::new(Potion::Swiftness).color(),
+        StatusEffect::Speed.color()
+    );
+    assert_eq!(
+        PotionContents::new(Potion::Swiftness)
+            .with_color(0xabcdef)
+            .color(),
+        0xabcdef
+    );
+}
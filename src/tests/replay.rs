@@ -0,0 +1,95 @@
+use bevy_ecs::prelude::*;
+
+use crate::entity::cow::CowEntityBundle;
+use crate::entity::{EntityLayerId, Position};
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::{BlockUpdateS2c, ChunkDataS2c, EntitySpawnS2c};
+use crate::replay::{Replay, ReplayEndEvent, ReplayPlayer, ReplayPlugin, ReplayRecorder};
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, ChunkLayer, ChunkPos, ChunkView};
+
+#[test]
+fn replays_are_recorded_and_played_back() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(ReplayPlugin);
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in -1..=1 {
+        for x in -1..=1 {
+            chunk_layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    app.world_mut().spawn(CowEntityBundle {
+        position: Position::new([8.0, 0.0, 8.0]),
+        layer: EntityLayerId(layer),
+        ..Default::default()
+    });
+
+    app.update();
+
+    let recorder = app
+        .world_mut()
+        .spawn(ReplayRecorder::new(
+            layer,
+            ChunkView::new(ChunkPos::new(0, 0), 2),
+        ))
+        .id();
+
+    // The first tick is a snapshot of the region.
+    app.update();
+
+    app.world_mut()
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .set_block([1, 0, 1], BlockState::STONE);
+
+    app.update();
+
+    let replay = app
+        .world_mut()
+        .get_mut::<ReplayRecorder>(recorder)
+        .unwrap()
+        .take_replay();
+
+    assert_eq!(replay.len(), 2);
+
+    let mut file = vec![];
+    replay.write_to(&mut file).unwrap();
+    assert_eq!(Replay::read_from(file.as_slice()).unwrap(), replay);
+
+    app.world_mut().entity_mut(recorder).despawn();
+    helper.clear_received();
+
+    let player = app.world_mut().spawn(ReplayPlayer::new(layer, replay)).id();
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<ChunkDataS2c>(9);
+        recvd.assert_count::<EntitySpawnS2c>(1);
+        recvd.assert_count::<BlockUpdateS2c>(0);
+    }
+
+    app.update();
+
+    helper.collect_received().assert_count::<BlockUpdateS2c>(1);
+
+    let ended: Vec<_> = app
+        .world()
+        .resource::<Events<ReplayEndEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(ended, [ReplayEndEvent { player }]);
+    assert!(app.world().get::<ReplayPlayer>(player).is_none());
+}
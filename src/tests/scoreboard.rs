@@ -3,12 +3,13 @@ use valence_scoreboard::*;
 use crate::client::VisibleEntityLayers;
 use crate::entity::EntityLayerId;
 use crate::layer::EntityLayer;
+use crate::protocol::packets::play::team_s2c::{Mode, TeamColor};
 use crate::protocol::packets::play::{
-    ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c,
+    ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c, TeamS2c,
 };
 use crate::testing::ScenarioSingleClient;
 use crate::text::IntoText;
-use crate::Server;
+use crate::{Despawned, Server};
 
 #[test]
 fn show_scoreboard_when_added_to_layer() {
@@ -191,3 +192,80 @@ fn should_only_update_score_diff() {
         recvd.assert_count::<ScoreboardPlayerUpdateS2c>(1);
     }
 }
+
+#[test]
+fn team_members_are_synced() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    let mut members = TeamMembers::new();
+    members.insert("foo");
+
+    let team = app
+        .world_mut()
+        .spawn(TeamBundle {
+            name: Team::new("red"),
+            info: TeamInfo {
+                color: TeamColor::Red,
+                ..Default::default()
+            },
+            members,
+            layer: EntityLayerId(layer),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+
+        recvd.assert_count::<TeamS2c>(1);
+
+        let pkt = recvd.first::<TeamS2c>();
+        assert_eq!(pkt.team_name, "red");
+        match pkt.mode {
+            Mode::CreateTeam {
+                team_color,
+                entities,
+                ..
+            } => {
+                assert_eq!(team_color, TeamColor::Red);
+                assert_eq!(entities, ["foo"]);
+            }
+            other => panic!("expected team to be created, got {other:?}"),
+        }
+    }
+
+    let mut members = app.world_mut().get_mut::<TeamMembers>(team).unwrap();
+    members.remove("foo");
+    members.insert("bar");
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+
+        recvd.assert_count::<TeamS2c>(2);
+    }
+
+    app.world_mut().entity_mut(team).insert(Despawned);
+
+    app.update();
+
+    {
+        let recvd = helper.collect_received();
+
+        let pkt = recvd.first::<TeamS2c>();
+        assert_eq!(pkt.mode, Mode::RemoveTeam);
+    }
+
+    assert!(app.world().get_entity(team).is_none());
+}
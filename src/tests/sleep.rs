@@ -0,0 +1,167 @@
+use valence_server::death::DeathPlugin;
+use valence_server::sleep::{SleepPlugin, Sleeping};
+use valence_server::world_time::WorldTime;
+
+use crate::block::{PropName, PropValue};
+use crate::inventory::Inventory;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::protocol::packets::play::PlayerInteractBlockC2s;
+use crate::spawn::RespawnPosition;
+use crate::testing::{MockClientHelper, ScenarioSingleClient};
+use crate::{BlockPos, BlockState, Direction, Hand, ItemKind, ItemStack};
+
+fn interact_block(helper: &mut MockClientHelper, position: BlockPos) {
+    helper.send(&PlayerInteractBlockC2s {
+        hand: Hand::Main,
+        position,
+        face: Direction::Up,
+        cursor_pos: Default::default(),
+        head_inside_block: false,
+        sequence: 0.into(),
+    });
+}
+
+/// Places a bed with its foot at `(1, 0, 1)` and its head at `(1, 0, 2)`.
+fn place_bed(scenario: &mut ScenarioSingleClient) {
+    let mut layer = scenario
+        .app
+        .world_mut()
+        .get_mut::<ChunkLayer>(scenario.layer)
+        .unwrap();
+
+    layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    let bed = BlockState::RED_BED.set(PropName::Facing, PropValue::South);
+    layer.set_block([1, 0, 1], bed.set(PropName::Part, PropValue::Foot));
+    layer.set_block([1, 0, 2], bed.set(PropName::Part, PropValue::Head));
+}
+
+#[test]
+fn sleeping_skips_the_night() {
+    let mut scenario = ScenarioSingleClient::new();
+    scenario.app.add_plugins(SleepPlugin);
+    place_bed(&mut scenario);
+
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = scenario;
+
+    app.world_mut()
+        .entity_mut(layer)
+        .insert(WorldTime::new(18000));
+
+    app.update();
+
+    interact_block(&mut helper, BlockPos::new(1, 0, 1));
+
+    app.update();
+
+    let head = BlockPos::new(1, 0, 2);
+
+    assert_eq!(
+        app.world_mut().get::<Sleeping>(client).map(|s| s.bed),
+        Some(head)
+    );
+    assert_eq!(
+        app.world_mut().get::<RespawnPosition>(client).unwrap().pos,
+        head
+    );
+
+    let occupied = app
+        .world_mut()
+        .get::<ChunkLayer>(layer)
+        .unwrap()
+        .block(head)
+        .unwrap()
+        .state
+        .get(PropName::Occupied);
+    assert_eq!(occupied, Some(PropValue::True));
+
+    for _ in 0..Sleeping::SKIP_NIGHT_TICKS {
+        app.update();
+    }
+
+    assert!(app.world_mut().get::<Sleeping>(client).is_none());
+
+    let time = app.world_mut().get::<WorldTime>(layer).unwrap();
+    assert_eq!(time.day(), 1);
+    assert!(!time.is_night());
+}
+
+#[test]
+fn cannot_sleep_during_the_day() {
+    let mut scenario = ScenarioSingleClient::new();
+    scenario.app.add_plugins(SleepPlugin);
+    place_bed(&mut scenario);
+
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = scenario;
+
+    app.world_mut()
+        .entity_mut(layer)
+        .insert(WorldTime::new(1000));
+
+    app.update();
+
+    interact_block(&mut helper, BlockPos::new(1, 0, 2));
+
+    app.update();
+
+    assert!(app.world_mut().get::<Sleeping>(client).is_none());
+    // The respawn position is set anyway.
+    assert_eq!(
+        app.world_mut().get::<RespawnPosition>(client).unwrap().pos,
+        BlockPos::new(1, 0, 2)
+    );
+}
+
+#[test]
+fn charge_respawn_anchor() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(DeathPlugin);
+
+    let anchor = BlockPos::new(1, 0, 1);
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(anchor, BlockState::RESPAWN_ANCHOR);
+
+    app.world_mut()
+        .get_mut::<Inventory>(client)
+        .unwrap()
+        .set_slot(36, ItemStack::new(ItemKind::Glowstone, 2, None));
+
+    app.update();
+
+    interact_block(&mut helper, anchor);
+
+    app.update();
+
+    let charges = app
+        .world_mut()
+        .get::<ChunkLayer>(layer)
+        .unwrap()
+        .block(anchor)
+        .unwrap()
+        .state
+        .get(PropName::Charges);
+    assert_eq!(charges, Some(PropValue::_1));
+    assert_eq!(
+        app.world_mut().get::<Inventory>(client).unwrap().slot(36),
+        &ItemStack::new(ItemKind::Glowstone, 1, None)
+    );
+}
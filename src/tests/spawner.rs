@@ -0,0 +1,142 @@
+use bevy_ecs::prelude::*;
+use valence_server::spawner::{
+    SpawnerData, SpawnerEntities, SpawnerPlugin, SpawnerSpawnEvent, Spawners,
+};
+
+use crate::entity::pig::PigEntityBundle;
+use crate::entity::{EntityKind, EntityLayerId, Position};
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::math::DVec3;
+use crate::nbt::Value;
+use crate::protocol::packets::play::BlockEventS2c;
+use crate::testing::ScenarioSingleClient;
+use crate::BlockPos;
+
+const SPAWNER_POS: BlockPos = BlockPos::new(8, 0, 8);
+
+#[test]
+fn spawner_data_nbt_round_trip() {
+    let data = SpawnerData {
+        spawn_count: 2,
+        ..SpawnerData::new(EntityKind::ZOMBIE)
+    };
+
+    let nbt = data.to_nbt();
+
+    let Some(Value::Compound(spawn_data)) = nbt.get("SpawnData") else {
+        panic!("missing spawn data");
+    };
+    let Some(Value::Compound(entity)) = spawn_data.get("entity") else {
+        panic!("missing spawn data entity");
+    };
+
+    // Clients show the entity in the spawner based on its ID.
+    assert_eq!(
+        entity.get("id"),
+        Some(&Value::String("minecraft:zombie".into()))
+    );
+    assert_eq!(SpawnerData::from_nbt(&nbt), Some(data));
+}
+
+#[test]
+fn spawner_spawns_near_players() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(SpawnerPlugin);
+
+    app.world_mut().resource_mut::<SpawnerEntities>().insert(
+        EntityKind::PIG,
+        |commands, layer, position| {
+            commands
+                .spawn(PigEntityBundle {
+                    layer: EntityLayerId(layer),
+                    position: Position(position),
+                    ..Default::default()
+                })
+                .id()
+        },
+    );
+
+    app.world_mut()
+        .get_mut::<ChunkLayer>(layer)
+        .unwrap()
+        .insert_chunk([0, 0], UnloadedChunk::new());
+
+    // Start far away from the spawner.
+    app.world_mut().get_mut::<Position>(client).unwrap().0 = DVec3::new(100.0, 0.0, 100.0);
+
+    app.update();
+
+    // Spawn entities right next to the spawner until there is one nearby.
+    app.world_mut().get_mut::<Spawners>(layer).unwrap().place(
+        SPAWNER_POS,
+        SpawnerData {
+            delay: 0,
+            spawn_count: 50,
+            max_nearby_entities: 1,
+            spawn_range: 0,
+            ..SpawnerData::new(EntityKind::PIG)
+        },
+    );
+
+    app.update();
+
+    assert_eq!(
+        app.world()
+            .get::<ChunkLayer>(layer)
+            .unwrap()
+            .block(SPAWNER_POS)
+            .and_then(|block| block.nbt.and_then(SpawnerData::from_nbt))
+            .map(|data| data.entity),
+        Some(EntityKind::PIG)
+    );
+
+    // The spawner is inactive without players nearby.
+    assert_eq!(
+        app.world()
+            .resource::<Events<SpawnerSpawnEvent>>()
+            .iter_current_update_events()
+            .count(),
+        0
+    );
+
+    app.world_mut().get_mut::<Position>(client).unwrap().0 = DVec3::new(8.5, 1.0, 8.5);
+    helper.clear_received();
+
+    app.update();
+
+    let events: Vec<_> = app
+        .world()
+        .resource::<Events<SpawnerSpawnEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].spawner, SPAWNER_POS);
+    assert_eq!(
+        app.world().get::<EntityKind>(events[0].entity),
+        Some(&EntityKind::PIG)
+    );
+
+    // Clients reset the spin of the spawner.
+    let pkt = helper.collect_received().first::<BlockEventS2c>();
+    assert_eq!(pkt.position, SPAWNER_POS);
+
+    app.update();
+
+    // The delay was reset.
+    assert_eq!(
+        app.world()
+            .resource::<Events<SpawnerSpawnEvent>>()
+            .iter_current_update_events()
+            .count(),
+        0
+    );
+}
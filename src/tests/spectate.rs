@@ -0,0 +1,80 @@
+use bevy_ecs::prelude::*;
+
+use crate::entity::{EntityId, Position};
+use crate::math::DVec3;
+use crate::protocol::packets::play::SetCameraEntityS2c;
+use crate::spectate::{SpectateEndEvent, SpectateMode, SpectatePlugin, Spectating};
+use crate::testing::{FakeClient, ScenarioSingleClient};
+use crate::Despawned;
+
+#[test]
+fn spectators_follow_their_target() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins(SpectatePlugin);
+
+    let other = FakeClient::join(&mut app, "other", layer);
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(Spectating::new(other.entity, SpectateMode::Camera));
+
+    app.world_mut().get_mut::<Position>(other.entity).unwrap().0 = DVec3::new(5.0, 10.0, 5.0);
+
+    app.update();
+
+    assert_eq!(
+        app.world().get::<Position>(client).unwrap().0,
+        DVec3::new(5.0, 10.0, 5.0)
+    );
+
+    let other_id = app.world().get::<EntityId>(other.entity).unwrap().get();
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<SetCameraEntityS2c>(1);
+        assert_eq!(recvd.first::<SetCameraEntityS2c>().entity_id.0, other_id);
+    }
+
+    // The camera is only set once.
+    app.update();
+
+    helper
+        .collect_received()
+        .assert_count::<SetCameraEntityS2c>(0);
+
+    // Following stops when the target goes away.
+    app.world_mut().entity_mut(other.entity).insert(Despawned);
+
+    app.update();
+
+    let ends: Vec<_> = app
+        .world()
+        .resource::<Events<SpectateEndEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(
+        ends,
+        [SpectateEndEvent {
+            client,
+            target: other.entity,
+        }]
+    );
+    assert!(app.world().get::<Spectating>(client).is_none());
+
+    app.update();
+
+    let recvd = helper.collect_received();
+    recvd.assert_count::<SetCameraEntityS2c>(1);
+    assert_eq!(recvd.first::<SetCameraEntityS2c>().entity_id.0, 0);
+}
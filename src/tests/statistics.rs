@@ -0,0 +1,86 @@
+use valence_server::protocol::packets::play::player_action_c2s::PlayerAction;
+use valence_server::protocol::packets::play::{ClientStatusC2s, PlayerActionC2s, StatisticsS2c};
+
+use crate::block::BlockKind;
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::statistics::{CustomStat, PlayerStatistics, StatKey};
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockPos, BlockState, Direction};
+
+#[test]
+fn request_statistics() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.update();
+
+    app.world_mut()
+        .get_mut::<PlayerStatistics>(client)
+        .unwrap()
+        .set(CustomStat::Jump, 5);
+
+    helper.clear_received();
+    helper.send(&ClientStatusC2s::RequestStats);
+
+    app.update();
+
+    let frames = helper.collect_received();
+    frames.assert_count::<StatisticsS2c>(1);
+
+    let pkt = frames.first::<StatisticsS2c>();
+    let jump = pkt
+        .statistics
+        .iter()
+        .find(|stat| stat.category_id.0 == 8 && stat.statistic_id.0 == CustomStat::Jump.id())
+        .expect("jump statistic should be sent");
+    assert_eq!(jump.value.0, 5);
+}
+
+#[test]
+fn mining_increments_statistic() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let pos = BlockPos::new(1, 0, 1);
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+    chunk_layer.set_block(pos, BlockState::STONE);
+
+    app.update();
+
+    helper.send(&PlayerActionC2s {
+        action: PlayerAction::StopDestroyBlock,
+        position: pos,
+        direction: Direction::Up,
+        sequence: 0.into(),
+    });
+
+    app.update();
+
+    let stats = app.world_mut().get::<PlayerStatistics>(client).unwrap();
+    assert_eq!(stats.get(StatKey::Mined(BlockKind::Stone)), 1);
+}
+
+#[test]
+fn statistics_nbt_round_trip() {
+    let mut stats = PlayerStatistics::new();
+    stats.set(CustomStat::Deaths, 3);
+    stats.set(StatKey::Mined(BlockKind::Dirt), 12);
+
+    let nbt = stats.to_compound();
+    let loaded = PlayerStatistics::from_compound(&nbt);
+
+    assert_eq!(loaded.get(CustomStat::Deaths), 3);
+    assert_eq!(loaded.get(StatKey::Mined(BlockKind::Dirt)), 12);
+    assert_eq!(loaded.iter().count(), 2);
+}
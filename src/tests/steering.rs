@@ -0,0 +1,92 @@
+use bevy_ecs::event::Events;
+use valence_server::entity::passengers::Vehicle;
+use valence_server::entity::pig::PigEntityBundle;
+use valence_server::entity::{EntityLayerId, Position};
+use valence_server::protocol::packets::play::player_input_c2s::PlayerInputFlags;
+use valence_server::protocol::packets::play::{PlayerInputC2s, VehicleMoveC2s};
+use valence_server::steering::{SteerVehicleEvent, VehicleMoveEvent};
+
+use crate::math::DVec3;
+use crate::testing::ScenarioSingleClient;
+
+#[test]
+fn steering_is_sent_for_the_vehicle() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    let pig = app
+        .world_mut()
+        .spawn(PigEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position::new([3.0, 64.0, 3.0]),
+            ..Default::default()
+        })
+        .id();
+
+    app.update();
+
+    // Input is ignored while the client isn't riding anything.
+    helper.send(&PlayerInputC2s {
+        sideways: 0.0,
+        forward: 0.98,
+        flags: PlayerInputFlags::new(),
+    });
+    app.update();
+
+    assert_eq!(
+        app.world()
+            .resource::<Events<SteerVehicleEvent>>()
+            .iter_current_update_events()
+            .count(),
+        0
+    );
+
+    app.world_mut().entity_mut(client).insert(Vehicle::new(pig));
+
+    helper.send(&PlayerInputC2s {
+        sideways: -0.5,
+        forward: 0.98,
+        flags: PlayerInputFlags::new().with_jump(true),
+    });
+    helper.send(&VehicleMoveC2s {
+        position: DVec3::new(3.0, 64.0, 4.0),
+        yaw: 90.0,
+        pitch: 0.0,
+    });
+    app.update();
+
+    let steer: Vec<_> = app
+        .world()
+        .resource::<Events<SteerVehicleEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(
+        steer,
+        [SteerVehicleEvent {
+            client,
+            vehicle: pig,
+            sideways: -0.5,
+            forward: 0.98,
+            jump: true,
+            unmount: false,
+        }]
+    );
+
+    let moves: Vec<_> = app
+        .world()
+        .resource::<Events<VehicleMoveEvent>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].vehicle, pig);
+    assert_eq!(moves[0].position, DVec3::new(3.0, 64.0, 4.0));
+    assert_eq!(moves[0].old_position, DVec3::new(3.0, 64.0, 3.0));
+}
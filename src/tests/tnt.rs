@@ -0,0 +1,52 @@
+use bevy_ecs::prelude::*;
+use valence_server::explosion::ExplosionPlugin;
+use valence_server::tnt::{PrimedTnt, PrimedTntBundle, TntPlugin};
+
+use crate::layer::chunk::UnloadedChunk;
+use crate::layer::ChunkLayer;
+use crate::testing::ScenarioSingleClient;
+use crate::{BlockState, Despawned};
+
+#[test]
+fn primed_tnt_explodes() {
+    let ScenarioSingleClient {
+        mut app,
+        client: _,
+        helper: _,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.add_plugins((ExplosionPlugin, TntPlugin));
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    chunk_layer.insert_chunk([0, 0], UnloadedChunk::new());
+
+    for x in 0..16 {
+        for z in 0..16 {
+            chunk_layer.set_block([x, 0, z], BlockState::STONE);
+        }
+    }
+
+    chunk_layer.set_block([8, 1, 10], BlockState::TNT);
+
+    app.world_mut()
+        .spawn(PrimedTntBundle::from_block(layer, [8, 1, 8]).with_fuse(1));
+
+    app.update();
+
+    let chunk_layer = app.world().get::<ChunkLayer>(layer).unwrap();
+
+    assert!(chunk_layer.block([8, 1, 10]).unwrap().state.is_air());
+
+    app.update();
+
+    // The destroyed TNT block was primed.
+    let primed = app
+        .world_mut()
+        .query_filtered::<(), (With<PrimedTnt>, Without<Despawned>)>()
+        .iter(app.world())
+        .count();
+
+    assert_eq!(primed, 1);
+}
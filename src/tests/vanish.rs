@@ -0,0 +1,90 @@
+use bevy_ecs::prelude::*;
+
+use crate::entity::EntityLayerId;
+use crate::layer::chunk::UnloadedChunk;
+use crate::protocol::packets::play::{EntitiesDestroyS2c, PlayerListS2c, PlayerSpawnS2c};
+use crate::testing::{FakeClient, ScenarioSingleClient};
+use crate::vanish::{AnnounceJoinEvent, AnnounceLeaveEvent, VanishLayer, Vanished};
+use crate::ChunkLayer;
+
+#[test]
+fn vanished_clients_are_hidden() {
+    let ScenarioSingleClient {
+        mut app,
+        mut helper,
+        layer,
+        ..
+    } = ScenarioSingleClient::new();
+
+    let mut chunk_layer = app.world_mut().get_mut::<ChunkLayer>(layer).unwrap();
+
+    for z in -5..5 {
+        for x in -5..5 {
+            chunk_layer.insert_chunk([x, z], UnloadedChunk::new());
+        }
+    }
+
+    app.update();
+
+    let other = FakeClient::join(&mut app, "other", layer);
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(other.entity)
+        .insert(Vanished::default());
+
+    app.update();
+
+    let vanish_layer = app.world().resource::<VanishLayer>().0;
+    assert_eq!(
+        app.world().get::<EntityLayerId>(other.entity).unwrap().0,
+        vanish_layer
+    );
+
+    let left = app
+        .world()
+        .resource::<Events<AnnounceLeaveEvent>>()
+        .iter_current_update_events()
+        .count();
+    assert_eq!(left, 1);
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<EntitiesDestroyS2c>(1);
+        recvd.assert_count::<PlayerListS2c>(1);
+
+        let pkt = recvd.first::<PlayerListS2c>();
+        assert!(pkt.actions.update_listed());
+        assert!(!pkt.entries[0].listed);
+    }
+
+    // Reappearing restores the layer and the listing.
+    app.world_mut()
+        .entity_mut(other.entity)
+        .remove::<Vanished>();
+
+    app.update();
+
+    assert_eq!(
+        app.world().get::<EntityLayerId>(other.entity).unwrap().0,
+        layer
+    );
+
+    let joined = app
+        .world()
+        .resource::<Events<AnnounceJoinEvent>>()
+        .iter_current_update_events()
+        .count();
+    assert_eq!(joined, 1);
+
+    {
+        let recvd = helper.collect_received();
+        recvd.assert_count::<PlayerSpawnS2c>(1);
+        recvd.assert_count::<PlayerListS2c>(1);
+
+        let pkt = recvd.first::<PlayerListS2c>();
+        assert!(pkt.entries[0].listed);
+    }
+}
@@ -0,0 +1,263 @@
+use bevy_app::App;
+use valence_server::client::{ViewDistance, VisibleChunkLayer};
+use valence_server::entity::Position;
+use valence_server::keepalive::Ping;
+use valence_server::math::DVec3;
+use valence_server::protocol::packets::play::client_settings_c2s::{
+    ChatMode, DisplayedSkinParts, MainArm,
+};
+use valence_server::protocol::packets::play::{ClientSettingsC2s, SimulationDistanceS2c};
+use valence_server::spawn::{JoinConfiguration, RespawnPosition};
+use valence_server::view_distance::{
+    AdaptiveViewDistance, MaxViewDistance, SimulatedChunks, SimulationDistance,
+};
+
+use crate::prelude::{BiomeRegistry, DimensionTypeRegistry};
+use crate::testing::ScenarioSingleClient;
+use crate::{ident, BlockPos, ChunkLayer, ChunkPos, Server};
+
+fn settings(view_distance: u8) -> ClientSettingsC2s<'static> {
+    ClientSettingsC2s {
+        locale: "en_us",
+        view_distance,
+        chat_mode: ChatMode::Enabled,
+        chat_colors: true,
+        displayed_skin_parts: DisplayedSkinParts::new(),
+        main_arm: MainArm::Right,
+        enable_text_filtering: false,
+        allow_server_listings: true,
+    }
+}
+
+#[test]
+fn max_view_distance_caps_requested_distance() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.world_mut().entity_mut(layer).insert(MaxViewDistance(8));
+
+    helper.send(&settings(16));
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 8);
+
+    // Smaller requests are kept.
+    helper.send(&settings(4));
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 4);
+
+    // Raising the cap gives the client the distance it asked for.
+    helper.send(&settings(16));
+    app.update();
+
+    app.world_mut().get_mut::<MaxViewDistance>(layer).unwrap().0 = 12;
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 12);
+}
+
+#[test]
+fn manual_view_distance_survives_layer_change() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    helper.send(&settings(16));
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 16);
+
+    *app.world_mut().get_mut::<ViewDistance>(client).unwrap() = ViewDistance::new(6);
+    app.update();
+
+    let other_layer = ChunkLayer::new(
+        ident!("overworld"),
+        app.world().resource::<DimensionTypeRegistry>(),
+        app.world().resource::<BiomeRegistry>(),
+        app.world().resource::<Server>(),
+    );
+    let other_layer = app.world_mut().spawn(other_layer).id();
+
+    app.world_mut()
+        .get_mut::<VisibleChunkLayer>(client)
+        .unwrap()
+        .0 = other_layer;
+    app.update();
+
+    // Neither layer limits the view distance, so it's left alone.
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 6);
+
+    // Moving to a limited layer and back restores the requested distance.
+    app.world_mut().entity_mut(layer).insert(MaxViewDistance(8));
+    app.world_mut()
+        .get_mut::<VisibleChunkLayer>(client)
+        .unwrap()
+        .0 = layer;
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 8);
+
+    app.world_mut()
+        .get_mut::<VisibleChunkLayer>(client)
+        .unwrap()
+        .0 = other_layer;
+    app.update();
+
+    assert_eq!(app.world().get::<ViewDistance>(client).unwrap().get(), 16);
+}
+
+#[test]
+fn adaptive_view_distance_follows_ping() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.world_mut()
+        .entity_mut(client)
+        .insert(AdaptiveViewDistance {
+            max_ping: 200,
+            min_view_distance: 6,
+            interval: 1,
+            ..Default::default()
+        });
+
+    helper.send(&settings(8));
+    app.update();
+
+    let view_dist = |app: &App| app.world().get::<ViewDistance>(client).unwrap().get();
+
+    assert_eq!(view_dist(&app), 8);
+
+    app.world_mut().get_mut::<Ping>(client).unwrap().0 = 500;
+
+    app.update();
+    assert_eq!(view_dist(&app), 7);
+
+    // The view distance isn't lowered below the minimum.
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(view_dist(&app), 6);
+
+    // A healthy connection gets its view distance back.
+    app.world_mut().get_mut::<Ping>(client).unwrap().0 = 20;
+
+    app.update();
+    assert_eq!(view_dist(&app), 7);
+
+    app.update();
+    assert_eq!(view_dist(&app), 8);
+
+    app.update();
+    assert_eq!(view_dist(&app), 8);
+}
+
+#[test]
+fn simulated_chunks_surround_clients() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        layer,
+    } = ScenarioSingleClient::new();
+
+    app.update();
+    helper.clear_received();
+
+    app.world_mut()
+        .entity_mut(layer)
+        .insert(SimulationDistance(1));
+
+    app.world_mut()
+        .get_mut::<ViewDistance>(client)
+        .unwrap()
+        .set(8);
+
+    app.update();
+
+    let simulated = app.world().get::<SimulatedChunks>(layer).unwrap();
+
+    assert!(simulated.contains(ChunkPos::new(0, 0)));
+    assert!(simulated.contains(ChunkPos::new(1, 0)));
+    assert!(!simulated.contains(ChunkPos::new(5, 0)));
+
+    let sent = helper.collect_received();
+    sent.assert_count::<SimulationDistanceS2c>(1);
+}
+
+#[test]
+fn view_grows_after_joining() {
+    let ScenarioSingleClient {
+        mut app,
+        client,
+        mut helper,
+        ..
+    } = ScenarioSingleClient::new();
+
+    app.insert_resource(JoinConfiguration {
+        initial_chunk_radius: Some(2),
+        chunk_radius_step: 2,
+        ..Default::default()
+    });
+
+    app.world_mut()
+        .get_mut::<ViewDistance>(client)
+        .unwrap()
+        .set(6);
+
+    app.update();
+
+    let view_dist = |app: &App| app.world().get::<ViewDistance>(client).unwrap().get();
+
+    // Only the initial radius is sent on join.
+    assert_eq!(view_dist(&app), 2);
+
+    app.update();
+    assert_eq!(view_dist(&app), 4);
+
+    // Settings arriving in the meantime change where the view ends up.
+    helper.send(&settings(7));
+    app.update();
+    assert_eq!(view_dist(&app), 6);
+
+    app.update();
+    assert_eq!(view_dist(&app), 7);
+
+    app.update();
+    assert_eq!(view_dist(&app), 7);
+}
+
+#[test]
+fn clients_join_at_respawn_position() {
+    let ScenarioSingleClient {
+        mut app, client, ..
+    } = ScenarioSingleClient::new();
+
+    app.insert_resource(JoinConfiguration {
+        spawn_at_respawn_position: true,
+        ..Default::default()
+    });
+
+    app.world_mut()
+        .get_mut::<RespawnPosition>(client)
+        .unwrap()
+        .pos = BlockPos::new(3, 64, -5);
+
+    app.update();
+
+    assert_eq!(
+        app.world().get::<Position>(client).unwrap().0,
+        DVec3::new(3.5, 64.0, -4.5)
+    );
+}
@@ -1,3 +1,6 @@
+use std::path::Path;
+use std::{fs, io};
+
 use eframe::epaint::PathShape;
 use egui::{
     Color32, Pos2, Rect, Response, Rgba, Sense, Shape, Stroke, TextStyle, TextWrapMode, Ui, Vec2,
@@ -28,6 +31,7 @@ impl View for PacketList {
             ui.heading("Packets");
             draw_packet_counter(state, ui);
             draw_clear_button(state, ui);
+            draw_export_corpus_button(state, ui);
         });
 
         draw_packet_list(state, ui);
@@ -91,6 +95,61 @@ fn draw_clear_button(state: &mut SharedState, ui: &mut Ui) {
     }
 }
 
+/// Where fuzzing seeds are exported to, relative to the working directory.
+const CORPUS_DIR: &str = "fuzz/corpus/packet_decoder";
+
+fn draw_export_corpus_button(state: &SharedState, ui: &mut Ui) {
+    if ui
+        .button("Export Corpus")
+        .on_hover_text(format!(
+            "Save serverbound packets as fuzzing seeds in {CORPUS_DIR}"
+        ))
+        .clicked()
+    {
+        let packets = state.packets.read().unwrap();
+
+        match export_corpus(&packets, Path::new(CORPUS_DIR)) {
+            Ok(count) => tracing::info!("exported {count} seeds to {CORPUS_DIR}"),
+            Err(e) => tracing::error!("failed to export corpus: {e}"),
+        }
+    }
+}
+
+/// Writes every serverbound packet to its own seed file, plus one seed with the
+/// whole session. Seeds start with a zero byte to disable compression in the
+/// `packet_decoder` fuzz target.
+fn export_corpus(packets: &[Packet], dir: &Path) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let session_id = time::OffsetDateTime::now_utc().unix_timestamp();
+    let mut session = vec![0];
+    let mut count = 0;
+
+    for (i, packet) in packets
+        .iter()
+        .filter(|p| p.side == PacketSide::Serverbound)
+        .enumerate()
+    {
+        let Some(frame) = packet.encode_frame() else {
+            continue;
+        };
+
+        let mut seed = vec![0];
+        seed.extend_from_slice(&frame);
+        fs::write(dir.join(format!("{session_id}_{i}_{}", packet.name)), seed)?;
+
+        session.extend_from_slice(&frame);
+        count += 1;
+    }
+
+    if count > 0 {
+        fs::write(dir.join(format!("{session_id}_session")), session)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
 fn draw_packet_list(state: &mut SharedState, ui: &mut Ui) {
     let packets = state.packets.read().unwrap();
     egui::ScrollArea::vertical()
@@ -1,9 +1,10 @@
 use std::hash::{Hash, Hasher};
 use std::sync::RwLock;
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use time::OffsetDateTime;
 use valence_protocol::decode::PacketFrame;
+use valence_protocol::encode::PacketEncoder;
 use valence_protocol::{CompressionThreshold, PacketSide, PacketState};
 
 pub struct PacketRegistry {
@@ -102,6 +103,23 @@ pub struct Packet {
     pub data: Option<Bytes>,
 }
 
+impl Packet {
+    /// Encodes this packet as an uncompressed frame, the way it was sent over
+    /// the connection. Returns `None` if the packet has no data.
+    pub fn encode_frame(&self) -> Option<BytesMut> {
+        let data = self.data.as_ref()?;
+
+        let mut enc = PacketEncoder::new();
+        enc.append_frame(&PacketFrame {
+            id: self.id,
+            body: BytesMut::from(&data[..]),
+        })
+        .ok()?;
+
+        Some(enc.take())
+    }
+}
+
 impl PartialEq for Packet {
     fn eq(&self, other: &Self) -> bool {
         self.side == other.side
@@ -22,6 +22,23 @@ pub(crate) struct StresserArgs {
     #[arg(long = "cooldown")]
     pub(crate) spawn_cooldown: u64,
 
+    /// Interval between random movements of sessions in milliseconds, or 0 to
+    /// stand still.
+    #[arg(default_value = "250")]
+    #[arg(long = "move-interval")]
+    pub(crate) move_interval: u64,
+
+    /// Interval between chat messages of sessions in milliseconds, or 0 to
+    /// stay silent.
+    #[arg(default_value = "0")]
+    #[arg(long = "chat-interval")]
+    pub(crate) chat_interval: u64,
+
+    /// Interval between printed statistics in seconds.
+    #[arg(default_value = "5")]
+    #[arg(long = "stats-interval")]
+    pub(crate) stats_interval: u64,
+
     /// Read buffer size in bytes.
     #[arg(default_value = "4096")]
     #[arg(long = "read-buffer")]
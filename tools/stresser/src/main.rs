@@ -22,18 +22,46 @@ use std::sync::Arc;
 
 use args::StresserArgs;
 use clap::Parser;
+use stats::Stats;
 use stresser::{make_session, SessionParams};
 use tokio::sync::Semaphore;
 
 mod args;
+pub mod stats;
 pub mod stresser;
 
+/// Converts an interval in milliseconds to a duration, or `None` if it's 0.
+fn interval_from_millis(millis: u64) -> Option<Duration> {
+    (millis != 0).then_some(Duration::from_millis(millis))
+}
+
 #[tokio::main]
 async fn main() {
     let args = StresserArgs::parse();
 
     let target_addr = args.target_host.to_socket_addrs().unwrap().next().unwrap();
 
+    let move_interval = interval_from_millis(args.move_interval);
+    let chat_interval = interval_from_millis(args.chat_interval);
+
+    let stats = Arc::new(Stats::default());
+
+    {
+        let stats = stats.clone();
+        let stats_interval = Duration::from_secs(args.stats_interval.max(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(stats_interval);
+            // The first tick completes immediately.
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                println!("{stats}");
+            }
+        });
+    }
+
     let mut session_index: usize = 0;
 
     let sema = Arc::new(Semaphore::new(args.sessions_count));
@@ -41,11 +69,16 @@ async fn main() {
     while let Ok(perm) = sema.clone().acquire_owned().await {
         let session_name = format!("{}{}", args.name_prefix, session_index);
 
+        let stats = stats.clone();
+
         tokio::spawn(async move {
             let params = SessionParams {
                 socket_addr: target_addr,
                 session_name: session_name.as_str(),
                 read_buffer_size: args.read_buffer_size,
+                move_interval,
+                chat_interval,
+                stats: &stats,
             };
 
             if let Err(err) = make_session(&params).await {
@@ -0,0 +1,65 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Metrics shared by all sessions.
+#[derive(Default)]
+pub struct Stats {
+    connect_attempts: AtomicU64,
+    connect_failures: AtomicU64,
+    logins: AtomicU64,
+    disconnects: AtomicU64,
+    login_latency_total_us: AtomicU64,
+    login_latency_max_us: AtomicU64,
+}
+
+impl Stats {
+    pub fn record_connect_attempt(&self) {
+        self.connect_attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_connect_failure(&self) {
+        self.connect_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a successful login, `latency` after starting to connect.
+    pub fn record_login(&self, latency: Duration) {
+        let latency_us = latency.as_micros() as u64;
+
+        self.logins.fetch_add(1, Ordering::Relaxed);
+        self.login_latency_total_us
+            .fetch_add(latency_us, Ordering::Relaxed);
+        self.login_latency_max_us
+            .fetch_max(latency_us, Ordering::Relaxed);
+    }
+
+    /// Records the end of a session which logged in.
+    pub fn record_disconnect(&self) {
+        self.disconnects.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let attempts = self.connect_attempts.load(Ordering::Relaxed);
+        let failures = self.connect_failures.load(Ordering::Relaxed);
+        let logins = self.logins.load(Ordering::Relaxed);
+        let disconnects = self.disconnects.load(Ordering::Relaxed);
+        let total_us = self.login_latency_total_us.load(Ordering::Relaxed);
+        let max_us = self.login_latency_max_us.load(Ordering::Relaxed);
+
+        let avg_ms = if logins == 0 {
+            0.0
+        } else {
+            total_us as f64 / logins as f64 / 1000.0
+        };
+
+        write!(
+            f,
+            "online: {}, connects: {attempts} ({failures} failed), logins: {logins}, disconnects: \
+             {disconnects}, login latency: {avg_ms:.1}ms avg, {:.1}ms max",
+            logins.saturating_sub(disconnects),
+            max_us as f64 / 1000.0
+        )
+    }
+}
@@ -1,9 +1,11 @@
 use std::io::{self, ErrorKind};
 use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::bail;
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
+use tokio::time::Interval;
 use uuid::Uuid;
 use valence_protocol::packets::handshaking::handshake_c2s::HandshakeNextState;
 use valence_protocol::packets::handshaking::HandshakeC2s;
@@ -11,23 +13,45 @@ use valence_protocol::packets::login::{
     LoginCompressionS2c, LoginHelloC2s, LoginHelloS2c, LoginSuccessS2c,
 };
 use valence_protocol::packets::play::{
-    KeepAliveC2s, KeepAliveS2c, PlayerPositionLookS2c, PositionAndOnGroundC2s, TeleportConfirmC2s,
+    ChatMessageC2s, KeepAliveC2s, KeepAliveS2c, PlayerPositionLookS2c, PositionAndOnGroundC2s,
+    TeleportConfirmC2s,
 };
 use valence_protocol::var_int::VarInt;
 use valence_protocol::{
-    CompressionThreshold, Packet, PacketDecoder, PacketEncoder, PROTOCOL_VERSION,
+    Bounded, CompressionThreshold, Packet, PacketDecoder, PacketEncoder, PROTOCOL_VERSION,
 };
 
+use crate::stats::Stats;
+
 pub struct SessionParams<'a> {
     pub socket_addr: SocketAddr,
     pub session_name: &'a str,
     pub read_buffer_size: usize,
+    /// The interval between random movements, or `None` to stand still.
+    pub move_interval: Option<Duration>,
+    /// The interval between chat messages, or `None` to stay silent.
+    pub chat_interval: Option<Duration>,
+    pub stats: &'a Stats,
+}
+
+/// Records a disconnect when a session which logged in ends.
+struct LoginGuard<'a>(&'a Stats);
+
+impl Drop for LoginGuard<'_> {
+    fn drop(&mut self) {
+        self.0.record_disconnect();
+    }
 }
 
 pub async fn make_session<'a>(params: &SessionParams<'a>) -> anyhow::Result<()> {
     let sock_addr = params.socket_addr;
     let sess_name = params.session_name;
     let rb_size = params.read_buffer_size;
+    let stats = params.stats;
+
+    let start = Instant::now();
+
+    stats.record_connect_attempt();
 
     let mut conn = match TcpStream::connect(sock_addr).await {
         Ok(conn) => {
@@ -36,6 +60,7 @@ pub async fn make_session<'a>(params: &SessionParams<'a>) -> anyhow::Result<()>
         }
         Err(err) => {
             eprintln!("{sess_name} connection failed");
+            stats.record_connect_failure();
             return Err(err.into());
         }
     };
@@ -105,6 +130,16 @@ pub async fn make_session<'a>(params: &SessionParams<'a>) -> anyhow::Result<()>
 
     println!("{sess_name} logged in");
 
+    stats.record_login(start.elapsed());
+    let _guard = LoginGuard(stats);
+
+    let mut move_timer = params.move_interval.map(tokio::time::interval);
+    let mut chat_timer = params.chat_interval.map(tokio::time::interval);
+
+    // Unknown until the server sends the first position.
+    let mut position = None;
+    let mut chat_count = 0;
+
     loop {
         while let Some(frame) = dec.try_next_packet()? {
             match frame.id {
@@ -130,24 +165,78 @@ pub async fn make_session<'a>(params: &SessionParams<'a>) -> anyhow::Result<()>
                     })?;
 
                     conn.write_all(&enc.take()).await?;
+
+                    position = Some(packet.position);
                 }
                 _ => (),
             }
         }
 
-        dec.reserve(rb_size);
+        tokio::select! {
+            res = conn.readable() => {
+                res?;
 
-        let mut read_buf = dec.take_capacity();
+                dec.reserve(rb_size);
 
-        conn.readable().await?;
+                let mut read_buf = dec.take_capacity();
 
-        match conn.try_read_buf(&mut read_buf) {
-            Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
-            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
-            Err(e) => return Err(e.into()),
-            Ok(_) => (),
-        };
+                match conn.try_read_buf(&mut read_buf) {
+                    Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(e.into()),
+                    Ok(_) => (),
+                };
 
-        dec.queue_bytes(read_buf);
+                dec.queue_bytes(read_buf);
+            }
+            _ = tick(&mut move_timer), if position.is_some() => {
+                let pos = position.as_mut().unwrap();
+
+                // Walk up to one block in a random direction.
+                pos.x += rand::random::<f64>() * 2.0 - 1.0;
+                pos.z += rand::random::<f64>() * 2.0 - 1.0;
+
+                enc.clear();
+
+                enc.append_packet(&PositionAndOnGroundC2s {
+                    position: *pos,
+                    on_ground: true,
+                })?;
+
+                conn.write_all(&enc.take()).await?;
+            }
+            _ = tick(&mut chat_timer) => {
+                let message = format!("{sess_name} says hello #{chat_count}");
+                chat_count += 1;
+
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+
+                enc.clear();
+
+                enc.append_packet(&ChatMessageC2s {
+                    message: Bounded(message.as_str()),
+                    timestamp,
+                    salt: rand::random(),
+                    signature: None,
+                    message_count: VarInt(0),
+                    acknowledgement: Default::default(),
+                })?;
+
+                conn.write_all(&enc.take()).await?;
+            }
+        }
+    }
+}
+
+/// Waits for the next tick of `interval`, or forever if there is none.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
     }
 }